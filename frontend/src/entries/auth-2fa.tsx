@@ -0,0 +1,18 @@
+import "../styles/shell.css";
+import { getNextFromLocation, getTwoFactorModeFromLocation } from "../lib/auth";
+import { loadComposedPage } from "../lib/loadBootstrap";
+import { AuthTwoFactorPage } from "../pages/AuthTwoFactorPage";
+
+const rootElement = document.getElementById("react-root");
+
+if (rootElement) {
+  void loadComposedPage(
+    rootElement,
+    () =>
+      Promise.resolve({
+        mode: getTwoFactorModeFromLocation(),
+        next: getNextFromLocation(),
+      }),
+    ({ mode, next }) => <AuthTwoFactorPage mode={mode} next={next} />,
+  );
+}
@@ -0,0 +1,21 @@
+import "../styles/shell.css";
+import { getTokenFromLocation } from "../lib/auth";
+import { loadComposedPage } from "../lib/loadBootstrap";
+import { AuthResetPage } from "../pages/AuthResetPage";
+
+const rootElement = document.getElementById("react-root");
+
+if (rootElement) {
+  void loadComposedPage(
+    rootElement,
+    () => Promise.resolve(getTokenFromLocation() ?? ""),
+    (token) => (
+      <AuthResetPage
+        endpoint="/auth/invite"
+        invalidTokenMessage="Приглашение недействительно."
+        title="Принять приглашение"
+        token={token}
+      />
+    ),
+  );
+}
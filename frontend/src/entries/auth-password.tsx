@@ -0,0 +1,20 @@
+import "../styles/shell.css";
+import { getNextFromLocation, withNext } from "../lib/auth";
+import { loadComposedPage } from "../lib/loadBootstrap";
+import { AuthResetPage } from "../pages/AuthResetPage";
+
+const rootElement = document.getElementById("react-root");
+
+if (rootElement) {
+  void loadComposedPage(
+    rootElement,
+    () => Promise.resolve(getNextFromLocation()),
+    (next) => (
+      <AuthResetPage
+        endpoint={withNext("/auth/password", next)}
+        title="Смените пароль"
+        token={null}
+      />
+    ),
+  );
+}
@@ -0,0 +1,14 @@
+import "../styles/shell.css";
+import { getTokenFromLocation } from "../lib/auth";
+import { loadComposedPage } from "../lib/loadBootstrap";
+import { AuthResetPage } from "../pages/AuthResetPage";
+
+const rootElement = document.getElementById("react-root");
+
+if (rootElement) {
+  void loadComposedPage(
+    rootElement,
+    () => Promise.resolve(getTokenFromLocation() ?? ""),
+    (token) => <AuthResetPage token={token} />,
+  );
+}
@@ -21,6 +21,8 @@ export {
   toFieldErrorMap,
 } from "@pushkind/frontend-shell/mutations";
 
+import { isApiMutationError as isSharedApiMutationError } from "@pushkind/frontend-shell/mutations";
+
 import { redirectTo } from "./redirect";
 import type { ShellData, UserMenuItem } from "./models";
 
@@ -39,6 +41,7 @@ export interface ApiShellPayload {
   navigation: ApiMenuItem[];
   local_menu_items: ApiMenuItem[];
   hub_name: string;
+  permissions: string[];
 }
 
 export interface ApiMenuItem {
@@ -50,6 +53,26 @@ export interface ApiAdminRole {
   id: number;
   name: string;
   can_delete: boolean;
+  permissions: string[];
+  system: boolean;
+  description: string;
+  user_count: number;
+  parents: number[];
+  inherited: number[];
+}
+
+export interface ApiRoleChangeSummary {
+  message: string;
+  changed: number;
+  unchanged: number;
+  not_found: number[];
+}
+
+export interface ApiAdminRoleMember {
+  id: number;
+  email: string;
+  name: string | null;
+  suspended: boolean;
 }
 
 export interface ApiAdminHub {
@@ -63,10 +86,132 @@ export interface ApiAdminMenuItem {
   name: string;
 }
 
+export interface ApiHubPolicy {
+  require_admin_2fa: boolean;
+  require_email_verification: boolean;
+}
+
+export type ApiRegistrationMode = "open" | "closed" | "invite_only";
+
+export interface ApiHubRegistrationSettings {
+  registration_mode: ApiRegistrationMode;
+  allowed_email_domains: string[];
+  default_roles: number[];
+}
+
+export interface ApiAdminOAuthClient {
+  id: number;
+  client_id: string;
+  name: string;
+  redirect_uris: string[];
+  confidential: boolean;
+  created_at: string;
+}
+
+export interface ApiOAuthClientCredentials {
+  message: string;
+  client_id: string;
+  client_secret: string | null;
+}
+
+export interface ApiAdminServiceAccount {
+  id: number;
+  client_id: string;
+  name: string;
+  roles: number[];
+  secret_rotated_at: string;
+  created_at: string;
+}
+
+export interface ApiAdminInvitation {
+  id: number;
+  email: string;
+  name: string;
+  roles: number[];
+  expires_at: string;
+  created_at: string;
+}
+
+export interface ApiAdminDeletedUser {
+  id: number;
+  email: string;
+  name: string | null;
+  deleted_at: string;
+  purge_at: string;
+}
+
+export interface ApiAdminDeletedHub {
+  id: number;
+  name: string;
+  deleted_at: string;
+  purge_at: string;
+}
+
+export interface ApiAdminDeleted {
+  users: ApiAdminDeletedUser[];
+  hubs: ApiAdminDeletedHub[];
+}
+
+export interface ApiServiceAccountCredentials {
+  message: string;
+  client_id: string;
+  client_secret: string;
+}
+
 export interface ApiAdminDashboard {
   roles: ApiAdminRole[];
+  permissions: string[];
   hubs: ApiAdminHub[];
   admin_menu: ApiAdminMenuItem[];
+  hub_policy: ApiHubPolicy;
+  oauth_clients: ApiAdminOAuthClient[];
+  service_accounts: ApiAdminServiceAccount[];
+}
+
+export interface ApiTotpEnrollment {
+  secret: string;
+  otpauth_uri: string;
+  qr_svg: string;
+}
+
+export interface ApiTotpRecoveryCodes {
+  message: string;
+  recovery_codes: string[];
+  redirect_to: string | null;
+}
+
+export interface ApiPasskey {
+  id: number;
+  name: string;
+  created_at: string;
+  last_used_at: string | null;
+}
+
+export interface ApiSession {
+  id: number;
+  user_agent: string | null;
+  ip_address: string | null;
+  created_at: string;
+  last_seen_at: string | null;
+  expires_at: string;
+  current: boolean;
+}
+
+export type ApiTokenScope = "profile" | "users" | "admin";
+
+export interface ApiPersonalToken {
+  id: number;
+  name: string;
+  scopes: ApiTokenScope[];
+  created_at: string;
+  last_used_at: string | null;
+  expires_at: string;
+}
+
+export interface ApiPersonalTokenCreated {
+  message: string;
+  token: string;
+  expires_at: string;
 }
 
 export interface ApiUserListItem {
@@ -181,3 +326,61 @@ export async function postJson<T>(endpoint: string): Promise<T> {
 
   return readSharedJsonResponse<T>(response, endpoint);
 }
+
+export async function postFormJson<T>(
+  endpoint: string,
+  body: URLSearchParams = new URLSearchParams(),
+): Promise<T> {
+  const response = await fetch(endpoint, {
+    method: "POST",
+    headers: {
+      Accept: "application/json",
+      "Content-Type": "application/x-www-form-urlencoded",
+    },
+    body,
+  });
+
+  ensureResponseIsNotAuthRedirect(response);
+
+  const payload = await response.json().catch(() => null);
+  if (!response.ok) {
+    if (isSharedApiMutationError(payload)) {
+      throw payload;
+    }
+
+    throw new Error(
+      `Request failed for ${endpoint} with status ${response.status}`,
+    );
+  }
+
+  return payload as T;
+}
+
+export async function postJsonBody<T>(
+  endpoint: string,
+  body?: unknown,
+): Promise<T> {
+  const response = await fetch(endpoint, {
+    method: "POST",
+    headers: {
+      Accept: "application/json",
+      "Content-Type": "application/json",
+    },
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+
+  ensureResponseIsNotAuthRedirect(response);
+
+  const payload = await response.json().catch(() => null);
+  if (!response.ok) {
+    if (isSharedApiMutationError(payload)) {
+      throw payload;
+    }
+
+    throw new Error(
+      `Request failed for ${endpoint} with status ${response.status}`,
+    );
+  }
+
+  return payload as T;
+}
@@ -1,6 +1,11 @@
 import { afterEach, describe, expect, it } from "vitest";
 
-import { getNextFromLocation, withNext } from "./auth";
+import {
+  getNextFromLocation,
+  getTokenFromLocation,
+  getTwoFactorModeFromLocation,
+  withNext,
+} from "./auth";
 
 describe("withNext", () => {
   it("returns the base path when next is missing", () => {
@@ -41,3 +46,47 @@ describe("getNextFromLocation", () => {
     expect(getNextFromLocation()).toBeNull();
   });
 });
+
+describe("getTokenFromLocation", () => {
+  afterEach(() => {
+    window.history.replaceState({}, "", "http://localhost/");
+  });
+
+  it("returns null when token is absent", () => {
+    window.history.replaceState({}, "", "http://localhost/auth/reset");
+
+    expect(getTokenFromLocation()).toBeNull();
+  });
+
+  it("returns token when it is present", () => {
+    window.history.replaceState(
+      {},
+      "",
+      "http://localhost/auth/reset?token=abc123",
+    );
+
+    expect(getTokenFromLocation()).toBe("abc123");
+  });
+});
+
+describe("getTwoFactorModeFromLocation", () => {
+  afterEach(() => {
+    window.history.replaceState({}, "", "http://localhost/");
+  });
+
+  it("defaults to verification", () => {
+    window.history.replaceState({}, "", "http://localhost/auth/2fa");
+
+    expect(getTwoFactorModeFromLocation()).toBe("verify");
+  });
+
+  it("returns setup when requested", () => {
+    window.history.replaceState(
+      {},
+      "",
+      "http://localhost/auth/2fa?mode=setup&next=%2F",
+    );
+
+    expect(getTwoFactorModeFromLocation()).toBe("setup");
+  });
+});
@@ -9,6 +9,20 @@ export function getNextFromLocation(): string | null {
   return next && next.length > 0 ? next : null;
 }
 
+export function getTokenFromLocation(): string | null {
+  const token = new URLSearchParams(window.location.search).get("token");
+
+  return token && token.length > 0 ? token : null;
+}
+
+export type TwoFactorMode = "verify" | "setup";
+
+export function getTwoFactorModeFromLocation(): TwoFactorMode {
+  const mode = new URLSearchParams(window.location.search).get("mode");
+
+  return mode === "setup" ? "setup" : "verify";
+}
+
 export function withNext(path: string, next: string | null): string {
   if (!next) {
     return path;
@@ -0,0 +1,21 @@
+import { describe, expect, it } from "vitest";
+
+import { base64UrlToBuffer, bufferToBase64Url } from "./webauthn";
+
+describe("base64url conversion", () => {
+  it("encodes without padding or URL-unsafe characters", () => {
+    const bytes = new Uint8Array([0xfb, 0xff, 0xbf, 0x01]);
+
+    expect(bufferToBase64Url(bytes.buffer)).toBe("-_-_AQ");
+  });
+
+  it("round-trips arbitrary bytes", () => {
+    const bytes = new Uint8Array([0, 1, 2, 250, 251, 252, 253, 254, 255]);
+
+    const decoded = new Uint8Array(
+      base64UrlToBuffer(bufferToBase64Url(bytes.buffer)),
+    );
+
+    expect(Array.from(decoded)).toEqual(Array.from(bytes));
+  });
+});
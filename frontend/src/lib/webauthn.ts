@@ -0,0 +1,141 @@
+import { postFormJson, postJsonBody, type ApiMutationSuccess } from "./api";
+
+type JsonCredentialDescriptor = {
+  id: string;
+  type: PublicKeyCredentialType;
+  transports?: AuthenticatorTransport[];
+};
+
+type JsonCreationOptions = {
+  publicKey: Omit<
+    PublicKeyCredentialCreationOptions,
+    "challenge" | "user" | "excludeCredentials"
+  > & {
+    challenge: string;
+    user: { id: string; name: string; displayName: string };
+    excludeCredentials?: JsonCredentialDescriptor[];
+  };
+};
+
+type JsonRequestOptions = {
+  publicKey: Omit<
+    PublicKeyCredentialRequestOptions,
+    "challenge" | "allowCredentials"
+  > & {
+    challenge: string;
+    allowCredentials?: JsonCredentialDescriptor[];
+  };
+};
+
+export function isPasskeySupported(): boolean {
+  return typeof window.PublicKeyCredential === "function";
+}
+
+export function base64UrlToBuffer(value: string): ArrayBuffer {
+  const base64 = value.replace(/-/g, "+").replace(/_/g, "/");
+  const padded = base64.padEnd(Math.ceil(base64.length / 4) * 4, "=");
+  const binary = atob(padded);
+  const bytes = new Uint8Array(binary.length);
+  for (let index = 0; index < binary.length; index += 1) {
+    bytes[index] = binary.charCodeAt(index);
+  }
+  return bytes.buffer;
+}
+
+export function bufferToBase64Url(buffer: ArrayBuffer): string {
+  const bytes = new Uint8Array(buffer);
+  let binary = "";
+  for (const byte of bytes) {
+    binary += String.fromCharCode(byte);
+  }
+  return btoa(binary)
+    .replace(/\+/g, "-")
+    .replace(/\//g, "_")
+    .replace(/=+$/, "");
+}
+
+function toDescriptor(
+  descriptor: JsonCredentialDescriptor,
+): PublicKeyCredentialDescriptor {
+  return { ...descriptor, id: base64UrlToBuffer(descriptor.id) };
+}
+
+/** Registers a new passkey for the signed-in user. */
+export async function registerPasskey(
+  name: string,
+): Promise<ApiMutationSuccess> {
+  const options = await postJsonBody<JsonCreationOptions>(
+    "/auth/webauthn/register/start",
+  );
+  const { publicKey } = options;
+  const credential = (await navigator.credentials.create({
+    publicKey: {
+      ...publicKey,
+      challenge: base64UrlToBuffer(publicKey.challenge),
+      user: { ...publicKey.user, id: base64UrlToBuffer(publicKey.user.id) },
+      excludeCredentials: publicKey.excludeCredentials?.map(toDescriptor),
+    },
+  })) as PublicKeyCredential | null;
+
+  if (!credential) {
+    throw new Error("Passkey registration was cancelled.");
+  }
+
+  const response = credential.response as AuthenticatorAttestationResponse;
+  return postJsonBody<ApiMutationSuccess>("/auth/webauthn/register/finish", {
+    name,
+    credential: {
+      id: credential.id,
+      rawId: bufferToBase64Url(credential.rawId),
+      type: credential.type,
+      response: {
+        attestationObject: bufferToBase64Url(response.attestationObject),
+        clientDataJSON: bufferToBase64Url(response.clientDataJSON),
+      },
+      extensions: {},
+    },
+  });
+}
+
+/** Signs in with a passkey of the account identified by email and hub. */
+export async function signInWithPasskey(
+  email: string,
+  hubId: string,
+  finishEndpoint: string,
+): Promise<ApiMutationSuccess> {
+  const body = new URLSearchParams();
+  body.set("email", email);
+  body.set("hub_id", hubId);
+  const options = await postFormJson<JsonRequestOptions>(
+    "/auth/webauthn/login/start",
+    body,
+  );
+  const { publicKey } = options;
+  const credential = (await navigator.credentials.get({
+    publicKey: {
+      ...publicKey,
+      challenge: base64UrlToBuffer(publicKey.challenge),
+      allowCredentials: publicKey.allowCredentials?.map(toDescriptor),
+    },
+  })) as PublicKeyCredential | null;
+
+  if (!credential) {
+    throw new Error("Passkey sign-in was cancelled.");
+  }
+
+  const response = credential.response as AuthenticatorAssertionResponse;
+  return postJsonBody<ApiMutationSuccess>(finishEndpoint, {
+    id: credential.id,
+    rawId: bufferToBase64Url(credential.rawId),
+    type: credential.type,
+    response: {
+      authenticatorData: bufferToBase64Url(response.authenticatorData),
+      clientDataJSON: bufferToBase64Url(response.clientDataJSON),
+      signature: bufferToBase64Url(response.signature),
+      userHandle: response.userHandle
+        ? bufferToBase64Url(response.userHandle)
+        : null,
+    },
+    extensions: {},
+  });
+}
@@ -0,0 +1,183 @@
+import { useMemo, useState } from "react";
+import type { FormEvent } from "react";
+
+import { AuthModalFlashShell } from "../components/AuthModalFlashShell";
+import { postForm, toFieldErrorMap, type ApiMutationError } from "../lib/api";
+
+type AuthResetPageProps = {
+  /** Emailed token; `null` when the session identifies the user instead. */
+  token: string | null;
+  /** Endpoint receiving the token and the new password. */
+  endpoint?: string;
+  title?: string;
+  invalidTokenMessage?: string;
+};
+
+/**
+ * Sets a password from an emailed token (password recovery or an invitation)
+ * or during a login that requires a new password.
+ */
+export function AuthResetPage({
+  token,
+  endpoint = "/auth/reset",
+  title = "Новый пароль",
+  invalidTokenMessage = "Ссылка для восстановления недействительна.",
+}: AuthResetPageProps) {
+  const [password, setPassword] = useState("");
+  const [passwordConfirmation, setPasswordConfirmation] = useState("");
+  const [fieldErrors, setFieldErrors] = useState<Record<string, string>>({});
+  const [isSubmitting, setIsSubmitting] = useState(false);
+
+  const passwordsMatch = useMemo(
+    () => password === passwordConfirmation,
+    [password, passwordConfirmation],
+  );
+
+  const submitClassName = passwordsMatch
+    ? "btn btn-primary text-white"
+    : "btn btn-danger text-white";
+
+  async function handleSubmit(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    if (!passwordsMatch) {
+      return;
+    }
+
+    setIsSubmitting(true);
+    setFieldErrors({});
+
+    const body = new URLSearchParams();
+    if (token !== null) {
+      body.set("token", token);
+    }
+    body.set("password", password);
+    body.set("confirm_password", passwordConfirmation);
+
+    try {
+      const result = await postForm(endpoint, body);
+      window.location.assign(result.redirect_to ?? "/");
+    } catch (error) {
+      const mutationError = error as ApiMutationError;
+      setFieldErrors(toFieldErrorMap(mutationError));
+      window.showFlashMessage?.(mutationError.message, "danger");
+    } finally {
+      setIsSubmitting(false);
+    }
+  }
+
+  if (token === "") {
+    return (
+      <AuthModalFlashShell>
+        <div className="row justify-content-center">
+          <div className="col-md-6">
+            <div className="card mt-5">
+              <div className="card-body">
+                <div className="alert alert-danger mb-0">
+                  {invalidTokenMessage}{" "}
+                  <a href="/auth/signin">Авторизация</a>
+                </div>
+              </div>
+            </div>
+          </div>
+        </div>
+      </AuthModalFlashShell>
+    );
+  }
+
+  return (
+    <AuthModalFlashShell>
+      <div className="row justify-content-center">
+        <div className="col-md-6">
+          <div className="card mt-5">
+            <div className="card-header text-muted fw-bold">{title}</div>
+            <div className="card-body">
+              <form onSubmit={(event) => void handleSubmit(event)}>
+                <div className="row mb-3">
+                  <label className="col-md-4 col-form-label" htmlFor="password">
+                    Пароль
+                  </label>
+                  <div className="col-md-6">
+                    <input
+                      autoFocus
+                      className={
+                        fieldErrors.password
+                          ? "form-control is-invalid"
+                          : "form-control"
+                      }
+                      id="password"
+                      name="password"
+                      required
+                      type="password"
+                      value={password}
+                      onChange={(event) => {
+                        setPassword(event.target.value);
+                        setFieldErrors((errors) => ({
+                          ...errors,
+                          password: "",
+                        }));
+                      }}
+                    />
+                    {fieldErrors.password ? (
+                      <div className="invalid-feedback">
+                        {fieldErrors.password}
+                      </div>
+                    ) : null}
+                  </div>
+                </div>
+                <div className="row mb-3">
+                  <label
+                    className="col-md-4 col-form-label"
+                    htmlFor="confirm_password"
+                  >
+                    Повторите пароль
+                  </label>
+                  <div className="col-md-6">
+                    <input
+                      className={
+                        fieldErrors.confirm_password
+                          ? "form-control is-invalid"
+                          : "form-control"
+                      }
+                      id="confirm_password"
+                      name="confirm_password"
+                      required
+                      type="password"
+                      value={passwordConfirmation}
+                      onChange={(event) => {
+                        setPasswordConfirmation(event.target.value);
+                        setFieldErrors((errors) => ({
+                          ...errors,
+                          confirm_password: "",
+                        }));
+                      }}
+                    />
+                    {fieldErrors.confirm_password ? (
+                      <div className="invalid-feedback">
+                        {fieldErrors.confirm_password}
+                      </div>
+                    ) : null}
+                  </div>
+                </div>
+                <div className="row mb-3">
+                  <div className="col-md-6 offset-md-4">
+                    <input
+                      className={submitClassName}
+                      disabled={!passwordsMatch || isSubmitting}
+                      id="submit"
+                      name="submit"
+                      type="submit"
+                      value="Сохранить"
+                    />
+                    <a href="/auth/signin" className="btn btn-link">
+                      Авторизация
+                    </a>
+                  </div>
+                </div>
+              </form>
+            </div>
+          </div>
+        </div>
+      </div>
+    </AuthModalFlashShell>
+  );
+}
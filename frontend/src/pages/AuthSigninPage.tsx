@@ -2,8 +2,14 @@ import { useState } from "react";
 import type { FormEvent } from "react";
 
 import { AuthModalFlashShell } from "../components/AuthModalFlashShell";
-import { postForm, toFieldErrorMap, type ApiMutationError } from "../lib/api";
+import {
+  isApiMutationError,
+  postForm,
+  toFieldErrorMap,
+  type ApiMutationError,
+} from "../lib/api";
 import { getNextFromLocation, type HubOption, withNext } from "../lib/auth";
+import { isPasskeySupported, signInWithPasskey } from "../lib/webauthn";
 
 export type SigninPageData = HubOption[];
 
@@ -23,13 +29,13 @@ export function AuthSigninPage({ hubs }: { hubs: SigninPageData }) {
     ? "bi bi-eye-slash"
     : "bi bi-eye";
 
-  const handleRecoverClick = async () => {
+  const reportMissingAccount = () => {
     if (!email.trim()) {
       const emailInput = document.getElementById("email");
       if (emailInput instanceof HTMLInputElement) {
         emailInput.reportValidity();
       }
-      return;
+      return true;
     }
 
     if (!hubId) {
@@ -37,6 +43,14 @@ export function AuthSigninPage({ hubs }: { hubs: SigninPageData }) {
       if (hubSelect instanceof HTMLSelectElement) {
         hubSelect.reportValidity();
       }
+      return true;
+    }
+
+    return false;
+  };
+
+  const handleRecoverClick = async () => {
+    if (reportMissingAccount()) {
       return;
     }
 
@@ -60,6 +74,55 @@ export function AuthSigninPage({ hubs }: { hubs: SigninPageData }) {
     }
   };
 
+  const handleResendVerificationClick = async () => {
+    if (reportMissingAccount()) {
+      return;
+    }
+
+    try {
+      const body = new URLSearchParams();
+      body.set("email", email);
+      body.set("hub_id", hubId);
+
+      const result = await postForm("/auth/verify/resend", body);
+      setRecoverErrors({});
+      window.showFlashMessage?.(result.message, "success");
+    } catch (error) {
+      const mutationError = error as ApiMutationError;
+      setRecoverErrors(toFieldErrorMap(mutationError));
+      window.showFlashMessage?.(mutationError.message, "danger");
+    }
+  };
+
+  const handlePasskeyClick = async () => {
+    if (reportMissingAccount()) {
+      return;
+    }
+
+    setIsSubmitting(true);
+    setLoginErrors({});
+    try {
+      const result = await signInWithPasskey(
+        email,
+        hubId,
+        withNext("/auth/webauthn/login/finish", next),
+      );
+      window.location.assign(result.redirect_to ?? "/");
+    } catch (error) {
+      if (isApiMutationError(error)) {
+        setLoginErrors(toFieldErrorMap(error));
+        window.showFlashMessage?.(error.message, "danger");
+      } else {
+        window.showFlashMessage?.(
+          "Не удалось войти с ключом доступа.",
+          "danger",
+        );
+      }
+    } finally {
+      setIsSubmitting(false);
+    }
+  };
+
   async function handleSubmit(event: FormEvent<HTMLFormElement>) {
     event.preventDefault();
     setIsSubmitting(true);
@@ -227,8 +290,29 @@ export function AuthSigninPage({ hubs }: { hubs: SigninPageData }) {
                     >
                       Регистрация
                     </a>
+                    <button
+                      className="btn btn-link"
+                      type="button"
+                      onClick={() => void handleResendVerificationClick()}
+                    >
+                      Выслать ссылку подтверждения
+                    </button>
                   </div>
                 </div>
+                {isPasskeySupported() ? (
+                  <div className="row mb-3">
+                    <div className="col-md-6 offset-md-4">
+                      <button
+                        className="btn btn-outline-secondary"
+                        disabled={isSubmitting}
+                        type="button"
+                        onClick={() => void handlePasskeyClick()}
+                      >
+                        <i className="bi bi-key"></i> Войти с ключом доступа
+                      </button>
+                    </div>
+                  </div>
+                ) : null}
               </form>
             </div>
           </div>
@@ -0,0 +1,241 @@
+import { useEffect, useState } from "react";
+import type { FormEvent } from "react";
+
+import { AuthModalFlashShell } from "../components/AuthModalFlashShell";
+import {
+  isApiMutationError,
+  postForm,
+  postFormJson,
+  toFieldErrorMap,
+  type ApiMutationError,
+  type ApiTotpEnrollment,
+  type ApiTotpRecoveryCodes,
+} from "../lib/api";
+import { withNext, type TwoFactorMode } from "../lib/auth";
+
+function toMutationError(error: unknown): ApiMutationError {
+  if (isApiMutationError(error)) {
+    return error;
+  }
+
+  return {
+    message: "Не удалось выполнить запрос.",
+    field_errors: [],
+  };
+}
+
+function CodeForm({
+  label,
+  submitLabel,
+  onSubmit,
+}: {
+  label: string;
+  submitLabel: string;
+  onSubmit: (code: string) => Promise<Record<string, string>>;
+}) {
+  const [code, setCode] = useState("");
+  const [fieldErrors, setFieldErrors] = useState<Record<string, string>>({});
+  const [isSubmitting, setIsSubmitting] = useState(false);
+
+  async function handleSubmit(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    setIsSubmitting(true);
+    setFieldErrors({});
+    setFieldErrors(await onSubmit(code));
+    setIsSubmitting(false);
+  }
+
+  return (
+    <form onSubmit={(event) => void handleSubmit(event)}>
+      <div className="row mb-3">
+        <label className="col-md-4 col-form-label" htmlFor="code">
+          {label}
+        </label>
+        <div className="col-md-6">
+          <input
+            autoComplete="one-time-code"
+            autoFocus
+            className={
+              fieldErrors.code ? "form-control is-invalid" : "form-control"
+            }
+            id="code"
+            name="code"
+            required
+            type="text"
+            value={code}
+            onChange={(event) => {
+              setCode(event.target.value);
+              setFieldErrors((errors) => ({ ...errors, code: "" }));
+            }}
+          />
+          {fieldErrors.code ? (
+            <div className="invalid-feedback">{fieldErrors.code}</div>
+          ) : null}
+        </div>
+      </div>
+      <div className="row mb-3">
+        <div className="col-md-6 offset-md-4">
+          <input
+            className="btn btn-primary text-white"
+            disabled={isSubmitting}
+            id="submit"
+            name="submit"
+            type="submit"
+            value={submitLabel}
+          />
+          <a href="/auth/signin" className="btn btn-link">
+            Авторизация
+          </a>
+        </div>
+      </div>
+    </form>
+  );
+}
+
+function VerifyCard({ next }: { next: string | null }) {
+  async function handleSubmit(code: string) {
+    const body = new URLSearchParams();
+    body.set("code", code);
+
+    try {
+      const result = await postForm(withNext("/auth/2fa/verify", next), body);
+      window.location.assign(result.redirect_to ?? "/");
+      return {};
+    } catch (error) {
+      const mutationError = toMutationError(error);
+      window.showFlashMessage?.(mutationError.message, "danger");
+      return toFieldErrorMap(mutationError);
+    }
+  }
+
+  return (
+    <>
+      <p className="text-muted">
+        Введите код из приложения-аутентификатора или один из кодов
+        восстановления.
+      </p>
+      <CodeForm
+        label="Код подтверждения"
+        submitLabel="Войти"
+        onSubmit={handleSubmit}
+      />
+    </>
+  );
+}
+
+function SetupCard({ next }: { next: string | null }) {
+  const [enrollment, setEnrollment] = useState<ApiTotpEnrollment | null>(null);
+  const [result, setResult] = useState<ApiTotpRecoveryCodes | null>(null);
+  const [loadError, setLoadError] = useState<string | null>(null);
+
+  useEffect(() => {
+    postFormJson<ApiTotpEnrollment>("/auth/2fa/setup")
+      .then(setEnrollment)
+      .catch((error: unknown) => {
+        setLoadError(toMutationError(error).message);
+      });
+  }, []);
+
+  async function handleSubmit(code: string) {
+    const body = new URLSearchParams();
+    body.set("code", code);
+
+    try {
+      const confirmed = await postFormJson<ApiTotpRecoveryCodes>(
+        withNext("/auth/2fa/enable", next),
+        body,
+      );
+      setResult(confirmed);
+      window.showFlashMessage?.(confirmed.message, "success");
+      return {};
+    } catch (error) {
+      const mutationError = toMutationError(error);
+      window.showFlashMessage?.(mutationError.message, "danger");
+      return toFieldErrorMap(mutationError);
+    }
+  }
+
+  if (loadError) {
+    return <div className="alert alert-danger mb-0">{loadError}</div>;
+  }
+
+  if (result) {
+    return (
+      <>
+        <p>
+          Сохраните коды восстановления. Каждый код можно использовать один
+          раз, если приложение-аутентификатор недоступно.
+        </p>
+        <ul className="list-unstyled font-monospace">
+          {result.recovery_codes.map((code) => (
+            <li key={code}>{code}</li>
+          ))}
+        </ul>
+        <a href={result.redirect_to ?? "/"} className="btn btn-primary">
+          Продолжить
+        </a>
+      </>
+    );
+  }
+
+  if (!enrollment) {
+    return (
+      <div className="text-center py-3">
+        <div className="spinner-border" role="status" aria-hidden="true"></div>
+      </div>
+    );
+  }
+
+  return (
+    <>
+      <p className="text-muted">
+        Отсканируйте QR-код в приложении-аутентификаторе и введите
+        показанный код.
+      </p>
+      <div className="text-center mb-3">
+        <img
+          alt="QR-код для приложения-аутентификатора"
+          src={`data:image/svg+xml;utf8,${encodeURIComponent(enrollment.qr_svg)}`}
+        />
+        <div className="small text-muted mt-2">
+          Ключ для ручного ввода:{" "}
+          <span className="font-monospace">{enrollment.secret}</span>
+        </div>
+      </div>
+      <CodeForm
+        label="Код подтверждения"
+        submitLabel="Включить"
+        onSubmit={handleSubmit}
+      />
+    </>
+  );
+}
+
+export function AuthTwoFactorPage({
+  mode,
+  next,
+}: {
+  mode: TwoFactorMode;
+  next: string | null;
+}) {
+  return (
+    <AuthModalFlashShell>
+      <div className="row justify-content-center">
+        <div className="col-md-6">
+          <div className="card mt-5">
+            <div className="card-header text-muted fw-bold">
+              Двухфакторная аутентификация
+            </div>
+            <div className="card-body">
+              {mode === "setup" ? (
+                <SetupCard next={next} />
+              ) : (
+                <VerifyCard next={next} />
+              )}
+            </div>
+          </div>
+        </div>
+      </div>
+    </AuthModalFlashShell>
+  );
+}
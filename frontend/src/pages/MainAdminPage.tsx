@@ -15,10 +15,18 @@ import {
   isRedirectResponseError,
   postEmpty,
   postForm,
+  postFormJson,
   postJson,
   toFieldErrorMap,
   type ApiAdminDashboard,
+  type ApiAdminDeleted,
+  type ApiAdminInvitation,
+  type ApiHubPolicy,
+  type ApiHubRegistrationSettings,
   type ApiMutationError,
+  type ApiOAuthClientCredentials,
+  type ApiRegistrationMode,
+  type ApiServiceAccountCredentials,
   type ApiUserListItem,
   type DashboardUser,
 } from "../lib/api";
@@ -33,8 +41,13 @@ interface RoleOption {
 interface AdminEditableUser {
   id: number;
   email: string;
+  email_verified_at: string | null;
+  disabled_at: string | null;
+  disabled_reason: string | null;
+  must_change_password: boolean;
   name: string;
   roles: number[];
+  inherited_roles: number[];
 }
 
 interface AdminUserModalBootstrap {
@@ -45,14 +58,25 @@ interface AdminUserModalBootstrap {
 interface AdminUserFormState {
   id: number;
   email: string;
+  emailVerified: boolean;
+  disabledAt: string | null;
+  disabledReason: string;
   name: string;
   password: string;
   roles: string[];
+  resetTwoFactor: boolean;
+  mustChangePassword: boolean;
 }
 
 type AdminPageState =
   | { status: "loading" }
-  | { status: "ready"; admin: ApiAdminDashboard; users: DashboardUser[] }
+  | {
+      status: "ready";
+      admin: ApiAdminDashboard;
+      users: DashboardUser[];
+      invitations: ApiAdminInvitation[];
+      deleted: ApiAdminDeleted;
+    }
   | { status: "error"; message: string };
 
 function toMutationError(
@@ -92,12 +116,53 @@ export function MainAdminPage() {
   });
   const [filterValue, setFilterValue] = useState("");
   const [roleName, setRoleName] = useState("");
+  const [rolePermissions, setRolePermissions] = useState<string[]>([]);
   const [hubName, setHubName] = useState("");
   const [menuName, setMenuName] = useState("");
   const [menuUrl, setMenuUrl] = useState("");
   const [roleErrors, setRoleErrors] = useState<Record<string, string>>({});
   const [hubErrors, setHubErrors] = useState<Record<string, string>>({});
   const [menuErrors, setMenuErrors] = useState<Record<string, string>>({});
+  const [clientName, setClientName] = useState("");
+  const [clientRedirectUris, setClientRedirectUris] = useState("");
+  const [clientConfidential, setClientConfidential] = useState(true);
+  const [clientErrors, setClientErrors] = useState<Record<string, string>>({});
+  const [isSubmittingClient, setIsSubmittingClient] = useState(false);
+  const [clientCredentials, setClientCredentials] =
+    useState<ApiOAuthClientCredentials | null>(null);
+  const [newUserEmail, setNewUserEmail] = useState("");
+  const [newUserName, setNewUserName] = useState("");
+  const [newUserPassword, setNewUserPassword] = useState("");
+  const [newUserRoles, setNewUserRoles] = useState<string[]>([]);
+  const [newUserMustChangePassword, setNewUserMustChangePassword] =
+    useState(true);
+  const [newUserErrors, setNewUserErrors] = useState<Record<string, string>>(
+    {},
+  );
+  const [isSubmittingNewUser, setIsSubmittingNewUser] = useState(false);
+  const [inviteEmail, setInviteEmail] = useState("");
+  const [inviteName, setInviteName] = useState("");
+  const [inviteRoles, setInviteRoles] = useState<string[]>([]);
+  const [inviteErrors, setInviteErrors] = useState<Record<string, string>>({});
+  const [isSubmittingInvite, setIsSubmittingInvite] = useState(false);
+  const [accountName, setAccountName] = useState("");
+  const [accountRoles, setAccountRoles] = useState<string[]>([]);
+  const [accountErrors, setAccountErrors] = useState<Record<string, string>>(
+    {},
+  );
+  const [isSubmittingAccount, setIsSubmittingAccount] = useState(false);
+  const [accountCredentials, setAccountCredentials] =
+    useState<ApiServiceAccountCredentials | null>(null);
+  const [settingsHubId, setSettingsHubId] = useState<number | null>(null);
+  const [registrationMode, setRegistrationMode] =
+    useState<ApiRegistrationMode>("open");
+  const [registrationDomains, setRegistrationDomains] = useState("");
+  const [registrationRoles, setRegistrationRoles] = useState<string[]>([]);
+  const [registrationErrors, setRegistrationErrors] = useState<
+    Record<string, string>
+  >({});
+  const [isSubmittingRegistration, setIsSubmittingRegistration] =
+    useState(false);
   const [isSubmittingRole, setIsSubmittingRole] = useState(false);
   const [isSubmittingHub, setIsSubmittingHub] = useState(false);
   const [isSubmittingMenu, setIsSubmittingMenu] = useState(false);
@@ -118,9 +183,46 @@ export function MainAdminPage() {
   useEffect(() => {
     if (shellState.status === "ready") {
       setMenuState(shellState.authMenuItems);
+      setSettingsHubId(
+        (hubId) => hubId ?? shellState.shell.currentUser.hubId,
+      );
     }
   }, [shellState]);
 
+  useEffect(() => {
+    if (settingsHubId === null) {
+      return;
+    }
+
+    let active = true;
+
+    void fetchJson<ApiHubRegistrationSettings>(
+      `/admin/hub/${settingsHubId}/settings`,
+    )
+      .then((settings) => {
+        if (!active) {
+          return;
+        }
+
+        setRegistrationMode(settings.registration_mode);
+        setRegistrationDomains(settings.allowed_email_domains.join("\n"));
+        setRegistrationRoles(settings.default_roles.map(String));
+        setRegistrationErrors({});
+      })
+      .catch(() => {
+        if (active) {
+          window.showFlashMessage?.(
+            "Не удалось загрузить настройки регистрации.",
+            "danger",
+          );
+        }
+      });
+
+    return () => {
+      active = false;
+    };
+  }, [settingsHubId]);
+
   useEffect(() => {
     if (shellState.status !== "ready") {
       return;
@@ -130,9 +232,11 @@ export function MainAdminPage() {
 
     void Promise.all([
       fetchJson<ApiAdminDashboard>("/api/v1/admin/dashboard"),
-      fetchJson<ApiUserListItem[]>("/api/v1/users"),
+      fetchJson<ApiUserListItem[]>("/api/v1/users?status=all"),
+      fetchJson<ApiAdminInvitation[]>("/api/v1/admin/invitations"),
+      fetchJson<ApiAdminDeleted>("/api/v1/admin/deleted"),
     ])
-      .then(([admin, users]) => {
+      .then(([admin, users, invitations, deleted]) => {
         if (!active) {
           return;
         }
@@ -141,6 +245,8 @@ export function MainAdminPage() {
           status: "ready",
           admin,
           users: mapUsers(users),
+          invitations,
+          deleted,
         });
       })
       .catch((error) => {
@@ -189,17 +295,22 @@ export function MainAdminPage() {
   });
 
   async function refreshAdminPage(): Promise<void> {
-    const [nextMenu, nextAdmin, nextUsers] = await Promise.all([
-      fetchHubMenuItems(shell.homeUrl, shell.currentUser.hubId),
-      fetchJson<ApiAdminDashboard>("/api/v1/admin/dashboard"),
-      fetchJson<ApiUserListItem[]>("/api/v1/users"),
-    ]);
+    const [nextMenu, nextAdmin, nextUsers, nextInvitations, nextDeleted] =
+      await Promise.all([
+        fetchHubMenuItems(shell.homeUrl, shell.currentUser.hubId),
+        fetchJson<ApiAdminDashboard>("/api/v1/admin/dashboard"),
+        fetchJson<ApiUserListItem[]>("/api/v1/users?status=all"),
+        fetchJson<ApiAdminInvitation[]>("/api/v1/admin/invitations"),
+        fetchJson<ApiAdminDeleted>("/api/v1/admin/deleted"),
+      ]);
 
     setMenuState(nextMenu);
     setPageState({
       status: "ready",
       admin: nextAdmin,
       users: mapUsers(nextUsers),
+      invitations: nextInvitations,
+      deleted: nextDeleted,
     });
   }
 
@@ -292,9 +403,14 @@ export function MainAdminPage() {
         setModalForm({
           id: data.user.id,
           email: data.user.email,
+          emailVerified: data.user.email_verified_at !== null,
+          disabledAt: data.user.disabled_at,
+          disabledReason: data.user.disabled_reason ?? "",
           name: data.user.name,
           password: "",
           roles: data.user.roles.map(String),
+          resetTwoFactor: false,
+          mustChangePassword: data.user.must_change_password,
         });
       }
     } catch (error) {
@@ -321,16 +437,23 @@ export function MainAdminPage() {
 
     const body = new URLSearchParams();
     body.set("name", roleName);
+    rolePermissions.forEach((permission) =>
+      body.append("permissions", permission),
+    );
 
     const didSucceed = await handleCreateMutation(
       "/admin/role/add",
       body,
       setRoleErrors,
-      () => setRoleName(""),
+      () => {
+        setRoleName("");
+        setRolePermissions([]);
+      },
     );
 
     if (didSucceed) {
       setRoleName("");
+      setRolePermissions([]);
     }
 
     setIsSubmittingRole(false);
@@ -357,6 +480,41 @@ export function MainAdminPage() {
     setIsSubmittingHub(false);
   }
 
+  async function handleHubPolicyChange(policy: ApiHubPolicy) {
+    const body = new URLSearchParams();
+    if (policy.require_admin_2fa) {
+      body.set("require_admin_2fa", "true");
+    }
+    if (policy.require_email_verification) {
+      body.set("require_email_verification", "true");
+    }
+
+    await handleCreateMutation("/admin/hub/policy", body, () => undefined);
+  }
+
+  async function handleRegistrationSettingsSubmit(
+    event: FormEvent<HTMLFormElement>,
+  ) {
+    event.preventDefault();
+    if (settingsHubId === null) {
+      return;
+    }
+    setIsSubmittingRegistration(true);
+
+    const body = new URLSearchParams();
+    body.set("registration_mode", registrationMode);
+    body.set("allowed_email_domains", registrationDomains);
+    registrationRoles.forEach((role) => body.append("default_roles", role));
+
+    await handleCreateMutation(
+      `/admin/hub/${settingsHubId}/settings`,
+      body,
+      setRegistrationErrors,
+    );
+
+    setIsSubmittingRegistration(false);
+  }
+
   async function handleMenuSubmit(event: FormEvent<HTMLFormElement>) {
     event.preventDefault();
     setIsSubmittingMenu(true);
@@ -383,6 +541,187 @@ export function MainAdminPage() {
     setIsSubmittingMenu(false);
   }
 
+  async function handleOAuthClientSubmit(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    setIsSubmittingClient(true);
+
+    const body = new URLSearchParams();
+    body.set("name", clientName);
+    body.set("redirect_uris", clientRedirectUris);
+    if (clientConfidential) {
+      body.set("confidential", "true");
+    }
+
+    try {
+      const credentials = await postFormJson<ApiOAuthClientCredentials>(
+        "/admin/oauth-client/add",
+        body,
+      );
+      setClientErrors({});
+      setClientName("");
+      setClientRedirectUris("");
+      setClientCredentials(credentials);
+      await refreshAdminPage();
+      window.showFlashMessage?.(credentials.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(
+          error,
+          "Не удалось сохранить изменения.",
+        );
+        setClientErrors(toFieldErrorMap(mutationError));
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+
+    setIsSubmittingClient(false);
+  }
+
+  async function handleNewUserSubmit(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    setIsSubmittingNewUser(true);
+
+    const body = new URLSearchParams();
+    body.set("email", newUserEmail);
+    body.set("name", newUserName);
+    body.set("password", newUserPassword);
+    newUserRoles.forEach((role) => body.append("roles", role));
+    if (newUserMustChangePassword) {
+      body.set("must_change_password", "true");
+    }
+
+    await handleCreateMutation(
+      "/admin/user/add",
+      body,
+      setNewUserErrors,
+      () => {
+        setNewUserEmail("");
+        setNewUserName("");
+        setNewUserPassword("");
+        setNewUserRoles([]);
+        setNewUserMustChangePassword(true);
+      },
+    );
+
+    setIsSubmittingNewUser(false);
+  }
+
+  async function handleInviteSubmit(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    setIsSubmittingInvite(true);
+
+    const body = new URLSearchParams();
+    body.set("email", inviteEmail);
+    body.set("name", inviteName);
+    inviteRoles.forEach((role) => body.append("roles", role));
+
+    await handleCreateMutation(
+      "/admin/user/invite",
+      body,
+      setInviteErrors,
+      () => {
+        setInviteEmail("");
+        setInviteName("");
+        setInviteRoles([]);
+      },
+    );
+
+    setIsSubmittingInvite(false);
+  }
+
+  async function handleInvitationResend(invitationId: number) {
+    try {
+      const result = await postEmpty(
+        `/admin/invitation/resend/${invitationId}`,
+      );
+      await refreshAdminPage();
+      window.showFlashMessage?.(result.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(
+          error,
+          "Не удалось отправить приглашение.",
+        );
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+  }
+
+  async function handleRestore(endpoint: string) {
+    try {
+      const result = await postEmpty(endpoint);
+      await refreshAdminPage();
+      window.showFlashMessage?.(result.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(error, "Не удалось восстановить.");
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+  }
+
+  async function handleServiceAccountSubmit(
+    event: FormEvent<HTMLFormElement>,
+  ) {
+    event.preventDefault();
+    setIsSubmittingAccount(true);
+
+    const body = new URLSearchParams();
+    body.set("name", accountName);
+    accountRoles.forEach((role) => body.append("roles", role));
+
+    try {
+      const credentials = await postFormJson<ApiServiceAccountCredentials>(
+        "/admin/service-account/add",
+        body,
+      );
+      setAccountErrors({});
+      setAccountName("");
+      setAccountRoles([]);
+      setAccountCredentials(credentials);
+      await refreshAdminPage();
+      window.showFlashMessage?.(credentials.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(
+          error,
+          "Не удалось сохранить изменения.",
+        );
+        setAccountErrors(toFieldErrorMap(mutationError));
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+
+    setIsSubmittingAccount(false);
+  }
+
+  async function handleServiceAccountRotate(accountId: number) {
+    if (
+      !window.confirm(
+        "Выдать новый секрет? Текущие токены перестанут действовать.",
+      )
+    ) {
+      return;
+    }
+
+    try {
+      const credentials = await postFormJson<ApiServiceAccountCredentials>(
+        `/admin/service-account/rotate/${accountId}`,
+      );
+      setAccountCredentials(credentials);
+      await refreshAdminPage();
+      window.showFlashMessage?.(credentials.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(
+          error,
+          "Не удалось сохранить изменения.",
+        );
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+  }
+
   async function handleModalSave(event: FormEvent<HTMLFormElement>) {
     event.preventDefault();
     if (!modalForm) {
@@ -395,6 +734,12 @@ export function MainAdminPage() {
     body.set("name", modalForm.name);
     body.set("password", modalForm.password);
     modalForm.roles.forEach((role) => body.append("roles", role));
+    if (modalForm.resetTwoFactor) {
+      body.set("reset_two_factor", "true");
+    }
+    if (modalForm.mustChangePassword) {
+      body.set("must_change_password", "true");
+    }
 
     const didSucceed = await handleCreateMutation(
       `/admin/user/update/${modalForm.id}`,
@@ -410,6 +755,83 @@ export function MainAdminPage() {
     setIsSavingModal(false);
   }
 
+  async function handleModalVerifyEmail() {
+    if (!modalForm) {
+      return;
+    }
+
+    try {
+      const result = await postEmpty(`/admin/user/verify/${modalForm.id}`);
+      setModalForm((current) =>
+        current ? { ...current, emailVerified: true } : current,
+      );
+      window.showFlashMessage?.(result.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(
+          error,
+          "Не удалось подтвердить адрес.",
+        );
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+  }
+
+  async function handleModalSuspend() {
+    if (!modalForm) {
+      return;
+    }
+
+    const body = new URLSearchParams();
+    body.set("reason", modalForm.disabledReason);
+
+    try {
+      const result = await postForm(
+        `/admin/user/suspend/${modalForm.id}`,
+        body,
+      );
+      setModalForm((current) =>
+        current
+          ? { ...current, disabledAt: new Date().toISOString() }
+          : current,
+      );
+      window.showFlashMessage?.(result.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(
+          error,
+          "Не удалось заблокировать пользователя.",
+        );
+        setModalFieldErrors(toFieldErrorMap(mutationError));
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+  }
+
+  async function handleModalReactivate() {
+    if (!modalForm) {
+      return;
+    }
+
+    try {
+      const result = await postEmpty(`/admin/user/reactivate/${modalForm.id}`);
+      setModalForm((current) =>
+        current
+          ? { ...current, disabledAt: null, disabledReason: "" }
+          : current,
+      );
+      window.showFlashMessage?.(result.message, "success");
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(
+          error,
+          "Не удалось разблокировать пользователя.",
+        );
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+  }
+
   async function handleModalDelete() {
     if (!modalForm) {
       return;
@@ -486,6 +908,33 @@ export function MainAdminPage() {
                       {roleErrors.name}
                     </div>
                   ) : null}
+                  <DropdownMultiSelect
+                    id="role-permissions"
+                    options={pageState.admin.permissions.map(
+                      (permission): DropdownMultiSelectOption => ({
+                        value: permission,
+                        label: permission,
+                      }),
+                    )}
+                    selectedValues={rolePermissions}
+                    onChange={(values) => {
+                      setRolePermissions(values);
+                      setRoleErrors((errors) => ({
+                        ...errors,
+                        permissions: "",
+                      }));
+                    }}
+                    className="my-1"
+                    menuHeightClassName="auth-dropdown-multiselect-options-md"
+                    searchPlaceholder="Поиск прав"
+                    clearable
+                    clearLabel="Очистить выбранные права"
+                  />
+                  {roleErrors.permissions ? (
+                    <div className="invalid-feedback d-block">
+                      {roleErrors.permissions}
+                    </div>
+                  ) : null}
                 </div>
                 <div className="col-auto">
                   <button
@@ -504,11 +953,15 @@ export function MainAdminPage() {
                   key={role.id}
                   type="button"
                   className="btn btn-sm btn-outline-secondary mt-1 me-1"
+                  title={role.description}
                   onClick={() =>
                     void handleDeleteMutation(`/admin/role/delete/${role.id}`)
                   }
                 >
                   {role.name}
+                  <span className="badge rounded-pill bg-secondary mx-1">
+                    {role.user_count}
+                  </span>
                   <span className="badge rounded-pill bg-danger">
                     <i className="bi bi-x"></i>
                   </span>
@@ -518,8 +971,12 @@ export function MainAdminPage() {
                   key={role.id}
                   type="button"
                   className="btn btn-sm btn-outline-secondary mt-1 me-1"
+                  title={role.description}
                 >
                   {role.name}
+                  <span className="badge rounded-pill bg-secondary ms-1">
+                    {role.user_count}
+                  </span>
                 </button>
               ),
             )}
@@ -588,6 +1045,53 @@ export function MainAdminPage() {
                 </button>
               ),
             )}
+            <div className="form-check form-switch mt-2">
+              <input
+                className="form-check-input"
+                type="checkbox"
+                role="switch"
+                id="hub-policy-require-admin-2fa"
+                checked={pageState.admin.hub_policy.require_admin_2fa}
+                onChange={(event) =>
+                  void handleHubPolicyChange({
+                    ...pageState.admin.hub_policy,
+                    require_admin_2fa: event.target.checked,
+                  })
+                }
+              />
+              <label
+                className="form-check-label"
+                htmlFor="hub-policy-require-admin-2fa"
+              >
+                Требовать двухфакторную аутентификацию для администраторов
+              </label>
+            </div>
+            <div className="form-check form-switch">
+              <input
+                className="form-check-input"
+                type="checkbox"
+                role="switch"
+                id="hub-policy-require-email-verification"
+                checked={
+                  pageState.admin.hub_policy.require_email_verification
+                }
+                onChange={(event) =>
+                  void handleHubPolicyChange({
+                    ...pageState.admin.hub_policy,
+                    require_email_verification: event.target.checked,
+                  })
+                }
+              />
+              <label
+                className="form-check-label"
+                htmlFor="hub-policy-require-email-verification"
+              >
+                Требовать подтверждение электронного адреса при входе
+              </label>
+            </div>
+            <a href="/auth/2fa?mode=setup&next=%2F" className="small">
+              Настроить двухфакторную аутентификацию
+            </a>
           </div>
 
           <div className="col-md">
@@ -670,6 +1174,640 @@ export function MainAdminPage() {
         </div>
       </div>
 
+      <div className="container my-2">
+        <h5>Клиенты OpenID Connect</h5>
+        <form onSubmit={(event) => void handleOAuthClientSubmit(event)}>
+          <div className="row">
+            <div className="col-md">
+              <input
+                className={
+                  clientErrors.name
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                type="text"
+                name="name"
+                placeholder="Название"
+                required
+                value={clientName}
+                onChange={(event) => {
+                  setClientName(event.target.value);
+                  setClientErrors((errors) => ({ ...errors, name: "" }));
+                }}
+              />
+              {clientErrors.name ? (
+                <div className="invalid-feedback d-block">
+                  {clientErrors.name}
+                </div>
+              ) : null}
+              <div className="form-check form-switch mt-2">
+                <input
+                  className="form-check-input"
+                  type="checkbox"
+                  role="switch"
+                  id="oauth-client-confidential"
+                  checked={clientConfidential}
+                  onChange={(event) =>
+                    setClientConfidential(event.target.checked)
+                  }
+                />
+                <label
+                  className="form-check-label"
+                  htmlFor="oauth-client-confidential"
+                >
+                  Выдать секрет клиента
+                </label>
+              </div>
+            </div>
+            <div className="col-md">
+              <textarea
+                className={
+                  clientErrors.redirect_uris
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                name="redirect_uris"
+                placeholder="Адреса перенаправления, по одному в строке"
+                rows={3}
+                required
+                value={clientRedirectUris}
+                onChange={(event) => {
+                  setClientRedirectUris(event.target.value);
+                  setClientErrors((errors) => ({
+                    ...errors,
+                    redirect_uris: "",
+                  }));
+                }}
+              />
+              {clientErrors.redirect_uris ? (
+                <div className="invalid-feedback d-block">
+                  {clientErrors.redirect_uris}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-auto">
+              <button
+                className="btn btn-primary my-1"
+                type="submit"
+                disabled={isSubmittingClient}
+              >
+                <i className="bi bi-plus"></i>
+              </button>
+            </div>
+          </div>
+        </form>
+        {clientCredentials ? (
+          <div className="alert alert-warning mt-2">
+            <div>
+              client_id: <code>{clientCredentials.client_id}</code>
+            </div>
+            {clientCredentials.client_secret ? (
+              <div>
+                client_secret: <code>{clientCredentials.client_secret}</code>
+              </div>
+            ) : null}
+            <div className="small">
+              Секрет показывается только один раз.
+            </div>
+          </div>
+        ) : null}
+        {pageState.admin.oauth_clients.map((client) => (
+          <button
+            key={client.id}
+            type="button"
+            className="btn btn-sm btn-outline-secondary mt-1 me-1"
+            title={client.redirect_uris.join("\n")}
+            onClick={() =>
+              void handleDeleteMutation(`/admin/oauth-client/delete/${client.id}`)
+            }
+          >
+            {client.name} <code>{client.client_id}</code>
+            <span className="badge rounded-pill bg-danger">
+              <i className="bi bi-x"></i>
+            </span>
+          </button>
+        ))}
+      </div>
+
+      <div className="container my-2">
+        <h5>Новый пользователь</h5>
+        <form onSubmit={(event) => void handleNewUserSubmit(event)}>
+          <div className="row">
+            <div className="col-md">
+              <input
+                className={
+                  newUserErrors.email
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                type="email"
+                name="email"
+                placeholder="Электронный адрес"
+                required
+                value={newUserEmail}
+                onChange={(event) => {
+                  setNewUserEmail(event.target.value);
+                  setNewUserErrors((errors) => ({ ...errors, email: "" }));
+                }}
+              />
+              {newUserErrors.email ? (
+                <div className="invalid-feedback d-block">
+                  {newUserErrors.email}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <input
+                className={
+                  newUserErrors.name
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                type="text"
+                name="name"
+                placeholder="Имя"
+                required
+                value={newUserName}
+                onChange={(event) => {
+                  setNewUserName(event.target.value);
+                  setNewUserErrors((errors) => ({ ...errors, name: "" }));
+                }}
+              />
+              {newUserErrors.name ? (
+                <div className="invalid-feedback d-block">
+                  {newUserErrors.name}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <input
+                className={
+                  newUserErrors.password
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                type="password"
+                name="password"
+                placeholder="Пароль"
+                autoComplete="new-password"
+                required
+                value={newUserPassword}
+                onChange={(event) => {
+                  setNewUserPassword(event.target.value);
+                  setNewUserErrors((errors) => ({ ...errors, password: "" }));
+                }}
+              />
+              {newUserErrors.password ? (
+                <div className="invalid-feedback d-block">
+                  {newUserErrors.password}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <DropdownMultiSelect
+                id="new-user-role-id"
+                options={pageState.admin.roles.map(
+                  (role): DropdownMultiSelectOption => ({
+                    value: String(role.id),
+                    label: role.name,
+                  }),
+                )}
+                selectedValues={newUserRoles}
+                onChange={(values) => {
+                  setNewUserRoles(values);
+                  setNewUserErrors((errors) => ({ ...errors, roles: "" }));
+                }}
+                className="my-1"
+                menuHeightClassName="auth-dropdown-multiselect-options-md"
+                searchPlaceholder="Поиск ролей"
+                clearable
+                clearLabel="Очистить выбранные роли"
+              />
+              {newUserErrors.roles ? (
+                <div className="invalid-feedback d-block">
+                  {newUserErrors.roles}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-auto">
+              <button
+                className="btn btn-primary my-1"
+                type="submit"
+                disabled={isSubmittingNewUser}
+              >
+                <i className="bi bi-person-plus"></i>
+              </button>
+            </div>
+          </div>
+          <div className="form-check">
+            <input
+              className="form-check-input"
+              type="checkbox"
+              id="new-user-must-change-password"
+              checked={newUserMustChangePassword}
+              onChange={(event) =>
+                setNewUserMustChangePassword(event.target.checked)
+              }
+            />
+            <label
+              className="form-check-label"
+              htmlFor="new-user-must-change-password"
+            >
+              Сменить пароль при первом входе
+            </label>
+          </div>
+        </form>
+      </div>
+
+      <div className="container my-2">
+        <h5>Приглашения</h5>
+        <form onSubmit={(event) => void handleInviteSubmit(event)}>
+          <div className="row">
+            <div className="col-md">
+              <input
+                className={
+                  inviteErrors.email
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                type="email"
+                name="email"
+                placeholder="Электронный адрес"
+                required
+                value={inviteEmail}
+                onChange={(event) => {
+                  setInviteEmail(event.target.value);
+                  setInviteErrors((errors) => ({ ...errors, email: "" }));
+                }}
+              />
+              {inviteErrors.email ? (
+                <div className="invalid-feedback d-block">
+                  {inviteErrors.email}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <input
+                className={
+                  inviteErrors.name
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                type="text"
+                name="name"
+                placeholder="Имя"
+                required
+                value={inviteName}
+                onChange={(event) => {
+                  setInviteName(event.target.value);
+                  setInviteErrors((errors) => ({ ...errors, name: "" }));
+                }}
+              />
+              {inviteErrors.name ? (
+                <div className="invalid-feedback d-block">
+                  {inviteErrors.name}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <DropdownMultiSelect
+                id="invitation-role-id"
+                options={pageState.admin.roles.map(
+                  (role): DropdownMultiSelectOption => ({
+                    value: String(role.id),
+                    label: role.name,
+                  }),
+                )}
+                selectedValues={inviteRoles}
+                onChange={(values) => {
+                  setInviteRoles(values);
+                  setInviteErrors((errors) => ({ ...errors, roles: "" }));
+                }}
+                className="my-1"
+                menuHeightClassName="auth-dropdown-multiselect-options-md"
+                searchPlaceholder="Поиск ролей"
+                clearable
+                clearLabel="Очистить выбранные роли"
+              />
+              {inviteErrors.roles ? (
+                <div className="invalid-feedback d-block">
+                  {inviteErrors.roles}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-auto">
+              <button
+                className="btn btn-primary my-1"
+                type="submit"
+                disabled={isSubmittingInvite}
+              >
+                <i className="bi bi-envelope"></i>
+              </button>
+            </div>
+          </div>
+        </form>
+        {pageState.invitations.map((invitation) => (
+          <div key={invitation.id} className="btn-group btn-group-sm mt-1 me-1">
+            <span
+              className="btn btn-outline-secondary disabled"
+              title={`Действует до ${invitation.expires_at}`}
+            >
+              {invitation.name} <code>{invitation.email}</code>
+            </span>
+            <button
+              type="button"
+              className="btn btn-outline-secondary"
+              title="Отправить повторно"
+              onClick={() => void handleInvitationResend(invitation.id)}
+            >
+              <i className="bi bi-arrow-repeat"></i>
+            </button>
+            <button
+              type="button"
+              className="btn btn-outline-danger"
+              title="Отменить"
+              onClick={() =>
+                void handleDeleteMutation(
+                  `/admin/invitation/cancel/${invitation.id}`,
+                )
+              }
+            >
+              <i className="bi bi-x"></i>
+            </button>
+          </div>
+        ))}
+      </div>
+
+      <div className="container my-2">
+        <h5>Сервисные аккаунты</h5>
+        <form onSubmit={(event) => void handleServiceAccountSubmit(event)}>
+          <div className="row">
+            <div className="col-md">
+              <input
+                className={
+                  accountErrors.name
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                type="text"
+                name="name"
+                placeholder="Название"
+                required
+                value={accountName}
+                onChange={(event) => {
+                  setAccountName(event.target.value);
+                  setAccountErrors((errors) => ({ ...errors, name: "" }));
+                }}
+              />
+              {accountErrors.name ? (
+                <div className="invalid-feedback d-block">
+                  {accountErrors.name}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <DropdownMultiSelect
+                id="service-account-role-id"
+                options={pageState.admin.roles.map(
+                  (role): DropdownMultiSelectOption => ({
+                    value: String(role.id),
+                    label: role.name,
+                  }),
+                )}
+                selectedValues={accountRoles}
+                onChange={(values) => {
+                  setAccountRoles(values);
+                  setAccountErrors((errors) => ({ ...errors, roles: "" }));
+                }}
+                className="my-1"
+                menuHeightClassName="auth-dropdown-multiselect-options-md"
+                searchPlaceholder="Поиск ролей"
+                clearable
+                clearLabel="Очистить выбранные роли"
+              />
+              {accountErrors.roles ? (
+                <div className="invalid-feedback d-block">
+                  {accountErrors.roles}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-auto">
+              <button
+                className="btn btn-primary my-1"
+                type="submit"
+                disabled={isSubmittingAccount}
+              >
+                <i className="bi bi-plus"></i>
+              </button>
+            </div>
+          </div>
+        </form>
+        {accountCredentials ? (
+          <div className="alert alert-warning mt-2">
+            <div>
+              client_id: <code>{accountCredentials.client_id}</code>
+            </div>
+            <div>
+              client_secret: <code>{accountCredentials.client_secret}</code>
+            </div>
+            <div className="small">
+              Секрет показывается только один раз.
+            </div>
+          </div>
+        ) : null}
+        {pageState.admin.service_accounts.map((account) => (
+          <div key={account.id} className="btn-group btn-group-sm mt-1 me-1">
+            <span className="btn btn-outline-secondary disabled">
+              {account.name} <code>{account.client_id}</code>
+            </span>
+            <button
+              type="button"
+              className="btn btn-outline-secondary"
+              title="Выдать новый секрет"
+              onClick={() => void handleServiceAccountRotate(account.id)}
+            >
+              <i className="bi bi-arrow-repeat"></i>
+            </button>
+            <button
+              type="button"
+              className="btn btn-outline-danger"
+              title="Удалить"
+              onClick={() =>
+                void handleDeleteMutation(
+                  `/admin/service-account/delete/${account.id}`,
+                )
+              }
+            >
+              <i className="bi bi-x"></i>
+            </button>
+          </div>
+        ))}
+      </div>
+
+      {pageState.deleted.users.length > 0 ||
+      pageState.deleted.hubs.length > 0 ? (
+        <div className="container my-2">
+          <h5>Удалённые</h5>
+          {pageState.deleted.hubs.map((hub) => (
+            <div
+              key={`hub-${hub.id}`}
+              className="btn-group btn-group-sm mt-1 me-1"
+            >
+              <span
+                className="btn btn-outline-secondary disabled"
+                title={`Будет удалён навсегда ${hub.purge_at}`}
+              >
+                <i className="bi bi-building"></i> {hub.name}
+              </span>
+              <button
+                type="button"
+                className="btn btn-outline-secondary"
+                title="Восстановить"
+                onClick={() =>
+                  void handleRestore(`/admin/hub/restore/${hub.id}`)
+                }
+              >
+                <i className="bi bi-arrow-counterclockwise"></i>
+              </button>
+            </div>
+          ))}
+          {pageState.deleted.users.map((user) => (
+            <div
+              key={`user-${user.id}`}
+              className="btn-group btn-group-sm mt-1 me-1"
+            >
+              <span
+                className="btn btn-outline-secondary disabled"
+                title={`Будет удалён навсегда ${user.purge_at}`}
+              >
+                <i className="bi bi-person"></i> {user.name ?? ""}{" "}
+                <code>{user.email}</code>
+              </span>
+              <button
+                type="button"
+                className="btn btn-outline-secondary"
+                title="Восстановить"
+                onClick={() =>
+                  void handleRestore(`/admin/user/restore/${user.id}`)
+                }
+              >
+                <i className="bi bi-arrow-counterclockwise"></i>
+              </button>
+            </div>
+          ))}
+        </div>
+      ) : null}
+
+      <div className="container my-2">
+        <h5>Регистрация</h5>
+        <form onSubmit={(event) => void handleRegistrationSettingsSubmit(event)}>
+          <div className="row">
+            <div className="col-md">
+              <select
+                className="form-select my-1"
+                aria-label="Хаб"
+                value={settingsHubId ?? ""}
+                onChange={(event) =>
+                  setSettingsHubId(Number(event.target.value))
+                }
+              >
+                {pageState.admin.hubs.map((hub) => (
+                  <option key={hub.id} value={hub.id}>
+                    {hub.name}
+                  </option>
+                ))}
+              </select>
+              <select
+                className={
+                  registrationErrors.registration_mode
+                    ? "form-select my-1 is-invalid"
+                    : "form-select my-1"
+                }
+                name="registration_mode"
+                value={registrationMode}
+                onChange={(event) =>
+                  setRegistrationMode(
+                    event.target.value as ApiRegistrationMode,
+                  )
+                }
+              >
+                <option value="open">Открытая</option>
+                <option value="invite_only">Только по приглашению</option>
+                <option value="closed">Закрыта</option>
+              </select>
+              {registrationErrors.registration_mode ? (
+                <div className="invalid-feedback d-block">
+                  {registrationErrors.registration_mode}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <textarea
+                className={
+                  registrationErrors.allowed_email_domains
+                    ? "form-control my-1 is-invalid"
+                    : "form-control my-1"
+                }
+                name="allowed_email_domains"
+                rows={2}
+                placeholder="Разрешённые домены, по одному в строке (@company.ru)"
+                value={registrationDomains}
+                onChange={(event) => {
+                  setRegistrationDomains(event.target.value);
+                  setRegistrationErrors((errors) => ({
+                    ...errors,
+                    allowed_email_domains: "",
+                  }));
+                }}
+              />
+              {registrationErrors.allowed_email_domains ? (
+                <div className="invalid-feedback d-block">
+                  {registrationErrors.allowed_email_domains}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-md">
+              <DropdownMultiSelect
+                id="registration-default-role-id"
+                options={pageState.admin.roles.map(
+                  (role): DropdownMultiSelectOption => ({
+                    value: String(role.id),
+                    label: role.name,
+                  }),
+                )}
+                selectedValues={registrationRoles}
+                onChange={(values) => {
+                  setRegistrationRoles(values);
+                  setRegistrationErrors((errors) => ({
+                    ...errors,
+                    roles: "",
+                  }));
+                }}
+                className="my-1"
+                menuHeightClassName="auth-dropdown-multiselect-options-md"
+                searchPlaceholder="Роли новых пользователей"
+                clearable
+                clearLabel="Очистить выбранные роли"
+              />
+              {registrationErrors.roles ? (
+                <div className="invalid-feedback d-block">
+                  {registrationErrors.roles}
+                </div>
+              ) : null}
+            </div>
+            <div className="col-auto">
+              <button
+                className="btn btn-primary my-1"
+                type="submit"
+                disabled={isSubmittingRegistration}
+              >
+                <i className="bi bi-check"></i>
+              </button>
+            </div>
+          </div>
+        </form>
+      </div>
+
       {pageState.users.length > 0 ? (
         <>
           <div className="container mb-1">
@@ -785,6 +1923,24 @@ export function MainAdminPage() {
                               placeholder="Электронный адрес"
                               required
                             />
+                            {modalForm.emailVerified ? (
+                              <span className="badge text-bg-success">
+                                Подтверждён
+                              </span>
+                            ) : (
+                              <>
+                                <span className="badge text-bg-warning me-2">
+                                  Не подтверждён
+                                </span>
+                                <button
+                                  className="btn btn-sm btn-outline-secondary"
+                                  type="button"
+                                  onClick={() => void handleModalVerifyEmail()}
+                                >
+                                  Подтвердить вручную
+                                </button>
+                              </>
+                            )}
                           </div>
                         </div>
                         <div className="row mb-3">
@@ -898,6 +2054,122 @@ export function MainAdminPage() {
                             ) : null}
                           </div>
                         </div>
+                        <div className="row mb-3">
+                          <div className="col-md-10 offset-md-2">
+                            <div className="form-check">
+                              <input
+                                className="form-check-input"
+                                type="checkbox"
+                                id="user-assign-form-reset-two-factor"
+                                checked={modalForm.resetTwoFactor}
+                                onChange={(event) => {
+                                  const resetTwoFactor = event.target.checked;
+                                  setModalForm((current) =>
+                                    current
+                                      ? { ...current, resetTwoFactor }
+                                      : current,
+                                  );
+                                }}
+                              />
+                              <label
+                                className="form-check-label"
+                                htmlFor="user-assign-form-reset-two-factor"
+                              >
+                                Сбросить двухфакторную аутентификацию
+                              </label>
+                            </div>
+                            <div className="form-check">
+                              <input
+                                className="form-check-input"
+                                type="checkbox"
+                                id="user-assign-form-must-change-password"
+                                checked={modalForm.mustChangePassword}
+                                onChange={(event) => {
+                                  const mustChangePassword =
+                                    event.target.checked;
+                                  setModalForm((current) =>
+                                    current
+                                      ? { ...current, mustChangePassword }
+                                      : current,
+                                  );
+                                }}
+                              />
+                              <label
+                                className="form-check-label"
+                                htmlFor="user-assign-form-must-change-password"
+                              >
+                                Сменить пароль при следующем входе
+                              </label>
+                            </div>
+                          </div>
+                        </div>
+                        <div className="row mb-3">
+                          <label
+                            htmlFor="modalUserDisabledReason"
+                            className="col-md-2 col-form-label"
+                          >
+                            Статус
+                          </label>
+                          <div className="col-md-10">
+                            {modalForm.disabledAt ? (
+                              <>
+                                <span className="badge text-bg-danger me-2">
+                                  Заблокирован
+                                </span>
+                                {modalForm.disabledReason ? (
+                                  <span className="me-2">
+                                    {modalForm.disabledReason}
+                                  </span>
+                                ) : null}
+                                <button
+                                  className="btn btn-sm btn-outline-secondary"
+                                  type="button"
+                                  onClick={() => void handleModalReactivate()}
+                                >
+                                  Разблокировать
+                                </button>
+                              </>
+                            ) : (
+                              <div className="input-group">
+                                <input
+                                  type="text"
+                                  className={
+                                    modalFieldErrors.reason
+                                      ? "form-control is-invalid"
+                                      : "form-control"
+                                  }
+                                  id="modalUserDisabledReason"
+                                  placeholder="Причина блокировки"
+                                  value={modalForm.disabledReason}
+                                  onChange={(event) => {
+                                    const disabledReason = event.target.value;
+                                    setModalForm((current) =>
+                                      current
+                                        ? { ...current, disabledReason }
+                                        : current,
+                                    );
+                                    setModalFieldErrors((errors) => ({
+                                      ...errors,
+                                      reason: "",
+                                    }));
+                                  }}
+                                />
+                                <button
+                                  className="btn btn-outline-danger"
+                                  type="button"
+                                  onClick={() => void handleModalSuspend()}
+                                >
+                                  Заблокировать
+                                </button>
+                                {modalFieldErrors.reason ? (
+                                  <div className="invalid-feedback">
+                                    {modalFieldErrors.reason}
+                                  </div>
+                                ) : null}
+                              </div>
+                            )}
+                          </div>
+                        </div>
                         <div className="row mb-3">
                           <div className="col">
                             <button
@@ -5,14 +5,23 @@ import { AuthShell } from "../components/AuthShell";
 import { AuthShellFatalState } from "../components/AuthShellFatalState";
 import {
   fetchHubMenuItems,
+  fetchJson,
   fetchShellData,
   isApiMutationError,
   isRedirectResponseError,
+  postEmpty,
   postForm,
+  postFormJson,
   toFieldErrorMap,
   type ApiMutationError,
+  type ApiPasskey,
+  type ApiPersonalToken,
+  type ApiPersonalTokenCreated,
+  type ApiSession,
+  type ApiTokenScope,
 } from "../lib/api";
 import type { ShellData, UserMenuItem } from "../lib/models";
+import { isPasskeySupported, registerPasskey } from "../lib/webauthn";
 import { useServiceShell } from "@pushkind/frontend-shell/useServiceShell";
 
 function toMutationError(error: unknown): ApiMutationError {
@@ -26,6 +35,446 @@ function toMutationError(error: unknown): ApiMutationError {
   };
 }
 
+function formatDate(value: string | null): string {
+  return value ? new Date(`${value}Z`).toLocaleString("ru-RU") : "—";
+}
+
+function PasskeysSection() {
+  const [passkeys, setPasskeys] = useState<ApiPasskey[]>([]);
+  const [passkeyName, setPasskeyName] = useState("");
+  const [isRegistering, setIsRegistering] = useState(false);
+
+  async function reloadPasskeys() {
+    try {
+      setPasskeys(await fetchJson<ApiPasskey[]>("/api/v1/passkeys"));
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        window.showFlashMessage?.(
+          "Не удалось загрузить ключи доступа.",
+          "danger",
+        );
+      }
+    }
+  }
+
+  useEffect(() => {
+    void reloadPasskeys();
+  }, []);
+
+  async function handleRegister(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    setIsRegistering(true);
+
+    try {
+      const result = await registerPasskey(passkeyName);
+      setPasskeyName("");
+      window.showFlashMessage?.(result.message, "success");
+      await reloadPasskeys();
+    } catch (error) {
+      if (isRedirectResponseError(error)) {
+        return;
+      }
+
+      window.showFlashMessage?.(
+        isApiMutationError(error)
+          ? error.message
+          : "Не удалось добавить ключ доступа.",
+        "danger",
+      );
+    } finally {
+      setIsRegistering(false);
+    }
+  }
+
+  async function handleDelete(passkey: ApiPasskey) {
+    if (!window.confirm(`Удалить ключ доступа «${passkey.name}»?`)) {
+      return;
+    }
+
+    try {
+      const result = await postEmpty(`/user/passkeys/delete/${passkey.id}`);
+      window.showFlashMessage?.(result.message, "success");
+      await reloadPasskeys();
+    } catch (error) {
+      if (isRedirectResponseError(error)) {
+        return;
+      }
+
+      window.showFlashMessage?.(toMutationError(error).message, "danger");
+    }
+  }
+
+  return (
+    <>
+      <h5 className="mt-4">Ключи доступа</h5>
+      {passkeys.length > 0 ? (
+        <table className="table table-sm align-middle">
+          <thead>
+            <tr>
+              <th>Название</th>
+              <th>Добавлен</th>
+              <th>Последний вход</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {passkeys.map((passkey) => (
+              <tr key={passkey.id}>
+                <td>{passkey.name}</td>
+                <td>{formatDate(passkey.created_at)}</td>
+                <td>{formatDate(passkey.last_used_at)}</td>
+                <td className="text-end">
+                  <button
+                    type="button"
+                    className="btn btn-sm btn-outline-danger"
+                    onClick={() => void handleDelete(passkey)}
+                  >
+                    Удалить
+                  </button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      ) : (
+        <p className="text-muted">Ключи доступа не добавлены.</p>
+      )}
+      {isPasskeySupported() ? (
+        <form onSubmit={(event) => void handleRegister(event)}>
+          <div className="mb-3 row">
+            <label htmlFor="passkey-name" className="col-sm-2 col-form-label">
+              Название
+            </label>
+            <div className="col-sm-6">
+              <input
+                type="text"
+                className="form-control"
+                id="passkey-name"
+                name="name"
+                maxLength={100}
+                placeholder="Например, рабочий ноутбук"
+                required
+                value={passkeyName}
+                onChange={(event) => setPasskeyName(event.target.value)}
+              />
+            </div>
+            <div className="col-sm-4">
+              <button
+                type="submit"
+                className="btn btn-outline-primary"
+                disabled={isRegistering}
+              >
+                Добавить ключ доступа
+              </button>
+            </div>
+          </div>
+        </form>
+      ) : null}
+    </>
+  );
+}
+
+function SessionsSection() {
+  const [sessions, setSessions] = useState<ApiSession[]>([]);
+  const [isRevokingAll, setIsRevokingAll] = useState(false);
+
+  async function reloadSessions() {
+    try {
+      setSessions(await fetchJson<ApiSession[]>("/api/v1/sessions"));
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        window.showFlashMessage?.("Не удалось загрузить сессии.", "danger");
+      }
+    }
+  }
+
+  useEffect(() => {
+    void reloadSessions();
+  }, []);
+
+  async function handleRevoke(session: ApiSession) {
+    try {
+      const result = await postEmpty(`/user/sessions/revoke/${session.id}`);
+      window.showFlashMessage?.(result.message, "success");
+      await reloadSessions();
+    } catch (error) {
+      if (isRedirectResponseError(error)) {
+        return;
+      }
+
+      window.showFlashMessage?.(toMutationError(error).message, "danger");
+    }
+  }
+
+  async function handleRevokeAll() {
+    if (!window.confirm("Выйти на всех устройствах, включая это?")) {
+      return;
+    }
+    setIsRevokingAll(true);
+
+    try {
+      const result = await postEmpty("/user/sessions/revoke-all");
+      window.location.assign(result.redirect_to ?? "/auth/signin");
+    } catch (error) {
+      if (isRedirectResponseError(error)) {
+        return;
+      }
+
+      window.showFlashMessage?.(toMutationError(error).message, "danger");
+      setIsRevokingAll(false);
+    }
+  }
+
+  return (
+    <>
+      <h5 className="mt-4">Активные сессии</h5>
+      <table className="table table-sm align-middle">
+        <thead>
+          <tr>
+            <th>Устройство</th>
+            <th>IP-адрес</th>
+            <th>Вход</th>
+            <th>Последняя активность</th>
+            <th></th>
+          </tr>
+        </thead>
+        <tbody>
+          {sessions.map((session) => (
+            <tr key={session.id}>
+              <td>{session.user_agent ?? "Неизвестное устройство"}</td>
+              <td>{session.ip_address ?? "—"}</td>
+              <td>{formatDate(session.created_at)}</td>
+              <td>{formatDate(session.last_seen_at)}</td>
+              <td className="text-end">
+                {session.current ? (
+                  <span className="badge text-bg-secondary">Текущая</span>
+                ) : (
+                  <button
+                    type="button"
+                    className="btn btn-sm btn-outline-danger"
+                    onClick={() => void handleRevoke(session)}
+                  >
+                    Завершить
+                  </button>
+                )}
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      <button
+        type="button"
+        className="btn btn-outline-danger"
+        disabled={isRevokingAll}
+        onClick={() => void handleRevokeAll()}
+      >
+        Выйти на всех устройствах
+      </button>
+    </>
+  );
+}
+
+const TOKEN_SCOPES: { value: ApiTokenScope; label: string }[] = [
+  { value: "profile", label: "Профиль" },
+  { value: "users", label: "Пользователи" },
+  { value: "admin", label: "Администрирование" },
+];
+
+function TokensSection() {
+  const [tokens, setTokens] = useState<ApiPersonalToken[]>([]);
+  const [tokenName, setTokenName] = useState("");
+  const [tokenScopes, setTokenScopes] = useState<ApiTokenScope[]>(["profile"]);
+  const [expiresInDays, setExpiresInDays] = useState("30");
+  const [tokenErrors, setTokenErrors] = useState<Record<string, string>>({});
+  const [createdToken, setCreatedToken] =
+    useState<ApiPersonalTokenCreated | null>(null);
+  const [isCreating, setIsCreating] = useState(false);
+
+  async function reloadTokens() {
+    try {
+      setTokens(await fetchJson<ApiPersonalToken[]>("/api/v1/tokens"));
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        window.showFlashMessage?.("Не удалось загрузить токены.", "danger");
+      }
+    }
+  }
+
+  useEffect(() => {
+    void reloadTokens();
+  }, []);
+
+  function toggleScope(scope: ApiTokenScope, checked: boolean) {
+    setTokenScopes((scopes) =>
+      checked ? [...scopes, scope] : scopes.filter((value) => value !== scope),
+    );
+    setTokenErrors((errors) => ({ ...errors, scopes: "" }));
+  }
+
+  async function handleCreate(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    setIsCreating(true);
+
+    const body = new URLSearchParams();
+    body.set("name", tokenName);
+    body.set("expires_in_days", expiresInDays);
+    for (const scope of tokenScopes) {
+      body.append("scopes", scope);
+    }
+
+    try {
+      const created = await postFormJson<ApiPersonalTokenCreated>(
+        "/user/tokens/add",
+        body,
+      );
+      setTokenErrors({});
+      setTokenName("");
+      setCreatedToken(created);
+      window.showFlashMessage?.(created.message, "success");
+      await reloadTokens();
+    } catch (error) {
+      if (!isRedirectResponseError(error)) {
+        const mutationError = toMutationError(error);
+        setTokenErrors(toFieldErrorMap(mutationError));
+        window.showFlashMessage?.(mutationError.message, "danger");
+      }
+    }
+
+    setIsCreating(false);
+  }
+
+  async function handleRevoke(token: ApiPersonalToken) {
+    if (!window.confirm(`Отозвать токен «${token.name}»?`)) {
+      return;
+    }
+
+    try {
+      const result = await postEmpty(`/user/tokens/revoke/${token.id}`);
+      window.showFlashMessage?.(result.message, "success");
+      await reloadTokens();
+    } catch (error) {
+      if (isRedirectResponseError(error)) {
+        return;
+      }
+
+      window.showFlashMessage?.(toMutationError(error).message, "danger");
+    }
+  }
+
+  return (
+    <>
+      <h5 className="mt-4">Токены доступа</h5>
+      <form className="mb-2" onSubmit={(event) => void handleCreate(event)}>
+        <div className="row g-2 align-items-start">
+          <div className="col-md">
+            <input
+              className={
+                tokenErrors.name ? "form-control is-invalid" : "form-control"
+              }
+              type="text"
+              placeholder="Название токена"
+              required
+              value={tokenName}
+              onChange={(event) => {
+                setTokenName(event.target.value);
+                setTokenErrors((errors) => ({ ...errors, name: "" }));
+              }}
+            />
+            {tokenErrors.name ? (
+              <div className="invalid-feedback">{tokenErrors.name}</div>
+            ) : null}
+          </div>
+          <div className="col-md-auto">
+            <select
+              className={
+                tokenErrors.expires_in_days
+                  ? "form-select is-invalid"
+                  : "form-select"
+              }
+              value={expiresInDays}
+              onChange={(event) => setExpiresInDays(event.target.value)}
+            >
+              <option value="7">7 дней</option>
+              <option value="30">30 дней</option>
+              <option value="90">90 дней</option>
+              <option value="365">365 дней</option>
+            </select>
+          </div>
+          <div className="col-auto">
+            <button
+              className="btn btn-primary"
+              type="submit"
+              disabled={isCreating}
+            >
+              Создать
+            </button>
+          </div>
+        </div>
+        <div className="mt-1">
+          {TOKEN_SCOPES.map((scope) => (
+            <div className="form-check form-check-inline" key={scope.value}>
+              <input
+                className="form-check-input"
+                type="checkbox"
+                id={`token-scope-${scope.value}`}
+                checked={tokenScopes.includes(scope.value)}
+                onChange={(event) =>
+                  toggleScope(scope.value, event.target.checked)
+                }
+              />
+              <label
+                className="form-check-label"
+                htmlFor={`token-scope-${scope.value}`}
+              >
+                {scope.label}
+              </label>
+            </div>
+          ))}
+          {tokenErrors.scopes ? (
+            <div className="invalid-feedback d-block">{tokenErrors.scopes}</div>
+          ) : null}
+        </div>
+      </form>
+      {createdToken ? (
+        <div className="alert alert-warning">
+          <code>{createdToken.token}</code>
+          <div className="small">Токен показывается только один раз.</div>
+        </div>
+      ) : null}
+      <table className="table table-sm align-middle">
+        <thead>
+          <tr>
+            <th>Название</th>
+            <th>Области</th>
+            <th>Последнее использование</th>
+            <th>Истекает</th>
+            <th></th>
+          </tr>
+        </thead>
+        <tbody>
+          {tokens.map((token) => (
+            <tr key={token.id}>
+              <td>{token.name}</td>
+              <td>{token.scopes.join(", ")}</td>
+              <td>{formatDate(token.last_used_at)}</td>
+              <td>{formatDate(token.expires_at)}</td>
+              <td className="text-end">
+                <button
+                  type="button"
+                  className="btn btn-sm btn-outline-danger"
+                  onClick={() => void handleRevoke(token)}
+                >
+                  Отозвать
+                </button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </>
+  );
+}
+
 export function MainBasicPage() {
   const shellState = useServiceShell<ShellData, UserMenuItem>({
     errorMessage: "Не удалось загрузить оболочку Auth.",
@@ -38,6 +487,8 @@ export function MainBasicPage() {
   const [password, setPassword] = useState("");
   const [fieldErrors, setFieldErrors] = useState<Record<string, string>>({});
   const [isSubmitting, setIsSubmitting] = useState(false);
+  const [disableCode, setDisableCode] = useState("");
+  const [isDisabling, setIsDisabling] = useState(false);
 
   useEffect(() => {
     if (shellState.status === "ready") {
@@ -81,6 +532,28 @@ export function MainBasicPage() {
     }
   }
 
+  async function handleDisableTwoFactor(event: FormEvent<HTMLFormElement>) {
+    event.preventDefault();
+    setIsDisabling(true);
+
+    const body = new URLSearchParams();
+    body.set("code", disableCode);
+
+    try {
+      const result = await postForm("/user/2fa/disable", body);
+      setDisableCode("");
+      window.showFlashMessage?.(result.message, "success");
+    } catch (error) {
+      if (isRedirectResponseError(error)) {
+        return;
+      }
+
+      window.showFlashMessage?.(toMutationError(error).message, "danger");
+    } finally {
+      setIsDisabling(false);
+    }
+  }
+
   return (
     <AuthShell
       navigation={shellState.shell.navigation}
@@ -192,6 +665,47 @@ export function MainBasicPage() {
                 Изменить
               </button>
             </form>
+            <h5 className="mt-4">Двухфакторная аутентификация</h5>
+            <p>
+              <a href="/auth/2fa?mode=setup&next=%2F">
+                Настроить приложение-аутентификатор
+              </a>
+            </p>
+            <form onSubmit={(event) => void handleDisableTwoFactor(event)}>
+              <div className="mb-3 row">
+                <label
+                  htmlFor="disable-2fa-code"
+                  className="col-sm-2 col-form-label"
+                >
+                  Код
+                </label>
+                <div className="col-sm-6">
+                  <input
+                    type="text"
+                    className="form-control"
+                    id="disable-2fa-code"
+                    name="code"
+                    autoComplete="one-time-code"
+                    placeholder="Код из приложения или код восстановления"
+                    required
+                    value={disableCode}
+                    onChange={(event) => setDisableCode(event.target.value)}
+                  />
+                </div>
+                <div className="col-sm-4">
+                  <button
+                    type="submit"
+                    className="btn btn-outline-danger"
+                    disabled={isDisabling}
+                  >
+                    Отключить
+                  </button>
+                </div>
+              </div>
+            </form>
+            <PasskeysSection />
+            <SessionsSection />
+            <TokensSection />
           </div>
         </div>
       </div>
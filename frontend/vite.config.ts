@@ -33,6 +33,10 @@ export default defineConfig({
       input: {
         "auth/signin.html": resolve(__dirname, "auth/signin.html"),
         "auth/signup.html": resolve(__dirname, "auth/signup.html"),
+        "auth/reset.html": resolve(__dirname, "auth/reset.html"),
+        "auth/invite.html": resolve(__dirname, "auth/invite.html"),
+        "auth/2fa.html": resolve(__dirname, "auth/2fa.html"),
+        "auth/password.html": resolve(__dirname, "auth/password.html"),
         "app/index-admin.html": resolve(__dirname, "app/index-admin.html"),
         "app/index-basic.html": resolve(__dirname, "app/index-basic.html"),
         "src/entries/auth-signin.tsx": resolve(
@@ -43,6 +47,22 @@ export default defineConfig({
           __dirname,
           "src/entries/auth-signup.tsx",
         ),
+        "src/entries/auth-reset.tsx": resolve(
+          __dirname,
+          "src/entries/auth-reset.tsx",
+        ),
+        "src/entries/auth-invite.tsx": resolve(
+          __dirname,
+          "src/entries/auth-invite.tsx",
+        ),
+        "src/entries/auth-2fa.tsx": resolve(
+          __dirname,
+          "src/entries/auth-2fa.tsx",
+        ),
+        "src/entries/auth-password.tsx": resolve(
+          __dirname,
+          "src/entries/auth-password.tsx",
+        ),
         "src/entries/main-admin.tsx": resolve(
           __dirname,
           "src/entries/main-admin.tsx",
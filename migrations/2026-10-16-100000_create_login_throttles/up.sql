@@ -0,0 +1,8 @@
+-- Persistent failed-login counters keyed by account or client IP
+CREATE TABLE login_throttles (
+    throttle_key VARCHAR NOT NULL PRIMARY KEY,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    window_started_at TIMESTAMP NOT NULL,
+    lockout_count INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMP
+);
@@ -0,0 +1,24 @@
+-- TOTP second factor per user; enabled_at stays NULL until enrollment is verified
+CREATE TABLE user_totp (
+    user_id INTEGER NOT NULL PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret VARCHAR NOT NULL,
+    enabled_at TIMESTAMP,
+    last_used_step BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- One-time recovery codes, stored hashed
+CREATE TABLE user_recovery_codes (
+    id INTEGER NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR NOT NULL,
+    used_at TIMESTAMP
+);
+
+CREATE INDEX idx_user_recovery_codes_user_id ON user_recovery_codes(user_id);
+
+-- Per-hub security policy; a missing row means defaults
+CREATE TABLE hub_policies (
+    hub_id INTEGER NOT NULL PRIMARY KEY REFERENCES hubs(id) ON DELETE CASCADE,
+    require_admin_2fa BOOLEAN NOT NULL DEFAULT 0
+);
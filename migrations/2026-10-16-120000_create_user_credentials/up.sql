@@ -0,0 +1,13 @@
+-- WebAuthn credentials (passkeys) registered by users
+CREATE TABLE user_credentials (
+    id INTEGER NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    credential_id VARCHAR NOT NULL UNIQUE,
+    name VARCHAR NOT NULL,
+    passkey TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMP
+);
+
+CREATE INDEX idx_user_credentials_user_id ON user_credentials(user_id);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_sessions_hub_id_expires_at;
+DROP INDEX IF EXISTS idx_sessions_user_id;
+DROP TABLE IF EXISTS sessions;
@@ -0,0 +1,13 @@
+-- Issued sessions and the JWT key that signed them
+CREATE TABLE sessions (
+    id INTEGER NOT NULL PRIMARY KEY,
+    jti VARCHAR NOT NULL UNIQUE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    key_id VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_sessions_user_id ON sessions(user_id);
+CREATE INDEX idx_sessions_hub_id_expires_at ON sessions(hub_id, expires_at);
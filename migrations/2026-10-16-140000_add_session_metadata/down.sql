@@ -0,0 +1,4 @@
+ALTER TABLE sessions DROP COLUMN revoked_at;
+ALTER TABLE sessions DROP COLUMN last_seen_at;
+ALTER TABLE sessions DROP COLUMN ip_address;
+ALTER TABLE sessions DROP COLUMN user_agent;
@@ -0,0 +1,5 @@
+-- Client details, activity and revocation of issued sessions
+ALTER TABLE sessions ADD COLUMN user_agent VARCHAR;
+ALTER TABLE sessions ADD COLUMN ip_address VARCHAR;
+ALTER TABLE sessions ADD COLUMN last_seen_at TIMESTAMP;
+ALTER TABLE sessions ADD COLUMN revoked_at TIMESTAMP;
@@ -0,0 +1,41 @@
+-- OpenID Connect clients; public clients have no secret and rely on PKCE
+CREATE TABLE oauth_clients (
+    id INTEGER NOT NULL PRIMARY KEY,
+    client_id VARCHAR NOT NULL UNIQUE,
+    client_secret_hash VARCHAR,
+    name VARCHAR NOT NULL,
+    -- Allowed redirect URIs, one per line
+    redirect_uris TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Single-use authorization codes, stored hashed
+CREATE TABLE oauth_authorization_codes (
+    id INTEGER NOT NULL PRIMARY KEY,
+    code_hash VARCHAR NOT NULL UNIQUE,
+    client_id INTEGER NOT NULL REFERENCES oauth_clients(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    redirect_uri VARCHAR NOT NULL,
+    scope VARCHAR NOT NULL,
+    nonce VARCHAR,
+    code_challenge VARCHAR NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Opaque access tokens, stored hashed
+CREATE TABLE oauth_access_tokens (
+    id INTEGER NOT NULL PRIMARY KEY,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    client_id INTEGER NOT NULL REFERENCES oauth_clients(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    scope VARCHAR NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_oauth_access_tokens_user_id ON oauth_access_tokens(user_id);
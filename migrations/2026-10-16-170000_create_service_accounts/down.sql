@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_service_account_tokens_service_account_id;
+DROP TABLE IF EXISTS service_account_tokens;
+DROP TABLE IF EXISTS service_account_roles;
+DROP TABLE IF EXISTS service_accounts;
@@ -0,0 +1,31 @@
+-- Non-human principals of a hub; they authenticate with client credentials
+CREATE TABLE service_accounts (
+    id INTEGER NOT NULL PRIMARY KEY,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    client_id VARCHAR NOT NULL UNIQUE,
+    secret_hash VARCHAR NOT NULL,
+    secret_rotated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(hub_id, name)
+);
+
+CREATE TABLE service_account_roles (
+    service_account_id INTEGER NOT NULL REFERENCES service_accounts(id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    PRIMARY KEY (service_account_id, role_id)
+);
+
+-- Access tokens issued by the client credentials grant, stored hashed
+CREATE TABLE service_account_tokens (
+    id INTEGER NOT NULL PRIMARY KEY,
+    service_account_id INTEGER NOT NULL REFERENCES service_accounts(id) ON DELETE CASCADE,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_service_account_tokens_service_account_id
+    ON service_account_tokens(service_account_id);
@@ -0,0 +1,19 @@
+-- Email address verification of registered users
+ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMP;
+-- Accounts created before verification existed keep signing in
+UPDATE users SET email_verified_at = created_at;
+
+ALTER TABLE hub_policies ADD COLUMN require_email_verification BOOLEAN NOT NULL DEFAULT 0;
+
+-- Single-use email verification tokens
+CREATE TABLE email_verifications (
+    id INTEGER NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_email_verifications_user_id ON email_verifications(user_id);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS hub_default_roles;
+DROP TABLE IF EXISTS hub_registration_settings;
@@ -0,0 +1,14 @@
+-- Per-hub self-registration rules; a missing row means open registration
+CREATE TABLE hub_registration_settings (
+    hub_id INTEGER NOT NULL PRIMARY KEY REFERENCES hubs(id) ON DELETE CASCADE,
+    registration_mode VARCHAR NOT NULL DEFAULT 'open',
+    -- Allowed email domains, one per line; empty allows any domain
+    allowed_email_domains VARCHAR NOT NULL DEFAULT ''
+);
+
+-- Roles assigned to users who register in a hub
+CREATE TABLE hub_default_roles (
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    PRIMARY KEY (hub_id, role_id)
+);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS invitation_roles;
+DROP INDEX IF EXISTS idx_invitations_pending_email;
+DROP TABLE IF EXISTS invitations;
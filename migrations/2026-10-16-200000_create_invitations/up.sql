@@ -0,0 +1,23 @@
+-- Pending invitations created by administrators; only the token hash is stored
+CREATE TABLE invitations (
+    id INTEGER NOT NULL PRIMARY KEY,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id) ON DELETE CASCADE,
+    email VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    invited_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    expires_at TIMESTAMP NOT NULL,
+    accepted_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- At most one pending invitation per address and hub
+CREATE UNIQUE INDEX idx_invitations_pending_email
+    ON invitations (hub_id, email) WHERE accepted_at IS NULL;
+
+-- Roles assigned to the user created from an invitation
+CREATE TABLE invitation_roles (
+    invitation_id INTEGER NOT NULL REFERENCES invitations(id) ON DELETE CASCADE,
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    PRIMARY KEY (invitation_id, role_id)
+);
@@ -0,0 +1,3 @@
+-- Suspension of user accounts without deleting them
+ALTER TABLE users ADD COLUMN disabled_at TIMESTAMP;
+ALTER TABLE users ADD COLUMN disabled_reason VARCHAR;
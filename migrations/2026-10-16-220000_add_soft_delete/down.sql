@@ -0,0 +1,72 @@
+-- Soft-deleted rows cannot coexist with the restored UNIQUE constraints and
+-- would come back as live rows, so the rollback refuses to run while any
+-- exist. Restore them or let the purge job remove them first; the CHECK below
+-- fails before anything is changed.
+DROP TABLE IF EXISTS temp.soft_delete_rollback_guard;
+CREATE TEMP TABLE soft_delete_rollback_guard (
+    soft_deleted_rows INTEGER NOT NULL CHECK (soft_deleted_rows = 0)
+);
+INSERT INTO soft_delete_rollback_guard (soft_deleted_rows)
+SELECT (SELECT COUNT(*) FROM hubs WHERE deleted_at IS NOT NULL)
+     + (SELECT COUNT(*) FROM users WHERE deleted_at IS NOT NULL);
+DROP TABLE soft_delete_rollback_guard;
+
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE hubs_new (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO hubs_new (id, name, created_at, updated_at)
+SELECT id, name, created_at, updated_at FROM hubs;
+
+DROP TABLE hubs;
+ALTER TABLE hubs_new RENAME TO hubs;
+
+CREATE TABLE users_new (
+    id INTEGER NOT NULL PRIMARY KEY,
+    email VARCHAR NOT NULL,
+    name VARCHAR,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id),
+    password_hash VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    email_verified_at TIMESTAMP,
+    disabled_at TIMESTAMP,
+    disabled_reason VARCHAR,
+    UNIQUE(email, hub_id)
+);
+
+INSERT INTO users_new (
+    id, email, name, hub_id, password_hash, created_at, updated_at,
+    email_verified_at, disabled_at, disabled_reason
+)
+SELECT
+    id, email, name, hub_id, password_hash, created_at, updated_at,
+    email_verified_at, disabled_at, disabled_reason
+FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
+
+CREATE INDEX idx_users_hub_id_id ON users(hub_id, id);
+
+CREATE TRIGGER users_ai AFTER INSERT ON users BEGIN
+  INSERT INTO user_fts(rowid, name, email) VALUES (new.id, new.name, new.email);
+END;
+CREATE TRIGGER users_ad AFTER DELETE ON users BEGIN
+  INSERT INTO user_fts(user_fts, rowid, name, email) VALUES('delete', old.id, old.name, old.email);
+END;
+CREATE TRIGGER users_au AFTER UPDATE ON users BEGIN
+  INSERT INTO user_fts(user_fts, rowid, name, email) VALUES('delete', old.id, old.name, old.email);
+  INSERT INTO user_fts(rowid, name, email) VALUES (new.id, new.name, new.email);
+END;
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,69 @@
+-- Soft deletion of hubs and users. SQLite cannot drop the inline UNIQUE
+-- constraints, so both tables are rebuilt and uniqueness is enforced by
+-- partial indexes over live rows only.
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE hubs_new (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TIMESTAMP
+);
+
+INSERT INTO hubs_new (id, name, created_at, updated_at)
+SELECT id, name, created_at, updated_at FROM hubs;
+
+DROP TABLE hubs;
+ALTER TABLE hubs_new RENAME TO hubs;
+
+CREATE UNIQUE INDEX idx_hubs_live_name ON hubs(name) WHERE deleted_at IS NULL;
+
+CREATE TABLE users_new (
+    id INTEGER NOT NULL PRIMARY KEY,
+    email VARCHAR NOT NULL,
+    name VARCHAR,
+    hub_id INTEGER NOT NULL REFERENCES hubs(id),
+    password_hash VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    email_verified_at TIMESTAMP,
+    disabled_at TIMESTAMP,
+    disabled_reason VARCHAR,
+    deleted_at TIMESTAMP
+);
+
+INSERT INTO users_new (
+    id, email, name, hub_id, password_hash, created_at, updated_at,
+    email_verified_at, disabled_at, disabled_reason
+)
+SELECT
+    id, email, name, hub_id, password_hash, created_at, updated_at,
+    email_verified_at, disabled_at, disabled_reason
+FROM users;
+
+-- Row ids are preserved, so the search index stays valid while the triggers
+-- are missing.
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
+
+CREATE UNIQUE INDEX idx_users_live_email ON users(email, hub_id) WHERE deleted_at IS NULL;
+CREATE INDEX idx_users_hub_id_id ON users(hub_id, id);
+CREATE INDEX idx_users_deleted_at ON users(deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE TRIGGER users_ai AFTER INSERT ON users BEGIN
+  INSERT INTO user_fts(rowid, name, email) VALUES (new.id, new.name, new.email);
+END;
+CREATE TRIGGER users_ad AFTER DELETE ON users BEGIN
+  INSERT INTO user_fts(user_fts, rowid, name, email) VALUES('delete', old.id, old.name, old.email);
+END;
+CREATE TRIGGER users_au AFTER UPDATE ON users BEGIN
+  INSERT INTO user_fts(user_fts, rowid, name, email) VALUES('delete', old.id, old.name, old.email);
+  INSERT INTO user_fts(rowid, name, email) VALUES (new.id, new.name, new.email);
+END;
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,2 @@
+-- Users created or reset by an admin can be made to choose their own password
+ALTER TABLE users ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS audit_events_no_delete;
+DROP TRIGGER IF EXISTS audit_events_no_update;
+DROP INDEX IF EXISTS idx_audit_events_hub_event_type;
+DROP INDEX IF EXISTS idx_audit_events_hub_created_at;
+DROP TABLE IF EXISTS audit_events;
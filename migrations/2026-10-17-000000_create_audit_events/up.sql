@@ -0,0 +1,28 @@
+-- Append-only record of security-relevant events. Actors, hubs and targets
+-- are plain ids so the history outlives purged users and hubs.
+CREATE TABLE audit_events (
+    id INTEGER NOT NULL PRIMARY KEY,
+    hub_id INTEGER NOT NULL,
+    actor_id INTEGER,
+    event_type VARCHAR NOT NULL,
+    target_id INTEGER,
+    detail TEXT,
+    ip_address TEXT,
+    user_agent TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_audit_events_hub_created_at ON audit_events (hub_id, created_at);
+CREATE INDEX idx_audit_events_hub_event_type ON audit_events (hub_id, event_type);
+
+CREATE TRIGGER audit_events_no_update
+BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
+
+CREATE TRIGGER audit_events_no_delete
+BEFORE DELETE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
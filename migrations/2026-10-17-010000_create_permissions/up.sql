@@ -0,0 +1,23 @@
+-- Fine-grained permissions granted to users through their roles
+CREATE TABLE permissions (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT ''
+);
+
+INSERT INTO permissions (name, description) VALUES
+    ('users.read', 'View users of the hub'),
+    ('users.write', 'Create, change, suspend and delete users of the hub'),
+    ('roles.manage', 'Create and delete roles'),
+    ('menu.manage', 'Change the menu of the hub'),
+    ('hubs.manage', 'Create, configure and delete hubs');
+
+CREATE TABLE role_permissions (
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    permission_id INTEGER NOT NULL REFERENCES permissions(id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, permission_id)
+);
+
+-- The base admin role keeps every permission it had implicitly
+INSERT INTO role_permissions (role_id, permission_id)
+SELECT roles.id, permissions.id FROM roles, permissions WHERE roles.name = 'admin';
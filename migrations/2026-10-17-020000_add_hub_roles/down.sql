@@ -0,0 +1,26 @@
+-- Hub roles cannot coexist with the restored global UNIQUE constraint, so
+-- they are removed first while foreign keys still cascade.
+PRAGMA foreign_keys = ON;
+
+DELETE FROM roles WHERE hub_id IS NOT NULL;
+
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE roles_new (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO roles_new (id, name, created_at, updated_at)
+SELECT id, name, created_at, updated_at FROM roles;
+
+DROP TABLE roles;
+ALTER TABLE roles_new RENAME TO roles;
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,28 @@
+-- Roles owned by a single hub. Existing roles, including `admin`, become
+-- system roles (`hub_id IS NULL`) shared by every hub. SQLite cannot drop the
+-- inline UNIQUE constraint on the name, so the table is rebuilt and names are
+-- kept unique among system roles and within each hub by partial indexes.
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE roles_new (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    hub_id INTEGER REFERENCES hubs(id)
+);
+
+INSERT INTO roles_new (id, name, created_at, updated_at)
+SELECT id, name, created_at, updated_at FROM roles;
+
+DROP TABLE roles;
+ALTER TABLE roles_new RENAME TO roles;
+
+CREATE UNIQUE INDEX idx_roles_system_name ON roles(name) WHERE hub_id IS NULL;
+CREATE UNIQUE INDEX idx_roles_hub_name ON roles(hub_id, name) WHERE hub_id IS NOT NULL;
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,2 @@
+-- Roles carry a free-form description shown to administrators
+ALTER TABLE roles ADD COLUMN description TEXT NOT NULL DEFAULT '';
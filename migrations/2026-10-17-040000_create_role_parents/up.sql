@@ -0,0 +1,10 @@
+-- Roles implied by another role: holding `role_id` also grants `parent_id`
+-- and, transitively, the parents of `parent_id`.
+CREATE TABLE role_parents (
+    role_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    parent_id INTEGER NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, parent_id),
+    CHECK (role_id <> parent_id)
+);
+
+CREATE INDEX idx_role_parents_parent_id ON role_parents(parent_id);
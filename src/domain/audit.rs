@@ -0,0 +1,226 @@
+//! Domain models for the audit log of security-relevant events.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::session::ClientInfo;
+use crate::domain::types::{AuditEventId, HubId, TypeConstraintError, UserId};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Kind of an [`AuditEvent`].
+pub enum AuditEventType {
+    /// A session was issued to a user.
+    LoginSucceeded,
+    /// A sign-in attempt was refused because of a wrong password or code.
+    LoginFailed,
+    /// A user created an account through `POST /auth/register`.
+    UserRegistered,
+    /// A password recovery link was sent.
+    RecoveryRequested,
+    /// A session was issued after the user set a new password with a
+    /// recovery link.
+    PasswordReset,
+    /// A session was issued after the user accepted an invitation.
+    InvitationAccepted,
+    UserCreated,
+    /// A user's profile, roles or email verification changed.
+    UserUpdated,
+    UserSuspended,
+    UserReactivated,
+    UserDeleted,
+    UserRestored,
+    RoleCreated,
+    /// A role was renamed or its description changed.
+    RoleUpdated,
+    RoleDeleted,
+    HubCreated,
+    /// The policy or registration settings of a hub changed.
+    HubUpdated,
+    HubDeleted,
+    HubRestored,
+    MenuCreated,
+    MenuDeleted,
+}
+
+impl AuditEventType {
+    /// Every event type, in the order they are presented to administrators.
+    pub const ALL: [AuditEventType; 21] = [
+        Self::LoginSucceeded,
+        Self::LoginFailed,
+        Self::UserRegistered,
+        Self::RecoveryRequested,
+        Self::PasswordReset,
+        Self::InvitationAccepted,
+        Self::UserCreated,
+        Self::UserUpdated,
+        Self::UserSuspended,
+        Self::UserReactivated,
+        Self::UserDeleted,
+        Self::UserRestored,
+        Self::RoleCreated,
+        Self::RoleUpdated,
+        Self::RoleDeleted,
+        Self::HubCreated,
+        Self::HubUpdated,
+        Self::HubDeleted,
+        Self::HubRestored,
+        Self::MenuCreated,
+        Self::MenuDeleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LoginSucceeded => "login_succeeded",
+            Self::LoginFailed => "login_failed",
+            Self::UserRegistered => "user_registered",
+            Self::RecoveryRequested => "recovery_requested",
+            Self::PasswordReset => "password_reset",
+            Self::InvitationAccepted => "invitation_accepted",
+            Self::UserCreated => "user_created",
+            Self::UserUpdated => "user_updated",
+            Self::UserSuspended => "user_suspended",
+            Self::UserReactivated => "user_reactivated",
+            Self::UserDeleted => "user_deleted",
+            Self::UserRestored => "user_restored",
+            Self::RoleCreated => "role_created",
+            Self::RoleUpdated => "role_updated",
+            Self::RoleDeleted => "role_deleted",
+            Self::HubCreated => "hub_created",
+            Self::HubUpdated => "hub_updated",
+            Self::HubDeleted => "hub_deleted",
+            Self::HubRestored => "hub_restored",
+            Self::MenuCreated => "menu_created",
+            Self::MenuDeleted => "menu_deleted",
+        }
+    }
+}
+
+impl fmt::Display for AuditEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditEventType {
+    type Err = TypeConstraintError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|event_type| event_type.as_str() == value)
+            .ok_or(TypeConstraintError::UnknownAuditEventType)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Recorded event. Events are never changed or removed once stored.
+pub struct AuditEvent {
+    pub id: AuditEventId,
+    /// Hub the event happened in.
+    pub hub_id: HubId,
+    /// User who caused the event; `None` for anonymous requests such as a
+    /// failed sign-in with an unknown address.
+    pub actor_id: Option<UserId>,
+    pub event_type: AuditEventType,
+    /// Id of the user, role, hub or menu the event is about, depending on
+    /// [`AuditEvent::event_type`].
+    pub target_id: Option<i32>,
+    /// Free-form context such as the address of a failed sign-in.
+    pub detail: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl AuditEvent {
+    /// Validates raw values before constructing an event record.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        id: i32,
+        hub_id: i32,
+        actor_id: Option<i32>,
+        event_type: &str,
+        target_id: Option<i32>,
+        detail: Option<String>,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self {
+            id: AuditEventId::try_from(id)?,
+            hub_id: HubId::try_from(hub_id)?,
+            actor_id: actor_id.map(UserId::try_from).transpose()?,
+            event_type: event_type.parse()?,
+            target_id,
+            detail,
+            ip_address,
+            user_agent,
+            created_at,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to record a new [`AuditEvent`].
+pub struct NewAuditEvent {
+    pub hub_id: HubId,
+    pub actor_id: Option<UserId>,
+    pub event_type: AuditEventType,
+    pub target_id: Option<i32>,
+    pub detail: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl NewAuditEvent {
+    /// Constructs an event of `event_type` in a hub, sent by `client`.
+    pub fn new(hub_id: HubId, event_type: AuditEventType, client: &ClientInfo) -> Self {
+        Self {
+            hub_id,
+            actor_id: None,
+            event_type,
+            target_id: None,
+            detail: None,
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+        }
+    }
+
+    pub fn with_actor(mut self, actor_id: UserId) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    pub fn with_target(mut self, target_id: i32) -> Self {
+        self.target_id = Some(target_id);
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audit_event_type_round_trips_through_its_name() {
+        for event_type in AuditEventType::ALL {
+            assert_eq!(
+                event_type.as_str().parse::<AuditEventType>(),
+                Ok(event_type)
+            );
+        }
+        assert_eq!(
+            "unknown".parse::<AuditEventType>(),
+            Err(TypeConstraintError::UnknownAuditEventType)
+        );
+    }
+}
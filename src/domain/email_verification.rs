@@ -0,0 +1,121 @@
+//! Domain models for single-use email verification tokens.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{EmailVerificationId, HubId, TypeConstraintError, UserId};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Verification token sent to the address of a user. Only the hash of the
+/// token is stored.
+pub struct EmailVerification {
+    pub id: EmailVerificationId,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl EmailVerification {
+    /// Constructs a verification token record from validated domain types.
+    pub fn new(
+        id: EmailVerificationId,
+        user_id: UserId,
+        hub_id: HubId,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+        used_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            user_id,
+            hub_id,
+            token_hash,
+            expires_at,
+            used_at,
+            created_at,
+        }
+    }
+
+    /// Validates raw values before constructing a verification token record.
+    pub fn try_new(
+        id: i32,
+        user_id: i32,
+        hub_id: i32,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+        used_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            EmailVerificationId::try_from(id)?,
+            UserId::try_from(user_id)?,
+            HubId::try_from(hub_id)?,
+            token_hash,
+            expires_at,
+            used_at,
+            created_at,
+        ))
+    }
+
+    /// Returns `true` when the token has not been used and has not expired.
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to issue a new [`EmailVerification`].
+pub struct NewEmailVerification {
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+}
+
+impl NewEmailVerification {
+    /// Constructs a new verification token payload from validated domain
+    /// types.
+    pub fn new(
+        user_id: UserId,
+        hub_id: HubId,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            user_id,
+            hub_id,
+            token_hash,
+            expires_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn email_verification_is_active_until_used_or_expired() {
+        let now = Utc::now().naive_utc();
+        let mut verification = EmailVerification::try_new(
+            1,
+            2,
+            3,
+            "hash".to_string(),
+            now + Duration::hours(1),
+            None,
+            now,
+        )
+        .unwrap();
+        assert!(verification.is_active(now));
+        assert!(!verification.is_active(now + Duration::hours(2)));
+
+        verification.used_at = Some(now);
+        assert!(!verification.is_active(now));
+    }
+}
@@ -1,9 +1,13 @@
-//! Domain models representing hubs and their creation input.
+//! Domain models representing hubs, their creation input, policies and
+//! registration settings.
+
+use std::fmt;
+use std::str::FromStr;
 
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::types::{HubId, HubName, TypeConstraintError};
+use crate::domain::types::{HubId, HubName, RoleId, TypeConstraintError, UserEmail};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 /// A business entity representing a hub which groups users and menus.
@@ -12,6 +16,8 @@ pub struct Hub {
     pub name: HubName,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// When the hub was deleted; deleted hubs are kept until purged.
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 impl Hub {
@@ -27,6 +33,7 @@ impl Hub {
             name,
             created_at,
             updated_at,
+            deleted_at: None,
         }
     }
 
@@ -64,6 +71,83 @@ impl NewHub {
     }
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+/// Security rules configured for a [`Hub`].
+pub struct HubPolicy {
+    /// Users holding the admin role must complete TOTP enrollment to sign in.
+    pub require_admin_2fa: bool,
+    /// Users must verify their email address before they can sign in.
+    pub require_email_verification: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Who may create an account in a [`Hub`] through `POST /auth/register`.
+pub enum RegistrationMode {
+    /// Anyone may register.
+    #[default]
+    Open,
+    /// Nobody may register; administrators add users themselves.
+    Closed,
+    /// Only invited users may join the hub.
+    InviteOnly,
+}
+
+impl RegistrationMode {
+    /// Every mode, in the order they are presented to administrators.
+    pub const ALL: [RegistrationMode; 3] = [Self::Open, Self::Closed, Self::InviteOnly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Closed => "closed",
+            Self::InviteOnly => "invite_only",
+        }
+    }
+}
+
+impl fmt::Display for RegistrationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RegistrationMode {
+    type Err = TypeConstraintError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == value)
+            .ok_or(TypeConstraintError::UnknownRegistrationMode)
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+/// Self-registration rules configured for a [`Hub`].
+pub struct HubRegistrationSettings {
+    pub registration_mode: RegistrationMode,
+    /// Lowercase domains, without `@`, that registering addresses must
+    /// belong to; an empty list allows any domain.
+    pub allowed_email_domains: Vec<String>,
+    /// Roles assigned to users who register in the hub.
+    pub default_roles: Vec<RoleId>,
+}
+
+impl HubRegistrationSettings {
+    /// Returns `true` when `email` belongs to one of the allowed domains.
+    pub fn allows_email(&self, email: &UserEmail) -> bool {
+        if self.allowed_email_domains.is_empty() {
+            return true;
+        }
+        email.as_str().rsplit_once('@').is_some_and(|(_, domain)| {
+            self.allowed_email_domains
+                .iter()
+                .any(|allowed| allowed == domain)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,6 +173,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn registration_mode_round_trips_through_strings() {
+        for mode in RegistrationMode::ALL {
+            assert_eq!(mode.as_str().parse::<RegistrationMode>().unwrap(), mode);
+        }
+        assert_eq!(
+            "public".parse::<RegistrationMode>().unwrap_err(),
+            TypeConstraintError::UnknownRegistrationMode
+        );
+    }
+
+    #[test]
+    fn registration_settings_match_whole_email_domains() {
+        let settings = HubRegistrationSettings {
+            allowed_email_domains: vec!["company.ru".into()],
+            ..Default::default()
+        };
+        assert!(settings.allows_email(&UserEmail::new("Ivan@Company.ru").unwrap()));
+        assert!(!settings.allows_email(&UserEmail::new("ivan@evilcompany.ru").unwrap()));
+        assert!(!settings.allows_email(&UserEmail::new("ivan@company.ru.evil.com").unwrap()));
+        assert!(
+            HubRegistrationSettings::default().allows_email(&UserEmail::new("a@b.com").unwrap())
+        );
+    }
+
     #[test]
     fn new_hub_try_new_rejects_empty_name() {
         assert_eq!(
@@ -0,0 +1,134 @@
+//! Domain models for invitations sent by hub administrators.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{
+    HubId, InvitationId, RoleId, TypeConstraintError, UserEmail, UserId, UserName,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Pending account created by an administrator. The invitee sets a password
+/// with the emailed token and joins the hub with `roles`. Only the hash of
+/// the token is stored.
+pub struct Invitation {
+    pub id: InvitationId,
+    pub hub_id: HubId,
+    pub email: UserEmail,
+    pub name: UserName,
+    pub token_hash: String,
+    /// Administrator who sent the invitation, if the account still exists.
+    pub invited_by: Option<UserId>,
+    pub expires_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub roles: Vec<RoleId>,
+}
+
+impl Invitation {
+    /// Constructs an invitation from validated domain types.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: InvitationId,
+        hub_id: HubId,
+        email: UserEmail,
+        name: UserName,
+        token_hash: String,
+        invited_by: Option<UserId>,
+        expires_at: NaiveDateTime,
+        accepted_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+        roles: Vec<RoleId>,
+    ) -> Self {
+        Self {
+            id,
+            hub_id,
+            email,
+            name,
+            token_hash,
+            invited_by,
+            expires_at,
+            accepted_at,
+            created_at,
+            roles,
+        }
+    }
+
+    /// Validates raw values before constructing an invitation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        id: i32,
+        hub_id: i32,
+        email: String,
+        name: String,
+        token_hash: String,
+        invited_by: Option<i32>,
+        expires_at: NaiveDateTime,
+        accepted_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+        roles: Vec<i32>,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            InvitationId::try_from(id)?,
+            HubId::try_from(hub_id)?,
+            UserEmail::try_from(email)?,
+            UserName::try_from(name)?,
+            token_hash,
+            invited_by.map(UserId::try_from).transpose()?,
+            expires_at,
+            accepted_at,
+            created_at,
+            roles
+                .into_iter()
+                .map(RoleId::try_from)
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    /// Returns `true` when the invitation has not been accepted and has not
+    /// expired.
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.accepted_at.is_none() && self.expires_at > now
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to create a new [`Invitation`].
+pub struct NewInvitation {
+    pub hub_id: HubId,
+    pub email: UserEmail,
+    pub name: UserName,
+    pub token_hash: String,
+    pub invited_by: Option<UserId>,
+    pub expires_at: NaiveDateTime,
+    pub roles: Vec<RoleId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn invitation_is_active_until_accepted_or_expired() {
+        let now = Utc::now().naive_utc();
+        let mut invitation = Invitation::try_new(
+            1,
+            2,
+            "new@example.com".into(),
+            "New".into(),
+            "hash".into(),
+            Some(3),
+            now + Duration::hours(1),
+            None,
+            now,
+            vec![4],
+        )
+        .unwrap();
+        assert!(invitation.is_active(now));
+        assert!(!invitation.is_active(now + Duration::hours(2)));
+
+        invitation.accepted_at = Some(now);
+        assert!(!invitation.is_active(now));
+    }
+}
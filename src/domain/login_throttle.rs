@@ -0,0 +1,148 @@
+//! Domain models for throttling failed login attempts.
+
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Limits applied to a single throttle key (an account or a client IP).
+pub struct LoginThrottlePolicy {
+    /// Failed attempts allowed within `window` before the key is locked.
+    pub max_attempts: u32,
+    /// Length of the window in which failed attempts are counted.
+    pub window: Duration,
+    /// Duration of the first lockout; every next lockout doubles it.
+    pub base_lockout: Duration,
+    /// Upper bound for a single lockout.
+    pub max_lockout: Duration,
+}
+
+impl LoginThrottlePolicy {
+    /// Returns the lockout duration for the given consecutive lockout number.
+    pub fn lockout_duration(&self, lockout_count: u32) -> Duration {
+        let exponent = lockout_count.saturating_sub(1).min(30);
+        let lockout = self
+            .base_lockout
+            .checked_mul(1_i32 << exponent)
+            .unwrap_or(self.max_lockout);
+        lockout.min(self.max_lockout)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Persisted failed-login counters for a single throttle key.
+pub struct LoginThrottle {
+    pub key: String,
+    pub failed_attempts: u32,
+    pub window_started_at: NaiveDateTime,
+    pub lockout_count: u32,
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+impl LoginThrottle {
+    /// Creates empty counters for `key` with a window starting at `now`.
+    pub fn new(key: impl Into<String>, now: NaiveDateTime) -> Self {
+        Self {
+            key: key.into(),
+            failed_attempts: 0,
+            window_started_at: now,
+            lockout_count: 0,
+            locked_until: None,
+        }
+    }
+
+    /// Returns the remaining lockout time, if the key is currently locked.
+    pub fn locked_for(&self, now: NaiveDateTime) -> Option<Duration> {
+        self.locked_until
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| locked_until - now)
+    }
+
+    /// Records a failed attempt, locking the key once the policy threshold is
+    /// reached within the current window.
+    pub fn register_failure(&mut self, now: NaiveDateTime, policy: &LoginThrottlePolicy) {
+        // Forget earlier lockouts once the key has stayed quiet for longer
+        // than the longest lockout, so backoff does not grow forever.
+        if self
+            .locked_until
+            .is_some_and(|locked_until| locked_until + policy.max_lockout <= now)
+        {
+            self.lockout_count = 0;
+            self.locked_until = None;
+        }
+
+        if self.window_started_at + policy.window <= now {
+            self.failed_attempts = 0;
+            self.window_started_at = now;
+        }
+
+        self.failed_attempts += 1;
+
+        if self.failed_attempts >= policy.max_attempts {
+            self.lockout_count += 1;
+            self.locked_until = Some(now + policy.lockout_duration(self.lockout_count));
+            self.failed_attempts = 0;
+            self.window_started_at = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn policy() -> LoginThrottlePolicy {
+        LoginThrottlePolicy {
+            max_attempts: 3,
+            window: Duration::minutes(15),
+            base_lockout: Duration::minutes(1),
+            max_lockout: Duration::minutes(10),
+        }
+    }
+
+    #[test]
+    fn lockout_duration_doubles_and_is_capped() {
+        let policy = policy();
+        assert_eq!(policy.lockout_duration(1), Duration::minutes(1));
+        assert_eq!(policy.lockout_duration(2), Duration::minutes(2));
+        assert_eq!(policy.lockout_duration(4), Duration::minutes(8));
+        assert_eq!(policy.lockout_duration(5), Duration::minutes(10));
+        assert_eq!(policy.lockout_duration(u32::MAX), Duration::minutes(10));
+    }
+
+    #[test]
+    fn register_failure_locks_after_threshold_with_backoff() {
+        let policy = policy();
+        let now = Utc::now().naive_utc();
+        let mut throttle = LoginThrottle::new("account:1:a@b", now);
+
+        throttle.register_failure(now, &policy);
+        throttle.register_failure(now, &policy);
+        assert_eq!(throttle.locked_for(now), None);
+
+        throttle.register_failure(now, &policy);
+        assert_eq!(throttle.locked_for(now), Some(Duration::minutes(1)));
+
+        let later = now + Duration::minutes(2);
+        for _ in 0..3 {
+            throttle.register_failure(later, &policy);
+        }
+        assert_eq!(throttle.locked_for(later), Some(Duration::minutes(2)));
+    }
+
+    #[test]
+    fn register_failure_resets_counters_after_window() {
+        let policy = policy();
+        let now = Utc::now().naive_utc();
+        let mut throttle = LoginThrottle::new("ip:127.0.0.1", now);
+
+        throttle.register_failure(now, &policy);
+        throttle.register_failure(now, &policy);
+
+        let later = now + Duration::minutes(16);
+        throttle.register_failure(later, &policy);
+        assert_eq!(throttle.failed_attempts, 1);
+        assert_eq!(throttle.window_started_at, later);
+        assert_eq!(throttle.locked_for(later), None);
+    }
+}
@@ -3,8 +3,20 @@
 //! These structs represent the business entities independent from any
 //! persistence or transport concerns.
 
+pub mod audit;
+pub mod email_verification;
 pub mod hub;
+pub mod invitation;
+pub mod login_throttle;
 pub mod menu;
+pub mod oauth;
+pub mod password_reset;
+pub mod permission;
+pub mod personal_token;
 pub mod role;
+pub mod service_account;
+pub mod session;
+pub mod two_factor;
 pub mod types;
 pub mod user;
+pub mod user_credential;
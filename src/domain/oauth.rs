@@ -0,0 +1,336 @@
+//! Domain models for the OpenID Connect provider.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{
+    AccessTokenId, AuthorizationCodeId, HubId, OAuthClientId, TypeConstraintError, UserId,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Application registered to sign users in through the provider.
+pub struct OAuthClient {
+    pub id: OAuthClientId,
+    /// Public identifier sent by the application as `client_id`.
+    pub client_id: String,
+    /// Hash of the client secret; public clients have none and rely on PKCE.
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl OAuthClient {
+    /// Constructs a client record from validated domain types.
+    pub fn new(
+        id: OAuthClientId,
+        client_id: String,
+        client_secret_hash: Option<String>,
+        name: String,
+        redirect_uris: Vec<String>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            client_id,
+            client_secret_hash,
+            name,
+            redirect_uris,
+            created_at,
+        }
+    }
+
+    /// Validates raw values before constructing a client record.
+    pub fn try_new(
+        id: i32,
+        client_id: String,
+        client_secret_hash: Option<String>,
+        name: String,
+        redirect_uris: Vec<String>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            OAuthClientId::try_from(id)?,
+            client_id,
+            client_secret_hash,
+            name,
+            redirect_uris,
+            created_at,
+        ))
+    }
+
+    /// Returns `true` for clients that cannot keep a secret, such as mobile
+    /// and single-page apps.
+    pub fn is_public(&self) -> bool {
+        self.client_secret_hash.is_none()
+    }
+
+    /// Returns `true` when `redirect_uri` exactly matches a registered URI.
+    pub fn allows_redirect_uri(&self, redirect_uri: &str) -> bool {
+        self.redirect_uris.iter().any(|uri| uri == redirect_uri)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to register a new [`OAuthClient`].
+pub struct NewOAuthClient {
+    pub client_id: String,
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+}
+
+impl NewOAuthClient {
+    /// Constructs a new client payload.
+    pub fn new(
+        client_id: String,
+        client_secret_hash: Option<String>,
+        name: String,
+        redirect_uris: Vec<String>,
+    ) -> Self {
+        Self {
+            client_id,
+            client_secret_hash,
+            name,
+            redirect_uris,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Authorization code issued to a client. Only the hash of the code is stored.
+pub struct AuthorizationCode {
+    pub id: AuthorizationCodeId,
+    pub code_hash: String,
+    pub client_id: OAuthClientId,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    /// Redirect URI the code was issued for; the token request must repeat it.
+    pub redirect_uri: String,
+    pub scope: String,
+    pub nonce: Option<String>,
+    /// PKCE `S256` challenge the token request must answer.
+    pub code_challenge: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl AuthorizationCode {
+    /// Constructs an authorization code record from validated domain types.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: AuthorizationCodeId,
+        code_hash: String,
+        client_id: OAuthClientId,
+        user_id: UserId,
+        hub_id: HubId,
+        redirect_uri: String,
+        scope: String,
+        nonce: Option<String>,
+        code_challenge: String,
+        expires_at: NaiveDateTime,
+        used_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            code_hash,
+            client_id,
+            user_id,
+            hub_id,
+            redirect_uri,
+            scope,
+            nonce,
+            code_challenge,
+            expires_at,
+            used_at,
+            created_at,
+        }
+    }
+
+    /// Validates raw values before constructing an authorization code record.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        id: i32,
+        code_hash: String,
+        client_id: i32,
+        user_id: i32,
+        hub_id: i32,
+        redirect_uri: String,
+        scope: String,
+        nonce: Option<String>,
+        code_challenge: String,
+        expires_at: NaiveDateTime,
+        used_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            AuthorizationCodeId::try_from(id)?,
+            code_hash,
+            OAuthClientId::try_from(client_id)?,
+            UserId::try_from(user_id)?,
+            HubId::try_from(hub_id)?,
+            redirect_uri,
+            scope,
+            nonce,
+            code_challenge,
+            expires_at,
+            used_at,
+            created_at,
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to issue a new [`AuthorizationCode`].
+pub struct NewAuthorizationCode {
+    pub code_hash: String,
+    pub client_id: OAuthClientId,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub nonce: Option<String>,
+    pub code_challenge: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Opaque access token issued to a client. Only the hash of the token is
+/// stored.
+pub struct AccessToken {
+    pub id: AccessTokenId,
+    pub token_hash: String,
+    pub client_id: OAuthClientId,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub scope: String,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl AccessToken {
+    /// Constructs an access token record from validated domain types.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: AccessTokenId,
+        token_hash: String,
+        client_id: OAuthClientId,
+        user_id: UserId,
+        hub_id: HubId,
+        scope: String,
+        expires_at: NaiveDateTime,
+        revoked_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            token_hash,
+            client_id,
+            user_id,
+            hub_id,
+            scope,
+            expires_at,
+            revoked_at,
+            created_at,
+        }
+    }
+
+    /// Validates raw values before constructing an access token record.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        id: i32,
+        token_hash: String,
+        client_id: i32,
+        user_id: i32,
+        hub_id: i32,
+        scope: String,
+        expires_at: NaiveDateTime,
+        revoked_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            AccessTokenId::try_from(id)?,
+            token_hash,
+            OAuthClientId::try_from(client_id)?,
+            UserId::try_from(user_id)?,
+            HubId::try_from(hub_id)?,
+            scope,
+            expires_at,
+            revoked_at,
+            created_at,
+        ))
+    }
+
+    /// Returns `true` when the token is neither revoked nor expired at `now`.
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to issue a new [`AccessToken`].
+pub struct NewAccessToken {
+    pub token_hash: String,
+    pub client_id: OAuthClientId,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub scope: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn make_client(client_secret_hash: Option<String>) -> OAuthClient {
+        OAuthClient::try_new(
+            1,
+            "app".into(),
+            client_secret_hash,
+            "App".into(),
+            vec!["https://app.example.org/callback".into()],
+            Utc::now().naive_utc(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn oauth_client_matches_redirect_uris_exactly() {
+        let client = make_client(None);
+        assert!(client.allows_redirect_uri("https://app.example.org/callback"));
+        assert!(!client.allows_redirect_uri("https://app.example.org/callback/"));
+        assert!(!client.allows_redirect_uri("https://app.example.org/callback?x=1"));
+    }
+
+    #[test]
+    fn oauth_client_without_secret_is_public() {
+        assert!(make_client(None).is_public());
+        assert!(!make_client(Some("hash".into())).is_public());
+    }
+
+    #[test]
+    fn access_token_is_active_until_revoked_or_expired() {
+        let now = Utc::now().naive_utc();
+        let mut token = AccessToken::try_new(
+            1,
+            "hash".into(),
+            1,
+            2,
+            3,
+            "openid".into(),
+            now + Duration::hours(1),
+            None,
+            now,
+        )
+        .unwrap();
+        assert!(token.is_active(now));
+        assert!(!token.is_active(now + Duration::hours(2)));
+
+        token.revoked_at = Some(now);
+        assert!(!token.is_active(now));
+    }
+}
@@ -0,0 +1,128 @@
+//! Domain models for single-use password recovery tokens.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{HubId, PasswordResetId, TypeConstraintError, UserId};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Recovery token issued to a user. Only the hash of the token is stored.
+pub struct PasswordReset {
+    pub id: PasswordResetId,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl PasswordReset {
+    /// Constructs a recovery token record from validated domain types.
+    pub fn new(
+        id: PasswordResetId,
+        user_id: UserId,
+        hub_id: HubId,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+        used_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            user_id,
+            hub_id,
+            token_hash,
+            expires_at,
+            used_at,
+            created_at,
+        }
+    }
+
+    /// Validates raw values before constructing a recovery token record.
+    pub fn try_new(
+        id: i32,
+        user_id: i32,
+        hub_id: i32,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+        used_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            PasswordResetId::try_from(id)?,
+            UserId::try_from(user_id)?,
+            HubId::try_from(hub_id)?,
+            token_hash,
+            expires_at,
+            used_at,
+            created_at,
+        ))
+    }
+
+    /// Returns `true` when the token has not been used and has not expired.
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.used_at.is_none() && self.expires_at > now
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to issue a new [`PasswordReset`].
+pub struct NewPasswordReset {
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+}
+
+impl NewPasswordReset {
+    /// Constructs a new recovery token payload from validated domain types.
+    pub fn new(
+        user_id: UserId,
+        hub_id: HubId,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            user_id,
+            hub_id,
+            token_hash,
+            expires_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn password_reset_is_active_until_used_or_expired() {
+        let now = Utc::now().naive_utc();
+        let mut reset = PasswordReset::try_new(
+            1,
+            2,
+            3,
+            "hash".to_string(),
+            now + Duration::hours(1),
+            None,
+            now,
+        )
+        .unwrap();
+        assert!(reset.is_active(now));
+        assert!(!reset.is_active(now + Duration::hours(2)));
+
+        reset.used_at = Some(now);
+        assert!(!reset.is_active(now));
+    }
+
+    #[test]
+    fn password_reset_try_new_rejects_invalid_ids() {
+        let now = Utc::now().naive_utc();
+        assert_eq!(
+            PasswordReset::try_new(1, 0, 1, "hash".to_string(), now, None, now).unwrap_err(),
+            TypeConstraintError::NonPositiveId
+        );
+    }
+}
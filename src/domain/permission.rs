@@ -0,0 +1,100 @@
+//! Domain models for permissions granted through roles.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{RoleId, TypeConstraintError};
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// Action a user may take in their hub. Users hold the permissions of all
+/// their roles.
+pub enum Permission {
+    /// View the users of the hub.
+    #[serde(rename = "users.read")]
+    UsersRead,
+    /// Create, change, suspend, delete, and restore users of the hub.
+    #[serde(rename = "users.write")]
+    UsersWrite,
+    /// Create and delete roles.
+    #[serde(rename = "roles.manage")]
+    RolesManage,
+    /// Change the menu of the hub.
+    #[serde(rename = "menu.manage")]
+    MenuManage,
+    /// Create, configure, delete, and restore hubs.
+    #[serde(rename = "hubs.manage")]
+    HubsManage,
+}
+
+impl Permission {
+    /// Every permission, in the order they are presented to administrators.
+    pub const ALL: [Permission; 5] = [
+        Self::UsersRead,
+        Self::UsersWrite,
+        Self::RolesManage,
+        Self::MenuManage,
+        Self::HubsManage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UsersRead => "users.read",
+            Self::UsersWrite => "users.write",
+            Self::RolesManage => "roles.manage",
+            Self::MenuManage => "menu.manage",
+            Self::HubsManage => "hubs.manage",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = TypeConstraintError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == value)
+            .ok_or(TypeConstraintError::UnknownPermission)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Permission granted to a role.
+pub struct RolePermission {
+    pub role_id: RoleId,
+    pub permission: Permission,
+}
+
+impl RolePermission {
+    /// Validates raw values before constructing a role permission.
+    pub fn try_new(role_id: i32, permission: &str) -> Result<Self, TypeConstraintError> {
+        Ok(Self {
+            role_id: RoleId::try_from(role_id)?,
+            permission: permission.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_round_trips_through_its_name() {
+        for permission in Permission::ALL {
+            assert_eq!(permission.as_str().parse::<Permission>(), Ok(permission));
+        }
+        assert_eq!(
+            "users.delete".parse::<Permission>(),
+            Err(TypeConstraintError::UnknownPermission)
+        );
+    }
+}
@@ -3,7 +3,8 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::types::{RoleId, RoleName, TypeConstraintError, UserId};
+use crate::domain::permission::Permission;
+use crate::domain::types::{HubId, RoleId, RoleName, TypeConstraintError, UserId};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 /// Role assigned to a user describing a set of permissions.
@@ -12,6 +13,11 @@ pub struct Role {
     pub name: RoleName,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// Hub that owns the role; `None` for system roles such as `admin` that
+    /// every hub shares.
+    pub hub_id: Option<HubId>,
+    /// Free-form explanation of what the role is for; may be empty.
+    pub description: String,
 }
 
 impl Role {
@@ -27,9 +33,26 @@ impl Role {
             name,
             created_at,
             updated_at,
+            hub_id: None,
+            description: String::new(),
         }
     }
 
+    pub fn with_hub(mut self, hub_id: HubId) -> Self {
+        self.hub_id = Some(hub_id);
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Whether the role can be assigned to users of `hub_id`.
+    pub fn is_visible_in(&self, hub_id: HubId) -> bool {
+        self.hub_id.is_none_or(|owner| owner == hub_id)
+    }
+
     /// Validates raw values before constructing a role.
     pub fn try_new(
         id: i32,
@@ -50,12 +73,37 @@ impl Role {
 /// Information required to create a new [`Role`].
 pub struct NewRole {
     pub name: RoleName,
+    /// Permissions granted to the role.
+    pub permissions: Vec<Permission>,
+    /// Hub that owns the role; `None` creates a system role.
+    pub hub_id: Option<HubId>,
+    pub description: String,
 }
 
 impl NewRole {
     /// Constructs a new role payload from validated domain types.
     pub fn new(name: RoleName) -> Self {
-        Self { name }
+        Self {
+            name,
+            permissions: Vec::new(),
+            hub_id: None,
+            description: String::new(),
+        }
+    }
+
+    pub fn with_hub(mut self, hub_id: HubId) -> Self {
+        self.hub_id = Some(hub_id);
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    pub fn with_permissions(mut self, permissions: Vec<Permission>) -> Self {
+        self.permissions = permissions;
+        self
     }
 
     /// Validates raw values before constructing a new role payload.
@@ -64,6 +112,99 @@ impl NewRole {
     }
 }
 
+#[derive(Clone, Debug, Deserialize)]
+/// Changes applied to an existing [`Role`]; permissions and the owning hub
+/// stay as they are.
+pub struct UpdateRole {
+    pub name: RoleName,
+    pub description: String,
+}
+
+impl UpdateRole {
+    /// Constructs a role update from validated domain types.
+    pub fn new(name: RoleName, description: impl Into<String>) -> Self {
+        Self {
+            name,
+            description: description.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Declares that holding `role_id` also grants `parent_id`, e.g. `manager`
+/// implying `editor`.
+pub struct RoleParent {
+    pub role_id: RoleId,
+    pub parent_id: RoleId,
+}
+
+impl RoleParent {
+    /// Constructs a role-parent link from validated identifiers.
+    pub fn new(role_id: RoleId, parent_id: RoleId) -> Self {
+        Self { role_id, parent_id }
+    }
+
+    /// Validates raw values before constructing a role-parent link.
+    pub fn try_new(role_id: i32, parent_id: i32) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            RoleId::try_from(role_id)?,
+            RoleId::try_from(parent_id)?,
+        ))
+    }
+}
+
+/// Roles implied by `direct` through `links`, transitively, without the
+/// roles of `direct` themselves. The result is sorted by id.
+pub fn inherited_role_ids(direct: &[RoleId], links: &[RoleParent]) -> Vec<RoleId> {
+    let mut reached = direct.to_vec();
+    let mut pending = direct.to_vec();
+    while let Some(role_id) = pending.pop() {
+        for link in links.iter().filter(|link| link.role_id == role_id) {
+            if !reached.contains(&link.parent_id) {
+                reached.push(link.parent_id);
+                pending.push(link.parent_id);
+            }
+        }
+    }
+    let mut inherited = reached
+        .into_iter()
+        .filter(|role_id| !direct.contains(role_id))
+        .collect::<Vec<_>>();
+    inherited.sort_by_key(|role_id| role_id.get());
+    inherited
+}
+
+/// Whether replacing the parents of `role_id` with `parent_ids` would let the
+/// role inherit itself.
+pub fn creates_cycle(role_id: RoleId, parent_ids: &[RoleId], links: &[RoleParent]) -> bool {
+    let other_links = links
+        .iter()
+        .filter(|link| link.role_id != role_id)
+        .cloned()
+        .collect::<Vec<_>>();
+    parent_ids.contains(&role_id) || inherited_role_ids(parent_ids, &other_links).contains(&role_id)
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Whether a bulk change grants a role to users or takes it away.
+pub enum RoleChange {
+    Add,
+    Remove,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+/// Outcome of adding a role to or removing it from many users at once.
+pub struct RoleChangeSummary {
+    /// Users whose roles changed.
+    pub changed: Vec<UserId>,
+    /// Selected users that already held the role, or did not hold it when
+    /// removing.
+    pub unchanged: usize,
+    /// Requested ids that are not live users of the hub.
+    pub not_found: Vec<UserId>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 /// Mapping table between users and roles.
 pub struct UserRole {
@@ -146,6 +287,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn system_roles_are_visible_in_every_hub() {
+        let ts = chrono::DateTime::<chrono::Utc>::from_timestamp(0, 0)
+            .unwrap()
+            .naive_utc();
+        let hub = HubId::new(1).unwrap();
+        let other_hub = HubId::new(2).unwrap();
+        let system = Role::try_new(1, "admin", ts, ts).unwrap();
+        assert!(system.is_visible_in(hub) && system.is_visible_in(other_hub));
+        let owned = Role::try_new(2, "staff", ts, ts).unwrap().with_hub(hub);
+        assert!(owned.is_visible_in(hub));
+        assert!(!owned.is_visible_in(other_hub));
+    }
+
+    fn link(role_id: i32, parent_id: i32) -> RoleParent {
+        RoleParent::try_new(role_id, parent_id).unwrap()
+    }
+
+    fn ids(ids: &[i32]) -> Vec<RoleId> {
+        ids.iter().map(|id| RoleId::new(*id).unwrap()).collect()
+    }
+
+    #[test]
+    fn inherited_roles_are_the_transitive_parents() {
+        // manager (3) -> editor (2) -> viewer (1); auditor (4) -> viewer.
+        let links = vec![link(3, 2), link(2, 1), link(4, 1)];
+        assert_eq!(inherited_role_ids(&ids(&[3]), &links), ids(&[1, 2]));
+        assert_eq!(inherited_role_ids(&ids(&[3, 2]), &links), ids(&[1]));
+        assert_eq!(inherited_role_ids(&ids(&[4]), &links), ids(&[1]));
+        assert!(inherited_role_ids(&ids(&[1]), &links).is_empty());
+    }
+
+    #[test]
+    fn cycles_are_detected_before_parents_are_replaced() {
+        let links = vec![link(3, 2), link(2, 1)];
+        let viewer = RoleId::new(1).unwrap();
+        let manager = RoleId::new(3).unwrap();
+        assert!(creates_cycle(viewer, &ids(&[3]), &links));
+        assert!(creates_cycle(viewer, &ids(&[1]), &links));
+        assert!(!creates_cycle(viewer, &ids(&[4]), &links));
+        // The current parents of the role itself are being replaced.
+        assert!(!creates_cycle(manager, &ids(&[1]), &links));
+    }
+
     #[test]
     fn user_role_try_new_rejects_invalid_ids() {
         assert_eq!(
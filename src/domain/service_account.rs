@@ -0,0 +1,236 @@
+//! Domain models for service accounts, the non-human principals of a hub.
+
+use chrono::NaiveDateTime;
+use pushkind_common::domain::auth::AuthenticatedUser;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::role::Role;
+use crate::domain::types::{
+    HubId, RoleId, ServiceAccountId, ServiceAccountTokenId, TypeConstraintError,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Integration acting on behalf of a hub, such as the emailer or a CRM sync.
+///
+/// Service accounts have no email or password and cannot sign in; they
+/// authenticate with their `client_id` and secret. Only the hash of the
+/// secret is stored.
+pub struct ServiceAccount {
+    pub id: ServiceAccountId,
+    pub hub_id: HubId,
+    pub name: String,
+    /// Public identifier sent as `client_id`.
+    pub client_id: String,
+    pub secret_hash: String,
+    pub secret_rotated_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub roles: Vec<Role>,
+}
+
+impl ServiceAccount {
+    /// Constructs a service account from validated domain types.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: ServiceAccountId,
+        hub_id: HubId,
+        name: String,
+        client_id: String,
+        secret_hash: String,
+        secret_rotated_at: NaiveDateTime,
+        created_at: NaiveDateTime,
+        roles: Vec<Role>,
+    ) -> Self {
+        Self {
+            id,
+            hub_id,
+            name,
+            client_id,
+            secret_hash,
+            secret_rotated_at,
+            created_at,
+            roles,
+        }
+    }
+
+    /// Validates raw values before constructing a service account.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        id: i32,
+        hub_id: i32,
+        name: String,
+        client_id: String,
+        secret_hash: String,
+        secret_rotated_at: NaiveDateTime,
+        created_at: NaiveDateTime,
+        roles: Vec<Role>,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            ServiceAccountId::try_from(id)?,
+            HubId::try_from(hub_id)?,
+            name,
+            client_id,
+            secret_hash,
+            secret_rotated_at,
+            created_at,
+            roles,
+        ))
+    }
+}
+
+impl From<ServiceAccount> for AuthenticatedUser {
+    /// Claims of a service account: `sub` is its `client_id`, which never
+    /// collides with the numeric ids of users, and `email` is empty.
+    fn from(account: ServiceAccount) -> Self {
+        let mut result = Self {
+            sub: account.client_id,
+            email: String::new(),
+            hub_id: account.hub_id.get(),
+            name: account.name,
+            roles: account
+                .roles
+                .into_iter()
+                .map(|role| role.name.into_inner())
+                .collect(),
+            exp: 0,
+        };
+        result.set_expiration(7);
+        result
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to create a new [`ServiceAccount`].
+pub struct NewServiceAccount {
+    pub hub_id: HubId,
+    pub name: String,
+    pub client_id: String,
+    pub secret_hash: String,
+    pub roles: Vec<RoleId>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Editable attributes of a [`ServiceAccount`]; `roles` replaces the full set.
+pub struct UpdateServiceAccount {
+    pub name: String,
+    pub roles: Vec<RoleId>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Access token issued to a service account by the client credentials
+/// grant. Only the hash of the token is stored.
+pub struct ServiceAccountToken {
+    pub id: ServiceAccountTokenId,
+    pub service_account_id: ServiceAccountId,
+    pub hub_id: HubId,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ServiceAccountToken {
+    /// Constructs a token record from validated domain types.
+    pub fn new(
+        id: ServiceAccountTokenId,
+        service_account_id: ServiceAccountId,
+        hub_id: HubId,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+        revoked_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id,
+            service_account_id,
+            hub_id,
+            token_hash,
+            expires_at,
+            revoked_at,
+            created_at,
+        }
+    }
+
+    /// Validates raw values before constructing a token record.
+    pub fn try_new(
+        id: i32,
+        service_account_id: i32,
+        hub_id: i32,
+        token_hash: String,
+        expires_at: NaiveDateTime,
+        revoked_at: Option<NaiveDateTime>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            ServiceAccountTokenId::try_from(id)?,
+            ServiceAccountId::try_from(service_account_id)?,
+            HubId::try_from(hub_id)?,
+            token_hash,
+            expires_at,
+            revoked_at,
+            created_at,
+        ))
+    }
+
+    /// Returns `true` when the token is neither revoked nor expired at `now`.
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to issue a new [`ServiceAccountToken`].
+pub struct NewServiceAccountToken {
+    pub service_account_id: ServiceAccountId,
+    pub hub_id: HubId,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn service_account_claims_use_client_id_and_role_names() {
+        let now = Utc::now().naive_utc();
+        let account = ServiceAccount::try_new(
+            4,
+            2,
+            "CRM sync".into(),
+            "sa_client".into(),
+            "hash".into(),
+            now,
+            now,
+            vec![Role::try_new(3, "crm", now, now).unwrap()],
+        )
+        .unwrap();
+
+        let claims = AuthenticatedUser::from(account);
+        assert_eq!(claims.sub, "sa_client");
+        assert_eq!(claims.email, "");
+        assert_eq!(claims.hub_id, 2);
+        assert_eq!(claims.name, "CRM sync");
+        assert_eq!(claims.roles, vec!["crm".to_string()]);
+    }
+
+    #[test]
+    fn service_account_token_is_active_until_revoked_or_expired() {
+        let now = Utc::now().naive_utc();
+        let mut token = ServiceAccountToken::try_new(
+            1,
+            4,
+            2,
+            "hash".into(),
+            now + Duration::hours(1),
+            None,
+            now,
+        )
+        .unwrap();
+        assert!(token.is_active(now));
+        assert!(!token.is_active(now + Duration::hours(2)));
+
+        token.revoked_at = Some(now);
+        assert!(!token.is_active(now));
+    }
+}
@@ -0,0 +1,189 @@
+//! Domain models for issued sessions.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{HubId, SessionId, TypeConstraintError, UserId};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Session JWT issued to a user.
+pub struct Session {
+    pub id: SessionId,
+    /// Value of the token's `jti` claim.
+    pub jti: String,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    /// `kid` of the key that signed the token.
+    pub key_id: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_seen_at: Option<NaiveDateTime>,
+    /// Set once the session is revoked; revoked sessions are rejected.
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl Session {
+    /// Constructs a session record from validated domain types.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: SessionId,
+        jti: String,
+        user_id: UserId,
+        hub_id: HubId,
+        key_id: String,
+        created_at: NaiveDateTime,
+        expires_at: NaiveDateTime,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        last_seen_at: Option<NaiveDateTime>,
+        revoked_at: Option<NaiveDateTime>,
+    ) -> Self {
+        Self {
+            id,
+            jti,
+            user_id,
+            hub_id,
+            key_id,
+            created_at,
+            expires_at,
+            user_agent,
+            ip_address,
+            last_seen_at,
+            revoked_at,
+        }
+    }
+
+    /// Validates raw values before constructing a session record.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        id: i32,
+        jti: String,
+        user_id: i32,
+        hub_id: i32,
+        key_id: String,
+        created_at: NaiveDateTime,
+        expires_at: NaiveDateTime,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        last_seen_at: Option<NaiveDateTime>,
+        revoked_at: Option<NaiveDateTime>,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            SessionId::try_from(id)?,
+            jti,
+            UserId::try_from(user_id)?,
+            HubId::try_from(hub_id)?,
+            key_id,
+            created_at,
+            expires_at,
+            user_agent,
+            ip_address,
+            last_seen_at,
+            revoked_at,
+        ))
+    }
+
+    /// Returns `true` when the session is neither revoked nor expired at `now`.
+    pub fn is_live(&self, now: NaiveDateTime) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+/// Client a session is issued to, as reported by the sign-in request.
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to record a newly issued [`Session`].
+pub struct NewSession {
+    pub jti: String,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub key_id: String,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl NewSession {
+    /// Constructs a new session payload from validated domain types.
+    pub fn new(
+        jti: String,
+        user_id: UserId,
+        hub_id: HubId,
+        key_id: String,
+        expires_at: NaiveDateTime,
+        client: ClientInfo,
+    ) -> Self {
+        Self {
+            jti,
+            user_id,
+            hub_id,
+            key_id,
+            expires_at,
+            user_agent: client.user_agent,
+            ip_address: client.ip_address,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    fn make_session(expires_at: NaiveDateTime, revoked_at: Option<NaiveDateTime>) -> Session {
+        let now = Utc::now().naive_utc();
+        Session::try_new(
+            1,
+            "jti".into(),
+            1,
+            1,
+            "default".into(),
+            now,
+            expires_at,
+            None,
+            None,
+            None,
+            revoked_at,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn session_try_new_rejects_invalid_ids() {
+        let now = Utc::now().naive_utc();
+        assert_eq!(
+            Session::try_new(
+                1,
+                "jti".into(),
+                0,
+                1,
+                "default".into(),
+                now,
+                now,
+                None,
+                None,
+                None,
+                None
+            )
+            .unwrap_err(),
+            TypeConstraintError::NonPositiveId
+        );
+    }
+
+    #[test]
+    fn session_is_live_until_revoked_or_expired() {
+        let now = Utc::now().naive_utc();
+        let later = now + Duration::hours(1);
+
+        assert!(make_session(later, None).is_live(now));
+        assert!(!make_session(later, Some(now)).is_live(now));
+        assert!(!make_session(now - Duration::hours(1), None).is_live(now));
+    }
+}
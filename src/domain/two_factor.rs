@@ -0,0 +1,87 @@
+//! Domain models for TOTP second factors.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{TypeConstraintError, UserId};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// TOTP secret attached to a user.
+///
+/// A secret without `enabled_at` is a pending enrollment that has not been
+/// confirmed with a valid code yet and is not enforced at login.
+pub struct UserTotp {
+    pub user_id: UserId,
+    /// Base32-encoded shared secret.
+    pub secret: String,
+    pub enabled_at: Option<NaiveDateTime>,
+    /// Last accepted time step, used to reject replayed codes.
+    pub last_used_step: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+impl UserTotp {
+    /// Constructs a TOTP record from validated domain types.
+    pub fn new(
+        user_id: UserId,
+        secret: String,
+        enabled_at: Option<NaiveDateTime>,
+        last_used_step: Option<i64>,
+        created_at: NaiveDateTime,
+    ) -> Self {
+        Self {
+            user_id,
+            secret,
+            enabled_at,
+            last_used_step,
+            created_at,
+        }
+    }
+
+    /// Validates raw values before constructing a TOTP record.
+    pub fn try_new(
+        user_id: i32,
+        secret: String,
+        enabled_at: Option<NaiveDateTime>,
+        last_used_step: Option<i64>,
+        created_at: NaiveDateTime,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            UserId::try_from(user_id)?,
+            secret,
+            enabled_at,
+            last_used_step,
+            created_at,
+        ))
+    }
+
+    /// Returns `true` once enrollment has been confirmed.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn user_totp_is_enabled_after_confirmation() {
+        let now = Utc::now().naive_utc();
+        let mut totp = UserTotp::try_new(1, "SECRET".to_string(), None, None, now).unwrap();
+        assert!(!totp.is_enabled());
+
+        totp.enabled_at = Some(now);
+        assert!(totp.is_enabled());
+    }
+
+    #[test]
+    fn user_totp_try_new_rejects_invalid_user_id() {
+        let now = Utc::now().naive_utc();
+        assert_eq!(
+            UserTotp::try_new(0, "SECRET".to_string(), None, None, now).unwrap_err(),
+            TypeConstraintError::NonPositiveId
+        );
+    }
+}
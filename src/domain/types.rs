@@ -25,6 +25,18 @@ pub enum TypeConstraintError {
     /// Provided string contained no non-whitespace characters.
     #[error("value cannot be empty")]
     EmptyString,
+    /// Provided token scope is not one of the known scopes.
+    #[error("unknown token scope")]
+    UnknownTokenScope,
+    /// Provided registration mode is not one of the known modes.
+    #[error("unknown registration mode")]
+    UnknownRegistrationMode,
+    /// Provided audit event type is not one of the known types.
+    #[error("unknown audit event type")]
+    UnknownAuditEventType,
+    /// Provided permission is not one of the known permissions.
+    #[error("unknown permission")]
+    UnknownPermission,
 }
 
 /// Macro to generate lightweight newtypes for positive identifiers.
@@ -75,6 +87,18 @@ id_newtype!(UserId);
 id_newtype!(HubId);
 id_newtype!(RoleId);
 id_newtype!(MenuId);
+id_newtype!(PasswordResetId);
+id_newtype!(EmailVerificationId);
+id_newtype!(UserCredentialId);
+id_newtype!(SessionId);
+id_newtype!(OAuthClientId);
+id_newtype!(AuthorizationCodeId);
+id_newtype!(AccessTokenId);
+id_newtype!(PersonalAccessTokenId);
+id_newtype!(ServiceAccountId);
+id_newtype!(ServiceAccountTokenId);
+id_newtype!(InvitationId);
+id_newtype!(AuditEventId);
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 /// Lower-cased and validated email address.
@@ -22,11 +22,23 @@ pub struct User {
     pub password_hash: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    /// When the address was confirmed; [`None`] until the user follows the
+    /// verification link or an admin verifies it by hand.
+    pub email_verified_at: Option<NaiveDateTime>,
+    /// When an admin suspended the account; [`None`] for active users.
+    pub disabled_at: Option<NaiveDateTime>,
+    /// Optional explanation recorded with the suspension.
+    pub disabled_reason: Option<String>,
+    /// When the account was deleted; deleted users are kept until purged.
+    pub deleted_at: Option<NaiveDateTime>,
+    /// `true` when the user has to choose a new password at the next login.
+    pub must_change_password: bool,
     pub roles: Vec<RoleId>,
 }
 
 impl User {
-    /// Constructs a user from validated domain types.
+    /// Constructs a user from validated domain types with an unverified
+    /// address.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: UserId,
@@ -46,6 +58,11 @@ impl User {
             password_hash,
             created_at,
             updated_at,
+            email_verified_at: None,
+            disabled_at: None,
+            disabled_reason: None,
+            deleted_at: None,
+            must_change_password: false,
             roles,
         }
     }
@@ -77,20 +94,43 @@ impl User {
             roles,
         ))
     }
+
+    /// Returns `true` once the address of the user has been verified.
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+
+    /// Returns `true` while the account is suspended.
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
 }
 #[derive(Clone, Serialize)]
 /// Wrapper combining a [`User`] with the fully resolved [`Role`]s attached to
 /// the account.
 pub struct UserWithRoles {
     pub user: User,
+    /// Roles assigned to the account directly.
     pub roles: Vec<Role>,
+    /// Roles implied by [`UserWithRoles::roles`] through their parent roles,
+    /// without the direct ones.
+    pub inherited_roles: Vec<Role>,
 }
 
 impl UserWithRoles {
     /// Constructs a user-with-roles bundle, syncing role IDs on the user.
     pub fn new(mut user: User, roles: Vec<Role>) -> Self {
         user.roles = roles.iter().map(|role| role.id).collect();
-        Self { user, roles }
+        Self {
+            user,
+            roles,
+            inherited_roles: Vec::new(),
+        }
+    }
+
+    pub fn with_inherited_roles(mut self, inherited_roles: Vec<Role>) -> Self {
+        self.inherited_roles = inherited_roles;
+        self
     }
 
     /// Builds a user-with-roles bundle without additional validation.
@@ -106,6 +146,10 @@ pub struct NewUser {
     pub name: Option<UserName>,
     pub hub_id: HubId,
     pub password: UserPassword,
+    /// Roles attached to the user when it is created.
+    pub roles: Vec<RoleId>,
+    /// Makes the user choose a new password at the first login.
+    pub must_change_password: bool,
 }
 
 impl NewUser {
@@ -121,9 +165,23 @@ impl NewUser {
             name,
             hub_id,
             password,
+            roles: Vec::new(),
+            must_change_password: false,
         }
     }
 
+    /// Attaches `roles` to the user being created.
+    pub fn with_roles(mut self, roles: Vec<RoleId>) -> Self {
+        self.roles = roles;
+        self
+    }
+
+    /// Requires the user to choose a new password at the first login.
+    pub fn with_must_change_password(mut self, must_change_password: bool) -> Self {
+        self.must_change_password = must_change_password;
+        self
+    }
+
     /// Validates raw values before constructing a new user payload.
     pub fn try_new(
         email: impl Into<String>,
@@ -146,6 +204,8 @@ pub struct UpdateUser {
     pub name: UserName,
     pub password: Option<UserPassword>,
     pub roles: Option<Vec<RoleId>>,
+    /// Sets or clears the password change requirement; [`None`] keeps it.
+    pub must_change_password: Option<bool>,
 }
 
 impl UpdateUser {
@@ -155,9 +215,17 @@ impl UpdateUser {
             name,
             password,
             roles,
+            must_change_password: None,
         }
     }
 
+    /// Sets or clears the requirement to choose a new password at the next
+    /// login.
+    pub fn with_must_change_password(mut self, must_change_password: bool) -> Self {
+        self.must_change_password = Some(must_change_password);
+        self
+    }
+
     /// Validates raw values before constructing an update payload.
     pub fn try_new(
         name: impl Into<String>,
@@ -197,7 +265,12 @@ impl From<UserWithRoles> for AuthenticatedUser {
             email: ur.user.email.as_str().to_string(),
             hub_id: ur.user.hub_id.get(),
             name: ur.user.name.map(|n| n.into_inner()).unwrap_or_default(),
-            roles: ur.roles.into_iter().map(|r| r.name.into_inner()).collect(),
+            roles: ur
+                .roles
+                .into_iter()
+                .chain(ur.inherited_roles)
+                .map(|r| r.name.into_inner())
+                .collect(),
             exp: 0,
         };
         result.set_expiration(7);
@@ -349,4 +422,28 @@ mod tests {
         assert_eq!(auth.name, "Alice");
         assert!(auth.exp > 0);
     }
+
+    #[test]
+    fn authenticated_user_from_user_with_roles_includes_inherited_roles() {
+        let ts = sample_timestamp();
+        let user = User::new(
+            UserId::new(1).unwrap(),
+            UserEmail::new("test@example.com").unwrap(),
+            None,
+            HubId::new(2).unwrap(),
+            "hash".to_string(),
+            ts,
+            ts,
+            vec![],
+        );
+        let manager = Role::try_new(3, "manager", ts, ts).unwrap();
+        let inherited = vec![
+            Role::try_new(1, "viewer", ts, ts).unwrap(),
+            Role::try_new(2, "editor", ts, ts).unwrap(),
+        ];
+        let bundle = UserWithRoles::new(user, vec![manager]).with_inherited_roles(inherited);
+        assert_eq!(bundle.user.roles, vec![RoleId::new(3).unwrap()]);
+        let auth: AuthenticatedUser = bundle.into();
+        assert_eq!(auth.roles, vec!["manager", "viewer", "editor"]);
+    }
 }
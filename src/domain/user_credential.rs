@@ -0,0 +1,129 @@
+//! Domain models for WebAuthn credentials (passkeys).
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::types::{HubId, TypeConstraintError, UserCredentialId, UserId};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+/// Passkey registered by a user.
+///
+/// The authenticator state is kept as serialized JSON so the domain layer
+/// does not depend on the WebAuthn implementation.
+pub struct UserCredential {
+    pub id: UserCredentialId,
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    /// Hex-encoded credential id reported by the authenticator.
+    pub credential_id: String,
+    /// Label chosen by the user.
+    pub name: String,
+    /// Serialized passkey, including the public key and signature counter.
+    pub passkey: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl UserCredential {
+    /// Constructs a credential record from validated domain types.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        id: UserCredentialId,
+        user_id: UserId,
+        hub_id: HubId,
+        credential_id: String,
+        name: String,
+        passkey: String,
+        created_at: NaiveDateTime,
+        last_used_at: Option<NaiveDateTime>,
+    ) -> Self {
+        Self {
+            id,
+            user_id,
+            hub_id,
+            credential_id,
+            name,
+            passkey,
+            created_at,
+            last_used_at,
+        }
+    }
+
+    /// Validates raw values before constructing a credential record.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        id: i32,
+        user_id: i32,
+        hub_id: i32,
+        credential_id: String,
+        name: String,
+        passkey: String,
+        created_at: NaiveDateTime,
+        last_used_at: Option<NaiveDateTime>,
+    ) -> Result<Self, TypeConstraintError> {
+        Ok(Self::new(
+            UserCredentialId::try_from(id)?,
+            UserId::try_from(user_id)?,
+            HubId::try_from(hub_id)?,
+            credential_id,
+            name,
+            passkey,
+            created_at,
+            last_used_at,
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Data required to store a newly registered [`UserCredential`].
+pub struct NewUserCredential {
+    pub user_id: UserId,
+    pub hub_id: HubId,
+    pub credential_id: String,
+    pub name: String,
+    pub passkey: String,
+}
+
+impl NewUserCredential {
+    /// Constructs a new credential payload from validated domain types.
+    pub fn new(
+        user_id: UserId,
+        hub_id: HubId,
+        credential_id: String,
+        name: String,
+        passkey: String,
+    ) -> Self {
+        Self {
+            user_id,
+            hub_id,
+            credential_id,
+            name,
+            passkey,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn user_credential_try_new_rejects_invalid_ids() {
+        let now = Utc::now().naive_utc();
+        assert_eq!(
+            UserCredential::try_new(
+                1,
+                1,
+                0,
+                "cred".to_string(),
+                "Laptop".to_string(),
+                "{}".to_string(),
+                now,
+                None,
+            )
+            .unwrap_err(),
+            TypeConstraintError::NonPositiveId
+        );
+    }
+}
@@ -9,4 +9,6 @@ use crate::domain::user::User;
 pub struct UserModalData {
     pub user: Option<User>,
     pub roles: Vec<Role>,
+    /// Roles the user holds through the parents of their own roles.
+    pub inherited_roles: Vec<Role>,
 }
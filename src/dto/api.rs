@@ -1,18 +1,55 @@
 //! DTOs exposed by the REST API.
 
-use crate::domain::hub::Hub;
+use crate::domain::audit::{AuditEvent, AuditEventType};
+use crate::domain::hub::{Hub, HubPolicy};
+use crate::domain::invitation::Invitation;
 use crate::domain::menu::Menu;
-use crate::domain::role::Role;
+use crate::domain::oauth::OAuthClient;
+use crate::domain::permission::Permission;
+use crate::domain::personal_token::{PersonalAccessToken, TokenScope};
+use crate::domain::role::{Role, RoleChangeSummary};
+use crate::domain::service_account::ServiceAccount;
+use crate::domain::session::Session;
+use crate::domain::types::UserName;
+use crate::domain::user::User;
+use crate::domain::user_credential::UserCredential;
+use crate::repository::UserStatusFilter;
+use chrono::{Duration, NaiveDateTime};
 use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::dto::shell::IamDto;
 use serde::{Deserialize, Serialize};
 
+/// Response of `GET /api/v1/iam`: the shared shell data with the permissions
+/// the current user holds through their roles.
+#[derive(Serialize)]
+pub struct ApiV1IamDto {
+    #[serde(flatten)]
+    pub shell: IamDto,
+    pub permissions: Vec<Permission>,
+}
+
 #[derive(Deserialize)]
 pub struct ApiV1UsersQueryParams {
+    /// Suspension state of the listed users; active users when omitted.
+    pub status: Option<UserStatusFilter>,
     pub role: Option<String>,
     pub query: Option<String>,
     pub page: Option<usize>,
 }
 
+/// Filters accepted by `GET /api/v1/admin/audit`.
+#[derive(Deserialize)]
+pub struct ApiV1AuditQueryParams {
+    pub event_type: Option<AuditEventType>,
+    pub actor_id: Option<i32>,
+    pub target_id: Option<i32>,
+    /// Only events recorded at or after this time.
+    pub since: Option<NaiveDateTime>,
+    /// Only events recorded before this time.
+    pub until: Option<NaiveDateTime>,
+    pub page: Option<usize>,
+}
+
 /// DTO returned by API endpoints representing a user with roles and hub context.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct UserDto {
@@ -75,6 +112,18 @@ pub struct AdminRoleItemDto {
     pub id: i32,
     pub name: String,
     pub can_delete: bool,
+    /// Permissions granted to the role.
+    pub permissions: Vec<Permission>,
+    /// Whether the role is a system role shared by every hub.
+    pub system: bool,
+    pub description: String,
+    /// Number of users of the current hub holding the role.
+    pub user_count: usize,
+    /// Parent roles the role declares directly.
+    pub parents: Vec<i32>,
+    /// Every role implied by the role through its parents, transitively,
+    /// the direct parents included.
+    pub inherited: Vec<i32>,
 }
 
 impl From<Role> for AdminRoleItemDto {
@@ -84,6 +133,34 @@ impl From<Role> for AdminRoleItemDto {
             id,
             name: role.name.into_inner(),
             can_delete: id != 1,
+            permissions: Vec::new(),
+            system: role.hub_id.is_none(),
+            description: role.description,
+            user_count: 0,
+            parents: Vec::new(),
+            inherited: Vec::new(),
+        }
+    }
+}
+
+/// User of the admin's hub holding a role, returned by
+/// `GET /api/v1/admin/roles/{id}/members`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminRoleMemberDto {
+    pub id: i32,
+    pub email: String,
+    pub name: Option<String>,
+    /// Whether the account is suspended.
+    pub suspended: bool,
+}
+
+impl From<User> for AdminRoleMemberDto {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id.get(),
+            email: user.email.into_inner(),
+            name: user.name.map(UserName::into_inner),
+            suspended: user.disabled_at.is_some(),
         }
     }
 }
@@ -130,8 +207,347 @@ impl From<Menu> for AdminMenuItemDto {
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct AdminDashboardDto {
     pub roles: Vec<AdminRoleItemDto>,
+    /// Every permission a role can be granted.
+    pub permissions: Vec<Permission>,
     pub hubs: Vec<AdminHubItemDto>,
     pub admin_menu: Vec<AdminMenuItemDto>,
+    /// Security policy of the current hub.
+    pub hub_policy: HubPolicy,
+    pub oauth_clients: Vec<AdminOAuthClientDto>,
+    /// Service accounts of the current hub.
+    pub service_accounts: Vec<AdminServiceAccountDto>,
+}
+
+/// OpenID Connect client registered with the provider, without its secret.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminOAuthClientDto {
+    pub id: i32,
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    /// `true` for clients authenticating with a secret.
+    pub confidential: bool,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<OAuthClient> for AdminOAuthClientDto {
+    fn from(client: OAuthClient) -> Self {
+        Self {
+            id: client.id.get(),
+            confidential: !client.is_public(),
+            client_id: client.client_id,
+            name: client.name,
+            redirect_uris: client.redirect_uris,
+            created_at: client.created_at,
+        }
+    }
+}
+
+/// Service account of the admin's hub, without its secret.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminServiceAccountDto {
+    pub id: i32,
+    pub client_id: String,
+    pub name: String,
+    /// Ids of the assigned roles.
+    pub roles: Vec<i32>,
+    pub secret_rotated_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<ServiceAccount> for AdminServiceAccountDto {
+    fn from(account: ServiceAccount) -> Self {
+        Self {
+            id: account.id.get(),
+            client_id: account.client_id,
+            name: account.name,
+            roles: account.roles.iter().map(|role| role.id.get()).collect(),
+            secret_rotated_at: account.secret_rotated_at,
+            created_at: account.created_at,
+        }
+    }
+}
+
+/// Pending invitation of the admin's hub, without its token.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminInvitationDto {
+    pub id: i32,
+    pub email: String,
+    pub name: String,
+    /// Ids of the roles the invitee receives.
+    pub roles: Vec<i32>,
+    pub expires_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<Invitation> for AdminInvitationDto {
+    fn from(invitation: Invitation) -> Self {
+        Self {
+            id: invitation.id.get(),
+            email: invitation.email.as_str().to_string(),
+            name: invitation.name.into_inner(),
+            roles: invitation.roles.iter().map(|role| role.get()).collect(),
+            expires_at: invitation.expires_at,
+            created_at: invitation.created_at,
+        }
+    }
+}
+
+/// Result of adding a role to or removing it from many users at once.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RoleChangeSummaryDto {
+    pub message: String,
+    /// Number of users whose roles changed.
+    pub changed: usize,
+    /// Number of selected users that needed no change.
+    pub unchanged: usize,
+    /// Requested ids that are not live users of the hub.
+    pub not_found: Vec<i32>,
+}
+
+impl RoleChangeSummaryDto {
+    pub fn new(message: impl Into<String>, summary: RoleChangeSummary) -> Self {
+        Self {
+            message: message.into(),
+            changed: summary.changed.len(),
+            unchanged: summary.unchanged,
+            not_found: summary.not_found.into_iter().map(|id| id.get()).collect(),
+        }
+    }
+}
+
+/// Credentials of a service account.
+///
+/// The secret is only returned once; a lost secret has to be rotated.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ServiceAccountCredentialsDto {
+    pub message: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Service account listed next to the users of a hub.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ServiceAccountDto {
+    pub client_id: String,
+    pub name: String,
+    pub hub_id: i32,
+    pub roles: Vec<String>,
+}
+
+impl From<ServiceAccount> for ServiceAccountDto {
+    fn from(account: ServiceAccount) -> Self {
+        Self {
+            client_id: account.client_id,
+            name: account.name,
+            hub_id: account.hub_id.get(),
+            roles: account
+                .roles
+                .into_iter()
+                .map(|role| role.name.into_inner())
+                .collect(),
+        }
+    }
+}
+
+/// Passkey registered by the current user, without authenticator state.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PasskeyListItemDto {
+    pub id: i32,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+impl From<UserCredential> for PasskeyListItemDto {
+    fn from(credential: UserCredential) -> Self {
+        Self {
+            id: credential.id.get(),
+            name: credential.name,
+            created_at: credential.created_at,
+            last_used_at: credential.last_used_at,
+        }
+    }
+}
+
+/// Live personal access token of the current user.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PersonalTokenListItemDto {
+    pub id: i32,
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+}
+
+impl From<PersonalAccessToken> for PersonalTokenListItemDto {
+    fn from(token: PersonalAccessToken) -> Self {
+        Self {
+            id: token.id.get(),
+            name: token.name,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+/// Newly issued personal access token.
+///
+/// The token itself is only returned once; a lost token has to be revoked
+/// and issued again.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PersonalTokenCreatedDto {
+    pub message: String,
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+}
+
+/// Live session of the current user.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SessionListItemDto {
+    pub id: i32,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    /// `true` for the session making the request.
+    pub current: bool,
+}
+
+/// JWT key accepted by the service and the number of live sessions it signed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SigningKeyDto {
+    pub key_id: String,
+    pub algorithm: String,
+    /// `true` for the key that signs new sessions.
+    pub active: bool,
+    pub live_sessions: usize,
+}
+
+/// Live session of the admin's hub with the key that signed it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminSessionDto {
+    pub id: i32,
+    pub user_id: i32,
+    pub key_id: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl From<Session> for AdminSessionDto {
+    fn from(session: Session) -> Self {
+        Self {
+            id: session.id.get(),
+            user_id: session.user_id.get(),
+            key_id: session.key_id,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+/// Payload returned by `GET /api/v1/admin/sessions`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminSessionsDto {
+    pub keys: Vec<SigningKeyDto>,
+    pub sessions: Vec<AdminSessionDto>,
+}
+
+/// Deleted user of the admin's hub that can still be restored.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminDeletedUserDto {
+    pub id: i32,
+    pub email: String,
+    pub name: Option<String>,
+    pub deleted_at: NaiveDateTime,
+    /// When the purge job removes the user for good.
+    pub purge_at: NaiveDateTime,
+}
+
+impl AdminDeletedUserDto {
+    /// Describes a deleted `user` kept for `retention` after deletion.
+    pub fn new(user: User, retention: Duration) -> Self {
+        let deleted_at = user.deleted_at.unwrap_or(user.updated_at);
+        Self {
+            id: user.id.get(),
+            email: user.email.into_inner(),
+            name: user.name.map(UserName::into_inner),
+            deleted_at,
+            purge_at: deleted_at + retention,
+        }
+    }
+}
+
+/// Deleted hub that can still be restored.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminDeletedHubDto {
+    pub id: i32,
+    pub name: String,
+    pub deleted_at: NaiveDateTime,
+    /// When the purge job removes the hub for good.
+    pub purge_at: NaiveDateTime,
+}
+
+impl AdminDeletedHubDto {
+    /// Describes a deleted `hub` kept for `retention` after deletion.
+    pub fn new(hub: Hub, retention: Duration) -> Self {
+        let deleted_at = hub.deleted_at.unwrap_or(hub.updated_at);
+        Self {
+            id: hub.id.get(),
+            name: hub.name.into_inner(),
+            deleted_at,
+            purge_at: deleted_at + retention,
+        }
+    }
+}
+
+/// Payload returned by `GET /api/v1/admin/deleted`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminDeletedDto {
+    pub users: Vec<AdminDeletedUserDto>,
+    pub hubs: Vec<AdminDeletedHubDto>,
+}
+
+/// Recorded event of the admin's hub.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminAuditEventDto {
+    pub id: i32,
+    pub event_type: AuditEventType,
+    pub actor_id: Option<i32>,
+    pub target_id: Option<i32>,
+    pub detail: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AuditEvent> for AdminAuditEventDto {
+    fn from(event: AuditEvent) -> Self {
+        Self {
+            id: event.id.get(),
+            event_type: event.event_type,
+            actor_id: event.actor_id.map(|id| id.get()),
+            target_id: event.target_id,
+            detail: event.detail,
+            ip_address: event.ip_address,
+            user_agent: event.user_agent,
+            created_at: event.created_at,
+        }
+    }
+}
+
+/// Payload returned by `GET /api/v1/admin/audit`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AdminAuditDto {
+    /// Number of events matching the filters across all pages.
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+    pub events: Vec<AdminAuditEventDto>,
 }
 
 #[cfg(test)]
@@ -197,11 +613,16 @@ mod tests {
 
         let dto = AdminDashboardDto {
             roles: vec![AdminRoleItemDto::from(role)],
+            permissions: Permission::ALL.to_vec(),
             hubs: vec![AdminHubItemDto::from(hub)],
             admin_menu: vec![AdminMenuItemDto::from(menu)],
+            hub_policy: HubPolicy::default(),
+            oauth_clients: Vec::new(),
+            service_accounts: Vec::new(),
         };
 
         assert_eq!(dto.roles.len(), 1);
+        assert!(dto.roles[0].system);
         assert_eq!(dto.hubs.len(), 1);
         assert_eq!(dto.admin_menu.len(), 1);
     }
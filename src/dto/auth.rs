@@ -1,6 +1,7 @@
 //! Authentication-related DTOs.
 
 use serde::{Deserialize, Serialize};
+use webauthn_rs::prelude::{PasskeyAuthentication, PasskeyRegistration};
 
 /// DTO carrying an issued session token.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -13,3 +14,55 @@ impl From<String> for SessionTokenDto {
         Self { token }
     }
 }
+
+/// Login that passed the password check but still needs a second factor or
+/// a new password.
+///
+/// Kept in the session between the password and the following step.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PendingLoginDto {
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub email: String,
+    /// `true` when the user has to enroll a second factor before signing in.
+    pub setup_required: bool,
+    /// Unix timestamp after which the pending login is discarded.
+    pub expires_at: i64,
+}
+
+/// DTO returned when TOTP enrollment starts.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TotpEnrollmentDto {
+    /// Base32 secret for manual entry.
+    pub secret: String,
+    /// `otpauth://` URI understood by authenticator apps.
+    pub otpauth_uri: String,
+    /// SVG rendering of `otpauth_uri` as a QR code.
+    pub qr_svg: String,
+}
+
+/// DTO returned when TOTP enrollment is confirmed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TotpRecoveryCodesDto {
+    pub message: String,
+    /// One-time recovery codes; they are not retrievable later.
+    pub recovery_codes: Vec<String>,
+    pub redirect_to: Option<String>,
+}
+
+/// Passkey registration ceremony in progress, kept in the session between
+/// `register/start` and `register/finish`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PasskeyRegistrationStateDto {
+    pub user_id: i32,
+    pub state: PasskeyRegistration,
+}
+
+/// Passkey sign-in ceremony in progress, kept in the session between
+/// `login/start` and `login/finish`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PasskeyLoginStateDto {
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub state: PasskeyAuthentication,
+}
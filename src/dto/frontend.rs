@@ -1,5 +1,6 @@
 //! DTOs used by frontend-facing routes that still return JSON payloads.
 
+use chrono::NaiveDateTime;
 use serde::Serialize;
 
 use crate::domain::role::Role;
@@ -26,8 +27,16 @@ impl From<Role> for RoleOptionDto {
 pub struct AdminEditableUserDto {
     pub id: i32,
     pub email: String,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub disabled_reason: Option<String>,
+    pub must_change_password: bool,
     pub name: String,
+    /// Roles assigned to the user directly.
     pub roles: Vec<i32>,
+    /// Roles implied by [`AdminEditableUserDto::roles`] through their parent
+    /// roles.
+    pub inherited_roles: Vec<i32>,
 }
 
 impl From<User> for AdminEditableUserDto {
@@ -35,12 +44,17 @@ impl From<User> for AdminEditableUserDto {
         Self {
             id: user.id.get(),
             email: user.email.into_inner(),
+            email_verified_at: user.email_verified_at,
+            disabled_at: user.disabled_at,
+            disabled_reason: user.disabled_reason,
+            must_change_password: user.must_change_password,
             name: user.name.map(|name| name.into_inner()).unwrap_or_default(),
             roles: user
                 .roles
                 .into_iter()
                 .map(|role_id| role_id.get())
                 .collect(),
+            inherited_roles: Vec::new(),
         }
     }
 }
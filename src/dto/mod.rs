@@ -7,3 +7,4 @@ pub mod api;
 pub mod auth;
 pub mod frontend;
 pub mod main;
+pub mod oauth;
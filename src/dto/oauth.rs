@@ -0,0 +1,125 @@
+//! DTOs exchanged with OpenID Connect clients.
+
+use pushkind_common::domain::auth::AuthenticatedUser;
+use serde::{Deserialize, Serialize};
+
+/// Discovery document served at `/.well-known/openid-configuration`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OpenIdConfigurationDto {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub introspection_endpoint: String,
+    pub revocation_endpoint: String,
+    pub jwks_uri: String,
+    pub response_types_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub scopes_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+}
+
+/// Successful response of `POST /oauth/token`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TokenResponseDto {
+    pub access_token: String,
+    pub token_type: String,
+    /// Lifetime of the access token in seconds.
+    pub expires_in: i64,
+    pub id_token: String,
+    pub scope: String,
+}
+
+/// Successful response of `POST /oauth/token` for the client credentials
+/// grant of a service account.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ClientCredentialsTokenDto {
+    pub access_token: String,
+    pub token_type: String,
+    /// Lifetime of the access token in seconds.
+    pub expires_in: i64,
+}
+
+/// Claims of an ID token.
+///
+/// Besides the standard claims the token carries the user's hub and roles,
+/// the same values a session JWT holds.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct IdTokenClaimsDto {
+    pub iss: String,
+    pub sub: String,
+    /// `client_id` of the client the token was issued to.
+    pub aud: String,
+    pub exp: i64,
+    pub iat: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    pub email: String,
+    pub name: String,
+    pub hub_id: i32,
+    pub roles: Vec<String>,
+}
+
+/// Response of `GET /oauth/userinfo`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct UserInfoDto {
+    pub sub: String,
+    pub email: String,
+    pub name: String,
+    pub hub_id: i32,
+    pub roles: Vec<String>,
+}
+
+/// Response of `POST /oauth/introspect` (RFC 7662).
+///
+/// Active tokens carry the current claims of their user, the same fields a
+/// session JWT holds, with `exp` set to the token's own expiry. Inactive
+/// tokens only report `active: false`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IntrospectionDto {
+    pub active: bool,
+    #[serde(flatten)]
+    pub user: Option<AuthenticatedUser>,
+    /// `Bearer` for OAuth access tokens, `service_account` for service
+    /// account tokens, `session` for session JWTs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    /// Scope granted to an OAuth access token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl IntrospectionDto {
+    /// Response for unknown, expired, or revoked tokens.
+    pub fn inactive() -> Self {
+        Self {
+            active: false,
+            user: None,
+            token_type: None,
+            scope: None,
+        }
+    }
+}
+
+/// Error body defined by RFC 6749.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OAuthErrorDto {
+    pub error: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+}
+
+/// Credentials of a newly registered client.
+///
+/// The secret is only returned once; confidential clients that lose it must
+/// be registered again.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct OAuthClientCredentialsDto {
+    pub message: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+}
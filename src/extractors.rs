@@ -0,0 +1,91 @@
+//! Request extractors used by the route handlers.
+
+use std::ops::Deref;
+
+use actix_identity::IdentityExt;
+use actix_web::{
+    Error, FromRequest, HttpMessage, HttpRequest,
+    dev::Payload,
+    error::{ErrorInternalServerError, ErrorUnauthorized},
+    web,
+};
+use futures_util::future::{Ready, ready};
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::ServiceError;
+
+use crate::repository::DieselRepository;
+use crate::services::jwt::{JwtKeys, SessionClaims};
+use crate::services::session as session_service;
+
+/// Claims of the signed-in user, read from the identity cookie and verified
+/// with the service's own [`JwtKeys`].
+///
+/// The [`AuthenticatedUser`] extractor only accepts `HS256` tokens signed
+/// with the shared secret, so handlers of this service extract `SessionUser`
+/// to also accept sessions signed with `RS256` or `EdDSA`. Sessions that were
+/// revoked are rejected like invalid tokens.
+pub struct SessionUser(pub AuthenticatedUser);
+
+impl Deref for SessionUser {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequest for SessionUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(extract_session_claims(req).map(|claims| SessionUser(claims.user)))
+    }
+}
+
+/// Full claims of the signed-in user's session, including its `jti`.
+impl FromRequest for SessionClaims {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(extract_session_claims(req))
+    }
+}
+
+/// Verifies the identity token and its session in the registry.
+///
+/// The result is cached in the request extensions, so the middleware and the
+/// handler of one request check the registry only once.
+fn extract_session_claims(req: &HttpRequest) -> Result<SessionClaims, Error> {
+    if let Some(claims) = req.extensions().get::<SessionClaims>() {
+        return Ok(claims.clone());
+    }
+
+    let token = req
+        .get_identity()
+        .ok()
+        .and_then(|identity| identity.id().ok())
+        .ok_or_else(|| ErrorUnauthorized("Unauthorized"))?;
+    let keys = req
+        .app_data::<web::Data<JwtKeys>>()
+        .ok_or_else(|| ErrorInternalServerError("JWT keys not found"))?;
+    let repo = req
+        .app_data::<web::Data<DieselRepository>>()
+        .ok_or_else(|| ErrorInternalServerError("DB repo not found"))?;
+
+    let claims = keys
+        .decode(&token)
+        .map_err(|_| ErrorUnauthorized("Invalid token"))?;
+    match session_service::verify_session(&claims, repo.get_ref()) {
+        Ok(_) => {}
+        Err(ServiceError::Unauthorized) => return Err(ErrorUnauthorized("Invalid session")),
+        Err(e) => {
+            log::error!("Failed to verify session: {e}");
+            return Err(ErrorInternalServerError("Failed to verify session"));
+        }
+    }
+
+    req.extensions_mut().insert(claims.clone());
+    Ok(claims)
+}
@@ -1,9 +1,12 @@
 //! Authentication-related request payloads.
 //!
-//! These types validate login, registration, and password recovery inputs
+//! These types validate login, registration, email verification, password
+//! recovery, reset, required password changes, invitation acceptance, second
+//! factor, and passkey inputs
 //! before they are transformed into domain types.
 use serde::Deserialize;
 use validator::Validate;
+use webauthn_rs::prelude::RegisterPublicKeyCredential;
 
 use crate::domain::types::{HubId, UserEmail, UserPassword};
 use crate::domain::user::NewUser as DomainNewUser;
@@ -45,6 +48,21 @@ pub struct RegisterPayload {
     pub hub_id: HubId,
 }
 
+#[derive(Deserialize, Validate, Clone)]
+/// Form data used to request a new email verification link.
+pub struct ResendVerificationForm {
+    #[validate(email(message = "Укажите корректный электронный адрес."))]
+    pub email: String,
+    #[validate(range(min = 1, message = "Выберите хаб."))]
+    pub hub_id: i32,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct ResendVerificationPayload {
+    pub email: UserEmail,
+    pub hub_id: HubId,
+}
+
 #[derive(Deserialize, Validate, Clone)]
 /// Form data used to recover a forgotten password.
 pub struct RecoverForm {
@@ -60,6 +78,97 @@ pub struct RecoverPayload {
     pub hub_id: HubId,
 }
 
+#[derive(Deserialize, Validate, Clone)]
+/// Form data used to set a new password from a recovery link.
+pub struct ResetPasswordForm {
+    #[validate(length(min = 1, message = "Ссылка для восстановления недействительна."))]
+    pub token: String,
+    #[validate(length(min = 1, message = "Введите пароль."))]
+    pub password: String,
+    #[validate(must_match(other = "password", message = "Пароли не совпадают."))]
+    pub confirm_password: String,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct ResetPasswordPayload {
+    pub token: String,
+    pub password: UserPassword,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form data submitted when a user has to replace the password before
+/// signing in.
+pub struct ChangePasswordForm {
+    #[validate(length(min = 1, message = "Введите пароль."))]
+    pub password: String,
+    #[validate(must_match(other = "password", message = "Пароли не совпадают."))]
+    pub confirm_password: String,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct ChangePasswordPayload {
+    pub password: UserPassword,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form data submitted when an invitee sets the password of the new account.
+pub struct AcceptInvitationForm {
+    #[validate(length(min = 1, message = "Приглашение недействительно."))]
+    pub token: String,
+    #[validate(length(min = 1, message = "Введите пароль."))]
+    pub password: String,
+    #[validate(must_match(other = "password", message = "Пароли не совпадают."))]
+    pub confirm_password: String,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct AcceptInvitationPayload {
+    pub token: String,
+    pub password: UserPassword,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form data carrying a TOTP or recovery code.
+pub struct TwoFactorCodeForm {
+    #[validate(length(min = 1, message = "Введите код."))]
+    pub code: String,
+}
+
+// Payload after validation.
+pub struct TwoFactorCodePayload {
+    pub code: String,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form data identifying the account that signs in with a passkey.
+pub struct PasskeyLoginForm {
+    #[validate(email(message = "Укажите корректный электронный адрес."))]
+    pub email: String,
+    #[validate(range(min = 1, message = "Выберите хаб."))]
+    pub hub_id: i32,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct PasskeyLoginPayload {
+    pub email: UserEmail,
+    pub hub_id: HubId,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// JSON body completing a passkey registration.
+pub struct PasskeyRegistrationForm {
+    #[validate(length(min = 1, max = 100, message = "Укажите название ключа."))]
+    pub name: String,
+    /// Attestation returned by `navigator.credentials.create`.
+    pub credential: RegisterPublicKeyCredential,
+}
+
+// Payload after validation.
+pub struct PasskeyRegistrationPayload {
+    pub name: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
 impl TryFrom<LoginForm> for LoginPayload {
     type Error = FormError;
 
@@ -86,6 +195,18 @@ impl TryFrom<RegisterForm> for RegisterPayload {
     }
 }
 
+impl TryFrom<ResendVerificationForm> for ResendVerificationPayload {
+    type Error = FormError;
+
+    fn try_from(form: ResendVerificationForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            email: UserEmail::new(form.email).map_err(|_| FormError::InvalidEmail)?,
+            hub_id: HubId::new(form.hub_id).map_err(|_| FormError::InvalidHubId)?,
+        })
+    }
+}
+
 impl TryFrom<RecoverForm> for RecoverPayload {
     type Error = FormError;
 
@@ -98,6 +219,80 @@ impl TryFrom<RecoverForm> for RecoverPayload {
     }
 }
 
+impl TryFrom<ResetPasswordForm> for ResetPasswordPayload {
+    type Error = FormError;
+
+    fn try_from(form: ResetPasswordForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            token: form.token,
+            password: UserPassword::new(form.password).map_err(|_| FormError::InvalidPassword)?,
+        })
+    }
+}
+
+impl TryFrom<ChangePasswordForm> for ChangePasswordPayload {
+    type Error = FormError;
+
+    fn try_from(form: ChangePasswordForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            password: UserPassword::new(form.password).map_err(|_| FormError::InvalidPassword)?,
+        })
+    }
+}
+
+impl TryFrom<AcceptInvitationForm> for AcceptInvitationPayload {
+    type Error = FormError;
+
+    fn try_from(form: AcceptInvitationForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            token: form.token,
+            password: UserPassword::new(form.password).map_err(|_| FormError::InvalidPassword)?,
+        })
+    }
+}
+
+impl TryFrom<TwoFactorCodeForm> for TwoFactorCodePayload {
+    type Error = FormError;
+
+    fn try_from(form: TwoFactorCodeForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            code: form.code.trim().to_string(),
+        })
+    }
+}
+
+impl TryFrom<PasskeyLoginForm> for PasskeyLoginPayload {
+    type Error = FormError;
+
+    fn try_from(form: PasskeyLoginForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            email: UserEmail::new(form.email).map_err(|_| FormError::InvalidEmail)?,
+            hub_id: HubId::new(form.hub_id).map_err(|_| FormError::InvalidHubId)?,
+        })
+    }
+}
+
+impl TryFrom<PasskeyRegistrationForm> for PasskeyRegistrationPayload {
+    type Error = FormError;
+
+    fn try_from(form: PasskeyRegistrationForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        let name = form.name.trim().to_string();
+        if name.is_empty() {
+            return Err(FormError::InvalidName);
+        }
+        Ok(Self {
+            name,
+            credential: form.credential,
+        })
+    }
+}
+
 impl From<RegisterPayload> for DomainNewUser {
     fn from(payload: RegisterPayload) -> Self {
         Self::new(payload.email, None, payload.hub_id, payload.password)
@@ -108,7 +303,9 @@ impl From<RegisterPayload> for DomainNewUser {
 mod tests {
     use crate::domain::types::{HubId, UserEmail, UserPassword};
     use crate::domain::user::NewUser as DomainNewUser;
-    use crate::forms::auth::{RegisterForm, RegisterPayload};
+    use crate::forms::auth::{
+        RegisterForm, RegisterPayload, ResetPasswordForm, ResetPasswordPayload,
+    };
     use validator::Validate;
 
     #[test]
@@ -163,4 +360,38 @@ mod tests {
         };
         assert!(form.validate().is_err())
     }
+
+    #[test]
+    fn test_reset_password_form_into_payload() {
+        let form = ResetPasswordForm {
+            token: "token".to_string(),
+            password: "secret".to_string(),
+            confirm_password: "secret".to_string(),
+        };
+
+        let payload: ResetPasswordPayload = form.try_into().expect("conversion failed");
+
+        assert_eq!(payload.token, "token");
+        assert_eq!(payload.password, UserPassword::new("secret").unwrap());
+    }
+
+    #[test]
+    fn test_reset_password_form_requires_matching_confirmation() {
+        let form = ResetPasswordForm {
+            token: "token".to_string(),
+            password: "secret".to_string(),
+            confirm_password: "other".to_string(),
+        };
+        assert!(form.validate().is_err())
+    }
+
+    #[test]
+    fn test_reset_password_form_requires_token() {
+        let form = ResetPasswordForm {
+            token: "".to_string(),
+            password: "secret".to_string(),
+            confirm_password: "secret".to_string(),
+        };
+        assert!(form.validate().is_err())
+    }
 }
@@ -1,19 +1,28 @@
 //! Forms backing the main application views and administrative pages.
 //!
-//! These payloads validate profile updates, role assignments, and hub or menu
-//! creation before handing data off to the service layer.
+//! These payloads validate profile updates, personal access tokens, user
+//! creation, role editing, inheritance and assignments, hub policies and registration settings, hub or menu creation, OpenID Connect client
+//! registration, service accounts, user invitations, and suspensions before
+//! handing data off to the service layer.
 use pushkind_common::routes::empty_string_as_none;
 use serde::Deserialize;
+use url::Url;
 use validator::Validate;
 
+use crate::domain::permission::Permission;
+use crate::domain::personal_token::TokenScope;
 use crate::domain::types::{
-    HubId, HubName, MenuName, MenuUrl, RoleId, RoleName, UserName, UserPassword,
+    HubId, HubName, MenuName, MenuUrl, RoleId, RoleName, UserEmail, UserId, UserName, UserPassword,
 };
 use crate::domain::{
-    hub::NewHub as DomainNewHub, menu::NewMenu as DomainNewMenu, role::NewRole as DomainNewRole,
+    hub::HubPolicy as DomainHubPolicy,
+    hub::HubRegistrationSettings as DomainHubRegistrationSettings, hub::NewHub as DomainNewHub,
+    hub::RegistrationMode, menu::NewMenu as DomainNewMenu, role::NewRole as DomainNewRole,
+    role::UpdateRole as DomainUpdateRole, user::NewUser as DomainNewUser,
     user::UpdateUser as DomainUpdateUser,
 };
 use crate::forms::FormError;
+use crate::repository::{UserListQuery, UserSelection, UserStatusFilter};
 
 #[derive(Deserialize, Validate, Clone)]
 /// Form used on the profile page to update the current user.
@@ -39,6 +48,12 @@ pub struct UpdateUserForm {
     pub password: Option<String>,
     #[serde(default)]
     pub roles: Vec<i32>,
+    /// Removes the user's second factor so it can be enrolled again.
+    #[serde(default)]
+    pub reset_two_factor: bool,
+    /// Makes the user choose a new password at the next login.
+    #[serde(default)]
+    pub must_change_password: bool,
 }
 
 // Payload after validation and conversion to domain types.
@@ -46,6 +61,33 @@ pub struct UpdateUserPayload {
     pub name: UserName,
     pub password: Option<UserPassword>,
     pub roles: Option<Vec<RoleId>>,
+    pub reset_two_factor: bool,
+    pub must_change_password: bool,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form used by administrators to create a user in the current hub.
+pub struct AddUserForm {
+    #[validate(email(message = "Укажите корректный электронный адрес."))]
+    pub email: String,
+    #[validate(length(min = 1, max = 100, message = "Укажите имя."))]
+    pub name: String,
+    #[validate(length(min = 1, message = "Введите пароль."))]
+    pub password: String,
+    #[serde(default)]
+    pub roles: Vec<i32>,
+    /// Makes the user choose a new password at the first login.
+    #[serde(default)]
+    pub must_change_password: bool,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct AddUserPayload {
+    pub email: UserEmail,
+    pub name: UserName,
+    pub password: UserPassword,
+    pub roles: Vec<RoleId>,
+    pub must_change_password: bool,
 }
 
 #[derive(Deserialize, Validate, Clone)]
@@ -53,11 +95,72 @@ pub struct UpdateUserPayload {
 pub struct AddRoleForm {
     #[validate(length(min = 1, message = "Укажите имя."))]
     pub name: String,
+    /// Names of the granted permissions, see [`Permission`].
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub description: String,
 }
 
 // Payload after validation and conversion to domain types.
 pub struct AddRolePayload {
     pub name: RoleName,
+    pub permissions: Vec<Permission>,
+    pub description: String,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form used to rename a role and change its description.
+pub struct UpdateRoleForm {
+    #[validate(length(min = 1, message = "Укажите имя."))]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct UpdateRolePayload {
+    pub name: RoleName,
+    pub description: String,
+}
+
+#[derive(Deserialize, Clone)]
+/// Parent roles implied by a role, sent as repeated `parents` fields; an
+/// empty form removes every parent.
+pub struct RoleParentsForm {
+    #[serde(default)]
+    pub parents: Vec<i32>,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct RoleParentsPayload {
+    pub parents: Vec<RoleId>,
+}
+
+#[derive(Deserialize, Clone)]
+/// Users a role is added to or removed from at once: the repeated
+/// `user_ids`, or every user matching `status`, `role` and `query` when
+/// `all_matching` is set.
+pub struct BulkRoleForm {
+    #[serde(default)]
+    pub user_ids: Vec<i32>,
+    #[serde(default)]
+    pub all_matching: bool,
+    #[serde(default)]
+    pub status: UserStatusFilter,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub role: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub query: Option<String>,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct BulkRolePayload {
+    /// Selected users; `None` selects every user matching the filter.
+    pub user_ids: Option<Vec<UserId>>,
+    pub status: UserStatusFilter,
+    pub role: Option<String>,
+    pub query: Option<String>,
 }
 
 #[derive(Deserialize, Validate, Clone)]
@@ -72,6 +175,28 @@ pub struct AddHubPayload {
     pub name: HubName,
 }
 
+#[derive(Deserialize, Clone)]
+/// Security policy settings of the current hub.
+pub struct UpdateHubPolicyForm {
+    #[serde(default)]
+    pub require_admin_2fa: bool,
+    #[serde(default)]
+    pub require_email_verification: bool,
+}
+
+#[derive(Deserialize, Clone)]
+/// Registration settings of a hub.
+pub struct HubRegistrationSettingsForm {
+    /// Name of the mode, see [`RegistrationMode`].
+    pub registration_mode: String,
+    /// Allowed email domains such as `@company.ru`, one per line; empty
+    /// allows any domain.
+    #[serde(default)]
+    pub allowed_email_domains: String,
+    #[serde(default)]
+    pub default_roles: Vec<i32>,
+}
+
 #[derive(Deserialize, Validate, Clone)]
 /// Payload for adding a menu entry to a hub.
 pub struct AddMenuForm {
@@ -87,6 +212,90 @@ pub struct AddMenuPayload {
     pub url: MenuUrl,
 }
 
+#[derive(Deserialize, Validate, Clone)]
+/// Parameters for registering an OpenID Connect client.
+pub struct AddOAuthClientForm {
+    #[validate(length(min = 1, message = "Укажите имя."))]
+    pub name: String,
+    /// Allowed redirect URIs, one per line.
+    pub redirect_uris: String,
+    /// Issues a client secret; public clients rely on PKCE alone.
+    #[serde(default)]
+    pub confidential: bool,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct AddOAuthClientPayload {
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    pub confidential: bool,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form used by administrators to create or edit a service account.
+pub struct ServiceAccountForm {
+    #[validate(length(min = 1, max = 100, message = "Укажите имя."))]
+    pub name: String,
+    #[serde(default)]
+    pub roles: Vec<i32>,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct ServiceAccountPayload {
+    pub name: String,
+    pub roles: Vec<RoleId>,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form used by administrators to invite a user to the current hub.
+pub struct InviteUserForm {
+    #[validate(email(message = "Укажите корректный электронный адрес."))]
+    pub email: String,
+    #[validate(length(min = 1, max = 100, message = "Укажите имя."))]
+    pub name: String,
+    #[serde(default)]
+    pub roles: Vec<i32>,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct InviteUserPayload {
+    pub email: UserEmail,
+    pub name: UserName,
+    pub roles: Vec<RoleId>,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form used by administrators to suspend a user.
+pub struct SuspendUserForm {
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    #[validate(length(max = 255, message = "Причина не длиннее 255 символов."))]
+    pub reason: Option<String>,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct SuspendUserPayload {
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize, Validate, Clone)]
+/// Form used on the profile page to issue a personal access token.
+pub struct AddPersonalTokenForm {
+    #[validate(length(min = 1, max = 100, message = "Укажите название токена."))]
+    pub name: String,
+    /// Names of the granted scopes, see [`TokenScope`].
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    #[validate(range(min = 1, max = 365, message = "Срок действия от 1 до 365 дней."))]
+    pub expires_in_days: i64,
+}
+
+// Payload after validation and conversion to domain types.
+pub struct AddPersonalTokenPayload {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires_in_days: i64,
+}
+
 impl TryFrom<SaveUserForm> for SaveUserPayload {
     type Error = FormError;
 
@@ -104,7 +313,14 @@ impl TryFrom<SaveUserForm> for SaveUserPayload {
 
 impl From<SaveUserPayload> for DomainUpdateUser {
     fn from(payload: SaveUserPayload) -> Self {
-        Self::new(payload.name, payload.password, None)
+        let changes_password = payload.password.is_some();
+        let update = Self::new(payload.name, payload.password, None);
+        // Choosing a new password satisfies a pending change requirement.
+        if changes_password {
+            update.with_must_change_password(false)
+        } else {
+            update
+        }
     }
 }
 
@@ -127,6 +343,8 @@ impl TryFrom<UpdateUserForm> for UpdateUserPayload {
                     .collect::<Result<Vec<_>, _>>()?;
                 Some(roles)
             },
+            reset_two_factor: form.reset_two_factor,
+            must_change_password: form.must_change_password,
         })
     }
 }
@@ -134,16 +352,108 @@ impl TryFrom<UpdateUserForm> for UpdateUserPayload {
 impl From<UpdateUserPayload> for DomainUpdateUser {
     fn from(payload: UpdateUserPayload) -> Self {
         Self::new(payload.name, payload.password, payload.roles)
+            .with_must_change_password(payload.must_change_password)
+    }
+}
+
+impl TryFrom<AddUserForm> for AddUserPayload {
+    type Error = FormError;
+
+    fn try_from(form: AddUserForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        let roles = form
+            .roles
+            .into_iter()
+            .map(|id| RoleId::new(id).map_err(|_| FormError::InvalidRoleId))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            email: UserEmail::new(form.email).map_err(|_| FormError::InvalidEmail)?,
+            name: UserName::new(form.name).map_err(|_| FormError::InvalidName)?,
+            password: UserPassword::new(form.password).map_err(|_| FormError::InvalidPassword)?,
+            roles,
+            must_change_password: form.must_change_password,
+        })
+    }
+}
+
+impl AddUserPayload {
+    pub fn into_new_user(self, hub_id: HubId) -> DomainNewUser {
+        DomainNewUser::new(self.email, Some(self.name), hub_id, self.password)
+            .with_roles(self.roles)
+            .with_must_change_password(self.must_change_password)
+    }
+}
+
+impl From<UpdateHubPolicyForm> for DomainHubPolicy {
+    fn from(form: UpdateHubPolicyForm) -> Self {
+        Self {
+            require_admin_2fa: form.require_admin_2fa,
+            require_email_verification: form.require_email_verification,
+        }
+    }
+}
+
+impl TryFrom<HubRegistrationSettingsForm> for DomainHubRegistrationSettings {
+    type Error = FormError;
+
+    fn try_from(form: HubRegistrationSettingsForm) -> Result<Self, Self::Error> {
+        let registration_mode = form
+            .registration_mode
+            .parse::<RegistrationMode>()
+            .map_err(|_| FormError::InvalidRegistrationMode)?;
+        let mut allowed_email_domains = Vec::new();
+        for line in form.allowed_email_domains.lines() {
+            let domain = line.trim().trim_start_matches('@').to_lowercase();
+            if domain.is_empty() {
+                continue;
+            }
+            let valid = domain.contains('.')
+                && domain
+                    .split('.')
+                    .all(|label| !label.is_empty() && label.chars().all(is_domain_char));
+            if !valid {
+                return Err(FormError::InvalidEmailDomain);
+            }
+            if !allowed_email_domains.contains(&domain) {
+                allowed_email_domains.push(domain);
+            }
+        }
+        let default_roles = form
+            .default_roles
+            .into_iter()
+            .map(|id| RoleId::new(id).map_err(|_| FormError::InvalidRoleId))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            registration_mode,
+            allowed_email_domains,
+            default_roles,
+        })
     }
 }
 
+/// Characters allowed in a label of an email domain.
+fn is_domain_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-'
+}
+
 impl TryFrom<AddRoleForm> for AddRolePayload {
     type Error = FormError;
 
     fn try_from(form: AddRoleForm) -> Result<Self, Self::Error> {
         form.validate().map_err(FormError::Validation)?;
+        let requested = form
+            .permissions
+            .iter()
+            .map(|permission| permission.parse::<Permission>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| FormError::InvalidPermission)?;
         Ok(Self {
             name: RoleName::new(form.name).map_err(|_| FormError::InvalidName)?,
+            permissions: Permission::ALL
+                .into_iter()
+                .filter(|permission| requested.contains(permission))
+                .collect(),
+            description: form.description.trim().to_string(),
         })
     }
 }
@@ -151,6 +461,85 @@ impl TryFrom<AddRoleForm> for AddRolePayload {
 impl From<AddRolePayload> for DomainNewRole {
     fn from(payload: AddRolePayload) -> Self {
         Self::new(payload.name)
+            .with_permissions(payload.permissions)
+            .with_description(payload.description)
+    }
+}
+
+impl TryFrom<UpdateRoleForm> for UpdateRolePayload {
+    type Error = FormError;
+
+    fn try_from(form: UpdateRoleForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            name: RoleName::new(form.name).map_err(|_| FormError::InvalidName)?,
+            description: form.description.trim().to_string(),
+        })
+    }
+}
+
+impl From<UpdateRolePayload> for DomainUpdateRole {
+    fn from(payload: UpdateRolePayload) -> Self {
+        Self::new(payload.name, payload.description)
+    }
+}
+
+impl TryFrom<RoleParentsForm> for RoleParentsPayload {
+    type Error = FormError;
+
+    fn try_from(form: RoleParentsForm) -> Result<Self, Self::Error> {
+        let mut parents = form
+            .parents
+            .into_iter()
+            .map(|id| RoleId::new(id).map_err(|_| FormError::InvalidRoleId))
+            .collect::<Result<Vec<_>, _>>()?;
+        parents.sort_by_key(|role_id| role_id.get());
+        parents.dedup();
+        Ok(Self { parents })
+    }
+}
+
+impl TryFrom<BulkRoleForm> for BulkRolePayload {
+    type Error = FormError;
+
+    fn try_from(form: BulkRoleForm) -> Result<Self, Self::Error> {
+        let user_ids = if form.all_matching {
+            None
+        } else if form.user_ids.is_empty() {
+            return Err(FormError::InvalidUserIds);
+        } else {
+            let ids = form
+                .user_ids
+                .into_iter()
+                .map(|id| UserId::new(id).map_err(|_| FormError::InvalidUserIds))
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(ids)
+        };
+        Ok(Self {
+            user_ids,
+            status: form.status,
+            role: form.role,
+            query: form.query,
+        })
+    }
+}
+
+impl BulkRolePayload {
+    /// Selects the users of `hub_id` the change applies to.
+    pub fn into_selection(self, hub_id: HubId) -> UserSelection {
+        match self.user_ids {
+            Some(ids) => UserSelection::Ids(ids),
+            None => {
+                let mut query = UserListQuery::new(hub_id).status(self.status);
+                if let Some(role) = self.role {
+                    query = query.role(role);
+                }
+                if let Some(search) = self.query {
+                    query = query.search(search);
+                }
+                UserSelection::Matching(query)
+            }
+        }
     }
 }
 
@@ -189,20 +578,139 @@ impl AddMenuPayload {
     }
 }
 
+impl TryFrom<AddOAuthClientForm> for AddOAuthClientPayload {
+    type Error = FormError;
+
+    fn try_from(form: AddOAuthClientForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        let redirect_uris = form
+            .redirect_uris
+            .lines()
+            .map(str::trim)
+            .filter(|uri| !uri.is_empty())
+            .map(|uri| match Url::parse(uri) {
+                // Redirect URIs must be absolute and may not carry a fragment.
+                Ok(url) if url.fragment().is_none() => Ok(uri.to_string()),
+                _ => Err(FormError::InvalidRedirectUri),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if redirect_uris.is_empty() {
+            return Err(FormError::InvalidRedirectUri);
+        }
+        Ok(Self {
+            name: form.name.trim().to_string(),
+            redirect_uris,
+            confidential: form.confidential,
+        })
+    }
+}
+
+impl TryFrom<ServiceAccountForm> for ServiceAccountPayload {
+    type Error = FormError;
+
+    fn try_from(form: ServiceAccountForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        let name = form.name.trim().to_string();
+        if name.is_empty() {
+            return Err(FormError::InvalidName);
+        }
+        let roles = form
+            .roles
+            .into_iter()
+            .map(|id| RoleId::new(id).map_err(|_| FormError::InvalidRoleId))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { name, roles })
+    }
+}
+
+impl TryFrom<InviteUserForm> for InviteUserPayload {
+    type Error = FormError;
+
+    fn try_from(form: InviteUserForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        let roles = form
+            .roles
+            .into_iter()
+            .map(|id| RoleId::new(id).map_err(|_| FormError::InvalidRoleId))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            email: UserEmail::new(form.email).map_err(|_| FormError::InvalidEmail)?,
+            name: UserName::new(form.name).map_err(|_| FormError::InvalidName)?,
+            roles,
+        })
+    }
+}
+
+impl TryFrom<SuspendUserForm> for SuspendUserPayload {
+    type Error = FormError;
+
+    fn try_from(form: SuspendUserForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        Ok(Self {
+            reason: form
+                .reason
+                .map(|reason| reason.trim().to_string())
+                .filter(|reason| !reason.is_empty()),
+        })
+    }
+}
+
+impl TryFrom<AddPersonalTokenForm> for AddPersonalTokenPayload {
+    type Error = FormError;
+
+    fn try_from(form: AddPersonalTokenForm) -> Result<Self, Self::Error> {
+        form.validate().map_err(FormError::Validation)?;
+        let name = form.name.trim().to_string();
+        if name.is_empty() {
+            return Err(FormError::InvalidName);
+        }
+        let requested = form
+            .scopes
+            .iter()
+            .map(|scope| scope.parse::<TokenScope>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| FormError::InvalidScope)?;
+        let scopes: Vec<_> = TokenScope::ALL
+            .into_iter()
+            .filter(|scope| requested.contains(scope))
+            .collect();
+        if scopes.is_empty() {
+            return Err(FormError::InvalidScope);
+        }
+        Ok(Self {
+            name,
+            scopes,
+            expires_in_days: form.expires_in_days,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::convert::TryInto;
 
-    use crate::domain::hub::NewHub as DomainNewHub;
-    use crate::domain::role::NewRole as DomainNewRole;
+    use crate::domain::hub::{
+        HubRegistrationSettings as DomainHubRegistrationSettings, NewHub as DomainNewHub,
+        RegistrationMode,
+    };
+    use crate::domain::permission::Permission;
+    use crate::domain::personal_token::TokenScope;
+    use crate::domain::role::{NewRole as DomainNewRole, UpdateRole as DomainUpdateRole};
     use crate::domain::types::{
-        HubId, HubName, MenuName, MenuUrl, RoleId, RoleName, UserName, UserPassword,
+        HubId, HubName, MenuName, MenuUrl, RoleId, RoleName, UserId, UserName, UserPassword,
     };
     use crate::domain::user::UpdateUser as DomainUpdateUser;
+    use crate::forms::FormError;
     use crate::forms::main::{
-        AddHubForm, AddHubPayload, AddMenuForm, AddMenuPayload, AddRoleForm, AddRolePayload,
-        SaveUserForm, SaveUserPayload, UpdateUserForm, UpdateUserPayload,
+        AddHubForm, AddHubPayload, AddMenuForm, AddMenuPayload, AddOAuthClientForm,
+        AddOAuthClientPayload, AddPersonalTokenForm, AddPersonalTokenPayload, AddRoleForm,
+        AddRolePayload, AddUserForm, AddUserPayload, BulkRoleForm, BulkRolePayload,
+        HubRegistrationSettingsForm, InviteUserForm, InviteUserPayload, RoleParentsForm,
+        RoleParentsPayload, SaveUserForm, SaveUserPayload, ServiceAccountForm,
+        ServiceAccountPayload, UpdateRoleForm, UpdateRolePayload, UpdateUserForm,
+        UpdateUserPayload,
     };
+    use crate::repository::{UserSelection, UserStatusFilter};
 
     #[test]
     fn test_save_user_form_into_domain_update_user() {
@@ -226,6 +734,8 @@ mod tests {
     fn test_add_role_form_into_domain_new_role() {
         let form = AddRoleForm {
             name: "editor".to_string(),
+            permissions: vec!["menu.manage".into(), "users.read".into()],
+            description: " Edits the menu ".to_string(),
         };
 
         let payload: AddRolePayload = form.try_into().expect("conversion failed");
@@ -233,6 +743,96 @@ mod tests {
         let role: DomainNewRole = payload.into();
 
         assert_eq!(role.name, RoleName::new("editor").unwrap());
+        assert_eq!(
+            role.permissions,
+            vec![Permission::UsersRead, Permission::MenuManage]
+        );
+        assert_eq!(role.description, "Edits the menu");
+    }
+
+    #[test]
+    fn test_update_role_form_trims_name_and_description() {
+        let form = UpdateRoleForm {
+            name: " editor ".to_string(),
+            description: " Edits the menu ".to_string(),
+        };
+
+        let payload: UpdateRolePayload = form.try_into().expect("conversion failed");
+
+        let updates: DomainUpdateRole = payload.into();
+
+        assert_eq!(updates.name, RoleName::new("editor").unwrap());
+        assert_eq!(updates.description, "Edits the menu");
+    }
+
+    #[test]
+    fn test_add_role_form_rejects_unknown_permissions() {
+        let form = AddRoleForm {
+            name: "editor".to_string(),
+            permissions: vec!["users.delete".into()],
+            description: String::new(),
+        };
+
+        let result: Result<AddRolePayload, _> = form.try_into();
+        assert!(matches!(result, Err(FormError::InvalidPermission)));
+    }
+
+    #[test]
+    fn test_bulk_role_form_selects_ids_or_matching_users() {
+        let form = BulkRoleForm {
+            user_ids: vec![3, 5],
+            all_matching: false,
+            status: UserStatusFilter::Active,
+            role: None,
+            query: None,
+        };
+        let payload: BulkRolePayload = form.try_into().expect("conversion failed");
+        let hub_id = HubId::new(1).unwrap();
+        assert!(matches!(
+            payload.into_selection(hub_id),
+            UserSelection::Ids(ids) if ids == vec![UserId::new(3).unwrap(), UserId::new(5).unwrap()]
+        ));
+
+        let form = BulkRoleForm {
+            user_ids: vec![],
+            all_matching: true,
+            status: UserStatusFilter::All,
+            role: Some("viewer".into()),
+            query: Some("bob".into()),
+        };
+        let payload: BulkRolePayload = form.try_into().expect("conversion failed");
+        let UserSelection::Matching(query) = payload.into_selection(hub_id) else {
+            panic!("expected a filter selection");
+        };
+        assert_eq!(query.status, UserStatusFilter::All);
+        assert_eq!(query.role.as_deref(), Some("viewer"));
+        assert_eq!(query.search.as_deref(), Some("bob"));
+
+        let form = BulkRoleForm {
+            user_ids: vec![],
+            all_matching: false,
+            status: UserStatusFilter::Active,
+            role: None,
+            query: None,
+        };
+        let result: Result<BulkRolePayload, _> = form.try_into();
+        assert!(matches!(result, Err(FormError::InvalidUserIds)));
+    }
+
+    #[test]
+    fn test_role_parents_form_sorts_and_deduplicates_ids() {
+        let form = RoleParentsForm {
+            parents: vec![3, 2, 3],
+        };
+        let payload: RoleParentsPayload = form.try_into().expect("conversion failed");
+        assert_eq!(
+            payload.parents,
+            vec![RoleId::new(2).unwrap(), RoleId::new(3).unwrap()]
+        );
+
+        let form = RoleParentsForm { parents: vec![0] };
+        let result: Result<RoleParentsPayload, _> = form.try_into();
+        assert!(matches!(result, Err(FormError::InvalidRoleId)));
     }
 
     #[test]
@@ -241,6 +841,8 @@ mod tests {
             name: "Bob".to_string(),
             password: Some("pwd".to_string()),
             roles: vec![1, 2],
+            reset_two_factor: false,
+            must_change_password: true,
         };
 
         let payload: UpdateUserPayload = form.try_into().expect("conversion failed");
@@ -259,6 +861,27 @@ mod tests {
             update.roles.unwrap(),
             vec![RoleId::new(1).unwrap(), RoleId::new(2).unwrap()]
         );
+        assert_eq!(update.must_change_password, Some(true));
+    }
+
+    #[test]
+    fn test_add_user_form_into_domain_new_user() {
+        let form = AddUserForm {
+            email: "New@Example.com".to_string(),
+            name: "New".to_string(),
+            password: "secret".to_string(),
+            roles: vec![3],
+            must_change_password: true,
+        };
+
+        let payload: AddUserPayload = form.try_into().expect("conversion failed");
+        let new_user = payload.into_new_user(HubId::new(5).unwrap());
+
+        assert_eq!(new_user.email.as_str(), "new@example.com");
+        assert_eq!(new_user.name, Some(UserName::new("New").unwrap()));
+        assert_eq!(new_user.hub_id, HubId::new(5).unwrap());
+        assert_eq!(new_user.roles, vec![RoleId::new(3).unwrap()]);
+        assert!(new_user.must_change_password);
     }
 
     #[test]
@@ -289,4 +912,162 @@ mod tests {
         assert_eq!(menu.url, MenuUrl::new("https://app.test.me/").unwrap());
         assert_eq!(menu.hub_id, HubId::new(3).unwrap());
     }
+
+    #[test]
+    fn test_add_oauth_client_form_splits_redirect_uris() {
+        let form = AddOAuthClientForm {
+            name: "CRM".to_string(),
+            redirect_uris: "https://crm.example.org/callback\r\n\ncom.example.app:/oauth\n"
+                .to_string(),
+            confidential: true,
+        };
+
+        let payload: AddOAuthClientPayload = form.try_into().expect("conversion failed");
+
+        assert_eq!(
+            payload.redirect_uris,
+            vec![
+                "https://crm.example.org/callback".to_string(),
+                "com.example.app:/oauth".to_string()
+            ]
+        );
+        assert!(payload.confidential);
+    }
+
+    #[test]
+    fn test_add_oauth_client_form_rejects_relative_redirect_uris() {
+        for redirect_uris in ["/callback", "", "https://crm.example.org/#done"] {
+            let form = AddOAuthClientForm {
+                name: "CRM".to_string(),
+                redirect_uris: redirect_uris.to_string(),
+                confidential: false,
+            };
+
+            let result: Result<AddOAuthClientPayload, _> = form.try_into();
+            assert!(result.is_err(), "{redirect_uris} should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_add_personal_token_form_orders_and_deduplicates_scopes() {
+        let form = AddPersonalTokenForm {
+            name: " CI ".to_string(),
+            scopes: vec!["users".into(), "profile".into(), "users".into()],
+            expires_in_days: 30,
+        };
+
+        let payload: AddPersonalTokenPayload = form.try_into().expect("conversion failed");
+
+        assert_eq!(payload.name, "CI");
+        assert_eq!(payload.scopes, vec![TokenScope::Profile, TokenScope::Users]);
+        assert_eq!(payload.expires_in_days, 30);
+    }
+
+    #[test]
+    fn test_add_personal_token_form_requires_known_scopes_and_expiry() {
+        for (scopes, expires_in_days) in [
+            (vec![], 30),
+            (vec!["write".to_string()], 30),
+            (vec!["users".to_string()], 0),
+            (vec!["users".to_string()], 366),
+        ] {
+            let form = AddPersonalTokenForm {
+                name: "CI".to_string(),
+                scopes,
+                expires_in_days,
+            };
+
+            let result: Result<AddPersonalTokenPayload, _> = form.try_into();
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_service_account_form_trims_name_and_checks_roles() {
+        let form = ServiceAccountForm {
+            name: "  CRM sync ".to_string(),
+            roles: vec![2, 3],
+        };
+
+        let payload: ServiceAccountPayload = form.try_into().expect("conversion failed");
+
+        assert_eq!(payload.name, "CRM sync");
+        assert_eq!(
+            payload.roles,
+            vec![RoleId::new(2).unwrap(), RoleId::new(3).unwrap()]
+        );
+
+        let form = ServiceAccountForm {
+            name: "CRM sync".to_string(),
+            roles: vec![0],
+        };
+        let result: Result<ServiceAccountPayload, _> = form.try_into();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_invite_user_form_normalizes_email_and_checks_roles() {
+        let form = InviteUserForm {
+            email: "New@Example.com".to_string(),
+            name: " New User ".to_string(),
+            roles: vec![2],
+        };
+
+        let payload: InviteUserPayload = form.try_into().expect("conversion failed");
+
+        assert_eq!(payload.email.as_str(), "new@example.com");
+        assert_eq!(payload.name, UserName::new("New User").unwrap());
+        assert_eq!(payload.roles, vec![RoleId::new(2).unwrap()]);
+
+        let form = InviteUserForm {
+            email: "not-an-email".to_string(),
+            name: "New User".to_string(),
+            roles: vec![],
+        };
+        let result: Result<InviteUserPayload, _> = form.try_into();
+        assert!(matches!(result, Err(FormError::Validation(_))));
+    }
+
+    #[test]
+    fn test_hub_registration_settings_form_normalizes_domains() {
+        let form = HubRegistrationSettingsForm {
+            registration_mode: "invite_only".into(),
+            allowed_email_domains: "@Company.ru\n\n  company.ru \nmail.example.com".into(),
+            default_roles: vec![2, 3],
+        };
+
+        let settings = DomainHubRegistrationSettings::try_from(form).unwrap();
+
+        assert_eq!(settings.registration_mode, RegistrationMode::InviteOnly);
+        assert_eq!(
+            settings.allowed_email_domains,
+            vec!["company.ru".to_string(), "mail.example.com".to_string()]
+        );
+        assert_eq!(
+            settings.default_roles,
+            vec![RoleId::new(2).unwrap(), RoleId::new(3).unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_hub_registration_settings_form_rejects_invalid_values() {
+        let form = |mode: &str, domains: &str| HubRegistrationSettingsForm {
+            registration_mode: mode.into(),
+            allowed_email_domains: domains.into(),
+            default_roles: vec![],
+        };
+
+        assert!(matches!(
+            DomainHubRegistrationSettings::try_from(form("public", "")),
+            Err(FormError::InvalidRegistrationMode)
+        ));
+        assert!(matches!(
+            DomainHubRegistrationSettings::try_from(form("open", "user@company.ru")),
+            Err(FormError::InvalidEmailDomain)
+        ));
+        assert!(matches!(
+            DomainHubRegistrationSettings::try_from(form("open", "localhost")),
+            Err(FormError::InvalidEmailDomain)
+        ));
+    }
 }
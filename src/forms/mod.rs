@@ -11,6 +11,7 @@ use validator::{ValidationError, ValidationErrors};
 
 pub mod auth;
 pub mod main;
+pub mod oauth;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 /// Field-level validation error emitted by the form layer.
@@ -42,6 +43,27 @@ pub enum FormError {
 
     #[error("Роль заполнена некорректно.")]
     InvalidRoleId,
+
+    #[error("Укажите корректные адреса перенаправления.")]
+    InvalidRedirectUri,
+
+    #[error("Выберите области доступа токена.")]
+    InvalidScope,
+
+    #[error("Выберите права роли из списка.")]
+    InvalidPermission,
+
+    #[error("Выберите режим регистрации.")]
+    InvalidRegistrationMode,
+
+    #[error("Укажите корректные домены электронной почты.")]
+    InvalidEmailDomain,
+
+    #[error("Выберите пользователей.")]
+    InvalidUserIds,
+
+    #[error("Роль не может наследовать саму себя.")]
+    RoleCycle,
 }
 
 impl FormError {
@@ -64,6 +86,13 @@ impl FormError {
             Self::InvalidName => Some("name"),
             Self::InvalidUrl => Some("url"),
             Self::InvalidRoleId => Some("roles"),
+            Self::InvalidRedirectUri => Some("redirect_uris"),
+            Self::InvalidScope => Some("scopes"),
+            Self::InvalidPermission => Some("permissions"),
+            Self::InvalidRegistrationMode => Some("registration_mode"),
+            Self::InvalidEmailDomain => Some("allowed_email_domains"),
+            Self::InvalidUserIds => Some("user_ids"),
+            Self::RoleCycle => Some("parents"),
         }
     }
 }
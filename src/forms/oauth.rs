@@ -0,0 +1,44 @@
+//! Parameters of the OpenID Connect authorization, token, introspection, and
+//! revocation requests.
+//!
+//! Every field is optional so missing parameters are reported with the
+//! protocol's own error codes instead of a generic deserialization failure.
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+/// Query string of `GET /oauth/authorize`.
+pub struct AuthorizeForm {
+    pub response_type: Option<String>,
+    pub client_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+/// Form body of `POST /oauth/token`.
+pub struct TokenForm {
+    pub grant_type: Option<String>,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    /// Client credentials for `client_secret_post`; `client_secret_basic`
+    /// sends them in the `Authorization` header instead.
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+/// Form body of `POST /oauth/introspect` and `POST /oauth/revoke`.
+pub struct TokenLookupForm {
+    pub token: Option<String>,
+    /// Accepted but not needed: access tokens and session JWTs are told
+    /// apart by their shape.
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
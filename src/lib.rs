@@ -16,7 +16,7 @@ use actix_identity::IdentityMiddleware;
 #[cfg(feature = "server")]
 use actix_session::{SessionMiddleware, config::PersistentSession, storage::CookieSessionStore};
 #[cfg(feature = "server")]
-use actix_web::cookie::{Key, time::Duration};
+use actix_web::cookie::time::Duration;
 #[cfg(feature = "server")]
 use actix_web::{App, HttpServer, dev::Server, web};
 
@@ -32,26 +32,56 @@ use pushkind_common::routes::logout;
 use pushkind_common::zmq::{ZmqSender, ZmqSenderOptions};
 
 #[cfg(feature = "server")]
-use crate::middleware::RequireUserExists;
+use crate::middleware::{
+    AcceptApiTokens, AcceptRetiredCookieKeys, RequireUserExists, SessionCookieKeys,
+};
 #[cfg(feature = "server")]
 use crate::models::config::{AppConfig, Settings};
 #[cfg(feature = "server")]
 use crate::repository::DieselRepository;
 #[cfg(feature = "server")]
 use crate::routes::admin::{
-    add_hub, add_menu, add_role, delete_hub, delete_menu, delete_role, delete_user, update_user,
-    user_modal,
+    add_hub, add_menu, add_oauth_client, add_role, add_service_account, add_user, assign_role,
+    cancel_invitation, delete_hub, delete_menu, delete_oauth_client, delete_role,
+    delete_service_account, delete_user, hub_registration_settings, invite_user, reactivate_user,
+    resend_invitation, restore_hub, restore_user, rotate_service_account_secret, set_role_parents,
+    suspend_user, unassign_role, update_hub_policy, update_hub_registration_settings, update_role,
+    update_service_account, update_user, user_modal, verify_user_email,
 };
 #[cfg(feature = "server")]
 use crate::routes::api::{
-    api_v1_admin_dashboard, api_v1_hub_menu_items, api_v1_hubs, api_v1_iam, api_v1_id, api_v1_users,
+    api_v1_admin_audit, api_v1_admin_dashboard, api_v1_admin_deleted, api_v1_admin_invitations,
+    api_v1_admin_role_members, api_v1_admin_sessions, api_v1_hub_menu_items, api_v1_hubs,
+    api_v1_iam, api_v1_id, api_v1_passkeys, api_v1_service_accounts, api_v1_sessions,
+    api_v1_tokens, api_v1_users,
 };
 #[cfg(feature = "server")]
 use crate::routes::auth::{
-    login, login_token, recover_password, register, signin_page, signup_page,
+    accept_invitation, change_password, enable_two_factor, invite_page, login,
+    password_change_page, recover_password, register, resend_verification, reset_page,
+    reset_password, setup_two_factor, signin_page, signup_page, two_factor_page, verify_email,
+    verify_two_factor,
+};
+#[cfg(feature = "server")]
+use crate::routes::main::{
+    add_personal_token, delete_passkey, disable_two_factor, health, revoke_all_sessions,
+    revoke_personal_token, revoke_session, save_user, show_index,
 };
 #[cfg(feature = "server")]
-use crate::routes::main::{health, save_user, show_index};
+use crate::routes::oauth::{authorize, introspect, revoke, token, userinfo};
+#[cfg(feature = "server")]
+use crate::routes::webauthn::{
+    finish_passkey_login, finish_passkey_registration, start_passkey_login,
+    start_passkey_registration,
+};
+#[cfg(feature = "server")]
+use crate::routes::well_known::{jwks, openid_configuration};
+#[cfg(feature = "server")]
+use crate::services::jwt::JwtKeys;
+#[cfg(feature = "server")]
+use crate::services::retention::run_purge_job;
+#[cfg(feature = "server")]
+use crate::services::webauthn::build_webauthn;
 
 #[cfg(feature = "data")]
 pub mod domain;
@@ -60,6 +90,8 @@ pub mod dto;
 
 pub mod error_conversions;
 #[cfg(feature = "server")]
+pub mod extractors;
+#[cfg(feature = "server")]
 pub mod forms;
 #[cfg(feature = "server")]
 pub mod middleware;
@@ -77,6 +109,9 @@ pub mod services;
 /// Role required to access administrative routes.
 #[cfg(feature = "server")]
 pub const SERVICE_ACCESS_ROLE: &str = "admin";
+/// Name of the cookie holding the encrypted session.
+#[cfg(feature = "server")]
+pub const SESSION_COOKIE_NAME: &str = "id";
 #[cfg(feature = "server")]
 const AUTH_SERVICE_URL: &str = "/auth/signin";
 
@@ -114,61 +149,145 @@ pub fn build_server(listener: TcpListener, app_config: AppConfig) -> std::io::Re
 
     let repo = DieselRepository::new(pool);
 
-    // Keys and stores for identity and sessions.
-    let secret_key = Key::from(app_config.secret.as_bytes());
+    // Background job removing deleted users and hubs after the retention window.
+    actix_web::rt::spawn(run_purge_job(app_config.retention.clone(), repo.clone()));
+
+    // WebAuthn relying party bound to the configured domain.
+    let webauthn = build_webauthn(&app_config.domain)
+        .map(web::Data::new)
+        .map_err(|e| std::io::Error::other(format!("Failed to configure WebAuthn: {e}")))?;
+
+    // Keys signing the session JWTs stored in the identity cookie.
+    let jwt_keys = JwtKeys::from_config(&app_config.jwt, &app_config.secret)
+        .map(web::Data::new)
+        .map_err(|e| std::io::Error::other(format!("Failed to load JWT keys: {e}")))?;
+
+    // Keys encrypting the session cookie, retired ones included.
+    let cookie_keys =
+        SessionCookieKeys::from_config(&app_config.session_cookie, &app_config.secret)
+            .map_err(|e| std::io::Error::other(format!("Failed to load cookie keys: {e}")))?;
 
     let server = HttpServer::new(move || {
         App::new()
             .wrap(Cors::permissive())
             .wrap(IdentityMiddleware::default())
             .wrap(
-                SessionMiddleware::builder(CookieSessionStore::default(), secret_key.clone())
-                    .session_lifecycle(PersistentSession::default().session_ttl(Duration::days(7)))
-                    .cookie_secure(false) // set to true in prod
-                    .cookie_domain(Some(format!(".{}", app_config.domain)))
-                    .build(),
+                SessionMiddleware::builder(
+                    CookieSessionStore::default(),
+                    cookie_keys.active().clone(),
+                )
+                .cookie_name(SESSION_COOKIE_NAME.to_string())
+                .session_lifecycle(PersistentSession::default().session_ttl(Duration::days(7)))
+                .cookie_secure(false) // set to true in prod
+                .cookie_domain(Some(format!(".{}", app_config.domain)))
+                .build(),
             )
+            .wrap(AcceptRetiredCookieKeys::new(cookie_keys.clone()))
             .wrap(actix_web::middleware::Compress::default())
             .wrap(actix_web::middleware::Logger::default())
             .service(
                 web::scope("/auth")
                     .service(logout)
                     .service(login)
-                    .service(login_token)
                     .service(signin_page)
                     .service(signup_page)
                     .service(register)
-                    .service(recover_password),
+                    .service(verify_email)
+                    .service(resend_verification)
+                    .service(recover_password)
+                    .service(reset_page)
+                    .service(reset_password)
+                    .service(password_change_page)
+                    .service(change_password)
+                    .service(invite_page)
+                    .service(accept_invitation)
+                    .service(two_factor_page)
+                    .service(verify_two_factor)
+                    .service(setup_two_factor)
+                    .service(enable_two_factor)
+                    .service(start_passkey_registration)
+                    .service(finish_passkey_registration)
+                    .service(start_passkey_login)
+                    .service(finish_passkey_login),
             )
             .service(
                 web::scope("/admin")
                     .wrap(RequireUserExists)
                     .service(add_role)
+                    .service(update_role)
+                    .service(set_role_parents)
+                    .service(assign_role)
+                    .service(unassign_role)
+                    .service(add_user)
                     .service(user_modal)
                     .service(delete_user)
+                    .service(restore_user)
                     .service(update_user)
+                    .service(verify_user_email)
+                    .service(suspend_user)
+                    .service(reactivate_user)
                     .service(add_hub)
+                    .service(update_hub_policy)
+                    .service(hub_registration_settings)
+                    .service(update_hub_registration_settings)
                     .service(delete_hub)
+                    .service(restore_hub)
                     .service(delete_role)
                     .service(add_menu)
-                    .service(delete_menu),
+                    .service(delete_menu)
+                    .service(add_oauth_client)
+                    .service(delete_oauth_client)
+                    .service(add_service_account)
+                    .service(update_service_account)
+                    .service(rotate_service_account_secret)
+                    .service(delete_service_account)
+                    .service(invite_user)
+                    .service(resend_invitation)
+                    .service(cancel_invitation),
             )
             .service(
                 web::scope("/api")
                     .wrap(RequireUserExists)
+                    .wrap(AcceptApiTokens)
+                    .service(api_v1_admin_audit)
                     .service(api_v1_admin_dashboard)
+                    .service(api_v1_admin_role_members)
+                    .service(api_v1_admin_sessions)
+                    .service(api_v1_admin_deleted)
+                    .service(api_v1_admin_invitations)
                     .service(api_v1_hub_menu_items)
                     .service(api_v1_hubs)
                     .service(api_v1_iam)
                     .service(api_v1_id)
-                    .service(api_v1_users),
+                    .service(api_v1_passkeys)
+                    .service(api_v1_sessions)
+                    .service(api_v1_tokens)
+                    .service(api_v1_users)
+                    .service(api_v1_service_accounts),
             )
             .service(Files::new("/assets", "./assets").prefer_utf8(true))
             .service(
                 web::scope("/user")
                     .wrap(RequireUserExists)
-                    .service(save_user),
+                    .service(save_user)
+                    .service(disable_two_factor)
+                    .service(delete_passkey)
+                    .service(revoke_session)
+                    .service(revoke_all_sessions)
+                    .service(add_personal_token)
+                    .service(revoke_personal_token),
+            )
+            .service(
+                web::scope("/oauth")
+                    .wrap(RequireUserExists)
+                    .service(authorize)
+                    .service(token)
+                    .service(userinfo)
+                    .service(introspect)
+                    .service(revoke),
             )
+            .service(jwks)
+            .service(openid_configuration)
             .service(
                 web::scope("")
                     .wrap(RequireUserExists)
@@ -180,6 +299,8 @@ pub fn build_server(listener: TcpListener, app_config: AppConfig) -> std::io::Re
             .app_data(web::Data::new(app_config.clone()))
             .app_data(web::Data::new(common_config.clone()))
             .app_data(web::Data::new(zmq_sender.clone()))
+            .app_data(webauthn.clone())
+            .app_data(jwt_keys.clone())
     })
     .listen(listener)?
     .run();
@@ -1,21 +1,50 @@
 //! Custom middleware components used across the application.
 
+use actix_identity::{Identity, IdentityExt};
 use actix_web::{
-    Error,
+    Error, HttpMessage,
+    cookie::{Cookie, CookieJar, Key, KeyError},
     dev::{self, Service, ServiceRequest, ServiceResponse, Transform},
-    error::{ErrorInternalServerError, ErrorUnauthorized},
+    error::{ErrorForbidden, ErrorInternalServerError, ErrorUnauthorized},
+    http::{
+        StatusCode,
+        header::{self, HeaderMap, HeaderValue},
+    },
     web,
 };
 use futures_util::future::{LocalBoxFuture, Ready, ok};
-use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::ServiceError;
 use std::rc::Rc;
 
+use crate::SESSION_COOKIE_NAME;
+use crate::domain::personal_token::{PersonalAccessToken, TokenScope};
+use crate::domain::service_account::ServiceAccount;
 use crate::domain::types::{HubId, UserId};
+use crate::models::config::SessionCookieConfig;
 use crate::repository::DieselRepository;
 use crate::repository::UserReader;
+use crate::routes::authorization;
+use crate::services::jwt::{JwtKeys, SessionClaims};
+use crate::services::permission as permission_service;
+use crate::services::personal_token as personal_token_service;
+use crate::services::service_account as service_account_service;
+use crate::services::session as session_service;
 
 /// Middleware ensuring that the authenticated user referenced in the request
 /// actually exists in the database.
+///
+/// Identity cookies whose session was revoked, expired, or never recorded are
+/// logged out, so the request continues as anonymous and handlers that need
+/// a user reject it. Requests of suspended users are rejected with `401`,
+/// including those made with their personal access tokens.
+///
+/// When the user's email, name, or roles changed since login, the request
+/// continues with the live values and the identity cookie is reissued for
+/// the same session, so a removed role stops granting access right away.
+///
+/// Requests made with a personal access token or a service account token pass
+/// through, since [`AcceptApiTokens`] already resolved the live user or
+/// account and there is no identity cookie to reissue.
 pub struct RequireUserExists;
 
 impl<S, B> Transform<S, ServiceRequest> for RequireUserExists
@@ -54,16 +83,30 @@ where
 
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
         let srv = Rc::clone(&self.service);
-        let user = req.extract::<AuthenticatedUser>();
+        let session = req.extract::<SessionClaims>();
         let repo = req.app_data::<web::Data<DieselRepository>>().cloned();
+        let keys = req.app_data::<web::Data<JwtKeys>>().cloned();
 
         Box::pin(async move {
-            let claims = match user.await {
+            let claims = match session.await {
                 Ok(claims) => claims,
-                Err(_) => return srv.call(req).await,
+                Err(e) => {
+                    if e.as_response_error().status_code() == StatusCode::UNAUTHORIZED
+                        && let Ok(identity) = req.get_identity()
+                    {
+                        identity.logout();
+                    }
+                    return srv.call(req).await;
+                }
             };
+            if req.extensions().contains::<ServiceAccount>()
+                || req.extensions().contains::<PersonalAccessToken>()
+            {
+                return srv.call(req).await;
+            }
 
             let uid: i32 = claims
+                .user
                 .sub
                 .parse()
                 .map_err(|_| ErrorUnauthorized("Invalid user"))?;
@@ -72,12 +115,348 @@ where
 
             let user_id = UserId::new(uid).map_err(|_| ErrorUnauthorized("Invalid user"))?;
             let hub_id =
-                HubId::new(claims.hub_id).map_err(|_| ErrorUnauthorized("Invalid user"))?;
+                HubId::new(claims.user.hub_id).map_err(|_| ErrorUnauthorized("Invalid user"))?;
+
+            // A failed lookup must not sign everyone out, so only a missing
+            // user is answered with 401.
+            let user_roles = match repo.get_user_by_id(user_id, hub_id) {
+                Ok(Some(user_roles)) => user_roles,
+                Ok(None) => return Err(ErrorUnauthorized("User not found")),
+                Err(e) => {
+                    log::error!("Failed to load user {uid}: {e}");
+                    return Err(ErrorInternalServerError("Failed to load user"));
+                }
+            };
+            if user_roles.user.is_disabled() {
+                return Err(ErrorUnauthorized("User suspended"));
+            }
+
+            let permissions = match keys.as_deref() {
+                Some(keys) => {
+                    permission_service::permissions_claim(&claims.user, keys, repo.get_ref())
+                        .map_err(|_| ErrorInternalServerError("Failed to load permissions"))?
+                }
+                None => Vec::new(),
+            };
+            if let Some(live) = session_service::refresh_claims(&claims, user_roles, permissions) {
+                match keys.as_deref().map(|keys| keys.encode(&live)) {
+                    Some(Ok(token)) => {
+                        if let Err(e) = Identity::login(&req.extensions(), token) {
+                            log::error!("Failed to reissue identity: {e}");
+                        }
+                    }
+                    _ => log::error!("Failed to reissue session token for user {uid}"),
+                }
+                req.extensions_mut().insert(live);
+            }
+
+            srv.call(req).await
+        })
+    }
+}
+
+/// Middleware accepting personal access tokens and service account tokens
+/// sent as `Authorization: Bearer`.
+///
+/// A valid token stores the claims of its owner in the request, so
+/// [`RequireUserExists`] and the `SessionUser` extractor treat the request
+/// like one made with a session. The [`PersonalAccessToken`] or
+/// [`ServiceAccount`] is stored as well to mark the request as token-based. Unknown, revoked, or expired tokens are rejected with
+/// `401`. Personal access tokens lacking the scope of the endpoint, and
+/// service account tokens outside the user listing, are rejected with `403`.
+/// Requests without a Bearer token pass through untouched.
+pub struct AcceptApiTokens;
+
+impl<S, B> Transform<S, ServiceRequest> for AcceptApiTokens
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AcceptApiTokensMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AcceptApiTokensMiddleware {
+            service: Rc::new(service),
+        })
+    }
+}
+
+/// Service wrapper produced by [`AcceptApiTokens`].
+pub struct AcceptApiTokensMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AcceptApiTokensMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = Rc::clone(&self.service);
+        let bearer = authorization(req.request(), "Bearer");
+        let repo = req.app_data::<web::Data<DieselRepository>>().cloned();
+
+        Box::pin(async move {
+            let Some(bearer) = bearer else {
+                return srv.call(req).await;
+            };
+            let repo = repo.ok_or_else(|| ErrorInternalServerError("DB repo not found"))?;
+
+            let required_scope = personal_token_service::required_scope(req.path());
+            let resolved = if bearer.starts_with(service_account_service::TOKEN_PREFIX) {
+                service_account_service::authenticate(&bearer, repo.get_ref()).map(
+                    |(claims, account)| {
+                        let allowed = required_scope == Some(TokenScope::Users);
+                        (claims, Some(account), None, allowed)
+                    },
+                )
+            } else {
+                personal_token_service::authenticate(&bearer, repo.get_ref()).map(
+                    |(claims, token)| {
+                        let allowed = required_scope.is_some_and(|scope| token.allows(scope));
+                        (claims, None, Some(token), allowed)
+                    },
+                )
+            };
+            let (claims, account, token, allowed) = match resolved {
+                Ok(resolved) => resolved,
+                Err(ServiceError::Unauthorized) => {
+                    return Err(ErrorUnauthorized("Invalid token"));
+                }
+                Err(e) => {
+                    log::error!("Failed to verify API token: {e}");
+                    return Err(ErrorInternalServerError("Failed to verify token"));
+                }
+            };
+            if !allowed {
+                return Err(ErrorForbidden("Insufficient token scope"));
+            }
+
+            req.extensions_mut().insert(claims);
+            if let Some(account) = account {
+                req.extensions_mut().insert(account);
+            }
+            if let Some(token) = token {
+                req.extensions_mut().insert(token);
+            }
+            srv.call(req).await
+        })
+    }
+}
+
+/// Keys encrypting the session cookie: the active one and the retired ones
+/// still accepted.
+#[derive(Clone)]
+pub struct SessionCookieKeys {
+    active: Key,
+    retired: Vec<Key>,
+}
 
-            match repo.get_user_by_id(user_id, hub_id) {
-                Ok(Some(_)) => srv.call(req).await,
-                _ => Err(ErrorUnauthorized("User not found")),
+impl SessionCookieKeys {
+    /// Builds the keys from `app.session_cookie`, defaulting the active key to
+    /// the shared secret. Fails when a key is shorter than 64 bytes.
+    pub fn from_config(
+        config: &SessionCookieConfig,
+        default_secret: &str,
+    ) -> Result<Self, KeyError> {
+        let active = Key::try_from(config.active_key(default_secret).as_bytes())?;
+        let retired = config
+            .retired_keys
+            .iter()
+            .map(|key| Key::try_from(key.as_bytes()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { active, retired })
+    }
+
+    /// Key handed to `SessionMiddleware`.
+    pub fn active(&self) -> &Key {
+        &self.active
+    }
+
+    /// Re-encrypts `cookie` with the active key when only a retired key opens
+    /// it. Returns `None` for cookies the active key opens or no key opens.
+    fn reencrypt(&self, cookie: &Cookie<'static>) -> Option<Cookie<'static>> {
+        let mut jar = CookieJar::new();
+        jar.add_original(cookie.clone());
+        if jar.private(&self.active).get(cookie.name()).is_some() {
+            return None;
+        }
+        let plain = self
+            .retired
+            .iter()
+            .find_map(|key| jar.private(key).get(cookie.name()))?;
+
+        let mut upgraded = CookieJar::new();
+        upgraded.private_mut(&self.active).add(plain);
+        upgraded.get(cookie.name()).cloned()
+    }
+
+    /// Rewrites the `Cookie` header so the session cookie is encrypted with
+    /// the active key, or returns `None` when nothing needs rewriting.
+    fn upgrade_cookie_header(&self, headers: &HeaderMap) -> Option<HeaderValue> {
+        if self.retired.is_empty() {
+            return None;
+        }
+
+        let mut upgraded = false;
+        let mut pairs = Vec::new();
+        for value in headers.get_all(header::COOKIE) {
+            let value = value.to_str().ok()?;
+            for pair in value.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+                let cookie = Cookie::parse(pair.to_owned())
+                    .ok()
+                    .filter(|cookie| cookie.name() == SESSION_COOKIE_NAME)
+                    .and_then(|cookie| self.reencrypt(&cookie));
+                match cookie {
+                    Some(cookie) => {
+                        upgraded = true;
+                        pairs.push(format!("{}={}", cookie.name(), cookie.value()));
+                    }
+                    None => pairs.push(pair.to_owned()),
+                }
             }
+        }
+
+        if !upgraded {
+            return None;
+        }
+        HeaderValue::from_str(&pairs.join("; ")).ok()
+    }
+}
+
+/// Middleware accepting session cookies encrypted with a retired cookie key.
+///
+/// `SessionMiddleware` decrypts with a single key, so a session cookie that
+/// only a retired key opens is re-encrypted with the active key before the
+/// request reaches it. The browser gets a cookie under the active key the next
+/// time the session changes. Must wrap `SessionMiddleware`.
+pub struct AcceptRetiredCookieKeys {
+    keys: SessionCookieKeys,
+}
+
+impl AcceptRetiredCookieKeys {
+    pub fn new(keys: SessionCookieKeys) -> Self {
+        Self { keys }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AcceptRetiredCookieKeys
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AcceptRetiredCookieKeysMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(AcceptRetiredCookieKeysMiddleware {
+            service,
+            keys: self.keys.clone(),
         })
     }
 }
+
+/// Service wrapper produced by [`AcceptRetiredCookieKeys`].
+pub struct AcceptRetiredCookieKeysMiddleware<S> {
+    service: S,
+    keys: SessionCookieKeys,
+}
+
+impl<S, B> Service<ServiceRequest> for AcceptRetiredCookieKeysMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    dev::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        if let Some(cookies) = self.keys.upgrade_cookie_header(req.headers()) {
+            req.headers_mut().insert(header::COOKIE, cookies);
+        }
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_KEY: &str = "old-0123456789abcdef0123456789abcdef0123456789abcdef0123456789ab";
+    const NEW_KEY: &str = "new-0123456789abcdef0123456789abcdef0123456789abcdef0123456789ab";
+
+    fn encrypted_cookie(key: &str, value: &str) -> Cookie<'static> {
+        let mut jar = CookieJar::new();
+        jar.private_mut(&Key::from(key.as_bytes()))
+            .add(Cookie::new(SESSION_COOKIE_NAME, value.to_owned()));
+        jar.get(SESSION_COOKIE_NAME).cloned().unwrap()
+    }
+
+    fn rotated_keys() -> SessionCookieKeys {
+        let config = SessionCookieConfig {
+            key: Some(NEW_KEY.into()),
+            retired_keys: vec![OLD_KEY.into()],
+        };
+        SessionCookieKeys::from_config(&config, "unused").unwrap()
+    }
+
+    #[test]
+    fn from_config_rejects_short_keys() {
+        let config = SessionCookieConfig {
+            key: None,
+            retired_keys: vec!["short".into()],
+        };
+
+        assert!(SessionCookieKeys::from_config(&config, NEW_KEY).is_err());
+    }
+
+    #[test]
+    fn upgrade_cookie_header_reencrypts_retired_cookies() {
+        let keys = rotated_keys();
+        let old = encrypted_cookie(OLD_KEY, "session");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&format!("theme=dark; {}={}", old.name(), old.value())).unwrap(),
+        );
+
+        let upgraded = keys.upgrade_cookie_header(&headers).unwrap();
+
+        let mut jar = CookieJar::new();
+        for pair in upgraded.to_str().unwrap().split("; ") {
+            jar.add_original(Cookie::parse(pair.to_owned()).unwrap());
+        }
+        assert_eq!(jar.get("theme").unwrap().value(), "dark");
+        let session = jar.private(keys.active()).get(SESSION_COOKIE_NAME).unwrap();
+        assert_eq!(session.value(), "session");
+    }
+
+    #[test]
+    fn upgrade_cookie_header_keeps_active_cookies() {
+        let keys = rotated_keys();
+        let current = encrypted_cookie(NEW_KEY, "session");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&format!("{}={}", current.name(), current.value())).unwrap(),
+        );
+
+        assert!(keys.upgrade_cookie_header(&headers).is_none());
+    }
+}
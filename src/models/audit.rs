@@ -0,0 +1,67 @@
+//! Diesel models and conversions for audit events.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::audit::{AuditEvent as DomainAuditEvent, NewAuditEvent as DomainNewAuditEvent};
+use crate::domain::types::TypeConstraintError;
+
+#[derive(Debug, Clone, Identifiable, Queryable)]
+#[diesel(table_name = crate::schema::audit_events)]
+/// Diesel model for [`crate::domain::audit::AuditEvent`].
+pub struct AuditEvent {
+    pub id: i32,
+    pub hub_id: i32,
+    pub actor_id: Option<i32>,
+    pub event_type: String,
+    pub target_id: Option<i32>,
+    pub detail: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::audit_events)]
+/// Insertable form of [`AuditEvent`].
+pub struct NewAuditEvent<'a> {
+    pub hub_id: i32,
+    pub actor_id: Option<i32>,
+    pub event_type: &'a str,
+    pub target_id: Option<i32>,
+    pub detail: Option<&'a str>,
+    pub ip_address: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+}
+
+impl TryFrom<AuditEvent> for DomainAuditEvent {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: AuditEvent) -> Result<Self, Self::Error> {
+        DomainAuditEvent::try_new(
+            db.id,
+            db.hub_id,
+            db.actor_id,
+            &db.event_type,
+            db.target_id,
+            db.detail,
+            db.ip_address,
+            db.user_agent,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewAuditEvent> for NewAuditEvent<'a> {
+    fn from(domain: &'a DomainNewAuditEvent) -> Self {
+        Self {
+            hub_id: domain.hub_id.get(),
+            actor_id: domain.actor_id.map(|id| id.get()),
+            event_type: domain.event_type.as_str(),
+            target_id: domain.target_id,
+            detail: domain.detail.as_deref(),
+            ip_address: domain.ip_address.as_deref(),
+            user_agent: domain.user_agent.as_deref(),
+        }
+    }
+}
@@ -1,7 +1,12 @@
 //! Configuration model loaded from external sources.
 
+use std::net::IpAddr;
+
+use chrono::Duration;
 use serde::Deserialize;
 
+use crate::domain::login_throttle::LoginThrottlePolicy;
+
 #[derive(Clone, Debug, Deserialize)]
 /// Basic configuration shared across handlers.
 pub struct Settings {
@@ -21,4 +26,198 @@ pub struct AppConfig {
     pub database_url: String,
     pub zmq_emailer_pub: String,
     pub secret: String,
+    /// Reverse proxies whose `Forwarded` and `X-Forwarded-For` headers name
+    /// the client IP. Requests from other peers are keyed by the peer address,
+    /// so clients cannot choose their own login throttle key.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+    #[serde(default)]
+    pub login_throttle: LoginThrottleConfig,
+    #[serde(default)]
+    pub session_cookie: SessionCookieConfig,
+    #[serde(default)]
+    pub jwt: JwtConfig,
+    #[serde(default)]
+    pub oidc: OidcConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+/// How long deleted users and hubs are kept before they are purged.
+pub struct RetentionConfig {
+    /// Days a deleted user or hub can still be restored.
+    pub deleted_retention_days: i64,
+    /// Interval in seconds between runs of the purge job.
+    pub purge_interval_seconds: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            deleted_retention_days: 30,
+            purge_interval_seconds: 60 * 60,
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Time a deleted entity stays restorable.
+    pub fn retention(&self) -> Duration {
+        Duration::days(self.deleted_retention_days)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+/// Settings of the OpenID Connect provider.
+pub struct OidcConfig {
+    /// Public base URL of the service, used as the `iss` of ID tokens and in
+    /// the discovery document. Defaults to the scheme and host of each
+    /// request, which only suits deployments reachable under a single name.
+    pub issuer: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+/// Keys encrypting the session cookie.
+///
+/// `key` encrypts new cookies. Cookies encrypted with one of `retired_keys`
+/// are still accepted, so the key can be rotated without logging everybody
+/// out. Every key must be at least 64 bytes long.
+pub struct SessionCookieConfig {
+    /// Active cookie key; defaults to `AppConfig.secret`.
+    pub key: Option<String>,
+    /// Keys no longer used for encryption but still accepted for decryption.
+    pub retired_keys: Vec<String>,
+}
+
+impl SessionCookieConfig {
+    /// The active cookie key, falling back to the shared secret.
+    pub fn active_key<'a>(&'a self, default_secret: &'a str) -> &'a str {
+        self.key.as_deref().unwrap_or(default_secret)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+/// Signature algorithm used for session JWTs.
+pub enum JwtAlgorithm {
+    /// HMAC with `AppConfig.secret`; verifiers need the shared secret.
+    #[default]
+    HS256,
+    /// RSA PKCS#1 v1.5 with SHA-256.
+    RS256,
+    /// Ed25519.
+    EdDSA,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+/// Keys used to sign and verify session JWTs.
+///
+/// The top-level fields describe the active key that signs new sessions.
+/// Sessions signed with one of `retired_keys` stay valid until they expire,
+/// so a key can be rotated without logging everybody out.
+pub struct JwtConfig {
+    pub algorithm: JwtAlgorithm,
+    /// HMAC secret for `HS256`; defaults to `AppConfig.secret`.
+    pub secret: Option<String>,
+    /// PEM private key, required for `RS256` and `EdDSA`. The public key
+    /// published in the JWKS is derived from it.
+    pub private_key_path: Option<String>,
+    /// Value of the `kid` header and of the published JWK.
+    pub key_id: String,
+    /// Keys no longer used for signing but still accepted for verification.
+    pub retired_keys: Vec<JwtKeyConfig>,
+    /// Adds the user's permissions to sessions as the `permissions` claim.
+    pub permissions_claim: bool,
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: JwtAlgorithm::HS256,
+            secret: None,
+            private_key_path: None,
+            key_id: "default".to_string(),
+            retired_keys: Vec::new(),
+            permissions_claim: false,
+        }
+    }
+}
+
+impl JwtConfig {
+    /// The active signing key.
+    pub fn active_key(&self) -> JwtKeyConfig {
+        JwtKeyConfig {
+            key_id: self.key_id.clone(),
+            algorithm: self.algorithm,
+            secret: self.secret.clone(),
+            private_key_path: self.private_key_path.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// A single JWT key, identified by its `kid`.
+pub struct JwtKeyConfig {
+    pub key_id: String,
+    #[serde(default)]
+    pub algorithm: JwtAlgorithm,
+    /// HMAC secret for `HS256`; defaults to `AppConfig.secret`.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// PEM private key for `RS256` and `EdDSA`.
+    #[serde(default)]
+    pub private_key_path: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+/// Limits applied to failed `POST /auth/login` attempts.
+pub struct LoginThrottleConfig {
+    /// Failed attempts allowed per account (email and hub) within the window.
+    pub max_attempts_per_account: u32,
+    /// Failed attempts allowed per client IP within the window.
+    pub max_attempts_per_ip: u32,
+    /// Length of the counting window in seconds.
+    pub window_seconds: i64,
+    /// Duration of the first lockout in seconds; doubled on every repeat.
+    pub lockout_seconds: i64,
+    /// Upper bound for a single lockout in seconds.
+    pub max_lockout_seconds: i64,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts_per_account: 5,
+            max_attempts_per_ip: 20,
+            window_seconds: 15 * 60,
+            lockout_seconds: 60,
+            max_lockout_seconds: 60 * 60,
+        }
+    }
+}
+
+impl LoginThrottleConfig {
+    /// Policy applied to the per-account counter.
+    pub fn account_policy(&self) -> LoginThrottlePolicy {
+        self.policy(self.max_attempts_per_account)
+    }
+
+    /// Policy applied to the per-IP counter.
+    pub fn ip_policy(&self) -> LoginThrottlePolicy {
+        self.policy(self.max_attempts_per_ip)
+    }
+
+    fn policy(&self, max_attempts: u32) -> LoginThrottlePolicy {
+        LoginThrottlePolicy {
+            max_attempts: max_attempts.max(1),
+            window: Duration::seconds(self.window_seconds),
+            base_lockout: Duration::seconds(self.lockout_seconds),
+            max_lockout: Duration::seconds(self.max_lockout_seconds),
+        }
+    }
 }
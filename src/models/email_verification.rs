@@ -0,0 +1,62 @@
+//! Diesel models and conversions for email verification tokens.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::email_verification::{
+    EmailVerification as DomainEmailVerification,
+    NewEmailVerification as DomainNewEmailVerification,
+};
+use crate::domain::types::TypeConstraintError;
+use crate::models::user::User;
+
+#[derive(Debug, Clone, Identifiable, Associations, Queryable)]
+#[diesel(belongs_to(User, foreign_key=user_id))]
+#[diesel(table_name = crate::schema::email_verifications)]
+/// Diesel model for [`crate::domain::email_verification::EmailVerification`].
+pub struct EmailVerification {
+    pub id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::email_verifications)]
+/// Insertable form of [`EmailVerification`].
+pub struct NewEmailVerification<'a> {
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub token_hash: &'a str,
+    pub expires_at: NaiveDateTime,
+}
+
+impl TryFrom<EmailVerification> for DomainEmailVerification {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: EmailVerification) -> Result<Self, Self::Error> {
+        DomainEmailVerification::try_new(
+            db.id,
+            db.user_id,
+            db.hub_id,
+            db.token_hash,
+            db.expires_at,
+            db.used_at,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewEmailVerification> for NewEmailVerification<'a> {
+    fn from(domain: &'a DomainNewEmailVerification) -> Self {
+        Self {
+            user_id: domain.user_id.get(),
+            hub_id: domain.hub_id.get(),
+            token_hash: domain.token_hash.as_str(),
+            expires_at: domain.expires_at,
+        }
+    }
+}
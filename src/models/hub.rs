@@ -3,8 +3,14 @@
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
-use crate::domain::types::TypeConstraintError;
-use crate::domain::{hub::Hub as DomainHub, hub::NewHub as DomainNewHub};
+use crate::domain::types::{RoleId, TypeConstraintError};
+use crate::domain::{
+    hub::Hub as DomainHub, hub::HubPolicy as DomainHubPolicy,
+    hub::HubRegistrationSettings as DomainHubRegistrationSettings, hub::NewHub as DomainNewHub,
+};
+
+/// Separator of the email domains stored in a single column.
+const EMAIL_DOMAIN_SEPARATOR: &str = "\n";
 
 #[derive(Debug, Clone, Identifiable, Queryable)]
 #[diesel(table_name = crate::schema::hubs)]
@@ -14,6 +20,7 @@ pub struct Hub {
     pub name: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable)]
@@ -27,7 +34,9 @@ impl TryFrom<Hub> for DomainHub {
     type Error = TypeConstraintError;
 
     fn try_from(db: Hub) -> Result<Self, Self::Error> {
-        DomainHub::try_new(db.id, db.name, db.created_at, db.updated_at)
+        let mut hub = DomainHub::try_new(db.id, db.name, db.created_at, db.updated_at)?;
+        hub.deleted_at = db.deleted_at;
+        Ok(hub)
     }
 }
 
@@ -38,3 +47,72 @@ impl<'a> From<&'a DomainNewHub> for NewHub<'a> {
         }
     }
 }
+
+#[derive(Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::hub_policies)]
+#[diesel(primary_key(hub_id))]
+/// Database representation of a [`crate::domain::hub::HubPolicy`].
+pub struct HubPolicy {
+    pub hub_id: i32,
+    pub require_admin_2fa: bool,
+    pub require_email_verification: bool,
+}
+
+impl From<HubPolicy> for DomainHubPolicy {
+    fn from(db: HubPolicy) -> Self {
+        Self {
+            require_admin_2fa: db.require_admin_2fa,
+            require_email_verification: db.require_email_verification,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::hub_registration_settings)]
+#[diesel(primary_key(hub_id))]
+/// Database representation of a
+/// [`crate::domain::hub::HubRegistrationSettings`] without its default roles.
+pub struct HubRegistrationSettings {
+    pub hub_id: i32,
+    pub registration_mode: String,
+    pub allowed_email_domains: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::hub_default_roles)]
+/// Role assigned to users who register in a hub.
+pub struct NewHubDefaultRole {
+    pub hub_id: i32,
+    pub role_id: i32,
+}
+
+impl HubRegistrationSettings {
+    /// Builds the stored row of `settings` for `hub_id`.
+    pub fn new(hub_id: i32, settings: &DomainHubRegistrationSettings) -> Self {
+        Self {
+            hub_id,
+            registration_mode: settings.registration_mode.as_str().to_string(),
+            allowed_email_domains: settings.allowed_email_domains.join(EMAIL_DOMAIN_SEPARATOR),
+        }
+    }
+
+    /// Converts the row into domain settings with the given default roles.
+    pub fn into_domain(
+        self,
+        default_roles: Vec<i32>,
+    ) -> Result<DomainHubRegistrationSettings, TypeConstraintError> {
+        Ok(DomainHubRegistrationSettings {
+            registration_mode: self.registration_mode.parse()?,
+            allowed_email_domains: self
+                .allowed_email_domains
+                .split(EMAIL_DOMAIN_SEPARATOR)
+                .filter(|domain| !domain.is_empty())
+                .map(str::to_string)
+                .collect(),
+            default_roles: default_roles
+                .into_iter()
+                .map(RoleId::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
@@ -0,0 +1,75 @@
+//! Diesel models and conversions for invitations.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::invitation::{
+    Invitation as DomainInvitation, NewInvitation as DomainNewInvitation,
+};
+use crate::domain::types::TypeConstraintError;
+
+#[derive(Debug, Clone, Identifiable, Queryable)]
+#[diesel(table_name = crate::schema::invitations)]
+/// Diesel model for [`crate::domain::invitation::Invitation`].
+pub struct Invitation {
+    pub id: i32,
+    pub hub_id: i32,
+    pub email: String,
+    pub name: String,
+    pub token_hash: String,
+    pub invited_by: Option<i32>,
+    pub expires_at: NaiveDateTime,
+    pub accepted_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::invitations)]
+/// Insertable form of [`Invitation`].
+pub struct NewInvitation<'a> {
+    pub hub_id: i32,
+    pub email: &'a str,
+    pub name: &'a str,
+    pub token_hash: &'a str,
+    pub invited_by: Option<i32>,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::invitation_roles)]
+/// Association row linking an invitation to a role.
+pub struct NewInvitationRole {
+    pub invitation_id: i32,
+    pub role_id: i32,
+}
+
+impl Invitation {
+    /// Converts the row into the domain type with the ids of its roles.
+    pub fn into_domain(self, roles: Vec<i32>) -> Result<DomainInvitation, TypeConstraintError> {
+        DomainInvitation::try_new(
+            self.id,
+            self.hub_id,
+            self.email,
+            self.name,
+            self.token_hash,
+            self.invited_by,
+            self.expires_at,
+            self.accepted_at,
+            self.created_at,
+            roles,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewInvitation> for NewInvitation<'a> {
+    fn from(domain: &'a DomainNewInvitation) -> Self {
+        Self {
+            hub_id: domain.hub_id.get(),
+            email: domain.email.as_str(),
+            name: domain.name.as_str(),
+            token_hash: domain.token_hash.as_str(),
+            invited_by: domain.invited_by.map(|id| id.get()),
+            expires_at: domain.expires_at,
+        }
+    }
+}
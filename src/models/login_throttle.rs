@@ -0,0 +1,43 @@
+//! Diesel models and conversions for failed-login counters.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::login_throttle::LoginThrottle as DomainLoginThrottle;
+
+#[derive(Debug, Clone, Identifiable, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = crate::schema::login_throttles)]
+#[diesel(primary_key(throttle_key))]
+#[diesel(treat_none_as_null = true)]
+/// Diesel model for [`crate::domain::login_throttle::LoginThrottle`].
+pub struct LoginThrottle {
+    pub throttle_key: String,
+    pub failed_attempts: i32,
+    pub window_started_at: NaiveDateTime,
+    pub lockout_count: i32,
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+impl From<LoginThrottle> for DomainLoginThrottle {
+    fn from(db: LoginThrottle) -> Self {
+        Self {
+            key: db.throttle_key,
+            failed_attempts: db.failed_attempts.max(0) as u32,
+            window_started_at: db.window_started_at,
+            lockout_count: db.lockout_count.max(0) as u32,
+            locked_until: db.locked_until,
+        }
+    }
+}
+
+impl From<&DomainLoginThrottle> for LoginThrottle {
+    fn from(domain: &DomainLoginThrottle) -> Self {
+        Self {
+            throttle_key: domain.key.clone(),
+            failed_attempts: domain.failed_attempts.min(i32::MAX as u32) as i32,
+            window_started_at: domain.window_started_at,
+            lockout_count: domain.lockout_count.min(i32::MAX as u32) as i32,
+            locked_until: domain.locked_until,
+        }
+    }
+}
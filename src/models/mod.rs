@@ -3,8 +3,19 @@
 //! These types closely mirror the schema of the database and are used by the
 //! repository layer. They also implement conversions to the domain layer types.
 
+pub mod audit;
 pub mod config;
+pub mod email_verification;
 pub mod hub;
+pub mod invitation;
+pub mod login_throttle;
 pub mod menu;
+pub mod oauth;
+pub mod password_reset;
+pub mod personal_token;
 pub mod role;
+pub mod service_account;
+pub mod session;
+pub mod two_factor;
 pub mod user;
+pub mod user_credential;
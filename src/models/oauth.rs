@@ -0,0 +1,198 @@
+//! Diesel models and conversions for the OpenID Connect provider.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::oauth::{
+    AccessToken as DomainAccessToken, AuthorizationCode as DomainAuthorizationCode,
+    NewAccessToken as DomainNewAccessToken, NewAuthorizationCode as DomainNewAuthorizationCode,
+    NewOAuthClient as DomainNewOAuthClient, OAuthClient as DomainOAuthClient,
+};
+use crate::domain::types::TypeConstraintError;
+
+/// Separator of the redirect URIs stored in a single column.
+const REDIRECT_URI_SEPARATOR: &str = "\n";
+
+#[derive(Debug, Clone, Identifiable, Queryable)]
+#[diesel(table_name = crate::schema::oauth_clients)]
+/// Diesel model for [`crate::domain::oauth::OAuthClient`].
+pub struct OAuthClient {
+    pub id: i32,
+    pub client_id: String,
+    pub client_secret_hash: Option<String>,
+    pub name: String,
+    pub redirect_uris: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::oauth_clients)]
+/// Insertable form of [`OAuthClient`].
+pub struct NewOAuthClient<'a> {
+    pub client_id: &'a str,
+    pub client_secret_hash: Option<&'a str>,
+    pub name: &'a str,
+    pub redirect_uris: String,
+}
+
+#[derive(Debug, Clone, Identifiable, Associations, Queryable)]
+#[diesel(belongs_to(OAuthClient, foreign_key=client_id))]
+#[diesel(table_name = crate::schema::oauth_authorization_codes)]
+/// Diesel model for [`crate::domain::oauth::AuthorizationCode`].
+pub struct AuthorizationCode {
+    pub id: i32,
+    pub code_hash: String,
+    pub client_id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub nonce: Option<String>,
+    pub code_challenge: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::oauth_authorization_codes)]
+/// Insertable form of [`AuthorizationCode`].
+pub struct NewAuthorizationCode<'a> {
+    pub code_hash: &'a str,
+    pub client_id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub redirect_uri: &'a str,
+    pub scope: &'a str,
+    pub nonce: Option<&'a str>,
+    pub code_challenge: &'a str,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Identifiable, Associations, Queryable)]
+#[diesel(belongs_to(OAuthClient, foreign_key=client_id))]
+#[diesel(table_name = crate::schema::oauth_access_tokens)]
+/// Diesel model for [`crate::domain::oauth::AccessToken`].
+pub struct AccessToken {
+    pub id: i32,
+    pub token_hash: String,
+    pub client_id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub scope: String,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::oauth_access_tokens)]
+/// Insertable form of [`AccessToken`].
+pub struct NewAccessToken<'a> {
+    pub token_hash: &'a str,
+    pub client_id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub scope: &'a str,
+    pub expires_at: NaiveDateTime,
+}
+
+impl TryFrom<OAuthClient> for DomainOAuthClient {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: OAuthClient) -> Result<Self, Self::Error> {
+        let redirect_uris = db
+            .redirect_uris
+            .split(REDIRECT_URI_SEPARATOR)
+            .filter(|uri| !uri.is_empty())
+            .map(str::to_string)
+            .collect();
+        DomainOAuthClient::try_new(
+            db.id,
+            db.client_id,
+            db.client_secret_hash,
+            db.name,
+            redirect_uris,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewOAuthClient> for NewOAuthClient<'a> {
+    fn from(domain: &'a DomainNewOAuthClient) -> Self {
+        Self {
+            client_id: domain.client_id.as_str(),
+            client_secret_hash: domain.client_secret_hash.as_deref(),
+            name: domain.name.as_str(),
+            redirect_uris: domain.redirect_uris.join(REDIRECT_URI_SEPARATOR),
+        }
+    }
+}
+
+impl TryFrom<AuthorizationCode> for DomainAuthorizationCode {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: AuthorizationCode) -> Result<Self, Self::Error> {
+        DomainAuthorizationCode::try_new(
+            db.id,
+            db.code_hash,
+            db.client_id,
+            db.user_id,
+            db.hub_id,
+            db.redirect_uri,
+            db.scope,
+            db.nonce,
+            db.code_challenge,
+            db.expires_at,
+            db.used_at,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewAuthorizationCode> for NewAuthorizationCode<'a> {
+    fn from(domain: &'a DomainNewAuthorizationCode) -> Self {
+        Self {
+            code_hash: domain.code_hash.as_str(),
+            client_id: domain.client_id.get(),
+            user_id: domain.user_id.get(),
+            hub_id: domain.hub_id.get(),
+            redirect_uri: domain.redirect_uri.as_str(),
+            scope: domain.scope.as_str(),
+            nonce: domain.nonce.as_deref(),
+            code_challenge: domain.code_challenge.as_str(),
+            expires_at: domain.expires_at,
+        }
+    }
+}
+
+impl TryFrom<AccessToken> for DomainAccessToken {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: AccessToken) -> Result<Self, Self::Error> {
+        DomainAccessToken::try_new(
+            db.id,
+            db.token_hash,
+            db.client_id,
+            db.user_id,
+            db.hub_id,
+            db.scope,
+            db.expires_at,
+            db.revoked_at,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewAccessToken> for NewAccessToken<'a> {
+    fn from(domain: &'a DomainNewAccessToken) -> Self {
+        Self {
+            token_hash: domain.token_hash.as_str(),
+            client_id: domain.client_id.get(),
+            user_id: domain.user_id.get(),
+            hub_id: domain.hub_id.get(),
+            scope: domain.scope.as_str(),
+            expires_at: domain.expires_at,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Diesel models and conversions for password recovery tokens.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::password_reset::{
+    NewPasswordReset as DomainNewPasswordReset, PasswordReset as DomainPasswordReset,
+};
+use crate::domain::types::TypeConstraintError;
+use crate::models::user::User;
+
+#[derive(Debug, Clone, Identifiable, Associations, Queryable)]
+#[diesel(belongs_to(User, foreign_key=user_id))]
+#[diesel(table_name = crate::schema::password_resets)]
+/// Diesel model for [`crate::domain::password_reset::PasswordReset`].
+pub struct PasswordReset {
+    pub id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::password_resets)]
+/// Insertable form of [`PasswordReset`].
+pub struct NewPasswordReset<'a> {
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub token_hash: &'a str,
+    pub expires_at: NaiveDateTime,
+}
+
+impl TryFrom<PasswordReset> for DomainPasswordReset {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: PasswordReset) -> Result<Self, Self::Error> {
+        DomainPasswordReset::try_new(
+            db.id,
+            db.user_id,
+            db.hub_id,
+            db.token_hash,
+            db.expires_at,
+            db.used_at,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewPasswordReset> for NewPasswordReset<'a> {
+    fn from(domain: &'a DomainNewPasswordReset) -> Self {
+        Self {
+            user_id: domain.user_id.get(),
+            hub_id: domain.hub_id.get(),
+            token_hash: domain.token_hash.as_str(),
+            expires_at: domain.expires_at,
+        }
+    }
+}
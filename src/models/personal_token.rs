@@ -0,0 +1,84 @@
+//! Diesel models and conversions for personal access tokens.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::personal_token::{
+    NewPersonalAccessToken as DomainNewPersonalAccessToken,
+    PersonalAccessToken as DomainPersonalAccessToken, TokenScope,
+};
+use crate::domain::types::TypeConstraintError;
+
+/// Separator of the scopes stored in a single column.
+const SCOPE_SEPARATOR: &str = " ";
+
+#[derive(Debug, Clone, Identifiable, Queryable)]
+#[diesel(table_name = crate::schema::personal_access_tokens)]
+/// Diesel model for [`crate::domain::personal_token::PersonalAccessToken`].
+pub struct PersonalAccessToken {
+    pub id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::personal_access_tokens)]
+/// Insertable form of [`PersonalAccessToken`].
+pub struct NewPersonalAccessToken<'a> {
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub name: &'a str,
+    pub token_hash: &'a str,
+    pub scopes: String,
+    pub expires_at: NaiveDateTime,
+}
+
+impl TryFrom<PersonalAccessToken> for DomainPersonalAccessToken {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: PersonalAccessToken) -> Result<Self, Self::Error> {
+        let scopes = db
+            .scopes
+            .split(SCOPE_SEPARATOR)
+            .filter(|scope| !scope.is_empty())
+            .map(str::parse::<TokenScope>)
+            .collect::<Result<Vec<_>, _>>()?;
+        DomainPersonalAccessToken::try_new(
+            db.id,
+            db.user_id,
+            db.hub_id,
+            db.name,
+            db.token_hash,
+            scopes,
+            db.expires_at,
+            db.last_used_at,
+            db.revoked_at,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewPersonalAccessToken> for NewPersonalAccessToken<'a> {
+    fn from(domain: &'a DomainNewPersonalAccessToken) -> Self {
+        Self {
+            user_id: domain.user_id.get(),
+            hub_id: domain.hub_id.get(),
+            name: domain.name.as_str(),
+            token_hash: domain.token_hash.as_str(),
+            scopes: domain
+                .scopes
+                .iter()
+                .map(TokenScope::as_str)
+                .collect::<Vec<_>>()
+                .join(SCOPE_SEPARATOR),
+            expires_at: domain.expires_at,
+        }
+    }
+}
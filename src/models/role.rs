@@ -4,7 +4,7 @@ use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
 use crate::domain::role::{NewUserRole as DomainNewUserRole, UserRole as DomainUserRole};
-use crate::domain::types::TypeConstraintError;
+use crate::domain::types::{HubId, TypeConstraintError};
 use crate::domain::{role::NewRole as DomainNewRole, role::Role as DomainRole};
 use crate::models::user::User;
 
@@ -16,6 +16,8 @@ pub struct Role {
     pub name: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub hub_id: Option<i32>,
+    pub description: String,
 }
 
 #[derive(Insertable)]
@@ -23,6 +25,17 @@ pub struct Role {
 /// Insertable form of [`Role`].
 pub struct NewRole<'a> {
     pub name: &'a str,
+    pub hub_id: Option<i32>,
+    pub description: &'a str,
+}
+
+#[derive(AsChangeset)]
+#[diesel(table_name = crate::schema::roles)]
+/// Data used when updating a [`Role`] record.
+pub struct UpdateRole<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub updated_at: NaiveDateTime,
 }
 
 #[derive(Debug, Clone, Queryable, Associations, Identifiable)]
@@ -44,11 +57,32 @@ pub struct NewUserRole {
     pub role_id: i32,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::role_parents)]
+/// Parent role implied by a role.
+pub struct NewRoleParent {
+    pub role_id: i32,
+    pub parent_id: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::role_permissions)]
+/// Permission granted to a role.
+pub struct NewRolePermission {
+    pub role_id: i32,
+    pub permission_id: i32,
+}
+
 impl TryFrom<Role> for DomainRole {
     type Error = TypeConstraintError;
 
     fn try_from(db: Role) -> Result<Self, Self::Error> {
-        DomainRole::try_new(db.id, db.name, db.created_at, db.updated_at)
+        let role = DomainRole::try_new(db.id, db.name, db.created_at, db.updated_at)?
+            .with_description(db.description);
+        Ok(match db.hub_id {
+            Some(hub_id) => role.with_hub(HubId::try_from(hub_id)?),
+            None => role,
+        })
     }
 }
 
@@ -56,6 +90,8 @@ impl<'a> From<&'a DomainNewRole> for NewRole<'a> {
     fn from(domain: &'a DomainNewRole) -> Self {
         Self {
             name: domain.name.as_str(),
+            hub_id: domain.hub_id.map(|id| id.get()),
+            description: domain.description.as_str(),
         }
     }
 }
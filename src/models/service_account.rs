@@ -0,0 +1,123 @@
+//! Diesel models and conversions for service accounts and their tokens.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::role::Role as DomainRole;
+use crate::domain::service_account::{
+    NewServiceAccount as DomainNewServiceAccount,
+    NewServiceAccountToken as DomainNewServiceAccountToken, ServiceAccount as DomainServiceAccount,
+    ServiceAccountToken as DomainServiceAccountToken,
+};
+use crate::domain::types::TypeConstraintError;
+
+#[derive(Debug, Clone, Identifiable, Queryable)]
+#[diesel(table_name = crate::schema::service_accounts)]
+/// Diesel model for [`crate::domain::service_account::ServiceAccount`].
+pub struct ServiceAccount {
+    pub id: i32,
+    pub hub_id: i32,
+    pub name: String,
+    pub client_id: String,
+    pub secret_hash: String,
+    pub secret_rotated_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::service_accounts)]
+/// Insertable form of [`ServiceAccount`].
+pub struct NewServiceAccount<'a> {
+    pub hub_id: i32,
+    pub name: &'a str,
+    pub client_id: &'a str,
+    pub secret_hash: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::service_account_roles)]
+/// Association row linking a service account to a role.
+pub struct NewServiceAccountRole {
+    pub service_account_id: i32,
+    pub role_id: i32,
+}
+
+#[derive(Debug, Clone, Identifiable, Queryable)]
+#[diesel(table_name = crate::schema::service_account_tokens)]
+/// Diesel model for [`crate::domain::service_account::ServiceAccountToken`].
+pub struct ServiceAccountToken {
+    pub id: i32,
+    pub service_account_id: i32,
+    pub hub_id: i32,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::service_account_tokens)]
+/// Insertable form of [`ServiceAccountToken`].
+pub struct NewServiceAccountToken<'a> {
+    pub service_account_id: i32,
+    pub hub_id: i32,
+    pub token_hash: &'a str,
+    pub expires_at: NaiveDateTime,
+}
+
+impl ServiceAccount {
+    /// Converts the row into the domain type with its assigned roles.
+    pub fn into_domain(
+        self,
+        roles: Vec<DomainRole>,
+    ) -> Result<DomainServiceAccount, TypeConstraintError> {
+        DomainServiceAccount::try_new(
+            self.id,
+            self.hub_id,
+            self.name,
+            self.client_id,
+            self.secret_hash,
+            self.secret_rotated_at,
+            self.created_at,
+            roles,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewServiceAccount> for NewServiceAccount<'a> {
+    fn from(domain: &'a DomainNewServiceAccount) -> Self {
+        Self {
+            hub_id: domain.hub_id.get(),
+            name: domain.name.as_str(),
+            client_id: domain.client_id.as_str(),
+            secret_hash: domain.secret_hash.as_str(),
+        }
+    }
+}
+
+impl TryFrom<ServiceAccountToken> for DomainServiceAccountToken {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: ServiceAccountToken) -> Result<Self, Self::Error> {
+        DomainServiceAccountToken::try_new(
+            db.id,
+            db.service_account_id,
+            db.hub_id,
+            db.token_hash,
+            db.expires_at,
+            db.revoked_at,
+            db.created_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewServiceAccountToken> for NewServiceAccountToken<'a> {
+    fn from(domain: &'a DomainNewServiceAccountToken) -> Self {
+        Self {
+            service_account_id: domain.service_account_id.get(),
+            hub_id: domain.hub_id.get(),
+            token_hash: domain.token_hash.as_str(),
+            expires_at: domain.expires_at,
+        }
+    }
+}
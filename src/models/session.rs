@@ -0,0 +1,73 @@
+//! Diesel models and conversions for issued sessions.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::session::{NewSession as DomainNewSession, Session as DomainSession};
+use crate::domain::types::TypeConstraintError;
+use crate::models::user::User;
+
+#[derive(Debug, Clone, Identifiable, Associations, Queryable)]
+#[diesel(belongs_to(User, foreign_key=user_id))]
+#[diesel(table_name = crate::schema::sessions)]
+/// Diesel model for [`crate::domain::session::Session`].
+pub struct Session {
+    pub id: i32,
+    pub jti: String,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub key_id: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_seen_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::sessions)]
+/// Insertable form of [`Session`].
+pub struct NewSession<'a> {
+    pub jti: &'a str,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub key_id: &'a str,
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<&'a str>,
+    pub ip_address: Option<&'a str>,
+}
+
+impl TryFrom<Session> for DomainSession {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: Session) -> Result<Self, Self::Error> {
+        DomainSession::try_new(
+            db.id,
+            db.jti,
+            db.user_id,
+            db.hub_id,
+            db.key_id,
+            db.created_at,
+            db.expires_at,
+            db.user_agent,
+            db.ip_address,
+            db.last_seen_at,
+            db.revoked_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewSession> for NewSession<'a> {
+    fn from(domain: &'a DomainNewSession) -> Self {
+        Self {
+            jti: domain.jti.as_str(),
+            user_id: domain.user_id.get(),
+            hub_id: domain.hub_id.get(),
+            key_id: domain.key_id.as_str(),
+            expires_at: domain.expires_at,
+            user_agent: domain.user_agent.as_deref(),
+            ip_address: domain.ip_address.as_deref(),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! Diesel models and conversions for TOTP second factors.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::two_factor::UserTotp as DomainUserTotp;
+use crate::domain::types::TypeConstraintError;
+use crate::models::user::User;
+
+#[derive(Debug, Clone, Identifiable, Associations, Queryable)]
+#[diesel(belongs_to(User, foreign_key=user_id))]
+#[diesel(table_name = crate::schema::user_totp)]
+#[diesel(primary_key(user_id))]
+/// Diesel model for [`crate::domain::two_factor::UserTotp`].
+pub struct UserTotp {
+    pub user_id: i32,
+    pub secret: String,
+    pub enabled_at: Option<NaiveDateTime>,
+    pub last_used_step: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::user_totp)]
+/// Insertable form of a pending [`UserTotp`].
+pub struct NewUserTotp<'a> {
+    pub user_id: i32,
+    pub secret: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::user_recovery_codes)]
+/// Insertable form of a hashed recovery code.
+pub struct NewRecoveryCode<'a> {
+    pub user_id: i32,
+    pub code_hash: &'a str,
+}
+
+impl TryFrom<UserTotp> for DomainUserTotp {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: UserTotp) -> Result<Self, Self::Error> {
+        DomainUserTotp::try_new(
+            db.user_id,
+            db.secret,
+            db.enabled_at,
+            db.last_used_step,
+            db.created_at,
+        )
+    }
+}
@@ -21,6 +21,11 @@ pub struct User {
     pub password_hash: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub disabled_reason: Option<String>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub must_change_password: bool,
 }
 
 #[derive(QueryableByName)]
@@ -37,6 +42,7 @@ pub struct NewUser {
     pub name: Option<String>,
     pub hub_id: i32,
     pub password_hash: String,
+    pub must_change_password: bool,
 }
 
 #[derive(AsChangeset)]
@@ -45,6 +51,7 @@ pub struct NewUser {
 pub struct UpdateUser<'a> {
     pub name: &'a str,
     pub password_hash: String,
+    pub must_change_password: Option<bool>,
     pub updated_at: NaiveDateTime,
 }
 
@@ -52,7 +59,7 @@ impl TryFrom<User> for DomainUser {
     type Error = TypeConstraintError;
 
     fn try_from(db: User) -> Result<Self, Self::Error> {
-        DomainUser::try_new(
+        let mut user = DomainUser::try_new(
             db.id,
             db.email,
             db.name,
@@ -61,7 +68,13 @@ impl TryFrom<User> for DomainUser {
             db.created_at,
             db.updated_at,
             vec![],
-        )
+        )?;
+        user.email_verified_at = db.email_verified_at;
+        user.disabled_at = db.disabled_at;
+        user.disabled_reason = db.disabled_reason;
+        user.deleted_at = db.deleted_at;
+        user.must_change_password = db.must_change_password;
+        Ok(user)
     }
 }
 
@@ -76,6 +89,7 @@ impl<'a> TryFrom<&'a DomainNewUser> for NewUser {
             name: nu.name.clone().map(UserName::into_inner),
             hub_id: nu.hub_id.get(),
             password_hash,
+            must_change_password: nu.must_change_password,
         })
     }
 }
@@ -91,6 +105,7 @@ impl TryFrom<DomainNewUser> for NewUser {
             name: nu.name.map(UserName::into_inner),
             hub_id: nu.hub_id.get(),
             password_hash,
+            must_change_password: nu.must_change_password,
         })
     }
 }
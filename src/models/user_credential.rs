@@ -0,0 +1,65 @@
+//! Diesel models and conversions for WebAuthn credentials.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+use crate::domain::types::TypeConstraintError;
+use crate::domain::user_credential::{
+    NewUserCredential as DomainNewUserCredential, UserCredential as DomainUserCredential,
+};
+use crate::models::user::User;
+
+#[derive(Debug, Clone, Identifiable, Associations, Queryable)]
+#[diesel(belongs_to(User, foreign_key=user_id))]
+#[diesel(table_name = crate::schema::user_credentials)]
+/// Diesel model for [`crate::domain::user_credential::UserCredential`].
+pub struct UserCredential {
+    pub id: i32,
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub credential_id: String,
+    pub name: String,
+    pub passkey: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::user_credentials)]
+/// Insertable form of [`UserCredential`].
+pub struct NewUserCredential<'a> {
+    pub user_id: i32,
+    pub hub_id: i32,
+    pub credential_id: &'a str,
+    pub name: &'a str,
+    pub passkey: &'a str,
+}
+
+impl TryFrom<UserCredential> for DomainUserCredential {
+    type Error = TypeConstraintError;
+
+    fn try_from(db: UserCredential) -> Result<Self, Self::Error> {
+        DomainUserCredential::try_new(
+            db.id,
+            db.user_id,
+            db.hub_id,
+            db.credential_id,
+            db.name,
+            db.passkey,
+            db.created_at,
+            db.last_used_at,
+        )
+    }
+}
+
+impl<'a> From<&'a DomainNewUserCredential> for NewUserCredential<'a> {
+    fn from(domain: &'a DomainNewUserCredential) -> Self {
+        Self {
+            user_id: domain.user_id.get(),
+            hub_id: domain.hub_id.get(),
+            credential_id: domain.credential_id.as_str(),
+            name: domain.name.as_str(),
+            passkey: domain.passkey.as_str(),
+        }
+    }
+}
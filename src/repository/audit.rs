@@ -0,0 +1,76 @@
+//! Diesel-backed repository operations for the audit log.
+
+use diesel::prelude::*;
+use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
+
+use crate::domain::audit::{AuditEvent, NewAuditEvent};
+use crate::models::audit::{AuditEvent as DbAuditEvent, NewAuditEvent as NewDbAuditEvent};
+use crate::repository::{AuditEventQuery, AuditReader, AuditWriter, DieselRepository};
+
+impl AuditReader for DieselRepository {
+    fn list_audit_events(
+        &self,
+        query: AuditEventQuery,
+    ) -> RepositoryResult<(usize, Vec<AuditEvent>)> {
+        use crate::schema::audit_events;
+
+        let mut conn = self.conn()?;
+
+        conn.transaction::<_, RepositoryError, _>(|conn| {
+            // Same definition for counting and for loading the page.
+            let query_builder = || {
+                let mut items = audit_events::table
+                    .filter(audit_events::hub_id.eq(query.hub_id.get()))
+                    .into_boxed::<diesel::sqlite::Sqlite>();
+                if let Some(event_type) = query.event_type {
+                    items = items.filter(audit_events::event_type.eq(event_type.as_str()));
+                }
+                if let Some(actor_id) = query.actor_id {
+                    items = items.filter(audit_events::actor_id.eq(actor_id.get()));
+                }
+                if let Some(target_id) = query.target_id {
+                    items = items.filter(audit_events::target_id.eq(target_id));
+                }
+                if let Some(since) = query.since {
+                    items = items.filter(audit_events::created_at.ge(since));
+                }
+                if let Some(until) = query.until {
+                    items = items.filter(audit_events::created_at.lt(until));
+                }
+                items
+            };
+
+            let total = query_builder().count().get_result::<i64>(conn)? as usize;
+
+            let mut items = query_builder();
+            if let Some(pagination) = &query.pagination {
+                let offset = ((pagination.page.max(1) - 1) * pagination.per_page) as i64;
+                let limit = pagination.per_page as i64;
+                items = items.offset(offset).limit(limit);
+            }
+
+            let events = items
+                .order((audit_events::created_at.desc(), audit_events::id.desc()))
+                .load::<DbAuditEvent>(conn)?
+                .into_iter()
+                .map(|event| Ok(event.try_into()?))
+                .collect::<RepositoryResult<Vec<AuditEvent>>>()?;
+
+            Ok((total, events))
+        })
+    }
+}
+
+impl AuditWriter for DieselRepository {
+    fn record_audit_event(&self, new_event: &NewAuditEvent) -> RepositoryResult<()> {
+        use crate::schema::audit_events;
+
+        let mut connection = self.conn()?;
+
+        diesel::insert_into(audit_events::table)
+            .values(&NewDbAuditEvent::from(new_event))
+            .execute(&mut connection)?;
+
+        Ok(())
+    }
+}
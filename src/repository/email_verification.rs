@@ -0,0 +1,78 @@
+//! Diesel-backed repository operations for email verification tokens.
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
+
+use crate::domain::email_verification::{EmailVerification, NewEmailVerification};
+use crate::models::email_verification::{
+    EmailVerification as DbEmailVerification, NewEmailVerification as NewDbEmailVerification,
+};
+use crate::repository::{DieselRepository, EmailVerificationWriter};
+
+impl EmailVerificationWriter for DieselRepository {
+    fn create_email_verification(
+        &self,
+        new_verification: &NewEmailVerification,
+    ) -> RepositoryResult<EmailVerification> {
+        use crate::schema::email_verifications;
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            // Only the most recently sent link may be used.
+            diesel::update(
+                email_verifications::table
+                    .filter(email_verifications::user_id.eq(new_verification.user_id.get()))
+                    .filter(email_verifications::used_at.is_null()),
+            )
+            .set(email_verifications::used_at.eq(Utc::now().naive_utc()))
+            .execute(conn)?;
+
+            let verification = diesel::insert_into(email_verifications::table)
+                .values(&NewDbEmailVerification::from(new_verification))
+                .get_result::<DbEmailVerification>(conn)?;
+
+            Ok(verification.try_into()?)
+        })
+    }
+
+    fn consume_email_verification(
+        &self,
+        token_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<EmailVerification>> {
+        use crate::schema::{email_verifications, users};
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            // Marking the row as used in the same statement that checks it
+            // guarantees a token can only be redeemed once.
+            let verification = diesel::update(
+                email_verifications::table
+                    .filter(email_verifications::token_hash.eq(token_hash))
+                    .filter(email_verifications::used_at.is_null())
+                    .filter(email_verifications::expires_at.gt(now)),
+            )
+            .set(email_verifications::used_at.eq(now))
+            .get_result::<DbEmailVerification>(conn)
+            .optional()?;
+
+            let Some(verification) = verification else {
+                return Ok(None);
+            };
+
+            diesel::update(
+                users::table
+                    .filter(users::id.eq(verification.user_id))
+                    .filter(users::hub_id.eq(verification.hub_id))
+                    .filter(users::email_verified_at.is_null()),
+            )
+            .set(users::email_verified_at.eq(now))
+            .execute(conn)?;
+
+            Ok(Some(verification.try_into()?))
+        })
+    }
+}
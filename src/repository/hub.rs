@@ -1,13 +1,111 @@
 //! Diesel-backed repository operations for hubs.
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
+use pushkind_common::db::DbConnection;
 use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
 
-use crate::domain::hub::{Hub, NewHub};
+use crate::domain::hub::{Hub, HubPolicy, HubRegistrationSettings, NewHub};
 use crate::domain::types::HubId;
-use crate::models::hub::{Hub as DbHub, NewHub as NewDbHub};
+use crate::models::hub::{
+    Hub as DbHub, HubPolicy as DbHubPolicy, HubRegistrationSettings as DbHubRegistrationSettings,
+    NewHub as NewDbHub, NewHubDefaultRole as NewDbHubDefaultRole,
+};
 use crate::repository::{DieselRepository, HubReader, HubWriter};
 
+/// Removes a hub with its users, menus, role assignments and other dependent
+/// rows.
+fn purge_hub(conn: &mut DbConnection, hub_id: i32) -> QueryResult<usize> {
+    use crate::schema::hub_default_roles;
+    use crate::schema::hub_registration_settings;
+    use crate::schema::hubs;
+    use crate::schema::invitation_roles;
+    use crate::schema::invitations;
+    use crate::schema::menu;
+    use crate::schema::personal_access_tokens;
+    use crate::schema::role_parents;
+    use crate::schema::role_permissions;
+    use crate::schema::roles;
+    use crate::schema::service_account_roles;
+    use crate::schema::service_account_tokens;
+    use crate::schema::service_accounts;
+    use crate::schema::user_roles;
+    use crate::schema::users;
+
+    // delete menus for hub
+    diesel::delete(menu::table.filter(menu::hub_id.eq(hub_id))).execute(conn)?;
+
+    // delete registration settings for hub
+    diesel::delete(hub_default_roles::table.filter(hub_default_roles::hub_id.eq(hub_id)))
+        .execute(conn)?;
+    diesel::delete(hub_registration_settings::table.find(hub_id)).execute(conn)?;
+
+    // delete invitations with their roles
+    let hub_invitations = invitations::table
+        .filter(invitations::hub_id.eq(hub_id))
+        .select(invitations::id)
+        .load::<i32>(conn)?;
+    diesel::delete(
+        invitation_roles::table.filter(invitation_roles::invitation_id.eq_any(&hub_invitations)),
+    )
+    .execute(conn)?;
+    diesel::delete(invitations::table.filter(invitations::hub_id.eq(hub_id))).execute(conn)?;
+
+    // delete personal access tokens issued in the hub
+    diesel::delete(personal_access_tokens::table.filter(personal_access_tokens::hub_id.eq(hub_id)))
+        .execute(conn)?;
+
+    // delete service accounts with their roles and tokens
+    let hub_accounts = service_accounts::table
+        .filter(service_accounts::hub_id.eq(hub_id))
+        .select(service_accounts::id)
+        .load::<i32>(conn)?;
+    diesel::delete(
+        service_account_tokens::table
+            .filter(service_account_tokens::service_account_id.eq_any(&hub_accounts)),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        service_account_roles::table
+            .filter(service_account_roles::service_account_id.eq_any(&hub_accounts)),
+    )
+    .execute(conn)?;
+    diesel::delete(service_accounts::table.filter(service_accounts::hub_id.eq(hub_id)))
+        .execute(conn)?;
+
+    let hub_users = users::table
+        .filter(users::hub_id.eq(hub_id))
+        .select(users::id)
+        .load::<i32>(conn)?;
+
+    // delete user_roles for hub users
+    diesel::delete(user_roles::table.filter(user_roles::user_id.eq_any(&hub_users)))
+        .execute(conn)?;
+
+    //delete users for hub
+    diesel::delete(users::table.filter(users::hub_id.eq(hub_id))).execute(conn)?;
+
+    // delete roles owned by the hub with their permissions and parents
+    let hub_roles = roles::table
+        .filter(roles::hub_id.eq(hub_id))
+        .select(roles::id)
+        .load::<i32>(conn)?;
+    diesel::delete(
+        role_parents::table.filter(
+            role_parents::role_id
+                .eq_any(&hub_roles)
+                .or(role_parents::parent_id.eq_any(&hub_roles)),
+        ),
+    )
+    .execute(conn)?;
+    diesel::delete(role_permissions::table.filter(role_permissions::role_id.eq_any(&hub_roles)))
+        .execute(conn)?;
+    diesel::delete(roles::table.filter(roles::hub_id.eq(hub_id))).execute(conn)?;
+
+    //delete hub
+    diesel::delete(hubs::table.filter(hubs::id.eq(hub_id))).execute(conn)
+}
+
 impl HubReader for DieselRepository {
     fn get_hub_by_id(&self, id: HubId) -> RepositoryResult<Option<Hub>> {
         use crate::schema::hubs;
@@ -16,6 +114,7 @@ impl HubReader for DieselRepository {
 
         let result = hubs::table
             .filter(hubs::id.eq(id.get()))
+            .filter(hubs::deleted_at.is_null())
             .first::<DbHub>(&mut connection)
             .optional()?;
 
@@ -30,6 +129,7 @@ impl HubReader for DieselRepository {
 
         let result = hubs::table
             .filter(hubs::name.eq(name))
+            .filter(hubs::deleted_at.is_null())
             .first::<DbHub>(&mut connection)
             .optional()?;
 
@@ -42,7 +142,26 @@ impl HubReader for DieselRepository {
 
         let mut connection = self.conn()?;
 
-        let results = hubs::table.load::<DbHub>(&mut connection)?;
+        let results = hubs::table
+            .filter(hubs::deleted_at.is_null())
+            .load::<DbHub>(&mut connection)?;
+
+        let hubs = results
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(hubs)
+    }
+
+    fn list_deleted_hubs(&self) -> RepositoryResult<Vec<Hub>> {
+        use crate::schema::hubs;
+
+        let mut connection = self.conn()?;
+
+        let results = hubs::table
+            .filter(hubs::deleted_at.is_not_null())
+            .order((hubs::deleted_at.desc(), hubs::id.desc()))
+            .load::<DbHub>(&mut connection)?;
 
         let hubs = results
             .into_iter()
@@ -50,6 +169,45 @@ impl HubReader for DieselRepository {
             .collect::<Result<Vec<_>, _>>()?;
         Ok(hubs)
     }
+
+    fn get_hub_policy(&self, hub_id: HubId) -> RepositoryResult<HubPolicy> {
+        use crate::schema::hub_policies;
+
+        let mut connection = self.conn()?;
+
+        let result = hub_policies::table
+            .find(hub_id.get())
+            .first::<DbHubPolicy>(&mut connection)
+            .optional()?;
+
+        Ok(result.map(Into::into).unwrap_or_default())
+    }
+
+    fn get_hub_registration_settings(
+        &self,
+        hub_id: HubId,
+    ) -> RepositoryResult<HubRegistrationSettings> {
+        use crate::schema::{hub_default_roles, hub_registration_settings};
+
+        let mut connection = self.conn()?;
+
+        let result = hub_registration_settings::table
+            .find(hub_id.get())
+            .first::<DbHubRegistrationSettings>(&mut connection)
+            .optional()?;
+
+        let Some(db_settings) = result else {
+            return Ok(HubRegistrationSettings::default());
+        };
+
+        let default_roles = hub_default_roles::table
+            .filter(hub_default_roles::hub_id.eq(hub_id.get()))
+            .order(hub_default_roles::role_id.asc())
+            .select(hub_default_roles::role_id)
+            .load::<i32>(&mut connection)?;
+
+        Ok(db_settings.into_domain(default_roles)?)
+    }
 }
 
 impl HubWriter for DieselRepository {
@@ -66,32 +224,42 @@ impl HubWriter for DieselRepository {
         Ok(hub)
     }
 
-    fn delete_hub(&self, hub_id: HubId) -> RepositoryResult<usize> {
-        use crate::schema::hubs;
-        use crate::schema::menu;
-        use crate::schema::user_roles;
-        use crate::schema::users;
+    fn delete_hub(&self, hub_id: HubId, now: NaiveDateTime) -> RepositoryResult<usize> {
+        use crate::schema::{hubs, service_account_tokens, sessions, users};
 
         let mut connection = self.conn()?;
 
         let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
-            // delete menus for hub
-            diesel::delete(menu::table.filter(menu::hub_id.eq(hub_id.get()))).execute(conn)?;
+            let result = diesel::update(hubs::table)
+                .filter(hubs::id.eq(hub_id.get()))
+                .filter(hubs::deleted_at.is_null())
+                .set(hubs::deleted_at.eq(now))
+                .execute(conn)?;
+            if result == 0 {
+                return Ok(0);
+            }
 
-            let hub_users = users::table
+            // Users share the timestamp of the hub, so restoring the hub only
+            // brings back the users deleted together with it.
+            diesel::update(users::table)
                 .filter(users::hub_id.eq(hub_id.get()))
-                .select(users::id)
-                .load::<i32>(conn)?;
+                .filter(users::deleted_at.is_null())
+                .set(users::deleted_at.eq(now))
+                .execute(conn)?;
 
-            // delete user_roles for hub users
-            diesel::delete(user_roles::table.filter(user_roles::user_id.eq_any(&hub_users)))
+            diesel::update(sessions::table)
+                .filter(sessions::hub_id.eq(hub_id.get()))
+                .filter(sessions::revoked_at.is_null())
+                .set(sessions::revoked_at.eq(now))
                 .execute(conn)?;
 
-            //delete users for hub
-            diesel::delete(users::table.filter(users::hub_id.eq(hub_id.get()))).execute(conn)?;
+            diesel::update(service_account_tokens::table)
+                .filter(service_account_tokens::hub_id.eq(hub_id.get()))
+                .filter(service_account_tokens::revoked_at.is_null())
+                .set(service_account_tokens::revoked_at.eq(now))
+                .execute(conn)?;
 
-            //delete hub
-            diesel::delete(hubs::table.filter(hubs::id.eq(hub_id.get()))).execute(conn)
+            Ok(result)
         })?;
 
         if result == 0 {
@@ -100,4 +268,116 @@ impl HubWriter for DieselRepository {
 
         Ok(result)
     }
+
+    fn restore_hub(&self, hub_id: HubId) -> RepositoryResult<usize> {
+        use crate::schema::{hubs, users};
+
+        let mut connection = self.conn()?;
+
+        let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let deleted_at = hubs::table
+                .filter(hubs::id.eq(hub_id.get()))
+                .select(hubs::deleted_at)
+                .first::<Option<NaiveDateTime>>(conn)
+                .optional()?
+                .flatten();
+            let Some(deleted_at) = deleted_at else {
+                return Ok(0);
+            };
+
+            diesel::update(users::table)
+                .filter(users::hub_id.eq(hub_id.get()))
+                .filter(users::deleted_at.eq(deleted_at))
+                .set(users::deleted_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?;
+
+            diesel::update(hubs::table.filter(hubs::id.eq(hub_id.get())))
+                .set(hubs::deleted_at.eq(None::<NaiveDateTime>))
+                .execute(conn)
+        })?;
+
+        Ok(result)
+    }
+
+    fn purge_deleted_hubs(&self, before: NaiveDateTime) -> RepositoryResult<usize> {
+        use crate::schema::hubs;
+
+        let mut connection = self.conn()?;
+
+        let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let expired_hubs = hubs::table
+                .filter(hubs::deleted_at.lt(before))
+                .select(hubs::id)
+                .load::<i32>(conn)?;
+
+            let mut result = 0;
+            for hub_id in expired_hubs {
+                result += purge_hub(conn, hub_id)?;
+            }
+            Ok(result)
+        })?;
+
+        Ok(result)
+    }
+
+    fn update_hub_policy(&self, hub_id: HubId, policy: &HubPolicy) -> RepositoryResult<HubPolicy> {
+        use crate::schema::hub_policies;
+
+        let mut connection = self.conn()?;
+
+        let db_policy = DbHubPolicy {
+            hub_id: hub_id.get(),
+            require_admin_2fa: policy.require_admin_2fa,
+            require_email_verification: policy.require_email_verification,
+        };
+        let db_policy = diesel::insert_into(hub_policies::table)
+            .values(&db_policy)
+            .on_conflict(hub_policies::hub_id)
+            .do_update()
+            .set(&db_policy)
+            .get_result::<DbHubPolicy>(&mut connection)?;
+
+        Ok(db_policy.into())
+    }
+
+    fn update_hub_registration_settings(
+        &self,
+        hub_id: HubId,
+        settings: &HubRegistrationSettings,
+    ) -> RepositoryResult<HubRegistrationSettings> {
+        use crate::schema::{hub_default_roles, hub_registration_settings};
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            let db_settings = DbHubRegistrationSettings::new(hub_id.get(), settings);
+            let db_settings = diesel::insert_into(hub_registration_settings::table)
+                .values(&db_settings)
+                .on_conflict(hub_registration_settings::hub_id)
+                .do_update()
+                .set(&db_settings)
+                .get_result::<DbHubRegistrationSettings>(conn)?;
+
+            // Replace the default roles; a missing role rolls back the whole
+            // update.
+            diesel::delete(
+                hub_default_roles::table.filter(hub_default_roles::hub_id.eq(hub_id.get())),
+            )
+            .execute(conn)?;
+            let default_roles = settings
+                .default_roles
+                .iter()
+                .map(|role_id| NewDbHubDefaultRole {
+                    hub_id: hub_id.get(),
+                    role_id: role_id.get(),
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(hub_default_roles::table)
+                .values(&default_roles)
+                .execute(conn)?;
+
+            let role_ids = settings.default_roles.iter().map(|id| id.get()).collect();
+            Ok(db_settings.into_domain(role_ids)?)
+        })
+    }
 }
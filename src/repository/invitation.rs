@@ -0,0 +1,232 @@
+//! Diesel-backed repository operations for invitations.
+
+use bcrypt::{DEFAULT_COST, hash};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::db::DbConnection;
+use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
+
+use crate::domain::invitation::{Invitation, NewInvitation};
+use crate::domain::types::{HubId, InvitationId, UserPassword};
+use crate::domain::user::User;
+use crate::models::invitation::{
+    Invitation as DbInvitation, NewInvitation as NewDbInvitation,
+    NewInvitationRole as NewDbInvitationRole,
+};
+use crate::models::role::NewUserRole as DbNewUserRole;
+use crate::models::user::{NewUser as NewDbUser, User as DbUser};
+use crate::repository::{DieselRepository, InvitationReader, InvitationWriter};
+
+/// Loads the role ids of `invitations` and converts them into domain values.
+fn with_roles(
+    conn: &mut DbConnection,
+    invitations: Vec<DbInvitation>,
+) -> RepositoryResult<Vec<Invitation>> {
+    use crate::schema::invitation_roles;
+
+    let invitation_ids: Vec<i32> = invitations.iter().map(|invitation| invitation.id).collect();
+    let roles = invitation_roles::table
+        .filter(invitation_roles::invitation_id.eq_any(invitation_ids))
+        .select((invitation_roles::invitation_id, invitation_roles::role_id))
+        .load::<(i32, i32)>(conn)?;
+
+    invitations
+        .into_iter()
+        .map(|invitation| {
+            let invitation_roles = roles
+                .iter()
+                .filter(|(invitation_id, _)| *invitation_id == invitation.id)
+                .map(|(_, role_id)| *role_id)
+                .collect();
+            Ok(invitation.into_domain(invitation_roles)?)
+        })
+        .collect()
+}
+
+impl InvitationReader for DieselRepository {
+    fn list_invitations(&self, hub_id: HubId) -> RepositoryResult<Vec<Invitation>> {
+        use crate::schema::invitations;
+
+        let mut connection = self.conn()?;
+
+        let invitations = invitations::table
+            .filter(invitations::hub_id.eq(hub_id.get()))
+            .filter(invitations::accepted_at.is_null())
+            .order((invitations::created_at.desc(), invitations::id.desc()))
+            .load::<DbInvitation>(&mut connection)?;
+
+        with_roles(&mut connection, invitations)
+    }
+
+    fn get_invitation(
+        &self,
+        id: InvitationId,
+        hub_id: HubId,
+    ) -> RepositoryResult<Option<Invitation>> {
+        use crate::schema::invitations;
+
+        let mut connection = self.conn()?;
+
+        let invitation = invitations::table
+            .filter(invitations::id.eq(id.get()))
+            .filter(invitations::hub_id.eq(hub_id.get()))
+            .first::<DbInvitation>(&mut connection)
+            .optional()?;
+
+        Ok(with_roles(&mut connection, invitation.into_iter().collect())?.pop())
+    }
+}
+
+impl InvitationWriter for DieselRepository {
+    fn create_invitation(&self, new_invitation: &NewInvitation) -> RepositoryResult<Invitation> {
+        use crate::schema::{invitation_roles, invitations};
+
+        let mut connection = self.conn()?;
+
+        let invitation = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let invitation = diesel::insert_into(invitations::table)
+                .values(&NewDbInvitation::from(new_invitation))
+                .get_result::<DbInvitation>(conn)?;
+
+            let new_roles = new_invitation
+                .roles
+                .iter()
+                .map(|role_id| NewDbInvitationRole {
+                    invitation_id: invitation.id,
+                    role_id: role_id.get(),
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(invitation_roles::table)
+                .values(&new_roles)
+                .execute(conn)?;
+            Ok(invitation)
+        })?;
+
+        Ok(with_roles(&mut connection, vec![invitation])?
+            .pop()
+            .ok_or(RepositoryError::NotFound)?)
+    }
+
+    fn renew_invitation(
+        &self,
+        id: InvitationId,
+        hub_id: HubId,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> RepositoryResult<Invitation> {
+        use crate::schema::invitations;
+
+        let mut connection = self.conn()?;
+
+        let invitation = diesel::update(
+            invitations::table
+                .filter(invitations::id.eq(id.get()))
+                .filter(invitations::hub_id.eq(hub_id.get()))
+                .filter(invitations::accepted_at.is_null()),
+        )
+        .set((
+            invitations::token_hash.eq(token_hash),
+            invitations::expires_at.eq(expires_at),
+        ))
+        .get_result::<DbInvitation>(&mut connection)
+        .optional()?
+        .ok_or(RepositoryError::NotFound)?;
+
+        Ok(with_roles(&mut connection, vec![invitation])?
+            .pop()
+            .ok_or(RepositoryError::NotFound)?)
+    }
+
+    fn delete_invitation(&self, id: InvitationId, hub_id: HubId) -> RepositoryResult<usize> {
+        use crate::schema::{invitation_roles, invitations};
+
+        let mut connection = self.conn()?;
+
+        let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let invitation_id = invitations::table
+                .filter(invitations::id.eq(id.get()))
+                .filter(invitations::hub_id.eq(hub_id.get()))
+                .filter(invitations::accepted_at.is_null())
+                .select(invitations::id)
+                .first::<i32>(conn)
+                .optional()?;
+            let Some(invitation_id) = invitation_id else {
+                return Ok(0);
+            };
+
+            diesel::delete(
+                invitation_roles::table.filter(invitation_roles::invitation_id.eq(invitation_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(invitations::table.filter(invitations::id.eq(invitation_id)))
+                .execute(conn)
+        })?;
+
+        Ok(result)
+    }
+
+    fn accept_invitation(
+        &self,
+        token_hash: &str,
+        password: &UserPassword,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<User>> {
+        use crate::schema::{invitation_roles, invitations, user_roles, users};
+
+        let password_hash = hash(password.as_str(), DEFAULT_COST).map_err(|e| {
+            RepositoryError::ValidationError(format!("Failed to saved User to DB: {e}"))
+        })?;
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            // Marking the row as accepted in the same statement that checks it
+            // guarantees an invitation can only be redeemed once.
+            let invitation = diesel::update(
+                invitations::table
+                    .filter(invitations::token_hash.eq(token_hash))
+                    .filter(invitations::accepted_at.is_null())
+                    .filter(invitations::expires_at.gt(now)),
+            )
+            .set(invitations::accepted_at.eq(now))
+            .get_result::<DbInvitation>(conn)
+            .optional()?;
+
+            let Some(invitation) = invitation else {
+                return Ok(None);
+            };
+
+            let user = diesel::insert_into(users::table)
+                .values(&NewDbUser {
+                    email: invitation.email,
+                    name: Some(invitation.name),
+                    hub_id: invitation.hub_id,
+                    password_hash,
+                    must_change_password: false,
+                })
+                .get_result::<DbUser>(conn)?;
+
+            // The invitation was delivered to this address, so it counts as
+            // verified.
+            let user = diesel::update(users::table.filter(users::id.eq(user.id)))
+                .set(users::email_verified_at.eq(now))
+                .get_result::<DbUser>(conn)?;
+
+            let new_user_roles = invitation_roles::table
+                .filter(invitation_roles::invitation_id.eq(invitation.id))
+                .select(invitation_roles::role_id)
+                .load::<i32>(conn)?
+                .into_iter()
+                .map(|role_id| DbNewUserRole {
+                    user_id: user.id,
+                    role_id,
+                })
+                .collect::<Vec<DbNewUserRole>>();
+            diesel::insert_into(user_roles::table)
+                .values(&new_user_roles)
+                .execute(conn)?;
+
+            Ok(Some(user.try_into()?))
+        })
+    }
+}
@@ -0,0 +1,68 @@
+//! Diesel-backed repository operations for failed-login counters.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
+
+use crate::domain::login_throttle::{LoginThrottle, LoginThrottlePolicy};
+use crate::models::login_throttle::LoginThrottle as DbLoginThrottle;
+use crate::repository::{DieselRepository, LoginThrottleReader, LoginThrottleWriter};
+
+impl LoginThrottleReader for DieselRepository {
+    fn get_login_throttle(&self, key: &str) -> RepositoryResult<Option<LoginThrottle>> {
+        use crate::schema::login_throttles;
+
+        let mut connection = self.conn()?;
+
+        let throttle = login_throttles::table
+            .find(key)
+            .first::<DbLoginThrottle>(&mut connection)
+            .optional()?;
+
+        Ok(throttle.map(Into::into))
+    }
+}
+
+impl LoginThrottleWriter for DieselRepository {
+    fn record_login_failure(
+        &self,
+        key: &str,
+        now: NaiveDateTime,
+        policy: &LoginThrottlePolicy,
+    ) -> RepositoryResult<LoginThrottle> {
+        use crate::schema::login_throttles;
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            let mut throttle = login_throttles::table
+                .find(key)
+                .first::<DbLoginThrottle>(conn)
+                .optional()?
+                .map(LoginThrottle::from)
+                .unwrap_or_else(|| LoginThrottle::new(key, now));
+
+            throttle.register_failure(now, policy);
+
+            let db_throttle = DbLoginThrottle::from(&throttle);
+            diesel::insert_into(login_throttles::table)
+                .values(&db_throttle)
+                .on_conflict(login_throttles::throttle_key)
+                .do_update()
+                .set(&db_throttle)
+                .execute(conn)?;
+
+            Ok(throttle)
+        })
+    }
+
+    fn clear_login_throttle(&self, key: &str) -> RepositoryResult<usize> {
+        use crate::schema::login_throttles;
+
+        let mut connection = self.conn()?;
+
+        let result = diesel::delete(login_throttles::table.find(key)).execute(&mut connection)?;
+
+        Ok(result)
+    }
+}
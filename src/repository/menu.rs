@@ -54,12 +54,16 @@ impl MenuReader for DieselRepository {
     }
 
     fn list_menu(&self, hub_id: HubId) -> RepositoryResult<Vec<Menu>> {
-        use crate::schema::menu;
+        use crate::schema::{hubs, menu};
 
         let mut connection = self.conn()?;
 
+        // Menus of a deleted hub are kept for restoring but no longer shown.
         let results = menu::table
+            .inner_join(hubs::table)
             .filter(menu::hub_id.eq(hub_id.get()))
+            .filter(hubs::deleted_at.is_null())
+            .select(menu::all_columns)
             .load::<DbMenu>(&mut connection)?;
 
         let menus = results
@@ -1,16 +1,43 @@
 //! Mock repository implementations for isolating services in tests.
 
+use chrono::NaiveDateTime;
 use mockall::mock;
 use pushkind_common::repository::errors::RepositoryResult;
 
-use crate::domain::hub::{Hub, NewHub};
+use crate::domain::audit::{AuditEvent, NewAuditEvent};
+use crate::domain::email_verification::{EmailVerification, NewEmailVerification};
+use crate::domain::hub::{Hub, HubPolicy, HubRegistrationSettings, NewHub};
+use crate::domain::invitation::{Invitation, NewInvitation};
+use crate::domain::login_throttle::{LoginThrottle, LoginThrottlePolicy};
 use crate::domain::menu::{Menu, NewMenu};
-use crate::domain::role::{NewRole, Role};
-use crate::domain::types::{HubId, MenuId, RoleId, UserEmail, UserId, UserPassword};
+use crate::domain::oauth::{
+    AccessToken, AuthorizationCode, NewAccessToken, NewAuthorizationCode, NewOAuthClient,
+    OAuthClient,
+};
+use crate::domain::password_reset::{NewPasswordReset, PasswordReset};
+use crate::domain::permission::{Permission, RolePermission};
+use crate::domain::personal_token::{NewPersonalAccessToken, PersonalAccessToken};
+use crate::domain::role::{NewRole, Role, RoleChange, RoleChangeSummary, RoleParent, UpdateRole};
+use crate::domain::service_account::{
+    NewServiceAccount, NewServiceAccountToken, ServiceAccount, ServiceAccountToken,
+    UpdateServiceAccount,
+};
+use crate::domain::session::{NewSession, Session};
+use crate::domain::two_factor::UserTotp;
+use crate::domain::types::{
+    HubId, InvitationId, MenuId, OAuthClientId, PersonalAccessTokenId, RoleId, ServiceAccountId,
+    SessionId, UserCredentialId, UserEmail, UserId, UserPassword,
+};
 use crate::domain::user::{NewUser, UpdateUser, User, UserWithRoles};
+use crate::domain::user_credential::{NewUserCredential, UserCredential};
 use crate::repository::{
-    HubReader, HubWriter, MenuReader, MenuWriter, RoleReader, RoleWriter, UserListQuery,
-    UserReader, UserWriter,
+    AuditEventQuery, AuditReader, AuditWriter, CredentialReader, CredentialWriter,
+    EmailVerificationWriter, HubReader, HubWriter, InvitationReader, InvitationWriter,
+    LoginThrottleReader, LoginThrottleWriter, MenuReader, MenuWriter, OAuthClientReader,
+    OAuthClientWriter, OAuthTokenReader, OAuthTokenWriter, PasswordResetWriter, PermissionReader,
+    PersonalTokenReader, PersonalTokenWriter, RoleReader, RoleWriter, ServiceAccountReader,
+    ServiceAccountWriter, SessionReader, SessionWriter, TwoFactorReader, TwoFactorWriter,
+    UserListQuery, UserReader, UserSelection, UserWriter,
 };
 
 mock! {
@@ -23,23 +50,35 @@ mock! {
         fn login(&self, email: &UserEmail, password: &UserPassword, hub_id: HubId) -> RepositoryResult<Option<UserWithRoles>>;
         fn get_roles(&self, user_id: UserId) -> RepositoryResult<Vec<Role>>;
         fn verify_password(&self, password: &str, stored_hash: &str) -> bool;
+        fn list_deleted_users(&self, hub_id: HubId) -> RepositoryResult<Vec<User>>;
     }
 
     impl UserWriter for Repository {
         fn create_user(&self, new_user: &NewUser) -> RepositoryResult<User>;
         fn update_user(&self, user_id: UserId, hub_id: HubId, updates: &UpdateUser) -> RepositoryResult<User>;
-        fn delete_user(&self, user_id: UserId) -> RepositoryResult<usize>;
+        fn change_role_for_users(&self, role_id: RoleId, hub_id: HubId, change: RoleChange, selection: &UserSelection, audit: &NewAuditEvent) -> RepositoryResult<RoleChangeSummary>;
+        fn update_password(&self, user_id: UserId, hub_id: HubId, password: &UserPassword) -> RepositoryResult<User>;
+        fn mark_email_verified(&self, user_id: UserId, hub_id: HubId, now: NaiveDateTime) -> RepositoryResult<usize>;
+        fn suspend_user<'a>(&self, user_id: UserId, hub_id: HubId, reason: Option<&'a str>, now: NaiveDateTime) -> RepositoryResult<usize>;
+        fn reactivate_user(&self, user_id: UserId, hub_id: HubId) -> RepositoryResult<usize>;
+        fn delete_user(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<usize>;
+        fn restore_user(&self, user_id: UserId, hub_id: HubId) -> RepositoryResult<usize>;
+        fn purge_deleted_users(&self, before: NaiveDateTime) -> RepositoryResult<usize>;
     }
 
     impl RoleReader for Repository {
         fn get_role_by_id(&self, id: RoleId) -> RepositoryResult<Option<Role>>;
-        fn get_role_by_name(&self, name: &str) -> RepositoryResult<Option<Role>>;
-        fn list_roles(&self) -> RepositoryResult<Vec<Role>>;
+        fn get_role_by_name(&self, name: &str, hub_id: HubId) -> RepositoryResult<Option<Role>>;
+        fn list_roles(&self, hub_id: HubId) -> RepositoryResult<Vec<Role>>;
+        fn count_role_members(&self, hub_id: HubId) -> RepositoryResult<Vec<(RoleId, usize)>>;
+        fn list_role_parents(&self) -> RepositoryResult<Vec<RoleParent>>;
     }
 
     impl RoleWriter for Repository {
         fn create_role(&self, new_role: &NewRole) -> RepositoryResult<Role>;
-        fn delete_role(&self, role_id: RoleId) -> RepositoryResult<usize>;
+        fn delete_role(&self, role_id: RoleId, hub_id: HubId) -> RepositoryResult<usize>;
+        fn update_role(&self, role_id: RoleId, hub_id: HubId, updates: &UpdateRole) -> RepositoryResult<Role>;
+        fn set_role_parents(&self, role_id: RoleId, hub_id: HubId, parent_ids: &[RoleId]) -> RepositoryResult<()>;
     }
 
     impl MenuReader for Repository {
@@ -56,10 +95,146 @@ mock! {
         fn get_hub_by_id(&self, id: HubId) -> RepositoryResult<Option<Hub>>;
         fn get_hub_by_name(&self, name: &str) -> RepositoryResult<Option<Hub>>;
         fn list_hubs(&self) -> RepositoryResult<Vec<Hub>>;
+        fn list_deleted_hubs(&self) -> RepositoryResult<Vec<Hub>>;
+        fn get_hub_policy(&self, hub_id: HubId) -> RepositoryResult<HubPolicy>;
+        fn get_hub_registration_settings(&self, hub_id: HubId) -> RepositoryResult<HubRegistrationSettings>;
     }
 
     impl HubWriter for Repository {
         fn create_hub(&self, new_hub: &NewHub) -> RepositoryResult<Hub>;
-        fn delete_hub(&self, hub_id: HubId) -> RepositoryResult<usize>;
+        fn delete_hub(&self, hub_id: HubId, now: NaiveDateTime) -> RepositoryResult<usize>;
+        fn restore_hub(&self, hub_id: HubId) -> RepositoryResult<usize>;
+        fn purge_deleted_hubs(&self, before: NaiveDateTime) -> RepositoryResult<usize>;
+        fn update_hub_policy(&self, hub_id: HubId, policy: &HubPolicy) -> RepositoryResult<HubPolicy>;
+        fn update_hub_registration_settings(&self, hub_id: HubId, settings: &HubRegistrationSettings) -> RepositoryResult<HubRegistrationSettings>;
+    }
+
+    impl PasswordResetWriter for Repository {
+        fn create_password_reset(&self, new_reset: &NewPasswordReset) -> RepositoryResult<PasswordReset>;
+        fn reset_password(&self, token_hash: &str, password: &UserPassword, now: NaiveDateTime) -> RepositoryResult<Option<PasswordReset>>;
+        fn invalidate_password_resets(&self, user_id: UserId) -> RepositoryResult<usize>;
+    }
+
+    impl EmailVerificationWriter for Repository {
+        fn create_email_verification(&self, new_verification: &NewEmailVerification) -> RepositoryResult<EmailVerification>;
+        fn consume_email_verification(&self, token_hash: &str, now: NaiveDateTime) -> RepositoryResult<Option<EmailVerification>>;
+    }
+
+    impl LoginThrottleReader for Repository {
+        fn get_login_throttle(&self, key: &str) -> RepositoryResult<Option<LoginThrottle>>;
+    }
+
+    impl LoginThrottleWriter for Repository {
+        fn record_login_failure(&self, key: &str, now: NaiveDateTime, policy: &LoginThrottlePolicy) -> RepositoryResult<LoginThrottle>;
+        fn clear_login_throttle(&self, key: &str) -> RepositoryResult<usize>;
+    }
+
+    impl TwoFactorReader for Repository {
+        fn get_user_totp(&self, user_id: UserId) -> RepositoryResult<Option<UserTotp>>;
+    }
+
+    impl TwoFactorWriter for Repository {
+        fn create_pending_totp(&self, user_id: UserId, secret: &str) -> RepositoryResult<UserTotp>;
+        fn enable_totp(&self, user_id: UserId, step: i64, recovery_code_hashes: &[String], now: NaiveDateTime) -> RepositoryResult<UserTotp>;
+        fn record_totp_step(&self, user_id: UserId, step: i64) -> RepositoryResult<bool>;
+        fn consume_recovery_code(&self, user_id: UserId, code_hash: &str, now: NaiveDateTime) -> RepositoryResult<bool>;
+        fn delete_user_totp(&self, user_id: UserId) -> RepositoryResult<usize>;
+    }
+
+    impl CredentialReader for Repository {
+        fn list_user_credentials(&self, user_id: UserId) -> RepositoryResult<Vec<UserCredential>>;
+        fn get_credential_by_credential_id(&self, credential_id: &str) -> RepositoryResult<Option<UserCredential>>;
+    }
+
+    impl CredentialWriter for Repository {
+        fn create_user_credential(&self, new_credential: &NewUserCredential) -> RepositoryResult<UserCredential>;
+        fn update_credential_passkey(&self, id: UserCredentialId, passkey: &str, now: NaiveDateTime) -> RepositoryResult<UserCredential>;
+        fn delete_user_credential(&self, user_id: UserId, id: UserCredentialId) -> RepositoryResult<usize>;
+    }
+
+    impl SessionReader for Repository {
+        fn get_session_by_jti(&self, jti: &str) -> RepositoryResult<Option<Session>>;
+        fn list_live_sessions(&self, hub_id: HubId, now: NaiveDateTime) -> RepositoryResult<Vec<Session>>;
+        fn list_user_sessions(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<Vec<Session>>;
+    }
+
+    impl SessionWriter for Repository {
+        fn create_session(&self, new_session: &NewSession) -> RepositoryResult<Session>;
+        fn touch_session(&self, id: SessionId, now: NaiveDateTime) -> RepositoryResult<()>;
+        fn revoke_session(&self, user_id: UserId, id: SessionId, now: NaiveDateTime) -> RepositoryResult<usize>;
+        fn revoke_session_by_jti(&self, jti: &str, now: NaiveDateTime) -> RepositoryResult<usize>;
+        fn revoke_user_sessions(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<usize>;
+    }
+
+    impl OAuthClientReader for Repository {
+        fn list_oauth_clients(&self) -> RepositoryResult<Vec<OAuthClient>>;
+        fn get_oauth_client(&self, client_id: &str) -> RepositoryResult<Option<OAuthClient>>;
+    }
+
+    impl OAuthClientWriter for Repository {
+        fn create_oauth_client(&self, new_client: &NewOAuthClient) -> RepositoryResult<OAuthClient>;
+        fn delete_oauth_client(&self, id: OAuthClientId) -> RepositoryResult<usize>;
+    }
+
+    impl OAuthTokenReader for Repository {
+        fn get_access_token(&self, token_hash: &str) -> RepositoryResult<Option<AccessToken>>;
+    }
+
+    impl OAuthTokenWriter for Repository {
+        fn create_authorization_code(&self, new_code: &NewAuthorizationCode) -> RepositoryResult<AuthorizationCode>;
+        fn consume_authorization_code(&self, code_hash: &str, now: NaiveDateTime) -> RepositoryResult<Option<AuthorizationCode>>;
+        fn create_access_token(&self, new_token: &NewAccessToken) -> RepositoryResult<AccessToken>;
+        fn revoke_access_token(&self, token_hash: &str, now: NaiveDateTime) -> RepositoryResult<usize>;
+    }
+
+    impl PersonalTokenReader for Repository {
+        fn list_personal_tokens(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<Vec<PersonalAccessToken>>;
+        fn get_personal_token_by_hash(&self, token_hash: &str) -> RepositoryResult<Option<PersonalAccessToken>>;
+    }
+
+    impl PersonalTokenWriter for Repository {
+        fn create_personal_token(&self, new_token: &NewPersonalAccessToken) -> RepositoryResult<PersonalAccessToken>;
+        fn touch_personal_token(&self, id: PersonalAccessTokenId, now: NaiveDateTime) -> RepositoryResult<()>;
+        fn revoke_personal_token(&self, user_id: UserId, id: PersonalAccessTokenId, now: NaiveDateTime) -> RepositoryResult<usize>;
+    }
+
+    impl ServiceAccountReader for Repository {
+        fn list_service_accounts(&self, hub_id: HubId) -> RepositoryResult<Vec<ServiceAccount>>;
+        fn get_service_account(&self, id: ServiceAccountId, hub_id: HubId) -> RepositoryResult<Option<ServiceAccount>>;
+        fn get_service_account_by_client_id(&self, client_id: &str) -> RepositoryResult<Option<ServiceAccount>>;
+        fn get_service_account_token(&self, token_hash: &str) -> RepositoryResult<Option<ServiceAccountToken>>;
+    }
+
+    impl ServiceAccountWriter for Repository {
+        fn create_service_account(&self, new_account: &NewServiceAccount) -> RepositoryResult<ServiceAccount>;
+        fn update_service_account(&self, id: ServiceAccountId, hub_id: HubId, updates: &UpdateServiceAccount) -> RepositoryResult<ServiceAccount>;
+        fn rotate_service_account_secret(&self, id: ServiceAccountId, hub_id: HubId, secret_hash: &str, now: NaiveDateTime) -> RepositoryResult<ServiceAccount>;
+        fn delete_service_account(&self, id: ServiceAccountId, hub_id: HubId) -> RepositoryResult<usize>;
+        fn create_service_account_token(&self, new_token: &NewServiceAccountToken) -> RepositoryResult<ServiceAccountToken>;
+    }
+
+    impl InvitationReader for Repository {
+        fn list_invitations(&self, hub_id: HubId) -> RepositoryResult<Vec<Invitation>>;
+        fn get_invitation(&self, id: InvitationId, hub_id: HubId) -> RepositoryResult<Option<Invitation>>;
+    }
+
+    impl InvitationWriter for Repository {
+        fn create_invitation(&self, new_invitation: &NewInvitation) -> RepositoryResult<Invitation>;
+        fn renew_invitation(&self, id: InvitationId, hub_id: HubId, token_hash: &str, expires_at: NaiveDateTime) -> RepositoryResult<Invitation>;
+        fn delete_invitation(&self, id: InvitationId, hub_id: HubId) -> RepositoryResult<usize>;
+        fn accept_invitation(&self, token_hash: &str, password: &UserPassword, now: NaiveDateTime) -> RepositoryResult<Option<User>>;
+    }
+
+    impl AuditReader for Repository {
+        fn list_audit_events(&self, query: AuditEventQuery) -> RepositoryResult<(usize, Vec<AuditEvent>)>;
+    }
+
+    impl AuditWriter for Repository {
+        fn record_audit_event(&self, new_event: &NewAuditEvent) -> RepositoryResult<()>;
+    }
+
+    impl PermissionReader for Repository {
+        fn list_user_permissions(&self, user_id: UserId, hub_id: HubId) -> RepositoryResult<Vec<Permission>>;
+        fn list_role_permissions(&self) -> RepositoryResult<Vec<RolePermission>>;
     }
 }
@@ -4,23 +4,58 @@
 //! on the underlying storage. Diesel based implementations live in the
 //! submodules.
 
+use chrono::NaiveDateTime;
 use pushkind_common::db::{DbConnection, DbPool};
 use pushkind_common::pagination::Pagination;
 use pushkind_common::repository::errors::RepositoryResult;
+use serde::Deserialize;
 
-use crate::domain::hub::{Hub, NewHub};
+use crate::domain::audit::{AuditEvent, AuditEventType, NewAuditEvent};
+use crate::domain::email_verification::{EmailVerification, NewEmailVerification};
+use crate::domain::hub::{Hub, HubPolicy, HubRegistrationSettings, NewHub};
+use crate::domain::invitation::{Invitation, NewInvitation};
+use crate::domain::login_throttle::{LoginThrottle, LoginThrottlePolicy};
 use crate::domain::menu::{Menu, NewMenu};
-use crate::domain::role::{NewRole, Role};
-use crate::domain::types::{HubId, MenuId, RoleId, UserEmail, UserId, UserPassword};
+use crate::domain::oauth::{
+    AccessToken, AuthorizationCode, NewAccessToken, NewAuthorizationCode, NewOAuthClient,
+    OAuthClient,
+};
+use crate::domain::password_reset::{NewPasswordReset, PasswordReset};
+use crate::domain::permission::{Permission, RolePermission};
+use crate::domain::personal_token::{NewPersonalAccessToken, PersonalAccessToken};
+use crate::domain::role::{NewRole, Role, RoleChange, RoleChangeSummary, RoleParent, UpdateRole};
+use crate::domain::service_account::{
+    NewServiceAccount, NewServiceAccountToken, ServiceAccount, ServiceAccountToken,
+    UpdateServiceAccount,
+};
+use crate::domain::session::{NewSession, Session};
+use crate::domain::two_factor::UserTotp;
+use crate::domain::types::{
+    HubId, InvitationId, MenuId, OAuthClientId, PersonalAccessTokenId, RoleId, ServiceAccountId,
+    SessionId, UserCredentialId, UserEmail, UserId, UserPassword,
+};
 use crate::domain::user::UserWithRoles;
 use crate::domain::user::{NewUser, UpdateUser, User};
+use crate::domain::user_credential::{NewUserCredential, UserCredential};
 
+pub mod audit;
+pub mod email_verification;
 pub mod hub;
+pub mod invitation;
+pub mod login_throttle;
 pub mod menu;
 #[cfg(test)]
 pub mod mock;
+pub mod oauth;
+pub mod password_reset;
+pub mod permission;
+pub mod personal_token;
 pub mod role;
+pub mod service_account;
+pub mod session;
+pub mod two_factor;
 pub mod user;
+pub mod user_credential;
 
 #[derive(Clone)]
 pub struct DieselRepository {
@@ -37,11 +72,26 @@ impl DieselRepository {
     }
 }
 
+/// Account states a user listing can be restricted to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UserStatusFilter {
+    /// Users that are not suspended.
+    #[default]
+    Active,
+    /// Suspended users only.
+    Suspended,
+    /// Every user regardless of suspension.
+    All,
+}
+
 /// Parameters used when querying for a list of users.
 #[derive(Debug, Clone)]
 pub struct UserListQuery {
     /// Identifier of the hub to which the users belong.
     pub hub_id: HubId,
+    /// Suspension state of the returned users; active users by default.
+    pub status: UserStatusFilter,
     /// Optional role to filter the resulting users by.
     pub role: Option<String>,
     /// Text term used when performing search queries.
@@ -54,12 +104,18 @@ impl UserListQuery {
     pub fn new(hub_id: HubId) -> Self {
         Self {
             hub_id,
+            status: UserStatusFilter::default(),
             role: None,
             search: None,
             pagination: None,
         }
     }
 
+    pub fn status(mut self, status: UserStatusFilter) -> Self {
+        self.status = status;
+        self
+    }
+
     pub fn role(mut self, role: impl Into<String>) -> Self {
         self.role = Some(role.into());
         self
@@ -76,6 +132,78 @@ impl UserListQuery {
     }
 }
 
+/// Users a bulk role change applies to.
+#[derive(Debug, Clone)]
+pub enum UserSelection {
+    /// Live users of the hub with these ids; unknown ids are reported back.
+    Ids(Vec<UserId>),
+    /// Every user matching the query; pagination is ignored.
+    Matching(UserListQuery),
+}
+
+/// Parameters used when querying the audit log of a hub.
+#[derive(Debug, Clone)]
+pub struct AuditEventQuery {
+    /// Identifier of the hub the events happened in.
+    pub hub_id: HubId,
+    /// Optional kind of event to filter by.
+    pub event_type: Option<AuditEventType>,
+    /// Optional user who caused the events.
+    pub actor_id: Option<UserId>,
+    /// Optional id of the user, role, hub or menu the events are about.
+    pub target_id: Option<i32>,
+    /// Only events recorded at or after this time.
+    pub since: Option<NaiveDateTime>,
+    /// Only events recorded before this time.
+    pub until: Option<NaiveDateTime>,
+    /// Pagination information for limiting results.
+    pub pagination: Option<Pagination>,
+}
+
+impl AuditEventQuery {
+    pub fn new(hub_id: HubId) -> Self {
+        Self {
+            hub_id,
+            event_type: None,
+            actor_id: None,
+            target_id: None,
+            since: None,
+            until: None,
+            pagination: None,
+        }
+    }
+
+    pub fn event_type(mut self, event_type: AuditEventType) -> Self {
+        self.event_type = Some(event_type);
+        self
+    }
+
+    pub fn actor(mut self, actor_id: UserId) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    pub fn target(mut self, target_id: i32) -> Self {
+        self.target_id = Some(target_id);
+        self
+    }
+
+    pub fn since(mut self, since: NaiveDateTime) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn until(mut self, until: NaiveDateTime) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn paginate(mut self, page: usize, per_page: usize) -> Self {
+        self.pagination = Some(Pagination { page, per_page });
+        self
+    }
+}
+
 pub trait UserReader {
     fn get_user_by_id(&self, id: UserId, hub_id: HubId) -> RepositoryResult<Option<UserWithRoles>>;
     fn get_user_by_email(
@@ -88,7 +216,7 @@ pub trait UserReader {
     /// Attempts to authenticate a user by email, password and hub.
     ///
     /// Returns the full user with roles on success, or [`None`] when
-    /// authentication fails.
+    /// authentication fails or the account is suspended.
     fn login(
         &self,
         email: &UserEmail,
@@ -97,6 +225,7 @@ pub trait UserReader {
     ) -> RepositoryResult<Option<UserWithRoles>> {
         let user = self.get_user_by_email(email, hub_id)?;
         if let Some(ur) = user
+            && !ur.user.is_disabled()
             && self.verify_password(password.as_str(), &ur.user.password_hash)
         {
             return Ok(Some(ur));
@@ -104,6 +233,8 @@ pub trait UserReader {
         Ok(None)
     }
     fn get_roles(&self, user_id: UserId) -> RepositoryResult<Vec<Role>>;
+    /// Lists the deleted users of a live hub, most recently deleted first.
+    fn list_deleted_users(&self, hub_id: HubId) -> RepositoryResult<Vec<User>>;
 }
 
 pub trait UserWriter {
@@ -121,7 +252,66 @@ pub trait UserWriter {
         hub_id: HubId,
         updates: &UpdateUser,
     ) -> RepositoryResult<User>;
-    fn delete_user(&self, user_id: UserId) -> RepositoryResult<usize>;
+    /// Adds `role_id` to or removes it from the selected users of `hub_id`
+    /// in a single transaction, leaving the other roles of each user as they
+    /// are. The same transaction records `audit` once per changed user, with
+    /// the user as its target.
+    fn change_role_for_users(
+        &self,
+        role_id: RoleId,
+        hub_id: HubId,
+        change: RoleChange,
+        selection: &UserSelection,
+        audit: &NewAuditEvent,
+    ) -> RepositoryResult<RoleChangeSummary>;
+    /// Replaces the stored password hash of a user and clears the
+    /// requirement to change it.
+    fn update_password(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+        password: &UserPassword,
+    ) -> RepositoryResult<User>;
+    /// Records that the address of a user was verified at `now`.
+    ///
+    /// Returns the number of updated rows, which is zero when the user does
+    /// not exist in the hub or was already verified.
+    fn mark_email_verified(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize>;
+    /// Suspends a user at `now` with an optional reason.
+    ///
+    /// Returns the number of updated rows, which is zero when the user does
+    /// not exist in the hub or is already suspended.
+    fn suspend_user(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+        reason: Option<&str>,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize>;
+    /// Lifts the suspension of a user.
+    ///
+    /// Returns the number of updated rows, which is zero when the user does
+    /// not exist in the hub or is not suspended.
+    fn reactivate_user(&self, user_id: UserId, hub_id: HubId) -> RepositoryResult<usize>;
+    /// Marks a user as deleted at `now` and revokes their sessions.
+    ///
+    /// The row is kept, without its email counting towards uniqueness, until
+    /// [`UserWriter::purge_deleted_users`] removes it.
+    fn delete_user(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<usize>;
+    /// Brings back a deleted user of a hub.
+    ///
+    /// Returns the number of updated rows, which is zero when the user does
+    /// not exist in the hub or is not deleted. Fails with a unique violation
+    /// when the address was taken by another user in the meantime.
+    fn restore_user(&self, user_id: UserId, hub_id: HubId) -> RepositoryResult<usize>;
+    /// Permanently removes users deleted before `before` together with their
+    /// dependent rows.
+    fn purge_deleted_users(&self, before: NaiveDateTime) -> RepositoryResult<usize>;
 }
 
 /// Convenience trait combining [`UserReader`] and [`UserWriter`].
@@ -131,11 +321,42 @@ pub trait HubReader {
     fn get_hub_by_id(&self, id: HubId) -> RepositoryResult<Option<Hub>>;
     fn get_hub_by_name(&self, name: &str) -> RepositoryResult<Option<Hub>>;
     fn list_hubs(&self) -> RepositoryResult<Vec<Hub>>;
+    /// Lists the deleted hubs, most recently deleted first.
+    fn list_deleted_hubs(&self) -> RepositoryResult<Vec<Hub>>;
+    /// Returns the hub policy, falling back to defaults when none is stored.
+    fn get_hub_policy(&self, hub_id: HubId) -> RepositoryResult<HubPolicy>;
+    /// Returns the registration settings of a hub, falling back to open
+    /// registration when none are stored.
+    fn get_hub_registration_settings(
+        &self,
+        hub_id: HubId,
+    ) -> RepositoryResult<HubRegistrationSettings>;
 }
 
 pub trait HubWriter {
     fn create_hub(&self, new_hub: &NewHub) -> RepositoryResult<Hub>;
-    fn delete_hub(&self, hub_id: HubId) -> RepositoryResult<usize>;
+    /// Marks a hub and its live users as deleted at `now`, and revokes the
+    /// sessions and service account tokens issued in the hub.
+    ///
+    /// Rows are kept until [`HubWriter::purge_deleted_hubs`] removes them.
+    fn delete_hub(&self, hub_id: HubId, now: NaiveDateTime) -> RepositoryResult<usize>;
+    /// Brings back a deleted hub with the users deleted together with it.
+    ///
+    /// Returns the number of updated hubs, which is zero when the hub does
+    /// not exist or is not deleted. Fails with a unique violation when the
+    /// name was taken by another hub in the meantime.
+    fn restore_hub(&self, hub_id: HubId) -> RepositoryResult<usize>;
+    /// Permanently removes hubs deleted before `before` with their users,
+    /// menus, role assignments and other dependent rows.
+    fn purge_deleted_hubs(&self, before: NaiveDateTime) -> RepositoryResult<usize>;
+    fn update_hub_policy(&self, hub_id: HubId, policy: &HubPolicy) -> RepositoryResult<HubPolicy>;
+    /// Replaces the registration settings of a hub, including the full set
+    /// of default roles, in a single transaction.
+    fn update_hub_registration_settings(
+        &self,
+        hub_id: HubId,
+        settings: &HubRegistrationSettings,
+    ) -> RepositoryResult<HubRegistrationSettings>;
 }
 
 pub trait HubRepository: HubReader + HubWriter {}
@@ -143,13 +364,39 @@ impl<T: HubReader + HubWriter> HubRepository for T {}
 
 pub trait RoleReader {
     fn get_role_by_id(&self, id: RoleId) -> RepositoryResult<Option<Role>>;
-    fn get_role_by_name(&self, name: &str) -> RepositoryResult<Option<Role>>;
-    fn list_roles(&self) -> RepositoryResult<Vec<Role>>;
+    /// Finds a system role or a role of `hub_id` by name.
+    fn get_role_by_name(&self, name: &str, hub_id: HubId) -> RepositoryResult<Option<Role>>;
+    /// Lists the system roles followed by the roles of `hub_id`.
+    fn list_roles(&self, hub_id: HubId) -> RepositoryResult<Vec<Role>>;
+    /// Counts the users of `hub_id`, suspended ones included, holding each
+    /// role. Roles nobody holds are left out.
+    fn count_role_members(&self, hub_id: HubId) -> RepositoryResult<Vec<(RoleId, usize)>>;
+    /// Lists every link between a role and the parent roles it implies.
+    fn list_role_parents(&self) -> RepositoryResult<Vec<RoleParent>>;
 }
 
 pub trait RoleWriter {
     fn create_role(&self, new_role: &NewRole) -> RepositoryResult<Role>;
-    fn delete_role(&self, role_id: RoleId) -> RepositoryResult<usize>;
+    /// Deletes a system role or a role of `hub_id` with its assignments.
+    fn delete_role(&self, role_id: RoleId, hub_id: HubId) -> RepositoryResult<usize>;
+    /// Renames and describes a system role or a role of `hub_id`.
+    fn update_role(
+        &self,
+        role_id: RoleId,
+        hub_id: HubId,
+        updates: &UpdateRole,
+    ) -> RepositoryResult<Role>;
+    /// Replaces the parent roles of a system role or a role of `hub_id`.
+    ///
+    /// Fails with `RepositoryError::ValidationError` when the role would
+    /// inherit itself and with `RepositoryError::NotFound` when the role is
+    /// not visible in the hub.
+    fn set_role_parents(
+        &self,
+        role_id: RoleId,
+        hub_id: HubId,
+        parent_ids: &[RoleId],
+    ) -> RepositoryResult<()>;
 }
 
 /// Convenience trait combining [`RoleReader`] and [`RoleWriter`].
@@ -169,3 +416,354 @@ pub trait MenuWriter {
 
 /// Backwards compatibility alias combining [`MenuReader`] and [`MenuWriter`].
 pub trait MenuRepository: MenuReader + MenuWriter {}
+
+pub trait PasswordResetWriter {
+    /// Stores a new recovery token.
+    ///
+    /// Any outstanding tokens previously issued to the same user are marked
+    /// as used in the same transaction so only the latest link stays valid.
+    fn create_password_reset(
+        &self,
+        new_reset: &NewPasswordReset,
+    ) -> RepositoryResult<PasswordReset>;
+    /// Consumes the active token matching `token_hash` and stores `password`
+    /// for its user in the same transaction, so the token is only spent when
+    /// the new password is saved. Also clears `must_change_password`.
+    ///
+    /// Returns [`None`] when the token is unknown, already used or expired,
+    /// and `RepositoryError::NotFound` when its user no longer exists. On
+    /// success every other outstanding token of the same user is invalidated
+    /// as well.
+    fn reset_password(
+        &self,
+        token_hash: &str,
+        password: &UserPassword,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<PasswordReset>>;
+    /// Marks all outstanding recovery tokens of a user as used.
+    fn invalidate_password_resets(&self, user_id: UserId) -> RepositoryResult<usize>;
+}
+
+pub trait EmailVerificationWriter {
+    /// Stores a new verification token.
+    ///
+    /// Any outstanding tokens previously issued to the same user are marked
+    /// as used in the same transaction so only the latest link stays valid.
+    fn create_email_verification(
+        &self,
+        new_verification: &NewEmailVerification,
+    ) -> RepositoryResult<EmailVerification>;
+    /// Consumes the active token matching `token_hash` and marks the address
+    /// of its user as verified in the same transaction.
+    ///
+    /// Returns [`None`] when the token is unknown, already used or expired.
+    fn consume_email_verification(
+        &self,
+        token_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<EmailVerification>>;
+}
+
+pub trait LoginThrottleReader {
+    fn get_login_throttle(&self, key: &str) -> RepositoryResult<Option<LoginThrottle>>;
+}
+
+pub trait LoginThrottleWriter {
+    /// Records a failed login attempt for `key` according to `policy`.
+    ///
+    /// The counters are loaded, updated and stored in a single transaction
+    /// so concurrent failures are not lost.
+    fn record_login_failure(
+        &self,
+        key: &str,
+        now: NaiveDateTime,
+        policy: &LoginThrottlePolicy,
+    ) -> RepositoryResult<LoginThrottle>;
+    /// Removes the counters stored for `key`.
+    fn clear_login_throttle(&self, key: &str) -> RepositoryResult<usize>;
+}
+
+pub trait TwoFactorReader {
+    fn get_user_totp(&self, user_id: UserId) -> RepositoryResult<Option<UserTotp>>;
+}
+
+pub trait TwoFactorWriter {
+    /// Stores a new unconfirmed TOTP secret for a user, replacing any earlier
+    /// unconfirmed one.
+    fn create_pending_totp(&self, user_id: UserId, secret: &str) -> RepositoryResult<UserTotp>;
+    /// Confirms a pending TOTP secret and replaces the user's recovery codes
+    /// in a single transaction.
+    fn enable_totp(
+        &self,
+        user_id: UserId,
+        step: i64,
+        recovery_code_hashes: &[String],
+        now: NaiveDateTime,
+    ) -> RepositoryResult<UserTotp>;
+    /// Records `step` as the last accepted TOTP step.
+    ///
+    /// Returns `false` when the step is not newer than the stored one, which
+    /// means the code has already been used.
+    fn record_totp_step(&self, user_id: UserId, step: i64) -> RepositoryResult<bool>;
+    /// Marks the unused recovery code matching `code_hash` as used.
+    ///
+    /// Returns `false` when no such code exists.
+    fn consume_recovery_code(
+        &self,
+        user_id: UserId,
+        code_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<bool>;
+    /// Removes the TOTP secret and all recovery codes of a user.
+    fn delete_user_totp(&self, user_id: UserId) -> RepositoryResult<usize>;
+}
+
+pub trait CredentialReader {
+    fn list_user_credentials(&self, user_id: UserId) -> RepositoryResult<Vec<UserCredential>>;
+    fn get_credential_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> RepositoryResult<Option<UserCredential>>;
+}
+
+pub trait CredentialWriter {
+    fn create_user_credential(
+        &self,
+        new_credential: &NewUserCredential,
+    ) -> RepositoryResult<UserCredential>;
+    /// Stores the updated authenticator state after a successful assertion.
+    fn update_credential_passkey(
+        &self,
+        id: UserCredentialId,
+        passkey: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<UserCredential>;
+    /// Removes a credential owned by `user_id`.
+    fn delete_user_credential(
+        &self,
+        user_id: UserId,
+        id: UserCredentialId,
+    ) -> RepositoryResult<usize>;
+}
+
+pub trait SessionReader {
+    fn get_session_by_jti(&self, jti: &str) -> RepositoryResult<Option<Session>>;
+    /// Lists sessions of a hub that are neither revoked nor expired at `now`,
+    /// newest first.
+    fn list_live_sessions(
+        &self,
+        hub_id: HubId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Vec<Session>>;
+    /// Lists the live sessions of a single user, newest first.
+    fn list_user_sessions(
+        &self,
+        user_id: UserId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Vec<Session>>;
+}
+
+pub trait SessionWriter {
+    fn create_session(&self, new_session: &NewSession) -> RepositoryResult<Session>;
+    /// Records activity on a session.
+    fn touch_session(&self, id: SessionId, now: NaiveDateTime) -> RepositoryResult<()>;
+    /// Revokes a session owned by `user_id` and returns the number of
+    /// sessions revoked.
+    fn revoke_session(
+        &self,
+        user_id: UserId,
+        id: SessionId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize>;
+    /// Revokes the session identified by `jti`, whoever owns it, and returns
+    /// the number of sessions revoked.
+    fn revoke_session_by_jti(&self, jti: &str, now: NaiveDateTime) -> RepositoryResult<usize>;
+    /// Revokes every unrevoked session of a user.
+    fn revoke_user_sessions(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<usize>;
+}
+
+pub trait OAuthClientReader {
+    fn list_oauth_clients(&self) -> RepositoryResult<Vec<OAuthClient>>;
+    /// Looks up a client by its public `client_id`.
+    fn get_oauth_client(&self, client_id: &str) -> RepositoryResult<Option<OAuthClient>>;
+}
+
+pub trait OAuthClientWriter {
+    fn create_oauth_client(&self, new_client: &NewOAuthClient) -> RepositoryResult<OAuthClient>;
+    /// Removes a client together with its authorization codes and access
+    /// tokens.
+    fn delete_oauth_client(&self, id: OAuthClientId) -> RepositoryResult<usize>;
+}
+
+pub trait OAuthTokenReader {
+    fn get_access_token(&self, token_hash: &str) -> RepositoryResult<Option<AccessToken>>;
+}
+
+pub trait OAuthTokenWriter {
+    fn create_authorization_code(
+        &self,
+        new_code: &NewAuthorizationCode,
+    ) -> RepositoryResult<AuthorizationCode>;
+    /// Consumes the unused, unexpired authorization code matching
+    /// `code_hash`.
+    ///
+    /// Returns [`None`] when the code is unknown, already used or expired.
+    fn consume_authorization_code(
+        &self,
+        code_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<AuthorizationCode>>;
+    fn create_access_token(&self, new_token: &NewAccessToken) -> RepositoryResult<AccessToken>;
+    /// Revokes the access token matching `token_hash` and returns the number
+    /// of tokens revoked.
+    fn revoke_access_token(&self, token_hash: &str, now: NaiveDateTime) -> RepositoryResult<usize>;
+}
+
+pub trait PersonalTokenReader {
+    /// Lists a user's personal access tokens that are neither revoked nor
+    /// expired at `now`, newest first.
+    fn list_personal_tokens(
+        &self,
+        user_id: UserId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Vec<PersonalAccessToken>>;
+    fn get_personal_token_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> RepositoryResult<Option<PersonalAccessToken>>;
+}
+
+pub trait PersonalTokenWriter {
+    fn create_personal_token(
+        &self,
+        new_token: &NewPersonalAccessToken,
+    ) -> RepositoryResult<PersonalAccessToken>;
+    /// Records use of a token.
+    fn touch_personal_token(
+        &self,
+        id: PersonalAccessTokenId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<()>;
+    /// Revokes a token owned by `user_id` and returns the number of tokens
+    /// revoked.
+    fn revoke_personal_token(
+        &self,
+        user_id: UserId,
+        id: PersonalAccessTokenId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize>;
+}
+
+pub trait ServiceAccountReader {
+    /// Lists the service accounts of a hub with their roles, by name.
+    fn list_service_accounts(&self, hub_id: HubId) -> RepositoryResult<Vec<ServiceAccount>>;
+    fn get_service_account(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+    ) -> RepositoryResult<Option<ServiceAccount>>;
+    /// Looks up a service account by its public `client_id`.
+    fn get_service_account_by_client_id(
+        &self,
+        client_id: &str,
+    ) -> RepositoryResult<Option<ServiceAccount>>;
+    fn get_service_account_token(
+        &self,
+        token_hash: &str,
+    ) -> RepositoryResult<Option<ServiceAccountToken>>;
+}
+
+pub trait ServiceAccountWriter {
+    fn create_service_account(
+        &self,
+        new_account: &NewServiceAccount,
+    ) -> RepositoryResult<ServiceAccount>;
+    /// Renames a service account of the hub and replaces its full set of
+    /// roles in a single transaction.
+    fn update_service_account(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+        updates: &UpdateServiceAccount,
+    ) -> RepositoryResult<ServiceAccount>;
+    /// Replaces the secret of a service account of the hub and revokes the
+    /// tokens issued with the previous one.
+    fn rotate_service_account_secret(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+        secret_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<ServiceAccount>;
+    /// Removes a service account of the hub with its roles and tokens.
+    fn delete_service_account(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+    ) -> RepositoryResult<usize>;
+    fn create_service_account_token(
+        &self,
+        new_token: &NewServiceAccountToken,
+    ) -> RepositoryResult<ServiceAccountToken>;
+}
+
+pub trait InvitationReader {
+    /// Lists the pending invitations of a hub, newest first.
+    fn list_invitations(&self, hub_id: HubId) -> RepositoryResult<Vec<Invitation>>;
+    fn get_invitation(
+        &self,
+        id: InvitationId,
+        hub_id: HubId,
+    ) -> RepositoryResult<Option<Invitation>>;
+}
+
+pub trait InvitationWriter {
+    fn create_invitation(&self, new_invitation: &NewInvitation) -> RepositoryResult<Invitation>;
+    /// Replaces the token of a pending invitation of the hub and extends its
+    /// expiry.
+    fn renew_invitation(
+        &self,
+        id: InvitationId,
+        hub_id: HubId,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> RepositoryResult<Invitation>;
+    /// Removes a pending invitation of the hub with its roles.
+    fn delete_invitation(&self, id: InvitationId, hub_id: HubId) -> RepositoryResult<usize>;
+    /// Redeems an active invitation: marks it accepted and creates its user
+    /// with a verified email and the invited roles in a single transaction.
+    ///
+    /// Returns `None` when the token is unknown, expired, or already used.
+    fn accept_invitation(
+        &self,
+        token_hash: &str,
+        password: &UserPassword,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<User>>;
+}
+
+pub trait AuditReader {
+    /// Lists the events of a hub matching `query`, newest first, with the
+    /// total number of matching events.
+    fn list_audit_events(
+        &self,
+        query: AuditEventQuery,
+    ) -> RepositoryResult<(usize, Vec<AuditEvent>)>;
+}
+
+pub trait AuditWriter {
+    /// Appends an event to the audit log. Stored events cannot be changed.
+    fn record_audit_event(&self, new_event: &NewAuditEvent) -> RepositoryResult<()>;
+}
+
+pub trait PermissionReader {
+    /// Lists the permissions a live user of the hub holds through their
+    /// roles, each once, in the order of [`Permission::ALL`].
+    fn list_user_permissions(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+    ) -> RepositoryResult<Vec<Permission>>;
+    /// Lists the permissions granted to every role.
+    fn list_role_permissions(&self) -> RepositoryResult<Vec<RolePermission>>;
+}
@@ -0,0 +1,172 @@
+//! Diesel-backed repository operations for the OpenID Connect provider.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::repository::errors::RepositoryResult;
+
+use crate::domain::oauth::{
+    AccessToken, AuthorizationCode, NewAccessToken, NewAuthorizationCode, NewOAuthClient,
+    OAuthClient,
+};
+use crate::domain::types::OAuthClientId;
+use crate::models::oauth::{
+    AccessToken as DbAccessToken, AuthorizationCode as DbAuthorizationCode,
+    NewAccessToken as NewDbAccessToken, NewAuthorizationCode as NewDbAuthorizationCode,
+    NewOAuthClient as NewDbOAuthClient, OAuthClient as DbOAuthClient,
+};
+use crate::repository::{
+    DieselRepository, OAuthClientReader, OAuthClientWriter, OAuthTokenReader, OAuthTokenWriter,
+};
+
+impl OAuthClientReader for DieselRepository {
+    fn list_oauth_clients(&self) -> RepositoryResult<Vec<OAuthClient>> {
+        use crate::schema::oauth_clients;
+
+        let mut connection = self.conn()?;
+
+        let clients = oauth_clients::table
+            .order(oauth_clients::name.asc())
+            .load::<DbOAuthClient>(&mut connection)?;
+
+        let clients = clients
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(clients)
+    }
+
+    fn get_oauth_client(&self, client_id: &str) -> RepositoryResult<Option<OAuthClient>> {
+        use crate::schema::oauth_clients;
+
+        let mut connection = self.conn()?;
+
+        let client = oauth_clients::table
+            .filter(oauth_clients::client_id.eq(client_id))
+            .first::<DbOAuthClient>(&mut connection)
+            .optional()?;
+
+        Ok(client.map(TryInto::try_into).transpose()?)
+    }
+}
+
+impl OAuthClientWriter for DieselRepository {
+    fn create_oauth_client(&self, new_client: &NewOAuthClient) -> RepositoryResult<OAuthClient> {
+        use crate::schema::oauth_clients;
+
+        let mut connection = self.conn()?;
+
+        let client = diesel::insert_into(oauth_clients::table)
+            .values(&NewDbOAuthClient::from(new_client))
+            .get_result::<DbOAuthClient>(&mut connection)?;
+
+        Ok(client.try_into()?)
+    }
+
+    fn delete_oauth_client(&self, id: OAuthClientId) -> RepositoryResult<usize> {
+        use crate::schema::{oauth_access_tokens, oauth_authorization_codes, oauth_clients};
+
+        let mut connection = self.conn()?;
+
+        let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(
+                oauth_authorization_codes::table
+                    .filter(oauth_authorization_codes::client_id.eq(id.get())),
+            )
+            .execute(conn)?;
+
+            diesel::delete(
+                oauth_access_tokens::table.filter(oauth_access_tokens::client_id.eq(id.get())),
+            )
+            .execute(conn)?;
+
+            diesel::delete(oauth_clients::table.filter(oauth_clients::id.eq(id.get())))
+                .execute(conn)
+        })?;
+
+        Ok(result)
+    }
+}
+
+impl OAuthTokenReader for DieselRepository {
+    fn get_access_token(&self, token_hash: &str) -> RepositoryResult<Option<AccessToken>> {
+        use crate::schema::oauth_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        let token = oauth_access_tokens::table
+            .filter(oauth_access_tokens::token_hash.eq(token_hash))
+            .first::<DbAccessToken>(&mut connection)
+            .optional()?;
+
+        Ok(token.map(TryInto::try_into).transpose()?)
+    }
+}
+
+impl OAuthTokenWriter for DieselRepository {
+    fn create_authorization_code(
+        &self,
+        new_code: &NewAuthorizationCode,
+    ) -> RepositoryResult<AuthorizationCode> {
+        use crate::schema::oauth_authorization_codes;
+
+        let mut connection = self.conn()?;
+
+        let code = diesel::insert_into(oauth_authorization_codes::table)
+            .values(&NewDbAuthorizationCode::from(new_code))
+            .get_result::<DbAuthorizationCode>(&mut connection)?;
+
+        Ok(code.try_into()?)
+    }
+
+    fn consume_authorization_code(
+        &self,
+        code_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<AuthorizationCode>> {
+        use crate::schema::oauth_authorization_codes;
+
+        let mut connection = self.conn()?;
+
+        // Marking the row as used in the same statement that checks it
+        // guarantees a code can only be exchanged once.
+        let code = diesel::update(
+            oauth_authorization_codes::table
+                .filter(oauth_authorization_codes::code_hash.eq(code_hash))
+                .filter(oauth_authorization_codes::used_at.is_null())
+                .filter(oauth_authorization_codes::expires_at.gt(now)),
+        )
+        .set(oauth_authorization_codes::used_at.eq(now))
+        .get_result::<DbAuthorizationCode>(&mut connection)
+        .optional()?;
+
+        Ok(code.map(TryInto::try_into).transpose()?)
+    }
+
+    fn create_access_token(&self, new_token: &NewAccessToken) -> RepositoryResult<AccessToken> {
+        use crate::schema::oauth_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        let token = diesel::insert_into(oauth_access_tokens::table)
+            .values(&NewDbAccessToken::from(new_token))
+            .get_result::<DbAccessToken>(&mut connection)?;
+
+        Ok(token.try_into()?)
+    }
+
+    fn revoke_access_token(&self, token_hash: &str, now: NaiveDateTime) -> RepositoryResult<usize> {
+        use crate::schema::oauth_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        let revoked = diesel::update(
+            oauth_access_tokens::table
+                .filter(oauth_access_tokens::token_hash.eq(token_hash))
+                .filter(oauth_access_tokens::revoked_at.is_null()),
+        )
+        .set(oauth_access_tokens::revoked_at.eq(now))
+        .execute(&mut connection)?;
+
+        Ok(revoked)
+    }
+}
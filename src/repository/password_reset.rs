@@ -0,0 +1,117 @@
+//! Diesel-backed repository operations for password recovery tokens.
+
+use bcrypt::{DEFAULT_COST, hash};
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
+
+use crate::domain::password_reset::{NewPasswordReset, PasswordReset};
+use crate::domain::types::{UserId, UserPassword};
+use crate::models::password_reset::{
+    NewPasswordReset as NewDbPasswordReset, PasswordReset as DbPasswordReset,
+};
+use crate::repository::{DieselRepository, PasswordResetWriter};
+
+impl PasswordResetWriter for DieselRepository {
+    fn create_password_reset(
+        &self,
+        new_reset: &NewPasswordReset,
+    ) -> RepositoryResult<PasswordReset> {
+        use crate::schema::password_resets;
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            // Only the most recently issued link may be used.
+            diesel::update(
+                password_resets::table
+                    .filter(password_resets::user_id.eq(new_reset.user_id.get()))
+                    .filter(password_resets::used_at.is_null()),
+            )
+            .set(password_resets::used_at.eq(Utc::now().naive_utc()))
+            .execute(conn)?;
+
+            let new_db_reset = NewDbPasswordReset::from(new_reset);
+            let reset = diesel::insert_into(password_resets::table)
+                .values(&new_db_reset)
+                .get_result::<DbPasswordReset>(conn)?;
+
+            Ok(reset.try_into()?)
+        })
+    }
+
+    fn reset_password(
+        &self,
+        token_hash: &str,
+        password: &UserPassword,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Option<PasswordReset>> {
+        use crate::schema::{password_resets, users};
+
+        // Hashing is slow, so it happens before the write transaction starts.
+        let password_hash = hash(password.as_str(), DEFAULT_COST).map_err(|e| {
+            RepositoryError::ValidationError(format!("Failed to update user password: {e}"))
+        })?;
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            // Marking the row as used in the same statement that checks it
+            // guarantees a token can only be redeemed once.
+            let reset = diesel::update(
+                password_resets::table
+                    .filter(password_resets::token_hash.eq(token_hash))
+                    .filter(password_resets::used_at.is_null())
+                    .filter(password_resets::expires_at.gt(now)),
+            )
+            .set(password_resets::used_at.eq(now))
+            .get_result::<DbPasswordReset>(conn)
+            .optional()?;
+
+            let Some(reset) = reset else {
+                return Ok(None);
+            };
+
+            diesel::update(
+                password_resets::table
+                    .filter(password_resets::user_id.eq(reset.user_id))
+                    .filter(password_resets::used_at.is_null()),
+            )
+            .set(password_resets::used_at.eq(now))
+            .execute(conn)?;
+
+            let updated = diesel::update(users::table)
+                .filter(users::id.eq(reset.user_id))
+                .filter(users::hub_id.eq(reset.hub_id))
+                .filter(users::deleted_at.is_null())
+                .set((
+                    users::password_hash.eq(password_hash),
+                    users::must_change_password.eq(false),
+                    users::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            if updated == 0 {
+                // Rolls back the consumed token along with the failed update.
+                return Err(RepositoryError::NotFound);
+            }
+
+            Ok(Some(reset.try_into()?))
+        })
+    }
+
+    fn invalidate_password_resets(&self, user_id: UserId) -> RepositoryResult<usize> {
+        use crate::schema::password_resets;
+
+        let mut connection = self.conn()?;
+
+        let result = diesel::update(
+            password_resets::table
+                .filter(password_resets::user_id.eq(user_id.get()))
+                .filter(password_resets::used_at.is_null()),
+        )
+        .set(password_resets::used_at.eq(Utc::now().naive_utc()))
+        .execute(&mut connection)?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,71 @@
+//! Diesel-backed repository operations for permissions granted through roles.
+
+use diesel::prelude::*;
+use pushkind_common::repository::errors::RepositoryResult;
+
+use crate::domain::permission::{Permission, RolePermission};
+use crate::domain::role::inherited_role_ids;
+use crate::domain::types::{HubId, RoleId, UserId};
+use crate::repository::role::load_role_parents;
+use crate::repository::{DieselRepository, PermissionReader};
+
+impl PermissionReader for DieselRepository {
+    fn list_user_permissions(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+    ) -> RepositoryResult<Vec<Permission>> {
+        use crate::schema::{permissions, role_permissions, user_roles, users};
+
+        let mut connection = self.conn()?;
+
+        let direct = user_roles::table
+            .inner_join(users::table)
+            .filter(user_roles::user_id.eq(user_id.get()))
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::deleted_at.is_null())
+            .select(user_roles::role_id)
+            .load::<i32>(&mut connection)?
+            .into_iter()
+            .map(RoleId::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let links = load_role_parents(&mut connection)?;
+        let role_ids = direct
+            .iter()
+            .copied()
+            .chain(inherited_role_ids(&direct, &links))
+            .map(|role_id| role_id.get())
+            .collect::<Vec<_>>();
+
+        let names = role_permissions::table
+            .inner_join(permissions::table)
+            .filter(role_permissions::role_id.eq_any(role_ids))
+            .select(permissions::name)
+            .distinct()
+            .load::<String>(&mut connection)?;
+
+        // Permissions added to the table by a newer release are skipped.
+        Ok(Permission::ALL
+            .into_iter()
+            .filter(|permission| names.iter().any(|name| name == permission.as_str()))
+            .collect())
+    }
+
+    fn list_role_permissions(&self) -> RepositoryResult<Vec<RolePermission>> {
+        use crate::schema::{permissions, role_permissions};
+
+        let mut connection = self.conn()?;
+
+        let rows = role_permissions::table
+            .inner_join(permissions::table)
+            .select((role_permissions::role_id, permissions::name))
+            .order((role_permissions::role_id, permissions::id))
+            .load::<(i32, String)>(&mut connection)?;
+
+        let grants = rows
+            .into_iter()
+            .filter_map(|(role_id, name)| RolePermission::try_new(role_id, &name).ok())
+            .collect();
+        Ok(grants)
+    }
+}
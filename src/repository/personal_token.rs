@@ -0,0 +1,114 @@
+//! Diesel-backed repository operations for personal access tokens.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::repository::errors::RepositoryResult;
+
+use crate::domain::personal_token::{NewPersonalAccessToken, PersonalAccessToken};
+use crate::domain::types::{PersonalAccessTokenId, UserId};
+use crate::models::personal_token::{
+    NewPersonalAccessToken as NewDbPersonalAccessToken,
+    PersonalAccessToken as DbPersonalAccessToken,
+};
+use crate::repository::{DieselRepository, PersonalTokenReader, PersonalTokenWriter};
+
+impl PersonalTokenReader for DieselRepository {
+    fn list_personal_tokens(
+        &self,
+        user_id: UserId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Vec<PersonalAccessToken>> {
+        use crate::schema::personal_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        let tokens = personal_access_tokens::table
+            .filter(personal_access_tokens::user_id.eq(user_id.get()))
+            .filter(personal_access_tokens::expires_at.gt(now))
+            .filter(personal_access_tokens::revoked_at.is_null())
+            .order((
+                personal_access_tokens::created_at.desc(),
+                personal_access_tokens::id.desc(),
+            ))
+            .load::<DbPersonalAccessToken>(&mut connection)?;
+
+        let tokens = tokens
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tokens)
+    }
+
+    fn get_personal_token_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> RepositoryResult<Option<PersonalAccessToken>> {
+        use crate::schema::personal_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        let token = personal_access_tokens::table
+            .filter(personal_access_tokens::token_hash.eq(token_hash))
+            .first::<DbPersonalAccessToken>(&mut connection)
+            .optional()?;
+
+        Ok(token.map(TryInto::try_into).transpose()?)
+    }
+}
+
+impl PersonalTokenWriter for DieselRepository {
+    fn create_personal_token(
+        &self,
+        new_token: &NewPersonalAccessToken,
+    ) -> RepositoryResult<PersonalAccessToken> {
+        use crate::schema::personal_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        let token = diesel::insert_into(personal_access_tokens::table)
+            .values(&NewDbPersonalAccessToken::from(new_token))
+            .get_result::<DbPersonalAccessToken>(&mut connection)?;
+
+        Ok(token.try_into()?)
+    }
+
+    fn touch_personal_token(
+        &self,
+        id: PersonalAccessTokenId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<()> {
+        use crate::schema::personal_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        diesel::update(
+            personal_access_tokens::table.filter(personal_access_tokens::id.eq(id.get())),
+        )
+        .set(personal_access_tokens::last_used_at.eq(now))
+        .execute(&mut connection)?;
+
+        Ok(())
+    }
+
+    fn revoke_personal_token(
+        &self,
+        user_id: UserId,
+        id: PersonalAccessTokenId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize> {
+        use crate::schema::personal_access_tokens;
+
+        let mut connection = self.conn()?;
+
+        let revoked = diesel::update(
+            personal_access_tokens::table
+                .filter(personal_access_tokens::id.eq(id.get()))
+                .filter(personal_access_tokens::user_id.eq(user_id.get()))
+                .filter(personal_access_tokens::revoked_at.is_null()),
+        )
+        .set(personal_access_tokens::revoked_at.eq(now))
+        .execute(&mut connection)?;
+
+        Ok(revoked)
+    }
+}
@@ -1,13 +1,31 @@
 //! Diesel-backed repository operations for roles.
 
+use chrono::Utc;
 use diesel::prelude::*;
+use pushkind_common::db::DbConnection;
 use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
 
-use crate::domain::role::{NewRole, Role};
-use crate::domain::types::RoleId;
-use crate::models::role::{NewRole as NewDbRole, Role as DbRole};
+use crate::domain::role::{NewRole, Role, RoleParent, UpdateRole, creates_cycle};
+use crate::domain::types::{HubId, RoleId};
+use crate::models::role::{
+    NewRole as NewDbRole, NewRoleParent as NewDbRoleParent,
+    NewRolePermission as NewDbRolePermission, Role as DbRole, UpdateRole as DbUpdateRole,
+};
 use crate::repository::{DieselRepository, RoleReader, RoleWriter};
 
+/// Loads every link between a role and its parent roles.
+pub(crate) fn load_role_parents(conn: &mut DbConnection) -> RepositoryResult<Vec<RoleParent>> {
+    use crate::schema::role_parents;
+
+    role_parents::table
+        .order((role_parents::role_id, role_parents::parent_id))
+        .select((role_parents::role_id, role_parents::parent_id))
+        .load::<(i32, i32)>(conn)?
+        .into_iter()
+        .map(|(role_id, parent_id)| Ok(RoleParent::try_new(role_id, parent_id)?))
+        .collect()
+}
+
 impl RoleReader for DieselRepository {
     fn get_role_by_id(&self, id: RoleId) -> RepositoryResult<Option<Role>> {
         use crate::schema::roles;
@@ -23,13 +41,14 @@ impl RoleReader for DieselRepository {
         Ok(role)
     }
 
-    fn get_role_by_name(&self, name: &str) -> RepositoryResult<Option<Role>> {
+    fn get_role_by_name(&self, name: &str, hub_id: HubId) -> RepositoryResult<Option<Role>> {
         use crate::schema::roles;
 
         let mut connection = self.conn()?;
 
         let result = roles::table
             .filter(roles::name.eq(name))
+            .filter(roles::hub_id.is_null().or(roles::hub_id.eq(hub_id.get())))
             .first::<DbRole>(&mut connection)
             .optional()?;
 
@@ -37,12 +56,16 @@ impl RoleReader for DieselRepository {
         Ok(role)
     }
 
-    fn list_roles(&self) -> RepositoryResult<Vec<Role>> {
+    fn list_roles(&self, hub_id: HubId) -> RepositoryResult<Vec<Role>> {
         use crate::schema::roles;
 
         let mut connection = self.conn()?;
 
-        let results = roles::table.load::<DbRole>(&mut connection)?;
+        // SQLite sorts NULL first, so system roles lead the list.
+        let results = roles::table
+            .filter(roles::hub_id.is_null().or(roles::hub_id.eq(hub_id.get())))
+            .order((roles::hub_id.asc(), roles::id.asc()))
+            .load::<DbRole>(&mut connection)?;
 
         let roles = results
             .into_iter()
@@ -50,32 +73,127 @@ impl RoleReader for DieselRepository {
             .collect::<Result<Vec<_>, _>>()?;
         Ok(roles)
     }
+
+    fn count_role_members(&self, hub_id: HubId) -> RepositoryResult<Vec<(RoleId, usize)>> {
+        use crate::schema::{user_roles, users};
+
+        let mut connection = self.conn()?;
+
+        let counts = user_roles::table
+            .inner_join(users::table)
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::deleted_at.is_null())
+            .group_by(user_roles::role_id)
+            .select((user_roles::role_id, diesel::dsl::count_star()))
+            .load::<(i32, i64)>(&mut connection)?;
+
+        counts
+            .into_iter()
+            .map(|(role_id, count)| Ok((RoleId::try_from(role_id)?, count as usize)))
+            .collect::<RepositoryResult<Vec<_>>>()
+    }
+
+    fn list_role_parents(&self) -> RepositoryResult<Vec<RoleParent>> {
+        let mut connection = self.conn()?;
+
+        load_role_parents(&mut connection)
+    }
 }
 
 impl RoleWriter for DieselRepository {
     fn create_role(&self, new_role: &NewRole) -> RepositoryResult<Role> {
         use crate::schema::roles;
 
+        use crate::schema::{permissions, role_permissions};
+
         let mut connection = self.conn()?;
 
-        let new_db_role = NewDbRole::from(new_role); // Convert to DbNewRole
-        let db_role = diesel::insert_into(roles::table)
-            .values(&new_db_role)
-            .get_result::<DbRole>(&mut connection)?;
+        let db_role = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let new_db_role = NewDbRole::from(new_role); // Convert to DbNewRole
+            let db_role = diesel::insert_into(roles::table)
+                .values(&new_db_role)
+                .get_result::<DbRole>(conn)?;
+
+            let names = new_role
+                .permissions
+                .iter()
+                .map(|permission| permission.as_str())
+                .collect::<Vec<_>>();
+            let permission_ids = permissions::table
+                .filter(permissions::name.eq_any(names))
+                .select(permissions::id)
+                .load::<i32>(conn)?;
+            let grants = permission_ids
+                .into_iter()
+                .map(|permission_id| NewDbRolePermission {
+                    role_id: db_role.id,
+                    permission_id,
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(role_permissions::table)
+                .values(&grants)
+                .execute(conn)?;
+
+            Ok(db_role)
+        })?;
         let role = db_role.try_into()?; // Convert DbRole to DomainRole
         Ok(role)
     }
 
-    fn delete_role(&self, role_id: RoleId) -> RepositoryResult<usize> {
+    fn delete_role(&self, role_id: RoleId, hub_id: HubId) -> RepositoryResult<usize> {
+        use crate::schema::hub_default_roles;
+        use crate::schema::invitation_roles;
+        use crate::schema::role_parents;
+        use crate::schema::role_permissions;
         use crate::schema::roles;
+        use crate::schema::service_account_roles;
         use crate::schema::user_roles;
 
         let mut connection = self.conn()?;
 
         let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let visible = roles::table
+                .filter(roles::id.eq(role_id.get()))
+                .filter(roles::hub_id.is_null().or(roles::hub_id.eq(hub_id.get())))
+                .count()
+                .get_result::<i64>(conn)?;
+            if visible == 0 {
+                return Ok(0);
+            }
+
             diesel::delete(user_roles::table.filter(user_roles::role_id.eq(role_id.get())))
                 .execute(conn)?;
 
+            diesel::delete(
+                service_account_roles::table
+                    .filter(service_account_roles::role_id.eq(role_id.get())),
+            )
+            .execute(conn)?;
+
+            diesel::delete(
+                hub_default_roles::table.filter(hub_default_roles::role_id.eq(role_id.get())),
+            )
+            .execute(conn)?;
+
+            diesel::delete(
+                invitation_roles::table.filter(invitation_roles::role_id.eq(role_id.get())),
+            )
+            .execute(conn)?;
+
+            diesel::delete(
+                role_permissions::table.filter(role_permissions::role_id.eq(role_id.get())),
+            )
+            .execute(conn)?;
+
+            diesel::delete(
+                role_parents::table.filter(
+                    role_parents::role_id
+                        .eq(role_id.get())
+                        .or(role_parents::parent_id.eq(role_id.get())),
+                ),
+            )
+            .execute(conn)?;
+
             diesel::delete(roles::table.filter(roles::id.eq(role_id.get()))).execute(conn)
         })?;
 
@@ -85,4 +203,82 @@ impl RoleWriter for DieselRepository {
 
         Ok(result)
     }
+
+    fn update_role(
+        &self,
+        role_id: RoleId,
+        hub_id: HubId,
+        updates: &UpdateRole,
+    ) -> RepositoryResult<Role> {
+        use crate::schema::roles;
+
+        let mut connection = self.conn()?;
+
+        let db_updates = DbUpdateRole {
+            name: updates.name.as_str(),
+            description: updates.description.as_str(),
+            updated_at: Utc::now().naive_utc(),
+        };
+
+        let db_role = diesel::update(roles::table)
+            .filter(roles::id.eq(role_id.get()))
+            .filter(roles::hub_id.is_null().or(roles::hub_id.eq(hub_id.get())))
+            .set(&db_updates)
+            .get_result::<DbRole>(&mut connection)
+            .optional()?
+            .ok_or(RepositoryError::NotFound)?;
+
+        Ok(db_role.try_into()?)
+    }
+
+    fn set_role_parents(
+        &self,
+        role_id: RoleId,
+        hub_id: HubId,
+        parent_ids: &[RoleId],
+    ) -> RepositoryResult<()> {
+        use crate::schema::{role_parents, roles};
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            let visible = roles::table
+                .filter(roles::id.eq(role_id.get()))
+                .filter(roles::hub_id.is_null().or(roles::hub_id.eq(hub_id.get())))
+                .count()
+                .get_result::<i64>(conn)?;
+            if visible == 0 {
+                return Err(RepositoryError::NotFound);
+            }
+
+            // Checked inside the transaction so that concurrent changes to
+            // other roles cannot close a loop.
+            if creates_cycle(role_id, parent_ids, &load_role_parents(conn)?) {
+                return Err(RepositoryError::ValidationError(
+                    "role inheritance would form a cycle".to_string(),
+                ));
+            }
+
+            diesel::delete(role_parents::table.filter(role_parents::role_id.eq(role_id.get())))
+                .execute(conn)?;
+            let mut links = parent_ids
+                .iter()
+                .map(|parent_id| NewDbRoleParent {
+                    role_id: role_id.get(),
+                    parent_id: parent_id.get(),
+                })
+                .collect::<Vec<_>>();
+            links.sort_by_key(|link| link.parent_id);
+            links.dedup_by_key(|link| link.parent_id);
+            diesel::insert_into(role_parents::table)
+                .values(&links)
+                .execute(conn)?;
+
+            diesel::update(roles::table.filter(roles::id.eq(role_id.get())))
+                .set(roles::updated_at.eq(Utc::now().naive_utc()))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
 }
@@ -0,0 +1,296 @@
+//! Diesel-backed repository operations for service accounts.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::db::DbConnection;
+use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
+
+use crate::domain::role::Role;
+use crate::domain::service_account::{
+    NewServiceAccount, NewServiceAccountToken, ServiceAccount, ServiceAccountToken,
+    UpdateServiceAccount,
+};
+use crate::domain::types::{HubId, RoleId, ServiceAccountId};
+use crate::models::role::Role as DbRole;
+use crate::models::service_account::{
+    NewServiceAccount as NewDbServiceAccount, NewServiceAccountRole as NewDbServiceAccountRole,
+    NewServiceAccountToken as NewDbServiceAccountToken, ServiceAccount as DbServiceAccount,
+    ServiceAccountToken as DbServiceAccountToken,
+};
+use crate::repository::{DieselRepository, ServiceAccountReader, ServiceAccountWriter};
+
+/// Loads the roles of `accounts` and converts them into domain values.
+fn with_roles(
+    conn: &mut DbConnection,
+    accounts: Vec<DbServiceAccount>,
+) -> RepositoryResult<Vec<ServiceAccount>> {
+    use crate::schema::{roles, service_account_roles};
+
+    let account_ids: Vec<i32> = accounts.iter().map(|account| account.id).collect();
+    let roles = roles::table
+        .inner_join(service_account_roles::table)
+        .filter(service_account_roles::service_account_id.eq_any(account_ids))
+        .order(roles::name.asc())
+        .select((
+            service_account_roles::service_account_id,
+            roles::all_columns,
+        ))
+        .load::<(i32, DbRole)>(conn)?
+        .into_iter()
+        .map(|(account_id, role)| {
+            let role: Role = role.try_into()?;
+            Ok((account_id, role))
+        })
+        .collect::<RepositoryResult<Vec<(i32, Role)>>>()?;
+
+    accounts
+        .into_iter()
+        .map(|account| {
+            let account_roles = roles
+                .iter()
+                .filter(|(account_id, _)| *account_id == account.id)
+                .map(|(_, role)| role.clone())
+                .collect();
+            Ok(account.into_domain(account_roles)?)
+        })
+        .collect()
+}
+
+/// Replaces the roles of a service account with `role_ids`.
+fn replace_roles(
+    conn: &mut DbConnection,
+    account_id: i32,
+    role_ids: &[RoleId],
+) -> Result<(), diesel::result::Error> {
+    use crate::schema::service_account_roles;
+
+    diesel::delete(
+        service_account_roles::table
+            .filter(service_account_roles::service_account_id.eq(account_id)),
+    )
+    .execute(conn)?;
+
+    let new_roles = role_ids
+        .iter()
+        .map(|role_id| NewDbServiceAccountRole {
+            service_account_id: account_id,
+            role_id: role_id.get(),
+        })
+        .collect::<Vec<_>>();
+    diesel::insert_into(service_account_roles::table)
+        .values(&new_roles)
+        .execute(conn)?;
+    Ok(())
+}
+
+impl ServiceAccountReader for DieselRepository {
+    fn list_service_accounts(&self, hub_id: HubId) -> RepositoryResult<Vec<ServiceAccount>> {
+        use crate::schema::service_accounts;
+
+        let mut connection = self.conn()?;
+
+        let accounts = service_accounts::table
+            .filter(service_accounts::hub_id.eq(hub_id.get()))
+            .order(service_accounts::name.asc())
+            .load::<DbServiceAccount>(&mut connection)?;
+
+        with_roles(&mut connection, accounts)
+    }
+
+    fn get_service_account(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+    ) -> RepositoryResult<Option<ServiceAccount>> {
+        use crate::schema::service_accounts;
+
+        let mut connection = self.conn()?;
+
+        let account = service_accounts::table
+            .filter(service_accounts::id.eq(id.get()))
+            .filter(service_accounts::hub_id.eq(hub_id.get()))
+            .first::<DbServiceAccount>(&mut connection)
+            .optional()?;
+
+        Ok(with_roles(&mut connection, account.into_iter().collect())?.pop())
+    }
+
+    fn get_service_account_by_client_id(
+        &self,
+        client_id: &str,
+    ) -> RepositoryResult<Option<ServiceAccount>> {
+        use crate::schema::{hubs, service_accounts};
+
+        let mut connection = self.conn()?;
+
+        let account = service_accounts::table
+            .inner_join(hubs::table)
+            .filter(service_accounts::client_id.eq(client_id))
+            .filter(hubs::deleted_at.is_null())
+            .select(service_accounts::all_columns)
+            .first::<DbServiceAccount>(&mut connection)
+            .optional()?;
+
+        Ok(with_roles(&mut connection, account.into_iter().collect())?.pop())
+    }
+
+    fn get_service_account_token(
+        &self,
+        token_hash: &str,
+    ) -> RepositoryResult<Option<ServiceAccountToken>> {
+        use crate::schema::service_account_tokens;
+
+        let mut connection = self.conn()?;
+
+        let token = service_account_tokens::table
+            .filter(service_account_tokens::token_hash.eq(token_hash))
+            .first::<DbServiceAccountToken>(&mut connection)
+            .optional()?;
+
+        Ok(token.map(TryInto::try_into).transpose()?)
+    }
+}
+
+impl ServiceAccountWriter for DieselRepository {
+    fn create_service_account(
+        &self,
+        new_account: &NewServiceAccount,
+    ) -> RepositoryResult<ServiceAccount> {
+        use crate::schema::service_accounts;
+
+        let mut connection = self.conn()?;
+
+        let account = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let account = diesel::insert_into(service_accounts::table)
+                .values(&NewDbServiceAccount::from(new_account))
+                .get_result::<DbServiceAccount>(conn)?;
+            replace_roles(conn, account.id, &new_account.roles)?;
+            Ok(account)
+        })?;
+
+        Ok(with_roles(&mut connection, vec![account])?
+            .pop()
+            .ok_or(RepositoryError::NotFound)?)
+    }
+
+    fn update_service_account(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+        updates: &UpdateServiceAccount,
+    ) -> RepositoryResult<ServiceAccount> {
+        use crate::schema::service_accounts;
+
+        let mut connection = self.conn()?;
+
+        let account = connection.transaction::<_, RepositoryError, _>(|conn| {
+            let account = diesel::update(
+                service_accounts::table
+                    .filter(service_accounts::id.eq(id.get()))
+                    .filter(service_accounts::hub_id.eq(hub_id.get())),
+            )
+            .set(service_accounts::name.eq(updates.name.as_str()))
+            .get_result::<DbServiceAccount>(conn)
+            .optional()?
+            .ok_or(RepositoryError::NotFound)?;
+            replace_roles(conn, account.id, &updates.roles)?;
+            Ok(account)
+        })?;
+
+        Ok(with_roles(&mut connection, vec![account])?
+            .pop()
+            .ok_or(RepositoryError::NotFound)?)
+    }
+
+    fn rotate_service_account_secret(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+        secret_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<ServiceAccount> {
+        use crate::schema::{service_account_tokens, service_accounts};
+
+        let mut connection = self.conn()?;
+
+        let account = connection.transaction::<_, RepositoryError, _>(|conn| {
+            let account = diesel::update(
+                service_accounts::table
+                    .filter(service_accounts::id.eq(id.get()))
+                    .filter(service_accounts::hub_id.eq(hub_id.get())),
+            )
+            .set((
+                service_accounts::secret_hash.eq(secret_hash),
+                service_accounts::secret_rotated_at.eq(now),
+            ))
+            .get_result::<DbServiceAccount>(conn)
+            .optional()?
+            .ok_or(RepositoryError::NotFound)?;
+
+            diesel::update(
+                service_account_tokens::table
+                    .filter(service_account_tokens::service_account_id.eq(account.id))
+                    .filter(service_account_tokens::revoked_at.is_null()),
+            )
+            .set(service_account_tokens::revoked_at.eq(now))
+            .execute(conn)?;
+            Ok(account)
+        })?;
+
+        Ok(with_roles(&mut connection, vec![account])?
+            .pop()
+            .ok_or(RepositoryError::NotFound)?)
+    }
+
+    fn delete_service_account(
+        &self,
+        id: ServiceAccountId,
+        hub_id: HubId,
+    ) -> RepositoryResult<usize> {
+        use crate::schema::{service_account_roles, service_account_tokens, service_accounts};
+
+        let mut connection = self.conn()?;
+
+        let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let account_id = service_accounts::table
+                .filter(service_accounts::id.eq(id.get()))
+                .filter(service_accounts::hub_id.eq(hub_id.get()))
+                .select(service_accounts::id)
+                .first::<i32>(conn)
+                .optional()?;
+            let Some(account_id) = account_id else {
+                return Ok(0);
+            };
+
+            diesel::delete(
+                service_account_tokens::table
+                    .filter(service_account_tokens::service_account_id.eq(account_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(
+                service_account_roles::table
+                    .filter(service_account_roles::service_account_id.eq(account_id)),
+            )
+            .execute(conn)?;
+            diesel::delete(service_accounts::table.filter(service_accounts::id.eq(account_id)))
+                .execute(conn)
+        })?;
+
+        Ok(result)
+    }
+
+    fn create_service_account_token(
+        &self,
+        new_token: &NewServiceAccountToken,
+    ) -> RepositoryResult<ServiceAccountToken> {
+        use crate::schema::service_account_tokens;
+
+        let mut connection = self.conn()?;
+
+        let token = diesel::insert_into(service_account_tokens::table)
+            .values(&NewDbServiceAccountToken::from(new_token))
+            .get_result::<DbServiceAccountToken>(&mut connection)?;
+
+        Ok(token.try_into()?)
+    }
+}
@@ -0,0 +1,151 @@
+//! Diesel-backed repository operations for issued sessions.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::repository::errors::RepositoryResult;
+
+use crate::domain::session::{NewSession, Session};
+use crate::domain::types::{HubId, SessionId, UserId};
+use crate::models::session::{NewSession as NewDbSession, Session as DbSession};
+use crate::repository::{DieselRepository, SessionReader, SessionWriter};
+
+impl SessionReader for DieselRepository {
+    fn get_session_by_jti(&self, jti: &str) -> RepositoryResult<Option<Session>> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        let session = sessions::table
+            .filter(sessions::jti.eq(jti))
+            .first::<DbSession>(&mut connection)
+            .optional()?;
+
+        Ok(session.map(TryInto::try_into).transpose()?)
+    }
+
+    fn list_live_sessions(
+        &self,
+        hub_id: HubId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Vec<Session>> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        let sessions = sessions::table
+            .filter(sessions::hub_id.eq(hub_id.get()))
+            .filter(sessions::expires_at.gt(now))
+            .filter(sessions::revoked_at.is_null())
+            .order(sessions::created_at.desc())
+            .load::<DbSession>(&mut connection)?;
+
+        let sessions = sessions
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+
+    fn list_user_sessions(
+        &self,
+        user_id: UserId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<Vec<Session>> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        let sessions = sessions::table
+            .filter(sessions::user_id.eq(user_id.get()))
+            .filter(sessions::expires_at.gt(now))
+            .filter(sessions::revoked_at.is_null())
+            .order((sessions::created_at.desc(), sessions::id.desc()))
+            .load::<DbSession>(&mut connection)?;
+
+        let sessions = sessions
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+}
+
+impl SessionWriter for DieselRepository {
+    fn create_session(&self, new_session: &NewSession) -> RepositoryResult<Session> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        let session = diesel::insert_into(sessions::table)
+            .values(&NewDbSession::from(new_session))
+            .get_result::<DbSession>(&mut connection)?;
+
+        Ok(session.try_into()?)
+    }
+
+    fn touch_session(&self, id: SessionId, now: NaiveDateTime) -> RepositoryResult<()> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        diesel::update(sessions::table.filter(sessions::id.eq(id.get())))
+            .set(sessions::last_seen_at.eq(now))
+            .execute(&mut connection)?;
+
+        Ok(())
+    }
+
+    fn revoke_session(
+        &self,
+        user_id: UserId,
+        id: SessionId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        let revoked = diesel::update(
+            sessions::table
+                .filter(sessions::id.eq(id.get()))
+                .filter(sessions::user_id.eq(user_id.get()))
+                .filter(sessions::revoked_at.is_null()),
+        )
+        .set(sessions::revoked_at.eq(now))
+        .execute(&mut connection)?;
+
+        Ok(revoked)
+    }
+
+    fn revoke_session_by_jti(&self, jti: &str, now: NaiveDateTime) -> RepositoryResult<usize> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        let revoked = diesel::update(
+            sessions::table
+                .filter(sessions::jti.eq(jti))
+                .filter(sessions::revoked_at.is_null()),
+        )
+        .set(sessions::revoked_at.eq(now))
+        .execute(&mut connection)?;
+
+        Ok(revoked)
+    }
+
+    fn revoke_user_sessions(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<usize> {
+        use crate::schema::sessions;
+
+        let mut connection = self.conn()?;
+
+        let revoked = diesel::update(
+            sessions::table
+                .filter(sessions::user_id.eq(user_id.get()))
+                .filter(sessions::revoked_at.is_null()),
+        )
+        .set(sessions::revoked_at.eq(now))
+        .execute(&mut connection)?;
+
+        Ok(revoked)
+    }
+}
@@ -0,0 +1,163 @@
+//! Diesel-backed repository operations for TOTP second factors.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
+
+use crate::domain::two_factor::UserTotp;
+use crate::domain::types::UserId;
+use crate::models::two_factor::{
+    NewRecoveryCode as NewDbRecoveryCode, NewUserTotp as NewDbUserTotp, UserTotp as DbUserTotp,
+};
+use crate::repository::{DieselRepository, TwoFactorReader, TwoFactorWriter};
+
+impl TwoFactorReader for DieselRepository {
+    fn get_user_totp(&self, user_id: UserId) -> RepositoryResult<Option<UserTotp>> {
+        use crate::schema::user_totp;
+
+        let mut connection = self.conn()?;
+
+        let result = user_totp::table
+            .find(user_id.get())
+            .first::<DbUserTotp>(&mut connection)
+            .optional()?;
+
+        let totp = result.map(TryInto::try_into).transpose()?;
+        Ok(totp)
+    }
+}
+
+impl TwoFactorWriter for DieselRepository {
+    fn create_pending_totp(&self, user_id: UserId, secret: &str) -> RepositoryResult<UserTotp> {
+        use crate::schema::user_totp;
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            // A confirmed secret is kept; inserting over it fails on the
+            // primary key instead of silently disabling the second factor.
+            diesel::delete(
+                user_totp::table
+                    .filter(user_totp::user_id.eq(user_id.get()))
+                    .filter(user_totp::enabled_at.is_null()),
+            )
+            .execute(conn)?;
+
+            let totp = diesel::insert_into(user_totp::table)
+                .values(&NewDbUserTotp {
+                    user_id: user_id.get(),
+                    secret,
+                })
+                .get_result::<DbUserTotp>(conn)?;
+
+            Ok(totp.try_into()?)
+        })
+    }
+
+    fn enable_totp(
+        &self,
+        user_id: UserId,
+        step: i64,
+        recovery_code_hashes: &[String],
+        now: NaiveDateTime,
+    ) -> RepositoryResult<UserTotp> {
+        use crate::schema::{user_recovery_codes, user_totp};
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            let totp = diesel::update(
+                user_totp::table
+                    .filter(user_totp::user_id.eq(user_id.get()))
+                    .filter(user_totp::enabled_at.is_null()),
+            )
+            .set((
+                user_totp::enabled_at.eq(now),
+                user_totp::last_used_step.eq(step),
+            ))
+            .get_result::<DbUserTotp>(conn)
+            .optional()?
+            .ok_or(RepositoryError::NotFound)?;
+
+            diesel::delete(
+                user_recovery_codes::table.filter(user_recovery_codes::user_id.eq(user_id.get())),
+            )
+            .execute(conn)?;
+
+            let new_codes = recovery_code_hashes
+                .iter()
+                .map(|code_hash| NewDbRecoveryCode {
+                    user_id: user_id.get(),
+                    code_hash,
+                })
+                .collect::<Vec<_>>();
+            diesel::insert_into(user_recovery_codes::table)
+                .values(&new_codes)
+                .execute(conn)?;
+
+            Ok(totp.try_into()?)
+        })
+    }
+
+    fn record_totp_step(&self, user_id: UserId, step: i64) -> RepositoryResult<bool> {
+        use crate::schema::user_totp;
+
+        let mut connection = self.conn()?;
+
+        // Compare and update in one statement so two requests racing with the
+        // same code cannot both succeed.
+        let updated = diesel::update(
+            user_totp::table
+                .filter(user_totp::user_id.eq(user_id.get()))
+                .filter(
+                    user_totp::last_used_step
+                        .is_null()
+                        .or(user_totp::last_used_step.lt(step)),
+                ),
+        )
+        .set(user_totp::last_used_step.eq(step))
+        .execute(&mut connection)?;
+
+        Ok(updated > 0)
+    }
+
+    fn consume_recovery_code(
+        &self,
+        user_id: UserId,
+        code_hash: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<bool> {
+        use crate::schema::user_recovery_codes;
+
+        let mut connection = self.conn()?;
+
+        let updated = diesel::update(
+            user_recovery_codes::table
+                .filter(user_recovery_codes::user_id.eq(user_id.get()))
+                .filter(user_recovery_codes::code_hash.eq(code_hash))
+                .filter(user_recovery_codes::used_at.is_null()),
+        )
+        .set(user_recovery_codes::used_at.eq(now))
+        .execute(&mut connection)?;
+
+        Ok(updated > 0)
+    }
+
+    fn delete_user_totp(&self, user_id: UserId) -> RepositoryResult<usize> {
+        use crate::schema::{user_recovery_codes, user_totp};
+
+        let mut connection = self.conn()?;
+
+        let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::delete(
+                user_recovery_codes::table.filter(user_recovery_codes::user_id.eq(user_id.get())),
+            )
+            .execute(conn)?;
+
+            diesel::delete(user_totp::table.filter(user_totp::user_id.eq(user_id.get())))
+                .execute(conn)
+        })?;
+
+        Ok(result)
+    }
+}
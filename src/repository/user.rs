@@ -5,19 +5,70 @@
 //! and full-text search filtering.
 
 use bcrypt::{DEFAULT_COST, hash, verify};
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use diesel::dsl::exists;
 use diesel::prelude::*;
 use diesel::sql_types::{Bool, Text};
+use diesel::sqlite::Sqlite;
+use pushkind_common::db::DbConnection;
 use pushkind_common::repository::build_fts_match_query;
 use pushkind_common::repository::errors::{RepositoryError, RepositoryResult};
 
-use crate::domain::role::Role;
-use crate::domain::types::{HubId, UserEmail, UserId};
+use crate::domain::audit::NewAuditEvent;
+use crate::domain::role::{Role, RoleChange, RoleChangeSummary, inherited_role_ids};
+use crate::domain::types::{HubId, RoleId, UserEmail, UserId, UserPassword};
 use crate::domain::user::{NewUser, UpdateUser, User, UserWithRoles};
+use crate::models::audit::NewAuditEvent as DbNewAuditEvent;
 use crate::models::role::{NewUserRole as DbNewUserRole, Role as DbRole};
 use crate::models::user::{NewUser as NewDbUser, UpdateUser as DbUpdateUser, User as DbUser};
-use crate::repository::{DieselRepository, UserListQuery, UserReader, UserRepository, UserWriter};
+use crate::repository::role::load_role_parents;
+use crate::repository::{
+    DieselRepository, UserListQuery, UserReader, UserRepository, UserSelection, UserStatusFilter,
+    UserWriter,
+};
+
+/// Attaches to each user the roles its direct roles imply through their
+/// parent roles.
+fn with_inherited_roles(
+    conn: &mut DbConnection,
+    users: Vec<UserWithRoles>,
+) -> RepositoryResult<Vec<UserWithRoles>> {
+    use crate::schema::roles;
+
+    let links = load_role_parents(conn)?;
+    if links.is_empty() {
+        return Ok(users);
+    }
+
+    let inherited = users
+        .iter()
+        .map(|user| inherited_role_ids(&user.user.roles, &links))
+        .collect::<Vec<_>>();
+    let role_ids = inherited
+        .iter()
+        .flatten()
+        .map(|role_id| role_id.get())
+        .collect::<Vec<_>>();
+    let roles = roles::table
+        .filter(roles::id.eq_any(role_ids))
+        .load::<DbRole>(conn)?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<Role>, _>>()?;
+
+    Ok(users
+        .into_iter()
+        .zip(inherited)
+        .map(|(user, role_ids)| {
+            let inherited_roles = role_ids
+                .iter()
+                .filter_map(|role_id| roles.iter().find(|role| role.id == *role_id))
+                .cloned()
+                .collect();
+            user.with_inherited_roles(inherited_roles)
+        })
+        .collect())
+}
 
 impl UserReader for DieselRepository {
     fn get_user_by_id(&self, id: UserId, hub_id: HubId) -> RepositoryResult<Option<UserWithRoles>> {
@@ -29,6 +80,7 @@ impl UserReader for DieselRepository {
             let user = users::table
                 .filter(users::id.eq(id.get()))
                 .filter(users::hub_id.eq(hub_id.get()))
+                .filter(users::deleted_at.is_null())
                 .first::<DbUser>(conn)
                 .optional()?;
 
@@ -50,7 +102,7 @@ impl UserReader for DieselRepository {
 
             let user: User = user.try_into()?;
 
-            Ok(Some(UserWithRoles::new(user, roles)))
+            Ok(with_inherited_roles(conn, vec![UserWithRoles::new(user, roles)])?.pop())
         })
     }
 
@@ -67,6 +119,7 @@ impl UserReader for DieselRepository {
             let user = users::table
                 .filter(users::email.eq(email.as_str()))
                 .filter(users::hub_id.eq(hub_id.get()))
+                .filter(users::deleted_at.is_null())
                 .first::<DbUser>(conn)
                 .optional()?;
             let user = match user {
@@ -87,50 +140,20 @@ impl UserReader for DieselRepository {
 
             let user: User = user.try_into()?;
 
-            Ok(Some(UserWithRoles::new(user, roles)))
+            Ok(with_inherited_roles(conn, vec![UserWithRoles::new(user, roles)])?.pop())
         })
     }
 
     fn list_users(&self, query: UserListQuery) -> RepositoryResult<(usize, Vec<UserWithRoles>)> {
         use crate::schema::roles;
-        use crate::schema::user_fts;
         use crate::schema::user_roles;
         use crate::schema::users;
 
         let mut conn = self.conn()?;
 
         conn.transaction::<_, RepositoryError, _>(|conn| {
-            // Build a boxed query with optional role and full-text filters so the
-            // same definition can be reused for total counting and pagination.
-            let query_builder = || {
-                let mut items = users::table
-                    .filter(users::hub_id.eq(query.hub_id.get()))
-                    .into_boxed::<diesel::sqlite::Sqlite>();
-                if let Some(role) = &query.role {
-                    items = items.filter(
-                        users::id.eq_any(
-                            user_roles::table
-                                .inner_join(roles::table)
-                                .filter(roles::name.eq(role))
-                                .select(user_roles::user_id),
-                        ),
-                    );
-                }
-                if let Some(term) = query.search.as_ref()
-                    && let Some(fts_query) = build_fts_match_query(term)
-                {
-                    let fts_filter = exists(
-                        user_fts::table
-                            .filter(user_fts::rowid.eq(users::id))
-                            .filter(
-                                diesel::dsl::sql::<Bool>("user_fts MATCH ")
-                                    .bind::<Text, _>(fts_query),
-                            ),
-                    );
-                    items = items.filter(fts_filter);
-                }
-                items
-            };
+            // The same definition is reused for total counting and pagination.
+            let query_builder = || filtered_users(&query);
 
             // Get the total count before applying pagination
             let total = query_builder().count().get_result::<i64>(conn)? as usize;
@@ -174,6 +197,7 @@ impl UserReader for DieselRepository {
                     Ok(UserWithRoles::new(user, user_roles))
                 })
                 .collect::<RepositoryResult<Vec<_>>>()?;
+            let user_with_roles = with_inherited_roles(conn, user_with_roles)?;
 
             Ok((total, user_with_roles))
         })
@@ -200,11 +224,32 @@ impl UserReader for DieselRepository {
             .collect::<Result<Vec<Role>, _>>()?;
         Ok(roles)
     }
+
+    fn list_deleted_users(&self, hub_id: HubId) -> RepositoryResult<Vec<User>> {
+        use crate::schema::{hubs, users};
+
+        let mut connection = self.conn()?;
+
+        let results = users::table
+            .inner_join(hubs::table)
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::deleted_at.is_not_null())
+            .filter(hubs::deleted_at.is_null())
+            .order((users::deleted_at.desc(), users::id.desc()))
+            .select(users::all_columns)
+            .load::<DbUser>(&mut connection)?;
+
+        let users = results
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<User>, _>>()?;
+        Ok(users)
+    }
 }
 
 impl UserWriter for DieselRepository {
     fn create_user(&self, new_user: &NewUser) -> RepositoryResult<User> {
-        use crate::schema::users;
+        use crate::schema::{user_roles, users};
 
         let mut connection = self.conn()?;
 
@@ -212,13 +257,28 @@ impl UserWriter for DieselRepository {
             RepositoryError::ValidationError(format!("Failed to saved User to DB: {e}"))
         })?;
 
-        // Persist the validated input and convert the Diesel model into the
-        // domain representation so callers never handle database types.
-        let user = diesel::insert_into(users::table)
-            .values(&new_db_user)
-            .get_result::<DbUser>(&mut connection)?;
-        let user = user.try_into()?;
-        Ok(user)
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            // Persist the validated input and convert the Diesel model into the
+            // domain representation so callers never handle database types.
+            let user = diesel::insert_into(users::table)
+                .values(&new_db_user)
+                .get_result::<DbUser>(conn)?;
+
+            let new_user_roles = new_user
+                .roles
+                .iter()
+                .map(|role_id| DbNewUserRole {
+                    user_id: user.id,
+                    role_id: role_id.get(),
+                })
+                .collect::<Vec<DbNewUserRole>>();
+            diesel::insert_into(user_roles::table)
+                .values(&new_user_roles)
+                .execute(conn)?;
+
+            let user = user.try_into()?;
+            Ok(user)
+        })
     }
 
     fn update_user(
@@ -235,6 +295,7 @@ impl UserWriter for DieselRepository {
             let user = users::table
                 .filter(users::id.eq(user_id.get()))
                 .filter(users::hub_id.eq(hub_id.get()))
+                .filter(users::deleted_at.is_null())
                 .first::<DbUser>(conn)
                 .optional()?
                 .ok_or(RepositoryError::NotFound)?;
@@ -253,6 +314,7 @@ impl UserWriter for DieselRepository {
             let db_updates = DbUpdateUser {
                 name: updates.name.as_str(),
                 password_hash,
+                must_change_password: updates.must_change_password,
                 updated_at: Utc::now().naive_utc(),
             };
 
@@ -289,30 +351,312 @@ impl UserWriter for DieselRepository {
         })
     }
 
-    fn delete_user(&self, user_id: UserId) -> RepositoryResult<usize> {
+    fn change_role_for_users(
+        &self,
+        role_id: RoleId,
+        hub_id: HubId,
+        change: RoleChange,
+        selection: &UserSelection,
+        audit: &NewAuditEvent,
+    ) -> RepositoryResult<RoleChangeSummary> {
+        use crate::schema::{audit_events, user_roles, users};
+
+        let mut connection = self.conn()?;
+
+        connection.transaction::<_, RepositoryError, _>(|conn| {
+            let mut summary = RoleChangeSummary::default();
+
+            // Resolve the selection inside the transaction so the change
+            // applies to the users as they are now.
+            let selected = match selection {
+                UserSelection::Ids(ids) => {
+                    let raw_ids = ids.iter().map(|id| id.get()).collect::<Vec<_>>();
+                    let found = users::table
+                        .filter(users::hub_id.eq(hub_id.get()))
+                        .filter(users::deleted_at.is_null())
+                        .filter(users::id.eq_any(raw_ids))
+                        .select(users::id)
+                        .order(users::id.asc())
+                        .load::<i32>(conn)?;
+                    summary.not_found = ids
+                        .iter()
+                        .filter(|id| !found.contains(&id.get()))
+                        .copied()
+                        .collect();
+                    found
+                }
+                UserSelection::Matching(query) => filtered_users(query)
+                    .filter(users::hub_id.eq(hub_id.get()))
+                    .select(users::id)
+                    .order(users::id.asc())
+                    .load::<i32>(conn)?,
+            };
+
+            let holders = user_roles::table
+                .filter(user_roles::role_id.eq(role_id.get()))
+                .filter(user_roles::user_id.eq_any(&selected))
+                .select(user_roles::user_id)
+                .order(user_roles::user_id.asc())
+                .load::<i32>(conn)?;
+
+            let changed = match change {
+                RoleChange::Add => {
+                    let new_user_roles = selected
+                        .iter()
+                        .filter(|user_id| !holders.contains(user_id))
+                        .map(|user_id| DbNewUserRole {
+                            user_id: *user_id,
+                            role_id: role_id.get(),
+                        })
+                        .collect::<Vec<DbNewUserRole>>();
+                    diesel::insert_into(user_roles::table)
+                        .values(&new_user_roles)
+                        .execute(conn)?;
+                    new_user_roles
+                        .into_iter()
+                        .map(|user_role| user_role.user_id)
+                        .collect::<Vec<_>>()
+                }
+                RoleChange::Remove => {
+                    diesel::delete(user_roles::table)
+                        .filter(user_roles::role_id.eq(role_id.get()))
+                        .filter(user_roles::user_id.eq_any(&holders))
+                        .execute(conn)?;
+                    holders
+                }
+            };
+
+            let events = changed
+                .iter()
+                .map(|user_id| audit.clone().with_target(*user_id))
+                .collect::<Vec<_>>();
+            let new_events = events
+                .iter()
+                .map(DbNewAuditEvent::from)
+                .collect::<Vec<DbNewAuditEvent>>();
+            diesel::insert_into(audit_events::table)
+                .values(&new_events)
+                .execute(conn)?;
+
+            summary.unchanged = selected.len() - changed.len();
+            summary.changed = changed
+                .into_iter()
+                .map(UserId::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(summary)
+        })
+    }
+
+    fn update_password(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+        password: &UserPassword,
+    ) -> RepositoryResult<User> {
+        use crate::schema::users;
+
+        let mut connection = self.conn()?;
+
+        let password_hash = hash(password.as_str(), DEFAULT_COST).map_err(|e| {
+            RepositoryError::ValidationError(format!("Failed to update user password: {e}"))
+        })?;
+
+        let user = diesel::update(users::table)
+            .filter(users::id.eq(user_id.get()))
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::deleted_at.is_null())
+            .set((
+                users::password_hash.eq(password_hash),
+                users::must_change_password.eq(false),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .get_result::<DbUser>(&mut connection)
+            .optional()?
+            .ok_or(RepositoryError::NotFound)?;
+
+        let user = user.try_into()?;
+        Ok(user)
+    }
+
+    fn mark_email_verified(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize> {
+        use crate::schema::users;
+
+        let mut connection = self.conn()?;
+
+        let result = diesel::update(users::table)
+            .filter(users::id.eq(user_id.get()))
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::email_verified_at.is_null())
+            .set(users::email_verified_at.eq(now))
+            .execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    fn suspend_user(
+        &self,
+        user_id: UserId,
+        hub_id: HubId,
+        reason: Option<&str>,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<usize> {
+        use crate::schema::users;
+
+        let mut connection = self.conn()?;
+
+        let result = diesel::update(users::table)
+            .filter(users::id.eq(user_id.get()))
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::disabled_at.is_null())
+            .set((
+                users::disabled_at.eq(now),
+                users::disabled_reason.eq(reason),
+                users::updated_at.eq(now),
+            ))
+            .execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    fn reactivate_user(&self, user_id: UserId, hub_id: HubId) -> RepositoryResult<usize> {
+        use crate::schema::users;
+
+        let mut connection = self.conn()?;
+
+        let result = diesel::update(users::table)
+            .filter(users::id.eq(user_id.get()))
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::disabled_at.is_not_null())
+            .set((
+                users::disabled_at.eq(None::<NaiveDateTime>),
+                users::disabled_reason.eq(None::<String>),
+                users::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    fn delete_user(&self, user_id: UserId, now: NaiveDateTime) -> RepositoryResult<usize> {
+        use crate::schema::{sessions, users};
+
+        let mut connection = self.conn()?;
+
+        // Keep the row for restoring and sign the user out everywhere in a
+        // single transaction.
+        let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let result = diesel::update(users::table)
+                .filter(users::id.eq(user_id.get()))
+                .filter(users::deleted_at.is_null())
+                .set(users::deleted_at.eq(now))
+                .execute(conn)?;
+
+            diesel::update(sessions::table)
+                .filter(sessions::user_id.eq(user_id.get()))
+                .filter(sessions::revoked_at.is_null())
+                .set(sessions::revoked_at.eq(now))
+                .execute(conn)?;
+
+            Ok(result)
+        })?;
+
+        if result == 0 {
+            return Err(RepositoryError::NotFound);
+        }
+        Ok(result)
+    }
+
+    fn restore_user(&self, user_id: UserId, hub_id: HubId) -> RepositoryResult<usize> {
+        use crate::schema::users;
+
+        let mut connection = self.conn()?;
+
+        let result = diesel::update(users::table)
+            .filter(users::id.eq(user_id.get()))
+            .filter(users::hub_id.eq(hub_id.get()))
+            .filter(users::deleted_at.is_not_null())
+            .set(users::deleted_at.eq(None::<NaiveDateTime>))
+            .execute(&mut connection)?;
+
+        Ok(result)
+    }
+
+    fn purge_deleted_users(&self, before: NaiveDateTime) -> RepositoryResult<usize> {
+        use crate::schema::personal_access_tokens;
         use crate::schema::user_roles;
         use crate::schema::users;
 
         let mut connection = self.conn()?;
 
-        // Delete role mappings and the user record inside a single
+        // Delete role mappings, tokens and the user records inside a single
         // transaction so referential integrity remains consistent even when
         // cascading deletes occur.
         let result = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+            let expired_users = users::table
+                .filter(users::deleted_at.lt(before))
+                .select(users::id)
+                .load::<i32>(conn)?;
+
             diesel::delete(user_roles::table)
-                .filter(user_roles::user_id.eq(user_id.get()))
+                .filter(user_roles::user_id.eq_any(&expired_users))
+                .execute(conn)?;
+
+            diesel::delete(personal_access_tokens::table)
+                .filter(personal_access_tokens::user_id.eq_any(&expired_users))
                 .execute(conn)?;
 
             diesel::delete(users::table)
-                .filter(users::id.eq(user_id.get()))
+                .filter(users::id.eq_any(&expired_users))
                 .execute(conn)
         })?;
 
-        if result == 0 {
-            return Err(RepositoryError::NotFound);
-        }
         Ok(result)
     }
 }
 
 impl UserRepository for DieselRepository {}
+
+/// Builds a boxed query over the live users of a hub with the status,
+/// optional role and full-text filters of `query`.
+fn filtered_users(query: &UserListQuery) -> crate::schema::users::BoxedQuery<'_, Sqlite> {
+    use crate::schema::roles;
+    use crate::schema::user_fts;
+    use crate::schema::user_roles;
+    use crate::schema::users;
+
+    let mut items = users::table
+        .filter(users::hub_id.eq(query.hub_id.get()))
+        .filter(users::deleted_at.is_null())
+        .into_boxed::<Sqlite>();
+    items = match query.status {
+        UserStatusFilter::Active => items.filter(users::disabled_at.is_null()),
+        UserStatusFilter::Suspended => items.filter(users::disabled_at.is_not_null()),
+        UserStatusFilter::All => items,
+    };
+    if let Some(role) = &query.role {
+        items = items.filter(
+            users::id.eq_any(
+                user_roles::table
+                    .inner_join(roles::table)
+                    .filter(roles::name.eq(role))
+                    .select(user_roles::user_id),
+            ),
+        );
+    }
+    if let Some(term) = query.search.as_ref()
+        && let Some(fts_query) = build_fts_match_query(term)
+    {
+        let fts_filter = exists(
+            user_fts::table
+                .filter(user_fts::rowid.eq(users::id))
+                .filter(diesel::dsl::sql::<Bool>("user_fts MATCH ").bind::<Text, _>(fts_query)),
+        );
+        items = items.filter(fts_filter);
+    }
+    items
+}
@@ -0,0 +1,104 @@
+//! Diesel-backed repository operations for WebAuthn credentials.
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pushkind_common::repository::errors::RepositoryResult;
+
+use crate::domain::types::{UserCredentialId, UserId};
+use crate::domain::user_credential::{NewUserCredential, UserCredential};
+use crate::models::user_credential::{
+    NewUserCredential as NewDbUserCredential, UserCredential as DbUserCredential,
+};
+use crate::repository::{CredentialReader, CredentialWriter, DieselRepository};
+
+impl CredentialReader for DieselRepository {
+    fn list_user_credentials(&self, user_id: UserId) -> RepositoryResult<Vec<UserCredential>> {
+        use crate::schema::user_credentials;
+
+        let mut connection = self.conn()?;
+
+        let credentials = user_credentials::table
+            .filter(user_credentials::user_id.eq(user_id.get()))
+            .order(user_credentials::id.asc())
+            .load::<DbUserCredential>(&mut connection)?;
+
+        let credentials = credentials
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(credentials)
+    }
+
+    fn get_credential_by_credential_id(
+        &self,
+        credential_id: &str,
+    ) -> RepositoryResult<Option<UserCredential>> {
+        use crate::schema::user_credentials;
+
+        let mut connection = self.conn()?;
+
+        let result = user_credentials::table
+            .filter(user_credentials::credential_id.eq(credential_id))
+            .first::<DbUserCredential>(&mut connection)
+            .optional()?;
+
+        let credential = result.map(TryInto::try_into).transpose()?;
+        Ok(credential)
+    }
+}
+
+impl CredentialWriter for DieselRepository {
+    fn create_user_credential(
+        &self,
+        new_credential: &NewUserCredential,
+    ) -> RepositoryResult<UserCredential> {
+        use crate::schema::user_credentials;
+
+        let mut connection = self.conn()?;
+
+        let credential = diesel::insert_into(user_credentials::table)
+            .values(&NewDbUserCredential::from(new_credential))
+            .get_result::<DbUserCredential>(&mut connection)?;
+
+        Ok(credential.try_into()?)
+    }
+
+    fn update_credential_passkey(
+        &self,
+        id: UserCredentialId,
+        passkey: &str,
+        now: NaiveDateTime,
+    ) -> RepositoryResult<UserCredential> {
+        use crate::schema::user_credentials;
+
+        let mut connection = self.conn()?;
+
+        let credential = diesel::update(user_credentials::table.find(id.get()))
+            .set((
+                user_credentials::passkey.eq(passkey),
+                user_credentials::last_used_at.eq(now),
+            ))
+            .get_result::<DbUserCredential>(&mut connection)?;
+
+        Ok(credential.try_into()?)
+    }
+
+    fn delete_user_credential(
+        &self,
+        user_id: UserId,
+        id: UserCredentialId,
+    ) -> RepositoryResult<usize> {
+        use crate::schema::user_credentials;
+
+        let mut connection = self.conn()?;
+
+        let deleted = diesel::delete(
+            user_credentials::table
+                .filter(user_credentials::id.eq(id.get()))
+                .filter(user_credentials::user_id.eq(user_id.get())),
+        )
+        .execute(&mut connection)?;
+
+        Ok(deleted)
+    }
+}
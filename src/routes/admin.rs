@@ -1,29 +1,55 @@
 //! Administrative endpoints used to manage users, roles and hubs.
 
-use actix_web::{HttpResponse, Responder, post, web};
+use std::sync::Arc;
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web};
 use log::error;
 use pushkind_common::domain::auth::AuthenticatedUser;
 use pushkind_common::dto::mutation::{ApiMutationErrorDto, ApiMutationSuccessDto};
 use pushkind_common::services::errors::ServiceError;
+use pushkind_common::zmq::ZmqSender;
 
+use crate::domain::hub::HubRegistrationSettings;
+use crate::domain::role::RoleChange;
 use crate::dto::admin::UserModalData;
+use crate::dto::api::RoleChangeSummaryDto;
 
 use crate::dto::frontend::{AdminEditableUserDto, AdminUserModalBootstrap, RoleOptionDto};
+use crate::extractors::SessionUser;
 use crate::forms::main::{
-    AddHubForm, AddHubPayload, AddMenuForm, AddMenuPayload, AddRoleForm, AddRolePayload,
+    AddHubForm, AddHubPayload, AddMenuForm, AddMenuPayload, AddOAuthClientForm,
+    AddOAuthClientPayload, AddRoleForm, AddRolePayload, AddUserForm, AddUserPayload, BulkRoleForm,
+    BulkRolePayload, HubRegistrationSettingsForm, InviteUserForm, InviteUserPayload,
+    RoleParentsForm, RoleParentsPayload, ServiceAccountForm, ServiceAccountPayload,
+    SuspendUserForm, SuspendUserPayload, UpdateHubPolicyForm, UpdateRoleForm, UpdateRolePayload,
     UpdateUserForm, UpdateUserPayload,
 };
 use crate::repository::DieselRepository;
-use crate::routes::{MutationResource, mutation_error_response};
+use crate::routes::auth::base_url;
+use crate::routes::{MutationResource, client_info, mutation_error_response};
 use crate::services::admin as admin_service;
+use crate::services::invitation as invitation_service;
+use crate::services::oauth as oauth_service;
+use crate::services::service_account as service_account_service;
 
-/// Handles `POST /role/add` to create a new role and flash the outcome.
+/// Handles `POST /role/add` to create a new role with its permissions.
 #[post("/role/add")]
 pub async fn add_role(
-    web::Form(form): web::Form<AddRoleForm>,
-    current_user: AuthenticatedUser,
+    form: web::Bytes,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
+    let form: AddRoleForm = match serde_html_form::from_bytes(&form) {
+        Ok(form) => form,
+        Err(err) => {
+            log::error!("Failed to process form: {err}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Ошибка при обработке формы.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+    };
     let payload = match AddRolePayload::try_from(form) {
         Ok(payload) => payload,
         Err(error) => {
@@ -32,7 +58,12 @@ pub async fn add_role(
         }
     };
 
-    match admin_service::create_role(payload, &current_user, repo.get_ref()) {
+    match admin_service::create_role(
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Created().json(ApiMutationSuccessDto {
             message: "Роль добавлена.".to_string(),
             redirect_to: None,
@@ -44,20 +75,244 @@ pub async fn add_role(
     }
 }
 
+/// Renames a role or changes its description via
+/// `POST /role/update/{role_id}`.
+#[post("/role/update/{role_id}")]
+pub async fn update_role(
+    role_id: web::Path<i32>,
+    web::Form(form): web::Form<UpdateRoleForm>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let payload = match UpdateRolePayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            log::error!("Invalid role data: {error}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match admin_service::update_role(
+        role_id.into_inner(),
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Роль сохранена.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to update role: {err}");
+            mutation_error_response(MutationResource::Role, &err)
+        }
+    }
+}
+
+/// Replaces the parent roles of a role via `POST /role/parents/{role_id}`,
+/// sent with repeated `parents` fields.
+#[post("/role/parents/{role_id}")]
+pub async fn set_role_parents(
+    role_id: web::Path<i32>,
+    form: web::Bytes,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let form: RoleParentsForm = match serde_html_form::from_bytes(&form) {
+        Ok(form) => form,
+        Err(err) => {
+            log::error!("Failed to process form: {err}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Ошибка при обработке формы.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+    };
+    let payload = match RoleParentsPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            log::error!("Invalid role parents: {error}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match admin_service::set_role_parents(
+        role_id.into_inner(),
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Наследование роли сохранено.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to set role parents: {err}");
+            mutation_error_response(MutationResource::Role, &err)
+        }
+    }
+}
+
+/// Parses a bulk role form sent with repeated `user_ids` fields.
+fn bulk_role_payload(form: &web::Bytes) -> Result<BulkRolePayload, HttpResponse> {
+    let form: BulkRoleForm = serde_html_form::from_bytes(form).map_err(|err| {
+        log::error!("Failed to process form: {err}");
+        HttpResponse::BadRequest().json(ApiMutationErrorDto {
+            message: "Ошибка при обработке формы.".to_string(),
+            field_errors: Vec::new(),
+        })
+    })?;
+    BulkRolePayload::try_from(form).map_err(|error| {
+        log::error!("Invalid bulk role data: {error}");
+        HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error))
+    })
+}
+
+/// Applies a bulk role change and reports how many users it touched.
+fn change_role_for_users(
+    role_id: i32,
+    change: RoleChange,
+    form: &web::Bytes,
+    request: &HttpRequest,
+    current_user: &AuthenticatedUser,
+    repo: &DieselRepository,
+) -> HttpResponse {
+    let payload = match bulk_role_payload(form) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    match admin_service::change_role_for_users(
+        role_id,
+        change,
+        payload,
+        current_user,
+        &client_info(request),
+        repo,
+    ) {
+        Ok(summary) => {
+            let message = match change {
+                RoleChange::Add => "Роль назначена пользователям.",
+                RoleChange::Remove => "Роль снята с пользователей.",
+            };
+            HttpResponse::Ok().json(RoleChangeSummaryDto::new(message, summary))
+        }
+        Err(err) => {
+            log::error!("Failed to change role of users: {err}");
+            mutation_error_response(MutationResource::Role, &err)
+        }
+    }
+}
+
+/// Adds a role to many users via `POST /role/assign/{role_id}`, sent with
+/// repeated `user_ids` or `all_matching=true` and the `status`, `role` and
+/// `query` filters of `/api/v1/users`.
+#[post("/role/assign/{role_id}")]
+pub async fn assign_role(
+    role_id: web::Path<i32>,
+    form: web::Bytes,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    change_role_for_users(
+        role_id.into_inner(),
+        RoleChange::Add,
+        &form,
+        &request,
+        &current_user,
+        repo.get_ref(),
+    )
+}
+
+/// Removes a role from many users via `POST /role/unassign/{role_id}`, with
+/// the same form as [`assign_role`].
+#[post("/role/unassign/{role_id}")]
+pub async fn unassign_role(
+    role_id: web::Path<i32>,
+    form: web::Bytes,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    change_role_for_users(
+        role_id.into_inner(),
+        RoleChange::Remove,
+        &form,
+        &request,
+        &current_user,
+        repo.get_ref(),
+    )
+}
+
+/// Creates a user in the current hub via `POST /user/add`.
+#[post("/user/add")]
+pub async fn add_user(
+    form: web::Bytes,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let form: AddUserForm = match serde_html_form::from_bytes(&form) {
+        Ok(form) => form,
+        Err(err) => {
+            log::error!("Failed to process form: {err}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Ошибка при обработке формы.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+    };
+    let payload = match AddUserPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            log::error!("Invalid user data: {error}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match admin_service::create_user(
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Created().json(ApiMutationSuccessDto {
+            message: "Пользователь добавлен.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to add user: {err}");
+            mutation_error_response(MutationResource::User, &err)
+        }
+    }
+}
+
 /// Builds modal data for a user via `POST /user/modal/{user_id}`.
 #[post("/user/modal/{user_id}")]
 pub async fn user_modal(
     user_id: web::Path<i32>,
-    current_user: AuthenticatedUser,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let user_id = user_id.into_inner();
 
     match admin_service::user_modal_data(user_id, &current_user, repo.get_ref()) {
         Ok(data) => {
-            let UserModalData { user, roles } = data;
+            let UserModalData {
+                user,
+                roles,
+                inherited_roles,
+            } = data;
             HttpResponse::Ok().json(AdminUserModalBootstrap {
-                user: user.map(AdminEditableUserDto::from),
+                user: user.map(|user| AdminEditableUserDto {
+                    inherited_roles: inherited_roles.iter().map(|role| role.id.get()).collect(),
+                    ..AdminEditableUserDto::from(user)
+                }),
                 roles: roles.into_iter().map(RoleOptionDto::from).collect(),
             })
         }
@@ -73,12 +328,18 @@ pub async fn user_modal(
 #[post("/user/delete/{user_id}")]
 pub async fn delete_user(
     user_id: web::Path<i32>,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let target_id = user_id.into_inner();
 
-    match admin_service::delete_user_by_id(target_id, &current_user, repo.get_ref()) {
+    match admin_service::delete_user_by_id(
+        target_id,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
             message: "Пользователь удалён.".to_string(),
             redirect_to: None,
@@ -90,12 +351,125 @@ pub async fn delete_user(
     }
 }
 
+/// Restores a deleted user for `POST /user/restore/{user_id}`.
+#[post("/user/restore/{user_id}")]
+pub async fn restore_user(
+    user_id: web::Path<i32>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match admin_service::restore_user_by_id(
+        user_id.into_inner(),
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Пользователь восстановлен.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to restore user: {err}");
+            mutation_error_response(MutationResource::User, &err)
+        }
+    }
+}
+
+/// Marks the email address of a user as verified for
+/// `POST /user/verify/{user_id}`.
+#[post("/user/verify/{user_id}")]
+pub async fn verify_user_email(
+    user_id: web::Path<i32>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match admin_service::verify_user_email(
+        user_id.into_inner(),
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Адрес электронной почты подтверждён.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to verify user email: {err}");
+            mutation_error_response(MutationResource::User, &err)
+        }
+    }
+}
+
+/// Suspends a user and signs them out everywhere for
+/// `POST /user/suspend/{user_id}`.
+#[post("/user/suspend/{user_id}")]
+pub async fn suspend_user(
+    user_id: web::Path<i32>,
+    web::Form(form): web::Form<SuspendUserForm>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let payload = match SuspendUserPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            log::error!("Invalid suspension data: {error}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match admin_service::suspend_user_by_id(
+        user_id.into_inner(),
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Пользователь заблокирован.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to suspend user: {err}");
+            mutation_error_response(MutationResource::User, &err)
+        }
+    }
+}
+
+/// Lifts the suspension of a user for `POST /user/reactivate/{user_id}`.
+#[post("/user/reactivate/{user_id}")]
+pub async fn reactivate_user(
+    user_id: web::Path<i32>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match admin_service::reactivate_user_by_id(
+        user_id.into_inner(),
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Пользователь разблокирован.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to reactivate user: {err}");
+            mutation_error_response(MutationResource::User, &err)
+        }
+    }
+}
+
 /// Updates user data and role assignments from the admin form.
 #[post("/user/update/{user_id}")]
 pub async fn update_user(
     user_id: web::Path<i32>,
     form: web::Bytes,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let form: UpdateUserForm = match serde_html_form::from_bytes(&form) {
@@ -122,6 +496,7 @@ pub async fn update_user(
         target_id,
         payload,
         &current_user,
+        &client_info(&request),
         repo.get_ref(),
     ) {
         Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
@@ -135,11 +510,104 @@ pub async fn update_user(
     }
 }
 
+/// Handles `POST /hub/policy` to update the security policy of the current hub.
+#[post("/hub/policy")]
+pub async fn update_hub_policy(
+    web::Form(form): web::Form<UpdateHubPolicyForm>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match admin_service::update_hub_policy(
+        form.into(),
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Политика хаба сохранена.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to update hub policy: {err}");
+            mutation_error_response(MutationResource::Hub, &err)
+        }
+    }
+}
+
+/// Returns the registration settings of a hub via
+/// `GET /hub/{hub_id}/settings`.
+#[get("/hub/{hub_id}/settings")]
+pub async fn hub_registration_settings(
+    hub_id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match admin_service::hub_registration_settings(
+        hub_id.into_inner(),
+        &current_user,
+        repo.get_ref(),
+    ) {
+        Ok(settings) => HttpResponse::Ok().json(settings),
+        Err(err) => {
+            log::error!("Failed to load hub registration settings: {err}");
+            mutation_error_response(MutationResource::Hub, &err)
+        }
+    }
+}
+
+/// Replaces the registration settings of a hub via
+/// `POST /hub/{hub_id}/settings`, sent with repeated `default_roles` fields.
+#[post("/hub/{hub_id}/settings")]
+pub async fn update_hub_registration_settings(
+    hub_id: web::Path<i32>,
+    form: web::Bytes,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let form: HubRegistrationSettingsForm = match serde_html_form::from_bytes(&form) {
+        Ok(form) => form,
+        Err(err) => {
+            log::error!("Failed to process form: {err}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Ошибка при обработке формы.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+    };
+    let settings = match HubRegistrationSettings::try_from(form) {
+        Ok(settings) => settings,
+        Err(error) => {
+            log::error!("Invalid hub registration settings: {error}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match admin_service::update_hub_registration_settings(
+        hub_id.into_inner(),
+        settings,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Настройки регистрации сохранены.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to update hub registration settings: {err}");
+            mutation_error_response(MutationResource::Hub, &err)
+        }
+    }
+}
+
 /// Handles `POST /hub/add` to create a hub for the current tenant.
 #[post("/hub/add")]
 pub async fn add_hub(
     web::Form(form): web::Form<AddHubForm>,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let payload = match AddHubPayload::try_from(form) {
@@ -150,7 +618,12 @@ pub async fn add_hub(
         }
     };
 
-    match admin_service::create_hub(payload, &current_user, repo.get_ref()) {
+    match admin_service::create_hub(
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Created().json(ApiMutationSuccessDto {
             message: "Хаб добавлен.".to_string(),
             redirect_to: None,
@@ -166,12 +639,18 @@ pub async fn add_hub(
 #[post("/role/delete/{role_id}")]
 pub async fn delete_role(
     role_id: web::Path<i32>,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let role_id = role_id.into_inner();
 
-    match admin_service::delete_role_by_id(role_id, &current_user, repo.get_ref()) {
+    match admin_service::delete_role_by_id(
+        role_id,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
             message: "Роль удалена.".to_string(),
             redirect_to: None,
@@ -187,12 +666,18 @@ pub async fn delete_role(
 #[post("/hub/delete/{hub_id}")]
 pub async fn delete_hub(
     hub_id: web::Path<i32>,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let hub_id = hub_id.into_inner();
 
-    match admin_service::delete_hub_by_id(hub_id, &current_user, repo.get_ref()) {
+    match admin_service::delete_hub_by_id(
+        hub_id,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
             message: "Хаб удалён.".to_string(),
             redirect_to: None,
@@ -204,11 +689,37 @@ pub async fn delete_hub(
     }
 }
 
+/// Restores a deleted hub with its users for `POST /hub/restore/{hub_id}`.
+#[post("/hub/restore/{hub_id}")]
+pub async fn restore_hub(
+    hub_id: web::Path<i32>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match admin_service::restore_hub_by_id(
+        hub_id.into_inner(),
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Хаб восстановлен.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to restore hub: {err}");
+            mutation_error_response(MutationResource::Hub, &err)
+        }
+    }
+}
+
 /// Handles `POST /menu/add` to create a menu entry.
 #[post("/menu/add")]
 pub async fn add_menu(
     web::Form(form): web::Form<AddMenuForm>,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let payload = match AddMenuPayload::try_from(form) {
@@ -219,7 +730,12 @@ pub async fn add_menu(
         }
     };
 
-    match admin_service::create_menu(payload, &current_user, repo.get_ref()) {
+    match admin_service::create_menu(
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Created().json(ApiMutationSuccessDto {
             message: "Меню добавлено.".to_string(),
             redirect_to: None,
@@ -235,11 +751,17 @@ pub async fn add_menu(
 #[post("/menu/delete/{menu_id}")]
 pub async fn delete_menu(
     menu_id: web::Path<i32>,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let menu_id = menu_id.into_inner();
-    match admin_service::delete_menu_by_id(menu_id, &current_user, repo.get_ref()) {
+    match admin_service::delete_menu_by_id(
+        menu_id,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
             message: "Меню удалено.".to_string(),
             redirect_to: None,
@@ -250,3 +772,257 @@ pub async fn delete_menu(
         }
     }
 }
+
+/// Registers an OpenID Connect client via `POST /oauth-client/add`.
+///
+/// The response carries the client credentials; the secret of a
+/// confidential client is not retrievable later.
+#[post("/oauth-client/add")]
+pub async fn add_oauth_client(
+    web::Form(form): web::Form<AddOAuthClientForm>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let payload = match AddOAuthClientPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            log::error!("Invalid OAuth client data: {error}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match oauth_service::create_client(payload, &current_user, repo.get_ref()) {
+        Ok(credentials) => HttpResponse::Created().json(credentials),
+        Err(err) => {
+            log::error!("Failed to add OAuth client: {err}");
+            mutation_error_response(MutationResource::OAuthClient, &err)
+        }
+    }
+}
+
+/// Removes an OpenID Connect client via `POST /oauth-client/delete/{id}`.
+#[post("/oauth-client/delete/{id}")]
+pub async fn delete_oauth_client(
+    id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match oauth_service::delete_client(id.into_inner(), &current_user, repo.get_ref()) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Клиент удалён.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to delete OAuth client: {err}");
+            mutation_error_response(MutationResource::OAuthClient, &err)
+        }
+    }
+}
+
+/// Parses a service account form sent with repeated `roles` fields.
+fn service_account_payload(form: &web::Bytes) -> Result<ServiceAccountPayload, HttpResponse> {
+    let form: ServiceAccountForm = serde_html_form::from_bytes(form).map_err(|err| {
+        log::error!("Failed to process form: {err}");
+        HttpResponse::BadRequest().json(ApiMutationErrorDto {
+            message: "Ошибка при обработке формы.".to_string(),
+            field_errors: Vec::new(),
+        })
+    })?;
+    ServiceAccountPayload::try_from(form).map_err(|error| {
+        log::error!("Invalid service account data: {error}");
+        HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error))
+    })
+}
+
+/// Creates a service account in the current hub via
+/// `POST /service-account/add`.
+///
+/// The response carries the credentials; the secret is not retrievable
+/// later.
+#[post("/service-account/add")]
+pub async fn add_service_account(
+    form: web::Bytes,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let payload = match service_account_payload(&form) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    match service_account_service::create_service_account(payload, &current_user, repo.get_ref()) {
+        Ok(credentials) => HttpResponse::Created().json(credentials),
+        Err(err) => {
+            log::error!("Failed to add service account: {err}");
+            mutation_error_response(MutationResource::ServiceAccount, &err)
+        }
+    }
+}
+
+/// Renames a service account and replaces its roles via
+/// `POST /service-account/update/{id}`.
+#[post("/service-account/update/{id}")]
+pub async fn update_service_account(
+    id: web::Path<i32>,
+    form: web::Bytes,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let payload = match service_account_payload(&form) {
+        Ok(payload) => payload,
+        Err(response) => return response,
+    };
+
+    match service_account_service::update_service_account(
+        id.into_inner(),
+        payload,
+        &current_user,
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Сервисный аккаунт изменён.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to update service account: {err}");
+            mutation_error_response(MutationResource::ServiceAccount, &err)
+        }
+    }
+}
+
+/// Issues a new secret for a service account via
+/// `POST /service-account/rotate/{id}`, revoking its access tokens.
+#[post("/service-account/rotate/{id}")]
+pub async fn rotate_service_account_secret(
+    id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match service_account_service::rotate_secret(id.into_inner(), &current_user, repo.get_ref()) {
+        Ok(credentials) => HttpResponse::Ok().json(credentials),
+        Err(err) => {
+            log::error!("Failed to rotate service account secret: {err}");
+            mutation_error_response(MutationResource::ServiceAccount, &err)
+        }
+    }
+}
+
+/// Removes a service account via `POST /service-account/delete/{id}`.
+#[post("/service-account/delete/{id}")]
+pub async fn delete_service_account(
+    id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match service_account_service::delete_service_account(
+        id.into_inner(),
+        &current_user,
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Сервисный аккаунт удалён.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to delete service account: {err}");
+            mutation_error_response(MutationResource::ServiceAccount, &err)
+        }
+    }
+}
+
+/// Invites a user to the current hub via `POST /user/invite` and emails the
+/// accept link.
+#[post("/user/invite")]
+pub async fn invite_user(
+    form: web::Bytes,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    zmq_sender: web::Data<Arc<ZmqSender>>,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let form: InviteUserForm = match serde_html_form::from_bytes(&form) {
+        Ok(form) => form,
+        Err(err) => {
+            log::error!("Failed to process form: {err}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Ошибка при обработке формы.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+    };
+    let payload = match InviteUserPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            log::error!("Invalid invitation data: {error}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match invitation_service::invite_user(
+        payload,
+        &base_url(&request),
+        zmq_sender.get_ref().as_ref(),
+        &current_user,
+        repo.get_ref(),
+    )
+    .await
+    {
+        Ok(_) => HttpResponse::Created().json(ApiMutationSuccessDto {
+            message: "Приглашение отправлено.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to invite user: {err}");
+            mutation_error_response(MutationResource::Invitation, &err)
+        }
+    }
+}
+
+/// Sends a pending invitation again with a new link via
+/// `POST /invitation/resend/{id}`.
+#[post("/invitation/resend/{id}")]
+pub async fn resend_invitation(
+    id: web::Path<i32>,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
+    zmq_sender: web::Data<Arc<ZmqSender>>,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match invitation_service::resend_invitation(
+        id.into_inner(),
+        &base_url(&request),
+        zmq_sender.get_ref().as_ref(),
+        &current_user,
+        repo.get_ref(),
+    )
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Приглашение отправлено повторно.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to resend invitation: {err}");
+            mutation_error_response(MutationResource::Invitation, &err)
+        }
+    }
+}
+
+/// Withdraws a pending invitation via `POST /invitation/cancel/{id}`.
+#[post("/invitation/cancel/{id}")]
+pub async fn cancel_invitation(
+    id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match invitation_service::cancel_invitation(id.into_inner(), &current_user, repo.get_ref()) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Приглашение отменено.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to cancel invitation: {err}");
+            mutation_error_response(MutationResource::Invitation, &err)
+        }
+    }
+}
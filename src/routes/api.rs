@@ -2,13 +2,20 @@
 
 use actix_web::{HttpResponse, Responder, get, web};
 use log::error;
-use pushkind_common::domain::auth::AuthenticatedUser;
 use pushkind_common::services::errors::ServiceError;
 use serde::Deserialize;
 
-use crate::dto::api::ApiV1UsersQueryParams;
+use crate::dto::api::{ApiV1AuditQueryParams, ApiV1UsersQueryParams};
+use crate::extractors::SessionUser;
+use crate::models::config::AppConfig;
 use crate::repository::DieselRepository;
 use crate::services::api as api_service;
+use crate::services::audit as audit_service;
+use crate::services::invitation as invitation_service;
+use crate::services::jwt::{JwtKeys, SessionClaims};
+use crate::services::personal_token as personal_token_service;
+use crate::services::session as session_service;
+use crate::services::webauthn as webauthn_service;
 
 #[derive(Deserialize)]
 struct ApiV1IdParams {
@@ -19,7 +26,7 @@ struct ApiV1IdParams {
 #[get("/v1/id")]
 pub async fn api_v1_id(
     params: web::Query<ApiV1IdParams>,
-    current_user: AuthenticatedUser,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     match api_service::get_user_by_optional_id(params.id, current_user, repo.get_ref()) {
@@ -47,7 +54,7 @@ pub async fn api_v1_hubs(repo: web::Data<DieselRepository>) -> impl Responder {
 /// Returns shared shell data for React-owned auth pages via `GET /v1/iam`.
 #[get("/v1/iam")]
 pub async fn api_v1_iam(
-    current_user: AuthenticatedUser,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     match api_service::get_shell_data(current_user, repo.get_ref()) {
@@ -64,7 +71,7 @@ pub async fn api_v1_iam(
 #[get("/v1/hubs/{hub_id}/menu-items")]
 pub async fn api_v1_hub_menu_items(
     hub_id: web::Path<i32>,
-    current_user: AuthenticatedUser,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     match api_service::list_hub_menu_items(hub_id.into_inner(), &current_user, repo.get_ref()) {
@@ -80,7 +87,7 @@ pub async fn api_v1_hub_menu_items(
 /// Returns admin-only dashboard collections via `GET /v1/admin/dashboard`.
 #[get("/v1/admin/dashboard")]
 pub async fn api_v1_admin_dashboard(
-    current_user: AuthenticatedUser,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     match api_service::get_admin_dashboard_data(&current_user, repo.get_ref()) {
@@ -93,20 +100,172 @@ pub async fn api_v1_admin_dashboard(
     }
 }
 
+/// Lists the users of the admin's hub holding a role via
+/// `GET /v1/admin/roles/{role_id}/members`.
+#[get("/v1/admin/roles/{role_id}/members")]
+pub async fn api_v1_admin_role_members(
+    role_id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match api_service::list_role_members(role_id.into_inner(), &current_user, repo.get_ref()) {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err(ServiceError::Unauthorized) => HttpResponse::Forbidden().finish(),
+        Err(ServiceError::NotFound) => HttpResponse::NotFound().finish(),
+        Err(e) => {
+            error!("Failed to list role members: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Reports which key signed each live session of the admin's hub via
+/// `GET /v1/admin/sessions`.
+#[get("/v1/admin/sessions")]
+pub async fn api_v1_admin_sessions(
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    match api_service::get_admin_sessions(&current_user, jwt_keys.get_ref(), repo.get_ref()) {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(ServiceError::Unauthorized) => HttpResponse::Forbidden().finish(),
+        Err(e) => {
+            error!("Failed to list admin sessions: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the deleted users of the admin's hub and the deleted hubs via
+/// `GET /v1/admin/deleted`.
+#[get("/v1/admin/deleted")]
+pub async fn api_v1_admin_deleted(
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+    app_config: web::Data<AppConfig>,
+) -> impl Responder {
+    match api_service::get_admin_deleted(&current_user, &app_config.retention, repo.get_ref()) {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(ServiceError::Unauthorized) => HttpResponse::Forbidden().finish(),
+        Err(e) => {
+            error!("Failed to list deleted users and hubs: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the audit events of the admin's hub with optional filters via
+/// `GET /v1/admin/audit`.
+#[get("/v1/admin/audit")]
+pub async fn api_v1_admin_audit(
+    params: web::Query<ApiV1AuditQueryParams>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match audit_service::list_audit_events(params.into_inner(), &current_user, repo.get_ref()) {
+        Ok(audit) => HttpResponse::Ok().json(audit),
+        Err(ServiceError::Unauthorized) => HttpResponse::Forbidden().finish(),
+        Err(e) => {
+            error!("Failed to list audit events: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the pending invitations of the admin's hub via
+/// `GET /v1/admin/invitations`.
+#[get("/v1/admin/invitations")]
+pub async fn api_v1_admin_invitations(
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match invitation_service::list_invitations(&current_user, repo.get_ref()) {
+        Ok(invitations) => HttpResponse::Ok().json(invitations),
+        Err(ServiceError::Unauthorized) => HttpResponse::Forbidden().finish(),
+        Err(e) => {
+            error!("Failed to list invitations: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the current user's passkeys via `GET /v1/passkeys`.
+#[get("/v1/passkeys")]
+pub async fn api_v1_passkeys(
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match webauthn_service::list_passkeys(&current_user, repo.get_ref()) {
+        Ok(passkeys) => HttpResponse::Ok().json(passkeys),
+        Err(e) => {
+            error!("Failed to list passkeys: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the current user's live sessions via `GET /v1/sessions`.
+#[get("/v1/sessions")]
+pub async fn api_v1_sessions(
+    claims: SessionClaims,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match session_service::list_sessions(&claims.user, claims.jti.as_deref(), repo.get_ref()) {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(e) => {
+            error!("Failed to list sessions: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Lists the current user's live personal access tokens via `GET /v1/tokens`.
+#[get("/v1/tokens")]
+pub async fn api_v1_tokens(
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match personal_token_service::list_tokens(&current_user, repo.get_ref()) {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => {
+            error!("Failed to list personal access tokens: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 /// Lists users for the current hub with optional filters via `GET /v1/users`.
 #[get("/v1/users")]
 pub async fn api_v1_users(
     params: web::Query<ApiV1UsersQueryParams>,
-    user: AuthenticatedUser,
+    SessionUser(user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let users = api_service::list_users(params.into_inner(), &user, repo.get_ref());
 
     match users {
         Ok(users) => HttpResponse::Ok().json(users),
+        Err(ServiceError::Unauthorized) => HttpResponse::Forbidden().finish(),
         Err(e) => {
             error!("Failed to list users: {e}");
             HttpResponse::InternalServerError().finish()
         }
     }
 }
+
+/// Lists the service accounts of the current hub via
+/// `GET /v1/users/service-accounts`.
+#[get("/v1/users/service-accounts")]
+pub async fn api_v1_service_accounts(
+    SessionUser(user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match api_service::list_service_accounts(&user, repo.get_ref()) {
+        Ok(accounts) => HttpResponse::Ok().json(accounts),
+        Err(e) => {
+            error!("Failed to list service accounts: {e}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
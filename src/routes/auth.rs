@@ -3,59 +3,182 @@
 use std::sync::Arc;
 
 use actix_identity::Identity;
+use actix_session::Session;
 use actix_web::{HttpMessage, HttpRequest, HttpResponse};
 use actix_web::{Responder, get, post, web};
-use pushkind_common::dto::mutation::{ApiMutationErrorDto, ApiMutationSuccessDto};
+use chrono::Utc;
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::dto::mutation::{
+    ApiFieldErrorDto, ApiMutationErrorDto, ApiMutationSuccessDto,
+};
 use pushkind_common::frontend::open_frontend_html;
-use pushkind_common::models::config::CommonServerConfig;
 use pushkind_common::routes::redirect;
 use pushkind_common::services::errors::ServiceError;
 use pushkind_common::zmq::ZmqSender;
 use serde::Deserialize;
+use url::form_urlencoded;
 
+use crate::dto::auth::{PendingLoginDto, TotpRecoveryCodesDto};
+use crate::extractors::SessionUser;
 use crate::forms::auth::{
+    AcceptInvitationForm, AcceptInvitationPayload, ChangePasswordForm, ChangePasswordPayload,
     LoginForm, LoginPayload, RecoverForm, RecoverPayload, RegisterForm, RegisterPayload,
+    ResendVerificationForm, ResendVerificationPayload, ResetPasswordForm, ResetPasswordPayload,
+    TwoFactorCodeForm, TwoFactorCodePayload,
 };
 use crate::models::config::AppConfig;
 use crate::repository::DieselRepository;
-use crate::routes::{MutationResource, is_valid_next, mutation_error_response};
-use crate::services::auth as auth_service;
+use crate::routes::{
+    MutationResource, client_info, is_valid_next, mutation_error_response,
+    registration_error_response,
+};
+use crate::services::auth::{self as auth_service, LoginError, LoginOutcome};
+use crate::services::invitation as invitation_service;
+use crate::services::jwt::JwtKeys;
+use crate::services::two_factor as two_factor_service;
+
+/// Session key holding a [`PendingLoginDto`] between the password and the
+/// second factor steps.
+const PENDING_LOGIN_SESSION_KEY: &str = "pending_login";
+/// Session key holding a [`PendingLoginDto`] until a user required to change
+/// the password has chosen a new one.
+const PENDING_PASSWORD_CHANGE_SESSION_KEY: &str = "pending_password_change";
 
 #[derive(Deserialize)]
-struct AuthQueryParams {
-    next: Option<String>,
+pub(crate) struct AuthQueryParams {
+    pub(crate) next: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct LoginTokenParams {
+struct VerifyEmailParams {
     token: String,
 }
 
-/// Reissues a session from a short-lived token via `GET /login`.
-#[get("/login")]
-pub async fn login_token(
-    query_params: web::Query<LoginTokenParams>,
-    request: HttpRequest,
-    repo: web::Data<DieselRepository>,
-    common_config: web::Data<CommonServerConfig>,
-) -> impl Responder {
-    let jwt = match auth_service::reissue_session_from_token(
-        &query_params.token,
-        7,
-        &common_config.secret,
-        repo.get_ref(),
-    ) {
-        Ok(jwt) => jwt,
-        Err(e) => {
-            log::error!("Failed to reissue session: {e}");
-            return redirect("/auth/signin");
+/// Returns `next` when it is a safe redirect target, `/` otherwise.
+pub(crate) fn success_redirect_url(next: Option<&str>, domain: &str) -> String {
+    next.filter(|next| !next.is_empty() && is_valid_next(next, domain))
+        .map(str::to_owned)
+        .unwrap_or_else(|| "/".to_string())
+}
+
+/// Builds the base URL of emailed links from the current request:
+/// `scheme://host`.
+pub(crate) fn base_url(request: &HttpRequest) -> String {
+    let conn_info = request.connection_info();
+    format!("{}://{}", conn_info.scheme(), conn_info.host())
+}
+
+/// Builds the second factor page URL that continues to `next` afterwards.
+fn second_factor_url(pending: &PendingLoginDto, next: &str) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    if pending.setup_required {
+        query.append_pair("mode", "setup");
+    }
+    query.append_pair("next", next);
+    format!("/auth/2fa?{}", query.finish())
+}
+
+/// Builds the password change page URL that continues to `next` afterwards.
+fn password_change_url(next: &str) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+    query.append_pair("next", next);
+    format!("/auth/password?{}", query.finish())
+}
+
+/// Returns the unexpired pending login stored under `key` in the session, if
+/// any.
+fn pending_login(session: &Session, key: &str) -> Option<PendingLoginDto> {
+    session
+        .get::<PendingLoginDto>(key)
+        .unwrap_or_else(|e| {
+            log::error!("Failed to read pending login: {e}");
+            None
+        })
+        .filter(|pending| pending.expires_at > Utc::now().timestamp())
+}
+
+/// Returns the user enrolling a second factor: the signed-in user, or a
+/// pending login that the hub policy sent to enrollment.
+fn enrollment_subject(
+    current_user: Option<&AuthenticatedUser>,
+    session: &Session,
+) -> Option<(i32, String, Option<PendingLoginDto>)> {
+    match current_user {
+        Some(user) => Some((user.sub.parse().ok()?, user.email.clone(), None)),
+        None => pending_login(session, PENDING_LOGIN_SESSION_KEY)
+            .filter(|pending| pending.setup_required)
+            .map(|pending| (pending.user_id, pending.email.clone(), Some(pending))),
+    }
+}
+
+fn invalid_code_response() -> HttpResponse {
+    HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+        message: "Неверный код подтверждения.".to_string(),
+        field_errors: Vec::new(),
+    })
+}
+
+fn expired_login_response() -> HttpResponse {
+    HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+        message: "Время на подтверждение входа истекло. Войдите снова.".to_string(),
+        field_errors: Vec::new(),
+    })
+}
+
+pub(crate) fn identity_error_response() -> HttpResponse {
+    HttpResponse::InternalServerError().json(ApiMutationErrorDto {
+        message: "Ошибка при аутентификации пользователя.".to_string(),
+        field_errors: Vec::new(),
+    })
+}
+
+/// Signs the user in, or parks the login in the session until the second
+/// factor or a new password is provided.
+pub(crate) fn login_outcome_response(
+    outcome: LoginOutcome,
+    message: &str,
+    next: &str,
+    request: &HttpRequest,
+    session: &Session,
+) -> HttpResponse {
+    match outcome {
+        LoginOutcome::Authenticated(jwt) => {
+            if let Err(e) = Identity::login(&request.extensions(), jwt.token) {
+                log::error!("Failed to login: {e}");
+                return identity_error_response();
+            }
+            HttpResponse::Ok().json(ApiMutationSuccessDto {
+                message: message.to_string(),
+                redirect_to: Some(next.to_string()),
+            })
+        }
+        LoginOutcome::SecondFactorRequired(pending) => {
+            if let Err(e) = session.insert(PENDING_LOGIN_SESSION_KEY, &pending) {
+                log::error!("Failed to store pending login: {e}");
+                return identity_error_response();
+            }
+            HttpResponse::Ok().json(ApiMutationSuccessDto {
+                message: if pending.setup_required {
+                    "Настройте двухфакторную аутентификацию."
+                } else {
+                    "Введите код подтверждения."
+                }
+                .to_string(),
+                redirect_to: Some(second_factor_url(&pending, next)),
+            })
+        }
+        LoginOutcome::PasswordChangeRequired(pending) => {
+            session.remove(PENDING_LOGIN_SESSION_KEY);
+            if let Err(e) = session.insert(PENDING_PASSWORD_CHANGE_SESSION_KEY, &pending) {
+                log::error!("Failed to store pending password change: {e}");
+                return identity_error_response();
+            }
+            HttpResponse::Ok().json(ApiMutationSuccessDto {
+                message: "Задайте новый пароль.".to_string(),
+                redirect_to: Some(password_change_url(next)),
+            })
         }
-    };
-    if let Err(e) = Identity::login(&request.extensions(), jwt.token) {
-        log::error!("Failed to login: {e}");
-        return redirect("/auth/signin");
     }
-    redirect("/")
 }
 
 /// Authenticates a user with credentials via `POST /login`.
@@ -64,16 +187,13 @@ pub async fn login(
     web::Form(form): web::Form<LoginForm>,
     query_params: web::Query<AuthQueryParams>,
     request: HttpRequest,
+    session: Session,
     repo: web::Data<DieselRepository>,
     server_config: web::Data<AppConfig>,
-    common_config: web::Data<CommonServerConfig>,
+    jwt_keys: web::Data<JwtKeys>,
 ) -> impl Responder {
-    let success_redirect_url = query_params
-        .next
-        .as_deref()
-        .filter(|next| !next.is_empty() && is_valid_next(next, &server_config.domain))
-        .map(str::to_owned)
-        .unwrap_or_else(|| "/".to_string());
+    let success_redirect_url =
+        success_redirect_url(query_params.next.as_deref(), &server_config.domain);
 
     let payload = match LoginPayload::try_from(form) {
         Ok(payload) => payload,
@@ -83,41 +203,42 @@ pub async fn login(
         }
     };
 
-    let jwt =
-        match auth_service::login_and_issue_token(payload, &common_config.secret, repo.get_ref()) {
-            Ok(jwt) => jwt,
-            Err(ServiceError::Unauthorized) => {
-                return HttpResponse::Unauthorized().json(ApiMutationErrorDto {
-                    message: "Неверный логин или пароль.".to_string(),
-                    field_errors: Vec::new(),
-                });
-            }
-            Err(err) => {
-                log::error!("Login error: {err}");
-                return mutation_error_response(MutationResource::Authentication, &err);
-            }
-        };
-
-    match Identity::login(&request.extensions(), jwt.token) {
-        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
-            message: "Авторизация выполнена.".to_string(),
-            redirect_to: Some(success_redirect_url),
-        }),
-        Err(e) => {
-            log::error!("Failed to login: {e}");
-
-            HttpResponse::InternalServerError().json(ApiMutationErrorDto {
-                message: "Ошибка при аутентификации пользователя.".to_string(),
+    let outcome = match auth_service::login_and_issue_token(
+        payload,
+        &client_info(&request),
+        &server_config.login_throttle,
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(outcome) => outcome,
+        Err(LoginError::Service(ServiceError::Unauthorized)) => {
+            return HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+                message: "Неверный логин или пароль.".to_string(),
                 field_errors: Vec::new(),
-            })
+            });
         }
-    }
+        Err(err) => {
+            log::error!("Login error: {err}");
+            return mutation_error_response(MutationResource::Authentication, &err);
+        }
+    };
+
+    login_outcome_response(
+        outcome,
+        "Авторизация выполнена.",
+        &success_redirect_url,
+        &request,
+        &session,
+    )
 }
 
-/// Registers a new user account via `POST /register`.
+/// Registers a new user account via `POST /register` and emails a link to
+/// verify its address.
 #[post("/register")]
 pub async fn register(
     web::Form(form): web::Form<RegisterForm>,
+    request: HttpRequest,
+    zmq_sender: web::Data<Arc<ZmqSender>>,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let payload = match RegisterPayload::try_from(form) {
@@ -128,14 +249,77 @@ pub async fn register(
         }
     };
 
-    match auth_service::register_user(payload, repo.get_ref()) {
-        Ok(_) => HttpResponse::Created().json(ApiMutationSuccessDto {
-            message: "Пользователь может войти.".to_string(),
-            redirect_to: Some("/auth/signin".to_string()),
+    let user_roles =
+        match auth_service::register_user(payload, &client_info(&request), repo.get_ref()) {
+            Ok(user_roles) => user_roles,
+            Err(err) => {
+                log::error!("Failed to create user: {err}");
+                return registration_error_response(&err);
+            }
+        };
+
+    // The account is kept when the email cannot be sent; the user can ask
+    // for a new link from the sign-in page.
+    if let Err(err) = auth_service::send_verification_email(
+        user_roles,
+        &base_url(&request),
+        zmq_sender.get_ref().as_ref(),
+        repo.get_ref(),
+    )
+    .await
+    {
+        log::error!("Failed to send verification email: {err}");
+    }
+
+    HttpResponse::Created().json(ApiMutationSuccessDto {
+        message: "Пользователь зарегистрирован. Подтвердите адрес по ссылке из письма.".to_string(),
+        redirect_to: Some("/auth/signin".to_string()),
+    })
+}
+
+/// Verifies an email address from a single-use link via `GET /verify` and
+/// continues to the sign-in page.
+#[get("/verify")]
+pub async fn verify_email(
+    query_params: web::Query<VerifyEmailParams>,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    if let Err(e) = auth_service::verify_email(&query_params.token, repo.get_ref()) {
+        log::error!("Failed to redeem verification token: {e}");
+    }
+    redirect("/auth/signin")
+}
+
+/// Sends a new email verification link via `POST /verify/resend`.
+#[post("/verify/resend")]
+pub async fn resend_verification(
+    web::Form(form): web::Form<ResendVerificationForm>,
+    request: HttpRequest,
+    zmq_sender: web::Data<Arc<ZmqSender>>,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let payload = match ResendVerificationPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match auth_service::resend_verification_email(
+        payload,
+        &base_url(&request),
+        zmq_sender.get_ref().as_ref(),
+        repo.get_ref(),
+    )
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Ссылка для подтверждения адреса выслана на электронную почту.".to_string(),
+            redirect_to: None,
         }),
         Err(err) => {
-            log::error!("Failed to create user: {err}");
-            mutation_error_response(MutationResource::UserRegistration, &err)
+            log::error!("Failed to resend verification email: {err}");
+            mutation_error_response(MutationResource::EmailVerification, &err)
         }
     }
 }
@@ -172,14 +356,187 @@ pub async fn signup_page(request: HttpRequest, user: Option<Identity>) -> impl R
     }
 }
 
-/// Sends a recovery email and issues a passwordless login link.
+/// Renders the password reset page via `GET /reset`.
+#[get("/reset")]
+pub async fn reset_page(request: HttpRequest) -> impl Responder {
+    match open_frontend_html("assets/dist/auth/reset.html").await {
+        Ok(file) => file.into_response(&request),
+        Err(err) => {
+            log::error!("Failed to open password reset frontend document: {err}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Sets a new password from a recovery token and signs the user in via
+/// `POST /reset`.
+#[post("/reset")]
+pub async fn reset_password(
+    web::Form(form): web::Form<ResetPasswordForm>,
+    request: HttpRequest,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    let payload = match ResetPasswordPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    let outcome = match auth_service::reset_password_and_issue_token(
+        payload,
+        &client_info(&request),
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(outcome) => outcome,
+        Err(ServiceError::Unauthorized) => {
+            return HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+                message: "Ссылка для восстановления недействительна или устарела.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+        Err(err) => {
+            log::error!("Failed to reset password: {err}");
+            return mutation_error_response(MutationResource::Recovery, &err);
+        }
+    };
+
+    login_outcome_response(outcome, "Пароль изменён.", "/", &request, &session)
+}
+
+/// Renders the required password change page via `GET /password`.
+#[get("/password")]
+pub async fn password_change_page(request: HttpRequest) -> impl Responder {
+    match open_frontend_html("assets/dist/auth/password.html").await {
+        Ok(file) => file.into_response(&request),
+        Err(err) => {
+            log::error!("Failed to open password change frontend document: {err}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Replaces the password of a user an admin required to change it and
+/// continues the login via `POST /password`.
+#[post("/password")]
+pub async fn change_password(
+    web::Form(form): web::Form<ChangePasswordForm>,
+    query_params: web::Query<AuthQueryParams>,
+    request: HttpRequest,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    server_config: web::Data<AppConfig>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    let success_redirect_url =
+        success_redirect_url(query_params.next.as_deref(), &server_config.domain);
+
+    let payload = match ChangePasswordPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    let Some(pending) = pending_login(&session, PENDING_PASSWORD_CHANGE_SESSION_KEY) else {
+        return expired_login_response();
+    };
+
+    let outcome = match auth_service::change_password_and_issue_token(
+        &pending,
+        &payload.password,
+        &client_info(&request),
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(outcome) => outcome,
+        Err(ServiceError::Unauthorized) => return expired_login_response(),
+        Err(ServiceError::Conflict) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Новый пароль должен отличаться от текущего.".to_string(),
+                field_errors: vec![ApiFieldErrorDto {
+                    field: "password".to_string(),
+                    message: "Новый пароль должен отличаться от текущего.".to_string(),
+                }],
+            });
+        }
+        Err(err) => {
+            log::error!("Failed to change password: {err}");
+            return mutation_error_response(MutationResource::Authentication, &err);
+        }
+    };
+
+    session.remove(PENDING_PASSWORD_CHANGE_SESSION_KEY);
+    login_outcome_response(
+        outcome,
+        "Пароль изменён.",
+        &success_redirect_url,
+        &request,
+        &session,
+    )
+}
+
+/// Renders the invitation accept page via `GET /invite`.
+#[get("/invite")]
+pub async fn invite_page(request: HttpRequest) -> impl Responder {
+    match open_frontend_html("assets/dist/auth/invite.html").await {
+        Ok(file) => file.into_response(&request),
+        Err(err) => {
+            log::error!("Failed to open invitation frontend document: {err}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Creates the account of an invitee with the chosen password and signs them
+/// in via `POST /invite`.
+#[post("/invite")]
+pub async fn accept_invitation(
+    web::Form(form): web::Form<AcceptInvitationForm>,
+    request: HttpRequest,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    let payload = match AcceptInvitationPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    let outcome = match invitation_service::accept_invitation_and_issue_token(
+        payload,
+        &client_info(&request),
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(outcome) => outcome,
+        Err(ServiceError::Unauthorized) => {
+            return HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+                message: "Приглашение недействительно или устарело.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+        Err(err) => {
+            log::error!("Failed to accept invitation: {err}");
+            return mutation_error_response(MutationResource::Invitation, &err);
+        }
+    };
+
+    login_outcome_response(outcome, "Приглашение принято.", "/", &request, &session)
+}
+
+/// Sends a recovery email with a single-use password reset link.
 #[post("/recover")]
 pub async fn recover_password(
     web::Form(form): web::Form<RecoverForm>,
     request: HttpRequest,
     zmq_sender: web::Data<Arc<ZmqSender>>,
     repo: web::Data<DieselRepository>,
-    common_config: web::Data<CommonServerConfig>,
 ) -> impl Responder {
     let payload = match RecoverPayload::try_from(form) {
         Ok(payload) => payload,
@@ -188,23 +545,17 @@ pub async fn recover_password(
         }
     };
 
-    // Build base URL from current request: schema://host
-    let base_url = {
-        let conn_info = request.connection_info();
-        format!("{}://{}", conn_info.scheme(), conn_info.host())
-    };
-
     match auth_service::send_recovery_email(
         payload,
-        &base_url,
+        &base_url(&request),
         zmq_sender.get_ref().as_ref(),
+        &client_info(&request),
         repo.get_ref(),
-        &common_config.secret,
     )
     .await
     {
         Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
-            message: "Ссылка для входа выслана на электронную почту.".to_string(),
+            message: "Ссылка для восстановления пароля выслана на электронную почту.".to_string(),
             redirect_to: None,
         }),
         Err(err) => {
@@ -213,3 +564,158 @@ pub async fn recover_password(
         }
     }
 }
+
+/// Renders the second factor page via `GET /2fa`.
+#[get("/2fa")]
+pub async fn two_factor_page(request: HttpRequest) -> impl Responder {
+    match open_frontend_html("assets/dist/auth/2fa.html").await {
+        Ok(file) => file.into_response(&request),
+        Err(err) => {
+            log::error!("Failed to open second factor frontend document: {err}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Completes a pending login with a TOTP or recovery code via
+/// `POST /2fa/verify`.
+#[post("/2fa/verify")]
+pub async fn verify_two_factor(
+    web::Form(form): web::Form<TwoFactorCodeForm>,
+    query_params: web::Query<AuthQueryParams>,
+    request: HttpRequest,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    server_config: web::Data<AppConfig>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    let success_redirect_url =
+        success_redirect_url(query_params.next.as_deref(), &server_config.domain);
+
+    let payload = match TwoFactorCodePayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    let Some(pending) = pending_login(&session, PENDING_LOGIN_SESSION_KEY) else {
+        return expired_login_response();
+    };
+
+    let jwt = match auth_service::complete_second_factor_login(
+        &pending,
+        &payload.code,
+        &client_info(&request),
+        &server_config.login_throttle,
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(jwt) => jwt,
+        Err(LoginError::Service(ServiceError::Unauthorized)) => return invalid_code_response(),
+        Err(err) => {
+            log::error!("Second factor error: {err}");
+            return mutation_error_response(MutationResource::Authentication, &err);
+        }
+    };
+
+    session.remove(PENDING_LOGIN_SESSION_KEY);
+    login_outcome_response(
+        LoginOutcome::Authenticated(jwt),
+        "Авторизация выполнена.",
+        &success_redirect_url,
+        &request,
+        &session,
+    )
+}
+
+/// Starts TOTP enrollment via `POST /2fa/setup` and returns the secret with
+/// its QR code.
+#[post("/2fa/setup")]
+pub async fn setup_two_factor(
+    current_user: Option<SessionUser>,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let Some((user_id, email, _)) = enrollment_subject(current_user.as_deref(), &session) else {
+        return expired_login_response();
+    };
+
+    match two_factor_service::begin_enrollment(user_id, &email, repo.get_ref()) {
+        Ok(enrollment) => HttpResponse::Ok().json(enrollment),
+        Err(err) => {
+            log::error!("Failed to start second factor enrollment: {err}");
+            mutation_error_response(MutationResource::TwoFactor, &err)
+        }
+    }
+}
+
+/// Confirms TOTP enrollment via `POST /2fa/enable` and returns one-time
+/// recovery codes. Pending logins sent to enrollment are signed in.
+#[post("/2fa/enable")]
+#[allow(clippy::too_many_arguments)]
+pub async fn enable_two_factor(
+    web::Form(form): web::Form<TwoFactorCodeForm>,
+    query_params: web::Query<AuthQueryParams>,
+    current_user: Option<SessionUser>,
+    request: HttpRequest,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    server_config: web::Data<AppConfig>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    let payload = match TwoFactorCodePayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    let Some((user_id, _, pending)) = enrollment_subject(current_user.as_deref(), &session) else {
+        return expired_login_response();
+    };
+
+    let recovery_codes =
+        match two_factor_service::confirm_enrollment(user_id, &payload.code, repo.get_ref()) {
+            Ok(recovery_codes) => recovery_codes,
+            Err(ServiceError::Unauthorized) => return invalid_code_response(),
+            Err(err) => {
+                log::error!("Failed to confirm second factor enrollment: {err}");
+                return mutation_error_response(MutationResource::TwoFactor, &err);
+            }
+        };
+
+    let redirect_to = match pending {
+        None => None,
+        Some(pending) => {
+            let jwt = match auth_service::complete_second_factor_setup(
+                &pending,
+                &client_info(&request),
+                jwt_keys.get_ref(),
+                repo.get_ref(),
+            ) {
+                Ok(jwt) => jwt,
+                Err(err) => {
+                    log::error!("Failed to sign in after enrollment: {err}");
+                    return mutation_error_response(MutationResource::Authentication, &err);
+                }
+            };
+            session.remove(PENDING_LOGIN_SESSION_KEY);
+            if let Err(e) = Identity::login(&request.extensions(), jwt.token) {
+                log::error!("Failed to login: {e}");
+                return identity_error_response();
+            }
+            Some(success_redirect_url(
+                query_params.next.as_deref(),
+                &server_config.domain,
+            ))
+        }
+    };
+
+    HttpResponse::Ok().json(TotpRecoveryCodesDto {
+        message: "Двухфакторная аутентификация включена. Сохраните коды восстановления."
+            .to_string(),
+        recovery_codes,
+        redirect_to,
+    })
+}
@@ -1,15 +1,25 @@
 //! General site routes and small API endpoints.
 
+use actix_identity::Identity;
 use actix_web::{HttpRequest, HttpResponse, Responder, get, post, web};
 use log::error;
 use pushkind_common::domain::auth::AuthenticatedUser;
 use pushkind_common::dto::mutation::{ApiMutationErrorDto, ApiMutationSuccessDto};
 use pushkind_common::frontend::open_frontend_html;
+use pushkind_common::services::errors::ServiceError;
 
-use crate::forms::main::{SaveUserForm, SaveUserPayload};
+use crate::extractors::SessionUser;
+use crate::forms::auth::{TwoFactorCodeForm, TwoFactorCodePayload};
+use crate::forms::main::{
+    AddPersonalTokenForm, AddPersonalTokenPayload, SaveUserForm, SaveUserPayload,
+};
 use crate::repository::DieselRepository;
-use crate::routes::{MutationResource, mutation_error_response};
+use crate::routes::{MutationResource, client_info, mutation_error_response};
 use crate::services::main as main_service;
+use crate::services::personal_token as personal_token_service;
+use crate::services::session as session_service;
+use crate::services::two_factor as two_factor_service;
+use crate::services::webauthn as webauthn_service;
 
 fn is_admin(user: &AuthenticatedUser) -> bool {
     user.roles
@@ -25,7 +35,7 @@ pub async fn health() -> impl Responder {
 
 /// Displays the main dashboard via `GET /` for the authenticated user.
 #[get("/")]
-pub async fn show_index(request: HttpRequest, user: AuthenticatedUser) -> impl Responder {
+pub async fn show_index(request: HttpRequest, SessionUser(user): SessionUser) -> impl Responder {
     let path = if is_admin(&user) {
         "assets/dist/app/index-admin.html"
     } else {
@@ -45,7 +55,8 @@ pub async fn show_index(request: HttpRequest, user: AuthenticatedUser) -> impl R
 #[post("/save")]
 pub async fn save_user(
     web::Form(form): web::Form<SaveUserForm>,
-    current_user: AuthenticatedUser,
+    request: HttpRequest,
+    SessionUser(current_user): SessionUser,
     repo: web::Data<DieselRepository>,
 ) -> impl Responder {
     let payload = match SaveUserPayload::try_from(form) {
@@ -56,7 +67,12 @@ pub async fn save_user(
         }
     };
 
-    match main_service::update_current_user(payload, &current_user, repo.get_ref()) {
+    match main_service::update_current_user(
+        payload,
+        &current_user,
+        &client_info(&request),
+        repo.get_ref(),
+    ) {
         Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
             message: "Параметры изменены.".to_string(),
             redirect_to: None,
@@ -67,3 +83,160 @@ pub async fn save_user(
         }
     }
 }
+
+/// Disables the current user's second factor via `POST /user/2fa/disable`.
+#[post("/2fa/disable")]
+pub async fn disable_two_factor(
+    web::Form(form): web::Form<TwoFactorCodeForm>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let payload = match TwoFactorCodePayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match two_factor_service::disable(&payload.code, &current_user, repo.get_ref()) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Двухфакторная аутентификация отключена.".to_string(),
+            redirect_to: None,
+        }),
+        Err(ServiceError::Unauthorized) => HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+            message: "Неверный код подтверждения.".to_string(),
+            field_errors: Vec::new(),
+        }),
+        Err(ServiceError::Conflict) => HttpResponse::Conflict().json(ApiMutationErrorDto {
+            message: "Политика хаба требует двухфакторную аутентификацию для администраторов."
+                .to_string(),
+            field_errors: Vec::new(),
+        }),
+        Err(err) => {
+            log::error!("Failed to disable second factor: {err}");
+            mutation_error_response(MutationResource::TwoFactor, &err)
+        }
+    }
+}
+
+/// Removes one of the current user's passkeys via
+/// `POST /user/passkeys/delete/{credential_id}`.
+#[post("/passkeys/delete/{credential_id}")]
+pub async fn delete_passkey(
+    credential_id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let credential_id = credential_id.into_inner();
+    match webauthn_service::delete_passkey(credential_id, &current_user, repo.get_ref()) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Ключ доступа удалён.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            log::error!("Failed to delete passkey: {err}");
+            mutation_error_response(MutationResource::Passkey, &err)
+        }
+    }
+}
+
+/// Revokes one of the current user's sessions via
+/// `POST /user/sessions/revoke/{session_id}`.
+#[post("/sessions/revoke/{session_id}")]
+pub async fn revoke_session(
+    session_id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let session_id = session_id.into_inner();
+    match session_service::revoke_session(session_id, &current_user, repo.get_ref()) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Сессия завершена.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            error!("Failed to revoke session: {err}");
+            mutation_error_response(MutationResource::Session, &err)
+        }
+    }
+}
+
+/// Revokes every session of the current user and signs out via
+/// `POST /user/sessions/revoke-all`.
+#[post("/sessions/revoke-all")]
+pub async fn revoke_all_sessions(
+    SessionUser(current_user): SessionUser,
+    identity: Identity,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    match session_service::revoke_all_sessions(&current_user, repo.get_ref()) {
+        Ok(_) => {
+            identity.logout();
+            HttpResponse::Ok().json(ApiMutationSuccessDto {
+                message: "Выполнен выход на всех устройствах.".to_string(),
+                redirect_to: Some("/auth/signin".to_string()),
+            })
+        }
+        Err(err) => {
+            error!("Failed to revoke sessions: {err}");
+            mutation_error_response(MutationResource::Session, &err)
+        }
+    }
+}
+
+/// Issues a personal access token for the current user via
+/// `POST /user/tokens/add`.
+///
+/// The response carries the token itself, which is not retrievable later.
+#[post("/tokens/add")]
+pub async fn add_personal_token(
+    form: web::Bytes,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let form: AddPersonalTokenForm = match serde_html_form::from_bytes(&form) {
+        Ok(form) => form,
+        Err(err) => {
+            log::error!("Failed to process form: {err}");
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Ошибка при обработке формы.".to_string(),
+                field_errors: Vec::new(),
+            });
+        }
+    };
+    let payload = match AddPersonalTokenPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    match personal_token_service::create_token(payload, &current_user, repo.get_ref()) {
+        Ok(created) => HttpResponse::Created().json(created),
+        Err(err) => {
+            error!("Failed to issue personal access token: {err}");
+            mutation_error_response(MutationResource::PersonalToken, &err)
+        }
+    }
+}
+
+/// Revokes one of the current user's personal access tokens via
+/// `POST /user/tokens/revoke/{token_id}`.
+#[post("/tokens/revoke/{token_id}")]
+pub async fn revoke_personal_token(
+    token_id: web::Path<i32>,
+    SessionUser(current_user): SessionUser,
+    repo: web::Data<DieselRepository>,
+) -> impl Responder {
+    let token_id = token_id.into_inner();
+    match personal_token_service::revoke_token(token_id, &current_user, repo.get_ref()) {
+        Ok(_) => HttpResponse::Ok().json(ApiMutationSuccessDto {
+            message: "Токен отозван.".to_string(),
+            redirect_to: None,
+        }),
+        Err(err) => {
+            error!("Failed to revoke personal access token: {err}");
+            mutation_error_response(MutationResource::PersonalToken, &err)
+        }
+    }
+}
@@ -1,26 +1,141 @@
 //! HTTP handlers and helpers.
-use actix_web::{HttpResponse, http::StatusCode};
-use pushkind_common::dto::mutation::ApiMutationErrorDto;
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::{HttpRequest, HttpResponse, http::StatusCode, http::header, web};
+use pushkind_common::dto::mutation::{ApiFieldErrorDto, ApiMutationErrorDto};
 use pushkind_common::services::errors::ServiceError;
 use url::Url;
 
+use crate::domain::session::ClientInfo;
+use crate::models::config::AppConfig;
+use crate::services::auth::{LoginError, RegistrationError};
+
 pub mod admin;
 pub mod api;
 pub mod auth;
 pub mod main;
+pub mod oauth;
+pub mod webauthn;
+pub mod well_known;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum MutationResource {
     Authentication,
+    EmailVerification,
     Hub,
+    Invitation,
     Menu,
+    OAuthClient,
+    Passkey,
+    PersonalToken,
     Recovery,
     Role,
+    ServiceAccount,
+    Session,
     Settings,
+    TwoFactor,
     User,
     UserRegistration,
 }
 
+/// Longest user agent recorded for a session.
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Returns the client IP used for login throttling, without the port.
+///
+/// Forwarding headers are only honoured when the peer is one of
+/// `AppConfig.trusted_proxies`. The forwarded chain is then walked from the
+/// nearest hop, and the first address that is not a trusted proxy is the
+/// client; entries further left are set by the client and ignored.
+fn client_ip(request: &HttpRequest) -> Option<String> {
+    let peer = request.peer_addr()?.ip();
+    let Some(config) = request.app_data::<web::Data<AppConfig>>() else {
+        return Some(peer.to_string());
+    };
+
+    let mut client = peer;
+    if config.trusted_proxies.contains(&client) {
+        for hop in forwarded_hops(request).iter().rev() {
+            let Some(ip) = parse_hop(hop) else {
+                break;
+            };
+            client = ip;
+            if !config.trusted_proxies.contains(&client) {
+                break;
+            }
+        }
+    }
+    Some(client.to_string())
+}
+
+/// Lists the addresses of the `X-Forwarded-For` header, or else of the `for`
+/// parameters of the `Forwarded` header, in header order: nearest hop last.
+fn forwarded_hops(request: &HttpRequest) -> Vec<String> {
+    let headers = request.headers();
+    let forwarded_for: Vec<String> = headers
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|hop| hop.trim().to_string())
+        .filter(|hop| !hop.is_empty())
+        .collect();
+    if !forwarded_for.is_empty() {
+        return forwarded_for;
+    }
+
+    headers
+        .get_all(header::FORWARDED)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (name, value) = pair.trim().split_once('=')?;
+                name.eq_ignore_ascii_case("for")
+                    .then(|| value.trim_matches('"').to_string())
+            })
+        })
+        .collect()
+}
+
+/// Parses a forwarded address, which may carry a port and IPv6 brackets.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    hop.parse::<IpAddr>()
+        .ok()
+        .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+        .or_else(|| {
+            hop.strip_prefix('[')
+                .and_then(|hop| hop.strip_suffix(']'))
+                .and_then(|hop| hop.parse().ok())
+        })
+}
+
+/// Describes the client of a sign-in request for the session registry.
+pub(crate) fn client_info(request: &HttpRequest) -> ClientInfo {
+    let user_agent = request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.chars().take(MAX_USER_AGENT_LEN).collect());
+    ClientInfo {
+        user_agent,
+        ip_address: client_ip(request),
+    }
+}
+
+/// Returns the value of an `Authorization` header with the given scheme.
+pub(crate) fn authorization(request: &HttpRequest, scheme: &str) -> Option<String> {
+    let value = request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?;
+    let (actual, credentials) = value.split_once(' ')?;
+    actual
+        .eq_ignore_ascii_case(scheme)
+        .then(|| credentials.trim().to_string())
+}
+
 pub(crate) fn is_valid_next(next: &str, domain: &str) -> bool {
     if next.starts_with("//") {
         return false;
@@ -55,9 +170,18 @@ fn mutation_error_dto(resource: MutationResource, err: &ServiceError) -> ApiMuta
         ServiceError::NotFound => ApiMutationErrorDto {
             message: match resource {
                 MutationResource::Hub => "Хаб не найден.",
+                MutationResource::Invitation => "Приглашение не найдено.",
                 MutationResource::Menu => "Меню не найдено.",
-                MutationResource::Recovery | MutationResource::User => "Пользователь не найден.",
+                MutationResource::OAuthClient => "Клиент не найден.",
+                MutationResource::Passkey => "Ключ доступа не найден.",
+                MutationResource::PersonalToken => "Токен не найден.",
+                MutationResource::EmailVerification
+                | MutationResource::Recovery
+                | MutationResource::User => "Пользователь не найден.",
                 MutationResource::Role => "Роль не найдена.",
+                MutationResource::ServiceAccount => "Сервисный аккаунт не найден.",
+                MutationResource::Session => "Сессия не найдена.",
+                MutationResource::TwoFactor => "Двухфакторная аутентификация не настроена.",
                 MutationResource::Authentication
                 | MutationResource::Settings
                 | MutationResource::UserRegistration => "Ресурс не найден.",
@@ -67,14 +191,27 @@ fn mutation_error_dto(resource: MutationResource, err: &ServiceError) -> ApiMuta
         },
         ServiceError::Conflict => ApiMutationErrorDto {
             message: match resource {
+                MutationResource::EmailVerification => "Адрес электронной почты уже подтверждён.",
+                MutationResource::Hub => "Хаб с таким названием уже существует.",
+                MutationResource::Invitation => {
+                    "Пользователь или приглашение с таким email уже существует."
+                }
+                MutationResource::Passkey => "Ключ доступа уже зарегистрирован.",
                 MutationResource::Role => "Роль уже существует.",
-                MutationResource::UserRegistration => "Пользователь с таким email уже существует.",
+                MutationResource::ServiceAccount => {
+                    "Сервисный аккаунт с таким именем уже существует."
+                }
+                MutationResource::TwoFactor => "Двухфакторная аутентификация уже включена.",
+                MutationResource::User | MutationResource::UserRegistration => {
+                    "Пользователь с таким email уже существует."
+                }
                 MutationResource::Authentication
-                | MutationResource::Hub
                 | MutationResource::Menu
+                | MutationResource::OAuthClient
+                | MutationResource::PersonalToken
                 | MutationResource::Recovery
-                | MutationResource::Settings
-                | MutationResource::User => "Конфликт данных.",
+                | MutationResource::Session
+                | MutationResource::Settings => "Конфликт данных.",
             }
             .to_string(),
             field_errors: Vec::new(),
@@ -86,11 +223,87 @@ fn mutation_error_dto(resource: MutationResource, err: &ServiceError) -> ApiMuta
     }
 }
 
-pub(crate) fn mutation_error_response(
+/// Failure rendered by [`mutation_error_response`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum MutationError<'a> {
+    Service(&'a ServiceError),
+    /// Too many attempts; answered with `429 Too Many Requests` and a
+    /// `Retry-After` header.
+    Throttled {
+        retry_after_seconds: i64,
+    },
+    /// The hub requires a verified address; answered with `403 Forbidden`.
+    EmailNotVerified,
+}
+
+impl<'a> From<&'a ServiceError> for MutationError<'a> {
+    fn from(err: &'a ServiceError) -> Self {
+        Self::Service(err)
+    }
+}
+
+impl<'a> From<&'a LoginError> for MutationError<'a> {
+    fn from(err: &'a LoginError) -> Self {
+        match err {
+            LoginError::Throttled {
+                retry_after_seconds,
+            } => Self::Throttled {
+                retry_after_seconds: *retry_after_seconds,
+            },
+            LoginError::EmailNotVerified => Self::EmailNotVerified,
+            LoginError::Service(err) => Self::Service(err),
+        }
+    }
+}
+
+pub(crate) fn mutation_error_response<'a>(
     resource: MutationResource,
-    err: &ServiceError,
+    err: impl Into<MutationError<'a>>,
 ) -> HttpResponse {
-    HttpResponse::build(mutation_error_status(err)).json(mutation_error_dto(resource, err))
+    match err.into() {
+        MutationError::Service(err) => {
+            HttpResponse::build(mutation_error_status(err)).json(mutation_error_dto(resource, err))
+        }
+        MutationError::Throttled {
+            retry_after_seconds,
+        } => HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, retry_after_seconds.to_string()))
+            .json(ApiMutationErrorDto {
+                message: "Слишком много попыток входа. Попробуйте позже.".to_string(),
+                field_errors: Vec::new(),
+            }),
+        MutationError::EmailNotVerified => HttpResponse::Forbidden().json(ApiMutationErrorDto {
+            message: "Подтвердите адрес электронной почты по ссылке из письма.".to_string(),
+            field_errors: Vec::new(),
+        }),
+    }
+}
+
+/// Maps a registration failure to a JSON response, using `403 Forbidden`
+/// for hubs that do not accept self-registration.
+pub(crate) fn registration_error_response(err: &RegistrationError) -> HttpResponse {
+    match err {
+        RegistrationError::Closed => HttpResponse::Forbidden().json(ApiMutationErrorDto {
+            message: "Регистрация в этом хабе закрыта.".to_string(),
+            field_errors: Vec::new(),
+        }),
+        RegistrationError::InviteOnly => HttpResponse::Forbidden().json(ApiMutationErrorDto {
+            message: "Регистрация в этом хабе возможна только по приглашению.".to_string(),
+            field_errors: Vec::new(),
+        }),
+        RegistrationError::EmailDomainNotAllowed => {
+            HttpResponse::BadRequest().json(ApiMutationErrorDto {
+                message: "Ошибка валидации формы.".to_string(),
+                field_errors: vec![ApiFieldErrorDto {
+                    field: "email".to_string(),
+                    message: "Регистрация с этим доменом электронной почты недоступна.".to_string(),
+                }],
+            })
+        }
+        RegistrationError::Service(err) => {
+            mutation_error_response(MutationResource::UserRegistration, err)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -204,4 +417,111 @@ mod tests {
             "Роль уже существует."
         );
     }
+
+    #[test]
+    fn mutation_error_response_uses_too_many_requests_for_throttled_logins() {
+        let err = LoginError::Throttled {
+            retry_after_seconds: 42,
+        };
+        let response = mutation_error_response(MutationResource::Authentication, &err);
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()),
+            Some("42")
+        );
+    }
+
+    #[test]
+    fn mutation_error_response_forbids_unverified_addresses() {
+        let response = mutation_error_response(
+            MutationResource::Authentication,
+            &LoginError::EmailNotVerified,
+        );
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn registration_error_response_forbids_closed_hubs() {
+        for err in [RegistrationError::Closed, RegistrationError::InviteOnly] {
+            let response = registration_error_response(&err);
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+        let response = registration_error_response(&RegistrationError::EmailDomainNotAllowed);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn mutation_error_response_unwraps_login_service_errors() {
+        let err = LoginError::Service(ServiceError::Internal);
+        let response = mutation_error_response(MutationResource::Authentication, &err);
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    fn app_config(trusted_proxies: Vec<std::net::IpAddr>) -> AppConfig {
+        AppConfig {
+            domain: "example.com".into(),
+            database_url: "app.db".into(),
+            zmq_emailer_pub: "tcp://127.0.0.1:5557".into(),
+            secret: "secret".into(),
+            trusted_proxies,
+            login_throttle: Default::default(),
+            session_cookie: Default::default(),
+            jwt: Default::default(),
+            oidc: Default::default(),
+            retention: Default::default(),
+        }
+    }
+
+    #[test]
+    fn client_ip_ignores_forwarded_headers_from_untrusted_peers() {
+        let request = actix_web::test::TestRequest::default()
+            .peer_addr("203.0.113.7:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "198.51.100.1"))
+            .app_data(web::Data::new(app_config(Vec::new())))
+            .to_http_request();
+
+        assert_eq!(client_ip(&request).as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn client_ip_reads_forwarded_headers_from_trusted_proxies() {
+        let request = actix_web::test::TestRequest::default()
+            .peer_addr("127.0.0.1:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "198.51.100.1"))
+            .app_data(web::Data::new(app_config(vec![
+                "127.0.0.1".parse().unwrap(),
+            ])))
+            .to_http_request();
+
+        assert_eq!(client_ip(&request).as_deref(), Some("198.51.100.1"));
+    }
+
+    #[test]
+    fn client_ip_ignores_spoofed_leftmost_forwarded_entries() {
+        let config = || {
+            web::Data::new(app_config(vec![
+                "127.0.0.1".parse().unwrap(),
+                "10.0.0.2".parse().unwrap(),
+            ]))
+        };
+        let request = actix_web::test::TestRequest::default()
+            .peer_addr("127.0.0.1:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "192.0.2.99, 198.51.100.1, 10.0.0.2"))
+            .app_data(config())
+            .to_http_request();
+        assert_eq!(client_ip(&request).as_deref(), Some("198.51.100.1"));
+
+        let request = actix_web::test::TestRequest::default()
+            .peer_addr("127.0.0.1:4000".parse().unwrap())
+            .insert_header((
+                "Forwarded",
+                "for=192.0.2.99, for=\"[2001:db8::1]:4711\";proto=https",
+            ))
+            .app_data(config())
+            .to_http_request();
+        assert_eq!(client_ip(&request).as_deref(), Some("2001:db8::1"));
+    }
 }
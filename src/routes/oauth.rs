@@ -0,0 +1,232 @@
+//! OpenID Connect authorization, token, userinfo, introspection, and
+//! revocation endpoints.
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, http::header, post, route, web};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use pushkind_common::services::errors::ServiceResult;
+use serde::Serialize;
+use url::form_urlencoded;
+
+use crate::dto::oauth::OAuthErrorDto;
+use crate::extractors::SessionUser;
+use crate::forms::oauth::{AuthorizeForm, TokenForm, TokenLookupForm};
+use crate::models::config::AppConfig;
+use crate::repository::DieselRepository;
+use crate::routes::authorization;
+use crate::services::jwt::JwtKeys;
+use crate::services::oauth::{self as oauth_service, OAuthError};
+use crate::services::service_account as service_account_service;
+
+/// Issuer of ID tokens: the configured one or the origin of the request.
+pub(crate) fn issuer(config: &AppConfig, request: &HttpRequest) -> String {
+    match &config.oidc.issuer {
+        Some(issuer) => issuer.trim_end_matches('/').to_string(),
+        None => {
+            let conn_info = request.connection_info();
+            format!("{}://{}", conn_info.scheme(), conn_info.host())
+        }
+    }
+}
+
+/// Client id and secret sent with `client_secret_basic`.
+fn basic_credentials(request: &HttpRequest) -> Option<(String, String)> {
+    let decoded = STANDARD
+        .decode(authorization(request, "Basic")?)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())?;
+    let (client_id, client_secret) = decoded.split_once(':')?;
+    Some((client_id.to_string(), client_secret.to_string()))
+}
+
+fn oauth_error_response(err: &OAuthError) -> HttpResponse {
+    let mut response = match err {
+        OAuthError::InvalidClient => HttpResponse::Unauthorized(),
+        OAuthError::InvalidToken => {
+            let mut response = HttpResponse::Unauthorized();
+            response.insert_header((header::WWW_AUTHENTICATE, "Bearer error=\"invalid_token\""));
+            response
+        }
+        OAuthError::Service(_) => HttpResponse::InternalServerError(),
+        _ => HttpResponse::BadRequest(),
+    };
+    response
+        .insert_header((header::CACHE_CONTROL, "no-store"))
+        .json(OAuthErrorDto::from(err))
+}
+
+fn redirect(location: ServiceResult<String>) -> HttpResponse {
+    match location {
+        Ok(location) => HttpResponse::Found()
+            .insert_header((header::LOCATION, location))
+            .finish(),
+        Err(err) => oauth_error_response(&err.into()),
+    }
+}
+
+fn token_response(tokens: Result<impl Serialize, OAuthError>) -> HttpResponse {
+    match tokens {
+        Ok(tokens) => HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .json(tokens),
+        Err(err) => {
+            match &err {
+                OAuthError::Service(_) => log::error!("Failed to issue tokens: {err}"),
+                _ => log::warn!("Rejected token request: {err}"),
+            }
+            oauth_error_response(&err)
+        }
+    }
+}
+
+/// Starts the authorization code flow via `GET /oauth/authorize`.
+///
+/// Anonymous users are sent to the sign-in page and come back here once
+/// signed in. Requests with an unknown client or redirect URI, and every
+/// request while ID tokens cannot be issued, are answered directly; every
+/// other outcome is redirected to the client.
+#[get("/authorize")]
+pub async fn authorize(
+    web::Query(form): web::Query<AuthorizeForm>,
+    session_user: Option<SessionUser>,
+    request: HttpRequest,
+    repo: web::Data<DieselRepository>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    if !oauth_service::id_tokens_supported(jwt_keys.get_ref()) {
+        log::warn!("Rejected authorization request: the active JWT key is HS256");
+        return oauth_error_response(&OAuthError::UnsupportedResponseType);
+    }
+
+    let client = match oauth_service::find_client(&form, repo.get_ref()) {
+        Ok(client) => client,
+        Err(err) => {
+            log::warn!("Rejected authorization request: {err}");
+            return oauth_error_response(&err);
+        }
+    };
+
+    let Some(SessionUser(current_user)) = session_user else {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.append_pair("next", &request.uri().to_string());
+        return redirect(Ok(format!("/auth/signin?{}", query.finish())));
+    };
+
+    let code = oauth_service::authorize(&form, &client, &current_user, repo.get_ref());
+    if let Err(OAuthError::Service(err)) = &code {
+        log::error!("Failed to issue authorization code: {err}");
+    }
+    let mut params = match &code {
+        Ok(code) => vec![("code", code.as_str())],
+        Err(err) => vec![("error", err.code())],
+    };
+    if let Some(state) = form.state.as_deref() {
+        params.push(("state", state));
+    }
+    let redirect_uri = form.redirect_uri.as_deref().unwrap_or_default();
+    redirect(oauth_service::authorization_response_url(
+        redirect_uri,
+        &params,
+    ))
+}
+
+/// Issues tokens via `POST /oauth/token`: exchanges an authorization code,
+/// or the credentials of a service account with the client credentials
+/// grant.
+#[post("/token")]
+pub async fn token(
+    web::Form(form): web::Form<TokenForm>,
+    request: HttpRequest,
+    repo: web::Data<DieselRepository>,
+    server_config: web::Data<AppConfig>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    if form.grant_type.as_deref() == Some("client_credentials") {
+        return token_response(service_account_service::issue_token(
+            form,
+            basic_credentials(&request),
+            repo.get_ref(),
+        ));
+    }
+    token_response(oauth_service::exchange_code(
+        form,
+        basic_credentials(&request),
+        &issuer(&server_config, &request),
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ))
+}
+
+/// Returns the claims of the user behind a Bearer access token via
+/// `GET` or `POST /oauth/userinfo`.
+#[route("/userinfo", method = "GET", method = "POST")]
+pub async fn userinfo(request: HttpRequest, repo: web::Data<DieselRepository>) -> impl Responder {
+    let Some(access_token) = authorization(&request, "Bearer") else {
+        return oauth_error_response(&OAuthError::InvalidToken);
+    };
+
+    match oauth_service::userinfo(&access_token, repo.get_ref()) {
+        Ok(user) => HttpResponse::Ok().json(user),
+        Err(err) => {
+            if let OAuthError::Service(_) = err {
+                log::error!("Failed to load userinfo: {err}");
+            }
+            oauth_error_response(&err)
+        }
+    }
+}
+
+/// Reports whether an access token or session JWT is active via
+/// `POST /oauth/introspect`.
+#[post("/introspect")]
+pub async fn introspect(
+    web::Form(form): web::Form<TokenLookupForm>,
+    request: HttpRequest,
+    repo: web::Data<DieselRepository>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    match oauth_service::introspect(
+        &form,
+        basic_credentials(&request),
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(introspection) => HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .json(introspection),
+        Err(err) => {
+            match &err {
+                OAuthError::Service(_) => log::error!("Failed to introspect token: {err}"),
+                _ => log::warn!("Rejected introspection request: {err}"),
+            }
+            oauth_error_response(&err)
+        }
+    }
+}
+
+/// Revokes an access token or session JWT via `POST /oauth/revoke`.
+#[post("/revoke")]
+pub async fn revoke(
+    web::Form(form): web::Form<TokenLookupForm>,
+    request: HttpRequest,
+    repo: web::Data<DieselRepository>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    match oauth_service::revoke(
+        &form,
+        basic_credentials(&request),
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(()) => HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .finish(),
+        Err(err) => {
+            match &err {
+                OAuthError::Service(_) => log::error!("Failed to revoke token: {err}"),
+                _ => log::warn!("Rejected revocation request: {err}"),
+            }
+            oauth_error_response(&err)
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! WebAuthn passkey registration and sign-in endpoints.
+
+use actix_session::Session;
+use actix_web::{HttpRequest, HttpResponse, Responder, post, web};
+use pushkind_common::dto::mutation::{ApiMutationErrorDto, ApiMutationSuccessDto};
+use pushkind_common::services::errors::ServiceError;
+use webauthn_rs::prelude::{PublicKeyCredential, Webauthn};
+
+use crate::dto::auth::{PasskeyLoginStateDto, PasskeyRegistrationStateDto};
+use crate::extractors::SessionUser;
+use crate::forms::auth::{
+    PasskeyLoginForm, PasskeyLoginPayload, PasskeyRegistrationForm, PasskeyRegistrationPayload,
+};
+use crate::models::config::AppConfig;
+use crate::repository::DieselRepository;
+use crate::routes::auth::{
+    AuthQueryParams, identity_error_response, login_outcome_response, success_redirect_url,
+};
+use crate::routes::{MutationResource, client_info, mutation_error_response};
+use crate::services::auth::LoginError;
+use crate::services::jwt::JwtKeys;
+use crate::services::webauthn as webauthn_service;
+
+/// Session key holding a [`PasskeyRegistrationStateDto`].
+const PASSKEY_REGISTRATION_SESSION_KEY: &str = "passkey_registration";
+/// Session key holding a [`PasskeyLoginStateDto`].
+const PASSKEY_LOGIN_SESSION_KEY: &str = "passkey_login";
+
+fn expired_ceremony_response() -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiMutationErrorDto {
+        message: "Время на подтверждение ключа доступа истекло. Попробуйте снова.".to_string(),
+        field_errors: Vec::new(),
+    })
+}
+
+fn rejected_passkey_response() -> HttpResponse {
+    HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+        message: "Не удалось проверить ключ доступа.".to_string(),
+        field_errors: Vec::new(),
+    })
+}
+
+/// Starts passkey registration for the signed-in user via
+/// `POST /webauthn/register/start`.
+#[post("/webauthn/register/start")]
+pub async fn start_passkey_registration(
+    SessionUser(current_user): SessionUser,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    webauthn: web::Data<Webauthn>,
+) -> impl Responder {
+    let (challenge, state) = match webauthn_service::start_registration(
+        webauthn.get_ref(),
+        &current_user,
+        repo.get_ref(),
+    ) {
+        Ok(result) => result,
+        Err(err) => {
+            log::error!("Failed to start passkey registration: {err}");
+            return mutation_error_response(MutationResource::Passkey, &err);
+        }
+    };
+
+    if let Err(e) = session.insert(PASSKEY_REGISTRATION_SESSION_KEY, &state) {
+        log::error!("Failed to store passkey registration: {e}");
+        return identity_error_response();
+    }
+    HttpResponse::Ok().json(challenge)
+}
+
+/// Verifies the authenticator response and stores the passkey via
+/// `POST /webauthn/register/finish`.
+#[post("/webauthn/register/finish")]
+pub async fn finish_passkey_registration(
+    web::Json(form): web::Json<PasskeyRegistrationForm>,
+    SessionUser(current_user): SessionUser,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    webauthn: web::Data<Webauthn>,
+) -> impl Responder {
+    let payload = match PasskeyRegistrationPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    let state = session
+        .remove_as::<PasskeyRegistrationStateDto>(PASSKEY_REGISTRATION_SESSION_KEY)
+        .and_then(Result::ok);
+    let Some(state) = state else {
+        return expired_ceremony_response();
+    };
+
+    match webauthn_service::finish_registration(
+        webauthn.get_ref(),
+        payload,
+        &state,
+        &current_user,
+        repo.get_ref(),
+    ) {
+        Ok(_) => HttpResponse::Created().json(ApiMutationSuccessDto {
+            message: "Ключ доступа добавлен.".to_string(),
+            redirect_to: None,
+        }),
+        Err(ServiceError::Unauthorized) => rejected_passkey_response(),
+        Err(err) => {
+            log::error!("Failed to finish passkey registration: {err}");
+            mutation_error_response(MutationResource::Passkey, &err)
+        }
+    }
+}
+
+/// Starts a passkey sign-in for the account given by email and hub via
+/// `POST /webauthn/login/start`.
+#[post("/webauthn/login/start")]
+pub async fn start_passkey_login(
+    web::Form(form): web::Form<PasskeyLoginForm>,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    webauthn: web::Data<Webauthn>,
+) -> impl Responder {
+    let payload = match PasskeyLoginPayload::try_from(form) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(ApiMutationErrorDto::from(&error));
+        }
+    };
+
+    let (challenge, state) =
+        match webauthn_service::start_login(webauthn.get_ref(), payload, repo.get_ref()) {
+            Ok(result) => result,
+            Err(ServiceError::Unauthorized) => {
+                return HttpResponse::Unauthorized().json(ApiMutationErrorDto {
+                    message: "Для этого пользователя нет ключей доступа.".to_string(),
+                    field_errors: Vec::new(),
+                });
+            }
+            Err(err) => {
+                log::error!("Failed to start passkey login: {err}");
+                return mutation_error_response(MutationResource::Authentication, &err);
+            }
+        };
+
+    if let Err(e) = session.insert(PASSKEY_LOGIN_SESSION_KEY, &state) {
+        log::error!("Failed to store passkey login: {e}");
+        return identity_error_response();
+    }
+    HttpResponse::Ok().json(challenge)
+}
+
+/// Verifies a passkey assertion and signs the user in via
+/// `POST /webauthn/login/finish`.
+#[post("/webauthn/login/finish")]
+#[allow(clippy::too_many_arguments)]
+pub async fn finish_passkey_login(
+    web::Json(credential): web::Json<PublicKeyCredential>,
+    query_params: web::Query<AuthQueryParams>,
+    request: HttpRequest,
+    session: Session,
+    repo: web::Data<DieselRepository>,
+    webauthn: web::Data<Webauthn>,
+    server_config: web::Data<AppConfig>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    let state = session
+        .remove_as::<PasskeyLoginStateDto>(PASSKEY_LOGIN_SESSION_KEY)
+        .and_then(Result::ok);
+    let Some(state) = state else {
+        return expired_ceremony_response();
+    };
+
+    let outcome = match webauthn_service::finish_login(
+        webauthn.get_ref(),
+        &credential,
+        &state,
+        &client_info(&request),
+        jwt_keys.get_ref(),
+        repo.get_ref(),
+    ) {
+        Ok(outcome) => outcome,
+        Err(LoginError::Service(ServiceError::Unauthorized)) => return rejected_passkey_response(),
+        Err(err) => {
+            log::error!("Failed to finish passkey login: {err}");
+            return mutation_error_response(MutationResource::Authentication, &err);
+        }
+    };
+
+    login_outcome_response(
+        outcome,
+        "Авторизация выполнена.",
+        &success_redirect_url(query_params.next.as_deref(), &server_config.domain),
+        &request,
+        &session,
+    )
+}
@@ -0,0 +1,34 @@
+//! Public discovery documents served under `/.well-known`.
+
+use actix_web::{HttpRequest, HttpResponse, Responder, get, http::header, web};
+
+use crate::models::config::AppConfig;
+use crate::routes::oauth::issuer;
+use crate::services::jwt::JwtKeys;
+use crate::services::oauth as oauth_service;
+
+/// Publishes the public keys that verify session JWTs via
+/// `GET /.well-known/jwks.json`.
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(jwt_keys: web::Data<JwtKeys>) -> impl Responder {
+    HttpResponse::Ok()
+        .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+        .json(jwt_keys.jwks())
+}
+
+/// Publishes the OpenID Connect discovery document via
+/// `GET /.well-known/openid-configuration`; `404` while the active JWT key is
+/// `HS256`.
+#[get("/.well-known/openid-configuration")]
+pub async fn openid_configuration(
+    request: HttpRequest,
+    server_config: web::Data<AppConfig>,
+    jwt_keys: web::Data<JwtKeys>,
+) -> impl Responder {
+    match oauth_service::discovery_document(&issuer(&server_config, &request), jwt_keys.get_ref()) {
+        Some(document) => HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "public, max-age=300"))
+            .json(document),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
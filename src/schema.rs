@@ -1,11 +1,92 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    hub_policies (hub_id) {
+        hub_id -> Integer,
+        require_admin_2fa -> Bool,
+        require_email_verification -> Bool,
+    }
+}
+
+diesel::table! {
+    audit_events (id) {
+        id -> Integer,
+        hub_id -> Integer,
+        actor_id -> Nullable<Integer>,
+        event_type -> Text,
+        target_id -> Nullable<Integer>,
+        detail -> Nullable<Text>,
+        ip_address -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    email_verifications (id) {
+        id -> Integer,
+        user_id -> Integer,
+        hub_id -> Integer,
+        token_hash -> Text,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    hub_default_roles (hub_id, role_id) {
+        hub_id -> Integer,
+        role_id -> Integer,
+    }
+}
+
+diesel::table! {
+    hub_registration_settings (hub_id) {
+        hub_id -> Integer,
+        registration_mode -> Text,
+        allowed_email_domains -> Text,
+    }
+}
+
 diesel::table! {
     hubs (id) {
         id -> Integer,
         name -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    invitation_roles (invitation_id, role_id) {
+        invitation_id -> Integer,
+        role_id -> Integer,
+    }
+}
+
+diesel::table! {
+    invitations (id) {
+        id -> Integer,
+        hub_id -> Integer,
+        email -> Text,
+        name -> Text,
+        token_hash -> Text,
+        invited_by -> Nullable<Integer>,
+        expires_at -> Timestamp,
+        accepted_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    login_throttles (throttle_key) {
+        throttle_key -> Text,
+        failed_attempts -> Integer,
+        window_started_at -> Timestamp,
+        lockout_count -> Integer,
+        locked_until -> Nullable<Timestamp>,
     }
 }
 
@@ -18,12 +99,165 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    oauth_access_tokens (id) {
+        id -> Integer,
+        token_hash -> Text,
+        client_id -> Integer,
+        user_id -> Integer,
+        hub_id -> Integer,
+        scope -> Text,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oauth_authorization_codes (id) {
+        id -> Integer,
+        code_hash -> Text,
+        client_id -> Integer,
+        user_id -> Integer,
+        hub_id -> Integer,
+        redirect_uri -> Text,
+        scope -> Text,
+        nonce -> Nullable<Text>,
+        code_challenge -> Text,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oauth_clients (id) {
+        id -> Integer,
+        client_id -> Text,
+        client_secret_hash -> Nullable<Text>,
+        name -> Text,
+        redirect_uris -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    password_resets (id) {
+        id -> Integer,
+        user_id -> Integer,
+        hub_id -> Integer,
+        token_hash -> Text,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    permissions (id) {
+        id -> Integer,
+        name -> Text,
+        description -> Text,
+    }
+}
+
+diesel::table! {
+    personal_access_tokens (id) {
+        id -> Integer,
+        user_id -> Integer,
+        hub_id -> Integer,
+        name -> Text,
+        token_hash -> Text,
+        scopes -> Text,
+        expires_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    role_parents (role_id, parent_id) {
+        role_id -> Integer,
+        parent_id -> Integer,
+    }
+}
+
+diesel::table! {
+    role_permissions (role_id, permission_id) {
+        role_id -> Integer,
+        permission_id -> Integer,
+    }
+}
+
 diesel::table! {
     roles (id) {
         id -> Integer,
         name -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        hub_id -> Nullable<Integer>,
+        description -> Text,
+    }
+}
+
+diesel::table! {
+    service_account_roles (service_account_id, role_id) {
+        service_account_id -> Integer,
+        role_id -> Integer,
+    }
+}
+
+diesel::table! {
+    service_account_tokens (id) {
+        id -> Integer,
+        service_account_id -> Integer,
+        hub_id -> Integer,
+        token_hash -> Text,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    service_accounts (id) {
+        id -> Integer,
+        hub_id -> Integer,
+        name -> Text,
+        client_id -> Text,
+        secret_hash -> Text,
+        secret_rotated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    sessions (id) {
+        id -> Integer,
+        jti -> Text,
+        user_id -> Integer,
+        hub_id -> Integer,
+        key_id -> Text,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        user_agent -> Nullable<Text>,
+        ip_address -> Nullable<Text>,
+        last_seen_at -> Nullable<Timestamp>,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    user_credentials (id) {
+        id -> Integer,
+        user_id -> Integer,
+        hub_id -> Integer,
+        credential_id -> Text,
+        name -> Text,
+        passkey -> Text,
+        created_at -> Timestamp,
+        last_used_at -> Nullable<Timestamp>,
     }
 }
 
@@ -67,6 +301,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_recovery_codes (id) {
+        id -> Integer,
+        user_id -> Integer,
+        code_hash -> Text,
+        used_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     user_roles (user_id, role_id) {
         user_id -> Integer,
@@ -74,6 +317,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_totp (user_id) {
+        user_id -> Integer,
+        secret -> Text,
+        enabled_at -> Nullable<Timestamp>,
+        last_used_step -> Nullable<BigInt>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Integer,
@@ -83,23 +336,85 @@ diesel::table! {
         password_hash -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        email_verified_at -> Nullable<Timestamp>,
+        disabled_at -> Nullable<Timestamp>,
+        disabled_reason -> Nullable<Text>,
+        deleted_at -> Nullable<Timestamp>,
+        must_change_password -> Bool,
     }
 }
 
+diesel::joinable!(email_verifications -> hubs (hub_id));
+diesel::joinable!(email_verifications -> users (user_id));
+diesel::joinable!(hub_default_roles -> hubs (hub_id));
+diesel::joinable!(hub_default_roles -> roles (role_id));
+diesel::joinable!(hub_policies -> hubs (hub_id));
+diesel::joinable!(hub_registration_settings -> hubs (hub_id));
+diesel::joinable!(invitation_roles -> invitations (invitation_id));
+diesel::joinable!(invitation_roles -> roles (role_id));
+diesel::joinable!(invitations -> hubs (hub_id));
+diesel::joinable!(invitations -> users (invited_by));
 diesel::joinable!(menu -> hubs (hub_id));
+diesel::joinable!(oauth_access_tokens -> hubs (hub_id));
+diesel::joinable!(oauth_access_tokens -> oauth_clients (client_id));
+diesel::joinable!(oauth_access_tokens -> users (user_id));
+diesel::joinable!(oauth_authorization_codes -> hubs (hub_id));
+diesel::joinable!(oauth_authorization_codes -> oauth_clients (client_id));
+diesel::joinable!(oauth_authorization_codes -> users (user_id));
+diesel::joinable!(password_resets -> hubs (hub_id));
+diesel::joinable!(password_resets -> users (user_id));
+diesel::joinable!(personal_access_tokens -> hubs (hub_id));
+diesel::joinable!(personal_access_tokens -> users (user_id));
+diesel::joinable!(role_permissions -> permissions (permission_id));
+diesel::joinable!(role_permissions -> roles (role_id));
+diesel::joinable!(roles -> hubs (hub_id));
+diesel::joinable!(service_account_roles -> roles (role_id));
+diesel::joinable!(service_account_roles -> service_accounts (service_account_id));
+diesel::joinable!(service_account_tokens -> hubs (hub_id));
+diesel::joinable!(service_account_tokens -> service_accounts (service_account_id));
+diesel::joinable!(service_accounts -> hubs (hub_id));
+diesel::joinable!(sessions -> hubs (hub_id));
+diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(user_credentials -> hubs (hub_id));
+diesel::joinable!(user_credentials -> users (user_id));
+diesel::joinable!(user_recovery_codes -> users (user_id));
 diesel::joinable!(user_roles -> roles (role_id));
 diesel::joinable!(user_roles -> users (user_id));
+diesel::joinable!(user_totp -> users (user_id));
 diesel::joinable!(users -> hubs (hub_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    audit_events,
+    email_verifications,
+    hub_default_roles,
+    hub_policies,
+    hub_registration_settings,
     hubs,
+    invitation_roles,
+    invitations,
+    login_throttles,
     menu,
+    oauth_access_tokens,
+    oauth_authorization_codes,
+    oauth_clients,
+    password_resets,
+    permissions,
+    personal_access_tokens,
+    role_parents,
+    role_permissions,
     roles,
+    service_account_roles,
+    service_account_tokens,
+    service_accounts,
+    sessions,
+    user_credentials,
     user_fts,
     user_fts_config,
     user_fts_data,
     user_fts_docsize,
     user_fts_idx,
+    user_recovery_codes,
     user_roles,
+    user_totp,
     users,
 );
@@ -1,50 +1,244 @@
 //! Administrative services for managing users, roles, menus, and hubs.
+//!
+//! Each operation requires a [`Permission`] held through the current user's
+//! roles. Every change is appended to the audit log with the admin who made
+//! it.
 
+use chrono::Utc;
 use pushkind_common::domain::auth::AuthenticatedUser;
-use pushkind_common::routes::ensure_role;
 use pushkind_common::services::errors::{ServiceError, ServiceResult};
 
-use crate::SERVICE_ACCESS_ROLE;
+use crate::domain::audit::AuditEventType;
+use crate::domain::hub::{HubPolicy, HubRegistrationSettings};
+use crate::domain::permission::Permission;
+use crate::domain::role::{NewRole, RoleChange, RoleChangeSummary, UpdateRole, creates_cycle};
+use crate::domain::session::ClientInfo;
 use crate::domain::types::{HubId, MenuId, RoleId, UserId};
+use crate::domain::user::{UpdateUser, User};
 use crate::dto::admin::UserModalData;
-use crate::forms::main::{AddHubPayload, AddMenuPayload, AddRolePayload, UpdateUserPayload};
+use crate::forms::FormError;
+use crate::forms::main::{
+    AddHubPayload, AddMenuPayload, AddRolePayload, AddUserPayload, BulkRolePayload,
+    RoleParentsPayload, SuspendUserPayload, UpdateRolePayload, UpdateUserPayload,
+};
 use crate::repository::{
-    HubWriter, MenuReader, MenuWriter, RoleReader, RoleWriter, UserReader, UserWriter,
+    AuditWriter, HubReader, HubWriter, MenuReader, MenuWriter, PermissionReader, RoleReader,
+    RoleWriter, SessionWriter, TwoFactorWriter, UserReader, UserWriter,
 };
+use crate::services::audit::{event_by, user_update_detail};
+use crate::services::permission::{current_permissions, ensure_permission};
+
+/// Returns [`FormError::InvalidRoleId`] unless every role is a system role
+/// or a role of `hub_id`.
+pub(crate) fn ensure_roles_in_hub(
+    role_ids: &[RoleId],
+    hub_id: HubId,
+    repo: &impl RoleReader,
+) -> ServiceResult<()> {
+    if role_ids.is_empty() {
+        return Ok(());
+    }
+    let roles = repo.list_roles(hub_id)?;
+    if role_ids
+        .iter()
+        .all(|role_id| roles.iter().any(|role| role.id == *role_id))
+    {
+        Ok(())
+    } else {
+        Err(FormError::InvalidRoleId.into())
+    }
+}
 
-/// Creates a new role from a validated payload when the current user is an admin.
+/// Creates a role of the current hub with its permissions from a validated
+/// payload.
+///
+/// The role may only grant permissions the current user holds, so that
+/// `roles.manage` cannot hand out e.g. the global `hubs.manage`; others are
+/// refused with [`ServiceError::Unauthorized`]. Returns
+/// [`ServiceError::Conflict`] when the hub already has a role of that name or
+/// a system role uses it.
 pub fn create_role(
     payload: AddRolePayload,
     current_user: &AuthenticatedUser,
-    repo: &impl RoleWriter,
+    client: &ClientInfo,
+    repo: &(impl RoleReader + RoleWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    let held = current_permissions(current_user, repo)?;
+    if !held.contains(&Permission::RolesManage)
+        || payload
+            .permissions
+            .iter()
+            .any(|permission| !held.contains(permission))
+    {
+        return Err(ServiceError::Unauthorized);
+    }
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let new_role = NewRole::from(payload).with_hub(hub_id);
+    // Roles travel by name in session tokens, so a hub role must not reuse
+    // the name of a system role such as `admin`.
+    if repo
+        .get_role_by_name(new_role.name.as_str(), hub_id)?
+        .is_some()
+    {
+        return Err(ServiceError::Conflict);
+    }
+    let role = repo.create_role(&new_role)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::RoleCreated, client)?
+            .with_target(role.id.get())
+            .with_detail(role.name.as_str()),
+    )?;
+    Ok(())
+}
+
+/// Renames a role of the current hub or changes the description of a role
+/// visible in it.
+///
+/// System roles keep their names since services of every hub check roles by
+/// name; renaming one returns [`ServiceError::Unauthorized`]. Returns
+/// [`ServiceError::Conflict`] when the new name is taken.
+pub fn update_role(
+    role_id: i32,
+    payload: UpdateRolePayload,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl RoleReader + RoleWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::RolesManage, repo)?;
+    let role_id = RoleId::new(role_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let role = repo
+        .get_role_by_id(role_id)?
+        .filter(|role| role.is_visible_in(hub_id))
+        .ok_or(ServiceError::NotFound)?;
+    let updates = UpdateRole::from(payload);
+    if updates.name != role.name {
+        if role.hub_id.is_none() {
+            return Err(ServiceError::Unauthorized);
+        }
+        if repo
+            .get_role_by_name(updates.name.as_str(), hub_id)?
+            .is_some()
+        {
+            return Err(ServiceError::Conflict);
+        }
+    }
+    let role = repo.update_role(role_id, hub_id, &updates)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::RoleUpdated, client)?
+            .with_target(role.id.get())
+            .with_detail(role.name.as_str()),
+    )?;
+    Ok(())
+}
+
+/// Replaces the parent roles of a role visible in the current hub, so that
+/// holding the role also grants its parents and, transitively, theirs.
+///
+/// Parents must be visible in the hub. A system role is shared by every hub,
+/// so it may only inherit system roles and changing it also requires
+/// [`Permission::HubsManage`]. Returns [`FormError::RoleCycle`] when the role
+/// would end up inheriting itself.
+pub fn set_role_parents(
+    role_id: i32,
+    payload: RoleParentsPayload,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl RoleReader + RoleWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
-    let new_role = payload.into();
-    repo.create_role(&new_role)?;
+    ensure_permission(current_user, Permission::RolesManage, repo)?;
+    let role_id = RoleId::new(role_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let role = repo
+        .get_role_by_id(role_id)?
+        .filter(|role| role.is_visible_in(hub_id))
+        .ok_or(ServiceError::NotFound)?;
+    if role.hub_id.is_none() {
+        ensure_permission(current_user, Permission::HubsManage, repo)?;
+    }
+
+    let roles = repo.list_roles(hub_id)?;
+    let parents = payload
+        .parents
+        .iter()
+        .map(|parent_id| roles.iter().find(|role| role.id == *parent_id))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(FormError::InvalidRoleId)?;
+    if role.hub_id.is_none() && parents.iter().any(|parent| parent.hub_id.is_some()) {
+        return Err(FormError::InvalidRoleId.into());
+    }
+    if creates_cycle(role_id, &payload.parents, &repo.list_role_parents()?) {
+        return Err(FormError::RoleCycle.into());
+    }
+
+    repo.set_role_parents(role_id, hub_id, &payload.parents)?;
+    let parent_ids = payload
+        .parents
+        .iter()
+        .map(|parent_id| parent_id.get().to_string())
+        .collect::<Vec<_>>();
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::RoleUpdated, client)?
+            .with_target(role.id.get())
+            .with_detail(format!("parents: {}", parent_ids.join(", "))),
+    )?;
     Ok(())
 }
 
+/// Creates a user with the given password and roles in the current hub.
+///
+/// The address starts out unverified. Returns [`ServiceError::Conflict`] when
+/// it already belongs to a user of the hub.
+pub fn create_user(
+    payload: AddUserPayload,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl UserWriter + RoleReader + AuditWriter + PermissionReader),
+) -> ServiceResult<User> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    ensure_roles_in_hub(&payload.roles, hub_id, repo)?;
+    let user = repo.create_user(&payload.into_new_user(hub_id))?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::UserCreated, client)?
+            .with_target(user.id.get())
+            .with_detail(user.email.as_str()),
+    )?;
+    Ok(user)
+}
+
 /// Retrieves the user and available roles for the modal editor.
 pub fn user_modal_data(
     user_id: i32,
     current_user: &AuthenticatedUser,
-    repo: &(impl UserReader + RoleReader),
+    repo: &(impl UserReader + RoleReader + PermissionReader),
 ) -> ServiceResult<UserModalData> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::UsersRead, repo)?;
     let user_id = UserId::new(user_id)?;
     let hub_id = HubId::new(current_user.hub_id)?;
-    let user = repo.get_user_by_id(user_id, hub_id)?.map(|u| u.user);
-    let roles = repo.list_roles()?;
-    Ok(UserModalData { user, roles })
+    let (user, inherited_roles) = match repo.get_user_by_id(user_id, hub_id)? {
+        Some(user_roles) => (Some(user_roles.user), user_roles.inherited_roles),
+        None => (None, Vec::new()),
+    };
+    let roles = repo.list_roles(hub_id)?;
+    Ok(UserModalData {
+        user,
+        roles,
+        inherited_roles,
+    })
 }
 
-/// Deletes a user by ID, preventing self-deletion and non-admin access.
+/// Deletes a user by ID, preventing self-deletion.
+///
+/// The user is signed out and can be restored until the purge job removes
+/// them.
 pub fn delete_user_by_id(
     user_id: i32,
     current_user: &AuthenticatedUser,
-    repo: &(impl UserReader + UserWriter),
+    client: &ClientInfo,
+    repo: &(impl UserReader + UserWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
 
     let current_user_id: i32 = current_user
         .sub
@@ -61,20 +255,53 @@ pub fn delete_user_by_id(
         Some(u) => u.user,
         None => return Err(ServiceError::NotFound),
     };
-    repo.delete_user(user.id)?;
+    repo.delete_user(user.id, Utc::now().naive_utc())?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::UserDeleted, client)?
+            .with_target(user.id.get())
+            .with_detail(user.email.as_str()),
+    )?;
+    Ok(())
+}
+
+/// Restores a deleted user of the current hub.
+///
+/// Returns [`ServiceError::Conflict`] when another user of the hub has taken
+/// the address in the meantime.
+pub fn restore_user_by_id(
+    user_id: i32,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl UserWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let user_id = UserId::new(user_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    if repo.restore_user(user_id, hub_id)? == 0 {
+        return Err(ServiceError::NotFound);
+    }
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::UserRestored, client)?.with_target(user_id.get()),
+    )?;
     Ok(())
 }
 
 /// Assigns roles and updates a user from a validated payload.
+///
+/// When requested, also removes the user's second factor and recovery codes.
 pub fn assign_roles_and_update_user(
     user_id: i32,
     payload: UpdateUserPayload,
     current_user: &AuthenticatedUser,
-    repo: &(impl UserWriter + UserReader),
+    client: &ClientInfo,
+    repo: &(
+         impl UserWriter + UserReader + RoleReader + TwoFactorWriter + AuditWriter + PermissionReader
+     ),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
     let user_id = UserId::new(user_id)?;
-    let updates = payload.into();
+    let reset_two_factor = payload.reset_two_factor;
+    let updates: UpdateUser = payload.into();
 
     // Validate user exists in the hub
     let hub_id = HubId::new(current_user.hub_id)?;
@@ -82,8 +309,204 @@ pub fn assign_roles_and_update_user(
         Some(u) => u.user,
         None => return Err(ServiceError::NotFound),
     };
+    if let Some(roles) = &updates.roles {
+        ensure_roles_in_hub(roles, hub_id, repo)?;
+    }
 
     repo.update_user(user.id, user.hub_id, &updates)?;
+    let mut detail = user_update_detail(&updates);
+    if reset_two_factor {
+        repo.delete_user_totp(user.id)?;
+        detail.push_str("; two_factor_reset");
+    }
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::UserUpdated, client)?
+            .with_target(user.id.get())
+            .with_detail(detail),
+    )?;
+    Ok(())
+}
+
+/// Adds a role to or removes it from many users of the current hub at once,
+/// leaving their other roles untouched.
+///
+/// The users are the listed ids or every user matching the filter of the
+/// payload. The change and the audit event of each changed user are stored in
+/// one transaction.
+pub fn change_role_for_users(
+    role_id: i32,
+    change: RoleChange,
+    payload: BulkRolePayload,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl UserWriter + RoleReader + AuditWriter + PermissionReader),
+) -> ServiceResult<RoleChangeSummary> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let role_id = RoleId::new(role_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    ensure_roles_in_hub(&[role_id], hub_id, repo)?;
+
+    let selection = payload.into_selection(hub_id);
+    let detail = match change {
+        RoleChange::Add => format!("roles: +{}", role_id.get()),
+        RoleChange::Remove => format!("roles: -{}", role_id.get()),
+    };
+    let audit = event_by(current_user, AuditEventType::UserUpdated, client)?.with_detail(detail);
+    Ok(repo.change_role_for_users(role_id, hub_id, change, &selection, &audit)?)
+}
+
+/// Marks the email address of a user in the current hub as verified by hand.
+///
+/// Addresses that are already verified keep their original timestamp.
+pub fn verify_user_email(
+    user_id: i32,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl UserReader + UserWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let user_id = UserId::new(user_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let user = match repo.get_user_by_id(user_id, hub_id)? {
+        Some(u) => u.user,
+        None => return Err(ServiceError::NotFound),
+    };
+
+    if !user.is_email_verified() {
+        repo.mark_email_verified(user.id, user.hub_id, Utc::now().naive_utc())?;
+        repo.record_audit_event(
+            &event_by(current_user, AuditEventType::UserUpdated, client)?
+                .with_target(user.id.get())
+                .with_detail("email_verified"),
+        )?;
+    }
+    Ok(())
+}
+
+/// Suspends a user of the current hub and revokes their sessions,
+/// preventing self-suspension and access without `users.write`.
+///
+/// Suspending an already suspended user keeps the original timestamp and
+/// reason.
+pub fn suspend_user_by_id(
+    user_id: i32,
+    payload: SuspendUserPayload,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl UserReader + UserWriter + SessionWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+
+    let current_user_id: i32 = current_user
+        .sub
+        .parse()
+        .map_err(|_| ServiceError::Internal)?;
+
+    if user_id == current_user_id {
+        return Err(ServiceError::Unauthorized);
+    }
+
+    let user_id = UserId::new(user_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let user = match repo.get_user_by_id(user_id, hub_id)? {
+        Some(u) => u.user,
+        None => return Err(ServiceError::NotFound),
+    };
+
+    if !user.is_disabled() {
+        let now = Utc::now().naive_utc();
+        repo.suspend_user(user.id, user.hub_id, payload.reason.as_deref(), now)?;
+        repo.revoke_user_sessions(user.id, now)?;
+        let mut event = event_by(current_user, AuditEventType::UserSuspended, client)?
+            .with_target(user.id.get());
+        if let Some(reason) = payload.reason {
+            event = event.with_detail(reason);
+        }
+        repo.record_audit_event(&event)?;
+    }
+    Ok(())
+}
+
+/// Lifts the suspension of a user of the current hub.
+pub fn reactivate_user_by_id(
+    user_id: i32,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl UserReader + UserWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let user_id = UserId::new(user_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let user = match repo.get_user_by_id(user_id, hub_id)? {
+        Some(u) => u.user,
+        None => return Err(ServiceError::NotFound),
+    };
+
+    if user.is_disabled() {
+        repo.reactivate_user(user.id, user.hub_id)?;
+        repo.record_audit_event(
+            &event_by(current_user, AuditEventType::UserReactivated, client)?
+                .with_target(user.id.get()),
+        )?;
+    }
+    Ok(())
+}
+
+/// Replaces the security policy of the current user's hub.
+pub fn update_hub_policy(
+    policy: HubPolicy,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl HubWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    repo.update_hub_policy(hub_id, &policy)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::HubUpdated, client)?
+            .with_target(hub_id.get())
+            .with_detail(format!(
+                "require_admin_2fa: {}; require_email_verification: {}",
+                policy.require_admin_2fa, policy.require_email_verification
+            )),
+    )?;
+    Ok(())
+}
+
+/// Returns the registration settings of a hub.
+pub fn hub_registration_settings(
+    hub_id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &(impl HubReader + PermissionReader),
+) -> ServiceResult<HubRegistrationSettings> {
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
+    let hub_id = HubId::new(hub_id)?;
+    if repo.get_hub_by_id(hub_id)?.is_none() {
+        return Err(ServiceError::NotFound);
+    }
+    Ok(repo.get_hub_registration_settings(hub_id)?)
+}
+
+/// Replaces the registration settings of a hub.
+pub fn update_hub_registration_settings(
+    hub_id: i32,
+    settings: HubRegistrationSettings,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl HubReader + HubWriter + RoleReader + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
+    let hub_id = HubId::new(hub_id)?;
+    if repo.get_hub_by_id(hub_id)?.is_none() {
+        return Err(ServiceError::NotFound);
+    }
+    ensure_roles_in_hub(&settings.default_roles, hub_id, repo)?;
+    repo.update_hub_registration_settings(hub_id, &settings)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::HubUpdated, client)?
+            .with_target(hub_id.get())
+            .with_detail(format!("registration_mode: {}", settings.registration_mode)),
+    )?;
     Ok(())
 }
 
@@ -91,43 +514,92 @@ pub fn assign_roles_and_update_user(
 pub fn create_hub(
     payload: AddHubPayload,
     current_user: &AuthenticatedUser,
-    repo: &impl HubWriter,
+    client: &ClientInfo,
+    repo: &(impl HubWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
     let new_hub = payload.into();
-    repo.create_hub(&new_hub)?;
+    let hub = repo.create_hub(&new_hub)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::HubCreated, client)?
+            .with_target(hub.id.get())
+            .with_detail(hub.name.as_str()),
+    )?;
     Ok(())
 }
 
-/// Deletes a role by ID, protecting the base admin role.
+/// Deletes a system role or a role of the current hub by ID, protecting the
+/// base admin role.
+///
+/// A system role is shared by every hub, so deleting one also requires
+/// [`Permission::HubsManage`].
 pub fn delete_role_by_id(
     role_id: i32,
     current_user: &AuthenticatedUser,
-    repo: &impl RoleWriter,
+    client: &ClientInfo,
+    repo: &(impl RoleReader + RoleWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::RolesManage, repo)?;
     if role_id == 1 {
         // Protect the base admin role from deletion.
         return Err(ServiceError::Unauthorized);
     }
     let role_id = RoleId::new(role_id)?;
-    repo.delete_role(role_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let role = repo
+        .get_role_by_id(role_id)?
+        .filter(|role| role.is_visible_in(hub_id))
+        .ok_or(ServiceError::NotFound)?;
+    if role.hub_id.is_none() {
+        ensure_permission(current_user, Permission::HubsManage, repo)?;
+    }
+    repo.delete_role(role_id, hub_id)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::RoleDeleted, client)?.with_target(role_id.get()),
+    )?;
     Ok(())
 }
 
 /// Deletes a hub by ID, preventing removal of the current user's hub.
+///
+/// The hub and its users can be restored until the purge job removes them.
 pub fn delete_hub_by_id(
     hub_id: i32,
     current_user: &AuthenticatedUser,
-    repo: &impl HubWriter,
+    client: &ClientInfo,
+    repo: &(impl HubWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
     if current_user.hub_id == hub_id {
         // Prevent deleting the hub currently associated with the user.
         return Err(ServiceError::Unauthorized);
     }
     let hub_id = HubId::new(hub_id)?;
-    repo.delete_hub(hub_id)?;
+    repo.delete_hub(hub_id, Utc::now().naive_utc())?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::HubDeleted, client)?.with_target(hub_id.get()),
+    )?;
+    Ok(())
+}
+
+/// Restores a deleted hub together with the users deleted with it.
+///
+/// Returns [`ServiceError::Conflict`] when another hub has taken the name in
+/// the meantime.
+pub fn restore_hub_by_id(
+    hub_id: i32,
+    current_user: &AuthenticatedUser,
+    client: &ClientInfo,
+    repo: &(impl HubWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
+    let hub_id = HubId::new(hub_id)?;
+    if repo.restore_hub(hub_id)? == 0 {
+        return Err(ServiceError::NotFound);
+    }
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::HubRestored, client)?.with_target(hub_id.get()),
+    )?;
     Ok(())
 }
 
@@ -135,12 +607,18 @@ pub fn delete_hub_by_id(
 pub fn create_menu(
     payload: AddMenuPayload,
     current_user: &AuthenticatedUser,
-    repo: &impl MenuWriter,
+    client: &ClientInfo,
+    repo: &(impl MenuWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::MenuManage, repo)?;
     let hub_id = HubId::new(current_user.hub_id)?;
     let new_menu = payload.into_new_menu(hub_id);
-    repo.create_menu(&new_menu)?;
+    let menu = repo.create_menu(&new_menu)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::MenuCreated, client)?
+            .with_target(menu.id.get())
+            .with_detail(menu.name.as_str()),
+    )?;
     Ok(())
 }
 
@@ -148,9 +626,10 @@ pub fn create_menu(
 pub fn delete_menu_by_id(
     menu_id: i32,
     current_user: &AuthenticatedUser,
-    repo: &(impl MenuReader + MenuWriter),
+    client: &ClientInfo,
+    repo: &(impl MenuReader + MenuWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<()> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::MenuManage, repo)?;
     let menu_id = MenuId::new(menu_id)?;
     let hub_id = HubId::new(current_user.hub_id)?;
     let menu = match repo.get_menu_by_id(menu_id, hub_id)? {
@@ -158,6 +637,11 @@ pub fn delete_menu_by_id(
         None => return Err(ServiceError::NotFound),
     };
     repo.delete_menu(menu.id)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::MenuDeleted, client)?
+            .with_target(menu.id.get())
+            .with_detail(menu.name.as_str()),
+    )?;
     Ok(())
 }
 
@@ -166,12 +650,15 @@ mod tests {
     use super::*;
     use crate::domain::hub::Hub;
     use crate::domain::menu::Menu;
-    use crate::domain::role::Role;
+    use crate::domain::role::{Role, RoleParent};
     use crate::domain::types::{HubId, MenuId, RoleId, RoleName, UserEmail, UserId};
     use crate::domain::user::{User, UserWithRoles};
-    use crate::forms::main::{AddHubPayload, AddMenuPayload, AddRolePayload};
+    use crate::forms::main::{
+        AddHubPayload, AddMenuPayload, AddRolePayload, BulkRolePayload, RoleParentsPayload,
+        SuspendUserPayload, UpdateRolePayload,
+    };
     use crate::repository::mock::MockRepository;
-    use chrono::Utc;
+    use crate::repository::{UserSelection, UserStatusFilter};
     use pushkind_common::domain::auth::AuthenticatedUser;
 
     fn admin_user() -> AuthenticatedUser {
@@ -207,6 +694,15 @@ mod tests {
         }
     }
 
+    /// Grants every permission to the admins and none to the other users.
+    fn expect_permissions(repo: &mut MockRepository) {
+        repo.expect_list_user_permissions()
+            .returning(|user_id, _| match user_id.get() {
+                2 => Ok(vec![]),
+                _ => Ok(Permission::ALL.to_vec()),
+            });
+    }
+
     fn make_user(id: i32, email: &str, hub_id: i32) -> UserWithRoles {
         let now = Utc::now().naive_utc();
         let user = User::new(
@@ -225,6 +721,7 @@ mod tests {
     #[test]
     fn user_modal_data_success_and_not_found() {
         let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
         let user = make_user(7, "u@e", 1);
         let role = Role::new(
             RoleId::new(1).unwrap(),
@@ -242,7 +739,7 @@ mod tests {
                 }
             });
         repo.expect_list_roles()
-            .returning(move || Ok(vec![role.clone()]));
+            .returning(move |_| Ok(vec![role.clone()]));
         let current_user = admin_user();
         let found = user_modal_data(7, &current_user, &repo).unwrap();
         assert!(found.user.is_some());
@@ -254,66 +751,479 @@ mod tests {
     #[test]
     fn create_role_authorization() {
         let mut repo = MockRepository::new();
-        repo.expect_create_role().returning(|new_role| {
+        expect_permissions(&mut repo);
+        repo.expect_get_role_by_name()
+            .withf(|name, hub_id| name == "new" && hub_id.get() == 1)
+            .returning(|_, _| Ok(None));
+        repo.expect_create_role()
+            .withf(|new_role| {
+                new_role.permissions == vec![Permission::MenuManage]
+                    && new_role.hub_id == Some(HubId::new(1).unwrap())
+            })
+            .returning(|new_role| {
+                let now = Utc::now().naive_utc();
+                Ok(
+                    Role::new(RoleId::new(2).unwrap(), new_role.name.clone(), now, now)
+                        .with_hub(HubId::new(1).unwrap()),
+                )
+            });
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::RoleCreated
+                    && event.actor_id == Some(UserId::new(1).unwrap())
+                    && event.target_id == Some(2)
+                    && event.detail.as_deref() == Some("new")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        let payload = AddRolePayload {
+            name: RoleName::new("new").unwrap(),
+            permissions: vec![Permission::MenuManage],
+            description: String::new(),
+        };
+        assert!(create_role(payload, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn create_role_rejects_names_of_system_roles() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_get_role_by_name().returning(|name, _| {
             let now = Utc::now().naive_utc();
-            Ok(Role::new(
-                RoleId::new(2).unwrap(),
-                new_role.name.clone(),
-                now,
-                now,
-            ))
+            Ok(Some(Role::try_new(1, name, now, now).unwrap()))
         });
+        repo.expect_create_role().never();
         let payload = AddRolePayload {
-            name: RoleName::new("new").unwrap(),
+            name: RoleName::new("admin").unwrap(),
+            permissions: vec![],
+            description: String::new(),
+        };
+        assert!(matches!(
+            create_role(payload, &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Conflict)
+        ));
+    }
+
+    #[test]
+    fn create_role_grants_only_permissions_the_admin_holds() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::RolesManage, Permission::UsersWrite]));
+        repo.expect_create_role().never();
+        let payload = AddRolePayload {
+            name: RoleName::new("owner").unwrap(),
+            permissions: vec![Permission::UsersWrite, Permission::HubsManage],
+            description: String::new(),
+        };
+        assert!(matches!(
+            create_role(payload, &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    fn make_role(id: i32, name: &str, hub_id: Option<i32>) -> Role {
+        let now = Utc::now().naive_utc();
+        let role = Role::try_new(id, name, now, now).unwrap();
+        match hub_id {
+            Some(hub_id) => role.with_hub(HubId::new(hub_id).unwrap()),
+            None => role,
+        }
+    }
+
+    #[test]
+    fn update_role_renames_a_role_of_the_hub() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_get_role_by_id()
+            .returning(|_| Ok(Some(make_role(4, "staff", Some(1)))));
+        repo.expect_get_role_by_name()
+            .withf(|name, hub_id| name == "support" && hub_id.get() == 1)
+            .returning(|_, _| Ok(None));
+        repo.expect_update_role()
+            .withf(|role_id, hub_id, updates| {
+                role_id.get() == 4
+                    && hub_id.get() == 1
+                    && updates.name.as_str() == "support"
+                    && updates.description == "Answers customers"
+            })
+            .times(1)
+            .returning(|role_id, _, updates| {
+                Ok(make_role(role_id.get(), updates.name.as_str(), Some(1))
+                    .with_description(updates.description.clone()))
+            });
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::RoleUpdated
+                    && event.target_id == Some(4)
+                    && event.detail.as_deref() == Some("support")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        let payload = UpdateRolePayload {
+            name: RoleName::new("support").unwrap(),
+            description: "Answers customers".into(),
+        };
+        assert!(update_role(4, payload, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn update_role_keeps_names_of_system_roles() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_get_role_by_id()
+            .returning(|_| Ok(Some(make_role(1, "admin", None))));
+        repo.expect_update_role()
+            .withf(|_, _, updates| updates.name.as_str() == "admin")
+            .times(1)
+            .returning(|role_id, _, updates| {
+                Ok(make_role(role_id.get(), updates.name.as_str(), None))
+            });
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+
+        let renamed = UpdateRolePayload {
+            name: RoleName::new("root").unwrap(),
+            description: String::new(),
+        };
+        assert!(matches!(
+            update_role(1, renamed, &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+        let described = UpdateRolePayload {
+            name: RoleName::new("admin").unwrap(),
+            description: "Manages the hub".into(),
+        };
+        assert!(update_role(1, described, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn update_role_rejects_taken_names_and_other_hubs() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_get_role_by_id()
+            .returning(|role_id| Ok(Some(make_role(role_id.get(), "staff", Some(1)))));
+        repo.expect_get_role_by_name()
+            .returning(|name, _| Ok(Some(make_role(5, name, Some(1)))));
+        repo.expect_update_role().never();
+        repo.expect_record_audit_event().never();
+
+        let payload = UpdateRolePayload {
+            name: RoleName::new("support").unwrap(),
+            description: String::new(),
+        };
+        assert!(matches!(
+            update_role(4, payload, &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Conflict)
+        ));
+        let payload = UpdateRolePayload {
+            name: RoleName::new("staff").unwrap(),
+            description: String::new(),
         };
-        assert!(create_role(payload, &admin_user(), &repo).is_ok());
+        assert!(matches!(
+            update_role(
+                4,
+                payload,
+                &admin_user_different_hub(),
+                &ClientInfo::default(),
+                &repo
+            ),
+            Err(ServiceError::NotFound)
+        ));
+    }
+
+    fn parents_payload(parents: &[i32]) -> RoleParentsPayload {
+        RoleParentsPayload {
+            parents: parents.iter().map(|id| RoleId::new(*id).unwrap()).collect(),
+        }
+    }
+
+    /// Hub roles `viewer` (1), `editor` (2) and `manager` (3), where each
+    /// inherits the previous one, and the system role `admin` (4).
+    fn expect_role_tree(repo: &mut MockRepository) {
+        let roles = || {
+            vec![
+                make_role(4, "admin", None),
+                make_role(1, "viewer", Some(1)),
+                make_role(2, "editor", Some(1)),
+                make_role(3, "manager", Some(1)),
+            ]
+        };
+        repo.expect_get_role_by_id()
+            .returning(move |role_id| Ok(roles().into_iter().find(|role| role.id == role_id)));
+        repo.expect_list_roles().returning(move |_| Ok(roles()));
+        repo.expect_list_role_parents().returning(|| {
+            Ok(vec![
+                RoleParent::try_new(2, 1).unwrap(),
+                RoleParent::try_new(3, 2).unwrap(),
+            ])
+        });
+    }
+
+    #[test]
+    fn set_role_parents_links_roles_of_the_hub() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        expect_role_tree(&mut repo);
+        repo.expect_set_role_parents()
+            .withf(|role_id, hub_id, parents| {
+                role_id.get() == 3 && hub_id.get() == 1 && parents == [RoleId::new(1).unwrap()]
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::RoleUpdated
+                    && event.target_id == Some(3)
+                    && event.detail.as_deref() == Some("parents: 1")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(
+            set_role_parents(
+                3,
+                parents_payload(&[1]),
+                &admin_user(),
+                &ClientInfo::default(),
+                &repo
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn set_role_parents_rejects_cycles_and_hub_parents_of_system_roles() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        expect_role_tree(&mut repo);
+        repo.expect_set_role_parents().never();
+        repo.expect_record_audit_event().never();
+
+        let client = ClientInfo::default();
+        let cycle = FormError::RoleCycle.to_string();
+        let invalid = FormError::InvalidRoleId.to_string();
+        assert!(matches!(
+            set_role_parents(1, parents_payload(&[3]), &admin_user(), &client, &repo),
+            Err(ServiceError::Form(message)) if message == cycle
+        ));
+        assert!(matches!(
+            set_role_parents(2, parents_payload(&[2]), &admin_user(), &client, &repo),
+            Err(ServiceError::Form(message)) if message == cycle
+        ));
+        assert!(matches!(
+            set_role_parents(4, parents_payload(&[1]), &admin_user(), &client, &repo),
+            Err(ServiceError::Form(message)) if message == invalid
+        ));
+        assert!(matches!(
+            set_role_parents(3, parents_payload(&[9]), &admin_user(), &client, &repo),
+            Err(ServiceError::Form(message)) if message == invalid
+        ));
+        assert!(matches!(
+            set_role_parents(3, parents_payload(&[1]), &non_admin_user(), &client, &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    fn bulk_payload(user_ids: Option<Vec<i32>>) -> BulkRolePayload {
+        BulkRolePayload {
+            user_ids: user_ids
+                .map(|ids| ids.into_iter().map(|id| UserId::new(id).unwrap()).collect()),
+            status: UserStatusFilter::Active,
+            role: Some("viewer".into()),
+            query: None,
+        }
+    }
+
+    #[test]
+    fn change_role_for_users_audits_within_the_change() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_list_roles()
+            .returning(|_| Ok(vec![make_role(4, "editor", Some(1))]));
+        repo.expect_change_role_for_users()
+            .withf(|role_id, hub_id, change, selection, audit| {
+                role_id.get() == 4
+                    && hub_id.get() == 1
+                    && *change == RoleChange::Add
+                    && matches!(selection, UserSelection::Matching(query) if query.role.as_deref() == Some("viewer"))
+                    && audit.event_type == AuditEventType::UserUpdated
+                    && audit.detail.as_deref() == Some("roles: +4")
+            })
+            .times(1)
+            .returning(|_, _, _, _, _| {
+                Ok(RoleChangeSummary {
+                    changed: vec![UserId::new(7).unwrap(), UserId::new(8).unwrap()],
+                    unchanged: 1,
+                    not_found: vec![],
+                })
+            });
+        repo.expect_record_audit_event().never();
+
+        let summary = change_role_for_users(
+            4,
+            RoleChange::Add,
+            bulk_payload(None),
+            &admin_user(),
+            &ClientInfo::default(),
+            &repo,
+        )
+        .unwrap();
+        assert_eq!(summary.changed.len(), 2);
+        assert_eq!(summary.unchanged, 1);
+    }
+
+    #[test]
+    fn change_role_for_users_rejects_roles_of_other_hubs() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_list_roles()
+            .returning(|_| Ok(vec![make_role(1, "admin", None)]));
+        repo.expect_change_role_for_users().never();
+
+        let result = change_role_for_users(
+            4,
+            RoleChange::Remove,
+            bulk_payload(Some(vec![7])),
+            &admin_user(),
+            &ClientInfo::default(),
+            &repo,
+        );
+        assert!(matches!(result, Err(ServiceError::Form(_))));
+
+        let result = change_role_for_users(
+            1,
+            RoleChange::Remove,
+            bulk_payload(Some(vec![7])),
+            &non_admin_user(),
+            &ClientInfo::default(),
+            &repo,
+        );
+        assert!(matches!(result, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn delete_role_is_limited_to_the_admins_hub() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_get_role_by_id()
+            .returning(|_| Ok(Some(make_role(5, "cashier", Some(1)))));
+        repo.expect_delete_role().never();
+        repo.expect_record_audit_event().never();
+        assert!(matches!(
+            delete_role_by_id(
+                5,
+                &admin_user_different_hub(),
+                &ClientInfo::default(),
+                &repo
+            ),
+            Err(ServiceError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn delete_system_role_requires_hubs_manage() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|user_id, _| match user_id.get() {
+                2 => Ok(vec![Permission::RolesManage]),
+                _ => Ok(Permission::ALL.to_vec()),
+            });
+        repo.expect_get_role_by_id()
+            .returning(|_| Ok(Some(make_role(5, "crm", None))));
+        repo.expect_delete_role()
+            .withf(|role_id, hub_id| role_id.get() == 5 && hub_id.get() == 1)
+            .times(1)
+            .returning(|_, _| Ok(1));
+        repo.expect_record_audit_event()
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(matches!(
+            delete_role_by_id(5, &non_admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(delete_role_by_id(5, &admin_user(), &ClientInfo::default(), &repo).is_ok());
     }
 
     #[test]
     fn create_and_delete_hub() {
         let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
         let now = Utc::now().naive_utc();
         repo.expect_create_hub()
             .returning(move |nh| Ok(Hub::new(HubId::new(2).unwrap(), nh.name.clone(), now, now)));
-        repo.expect_delete_hub().returning(|_| Ok(1));
+        repo.expect_delete_hub().returning(|_, _| Ok(1));
         let payload = AddHubPayload {
             name: crate::domain::types::HubName::new("hub").unwrap(),
         };
-        assert!(create_hub(payload, &admin_user(), &repo).is_ok());
-        assert!(delete_hub_by_id(2, &admin_user(), &repo).is_ok());
+        assert!(create_hub(payload, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+        assert!(delete_hub_by_id(2, &admin_user(), &ClientInfo::default(), &repo).is_ok());
     }
 
     #[test]
     fn delete_hub_fails_for_non_admin() {
         let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
         let now = Utc::now().naive_utc();
         repo.expect_create_hub()
             .returning(move |nh| Ok(Hub::new(HubId::new(2).unwrap(), nh.name.clone(), now, now)));
-        repo.expect_delete_hub().returning(|_| Ok(1));
+        repo.expect_delete_hub().returning(|_, _| Ok(1));
         let payload = AddHubPayload {
             name: crate::domain::types::HubName::new("hub").unwrap(),
         };
-        assert!(create_hub(payload, &admin_user(), &repo).is_ok());
-        assert!(delete_hub_by_id(2, &non_admin_user(), &repo).is_err());
+        assert!(create_hub(payload, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+        assert!(delete_hub_by_id(2, &non_admin_user(), &ClientInfo::default(), &repo).is_err());
+    }
+
+    #[test]
+    fn operations_require_their_own_permission() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
+        repo.expect_get_user_by_id()
+            .returning(|_, _| Ok(Some(make_user(7, "u@e", 1))));
+        repo.expect_list_roles().returning(|_| Ok(vec![]));
+        repo.expect_suspend_user().never();
+
+        let viewer = non_admin_user();
+        assert!(user_modal_data(7, &viewer, &repo).is_ok());
+        let payload = SuspendUserPayload { reason: None };
+        assert!(matches!(
+            suspend_user_by_id(7, payload, &viewer, &ClientInfo::default(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
     }
 
     #[test]
     fn delete_hub_fails_for_admin_from_same_hub() {
         let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
         let now = Utc::now().naive_utc();
         repo.expect_create_hub()
             .returning(move |nh| Ok(Hub::new(HubId::new(2).unwrap(), nh.name.clone(), now, now)));
-        repo.expect_delete_hub().returning(|_| Ok(1));
+        repo.expect_delete_hub().returning(|_, _| Ok(1));
         let payload = AddHubPayload {
             name: crate::domain::types::HubName::new("hub").unwrap(),
         };
-        assert!(create_hub(payload, &admin_user(), &repo).is_ok());
-        assert!(delete_hub_by_id(2, &admin_user_different_hub(), &repo).is_err());
+        assert!(create_hub(payload, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+        assert!(
+            delete_hub_by_id(
+                2,
+                &admin_user_different_hub(),
+                &ClientInfo::default(),
+                &repo
+            )
+            .is_err()
+        );
     }
 
     #[test]
     fn create_and_delete_menu() {
         let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
         // The service first fetches the menu by id and hub before deleting.
         repo.expect_get_menu_by_id().returning(|id, hub_id| {
             Ok(Some(Menu::new(
@@ -336,7 +1246,304 @@ mod tests {
             name: crate::domain::types::MenuName::new("m").unwrap(),
             url: crate::domain::types::MenuUrl::new("https://app.test.me/").unwrap(),
         };
-        assert!(create_menu(payload, &admin_user(), &repo).is_ok());
-        assert!(delete_menu_by_id(1, &admin_user(), &repo).is_ok());
+        assert!(create_menu(payload, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+        assert!(delete_menu_by_id(1, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn create_user_adds_the_user_to_the_admin_hub() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+        repo.expect_create_user()
+            .withf(|new_user| {
+                new_user.hub_id.get() == 1
+                    && new_user.email.as_str() == "new@example.com"
+                    && new_user.must_change_password
+            })
+            .times(1)
+            .returning(|new_user| {
+                Ok(make_user(9, new_user.email.as_str(), new_user.hub_id.get()).user)
+            });
+
+        let payload = AddUserPayload {
+            email: UserEmail::new("new@example.com").unwrap(),
+            name: crate::domain::types::UserName::new("New").unwrap(),
+            password: crate::domain::types::UserPassword::new("secret").unwrap(),
+            roles: vec![],
+            must_change_password: true,
+        };
+        let user = create_user(payload, &admin_user(), &ClientInfo::default(), &repo).unwrap();
+        assert_eq!(user.id.get(), 9);
+    }
+
+    #[test]
+    fn update_user_resets_two_factor_on_request() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+        let user = make_user(7, "u@e", 1);
+        let updated = user.user.clone();
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(user.clone())));
+        repo.expect_update_user()
+            .returning(move |_, _, _| Ok(updated.clone()));
+        repo.expect_delete_user_totp()
+            .withf(|user_id| user_id.get() == 7)
+            .times(1)
+            .returning(|_| Ok(1));
+
+        let payload = UpdateUserPayload {
+            name: crate::domain::types::UserName::new("User").unwrap(),
+            password: None,
+            roles: None,
+            reset_two_factor: true,
+            must_change_password: false,
+        };
+        assert!(
+            assign_roles_and_update_user(7, payload, &admin_user(), &ClientInfo::default(), &repo)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn update_user_rejects_roles_of_other_hubs() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        let user = make_user(7, "u@e", 1);
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(user.clone())));
+        repo.expect_list_roles().returning(|hub_id| {
+            let now = Utc::now().naive_utc();
+            Ok(vec![
+                Role::try_new(1, "admin", now, now).unwrap(),
+                Role::try_new(4, "staff", now, now)
+                    .unwrap()
+                    .with_hub(hub_id),
+            ])
+        });
+        repo.expect_update_user().never();
+
+        let payload = UpdateUserPayload {
+            name: crate::domain::types::UserName::new("User").unwrap(),
+            password: None,
+            roles: Some(vec![RoleId::new(4).unwrap(), RoleId::new(6).unwrap()]),
+            reset_two_factor: false,
+            must_change_password: false,
+        };
+        assert!(matches!(
+            assign_roles_and_update_user(7, payload, &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Form(_))
+        ));
+    }
+
+    #[test]
+    fn verify_user_email_marks_unverified_users_of_the_hub() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+        repo.expect_get_user_by_id()
+            .withf(|id, hub_id| id.get() == 7 && hub_id.get() == 1)
+            .returning(|id, hub_id| Ok(Some(make_user(id.get(), "u@e", hub_id.get()))));
+        repo.expect_mark_email_verified()
+            .withf(|id, hub_id, _| id.get() == 7 && hub_id.get() == 1)
+            .times(1)
+            .returning(|_, _, _| Ok(1));
+
+        assert!(matches!(
+            verify_user_email(7, &non_admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(verify_user_email(7, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn suspend_user_revokes_sessions_and_rejects_self() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_get_user_by_id()
+            .withf(|id, hub_id| id.get() == 7 && hub_id.get() == 1)
+            .returning(|id, hub_id| Ok(Some(make_user(id.get(), "u@e", hub_id.get()))));
+        repo.expect_suspend_user()
+            .withf(|id, hub_id, reason, _| {
+                id.get() == 7 && hub_id.get() == 1 && *reason == Some("Спам")
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(1));
+        repo.expect_revoke_user_sessions()
+            .withf(|id, _| id.get() == 7)
+            .times(1)
+            .returning(|_, _| Ok(2));
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::UserSuspended
+                    && event.target_id == Some(7)
+                    && event.detail.as_deref() == Some("Спам")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let payload = || SuspendUserPayload {
+            reason: Some("Спам".into()),
+        };
+        assert!(matches!(
+            suspend_user_by_id(1, payload(), &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            suspend_user_by_id(
+                7,
+                payload(),
+                &non_admin_user(),
+                &ClientInfo::default(),
+                &repo
+            ),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(
+            suspend_user_by_id(7, payload(), &admin_user(), &ClientInfo::default(), &repo).is_ok()
+        );
+    }
+
+    #[test]
+    fn reactivate_user_only_updates_suspended_users() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+        repo.expect_get_user_by_id().returning(|id, hub_id| {
+            let mut user = make_user(id.get(), "u@e", hub_id.get());
+            if id.get() == 7 {
+                user.user.disabled_at = Some(Utc::now().naive_utc());
+            }
+            Ok(Some(user))
+        });
+        repo.expect_reactivate_user()
+            .withf(|id, hub_id| id.get() == 7 && hub_id.get() == 1)
+            .times(1)
+            .returning(|_, _| Ok(1));
+
+        assert!(reactivate_user_by_id(7, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+        assert!(reactivate_user_by_id(8, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn restore_user_is_scoped_to_the_current_hub() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_restore_user()
+            .withf(|_, hub_id| hub_id.get() == 1)
+            .returning(|id, _| Ok(usize::from(id.get() == 7)));
+        repo.expect_record_audit_event()
+            .withf(|event| event.event_type == AuditEventType::UserRestored)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(matches!(
+            restore_user_by_id(7, &non_admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            restore_user_by_id(8, &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::NotFound)
+        ));
+        assert!(restore_user_by_id(7, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn restore_hub_reports_hubs_that_are_not_deleted() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+        repo.expect_restore_hub()
+            .returning(|hub_id| Ok(usize::from(hub_id.get() == 2)));
+
+        assert!(matches!(
+            restore_hub_by_id(3, &admin_user(), &ClientInfo::default(), &repo),
+            Err(ServiceError::NotFound)
+        ));
+        assert!(restore_hub_by_id(2, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn update_hub_policy_requires_admin() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+        repo.expect_update_hub_policy()
+            .withf(|hub_id, policy| hub_id.get() == 1 && policy.require_admin_2fa)
+            .times(1)
+            .returning(|_, policy| Ok(policy.clone()));
+        let policy = HubPolicy {
+            require_admin_2fa: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            update_hub_policy(
+                policy.clone(),
+                &non_admin_user(),
+                &ClientInfo::default(),
+                &repo
+            ),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(update_hub_policy(policy, &admin_user(), &ClientInfo::default(), &repo).is_ok());
+    }
+
+    #[test]
+    fn update_hub_registration_settings_requires_existing_hub() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo);
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+        repo.expect_get_hub_by_id().returning(|hub_id| {
+            let now = Utc::now().naive_utc();
+            Ok((hub_id.get() == 2).then(|| Hub::try_new(hub_id.get(), "Other", now, now).unwrap()))
+        });
+        repo.expect_update_hub_registration_settings()
+            .withf(|hub_id, settings| {
+                hub_id.get() == 2 && settings.default_roles == vec![RoleId::new(3).unwrap()]
+            })
+            .times(1)
+            .returning(|_, settings| Ok(settings.clone()));
+        repo.expect_list_roles()
+            .withf(|hub_id| hub_id.get() == 2)
+            .returning(|_| {
+                let now = Utc::now().naive_utc();
+                Ok(vec![Role::try_new(3, "staff", now, now).unwrap()])
+            });
+        let settings = HubRegistrationSettings {
+            default_roles: vec![RoleId::new(3).unwrap()],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            update_hub_registration_settings(
+                2,
+                settings.clone(),
+                &non_admin_user(),
+                &ClientInfo::default(),
+                &repo
+            ),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            update_hub_registration_settings(
+                5,
+                settings.clone(),
+                &admin_user(),
+                &ClientInfo::default(),
+                &repo
+            ),
+            Err(ServiceError::NotFound)
+        ));
+        assert!(
+            update_hub_registration_settings(
+                2,
+                settings,
+                &admin_user(),
+                &ClientInfo::default(),
+                &repo
+            )
+            .is_ok()
+        );
     }
 }
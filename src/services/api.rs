@@ -1,18 +1,27 @@
-//! API services for retrieving and listing users.
+//! API services for retrieving and listing users and service accounts.
 
+use chrono::Utc;
 use pushkind_common::domain::auth::AuthenticatedUser;
 use pushkind_common::dto::shell::{CurrentUserDto, IamDto, NavigationItemDto};
 use pushkind_common::pagination::DEFAULT_ITEMS_PER_PAGE;
-use pushkind_common::routes::ensure_role;
 use pushkind_common::services::errors::{ServiceError, ServiceResult};
 
-use crate::SERVICE_ACCESS_ROLE;
-use crate::domain::types::{HubId, UserId};
+use crate::domain::permission::Permission;
+use crate::domain::role::inherited_role_ids;
+use crate::domain::types::{HubId, RoleId, UserId};
 use crate::dto::api::{
-    AdminDashboardDto, AdminHubItemDto, AdminMenuItemDto, AdminRoleItemDto, ApiV1UsersQueryParams,
-    HubListItemDto, HubMenuItemDto, UserDto,
+    AdminDashboardDto, AdminDeletedDto, AdminDeletedHubDto, AdminDeletedUserDto, AdminHubItemDto,
+    AdminMenuItemDto, AdminOAuthClientDto, AdminRoleItemDto, AdminRoleMemberDto,
+    AdminServiceAccountDto, AdminSessionDto, AdminSessionsDto, ApiV1IamDto, ApiV1UsersQueryParams,
+    HubListItemDto, HubMenuItemDto, ServiceAccountDto, SigningKeyDto, UserDto,
 };
-use crate::repository::{HubReader, MenuReader, RoleReader, UserListQuery, UserReader};
+use crate::models::config::RetentionConfig;
+use crate::repository::{
+    HubReader, MenuReader, OAuthClientReader, PermissionReader, RoleReader, ServiceAccountReader,
+    SessionReader, UserListQuery, UserReader, UserStatusFilter,
+};
+use crate::services::jwt::JwtKeys;
+use crate::services::permission::{current_permissions, ensure_permission};
 
 /// Returns the authenticated user when `id` is `None`, otherwise
 /// attempts to fetch the user by `id` limited to the current hub.
@@ -34,16 +43,26 @@ pub fn get_user_by_optional_id(
     }
 }
 
-/// Lists users for a hub with optional role filter, search query,
-/// and pagination. Returns only the users (total is ignored upstream).
+/// Lists users for a hub with optional status and role filters, search
+/// query, and pagination. Returns only the users (total is ignored upstream).
+///
+/// Suspended users are left out unless an admin asks for them through the
+/// status filter.
 pub fn list_users(
     query: ApiV1UsersQueryParams,
     current_user: &AuthenticatedUser,
-    repo: &impl UserReader,
+    repo: &(impl UserReader + PermissionReader),
 ) -> ServiceResult<Vec<UserDto>> {
     let hub_id = HubId::new(current_user.hub_id)?;
     let mut list_query = UserListQuery::new(hub_id);
 
+    if let Some(status) = query.status {
+        if status != UserStatusFilter::Active {
+            ensure_permission(current_user, Permission::UsersRead, repo)?;
+        }
+        list_query = list_query.status(status);
+    }
+
     if let Some(role) = &query.role {
         list_query = list_query.role(role);
     }
@@ -66,29 +85,45 @@ pub fn list_users(
     Ok(users)
 }
 
+/// Lists the service accounts of the current user's hub, kept apart from
+/// [`list_users`] since they are not people.
+pub fn list_service_accounts(
+    current_user: &AuthenticatedUser,
+    repo: &impl ServiceAccountReader,
+) -> ServiceResult<Vec<ServiceAccountDto>> {
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let accounts = repo.list_service_accounts(hub_id)?;
+    Ok(accounts.into_iter().map(ServiceAccountDto::from).collect())
+}
+
 /// Lists hubs in a DTO shape suitable for the future `/api/v1/hubs` endpoint.
 pub fn list_hubs(repo: &impl HubReader) -> ServiceResult<Vec<HubListItemDto>> {
     let hubs = repo.list_hubs()?;
     Ok(hubs.into_iter().map(HubListItemDto::from).collect())
 }
 
-/// Builds shared shell data for React-owned auth pages.
+/// Builds shared shell data for React-owned auth pages, with the current
+/// user's permissions.
 pub fn get_shell_data(
     current_user: AuthenticatedUser,
-    repo: &impl HubReader,
-) -> ServiceResult<IamDto> {
+    repo: &(impl HubReader + PermissionReader),
+) -> ServiceResult<ApiV1IamDto> {
     let hub_id = HubId::new(current_user.hub_id)?;
     let hub = repo.get_hub_by_id(hub_id)?.ok_or(ServiceError::NotFound)?;
-
-    Ok(IamDto {
-        current_user: CurrentUserDto::from(current_user),
-        home_url: "/".to_string(),
-        navigation: vec![NavigationItemDto {
-            name: "Главная".to_string(),
-            url: "/".to_string(),
-        }],
-        local_menu_items: Vec::new(),
-        hub_name: hub.name.into_inner(),
+    let permissions = current_permissions(&current_user, repo)?;
+
+    Ok(ApiV1IamDto {
+        shell: IamDto {
+            current_user: CurrentUserDto::from(current_user),
+            home_url: "/".to_string(),
+            navigation: vec![NavigationItemDto {
+                name: "Главная".to_string(),
+                url: "/".to_string(),
+            }],
+            local_menu_items: Vec::new(),
+            hub_name: hub.name.into_inner(),
+        },
+        permissions,
     })
 }
 
@@ -117,19 +152,159 @@ pub fn list_hub_menu_items(
 /// list instead of duplicating that payload inside the admin aggregate.
 pub fn get_admin_dashboard_data(
     current_user: &AuthenticatedUser,
-    repo: &(impl RoleReader + HubReader + MenuReader),
+    repo: &(
+         impl RoleReader
+         + PermissionReader
+         + HubReader
+         + MenuReader
+         + OAuthClientReader
+         + ServiceAccountReader
+     ),
 ) -> ServiceResult<AdminDashboardDto> {
-    ensure_role(current_user, SERVICE_ACCESS_ROLE)?;
+    ensure_permission(current_user, Permission::UsersRead, repo)?;
 
     let hub_id = HubId::new(current_user.hub_id)?;
-    let roles = repo.list_roles()?;
+    let roles = repo.list_roles(hub_id)?;
+    let grants = repo.list_role_permissions()?;
+    let members = repo.count_role_members(hub_id)?;
+    let links = repo.list_role_parents()?;
     let hubs = repo.list_hubs()?;
     let admin_menu = repo.list_menu(hub_id)?;
+    let hub_policy = repo.get_hub_policy(hub_id)?;
+    let oauth_clients = repo.list_oauth_clients()?;
+    let service_accounts = repo.list_service_accounts(hub_id)?;
 
     Ok(AdminDashboardDto {
-        roles: roles.into_iter().map(AdminRoleItemDto::from).collect(),
+        roles: roles
+            .into_iter()
+            .map(|role| {
+                let permissions = grants
+                    .iter()
+                    .filter(|grant| grant.role_id == role.id)
+                    .map(|grant| grant.permission)
+                    .collect();
+                let user_count = members
+                    .iter()
+                    .find(|(role_id, _)| *role_id == role.id)
+                    .map_or(0, |(_, count)| *count);
+                let parents = links
+                    .iter()
+                    .filter(|link| link.role_id == role.id)
+                    .map(|link| link.parent_id.get())
+                    .collect();
+                let inherited = inherited_role_ids(&[role.id], &links)
+                    .into_iter()
+                    .map(|role_id| role_id.get())
+                    .collect();
+                AdminRoleItemDto {
+                    permissions,
+                    user_count,
+                    parents,
+                    inherited,
+                    ..AdminRoleItemDto::from(role)
+                }
+            })
+            .collect(),
+        permissions: Permission::ALL.to_vec(),
         hubs: hubs.into_iter().map(AdminHubItemDto::from).collect(),
         admin_menu: admin_menu.into_iter().map(AdminMenuItemDto::from).collect(),
+        hub_policy,
+        oauth_clients: oauth_clients
+            .into_iter()
+            .map(AdminOAuthClientDto::from)
+            .collect(),
+        service_accounts: service_accounts
+            .into_iter()
+            .map(AdminServiceAccountDto::from)
+            .collect(),
+    })
+}
+
+/// Lists the users of the admin's hub holding a role, suspended ones
+/// included, so the impact of changing the role is visible beforehand.
+///
+/// Returns [`ServiceError::NotFound`] for roles of other hubs.
+pub fn list_role_members(
+    role_id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &(impl RoleReader + UserReader + PermissionReader),
+) -> ServiceResult<Vec<AdminRoleMemberDto>> {
+    ensure_permission(current_user, Permission::UsersRead, repo)?;
+
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let role = repo
+        .get_role_by_id(RoleId::new(role_id)?)?
+        .filter(|role| role.is_visible_in(hub_id))
+        .ok_or(ServiceError::NotFound)?;
+    // Role names are unique among the roles visible in a hub.
+    let query = UserListQuery::new(hub_id)
+        .status(UserStatusFilter::All)
+        .role(role.name.as_str());
+    let (_total, users) = repo.list_users(query)?;
+
+    Ok(users
+        .into_iter()
+        .map(|user| AdminRoleMemberDto::from(user.user))
+        .collect())
+}
+
+/// Reports the accepted JWT keys and the key that signed each live session
+/// of the admin's hub.
+///
+/// Sessions signed with a key that is no longer configured are listed too;
+/// they fail verification and end on their next request.
+pub fn get_admin_sessions(
+    current_user: &AuthenticatedUser,
+    keys: &JwtKeys,
+    repo: &(impl SessionReader + PermissionReader),
+) -> ServiceResult<AdminSessionsDto> {
+    ensure_permission(current_user, Permission::UsersRead, repo)?;
+
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let sessions = repo.list_live_sessions(hub_id, Utc::now().naive_utc())?;
+
+    let signing_keys = keys
+        .key_ids()
+        .map(|(key_id, algorithm)| SigningKeyDto {
+            key_id: key_id.to_string(),
+            algorithm: format!("{algorithm:?}"),
+            active: key_id == keys.active_key_id(),
+            live_sessions: sessions
+                .iter()
+                .filter(|session| session.key_id == key_id)
+                .count(),
+        })
+        .collect();
+
+    Ok(AdminSessionsDto {
+        keys: signing_keys,
+        sessions: sessions.into_iter().map(AdminSessionDto::from).collect(),
+    })
+}
+
+/// Lists the deleted users of the admin's hub and the deleted hubs that can
+/// still be restored, with the time the purge job removes each of them.
+pub fn get_admin_deleted(
+    current_user: &AuthenticatedUser,
+    retention: &RetentionConfig,
+    repo: &(impl UserReader + HubReader + PermissionReader),
+) -> ServiceResult<AdminDeletedDto> {
+    ensure_permission(current_user, Permission::UsersRead, repo)?;
+
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let retention = retention.retention();
+    let users = repo.list_deleted_users(hub_id)?;
+    let hubs = repo.list_deleted_hubs()?;
+
+    Ok(AdminDeletedDto {
+        users: users
+            .into_iter()
+            .map(|user| AdminDeletedUserDto::new(user, retention))
+            .collect(),
+        hubs: hubs
+            .into_iter()
+            .map(|hub| AdminDeletedHubDto::new(hub, retention))
+            .collect(),
     })
 }
 
@@ -138,9 +313,11 @@ mod tests {
     use super::*;
     use crate::domain::hub::Hub;
     use crate::domain::menu::Menu;
-    use crate::domain::role::Role;
+    use crate::domain::permission::RolePermission;
+    use crate::domain::role::{Role, RoleParent};
+    use crate::domain::session::Session;
     use crate::domain::types::{
-        HubId, HubName, MenuId, MenuName, MenuUrl, RoleId, RoleName, UserEmail, UserId,
+        HubId, HubName, MenuId, MenuName, MenuUrl, RoleId, RoleName, SessionId, UserEmail, UserId,
     };
     use crate::domain::user::{User, UserWithRoles};
     use crate::repository::mock::MockRepository;
@@ -247,6 +424,7 @@ mod tests {
         repo.expect_list_users()
             .returning(move |_| Ok((2, vec![u1.clone(), u2.clone()])));
         let params = ApiV1UsersQueryParams {
+            status: None,
             role: None,
             query: None,
             page: None,
@@ -270,6 +448,7 @@ mod tests {
         repo.expect_list_users()
             .returning(move |_| Ok((1, vec![u1.clone()])));
         let params = ApiV1UsersQueryParams {
+            status: None,
             role: None,
             query: Some("user1".into()),
             page: None,
@@ -294,6 +473,7 @@ mod tests {
         repo.expect_list_users()
             .returning(move |_| Ok((1, vec![u2.clone()])));
         let params = ApiV1UsersQueryParams {
+            status: None,
             role: Some("member".into()),
             query: None,
             page: Some(1),
@@ -311,6 +491,41 @@ mod tests {
         assert_eq!(out[0].email, "user2@example.com");
     }
 
+    #[test]
+    fn list_users_suspended_filter_requires_users_read() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|user_id, _| match user_id.get() {
+                1 => Ok(vec![Permission::UsersRead]),
+                _ => Ok(vec![]),
+            });
+        repo.expect_list_users()
+            .withf(|query| query.status == UserStatusFilter::Suspended)
+            .times(1)
+            .returning(|_| Ok((0, vec![])));
+        let params = || ApiV1UsersQueryParams {
+            status: Some(UserStatusFilter::Suspended),
+            role: None,
+            query: None,
+            page: None,
+        };
+        let mut current_user = AuthenticatedUser {
+            sub: "2".into(),
+            email: "user2@example.com".into(),
+            hub_id: 10,
+            name: "User2".into(),
+            roles: vec![],
+            exp: 0,
+        };
+        assert!(matches!(
+            list_users(params(), &current_user, &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+
+        current_user.sub = "1".into();
+        assert!(list_users(params(), &current_user, &repo).is_ok());
+    }
+
     #[test]
     fn list_hubs_maps_to_resource_dtos() {
         let mut repo = MockRepository::new();
@@ -335,6 +550,9 @@ mod tests {
         let hub = make_hub(10, "Main");
         repo.expect_get_hub_by_id()
             .returning(move |_| Ok(Some(hub.clone())));
+        repo.expect_list_user_permissions()
+            .withf(|user_id, hub_id| user_id.get() == 1 && hub_id.get() == 10)
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
 
         let current_user = AuthenticatedUser {
             sub: "1".into(),
@@ -345,13 +563,14 @@ mod tests {
             exp: 123,
         };
 
-        let shell = get_shell_data(current_user, &repo).unwrap();
+        let iam = get_shell_data(current_user, &repo).unwrap();
 
-        assert_eq!(shell.current_user.email, "user1@example.com");
-        assert_eq!(shell.home_url, "/");
-        assert_eq!(shell.hub_name, "Main");
-        assert_eq!(shell.navigation.len(), 1);
-        assert_eq!(shell.navigation[0].name, "Главная");
+        assert_eq!(iam.shell.current_user.email, "user1@example.com");
+        assert_eq!(iam.shell.home_url, "/");
+        assert_eq!(iam.shell.hub_name, "Main");
+        assert_eq!(iam.shell.navigation.len(), 1);
+        assert_eq!(iam.shell.navigation[0].name, "Главная");
+        assert_eq!(iam.permissions, vec![Permission::UsersRead]);
     }
 
     #[test]
@@ -374,15 +593,35 @@ mod tests {
     #[test]
     fn get_admin_dashboard_data_excludes_user_list() {
         let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
         let role = make_role(1, "admin");
         let hub = make_hub(10, "Main");
         let menu = make_menu(1, 10, "Settings", "/settings");
         repo.expect_list_roles()
-            .returning(move || Ok(vec![role.clone()]));
+            .withf(|hub_id| hub_id.get() == 10)
+            .returning(move |_| Ok(vec![role.clone()]));
+        repo.expect_list_role_permissions()
+            .returning(|| Ok(vec![RolePermission::try_new(1, "users.read").unwrap()]));
+        repo.expect_count_role_members()
+            .withf(|hub_id| hub_id.get() == 10)
+            .returning(|_| Ok(vec![(RoleId::new(1).unwrap(), 3)]));
+        repo.expect_list_role_parents().returning(|| {
+            Ok(vec![
+                RoleParent::try_new(1, 2).unwrap(),
+                RoleParent::try_new(2, 3).unwrap(),
+            ])
+        });
         repo.expect_list_hubs()
             .returning(move || Ok(vec![hub.clone()]));
         repo.expect_list_menu()
             .returning(move |_| Ok(vec![menu.clone()]));
+        repo.expect_get_hub_policy()
+            .returning(|_| Ok(Default::default()));
+        repo.expect_list_oauth_clients().returning(|| Ok(vec![]));
+        repo.expect_list_service_accounts()
+            .withf(|hub_id| hub_id.get() == 10)
+            .returning(|_| Ok(vec![]));
 
         let current_user = AuthenticatedUser {
             sub: "1".into(),
@@ -396,7 +635,165 @@ mod tests {
         let dto = get_admin_dashboard_data(&current_user, &repo).unwrap();
 
         assert_eq!(dto.roles.len(), 1);
+        assert_eq!(dto.roles[0].permissions, vec![Permission::UsersRead]);
+        assert_eq!(dto.roles[0].user_count, 3);
+        assert_eq!(dto.roles[0].parents, vec![2]);
+        assert_eq!(dto.roles[0].inherited, vec![2, 3]);
         assert_eq!(dto.hubs.len(), 1);
         assert_eq!(dto.admin_menu.len(), 1);
+        assert!(!dto.hub_policy.require_admin_2fa);
+    }
+
+    fn make_admin() -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: "1".into(),
+            email: "admin@example.com".into(),
+            hub_id: 10,
+            name: "Admin".into(),
+            roles: vec!["admin".into()],
+            exp: 0,
+        }
+    }
+
+    #[test]
+    fn list_role_members_lists_users_of_the_hub_holding_the_role() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
+        repo.expect_get_role_by_id().returning(|_| {
+            Ok(Some(
+                make_role(4, "staff").with_hub(HubId::new(10).unwrap()),
+            ))
+        });
+        let mut member = make_user(2, "user2@example.com", 10);
+        member.user.disabled_at = Some(Utc::now().naive_utc());
+        repo.expect_list_users()
+            .withf(|query| {
+                query.hub_id.get() == 10
+                    && query.status == UserStatusFilter::All
+                    && query.role.as_deref() == Some("staff")
+            })
+            .returning(move |_| Ok((1, vec![member.clone()])));
+
+        let members = list_role_members(4, &make_admin(), &repo).unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].email, "user2@example.com");
+        assert!(members[0].suspended);
+    }
+
+    #[test]
+    fn list_role_members_hides_roles_of_other_hubs() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
+        repo.expect_get_role_by_id().returning(|_| {
+            Ok(Some(
+                make_role(4, "staff").with_hub(HubId::new(11).unwrap()),
+            ))
+        });
+        repo.expect_list_users().never();
+
+        assert!(matches!(
+            list_role_members(4, &make_admin(), &repo),
+            Err(ServiceError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn list_role_members_requires_users_read() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::RolesManage]));
+        repo.expect_get_role_by_id().never();
+        repo.expect_list_users().never();
+
+        assert!(matches!(
+            list_role_members(4, &make_admin(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn get_admin_sessions_reports_signing_key_per_session() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
+        repo.expect_list_live_sessions()
+            .withf(|hub_id, _| hub_id.get() == 10)
+            .returning(|hub_id, now| {
+                Ok(["2026-10", "2026-04", "2026-04"]
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, key_id)| {
+                        Session::new(
+                            SessionId::new(index as i32 + 1).unwrap(),
+                            format!("jti-{index}"),
+                            UserId::new(1).unwrap(),
+                            hub_id,
+                            key_id.to_string(),
+                            now,
+                            now,
+                            None,
+                            None,
+                            None,
+                            None,
+                        )
+                    })
+                    .collect())
+            });
+        let keys = JwtKeys::from_secret("new", "2026-10")
+            .with_retired(&JwtKeys::from_secret("old", "2026-04"))
+            .unwrap();
+
+        let current_user = AuthenticatedUser {
+            sub: "1".into(),
+            email: "admin@example.com".into(),
+            hub_id: 10,
+            name: "Admin".into(),
+            roles: vec!["admin".into()],
+            exp: 0,
+        };
+
+        let dto = get_admin_sessions(&current_user, &keys, &repo).unwrap();
+
+        assert_eq!(dto.sessions.len(), 3);
+        assert_eq!(dto.sessions[1].key_id, "2026-04");
+        assert_eq!(
+            dto.keys,
+            vec![
+                SigningKeyDto {
+                    key_id: "2026-10".into(),
+                    algorithm: "HS256".into(),
+                    active: true,
+                    live_sessions: 1,
+                },
+                SigningKeyDto {
+                    key_id: "2026-04".into(),
+                    algorithm: "HS256".into(),
+                    active: false,
+                    live_sessions: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn get_admin_sessions_requires_users_read() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::MenuManage]));
+        repo.expect_list_live_sessions().never();
+        let current_user = AuthenticatedUser {
+            sub: "2".into(),
+            email: "user@example.com".into(),
+            hub_id: 10,
+            name: "User".into(),
+            roles: vec!["member".into()],
+            exp: 0,
+        };
+
+        let result = get_admin_sessions(&current_user, &JwtKeys::from_secret("s", "k"), &repo);
+
+        assert!(matches!(result, Err(ServiceError::Unauthorized)));
     }
 }
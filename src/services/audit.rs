@@ -0,0 +1,210 @@
+//! Audit log of security-relevant events.
+//!
+//! Services append an event after each sign-in attempt, registration,
+//! recovery request and administrative change, with the client that sent the
+//! request. Administrators read the events of their hub through
+//! `GET /api/v1/admin/audit`.
+
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::pagination::DEFAULT_ITEMS_PER_PAGE;
+use pushkind_common::services::errors::ServiceResult;
+
+use crate::domain::audit::{AuditEventType, NewAuditEvent};
+use crate::domain::permission::Permission;
+use crate::domain::session::ClientInfo;
+use crate::domain::types::{HubId, UserId};
+use crate::domain::user::UpdateUser;
+use crate::dto::api::{AdminAuditDto, AdminAuditEventDto, ApiV1AuditQueryParams};
+use crate::repository::{AuditEventQuery, AuditReader, PermissionReader};
+use crate::services::permission::ensure_permission;
+
+/// Starts an event caused by `current_user` in their hub.
+pub(crate) fn event_by(
+    current_user: &AuthenticatedUser,
+    event_type: AuditEventType,
+    client: &ClientInfo,
+) -> ServiceResult<NewAuditEvent> {
+    let event = NewAuditEvent::new(HubId::new(current_user.hub_id)?, event_type, client);
+    let actor_id = current_user
+        .sub
+        .parse()
+        .ok()
+        .and_then(|id| UserId::new(id).ok());
+    Ok(match actor_id {
+        Some(actor_id) => event.with_actor(actor_id),
+        None => event,
+    })
+}
+
+/// Describes an update of a user, e.g. `name: Bob; roles: 2, 3; password`.
+/// Only the fact that the password changed is recorded, never the password.
+pub(crate) fn user_update_detail(updates: &UpdateUser) -> String {
+    let mut parts = vec![format!("name: {}", updates.name.as_str())];
+    if let Some(roles) = &updates.roles {
+        let roles = roles
+            .iter()
+            .map(|role_id| role_id.get().to_string())
+            .collect::<Vec<_>>();
+        parts.push(format!("roles: {}", roles.join(", ")));
+    }
+    if updates.password.is_some() {
+        parts.push("password".to_string());
+    }
+    if let Some(must_change_password) = updates.must_change_password {
+        parts.push(format!("must_change_password: {must_change_password}"));
+    }
+    parts.join("; ")
+}
+
+/// Lists the events of the admin's hub, newest first, filtered by type,
+/// actor, target and time and split into pages.
+pub fn list_audit_events(
+    params: ApiV1AuditQueryParams,
+    current_user: &AuthenticatedUser,
+    repo: &(impl AuditReader + PermissionReader),
+) -> ServiceResult<AdminAuditDto> {
+    ensure_permission(current_user, Permission::UsersRead, repo)?;
+
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let page = params.page.unwrap_or(1).max(1);
+    let mut query = AuditEventQuery::new(hub_id).paginate(page, DEFAULT_ITEMS_PER_PAGE);
+    if let Some(event_type) = params.event_type {
+        query = query.event_type(event_type);
+    }
+    if let Some(actor_id) = params.actor_id {
+        query = query.actor(UserId::new(actor_id)?);
+    }
+    if let Some(target_id) = params.target_id {
+        query = query.target(target_id);
+    }
+    if let Some(since) = params.since {
+        query = query.since(since);
+    }
+    if let Some(until) = params.until {
+        query = query.until(until);
+    }
+
+    let (total, events) = repo.list_audit_events(query)?;
+    Ok(AdminAuditDto {
+        total,
+        page,
+        per_page: DEFAULT_ITEMS_PER_PAGE,
+        events: events.into_iter().map(AdminAuditEventDto::from).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use pushkind_common::services::errors::ServiceError;
+
+    use crate::domain::audit::AuditEvent;
+    use crate::domain::types::{RoleId, UserName, UserPassword};
+    use crate::repository::mock::MockRepository;
+
+    fn make_admin() -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: "1".into(),
+            email: "admin@example.com".into(),
+            hub_id: 10,
+            name: "Admin".into(),
+            roles: vec!["admin".into()],
+            exp: 0,
+        }
+    }
+
+    fn make_params() -> ApiV1AuditQueryParams {
+        ApiV1AuditQueryParams {
+            event_type: None,
+            actor_id: None,
+            target_id: None,
+            since: None,
+            until: None,
+            page: None,
+        }
+    }
+
+    #[test]
+    fn event_by_records_the_current_user_and_client() {
+        let client = ClientInfo {
+            user_agent: Some("agent".into()),
+            ip_address: Some("10.0.0.1".into()),
+        };
+        let event = event_by(&make_admin(), AuditEventType::RoleCreated, &client).unwrap();
+        assert_eq!(event.hub_id.get(), 10);
+        assert_eq!(event.actor_id, Some(UserId::new(1).unwrap()));
+        assert_eq!(event.ip_address.as_deref(), Some("10.0.0.1"));
+        assert_eq!(event.user_agent.as_deref(), Some("agent"));
+    }
+
+    #[test]
+    fn user_update_detail_leaves_out_the_password() {
+        let updates = UpdateUser::new(
+            UserName::new("Bob").unwrap(),
+            Some(UserPassword::new("secret").unwrap()),
+            Some(vec![RoleId::new(2).unwrap(), RoleId::new(3).unwrap()]),
+        );
+        assert_eq!(
+            user_update_detail(&updates),
+            "name: Bob; roles: 2, 3; password"
+        );
+    }
+
+    #[test]
+    fn list_audit_events_applies_filters_to_the_admins_hub() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
+        repo.expect_list_audit_events()
+            .withf(|query| {
+                query.hub_id.get() == 10
+                    && query.event_type == Some(AuditEventType::LoginFailed)
+                    && query.actor_id.is_none()
+                    && query.target_id == Some(5)
+                    && query
+                        .pagination
+                        .as_ref()
+                        .is_some_and(|pagination| pagination.page == 2)
+            })
+            .returning(|_| {
+                let event = AuditEvent::try_new(
+                    7,
+                    10,
+                    None,
+                    "login_failed",
+                    Some(5),
+                    Some("user@example.com".into()),
+                    None,
+                    None,
+                    Utc::now().naive_utc(),
+                )
+                .unwrap();
+                Ok((21, vec![event]))
+            });
+
+        let params = ApiV1AuditQueryParams {
+            event_type: Some(AuditEventType::LoginFailed),
+            target_id: Some(5),
+            page: Some(2),
+            ..make_params()
+        };
+        let audit = list_audit_events(params, &make_admin(), &repo).unwrap();
+        assert_eq!(audit.total, 21);
+        assert_eq!(audit.page, 2);
+        assert_eq!(audit.events[0].event_type, AuditEventType::LoginFailed);
+    }
+
+    #[test]
+    fn list_audit_events_requires_users_read() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersWrite]));
+        repo.expect_list_audit_events().never();
+
+        assert!(matches!(
+            list_audit_events(make_params(), &make_admin(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+}
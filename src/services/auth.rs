@@ -1,6 +1,9 @@
-//! Authentication services for logging in users, registering new accounts, and listing hubs.
+//! Authentication services for logging in users, registering new accounts,
+//! verifying their email addresses, and listing hubs.
 
+use chrono::{DateTime, Duration, Utc};
 use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::repository::errors::RepositoryError;
 use pushkind_common::services::errors::{ServiceError, ServiceResult};
 use pushkind_common::zmq::{ZmqSender, ZmqSenderExt};
 use pushkind_emailer::domain::email::{NewEmail, NewEmailRecipient};
@@ -8,16 +11,202 @@ use pushkind_emailer::domain::types::{
     EmailBody, EmailSubject, HubId as EmailHubId, RecipientEmail, RecipientName,
 };
 use pushkind_emailer::models::zmq::ZMQSendEmailMessage;
+use thiserror::Error;
 
-use crate::domain::types::{HubId, UserEmail};
-use crate::dto::auth::SessionTokenDto;
-use crate::forms::auth::{LoginPayload, RecoverPayload, RegisterPayload};
-use crate::repository::{HubReader, UserReader, UserWriter};
+use crate::SERVICE_ACCESS_ROLE;
+use crate::domain::audit::{AuditEventType, NewAuditEvent};
+use crate::domain::email_verification::NewEmailVerification;
+use crate::domain::hub::RegistrationMode;
+use crate::domain::password_reset::NewPasswordReset;
+use crate::domain::session::{ClientInfo, NewSession};
+use crate::domain::types::{HubId, UserId, UserPassword};
+use crate::domain::user::{NewUser, UserWithRoles};
+use crate::dto::auth::{PendingLoginDto, SessionTokenDto};
+use crate::forms::auth::{
+    LoginPayload, RecoverPayload, RegisterPayload, ResendVerificationPayload, ResetPasswordPayload,
+};
+use crate::models::config::LoginThrottleConfig;
+use crate::repository::{
+    AuditWriter, EmailVerificationWriter, HubReader, LoginThrottleReader, LoginThrottleWriter,
+    PasswordResetWriter, PermissionReader, SessionWriter, TwoFactorReader, TwoFactorWriter,
+    UserReader, UserWriter,
+};
+use crate::services::jwt::{JwtKeys, SessionClaims};
+use crate::services::permission::permissions_claim;
+use crate::services::tokens::{generate_token, hash_token};
+use crate::services::two_factor;
+
+/// Lifetime of a password recovery link.
+const RECOVERY_TOKEN_TTL_HOURS: i64 = 24;
+/// Lifetime of an email verification link.
+const VERIFICATION_TOKEN_TTL_HOURS: i64 = 72;
+/// Time allowed between the password and the second factor or password
+/// change steps.
+const PENDING_LOGIN_TTL_MINUTES: i64 = 10;
+
+/// Result of a successful first authentication step.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoginOutcome {
+    /// The user is fully authenticated.
+    Authenticated(SessionTokenDto),
+    /// A TOTP or recovery code is required, or has to be enrolled first when
+    /// [`PendingLoginDto::setup_required`] is set.
+    SecondFactorRequired(PendingLoginDto),
+    /// An admin requires the user to choose a new password before signing
+    /// in; see [`change_password_and_issue_token`].
+    PasswordChangeRequired(PendingLoginDto),
+}
+
+/// Errors returned by [`login_and_issue_token`].
+#[derive(Debug, Error)]
+pub enum LoginError {
+    /// Too many failed attempts; the caller must wait `retry_after_seconds`.
+    #[error("too many login attempts, retry in {retry_after_seconds} seconds")]
+    Throttled { retry_after_seconds: i64 },
+    /// The hub requires a verified email address and the user has none yet.
+    #[error("email address is not verified")]
+    EmailNotVerified,
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<RepositoryError> for LoginError {
+    fn from(err: RepositoryError) -> Self {
+        Self::Service(err.into())
+    }
+}
+
+/// Errors returned by [`register_user`].
+#[derive(Debug, Error)]
+pub enum RegistrationError {
+    /// The hub does not accept new accounts.
+    #[error("registration is closed")]
+    Closed,
+    /// The hub only accepts invited users.
+    #[error("registration is by invitation only")]
+    InviteOnly,
+    /// The address is outside the email domains allowed by the hub.
+    #[error("email domain is not allowed")]
+    EmailDomainNotAllowed,
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<RepositoryError> for RegistrationError {
+    fn from(err: RepositoryError) -> Self {
+        Self::Service(err.into())
+    }
+}
+
+/// Persists a new user from a validated payload and returns it.
+///
+/// The registration settings of the hub decide whether the hub accepts new
+/// accounts and from which email domains; the user receives the hub's
+/// default roles. The address starts out unverified; callers send the link
+/// with [`send_verification_email`].
+pub fn register_user(
+    payload: RegisterPayload,
+    client: &ClientInfo,
+    repo: &(impl HubReader + UserReader + UserWriter + AuditWriter),
+) -> Result<UserWithRoles, RegistrationError> {
+    let settings = repo.get_hub_registration_settings(payload.hub_id)?;
+    match settings.registration_mode {
+        RegistrationMode::Open => {}
+        RegistrationMode::Closed => return Err(RegistrationError::Closed),
+        RegistrationMode::InviteOnly => return Err(RegistrationError::InviteOnly),
+    }
+    if !settings.allows_email(&payload.email) {
+        return Err(RegistrationError::EmailDomainNotAllowed);
+    }
+
+    let new_user = NewUser::from(payload).with_roles(settings.default_roles);
+    let user = repo.create_user(&new_user)?;
+    repo.record_audit_event(
+        &NewAuditEvent::new(user.hub_id, AuditEventType::UserRegistered, client)
+            .with_actor(user.id)
+            .with_target(user.id.get())
+            .with_detail(user.email.as_str()),
+    )?;
+    // Loaded again so that roles implied by the default roles are included.
+    let user_roles = repo
+        .get_user_by_id(user.id, user.hub_id)?
+        .ok_or(RepositoryError::NotFound)?;
+    Ok(user_roles)
+}
+
+/// Emails a single-use link that verifies the address of `user_roles`.
+///
+/// Issuing a link invalidates any link sent earlier to the same user.
+pub async fn send_verification_email(
+    user_roles: UserWithRoles,
+    base_url: &str,
+    zmq_sender: &ZmqSender,
+    repo: &impl EmailVerificationWriter,
+) -> ServiceResult<()> {
+    let token = generate_token();
+    let expires_at = Utc::now().naive_utc() + Duration::hours(VERIFICATION_TOKEN_TTL_HOURS);
+    repo.create_email_verification(&NewEmailVerification::new(
+        user_roles.user.id,
+        user_roles.user.hub_id,
+        hash_token(&token),
+        expires_at,
+    ))?;
+    let verification_url = format!("{}/auth/verify?token={}", base_url, token);
+
+    let hub_id = user_roles.user.hub_id;
+    let user: AuthenticatedUser = user_roles.into();
+
+    let new_email = NewEmail {
+        message: EmailBody::new(
+            "Чтобы подтвердить адрес электронной почты, перейдите по ссылке: {verification_url}\nЕсли вы не регистрировались, проигнорируйте это письмо.",
+        )?,
+        subject: Some(EmailSubject::new("Подтверждение адреса электронной почты")?),
+        attachment: None,
+        attachment_name: None,
+        attachment_mime: None,
+        hub_id: EmailHubId::new(hub_id.get())?,
+        recipients: vec![NewEmailRecipient {
+            address: RecipientEmail::new(&user.email)?,
+            name: RecipientName::new(&user.name)?,
+            fields: std::iter::once(("verification_url".to_string(), verification_url)).collect(),
+        }],
+    };
 
-/// Persists a new user from a validated payload.
-pub fn register_user(payload: RegisterPayload, repo: &impl UserWriter) -> ServiceResult<()> {
-    let new_user = payload.into();
-    repo.create_user(&new_user)?;
+    let zmq_message = ZMQSendEmailMessage::NewEmail(Box::new((user, new_email)));
+    zmq_sender
+        .send_json(&zmq_message)
+        .await
+        .map_err(|_| ServiceError::Internal)?;
+    Ok(())
+}
+
+/// Sends a new verification link to a registered user.
+///
+/// Returns [`ServiceError::NotFound`] for unknown users and
+/// [`ServiceError::Conflict`] when the address is already verified.
+pub async fn resend_verification_email(
+    payload: ResendVerificationPayload,
+    base_url: &str,
+    zmq_sender: &ZmqSender,
+    repo: &(impl UserReader + EmailVerificationWriter),
+) -> ServiceResult<()> {
+    let user_roles = repo
+        .get_user_by_email(&payload.email, payload.hub_id)?
+        .ok_or(ServiceError::NotFound)?;
+    if user_roles.user.is_email_verified() {
+        return Err(ServiceError::Conflict);
+    }
+    send_verification_email(user_roles, base_url, zmq_sender, repo).await
+}
+
+/// Redeems a verification token and marks the address of its user as
+/// verified.
+///
+/// Returns [`ServiceError::Unauthorized`] when the token is unknown, already
+/// used, or expired.
+pub fn verify_email(token: &str, repo: &impl EmailVerificationWriter) -> ServiceResult<()> {
+    repo.consume_email_verification(&hash_token(token), Utc::now().naive_utc())?
+        .ok_or(ServiceError::Unauthorized)?;
     Ok(())
 }
 
@@ -26,70 +215,418 @@ pub fn list_hubs(repo: &impl HubReader) -> ServiceResult<Vec<crate::domain::hub:
     Ok(repo.list_hubs()?)
 }
 
-/// Encodes the provided claims into a JWT using the given secret.
-pub fn issue_jwt(user: &AuthenticatedUser, secret: &str) -> ServiceResult<SessionTokenDto> {
-    user.to_jwt(secret)
-        .map(SessionTokenDto::from)
-        .map_err(|_| ServiceError::Internal)
-}
-
-/// Verifies an incoming token and reissues a new session token
-/// with the provided expiration in days.
-pub fn reissue_session_from_token(
-    token: &str,
-    expiration_days: i64,
-    secret: &str,
-    repo: &impl UserReader,
+/// Encodes the provided claims into a JWT signed with the active key and
+/// records the session together with the id of that key and the client it
+/// was issued to.
+///
+/// The token lists the user's permissions when the keys are configured to
+/// include them. The session is recorded in the audit log as `event_type`,
+/// which tells how the user got it.
+pub fn issue_jwt(
+    user: &AuthenticatedUser,
+    event_type: AuditEventType,
+    client: &ClientInfo,
+    keys: &JwtKeys,
+    repo: &(impl SessionWriter + AuditWriter + PermissionReader),
 ) -> ServiceResult<SessionTokenDto> {
-    let mut user =
-        AuthenticatedUser::from_jwt(token, secret).map_err(|_| ServiceError::Unauthorized)?;
-    // Ensure the user still exists and belongs to the hub before issuing a new session
-    let email = UserEmail::new(&user.email)?;
+    let user_id: i32 = user.sub.parse().map_err(|_| ServiceError::Internal)?;
+    let expires_at = DateTime::from_timestamp(user.exp as i64, 0)
+        .ok_or(ServiceError::Internal)?
+        .naive_utc();
+    let jti = generate_token();
+
+    let token = keys.encode(&SessionClaims {
+        user: user.clone(),
+        jti: Some(jti.clone()),
+        permissions: permissions_claim(user, keys, repo)?,
+    })?;
+    let user_id = UserId::new(user_id)?;
     let hub_id = HubId::new(user.hub_id)?;
-    match repo.get_user_by_email(&email, hub_id)? {
-        Some(_) => {
-            user.set_expiration(expiration_days);
-            issue_jwt(&user, secret)
-        }
-        None => Err(ServiceError::Unauthorized),
+    repo.create_session(&NewSession::new(
+        jti,
+        user_id,
+        hub_id,
+        keys.active_key_id().to_string(),
+        expires_at,
+        client.clone(),
+    ))?;
+    repo.record_audit_event(
+        &NewAuditEvent::new(hub_id, event_type, client)
+            .with_actor(user_id)
+            .with_target(user_id.get()),
+    )?;
+    Ok(token.into())
+}
+
+/// Refuses users whose address is not verified yet when their hub requires
+/// verification. Applies to every sign-in method the user chooses.
+pub(crate) fn ensure_email_verified(
+    user_roles: &UserWithRoles,
+    repo: &impl HubReader,
+) -> Result<(), LoginError> {
+    if !user_roles.user.is_email_verified()
+        && repo
+            .get_hub_policy(user_roles.user.hub_id)?
+            .require_email_verification
+    {
+        return Err(LoginError::EmailNotVerified);
     }
+    Ok(())
 }
 
-/// Performs login and issues a session JWT from a validated payload.
-pub fn login_and_issue_token(
-    payload: LoginPayload,
-    secret: &str,
-    repo: &impl UserReader,
-) -> ServiceResult<SessionTokenDto> {
+/// Sends users who have to change their password to that step before they
+/// get a session or a second factor prompt.
+pub(crate) fn password_change_step(user_roles: &UserWithRoles) -> Option<LoginOutcome> {
+    if !user_roles.user.must_change_password {
+        return None;
+    }
+    let expires_at = Utc::now() + Duration::minutes(PENDING_LOGIN_TTL_MINUTES);
+    Some(LoginOutcome::PasswordChangeRequired(PendingLoginDto {
+        user_id: user_roles.user.id.get(),
+        hub_id: user_roles.user.hub_id.get(),
+        email: user_roles.user.email.as_str().to_string(),
+        setup_required: false,
+        expires_at: expires_at.timestamp(),
+    }))
+}
+
+/// Issues a session for a user who passed the first factor, or asks for the
+/// second factor when the user has one or the hub policy requires it.
+///
+/// Users who have to change their password are sent to that step first.
+/// Suspended users are refused with [`ServiceError::Unauthorized`]. A session
+/// issued right away is audited as `event_type`.
+pub(crate) fn finish_login(
+    user_roles: UserWithRoles,
+    event_type: AuditEventType,
+    client: &ClientInfo,
+    keys: &JwtKeys,
+    repo: &(impl HubReader + TwoFactorReader + SessionWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<LoginOutcome> {
+    if user_roles.user.is_disabled() {
+        return Err(ServiceError::Unauthorized);
+    }
+    if let Some(outcome) = password_change_step(&user_roles) {
+        return Ok(outcome);
+    }
+    let user_id = user_roles.user.id;
+    let hub_id = user_roles.user.hub_id;
+    let has_totp = repo
+        .get_user_totp(user_id)?
+        .is_some_and(|totp| totp.is_enabled());
+    let is_admin = user_roles
+        .roles
+        .iter()
+        .chain(&user_roles.inherited_roles)
+        .any(|role| role.name.as_str() == SERVICE_ACCESS_ROLE);
+
+    if has_totp || two_factor::is_required(hub_id, is_admin, repo)? {
+        let expires_at = Utc::now() + Duration::minutes(PENDING_LOGIN_TTL_MINUTES);
+        return Ok(LoginOutcome::SecondFactorRequired(PendingLoginDto {
+            user_id: user_id.get(),
+            hub_id: hub_id.get(),
+            email: user_roles.user.email.as_str().to_string(),
+            setup_required: !has_totp,
+            expires_at: expires_at.timestamp(),
+        }));
+    }
+
+    let claims = AuthenticatedUser::from(user_roles);
+    Ok(LoginOutcome::Authenticated(issue_jwt(
+        &claims, event_type, client, keys, repo,
+    )?))
+}
+
+/// Loads the user behind a pending login that has not expired yet, unless
+/// the user was suspended in the meantime or still has to change the
+/// password.
+fn pending_user(pending: &PendingLoginDto, repo: &impl UserReader) -> ServiceResult<UserWithRoles> {
+    if pending.expires_at <= Utc::now().timestamp() {
+        return Err(ServiceError::Unauthorized);
+    }
+    repo.get_user_by_id(UserId::new(pending.user_id)?, HubId::new(pending.hub_id)?)?
+        .filter(|user_roles| {
+            !user_roles.user.is_disabled() && !user_roles.user.must_change_password
+        })
+        .ok_or(ServiceError::Unauthorized)
+}
+
+/// Stores the new password of a pending login that an admin required to
+/// change it, then continues the login.
+///
+/// Returns [`ServiceError::Unauthorized`] when the pending login expired or
+/// does not need a new password, and [`ServiceError::Conflict`] when the new
+/// password equals the current one. Other sessions of the user are revoked.
+/// The second factor, if any, is still required afterwards.
+pub fn change_password_and_issue_token<R>(
+    pending: &PendingLoginDto,
+    password: &UserPassword,
+    client: &ClientInfo,
+    keys: &JwtKeys,
+    repo: &R,
+) -> ServiceResult<LoginOutcome>
+where
+    R: UserReader
+        + UserWriter
+        + HubReader
+        + TwoFactorReader
+        + SessionWriter
+        + AuditWriter
+        + PermissionReader,
+{
+    if pending.expires_at <= Utc::now().timestamp() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let user_id = UserId::new(pending.user_id)?;
+    let hub_id = HubId::new(pending.hub_id)?;
     let user_roles = repo
-        .login(&payload.email, &payload.password, payload.hub_id)?
+        .get_user_by_id(user_id, hub_id)?
+        .filter(|user_roles| !user_roles.user.is_disabled() && user_roles.user.must_change_password)
         .ok_or(ServiceError::Unauthorized)?;
+    if repo.verify_password(password.as_str(), &user_roles.user.password_hash) {
+        return Err(ServiceError::Conflict);
+    }
+
+    let user = repo.update_password(user_id, hub_id, password)?;
+    repo.revoke_user_sessions(user_id, Utc::now().naive_utc())?;
+    finish_login(
+        UserWithRoles::new(user, user_roles.roles).with_inherited_roles(user_roles.inherited_roles),
+        AuditEventType::LoginSucceeded,
+        client,
+        keys,
+        repo,
+    )
+}
+
+/// Completes a pending login with a TOTP or recovery code.
+///
+/// Wrong codes are throttled per user with the account policy, so the six
+/// digit code cannot be brute-forced within its validity window.
+pub fn complete_second_factor_login<R>(
+    pending: &PendingLoginDto,
+    code: &str,
+    client: &ClientInfo,
+    throttle: &LoginThrottleConfig,
+    keys: &JwtKeys,
+    repo: &R,
+) -> Result<SessionTokenDto, LoginError>
+where
+    R: UserReader
+        + TwoFactorReader
+        + TwoFactorWriter
+        + LoginThrottleReader
+        + LoginThrottleWriter
+        + SessionWriter
+        + AuditWriter
+        + PermissionReader,
+{
+    let now = Utc::now().naive_utc();
+    let key = format!("2fa:{}", pending.user_id);
+    if let Some(locked_for) = repo
+        .get_login_throttle(&key)?
+        .and_then(|state| state.locked_for(now))
+    {
+        return Err(LoginError::Throttled {
+            retry_after_seconds: locked_for.num_seconds().max(1),
+        });
+    }
+
+    let user_roles = pending_user(pending, repo)?;
+    if !two_factor::verify_code(user_roles.user.id, code, repo)? {
+        repo.record_login_failure(&key, now, &throttle.account_policy())?;
+        repo.record_audit_event(
+            &NewAuditEvent::new(user_roles.user.hub_id, AuditEventType::LoginFailed, client)
+                .with_actor(user_roles.user.id)
+                .with_target(user_roles.user.id.get())
+                .with_detail("second_factor"),
+        )?;
+        return Err(ServiceError::Unauthorized.into());
+    }
+    repo.clear_login_throttle(&key)?;
+
+    let claims = AuthenticatedUser::from(user_roles);
+    Ok(issue_jwt(
+        &claims,
+        AuditEventType::LoginSucceeded,
+        client,
+        keys,
+        repo,
+    )?)
+}
+
+/// Issues a session for a pending login once the user has enrolled the
+/// second factor that the hub policy requires.
+pub fn complete_second_factor_setup(
+    pending: &PendingLoginDto,
+    client: &ClientInfo,
+    keys: &JwtKeys,
+    repo: &(impl UserReader + TwoFactorReader + SessionWriter + AuditWriter + PermissionReader),
+) -> ServiceResult<SessionTokenDto> {
+    let user_roles = pending_user(pending, repo)?;
+    if !repo
+        .get_user_totp(user_roles.user.id)?
+        .is_some_and(|totp| totp.is_enabled())
+    {
+        return Err(ServiceError::Unauthorized);
+    }
     let claims = AuthenticatedUser::from(user_roles);
-    issue_jwt(&claims, secret)
+    issue_jwt(&claims, AuditEventType::LoginSucceeded, client, keys, repo)
+}
+
+/// Redeems a recovery token, stores the new password, and starts a session.
+///
+/// The token is only consumed together with the new password, so a failed
+/// update leaves the link usable. Sessions started with the old password are
+/// revoked. Returns [`ServiceError::Unauthorized`] when the token is unknown,
+/// already used, or expired.
+pub fn reset_password_and_issue_token<R>(
+    payload: ResetPasswordPayload,
+    client: &ClientInfo,
+    keys: &JwtKeys,
+    repo: &R,
+) -> ServiceResult<LoginOutcome>
+where
+    R: UserReader
+        + PasswordResetWriter
+        + HubReader
+        + TwoFactorReader
+        + SessionWriter
+        + AuditWriter
+        + PermissionReader,
+{
+    let now = Utc::now().naive_utc();
+    let reset = repo
+        .reset_password(&hash_token(&payload.token), &payload.password, now)?
+        .ok_or(ServiceError::Unauthorized)?;
+    repo.revoke_user_sessions(reset.user_id, now)?;
+    let user_roles = repo
+        .get_user_by_id(reset.user_id, reset.hub_id)?
+        .ok_or(ServiceError::Unauthorized)?;
+    finish_login(
+        user_roles,
+        AuditEventType::PasswordReset,
+        client,
+        keys,
+        repo,
+    )
+}
+
+/// Performs login and issues a session JWT from a validated payload.
+///
+/// Failed attempts are counted per account and per client IP and recorded in
+/// the audit log. Once either counter reaches its threshold further attempts
+/// are rejected with [`LoginError::Throttled`] until the lockout expires, even
+/// when the password is correct. A successful login clears the account
+/// counter and invalidates any outstanding recovery links. Users whose address
+/// is not verified yet are refused with [`LoginError::EmailNotVerified`] when
+/// the hub requires verification. Users with a second factor, or admins of
+/// hubs that require one, get [`LoginOutcome::SecondFactorRequired`] instead
+/// of a session.
+pub fn login_and_issue_token<R>(
+    payload: LoginPayload,
+    client: &ClientInfo,
+    throttle: &LoginThrottleConfig,
+    keys: &JwtKeys,
+    repo: &R,
+) -> Result<LoginOutcome, LoginError>
+where
+    R: UserReader
+        + PasswordResetWriter
+        + LoginThrottleReader
+        + LoginThrottleWriter
+        + HubReader
+        + TwoFactorReader
+        + SessionWriter
+        + AuditWriter
+        + PermissionReader,
+{
+    let now = Utc::now().naive_utc();
+    let account_key = format!(
+        "account:{}:{}",
+        payload.hub_id.get(),
+        payload.email.as_str()
+    );
+    let mut throttle_keys = vec![(account_key.clone(), throttle.account_policy())];
+    if let Some(ip) = &client.ip_address {
+        throttle_keys.push((format!("ip:{ip}"), throttle.ip_policy()));
+    }
+
+    for (key, _) in &throttle_keys {
+        let locked_for = repo
+            .get_login_throttle(key)?
+            .and_then(|state| state.locked_for(now));
+        if let Some(locked_for) = locked_for {
+            return Err(LoginError::Throttled {
+                retry_after_seconds: locked_for.num_seconds().max(1),
+            });
+        }
+    }
+
+    let user_roles = match repo.login(&payload.email, &payload.password, payload.hub_id)? {
+        Some(user_roles) => user_roles,
+        None => {
+            for (key, policy) in &throttle_keys {
+                repo.record_login_failure(key, now, policy)?;
+            }
+            repo.record_audit_event(
+                &NewAuditEvent::new(payload.hub_id, AuditEventType::LoginFailed, client)
+                    .with_detail(payload.email.as_str()),
+            )?;
+            return Err(ServiceError::Unauthorized.into());
+        }
+    };
+    repo.clear_login_throttle(&account_key)?;
+    ensure_email_verified(&user_roles, repo)?;
+    repo.invalidate_password_resets(user_roles.user.id)?;
+    Ok(finish_login(
+        user_roles,
+        AuditEventType::LoginSucceeded,
+        client,
+        keys,
+        repo,
+    )?)
 }
 
 /// Sends a recovery email using a validated payload.
+///
+/// Issues a fresh single-use recovery token, which invalidates any link sent
+/// earlier to the same user.
 pub async fn send_recovery_email(
     payload: RecoverPayload,
     base_url: &str,
     zmq_sender: &ZmqSender,
-    repo: &impl UserReader,
-    secret: &str,
+    client: &ClientInfo,
+    repo: &(impl UserReader + PasswordResetWriter + AuditWriter),
 ) -> ServiceResult<()> {
-    let mut user: AuthenticatedUser =
-        match repo.get_user_by_email(&payload.email, payload.hub_id)? {
-            Some(user) => user.into(),
-            None => return Err(ServiceError::NotFound),
-        };
+    let user_roles = match repo.get_user_by_email(&payload.email, payload.hub_id)? {
+        Some(user) => user,
+        None => return Err(ServiceError::NotFound),
+    };
+
+    let token = generate_token();
+    let expires_at = Utc::now().naive_utc() + Duration::hours(RECOVERY_TOKEN_TTL_HOURS);
+    repo.create_password_reset(&NewPasswordReset::new(
+        user_roles.user.id,
+        user_roles.user.hub_id,
+        hash_token(&token),
+        expires_at,
+    ))?;
+    repo.record_audit_event(
+        &NewAuditEvent::new(
+            user_roles.user.hub_id,
+            AuditEventType::RecoveryRequested,
+            client,
+        )
+        .with_target(user_roles.user.id.get())
+        .with_detail(payload.email.as_str()),
+    )?;
+    let recovery_url = format!("{}/auth/reset?token={}", base_url, token);
 
-    // 1-day token for recovery
-    user.set_expiration(1);
-    let jwt = issue_jwt(&user, secret)?;
-    let recovery_url = format!("{}/auth/login?token={}", base_url, jwt.token);
+    let user: AuthenticatedUser = user_roles.into();
 
     let new_email = NewEmail {
         message: EmailBody::new(
-            "Для входа в систему перейдите по ссылке: {recovery_url}\nЕсли вы не запрашивали восстановление, проигнорируйте это письмо.",
+            "Чтобы задать новый пароль, перейдите по ссылке: {recovery_url}\nЕсли вы не запрашивали восстановление, проигнорируйте это письмо.",
         )?,
         subject: Some(EmailSubject::new("Восстановление пароля")?),
         attachment: None,
@@ -114,9 +651,16 @@ pub async fn send_recovery_email(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::hub::Hub;
-    use crate::domain::types::{HubId, HubName, UserEmail, UserId, UserName};
-    use crate::domain::user::{User, UserWithRoles};
+    use crate::domain::hub::{Hub, HubPolicy, HubRegistrationSettings};
+    use crate::domain::login_throttle::LoginThrottle;
+    use crate::domain::password_reset::PasswordReset;
+    use crate::domain::role::Role;
+    use crate::domain::session::Session;
+    use crate::domain::two_factor::UserTotp;
+    use crate::domain::types::{
+        HubName, RoleId, RoleName, SessionId, UserEmail, UserName, UserPassword,
+    };
+    use crate::domain::user::User;
     use crate::forms::auth::{LoginPayload, RegisterPayload};
     use crate::repository::mock::MockRepository;
     use bcrypt::{DEFAULT_COST, hash};
@@ -127,9 +671,58 @@ mod tests {
         "my_secret_key".to_string()
     }
 
+    fn make_keys() -> JwtKeys {
+        JwtKeys::from_secret(&make_secret(), "default")
+    }
+
+    fn make_client() -> ClientInfo {
+        ClientInfo {
+            user_agent: Some("test-agent".to_string()),
+            ip_address: Some("127.0.0.1".to_string()),
+        }
+    }
+
+    fn expect_session(repo: &mut MockRepository) {
+        expect_session_audited_as(repo, AuditEventType::LoginSucceeded);
+    }
+
+    fn expect_session_audited_as(repo: &mut MockRepository, event_type: AuditEventType) {
+        repo.expect_create_session()
+            .withf(|new_session| {
+                new_session.key_id == "default"
+                    && !new_session.jti.is_empty()
+                    && new_session.user_agent.as_deref() == Some("test-agent")
+            })
+            .times(1)
+            .returning(|new_session| {
+                Ok(Session::new(
+                    SessionId::new(1).unwrap(),
+                    new_session.jti.clone(),
+                    new_session.user_id,
+                    new_session.hub_id,
+                    new_session.key_id.clone(),
+                    Utc::now().naive_utc(),
+                    new_session.expires_at,
+                    new_session.user_agent.clone(),
+                    new_session.ip_address.clone(),
+                    None,
+                    None,
+                ))
+            });
+        repo.expect_record_audit_event()
+            .withf(move |event| {
+                event.event_type == event_type
+                    && event.actor_id.is_some()
+                    && event.target_id == event.actor_id.map(|id| id.get())
+                    && event.user_agent.as_deref() == Some("test-agent")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+    }
+
     fn make_user(id: i32, email: &str, hub_id: i32) -> UserWithRoles {
         let now = Utc::now().naive_utc();
-        let user = User::new(
+        let mut user = User::new(
             UserId::new(id).unwrap(),
             UserEmail::new(email).unwrap(),
             Some(UserName::new("User").unwrap()),
@@ -139,15 +732,126 @@ mod tests {
             now,
             vec![],
         );
+        user.email_verified_at = Some(now);
         UserWithRoles::new(user, vec![])
     }
 
+    fn make_unverified_user(id: i32, email: &str, hub_id: i32) -> UserWithRoles {
+        let mut user_roles = make_user(id, email, hub_id);
+        user_roles.user.email_verified_at = None;
+        user_roles
+    }
+
     #[test]
     fn test_login_user_success() {
         let mut repo = MockRepository::new();
         let user = make_user(9, "a@b", 5);
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_login()
+            .returning(move |_, _, _| Ok(Some(user.clone())));
+        repo.expect_clear_login_throttle()
+            .withf(|key| key == "account:5:a@b")
+            .times(1)
+            .returning(|_| Ok(1));
+        repo.expect_invalidate_password_resets()
+            .times(1)
+            .returning(|_| Ok(0));
+        repo.expect_get_user_totp().returning(|_| Ok(None));
+        expect_session(&mut repo);
+
+        let payload = LoginPayload {
+            email: UserEmail::new("a@b").unwrap(),
+            password: crate::domain::types::UserPassword::new("pass").unwrap(),
+            hub_id: HubId::new(5).unwrap(),
+        };
+
+        let keys = make_keys();
+
+        let outcome = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &keys,
+            &repo,
+        )
+        .unwrap();
+        match outcome {
+            LoginOutcome::Authenticated(claims) => assert!(!claims.token.is_empty()),
+            other => panic!("expected a session, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_login_user_with_totp_requires_second_factor() {
+        let mut repo = MockRepository::new();
+        let user = make_user(9, "a@b", 5);
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_login()
+            .returning(move |_, _, _| Ok(Some(user.clone())));
+        repo.expect_clear_login_throttle().returning(|_| Ok(1));
+        repo.expect_invalidate_password_resets()
+            .returning(|_| Ok(0));
+        repo.expect_get_user_totp().returning(|user_id| {
+            let now = Utc::now().naive_utc();
+            Ok(Some(UserTotp::new(
+                user_id,
+                "JBSWY3DPEHPK3PXP".to_string(),
+                Some(now),
+                None,
+                now,
+            )))
+        });
+
+        let payload = LoginPayload {
+            email: UserEmail::new("a@b").unwrap(),
+            password: crate::domain::types::UserPassword::new("pass").unwrap(),
+            hub_id: HubId::new(5).unwrap(),
+        };
+
+        let outcome = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap();
+        match outcome {
+            LoginOutcome::SecondFactorRequired(pending) => {
+                assert_eq!(pending.user_id, 9);
+                assert_eq!(pending.hub_id, 5);
+                assert!(!pending.setup_required);
+            }
+            other => panic!("expected a second factor challenge, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_login_admin_requires_setup_when_hub_enforces_2fa() {
+        let mut repo = MockRepository::new();
+        let now = Utc::now().naive_utc();
+        let mut user = make_user(9, "a@b", 5);
+        user.roles = vec![Role::new(
+            RoleId::new(1).unwrap(),
+            RoleName::new(SERVICE_ACCESS_ROLE).unwrap(),
+            now,
+            now,
+        )];
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
         repo.expect_login()
             .returning(move |_, _, _| Ok(Some(user.clone())));
+        repo.expect_clear_login_throttle().returning(|_| Ok(1));
+        repo.expect_invalidate_password_resets()
+            .returning(|_| Ok(0));
+        repo.expect_get_user_totp().returning(|_| Ok(None));
+        repo.expect_get_hub_policy()
+            .withf(|hub_id| hub_id.get() == 5)
+            .returning(|_| {
+                Ok(HubPolicy {
+                    require_admin_2fa: true,
+                    ..Default::default()
+                })
+            });
 
         let payload = LoginPayload {
             email: UserEmail::new("a@b").unwrap(),
@@ -155,16 +859,192 @@ mod tests {
             hub_id: HubId::new(5).unwrap(),
         };
 
-        let secret = make_secret();
+        let outcome = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap();
+        assert!(matches!(
+            outcome,
+            LoginOutcome::SecondFactorRequired(PendingLoginDto {
+                setup_required: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_finish_login_enforces_2fa_for_inherited_admin_role() {
+        let mut repo = MockRepository::new();
+        let now = Utc::now().naive_utc();
+        let user = make_user(9, "a@b", 5).with_inherited_roles(vec![Role::new(
+            RoleId::new(1).unwrap(),
+            RoleName::new(SERVICE_ACCESS_ROLE).unwrap(),
+            now,
+            now,
+        )]);
+        repo.expect_get_user_totp().returning(|_| Ok(None));
+        repo.expect_get_hub_policy().returning(|_| {
+            Ok(HubPolicy {
+                require_admin_2fa: true,
+                ..Default::default()
+            })
+        });
+        repo.expect_create_session().never();
+
+        let outcome = finish_login(
+            user,
+            AuditEventType::LoginSucceeded,
+            &make_client(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap();
+        assert!(matches!(
+            outcome,
+            LoginOutcome::SecondFactorRequired(PendingLoginDto {
+                setup_required: true,
+                ..
+            })
+        ));
+    }
+
+    fn make_pending(user_id: i32, hub_id: i32) -> PendingLoginDto {
+        PendingLoginDto {
+            user_id,
+            hub_id,
+            email: "a@b".to_string(),
+            setup_required: false,
+            expires_at: (Utc::now() + Duration::minutes(5)).timestamp(),
+        }
+    }
+
+    #[test]
+    fn test_complete_second_factor_login_rejects_expired_pending_login() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_get_user_by_id().never();
+        let mut pending = make_pending(1, 2);
+        pending.expires_at = Utc::now().timestamp() - 1;
+
+        let res = complete_second_factor_login(
+            &pending,
+            "123456",
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        );
+        assert!(matches!(
+            res,
+            Err(LoginError::Service(ServiceError::Unauthorized))
+        ));
+    }
+
+    #[test]
+    fn test_complete_second_factor_login_records_wrong_codes() {
+        let mut repo = MockRepository::new();
+        let uwr = make_user(1, "a@b", 2);
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(uwr.clone())));
+        repo.expect_get_user_totp().returning(|user_id| {
+            let now = Utc::now().naive_utc();
+            Ok(Some(UserTotp::new(
+                user_id,
+                "JBSWY3DPEHPK3PXP".to_string(),
+                Some(now),
+                None,
+                now,
+            )))
+        });
+        repo.expect_consume_recovery_code()
+            .returning(|_, _, _| Ok(false));
+        repo.expect_record_login_failure()
+            .withf(|key, _, _| key == "2fa:1")
+            .times(1)
+            .returning(|key, now, _| Ok(LoginThrottle::new(key, now)));
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::LoginFailed
+                    && event.actor_id == Some(UserId::new(1).unwrap())
+                    && event.detail.as_deref() == Some("second_factor")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let res = complete_second_factor_login(
+            &make_pending(1, 2),
+            "not-a-code",
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        );
+        assert!(matches!(
+            res,
+            Err(LoginError::Service(ServiceError::Unauthorized))
+        ));
+    }
+
+    #[test]
+    fn test_complete_second_factor_login_accepts_recovery_code() {
+        let mut repo = MockRepository::new();
+        let uwr = make_user(1, "a@b", 2);
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(uwr.clone())));
+        repo.expect_get_user_totp().returning(|user_id| {
+            let now = Utc::now().naive_utc();
+            Ok(Some(UserTotp::new(
+                user_id,
+                "JBSWY3DPEHPK3PXP".to_string(),
+                Some(now),
+                None,
+                now,
+            )))
+        });
+        repo.expect_consume_recovery_code()
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+        repo.expect_clear_login_throttle()
+            .withf(|key| key == "2fa:1")
+            .times(1)
+            .returning(|_| Ok(1));
+        expect_session(&mut repo);
 
-        let claims = login_and_issue_token(payload, &secret, &repo).unwrap();
-        assert!(!claims.token.is_empty());
+        let res = complete_second_factor_login(
+            &make_pending(1, 2),
+            "abcdef-123456",
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap();
+        let claims = AuthenticatedUser::from_jwt(&res.token, &make_secret()).unwrap();
+        assert_eq!(claims.sub, "1");
     }
 
     #[test]
     fn test_login_user_invalid_password() {
         let mut repo = MockRepository::new();
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
         repo.expect_login().returning(|_, _, _| Ok(None));
+        repo.expect_record_login_failure()
+            .times(2)
+            .returning(|key, now, _| Ok(LoginThrottle::new(key, now)));
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::LoginFailed
+                    && event.actor_id.is_none()
+                    && event.ip_address.as_deref() == Some("127.0.0.1")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
 
         let payload = LoginPayload {
             email: UserEmail::new("a@b").unwrap(),
@@ -172,16 +1052,37 @@ mod tests {
             hub_id: HubId::new(5).unwrap(),
         };
 
-        let secret = make_secret();
+        let keys = make_keys();
 
-        let res = login_and_issue_token(payload, &secret, &repo);
-        assert!(matches!(res, Err(ServiceError::Unauthorized)));
+        let res = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &keys,
+            &repo,
+        );
+        assert!(matches!(
+            res,
+            Err(LoginError::Service(ServiceError::Unauthorized))
+        ));
     }
 
     #[test]
     fn test_login_user_unknown_user() {
         let mut repo = MockRepository::new();
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
         repo.expect_login().returning(|_, _, _| Ok(None));
+        repo.expect_record_login_failure()
+            .times(2)
+            .returning(|key, now, _| Ok(LoginThrottle::new(key, now)));
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::LoginFailed
+                    && event.actor_id.is_none()
+                    && event.ip_address.as_deref() == Some("127.0.0.1")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
 
         let payload = LoginPayload {
             email: UserEmail::new("missing@ex").unwrap(),
@@ -189,15 +1090,109 @@ mod tests {
             hub_id: HubId::new(1).unwrap(),
         };
 
-        let secret = make_secret();
+        let keys = make_keys();
 
-        let res = login_and_issue_token(payload, &secret, &repo);
-        assert!(matches!(res, Err(ServiceError::Unauthorized)));
+        let res = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &keys,
+            &repo,
+        );
+        assert!(matches!(
+            res,
+            Err(LoginError::Service(ServiceError::Unauthorized))
+        ));
+    }
+
+    #[test]
+    fn test_login_user_throttled_account_skips_password_check() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_login_throttle()
+            .withf(|key| key == "account:5:a@b")
+            .returning(|key| {
+                let now = Utc::now().naive_utc();
+                let mut state = LoginThrottle::new(key, now);
+                state.locked_until = Some(now + Duration::seconds(30));
+                Ok(Some(state))
+            });
+        repo.expect_login().never();
+        repo.expect_record_login_failure().never();
+        repo.expect_record_audit_event().never();
+
+        let payload = LoginPayload {
+            email: UserEmail::new("a@b").unwrap(),
+            password: crate::domain::types::UserPassword::new("pass").unwrap(),
+            hub_id: HubId::new(5).unwrap(),
+        };
+
+        let res = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        );
+        match res {
+            Err(LoginError::Throttled {
+                retry_after_seconds,
+            }) => assert!((1..=30).contains(&retry_after_seconds)),
+            _ => panic!("expected throttled login"),
+        }
+    }
+
+    #[test]
+    fn test_login_user_failure_records_account_and_ip_counters() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_login().returning(|_, _, _| Ok(None));
+        repo.expect_record_login_failure()
+            .withf(|key, _, policy| key == "account:5:a@b" && policy.max_attempts == 5)
+            .times(1)
+            .returning(|key, now, _| Ok(LoginThrottle::new(key, now)));
+        repo.expect_record_login_failure()
+            .withf(|key, _, policy| key == "ip:10.0.0.1" && policy.max_attempts == 20)
+            .times(1)
+            .returning(|key, now, _| Ok(LoginThrottle::new(key, now)));
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::LoginFailed
+                    && event.hub_id.get() == 5
+                    && event.detail.as_deref() == Some("a@b")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let payload = LoginPayload {
+            email: UserEmail::new("a@b").unwrap(),
+            password: crate::domain::types::UserPassword::new("wrong").unwrap(),
+            hub_id: HubId::new(5).unwrap(),
+        };
+
+        let res = login_and_issue_token(
+            payload,
+            &ClientInfo {
+                user_agent: None,
+                ip_address: Some("10.0.0.1".into()),
+            },
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        );
+        assert!(matches!(
+            res,
+            Err(LoginError::Service(ServiceError::Unauthorized))
+        ));
     }
 
     #[test]
     fn test_register_user_success() {
         let mut repo = MockRepository::new();
+        repo.expect_get_hub_registration_settings()
+            .returning(|_| Ok(Default::default()));
+        repo.expect_get_user_by_id()
+            .withf(|user_id, hub_id| user_id.get() == 1 && hub_id.get() == 1)
+            .returning(|_, _| Ok(Some(make_unverified_user(1, "x@y", 1))));
         repo.expect_create_user().returning(|new| {
             let now = Utc::now().naive_utc();
             Ok(User::new(
@@ -211,18 +1206,28 @@ mod tests {
                 vec![],
             ))
         });
+        repo.expect_record_audit_event()
+            .withf(|event| {
+                event.event_type == AuditEventType::UserRegistered
+                    && event.actor_id == Some(UserId::new(1).unwrap())
+                    && event.target_id == Some(1)
+            })
+            .times(1)
+            .returning(|_| Ok(()));
         let payload = RegisterPayload {
             email: UserEmail::new("x@y").unwrap(),
             password: crate::domain::types::UserPassword::new("p").unwrap(),
             hub_id: HubId::new(1).unwrap(),
         };
-        let res = register_user(payload, &repo);
-        assert!(res.is_ok());
+        let user_roles = register_user(payload, &make_client(), &repo).unwrap();
+        assert!(!user_roles.user.is_email_verified());
     }
 
     #[test]
     fn test_register_user_error() {
         let mut repo = MockRepository::new();
+        repo.expect_get_hub_registration_settings()
+            .returning(|_| Ok(Default::default()));
         repo.expect_create_user()
             .returning(|_| Err(RepositoryError::ValidationError("fail".into())));
         let payload = RegisterPayload {
@@ -230,10 +1235,169 @@ mod tests {
             password: crate::domain::types::UserPassword::new("p").unwrap(),
             hub_id: HubId::new(1).unwrap(),
         };
-        let res = register_user(payload, &repo);
+        let res = register_user(payload, &make_client(), &repo);
         assert!(res.is_err());
     }
 
+    #[test]
+    fn test_register_user_refuses_closed_and_invite_only_hubs() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_hub_registration_settings()
+            .returning(|hub_id| {
+                Ok(HubRegistrationSettings {
+                    registration_mode: match hub_id.get() {
+                        1 => RegistrationMode::Closed,
+                        _ => RegistrationMode::InviteOnly,
+                    },
+                    ..Default::default()
+                })
+            });
+        repo.expect_create_user().never();
+
+        let payload = |hub_id: i32| RegisterPayload {
+            email: UserEmail::new("x@y").unwrap(),
+            password: crate::domain::types::UserPassword::new("p").unwrap(),
+            hub_id: HubId::new(hub_id).unwrap(),
+        };
+        assert!(matches!(
+            register_user(payload(1), &make_client(), &repo),
+            Err(RegistrationError::Closed)
+        ));
+        assert!(matches!(
+            register_user(payload(2), &make_client(), &repo),
+            Err(RegistrationError::InviteOnly)
+        ));
+    }
+
+    #[test]
+    fn test_register_user_checks_domain_and_assigns_default_roles() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_hub_registration_settings()
+            .withf(|hub_id| hub_id.get() == 1)
+            .returning(|_| {
+                Ok(HubRegistrationSettings {
+                    allowed_email_domains: vec!["company.ru".into()],
+                    default_roles: vec![RoleId::new(4).unwrap()],
+                    ..Default::default()
+                })
+            });
+        repo.expect_create_user()
+            .withf(|new| new.roles == vec![RoleId::new(4).unwrap()])
+            .times(1)
+            .returning(|new| {
+                let now = Utc::now().naive_utc();
+                Ok(User::new(
+                    UserId::new(1).unwrap(),
+                    new.email.clone(),
+                    new.name.clone(),
+                    new.hub_id,
+                    "".into(),
+                    now,
+                    now,
+                    vec![],
+                ))
+            });
+        repo.expect_get_user_by_id().returning(|_, _| {
+            let now = Utc::now().naive_utc();
+            let user = make_user(1, "x@company.ru", 1).user;
+            Ok(Some(UserWithRoles::new(
+                user,
+                vec![Role::try_new(4, "staff", now, now).unwrap()],
+            )))
+        });
+        repo.expect_record_audit_event().returning(|_| Ok(()));
+
+        let payload = |email: &str| RegisterPayload {
+            email: UserEmail::new(email).unwrap(),
+            password: crate::domain::types::UserPassword::new("p").unwrap(),
+            hub_id: HubId::new(1).unwrap(),
+        };
+        assert!(matches!(
+            register_user(payload("x@gmail.com"), &make_client(), &repo),
+            Err(RegistrationError::EmailDomainNotAllowed)
+        ));
+        let user_roles = register_user(payload("x@company.ru"), &make_client(), &repo).unwrap();
+        assert_eq!(user_roles.roles.len(), 1);
+    }
+
+    #[test]
+    fn test_login_refuses_unverified_user_when_hub_requires_verification() {
+        let mut repo = MockRepository::new();
+        let user = make_unverified_user(9, "a@b", 5);
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_login()
+            .returning(move |_, _, _| Ok(Some(user.clone())));
+        repo.expect_clear_login_throttle().returning(|_| Ok(1));
+        repo.expect_get_hub_policy()
+            .withf(|hub_id| hub_id.get() == 5)
+            .returning(|_| {
+                Ok(HubPolicy {
+                    require_email_verification: true,
+                    ..Default::default()
+                })
+            });
+        repo.expect_create_session().never();
+
+        let payload = LoginPayload {
+            email: UserEmail::new("a@b").unwrap(),
+            password: UserPassword::new("pass").unwrap(),
+            hub_id: HubId::new(5).unwrap(),
+        };
+
+        let res = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        );
+        assert!(matches!(res, Err(LoginError::EmailNotVerified)));
+    }
+
+    #[test]
+    fn test_login_allows_unverified_user_when_hub_does_not_require_verification() {
+        let mut repo = MockRepository::new();
+        let user = make_unverified_user(9, "a@b", 5);
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_login()
+            .returning(move |_, _, _| Ok(Some(user.clone())));
+        repo.expect_clear_login_throttle().returning(|_| Ok(1));
+        repo.expect_invalidate_password_resets()
+            .returning(|_| Ok(0));
+        repo.expect_get_hub_policy()
+            .returning(|_| Ok(HubPolicy::default()));
+        repo.expect_get_user_totp().returning(|_| Ok(None));
+        expect_session(&mut repo);
+
+        let payload = LoginPayload {
+            email: UserEmail::new("a@b").unwrap(),
+            password: UserPassword::new("pass").unwrap(),
+            hub_id: HubId::new(5).unwrap(),
+        };
+
+        let outcome = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap();
+        assert!(matches!(outcome, LoginOutcome::Authenticated(_)));
+    }
+
+    #[test]
+    fn test_verify_email_rejects_unknown_or_used_token() {
+        let mut repo = MockRepository::new();
+        repo.expect_consume_email_verification()
+            .withf(|hash, _| hash == hash_token("token"))
+            .returning(|_, _| Ok(None));
+        assert!(matches!(
+            verify_email("token", &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
     #[test]
     fn test_list_hubs_returns_all() {
         let mut repo = MockRepository::new();
@@ -267,28 +1431,182 @@ mod tests {
         assert!(res.is_empty());
     }
 
+    fn make_reset(user_id: i32, hub_id: i32, token: &str) -> PasswordReset {
+        let now = Utc::now().naive_utc();
+        PasswordReset::try_new(
+            1,
+            user_id,
+            hub_id,
+            hash_token(token),
+            now + Duration::hours(1),
+            Some(now),
+            now,
+        )
+        .unwrap()
+    }
+
+    fn make_reset_payload(token: &str, password: &str) -> ResetPasswordPayload {
+        ResetPasswordPayload {
+            token: token.to_string(),
+            password: UserPassword::new(password).unwrap(),
+        }
+    }
+
     #[test]
-    fn test_reissue_session_from_token_requires_existing_user() {
+    fn test_reset_password_rejects_invalid_token() {
         let mut repo = MockRepository::new();
-        repo.expect_get_user_by_email().returning(|_, _| Ok(None));
-        let mut user: AuthenticatedUser = make_user(1, "a@b", 2).into();
-        user.set_expiration(1);
-        let token = issue_jwt(&user, "secret").unwrap();
-        let res = reissue_session_from_token(&token.token, 7, "secret", &repo);
+        repo.expect_reset_password().returning(|_, _, _| Ok(None));
+        repo.expect_revoke_user_sessions().never();
+        repo.expect_get_user_by_id().never();
+        let res = reset_password_and_issue_token(
+            make_reset_payload("token", "new"),
+            &make_client(),
+            &make_keys(),
+            &repo,
+        );
         assert!(matches!(res, Err(ServiceError::Unauthorized)));
     }
 
     #[test]
-    fn test_reissue_session_from_token_success() {
+    fn test_reset_password_updates_hash_and_issues_token() {
         let mut repo = MockRepository::new();
         let uwr = make_user(1, "a@b", 2);
-        let uwr_clone = uwr.clone();
-        repo.expect_get_user_by_email()
-            .returning(move |_, _| Ok(Some(uwr_clone.clone())));
-        let mut user: AuthenticatedUser = uwr.into();
-        user.set_expiration(1);
-        let token = issue_jwt(&user, "secret").unwrap();
-        let res = reissue_session_from_token(&token.token, 7, "secret", &repo);
-        assert!(res.is_ok());
+        repo.expect_reset_password()
+            .withf(|hash, password, _| hash == hash_token("token") && password.as_str() == "new")
+            .times(1)
+            .returning(|_, _, _| Ok(Some(make_reset(1, 2, "token"))));
+        repo.expect_update_password().never();
+        repo.expect_revoke_user_sessions()
+            .withf(|user_id, _| user_id.get() == 1)
+            .times(1)
+            .returning(|_, _| Ok(2));
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(uwr.clone())));
+        repo.expect_get_user_totp().returning(|_| Ok(None));
+        expect_session_audited_as(&mut repo, AuditEventType::PasswordReset);
+        let res = match reset_password_and_issue_token(
+            make_reset_payload("token", "new"),
+            &make_client(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap()
+        {
+            LoginOutcome::Authenticated(res) => res,
+            other => panic!("expected a session, got {other:?}"),
+        };
+        let claims = AuthenticatedUser::from_jwt(&res.token, &make_secret()).unwrap();
+        assert_eq!(claims.sub, "1");
+    }
+
+    fn make_user_with_password_change(id: i32, email: &str, hub_id: i32) -> UserWithRoles {
+        let mut user_roles = make_user(id, email, hub_id);
+        user_roles.user.must_change_password = true;
+        user_roles
+    }
+
+    #[test]
+    fn test_login_user_who_must_change_password_gets_no_session() {
+        let mut repo = MockRepository::new();
+        let user = make_user_with_password_change(9, "a@b", 5);
+        repo.expect_get_login_throttle().returning(|_| Ok(None));
+        repo.expect_login()
+            .returning(move |_, _, _| Ok(Some(user.clone())));
+        repo.expect_clear_login_throttle().returning(|_| Ok(1));
+        repo.expect_invalidate_password_resets()
+            .returning(|_| Ok(0));
+        repo.expect_create_session().never();
+
+        let payload = LoginPayload {
+            email: UserEmail::new("a@b").unwrap(),
+            password: UserPassword::new("pass").unwrap(),
+            hub_id: HubId::new(5).unwrap(),
+        };
+
+        let outcome = login_and_issue_token(
+            payload,
+            &make_client(),
+            &LoginThrottleConfig::default(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap();
+        match outcome {
+            LoginOutcome::PasswordChangeRequired(pending) => {
+                assert_eq!(pending.user_id, 9);
+                assert_eq!(pending.hub_id, 5);
+                assert!(!pending.setup_required);
+            }
+            other => panic!("expected a password change, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_change_password_continues_the_login() {
+        let mut repo = MockRepository::new();
+        let uwr = make_user_with_password_change(1, "a@b", 2);
+        let updated = make_user(1, "a@b", 2).user;
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(uwr.clone())));
+        repo.expect_verify_password().returning(|_, _| false);
+        repo.expect_update_password()
+            .withf(|id, hub_id, password| {
+                id.get() == 1 && hub_id.get() == 2 && password.as_str() == "new"
+            })
+            .times(1)
+            .returning(move |_, _, _| Ok(updated.clone()));
+        repo.expect_revoke_user_sessions()
+            .withf(|user_id, _| user_id.get() == 1)
+            .times(1)
+            .returning(|_, _| Ok(1));
+        repo.expect_get_user_totp().returning(|_| Ok(None));
+        expect_session(&mut repo);
+
+        let outcome = change_password_and_issue_token(
+            &make_pending(1, 2),
+            &UserPassword::new("new").unwrap(),
+            &make_client(),
+            &make_keys(),
+            &repo,
+        )
+        .unwrap();
+        assert!(matches!(outcome, LoginOutcome::Authenticated(_)));
+    }
+
+    #[test]
+    fn test_change_password_rejects_the_current_password() {
+        let mut repo = MockRepository::new();
+        let uwr = make_user_with_password_change(1, "a@b", 2);
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(uwr.clone())));
+        repo.expect_verify_password().returning(|_, _| true);
+        repo.expect_update_password().never();
+
+        let res = change_password_and_issue_token(
+            &make_pending(1, 2),
+            &UserPassword::new("pass").unwrap(),
+            &make_client(),
+            &make_keys(),
+            &repo,
+        );
+        assert!(matches!(res, Err(ServiceError::Conflict)));
+    }
+
+    #[test]
+    fn test_change_password_requires_a_pending_change() {
+        let mut repo = MockRepository::new();
+        let uwr = make_user(1, "a@b", 2);
+        repo.expect_get_user_by_id()
+            .returning(move |_, _| Ok(Some(uwr.clone())));
+        repo.expect_update_password().never();
+
+        let res = change_password_and_issue_token(
+            &make_pending(1, 2),
+            &UserPassword::new("new").unwrap(),
+            &make_client(),
+            &make_keys(),
+            &repo,
+        );
+        assert!(matches!(res, Err(ServiceError::Unauthorized)));
     }
 }
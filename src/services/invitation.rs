@@ -0,0 +1,383 @@
+//! Invitations: accounts created by hub administrators.
+//!
+//! An administrator invites an address with a name and a set of roles. The
+//! invitee receives a single-use link, sets a password on the accept page,
+//! and is signed in to the hub with the invited roles; the address counts as
+//! verified. Resending an invitation replaces its link and extends its
+//! expiry, so earlier links stop working.
+
+use chrono::{Duration, Utc};
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+use pushkind_common::zmq::{ZmqSender, ZmqSenderExt};
+use pushkind_emailer::domain::email::{NewEmail, NewEmailRecipient};
+use pushkind_emailer::domain::types::{
+    EmailBody, EmailSubject, HubId as EmailHubId, RecipientEmail, RecipientName,
+};
+use pushkind_emailer::models::zmq::ZMQSendEmailMessage;
+
+use crate::domain::audit::AuditEventType;
+use crate::domain::invitation::{Invitation, NewInvitation};
+use crate::domain::permission::Permission;
+use crate::domain::session::ClientInfo;
+use crate::domain::types::{HubId, InvitationId, UserId};
+use crate::dto::api::AdminInvitationDto;
+use crate::forms::auth::AcceptInvitationPayload;
+use crate::forms::main::InviteUserPayload;
+use crate::repository::{
+    AuditWriter, HubReader, InvitationReader, InvitationWriter, PermissionReader, RoleReader,
+    SessionWriter, TwoFactorReader, UserReader,
+};
+use crate::services::admin::ensure_roles_in_hub;
+use crate::services::auth::{LoginOutcome, finish_login};
+use crate::services::jwt::JwtKeys;
+use crate::services::permission::ensure_permission;
+use crate::services::tokens::{generate_token, hash_token};
+
+/// Lifetime of an invitation link.
+const INVITATION_TOKEN_TTL_HOURS: i64 = 168;
+
+/// Stores a pending invitation to the current hub and returns it with its
+/// plain token.
+///
+/// Returns [`ServiceError::Conflict`] when the address already belongs to a
+/// user of the hub or has a pending invitation.
+fn issue_invitation(
+    payload: InviteUserPayload,
+    current_user: &AuthenticatedUser,
+    repo: &(impl UserReader + RoleReader + InvitationWriter + PermissionReader),
+) -> ServiceResult<(Invitation, String)> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+
+    if repo.get_user_by_email(&payload.email, hub_id)?.is_some() {
+        return Err(ServiceError::Conflict);
+    }
+    ensure_roles_in_hub(&payload.roles, hub_id, repo)?;
+
+    let token = generate_token();
+    let invitation = repo.create_invitation(&NewInvitation {
+        hub_id,
+        email: payload.email,
+        name: payload.name,
+        token_hash: hash_token(&token),
+        invited_by: current_user
+            .sub
+            .parse()
+            .ok()
+            .and_then(|id| UserId::new(id).ok()),
+        expires_at: Utc::now().naive_utc() + Duration::hours(INVITATION_TOKEN_TTL_HOURS),
+        roles: payload.roles,
+    })?;
+    Ok((invitation, token))
+}
+
+/// Emails the accept link of `invitation` on behalf of `current_user`.
+async fn send_invitation_email(
+    invitation: &Invitation,
+    token: &str,
+    base_url: &str,
+    zmq_sender: &ZmqSender,
+    current_user: &AuthenticatedUser,
+) -> ServiceResult<()> {
+    let invitation_url = format!("{}/auth/invite?token={}", base_url, token);
+
+    let new_email = NewEmail {
+        message: EmailBody::new(
+            "Вас пригласили в Pushkind. Чтобы задать пароль и войти, перейдите по ссылке: {invitation_url}\nЕсли вы не ждали приглашения, проигнорируйте это письмо.",
+        )?,
+        subject: Some(EmailSubject::new("Приглашение в Pushkind")?),
+        attachment: None,
+        attachment_name: None,
+        attachment_mime: None,
+        hub_id: EmailHubId::new(invitation.hub_id.get())?,
+        recipients: vec![NewEmailRecipient {
+            address: RecipientEmail::new(invitation.email.as_str())?,
+            name: RecipientName::new(invitation.name.as_str())?,
+            fields: std::iter::once(("invitation_url".to_string(), invitation_url)).collect(),
+        }],
+    };
+
+    let zmq_message = ZMQSendEmailMessage::NewEmail(Box::new((current_user.clone(), new_email)));
+    zmq_sender
+        .send_json(&zmq_message)
+        .await
+        .map_err(|_| ServiceError::Internal)?;
+    Ok(())
+}
+
+/// Invites a user to the current hub and emails the accept link.
+pub async fn invite_user(
+    payload: InviteUserPayload,
+    base_url: &str,
+    zmq_sender: &ZmqSender,
+    current_user: &AuthenticatedUser,
+    repo: &(impl UserReader + RoleReader + InvitationWriter + PermissionReader),
+) -> ServiceResult<Invitation> {
+    let (invitation, token) = issue_invitation(payload, current_user, repo)?;
+    send_invitation_email(&invitation, &token, base_url, zmq_sender, current_user).await?;
+    Ok(invitation)
+}
+
+/// Lists the pending invitations of the current hub.
+pub fn list_invitations(
+    current_user: &AuthenticatedUser,
+    repo: &(impl InvitationReader + PermissionReader),
+) -> ServiceResult<Vec<AdminInvitationDto>> {
+    ensure_permission(current_user, Permission::UsersRead, repo)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let invitations = repo.list_invitations(hub_id)?;
+    Ok(invitations
+        .into_iter()
+        .map(AdminInvitationDto::from)
+        .collect())
+}
+
+/// Replaces the link of a pending invitation of the current hub, extends its
+/// expiry, and emails it again.
+pub async fn resend_invitation(
+    id: i32,
+    base_url: &str,
+    zmq_sender: &ZmqSender,
+    current_user: &AuthenticatedUser,
+    repo: &(impl InvitationWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let id = InvitationId::new(id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+
+    let token = generate_token();
+    let invitation = repo.renew_invitation(
+        id,
+        hub_id,
+        &hash_token(&token),
+        Utc::now().naive_utc() + Duration::hours(INVITATION_TOKEN_TTL_HOURS),
+    )?;
+    send_invitation_email(&invitation, &token, base_url, zmq_sender, current_user).await
+}
+
+/// Withdraws a pending invitation of the current hub.
+pub fn cancel_invitation(
+    id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &(impl InvitationWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let id = InvitationId::new(id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    match repo.delete_invitation(id, hub_id)? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Redeems an invitation, creates its user with the chosen password, and
+/// starts a session.
+///
+/// Returns [`ServiceError::Unauthorized`] when the token is unknown, already
+/// used, or expired, and [`ServiceError::Conflict`] when the address has
+/// registered in the hub in the meantime.
+pub fn accept_invitation_and_issue_token<R>(
+    payload: AcceptInvitationPayload,
+    client: &ClientInfo,
+    keys: &JwtKeys,
+    repo: &R,
+) -> ServiceResult<LoginOutcome>
+where
+    R: UserReader
+        + InvitationWriter
+        + HubReader
+        + TwoFactorReader
+        + SessionWriter
+        + AuditWriter
+        + PermissionReader,
+{
+    let user = repo
+        .accept_invitation(
+            &hash_token(&payload.token),
+            &payload.password,
+            Utc::now().naive_utc(),
+        )?
+        .ok_or(ServiceError::Unauthorized)?;
+    let user_roles = repo
+        .get_user_by_id(user.id, user.hub_id)?
+        .ok_or(ServiceError::Unauthorized)?;
+    finish_login(
+        user_roles,
+        AuditEventType::InvitationAccepted,
+        client,
+        keys,
+        repo,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::role::Role;
+    use crate::domain::types::{RoleId, UserEmail, UserName, UserPassword};
+    use crate::domain::user::{User, UserWithRoles};
+    use crate::repository::mock::MockRepository;
+
+    fn make_admin() -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: "1".into(),
+            email: "admin@example.com".into(),
+            hub_id: 10,
+            name: "Admin".into(),
+            roles: vec!["admin".into()],
+            exp: 0,
+        }
+    }
+
+    /// Grants `permissions` to every user.
+    fn expect_permissions(repo: &mut MockRepository, permissions: &'static [Permission]) {
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(permissions.to_vec()));
+    }
+
+    fn make_payload() -> InviteUserPayload {
+        InviteUserPayload {
+            email: UserEmail::new("new@example.com").unwrap(),
+            name: UserName::new("New").unwrap(),
+            roles: vec![RoleId::new(3).unwrap()],
+        }
+    }
+
+    fn make_invitation(token_hash: &str) -> Invitation {
+        let now = Utc::now().naive_utc();
+        Invitation::try_new(
+            5,
+            10,
+            "new@example.com".into(),
+            "New".into(),
+            token_hash.to_string(),
+            Some(1),
+            now + Duration::hours(INVITATION_TOKEN_TTL_HOURS),
+            None,
+            now,
+            vec![3],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn issue_invitation_stores_only_the_token_hash() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo, &[Permission::UsersWrite]);
+        repo.expect_get_user_by_email().returning(|_, _| Ok(None));
+        repo.expect_list_roles()
+            .withf(|hub_id| hub_id.get() == 10)
+            .returning(|hub_id| {
+                let now = Utc::now().naive_utc();
+                Ok(vec![
+                    Role::try_new(3, "staff", now, now)
+                        .unwrap()
+                        .with_hub(hub_id),
+                ])
+            });
+        repo.expect_create_invitation()
+            .withf(|new_invitation| {
+                new_invitation.hub_id.get() == 10
+                    && new_invitation.invited_by == Some(UserId::new(1).unwrap())
+                    && new_invitation.roles == vec![RoleId::new(3).unwrap()]
+            })
+            .times(1)
+            .returning(|new_invitation| Ok(make_invitation(&new_invitation.token_hash)));
+
+        let (invitation, token) = issue_invitation(make_payload(), &make_admin(), &repo).unwrap();
+        assert_eq!(invitation.token_hash, hash_token(&token));
+    }
+
+    #[test]
+    fn issue_invitation_rejects_existing_users() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo, &[Permission::UsersWrite]);
+        repo.expect_get_user_by_email()
+            .withf(|email, hub_id| email.as_str() == "new@example.com" && hub_id.get() == 10)
+            .returning(|_, _| {
+                let now = Utc::now().naive_utc();
+                let user = User::try_new(
+                    2,
+                    "new@example.com",
+                    Some("New".into()),
+                    10,
+                    "hash".into(),
+                    now,
+                    now,
+                    vec![],
+                )
+                .unwrap();
+                Ok(Some(UserWithRoles::new(user, vec![])))
+            });
+        repo.expect_create_invitation().never();
+
+        assert!(matches!(
+            issue_invitation(make_payload(), &make_admin(), &repo),
+            Err(ServiceError::Conflict)
+        ));
+    }
+
+    #[test]
+    fn issue_invitation_rejects_roles_of_other_hubs() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo, &[Permission::UsersWrite]);
+        repo.expect_get_user_by_email().returning(|_, _| Ok(None));
+        repo.expect_list_roles().returning(|_| Ok(vec![]));
+        repo.expect_create_invitation().never();
+
+        assert!(matches!(
+            issue_invitation(make_payload(), &make_admin(), &repo),
+            Err(ServiceError::Form(_))
+        ));
+    }
+
+    #[test]
+    fn issue_invitation_requires_users_write() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo, &[Permission::UsersRead]);
+        repo.expect_create_invitation().never();
+
+        assert!(matches!(
+            issue_invitation(make_payload(), &make_admin(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn cancel_invitation_reports_missing_invitations() {
+        let mut repo = MockRepository::new();
+        expect_permissions(&mut repo, &[Permission::UsersWrite]);
+        repo.expect_delete_invitation()
+            .withf(|id, hub_id| id.get() == 5 && hub_id.get() == 10)
+            .returning(|_, _| Ok(0));
+
+        assert!(matches!(
+            cancel_invitation(5, &make_admin(), &repo),
+            Err(ServiceError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn accept_invitation_rejects_invalid_tokens() {
+        let mut repo = MockRepository::new();
+        repo.expect_accept_invitation()
+            .withf(|token_hash, _, _| token_hash == hash_token("token"))
+            .returning(|_, _, _| Ok(None));
+        repo.expect_create_session().never();
+
+        let payload = AcceptInvitationPayload {
+            token: "token".into(),
+            password: UserPassword::new("secret").unwrap(),
+        };
+        let client = ClientInfo {
+            user_agent: None,
+            ip_address: None,
+        };
+        let keys = JwtKeys::from_secret("my_secret_key", "default");
+        assert!(matches!(
+            accept_invitation_and_issue_token(payload, &client, &keys, &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+}
@@ -2,10 +2,15 @@
 
 use pushkind_common::services::errors::ServiceResult;
 
+use crate::domain::audit::AuditEventType;
+use crate::domain::session::ClientInfo;
 use crate::domain::types::{HubId, UserEmail, UserId};
 use crate::dto::main::IndexData;
 use crate::forms::main::SaveUserPayload;
-use crate::repository::{HubReader, MenuReader, RoleReader, UserListQuery, UserReader, UserWriter};
+use crate::repository::{
+    AuditWriter, HubReader, MenuReader, RoleReader, UserListQuery, UserReader, UserWriter,
+};
+use crate::services::audit::{event_by, user_update_detail};
 use pushkind_common::domain::auth::AuthenticatedUser;
 
 /// Gathers all information necessary to render the main index view for a hub.
@@ -23,7 +28,7 @@ pub fn get_index_data(
         .get_hub_by_id(hub_id)?
         .ok_or(pushkind_common::services::errors::ServiceError::NotFound)?;
     let (_total, users) = repo.list_users(UserListQuery::new(hub_id))?;
-    let roles = repo.list_roles()?;
+    let roles = repo.list_roles(hub_id)?;
     let hubs = repo.list_hubs()?;
     let menu = repo.list_menu(hub_id)?;
     let user_name = repo
@@ -43,7 +48,8 @@ pub fn get_index_data(
 pub fn update_current_user(
     payload: SaveUserPayload,
     current_user: &AuthenticatedUser,
-    repo: &impl UserWriter,
+    client: &ClientInfo,
+    repo: &(impl UserWriter + AuditWriter),
 ) -> ServiceResult<()> {
     let user_id: i32 = current_user
         .sub
@@ -53,6 +59,11 @@ pub fn update_current_user(
     let hub_id = HubId::new(current_user.hub_id)?;
     let updates: crate::domain::user::UpdateUser = payload.into();
     repo.update_user(user_id, hub_id, &updates)?;
+    repo.record_audit_event(
+        &event_by(current_user, AuditEventType::UserUpdated, client)?
+            .with_target(user_id.get())
+            .with_detail(user_update_detail(&updates)),
+    )?;
     Ok(())
 }
 
@@ -91,7 +102,7 @@ mod tests {
             .returning(move |_| Ok(Some(hub_clone.clone())));
         repo.expect_list_users()
             .returning(move |_| Ok((1, vec![uwr_clone.clone()])));
-        repo.expect_list_roles().returning(|| Ok(vec![]));
+        repo.expect_list_roles().returning(|_| Ok(vec![]));
         repo.expect_list_hubs()
             .returning(move || Ok(vec![hub_clone2.clone()]));
         repo.expect_list_menu().returning(|_| Ok(vec![]));
@@ -123,6 +134,15 @@ mod tests {
         let user_clone = uwr.user.clone();
         repo.expect_update_user()
             .returning(move |_, _, _| Ok(user_clone.clone()));
+        let user_id = uwr.user.id.get();
+        repo.expect_record_audit_event()
+            .withf(move |event| {
+                event.event_type == AuditEventType::UserUpdated
+                    && event.actor_id.map(|id| id.get()) == Some(user_id)
+                    && event.target_id == Some(user_id)
+            })
+            .times(1)
+            .returning(|_| Ok(()));
         let payload = SaveUserPayload {
             name: UserName::new("X").unwrap(),
             password: None,
@@ -135,7 +155,7 @@ mod tests {
             roles: vec![],
             exp: 0,
         };
-        let res = update_current_user(payload, &current_user, &repo);
+        let res = update_current_user(payload, &current_user, &ClientInfo::default(), &repo);
         assert!(res.is_ok());
     }
 
@@ -156,7 +176,7 @@ mod tests {
             roles: vec![],
             exp: 0,
         };
-        let res = update_current_user(payload, &current_user, &repo);
+        let res = update_current_user(payload, &current_user, &ClientInfo::default(), &repo);
         assert!(matches!(
             res,
             Err(pushkind_common::services::errors::ServiceError::NotFound)
@@ -3,10 +3,34 @@
 //! Submodules:
 //! - [`admin`]: administrative operations.
 //! - [`api`]: API-facing utilities.
+//! - [`audit`]: audit log of security-relevant events.
 //! - [`auth`]: authentication workflows.
+//! - [`invitation`]: user invitations sent by hub administrators.
+//! - [`jwt`]: session JWT signing keys and the published JWKS.
 //! - [`main`]: main application view helpers.
+//! - [`oauth`]: OpenID Connect provider.
+//! - [`permission`]: permission checks for the current user.
+//! - [`personal_token`]: personal access tokens for scripts and CI.
+//! - [`retention`]: purging of deleted users and hubs.
+//! - [`service_account`]: hub-scoped service accounts for integrations.
+//! - [`session`]: session registry checks, listing, and revocation.
+//! - [`tokens`]: opaque token generation and hashing.
+//! - [`two_factor`]: TOTP second factor enrollment and verification.
+//! - [`webauthn`]: passkey registration and sign-in.
 
 pub mod admin;
 pub mod api;
+pub mod audit;
 pub mod auth;
+pub mod invitation;
+pub mod jwt;
 pub mod main;
+pub mod oauth;
+pub mod permission;
+pub mod personal_token;
+pub mod retention;
+pub mod service_account;
+pub mod session;
+pub mod tokens;
+pub mod two_factor;
+pub mod webauthn;
@@ -0,0 +1,1167 @@
+//! OpenID Connect provider: client registry, authorization code flow,
+//! userinfo, and token introspection and revocation.
+//!
+//! Only the authorization code flow with `S256` PKCE is supported, for public
+//! and confidential clients alike. Authorization codes and access tokens are
+//! opaque random strings stored hashed, like recovery tokens. ID tokens are
+//! signed with the active session key, so relying parties verify them with
+//! the published JWKS; their `aud` is the client id, which keeps them from
+//! being accepted as sessions. With an `HS256` active key nothing outside
+//! this service could verify them, so the code flow and discovery are
+//! refused, see [`id_tokens_supported`].
+//!
+//! Clients are registered by administrators holding `hubs.manage`, since a
+//! client may sign in users of every hub. Signed-in users are therefore not
+//! asked for consent.
+//!
+//! The token endpoint also serves the client credentials grant of service
+//! accounts, see [`crate::services::service_account`].
+//!
+//! Introspection (RFC 7662) and revocation (RFC 7009) accept opaque access
+//! tokens, service account tokens, and session JWTs, and answer from the token and session
+//! stores rather than from the token itself, so a revoked token is reported
+//! inactive before it expires.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{Duration, Utc};
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::repository::errors::RepositoryError;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use url::Url;
+
+use crate::domain::oauth::{
+    AccessToken, NewAccessToken, NewAuthorizationCode, NewOAuthClient, OAuthClient,
+};
+use crate::domain::permission::Permission;
+use crate::domain::service_account::ServiceAccountToken;
+use crate::domain::session::Session;
+use crate::domain::types::{HubId, OAuthClientId, UserId};
+use crate::dto::oauth::{
+    IdTokenClaimsDto, IntrospectionDto, OAuthClientCredentialsDto, OAuthErrorDto,
+    OpenIdConfigurationDto, TokenResponseDto, UserInfoDto,
+};
+use crate::forms::main::AddOAuthClientPayload;
+use crate::forms::oauth::{AuthorizeForm, TokenForm, TokenLookupForm};
+use crate::repository::{
+    OAuthClientReader, OAuthClientWriter, OAuthTokenReader, OAuthTokenWriter, PermissionReader,
+    ServiceAccountReader, SessionReader, SessionWriter, UserReader,
+};
+use crate::services::jwt::JwtKeys;
+use crate::services::permission::ensure_permission;
+use crate::services::service_account::TOKEN_PREFIX as SERVICE_ACCOUNT_TOKEN_PREFIX;
+use crate::services::tokens::{generate_token, hash_token};
+
+/// Lifetime of an authorization code.
+const AUTHORIZATION_CODE_TTL_SECONDS: i64 = 60;
+/// Lifetime of access and ID tokens.
+const ACCESS_TOKEN_TTL_SECONDS: i64 = 3600;
+/// Length of a generated `client_id`.
+const CLIENT_ID_LEN: usize = 24;
+/// Scopes understood by the provider; `openid` is required.
+const SUPPORTED_SCOPES: [&str; 3] = ["openid", "profile", "email"];
+/// Claims carried by ID tokens and returned by userinfo.
+const SUPPORTED_CLAIMS: [&str; 9] = [
+    "iss", "sub", "aud", "exp", "iat", "email", "name", "hub_id", "roles",
+];
+
+/// Errors of the OpenID Connect endpoints, named after the RFC 6749 error
+/// codes returned to clients.
+#[derive(Debug, Error)]
+pub enum OAuthError {
+    #[error("invalid_request: {0}")]
+    InvalidRequest(&'static str),
+    #[error("invalid_client")]
+    InvalidClient,
+    #[error("invalid_grant")]
+    InvalidGrant,
+    #[error("invalid_scope")]
+    InvalidScope,
+    #[error("unsupported_response_type")]
+    UnsupportedResponseType,
+    #[error("unsupported_grant_type")]
+    UnsupportedGrantType,
+    #[error("invalid_token")]
+    InvalidToken,
+    #[error(transparent)]
+    Service(#[from] ServiceError),
+}
+
+impl From<RepositoryError> for OAuthError {
+    fn from(err: RepositoryError) -> Self {
+        Self::Service(err.into())
+    }
+}
+
+impl OAuthError {
+    /// Error code sent to the client.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::InvalidClient => "invalid_client",
+            Self::InvalidGrant => "invalid_grant",
+            Self::InvalidScope => "invalid_scope",
+            Self::UnsupportedResponseType => "unsupported_response_type",
+            Self::UnsupportedGrantType => "unsupported_grant_type",
+            Self::InvalidToken => "invalid_token",
+            Self::Service(_) => "server_error",
+        }
+    }
+}
+
+impl From<&OAuthError> for OAuthErrorDto {
+    fn from(err: &OAuthError) -> Self {
+        Self {
+            error: err.code().to_string(),
+            error_description: match err {
+                OAuthError::InvalidRequest(description) => Some(description.to_string()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Whether the provider can issue ID tokens that relying parties verify
+/// with the JWKS. Under `HS256` the authorization code flow and discovery are
+/// refused; the client credentials grant, introspection, and revocation keep
+/// working.
+pub fn id_tokens_supported(keys: &JwtKeys) -> bool {
+    keys.verifiable_by_jwks()
+}
+
+/// Computes the `S256` PKCE challenge of a code verifier.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Returns the supported scopes requested in `scope`, or
+/// [`OAuthError::InvalidScope`] when `openid` is missing.
+fn normalize_scope(scope: Option<&str>) -> Result<String, OAuthError> {
+    let requested: Vec<&str> = scope.unwrap_or_default().split_whitespace().collect();
+    if !requested.contains(&"openid") {
+        return Err(OAuthError::InvalidScope);
+    }
+    Ok(SUPPORTED_SCOPES
+        .into_iter()
+        .filter(|scope| requested.contains(scope))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+/// Registers a new client and returns its credentials.
+///
+/// Confidential clients get a secret that is only shown in this response.
+pub fn create_client(
+    payload: AddOAuthClientPayload,
+    current_user: &AuthenticatedUser,
+    repo: &(impl OAuthClientWriter + PermissionReader),
+) -> ServiceResult<OAuthClientCredentialsDto> {
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
+
+    let client_id = generate_token()[..CLIENT_ID_LEN].to_string();
+    let client_secret = payload.confidential.then(generate_token);
+    let new_client = NewOAuthClient::new(
+        client_id,
+        client_secret.as_deref().map(hash_token),
+        payload.name,
+        payload.redirect_uris,
+    );
+    let client = repo.create_oauth_client(&new_client)?;
+
+    Ok(OAuthClientCredentialsDto {
+        message: "Клиент добавлен.".to_string(),
+        client_id: client.client_id,
+        client_secret,
+    })
+}
+
+/// Removes a client; its codes and access tokens stop working immediately.
+pub fn delete_client(
+    id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &(impl OAuthClientWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::HubsManage, repo)?;
+    let id = OAuthClientId::new(id)?;
+    match repo.delete_oauth_client(id)? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Looks up the client of an authorization request and checks its redirect
+/// URI.
+///
+/// Errors returned here must not be redirected to the client, since the
+/// redirect URI itself is not trusted.
+pub fn find_client(
+    form: &AuthorizeForm,
+    repo: &impl OAuthClientReader,
+) -> Result<OAuthClient, OAuthError> {
+    let client_id = form
+        .client_id
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest("client_id is required"))?;
+    let client = repo
+        .get_oauth_client(client_id)?
+        .ok_or(OAuthError::InvalidClient)?;
+    match form.redirect_uri.as_deref() {
+        Some(redirect_uri) if client.allows_redirect_uri(redirect_uri) => Ok(client),
+        _ => Err(OAuthError::InvalidRequest("redirect_uri is not registered")),
+    }
+}
+
+/// Issues an authorization code for the signed-in user and returns the raw
+/// code.
+///
+/// `client` must come from [`find_client`] for the same request.
+pub fn authorize(
+    form: &AuthorizeForm,
+    client: &OAuthClient,
+    current_user: &AuthenticatedUser,
+    repo: &impl OAuthTokenWriter,
+) -> Result<String, OAuthError> {
+    if form.response_type.as_deref() != Some("code") {
+        return Err(OAuthError::UnsupportedResponseType);
+    }
+    let scope = normalize_scope(form.scope.as_deref())?;
+    let code_challenge = form
+        .code_challenge
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest("code_challenge is required"))?;
+    if form.code_challenge_method.as_deref() != Some("S256") {
+        return Err(OAuthError::InvalidRequest(
+            "code_challenge_method must be S256",
+        ));
+    }
+    // A SHA-256 digest is always 43 characters in unpadded base64url.
+    if code_challenge.len() != 43 {
+        return Err(OAuthError::InvalidRequest("code_challenge is malformed"));
+    }
+
+    let user_id: i32 = current_user
+        .sub
+        .parse()
+        .map_err(|_| ServiceError::Internal)?;
+    let code = generate_token();
+    repo.create_authorization_code(&NewAuthorizationCode {
+        code_hash: hash_token(&code),
+        client_id: client.id,
+        user_id: UserId::new(user_id).map_err(ServiceError::from)?,
+        hub_id: HubId::new(current_user.hub_id).map_err(ServiceError::from)?,
+        redirect_uri: form.redirect_uri.clone().unwrap_or_default(),
+        scope,
+        nonce: form.nonce.clone(),
+        code_challenge: code_challenge.to_string(),
+        expires_at: Utc::now().naive_utc() + Duration::seconds(AUTHORIZATION_CODE_TTL_SECONDS),
+    })?;
+    Ok(code)
+}
+
+/// Appends `params` to the query of a registered redirect URI.
+pub fn authorization_response_url(
+    redirect_uri: &str,
+    params: &[(&str, &str)],
+) -> ServiceResult<String> {
+    let mut url = Url::parse(redirect_uri).map_err(|_| ServiceError::Internal)?;
+    url.query_pairs_mut().extend_pairs(params);
+    Ok(url.into())
+}
+
+/// Returns the client id and secret of a request.
+///
+/// `basic_credentials` come from the `Authorization` header and take
+/// precedence over `form_credentials` from the form body.
+pub(crate) fn client_credentials(
+    form_credentials: (Option<&str>, Option<&str>),
+    basic_credentials: Option<(String, String)>,
+) -> (Option<String>, Option<String>) {
+    match basic_credentials {
+        Some((client_id, client_secret)) => (Some(client_id), Some(client_secret)),
+        None => (
+            form_credentials.0.map(str::to_string),
+            form_credentials.1.map(str::to_string),
+        ),
+    }
+}
+
+/// Authenticates the client of a token, introspection, or revocation
+/// request.
+fn authenticate_client(
+    form_credentials: (Option<&str>, Option<&str>),
+    basic_credentials: Option<(String, String)>,
+    repo: &impl OAuthClientReader,
+) -> Result<OAuthClient, OAuthError> {
+    let (client_id, client_secret) = client_credentials(form_credentials, basic_credentials);
+    let client = repo
+        .get_oauth_client(client_id.as_deref().ok_or(OAuthError::InvalidClient)?)?
+        .ok_or(OAuthError::InvalidClient)?;
+
+    match (&client.client_secret_hash, client_secret) {
+        (None, _) => Ok(client),
+        (Some(secret_hash), Some(secret)) if hash_token(&secret) == *secret_hash => Ok(client),
+        _ => Err(OAuthError::InvalidClient),
+    }
+}
+
+/// Exchanges an authorization code for an access token and an ID token.
+///
+/// The code is consumed before it is matched against the client, redirect
+/// URI and verifier, so a code presented with wrong values cannot be retried.
+/// Returns [`OAuthError::UnsupportedGrantType`] unless
+/// [`id_tokens_supported`].
+pub fn exchange_code<R>(
+    form: TokenForm,
+    basic_credentials: Option<(String, String)>,
+    issuer: &str,
+    keys: &JwtKeys,
+    repo: &R,
+) -> Result<TokenResponseDto, OAuthError>
+where
+    R: OAuthClientReader + OAuthTokenWriter + UserReader,
+{
+    if form.grant_type.as_deref() != Some("authorization_code") || !id_tokens_supported(keys) {
+        return Err(OAuthError::UnsupportedGrantType);
+    }
+    let client = authenticate_client(
+        (form.client_id.as_deref(), form.client_secret.as_deref()),
+        basic_credentials,
+        repo,
+    )?;
+    let code = form
+        .code
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest("code is required"))?;
+
+    let now = Utc::now();
+    let code = repo
+        .consume_authorization_code(&hash_token(code), now.naive_utc())?
+        .ok_or(OAuthError::InvalidGrant)?;
+    if code.client_id != client.id || form.redirect_uri.as_deref() != Some(&code.redirect_uri) {
+        return Err(OAuthError::InvalidGrant);
+    }
+    let code_verifier = form
+        .code_verifier
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest("code_verifier is required"))?;
+    if pkce_challenge(code_verifier) != code.code_challenge {
+        return Err(OAuthError::InvalidGrant);
+    }
+
+    let user = repo
+        .get_user_by_id(code.user_id, code.hub_id)?
+        .filter(|user_roles| !user_roles.user.is_disabled())
+        .map(AuthenticatedUser::from)
+        .ok_or(OAuthError::InvalidGrant)?;
+
+    let expires_at = now + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS);
+    let id_token = keys.encode(&IdTokenClaimsDto {
+        iss: issuer.to_string(),
+        sub: user.sub,
+        aud: client.client_id,
+        exp: expires_at.timestamp(),
+        iat: now.timestamp(),
+        nonce: code.nonce,
+        email: user.email,
+        name: user.name,
+        hub_id: user.hub_id,
+        roles: user.roles,
+    })?;
+
+    let access_token = generate_token();
+    repo.create_access_token(&NewAccessToken {
+        token_hash: hash_token(&access_token),
+        client_id: client.id,
+        user_id: code.user_id,
+        hub_id: code.hub_id,
+        scope: code.scope.clone(),
+        expires_at: expires_at.naive_utc(),
+    })?;
+
+    Ok(TokenResponseDto {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ACCESS_TOKEN_TTL_SECONDS,
+        id_token,
+        scope: code.scope,
+    })
+}
+
+/// Returns the current claims of the user an access token was issued to.
+pub fn userinfo(
+    access_token: &str,
+    repo: &(impl OAuthTokenReader + UserReader),
+) -> Result<UserInfoDto, OAuthError> {
+    let now = Utc::now().naive_utc();
+    let token = repo
+        .get_access_token(&hash_token(access_token))?
+        .filter(|token| token.is_active(now))
+        .ok_or(OAuthError::InvalidToken)?;
+    let user = repo
+        .get_user_by_id(token.user_id, token.hub_id)?
+        .filter(|user_roles| !user_roles.user.is_disabled())
+        .map(AuthenticatedUser::from)
+        .ok_or(OAuthError::InvalidToken)?;
+
+    Ok(UserInfoDto {
+        sub: user.sub,
+        email: user.email,
+        name: user.name,
+        hub_id: user.hub_id,
+        roles: user.roles,
+    })
+}
+
+/// A live token found in the token or session store.
+enum StoredToken {
+    Access(AccessToken),
+    ServiceAccount(ServiceAccountToken),
+    Session(Session),
+}
+
+/// Looks up a presented token: service account tokens by their prefix and
+/// hash, then as an opaque access token by its hash, then as a session JWT by
+/// its `jti`.
+///
+/// Returns [`None`] for unknown, malformed, expired, or revoked tokens.
+fn find_live_token(
+    token: &str,
+    keys: &JwtKeys,
+    repo: &(impl OAuthTokenReader + ServiceAccountReader + SessionReader),
+) -> Result<Option<StoredToken>, OAuthError> {
+    let now = Utc::now().naive_utc();
+    if token.starts_with(SERVICE_ACCOUNT_TOKEN_PREFIX) {
+        return Ok(repo
+            .get_service_account_token(&hash_token(token))?
+            .filter(|token| token.is_active(now))
+            .map(StoredToken::ServiceAccount));
+    }
+    if let Some(access_token) = repo.get_access_token(&hash_token(token))? {
+        return Ok(access_token
+            .is_active(now)
+            .then_some(StoredToken::Access(access_token)));
+    }
+
+    let Ok(claims) = keys.decode(token) else {
+        return Ok(None);
+    };
+    let Some(jti) = claims.jti.as_deref() else {
+        return Ok(None);
+    };
+    Ok(repo
+        .get_session_by_jti(jti)?
+        .filter(|session| session.user_id.get().to_string() == claims.user.sub)
+        .filter(|session| session.is_live(now))
+        .map(StoredToken::Session))
+}
+
+/// Reports whether a token is active and, if so, the current claims of its
+/// user or service account.
+///
+/// Only confidential clients may introspect tokens. A token whose user or
+/// service account no longer exists is reported inactive.
+pub fn introspect<R>(
+    form: &TokenLookupForm,
+    basic_credentials: Option<(String, String)>,
+    keys: &JwtKeys,
+    repo: &R,
+) -> Result<IntrospectionDto, OAuthError>
+where
+    R: OAuthClientReader + OAuthTokenReader + ServiceAccountReader + SessionReader + UserReader,
+{
+    let client = authenticate_client(
+        (form.client_id.as_deref(), form.client_secret.as_deref()),
+        basic_credentials,
+        repo,
+    )?;
+    if client.is_public() {
+        return Err(OAuthError::InvalidClient);
+    }
+    let token = form
+        .token
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest("token is required"))?;
+
+    let stored = find_live_token(token, keys, repo)?;
+    let (user, expires_at, token_type, scope) = match stored {
+        Some(StoredToken::Access(access_token)) => (
+            repo.get_user_by_id(access_token.user_id, access_token.hub_id)?
+                .filter(|user_roles| !user_roles.user.is_disabled())
+                .map(AuthenticatedUser::from),
+            access_token.expires_at,
+            "Bearer",
+            Some(access_token.scope),
+        ),
+        Some(StoredToken::ServiceAccount(token)) => (
+            repo.get_service_account(token.service_account_id, token.hub_id)?
+                .map(AuthenticatedUser::from),
+            token.expires_at,
+            "service_account",
+            None,
+        ),
+        Some(StoredToken::Session(session)) => (
+            repo.get_user_by_id(session.user_id, session.hub_id)?
+                .filter(|user_roles| !user_roles.user.is_disabled())
+                .map(AuthenticatedUser::from),
+            session.expires_at,
+            "session",
+            None,
+        ),
+        None => return Ok(IntrospectionDto::inactive()),
+    };
+    let Some(mut user) = user else {
+        return Ok(IntrospectionDto::inactive());
+    };
+    user.exp = expires_at.and_utc().timestamp();
+
+    Ok(IntrospectionDto {
+        active: true,
+        user: Some(user),
+        token_type: Some(token_type.to_string()),
+        scope,
+    })
+}
+
+/// Revokes an access token or a session.
+///
+/// Clients may revoke the access tokens issued to them; confidential clients
+/// may also end sessions. Service account tokens are only revoked by rotating
+/// the account's secret. As RFC 7009 requires, unknown tokens and tokens
+/// the client may not revoke are ignored, so the response never reveals
+/// whether a token exists.
+pub fn revoke<R>(
+    form: &TokenLookupForm,
+    basic_credentials: Option<(String, String)>,
+    keys: &JwtKeys,
+    repo: &R,
+) -> Result<(), OAuthError>
+where
+    R: OAuthClientReader
+        + OAuthTokenReader
+        + OAuthTokenWriter
+        + ServiceAccountReader
+        + SessionReader
+        + SessionWriter,
+{
+    let client = authenticate_client(
+        (form.client_id.as_deref(), form.client_secret.as_deref()),
+        basic_credentials,
+        repo,
+    )?;
+    let token = form
+        .token
+        .as_deref()
+        .ok_or(OAuthError::InvalidRequest("token is required"))?;
+
+    let now = Utc::now().naive_utc();
+    match find_live_token(token, keys, repo)? {
+        Some(StoredToken::Access(access_token)) if access_token.client_id == client.id => {
+            repo.revoke_access_token(&access_token.token_hash, now)?;
+        }
+        Some(StoredToken::Session(session)) if !client.is_public() => {
+            repo.revoke_session_by_jti(&session.jti, now)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+/// Builds the discovery document for `issuer`, or `None` unless
+/// [`id_tokens_supported`].
+pub fn discovery_document(issuer: &str, keys: &JwtKeys) -> Option<OpenIdConfigurationDto> {
+    if !id_tokens_supported(keys) {
+        return None;
+    }
+    Some(OpenIdConfigurationDto {
+        issuer: issuer.to_string(),
+        authorization_endpoint: format!("{issuer}/oauth/authorize"),
+        token_endpoint: format!("{issuer}/oauth/token"),
+        userinfo_endpoint: format!("{issuer}/oauth/userinfo"),
+        introspection_endpoint: format!("{issuer}/oauth/introspect"),
+        revocation_endpoint: format!("{issuer}/oauth/revoke"),
+        jwks_uri: format!("{issuer}/.well-known/jwks.json"),
+        response_types_supported: strings(&["code"]),
+        grant_types_supported: strings(&["authorization_code", "client_credentials"]),
+        subject_types_supported: strings(&["public"]),
+        id_token_signing_alg_values_supported: vec![format!("{:?}", keys.algorithm())],
+        scopes_supported: strings(&SUPPORTED_SCOPES),
+        token_endpoint_auth_methods_supported: strings(&[
+            "client_secret_basic",
+            "client_secret_post",
+            "none",
+        ]),
+        code_challenge_methods_supported: strings(&["S256"]),
+        claims_supported: strings(&SUPPORTED_CLAIMS),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::oauth::{AccessToken, AuthorizationCode};
+    use crate::domain::role::Role;
+    use crate::domain::service_account::ServiceAccount;
+    use crate::domain::types::{AccessTokenId, RoleId, RoleName, UserEmail, UserName};
+    use crate::domain::user::{User, UserWithRoles};
+    use crate::models::config::JwtAlgorithm;
+    use crate::repository::mock::MockRepository;
+    use crate::services::jwt::SessionClaims;
+    use crate::services::jwt::tests::ED25519_PEM;
+    use jsonwebtoken::{DecodingKey, Validation};
+
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+    const REDIRECT_URI: &str = "https://app.example.org/callback";
+
+    /// Keys whose ID tokens relying parties can verify with the JWKS.
+    fn make_oidc_keys() -> JwtKeys {
+        JwtKeys::from_pem(JwtAlgorithm::EdDSA, ED25519_PEM.as_bytes(), "k1").unwrap()
+    }
+
+    fn make_current_user() -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: "3".into(),
+            email: "user@example.com".into(),
+            hub_id: 1,
+            name: "User".into(),
+            roles: vec!["crm".into()],
+            exp: 0,
+        }
+    }
+
+    fn make_client(secret: Option<&str>) -> OAuthClient {
+        OAuthClient::try_new(
+            5,
+            "app".into(),
+            secret.map(hash_token),
+            "App".into(),
+            vec![REDIRECT_URI.into()],
+            Utc::now().naive_utc(),
+        )
+        .unwrap()
+    }
+
+    fn make_authorize_form() -> AuthorizeForm {
+        AuthorizeForm {
+            response_type: Some("code".into()),
+            client_id: Some("app".into()),
+            redirect_uri: Some(REDIRECT_URI.into()),
+            scope: Some("openid email".into()),
+            state: Some("xyz".into()),
+            nonce: Some("n-1".into()),
+            code_challenge: Some(CHALLENGE.into()),
+            code_challenge_method: Some("S256".into()),
+        }
+    }
+
+    fn make_token_form() -> TokenForm {
+        TokenForm {
+            grant_type: Some("authorization_code".into()),
+            code: Some("code".into()),
+            redirect_uri: Some(REDIRECT_URI.into()),
+            code_verifier: Some(VERIFIER.into()),
+            client_id: Some("app".into()),
+            client_secret: None,
+        }
+    }
+
+    fn make_code() -> AuthorizationCode {
+        let now = Utc::now().naive_utc();
+        AuthorizationCode::try_new(
+            1,
+            hash_token("code"),
+            5,
+            3,
+            1,
+            REDIRECT_URI.into(),
+            "openid email".into(),
+            Some("n-1".into()),
+            CHALLENGE.into(),
+            now + Duration::seconds(60),
+            Some(now),
+            now,
+        )
+        .unwrap()
+    }
+
+    fn make_user_roles() -> UserWithRoles {
+        let now = Utc::now().naive_utc();
+        let user = User::new(
+            UserId::new(3).unwrap(),
+            UserEmail::new("user@example.com").unwrap(),
+            Some(UserName::new("User").unwrap()),
+            HubId::new(1).unwrap(),
+            "hash".into(),
+            now,
+            now,
+            vec![],
+        );
+        let role = Role::new(
+            RoleId::new(2).unwrap(),
+            RoleName::new("crm").unwrap(),
+            now,
+            now,
+        );
+        UserWithRoles::new(user, vec![role])
+    }
+
+    fn expect_token_issue(repo: &mut MockRepository) {
+        repo.expect_get_user_by_id()
+            .returning(|_, _| Ok(Some(make_user_roles())));
+        repo.expect_create_access_token().returning(|new_token| {
+            let now = Utc::now().naive_utc();
+            Ok(AccessToken::new(
+                AccessTokenId::new(1).unwrap(),
+                new_token.token_hash.clone(),
+                new_token.client_id,
+                new_token.user_id,
+                new_token.hub_id,
+                new_token.scope.clone(),
+                new_token.expires_at,
+                None,
+                now,
+            ))
+        });
+    }
+
+    #[test]
+    fn pkce_challenge_matches_rfc_7636_example() {
+        assert_eq!(pkce_challenge(VERIFIER), CHALLENGE);
+    }
+
+    #[test]
+    fn normalize_scope_requires_openid_and_drops_unknown_scopes() {
+        assert_eq!(
+            normalize_scope(Some("email offline_access openid")).unwrap(),
+            "openid email"
+        );
+        assert!(matches!(
+            normalize_scope(Some("email")),
+            Err(OAuthError::InvalidScope)
+        ));
+    }
+
+    #[test]
+    fn find_client_rejects_unregistered_redirect_uri() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(None))));
+
+        let mut form = make_authorize_form();
+        form.redirect_uri = Some("https://evil.example.com/callback".into());
+        assert!(matches!(
+            find_client(&form, &repo),
+            Err(OAuthError::InvalidRequest(_))
+        ));
+        assert!(find_client(&make_authorize_form(), &repo).is_ok());
+    }
+
+    #[test]
+    fn authorize_requires_s256_pkce() {
+        let mut repo = MockRepository::new();
+        repo.expect_create_authorization_code().never();
+
+        let mut form = make_authorize_form();
+        form.code_challenge_method = Some("plain".into());
+        let res = authorize(&form, &make_client(None), &make_current_user(), &repo);
+        assert!(matches!(res, Err(OAuthError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn authorize_stores_hashed_code_for_current_user() {
+        let mut repo = MockRepository::new();
+        repo.expect_create_authorization_code()
+            .withf(|new_code| {
+                new_code.user_id.get() == 3
+                    && new_code.hub_id.get() == 1
+                    && new_code.client_id.get() == 5
+                    && new_code.scope == "openid email"
+                    && new_code.nonce.as_deref() == Some("n-1")
+            })
+            .times(1)
+            .returning(|_| Ok(make_code()));
+
+        let code = authorize(
+            &make_authorize_form(),
+            &make_client(None),
+            &make_current_user(),
+            &repo,
+        )
+        .unwrap();
+        assert_ne!(hash_token(&code), code);
+    }
+
+    #[test]
+    fn exchange_code_issues_id_token_with_hub_and_roles() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(None))));
+        repo.expect_consume_authorization_code()
+            .withf(|code_hash, _| code_hash == hash_token("code"))
+            .returning(|_, _| Ok(Some(make_code())));
+        expect_token_issue(&mut repo);
+
+        let keys = make_oidc_keys();
+        let response = exchange_code(
+            make_token_form(),
+            None,
+            "https://auth.example.org",
+            &keys,
+            &repo,
+        )
+        .unwrap();
+        assert_eq!(response.token_type, "Bearer");
+
+        let mut validation = Validation::new(jsonwebtoken::Algorithm::EdDSA);
+        validation.set_audience(&["app"]);
+        let claims = jsonwebtoken::decode::<IdTokenClaimsDto>(
+            &response.id_token,
+            &DecodingKey::from_jwk(keys.jwks().find("k1").unwrap()).unwrap(),
+            &validation,
+        )
+        .unwrap()
+        .claims;
+        assert_eq!(claims.iss, "https://auth.example.org");
+        assert_eq!(claims.sub, "3");
+        assert_eq!(claims.hub_id, 1);
+        assert_eq!(claims.roles, vec!["crm".to_string()]);
+        assert_eq!(claims.nonce.as_deref(), Some("n-1"));
+
+        // ID tokens are never accepted as sessions.
+        assert!(keys.decode(&response.id_token).is_err());
+    }
+
+    #[test]
+    fn exchange_code_rejects_wrong_verifier() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(None))));
+        repo.expect_consume_authorization_code()
+            .returning(|_, _| Ok(Some(make_code())));
+        repo.expect_create_access_token().never();
+
+        let mut form = make_token_form();
+        form.code_verifier = Some("wrong-verifier".into());
+        let keys = make_oidc_keys();
+        let res = exchange_code(form, None, "https://auth.example.org", &keys, &repo);
+        assert!(matches!(res, Err(OAuthError::InvalidGrant)));
+    }
+
+    #[test]
+    fn exchange_code_requires_secret_of_confidential_client() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(Some("s3cret")))));
+        repo.expect_consume_authorization_code().never();
+
+        let keys = make_oidc_keys();
+        let res = exchange_code(
+            make_token_form(),
+            Some(("app".into(), "wrong".into())),
+            "https://auth.example.org",
+            &keys,
+            &repo,
+        );
+        assert!(matches!(res, Err(OAuthError::InvalidClient)));
+    }
+
+    #[test]
+    fn exchange_code_is_refused_under_hs256() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client().never();
+        repo.expect_consume_authorization_code().never();
+
+        let keys = JwtKeys::from_secret("secret", "default");
+        let res = exchange_code(
+            make_token_form(),
+            None,
+            "https://auth.example.org",
+            &keys,
+            &repo,
+        );
+        assert!(matches!(res, Err(OAuthError::UnsupportedGrantType)));
+    }
+
+    #[test]
+    fn userinfo_rejects_revoked_tokens() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_access_token().returning(|token_hash| {
+            let now = Utc::now().naive_utc();
+            Ok(Some(
+                AccessToken::try_new(
+                    1,
+                    token_hash.to_string(),
+                    5,
+                    3,
+                    1,
+                    "openid".into(),
+                    now + Duration::hours(1),
+                    Some(now),
+                    now,
+                )
+                .unwrap(),
+            ))
+        });
+        repo.expect_get_user_by_id().never();
+
+        assert!(matches!(
+            userinfo("token", &repo),
+            Err(OAuthError::InvalidToken)
+        ));
+    }
+
+    fn make_access_token(token_hash: &str, client_id: i32) -> AccessToken {
+        let now = Utc::now().naive_utc();
+        AccessToken::try_new(
+            1,
+            token_hash.to_string(),
+            client_id,
+            3,
+            1,
+            "openid email".into(),
+            now + Duration::hours(1),
+            None,
+            now,
+        )
+        .unwrap()
+    }
+
+    fn make_session(jti: &str) -> Session {
+        let now = Utc::now().naive_utc();
+        Session::try_new(
+            7,
+            jti.to_string(),
+            3,
+            1,
+            "default".into(),
+            now,
+            now + Duration::days(7),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn make_lookup_form(token: &str) -> TokenLookupForm {
+        TokenLookupForm {
+            token: Some(token.into()),
+            ..Default::default()
+        }
+    }
+
+    fn service_credentials() -> Option<(String, String)> {
+        Some(("app".into(), "s3cret".into()))
+    }
+
+    #[test]
+    fn introspect_reports_live_access_token_with_user_claims() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(Some("s3cret")))));
+        repo.expect_get_access_token()
+            .withf(|token_hash| token_hash == hash_token("token"))
+            .returning(|token_hash| Ok(Some(make_access_token(token_hash, 5))));
+        repo.expect_get_user_by_id()
+            .returning(|_, _| Ok(Some(make_user_roles())));
+
+        let keys = JwtKeys::from_secret("secret", "default");
+        let response = introspect(
+            &make_lookup_form("token"),
+            service_credentials(),
+            &keys,
+            &repo,
+        )
+        .unwrap();
+        assert!(response.active);
+        assert_eq!(response.token_type.as_deref(), Some("Bearer"));
+        assert_eq!(response.scope.as_deref(), Some("openid email"));
+        let user = response.user.unwrap();
+        assert_eq!(user.sub, "3");
+        assert_eq!(user.roles, vec!["crm".to_string()]);
+    }
+
+    #[test]
+    fn introspect_reports_revoked_session_as_inactive() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(Some("s3cret")))));
+        repo.expect_get_access_token().returning(|_| Ok(None));
+        repo.expect_get_session_by_jti().returning(|jti| {
+            let mut session = make_session(jti);
+            session.revoked_at = Some(Utc::now().naive_utc());
+            Ok(Some(session))
+        });
+        repo.expect_get_user_by_id().never();
+
+        let keys = JwtKeys::from_secret("secret", "default");
+        let mut user = make_current_user();
+        user.set_expiration(7);
+        let token = keys
+            .encode(&SessionClaims {
+                user,
+                jti: Some("jti-1".into()),
+                permissions: Vec::new(),
+            })
+            .unwrap();
+        let response = introspect(
+            &make_lookup_form(&token),
+            service_credentials(),
+            &keys,
+            &repo,
+        )
+        .unwrap();
+        assert!(!response.active);
+        assert!(response.user.is_none());
+    }
+
+    #[test]
+    fn introspect_reports_service_account_token_with_account_claims() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(Some("s3cret")))));
+        repo.expect_get_access_token().never();
+        repo.expect_get_service_account_token()
+            .withf(|token_hash| token_hash == hash_token("sat_token"))
+            .returning(|token_hash| {
+                let now = Utc::now().naive_utc();
+                Ok(Some(
+                    ServiceAccountToken::try_new(
+                        1,
+                        4,
+                        10,
+                        token_hash.to_string(),
+                        now + Duration::hours(1),
+                        None,
+                        now,
+                    )
+                    .unwrap(),
+                ))
+            });
+        repo.expect_get_service_account().returning(|_, _| {
+            let now = Utc::now().naive_utc();
+            Ok(Some(
+                ServiceAccount::try_new(
+                    4,
+                    10,
+                    "CRM sync".into(),
+                    "sa_client".into(),
+                    "hash".into(),
+                    now,
+                    now,
+                    vec![],
+                )
+                .unwrap(),
+            ))
+        });
+
+        let keys = JwtKeys::from_secret("secret", "default");
+        let response = introspect(
+            &make_lookup_form("sat_token"),
+            service_credentials(),
+            &keys,
+            &repo,
+        )
+        .unwrap();
+        assert!(response.active);
+        assert_eq!(response.token_type.as_deref(), Some("service_account"));
+        assert_eq!(response.user.unwrap().sub, "sa_client");
+    }
+
+    #[test]
+    fn introspect_requires_confidential_client() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(None))));
+        repo.expect_get_access_token().never();
+
+        let keys = JwtKeys::from_secret("secret", "default");
+        let mut form = make_lookup_form("token");
+        form.client_id = Some("app".into());
+        let res = introspect(&form, None, &keys, &repo);
+        assert!(matches!(res, Err(OAuthError::InvalidClient)));
+    }
+
+    #[test]
+    fn revoke_only_touches_tokens_of_calling_client() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(Some("s3cret")))));
+        repo.expect_get_access_token()
+            .returning(|token_hash| Ok(Some(make_access_token(token_hash, 6))));
+        repo.expect_revoke_access_token().never();
+
+        let keys = JwtKeys::from_secret("secret", "default");
+        assert!(
+            revoke(
+                &make_lookup_form("token"),
+                service_credentials(),
+                &keys,
+                &repo
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn revoke_ends_session_of_session_jwt() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_oauth_client()
+            .returning(|_| Ok(Some(make_client(Some("s3cret")))));
+        repo.expect_get_access_token().returning(|_| Ok(None));
+        repo.expect_get_session_by_jti()
+            .returning(|jti| Ok(Some(make_session(jti))));
+        repo.expect_revoke_session_by_jti()
+            .withf(|jti, _| jti == "jti-1")
+            .times(1)
+            .returning(|_, _| Ok(1));
+
+        let keys = JwtKeys::from_secret("secret", "default");
+        let mut user = make_current_user();
+        user.set_expiration(7);
+        let token = keys
+            .encode(&SessionClaims {
+                user,
+                jti: Some("jti-1".into()),
+                permissions: Vec::new(),
+            })
+            .unwrap();
+        assert!(
+            revoke(
+                &make_lookup_form(&token),
+                service_credentials(),
+                &keys,
+                &repo
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn discovery_document_advertises_active_algorithm() {
+        let keys = JwtKeys::from_secret("secret", "default");
+        assert!(discovery_document("https://auth.example.org", &keys).is_none());
+
+        let document = discovery_document("https://auth.example.org", &make_oidc_keys()).unwrap();
+        assert_eq!(
+            document.token_endpoint,
+            "https://auth.example.org/oauth/token"
+        );
+        assert_eq!(
+            document.id_token_signing_alg_values_supported,
+            vec!["EdDSA"]
+        );
+        assert_eq!(document.code_challenge_methods_supported, vec!["S256"]);
+        assert_eq!(
+            document.introspection_endpoint,
+            "https://auth.example.org/oauth/introspect"
+        );
+    }
+}
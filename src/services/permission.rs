@@ -0,0 +1,119 @@
+//! Permission checks for the current user.
+//!
+//! Users hold the permissions of all their roles. The checks read the
+//! assignments from the database, so a change to a user's roles applies to
+//! their next request.
+
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+
+use crate::domain::permission::Permission;
+use crate::domain::types::{HubId, UserId};
+use crate::repository::PermissionReader;
+use crate::services::jwt::JwtKeys;
+
+/// Lists the permissions of the current user. Service accounts hold none.
+pub fn current_permissions(
+    current_user: &AuthenticatedUser,
+    repo: &impl PermissionReader,
+) -> ServiceResult<Vec<Permission>> {
+    let Ok(user_id) = current_user.sub.parse::<i32>() else {
+        return Ok(Vec::new());
+    };
+    let user_id = UserId::new(user_id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    Ok(repo.list_user_permissions(user_id, hub_id)?)
+}
+
+/// Returns [`ServiceError::Unauthorized`] unless the current user holds
+/// `permission`.
+pub fn ensure_permission(
+    current_user: &AuthenticatedUser,
+    permission: Permission,
+    repo: &impl PermissionReader,
+) -> ServiceResult<()> {
+    if current_permissions(current_user, repo)?.contains(&permission) {
+        Ok(())
+    } else {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+/// Names of the permissions to put into a session token of the current
+/// user; empty unless `app.jwt.permissions_claim` is enabled.
+pub fn permissions_claim(
+    current_user: &AuthenticatedUser,
+    keys: &JwtKeys,
+    repo: &impl PermissionReader,
+) -> ServiceResult<Vec<String>> {
+    if !keys.permissions_claim() {
+        return Ok(Vec::new());
+    }
+    Ok(current_permissions(current_user, repo)?
+        .into_iter()
+        .map(|permission| permission.to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::mock::MockRepository;
+
+    fn make_user(sub: &str) -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: sub.into(),
+            email: "user@example.com".into(),
+            hub_id: 10,
+            name: "User".into(),
+            roles: vec![],
+            exp: 0,
+        }
+    }
+
+    #[test]
+    fn ensure_permission_checks_the_users_roles_in_their_hub() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .withf(|user_id, hub_id| user_id.get() == 5 && hub_id.get() == 10)
+            .returning(|_, _| Ok(vec![Permission::UsersRead, Permission::MenuManage]));
+
+        let user = make_user("5");
+        assert!(ensure_permission(&user, Permission::MenuManage, &repo).is_ok());
+        assert!(matches!(
+            ensure_permission(&user, Permission::UsersWrite, &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn permissions_claim_is_filled_only_when_enabled() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .times(1)
+            .returning(|_, _| Ok(vec![Permission::UsersRead, Permission::HubsManage]));
+
+        let user = make_user("5");
+        let keys = JwtKeys::from_secret("secret", "default");
+        assert!(permissions_claim(&user, &keys, &repo).unwrap().is_empty());
+
+        let keys = keys.with_permissions_claim(true);
+        assert_eq!(
+            permissions_claim(&user, &keys, &repo).unwrap(),
+            vec!["users.read".to_string(), "hubs.manage".to_string()]
+        );
+    }
+
+    #[test]
+    fn service_accounts_hold_no_permissions() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions().never();
+
+        let account = make_user("sa_3f2c9e");
+        assert!(current_permissions(&account, &repo).unwrap().is_empty());
+        assert!(matches!(
+            ensure_permission(&account, Permission::UsersRead, &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+}
@@ -0,0 +1,296 @@
+//! Personal access tokens for scripts and CI.
+//!
+//! Users issue named tokens from their profile. A token is shown once,
+//! stored hashed, expires after the chosen number of days, and is only
+//! accepted as `Authorization: Bearer` by the `/api` scope, where it resolves
+//! to the same claims a session would. Each token is limited to the parts of
+//! the API named by its scopes; endpoints managing the user's own
+//! credentials never accept tokens.
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+
+use crate::domain::personal_token::{NewPersonalAccessToken, PersonalAccessToken, TokenScope};
+use crate::domain::types::{HubId, PersonalAccessTokenId, UserId};
+use crate::dto::api::{PersonalTokenCreatedDto, PersonalTokenListItemDto};
+use crate::forms::main::AddPersonalTokenPayload;
+use crate::repository::{PersonalTokenReader, PersonalTokenWriter, UserReader};
+use crate::services::jwt::SessionClaims;
+use crate::services::tokens::{generate_token, hash_token};
+
+/// Prefix of issued tokens, so they are easy to recognize in logs and by
+/// secret scanners.
+const TOKEN_PREFIX: &str = "pat_";
+/// Minimum time between two `last_used_at` updates of a token.
+const LAST_USED_INTERVAL_SECONDS: i64 = 60;
+
+fn current_user_id(current_user: &AuthenticatedUser) -> ServiceResult<UserId> {
+    let user_id: i32 = current_user
+        .sub
+        .parse()
+        .map_err(|_| ServiceError::Internal)?;
+    Ok(UserId::new(user_id)?)
+}
+
+fn needs_touch(token: &PersonalAccessToken, now: NaiveDateTime) -> bool {
+    token.last_used_at.is_none_or(|last_used_at| {
+        now - last_used_at >= Duration::seconds(LAST_USED_INTERVAL_SECONDS)
+    })
+}
+
+/// Returns the scope a token needs to call the `/api` endpoint at `path`, or
+/// [`None`] when the endpoint does not accept tokens at all.
+pub fn required_scope(path: &str) -> Option<TokenScope> {
+    let path = path.trim_end_matches('/');
+    let under = |prefix: &str| {
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+
+    if under("/api/v1/users") {
+        Some(TokenScope::Users)
+    } else if under("/api/v1/admin") {
+        Some(TokenScope::Admin)
+    } else if under("/api/v1/id") || under("/api/v1/iam") || under("/api/v1/hubs") {
+        Some(TokenScope::Profile)
+    } else {
+        None
+    }
+}
+
+/// Issues a personal access token for the current user and returns it once.
+pub fn create_token(
+    payload: AddPersonalTokenPayload,
+    current_user: &AuthenticatedUser,
+    repo: &impl PersonalTokenWriter,
+) -> ServiceResult<PersonalTokenCreatedDto> {
+    let user_id = current_user_id(current_user)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+
+    let token = format!("{TOKEN_PREFIX}{}", generate_token());
+    let expires_at = Utc::now().naive_utc() + Duration::days(payload.expires_in_days);
+    let stored = repo.create_personal_token(&NewPersonalAccessToken {
+        user_id,
+        hub_id,
+        name: payload.name,
+        token_hash: hash_token(&token),
+        scopes: payload.scopes,
+        expires_at,
+    })?;
+
+    Ok(PersonalTokenCreatedDto {
+        message: "Токен создан. Скопируйте его сейчас, он больше не будет показан.".to_string(),
+        token,
+        expires_at: stored.expires_at,
+    })
+}
+
+/// Lists the live personal access tokens of the current user.
+pub fn list_tokens(
+    current_user: &AuthenticatedUser,
+    repo: &impl PersonalTokenReader,
+) -> ServiceResult<Vec<PersonalTokenListItemDto>> {
+    let user_id = current_user_id(current_user)?;
+    Ok(repo
+        .list_personal_tokens(user_id, Utc::now().naive_utc())?
+        .into_iter()
+        .map(PersonalTokenListItemDto::from)
+        .collect())
+}
+
+/// Revokes one of the current user's personal access tokens.
+pub fn revoke_token(
+    id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &impl PersonalTokenWriter,
+) -> ServiceResult<()> {
+    let user_id = current_user_id(current_user)?;
+    let id = PersonalAccessTokenId::new(id)?;
+    match repo.revoke_personal_token(user_id, id, Utc::now().naive_utc())? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Resolves a Bearer token to the current claims of its owner and records
+/// its use.
+///
+/// The claims carry no `jti` and expire with the token. Returns
+/// [`ServiceError::Unauthorized`] when the token is unknown, revoked, or
+/// expired, or its owner no longer exists or is suspended.
+pub fn authenticate(
+    token: &str,
+    repo: &(impl PersonalTokenReader + PersonalTokenWriter + UserReader),
+) -> ServiceResult<(SessionClaims, PersonalAccessToken)> {
+    let now = Utc::now().naive_utc();
+    let token = repo
+        .get_personal_token_by_hash(&hash_token(token))?
+        .filter(|token| token.is_active(now))
+        .ok_or(ServiceError::Unauthorized)?;
+    let mut user = repo
+        .get_user_by_id(token.user_id, token.hub_id)?
+        .filter(|user_roles| !user_roles.user.is_disabled())
+        .map(AuthenticatedUser::from)
+        .ok_or(ServiceError::Unauthorized)?;
+    user.exp = token.expires_at.and_utc().timestamp();
+
+    if needs_touch(&token, now) {
+        repo.touch_personal_token(token.id, now)?;
+    }
+    Ok((
+        SessionClaims {
+            user,
+            jti: None,
+            permissions: Vec::new(),
+        },
+        token,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::types::{UserEmail, UserName};
+    use crate::domain::user::{User, UserWithRoles};
+    use crate::repository::mock::MockRepository;
+
+    fn make_current_user() -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: "3".into(),
+            email: "user@example.com".into(),
+            hub_id: 1,
+            name: "User".into(),
+            roles: vec![],
+            exp: 0,
+        }
+    }
+
+    fn make_token(
+        token_hash: &str,
+        last_used_at: Option<NaiveDateTime>,
+        revoked_at: Option<NaiveDateTime>,
+    ) -> PersonalAccessToken {
+        let now = Utc::now().naive_utc();
+        PersonalAccessToken::try_new(
+            8,
+            3,
+            1,
+            "CI".into(),
+            token_hash.to_string(),
+            vec![TokenScope::Users],
+            now + Duration::days(30),
+            last_used_at,
+            revoked_at,
+            now,
+        )
+        .unwrap()
+    }
+
+    fn make_user_roles() -> UserWithRoles {
+        let now = Utc::now().naive_utc();
+        let user = User::new(
+            UserId::new(3).unwrap(),
+            UserEmail::new("user@example.com").unwrap(),
+            Some(UserName::new("User").unwrap()),
+            HubId::new(1).unwrap(),
+            "hash".into(),
+            now,
+            now,
+            vec![],
+        );
+        UserWithRoles::new(user, vec![])
+    }
+
+    #[test]
+    fn required_scope_maps_api_paths() {
+        assert_eq!(required_scope("/api/v1/users"), Some(TokenScope::Users));
+        assert_eq!(required_scope("/api/v1/id"), Some(TokenScope::Profile));
+        assert_eq!(
+            required_scope("/api/v1/hubs/1/menu-items"),
+            Some(TokenScope::Profile)
+        );
+        assert_eq!(
+            required_scope("/api/v1/admin/dashboard"),
+            Some(TokenScope::Admin)
+        );
+        assert_eq!(required_scope("/api/v1/usersx"), None);
+        assert_eq!(required_scope("/api/v1/tokens"), None);
+        assert_eq!(required_scope("/api/v1/sessions"), None);
+    }
+
+    #[test]
+    fn create_token_stores_only_the_hash() {
+        let mut repo = MockRepository::new();
+        repo.expect_create_personal_token()
+            .withf(|new_token| {
+                new_token.user_id.get() == 3
+                    && new_token.hub_id.get() == 1
+                    && new_token.name == "CI"
+                    && !new_token.token_hash.starts_with(TOKEN_PREFIX)
+            })
+            .times(1)
+            .returning(|new_token| Ok(make_token(&new_token.token_hash, None, None)));
+
+        let payload = AddPersonalTokenPayload {
+            name: "CI".into(),
+            scopes: vec![TokenScope::Users],
+            expires_in_days: 30,
+        };
+        let created = create_token(payload, &make_current_user(), &repo).unwrap();
+        assert!(created.token.starts_with(TOKEN_PREFIX));
+    }
+
+    #[test]
+    fn authenticate_resolves_owner_and_records_use() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_personal_token_by_hash()
+            .withf(|token_hash| token_hash == hash_token("pat_secret"))
+            .returning(|token_hash| Ok(Some(make_token(token_hash, None, None))));
+        repo.expect_get_user_by_id()
+            .returning(|_, _| Ok(Some(make_user_roles())));
+        repo.expect_touch_personal_token()
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let (claims, token) = authenticate("pat_secret", &repo).unwrap();
+        assert_eq!(claims.user.sub, "3");
+        assert_eq!(claims.user.hub_id, 1);
+        assert!(claims.jti.is_none());
+        assert_eq!(claims.user.exp, token.expires_at.and_utc().timestamp());
+    }
+
+    #[test]
+    fn authenticate_rejects_revoked_tokens() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_personal_token_by_hash()
+            .returning(|token_hash| {
+                Ok(Some(make_token(
+                    token_hash,
+                    None,
+                    Some(Utc::now().naive_utc()),
+                )))
+            });
+        repo.expect_get_user_by_id().never();
+        repo.expect_touch_personal_token().never();
+
+        assert!(matches!(
+            authenticate("pat_secret", &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn revoke_token_reports_missing_tokens() {
+        let mut repo = MockRepository::new();
+        repo.expect_revoke_personal_token()
+            .returning(|_, _, _| Ok(0));
+
+        assert!(matches!(
+            revoke_token(8, &make_current_user(), &repo),
+            Err(ServiceError::NotFound)
+        ));
+    }
+}
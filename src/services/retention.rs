@@ -0,0 +1,82 @@
+//! Purging of deleted users and hubs.
+//!
+//! Deleting a user or a hub only marks its rows, so administrators can
+//! restore it during the retention window. A background job started with the
+//! server removes the rows for good once the window has passed.
+
+use std::time::Duration as StdDuration;
+
+use actix_web::rt::time::interval;
+use actix_web::web;
+use chrono::{NaiveDateTime, Utc};
+use pushkind_common::services::errors::ServiceResult;
+
+use crate::models::config::RetentionConfig;
+use crate::repository::{DieselRepository, HubWriter, UserWriter};
+
+/// Permanently removes the hubs and users deleted before `now` minus the
+/// retention window.
+///
+/// Returns the number of purged hubs and users.
+pub fn purge_expired(
+    config: &RetentionConfig,
+    now: NaiveDateTime,
+    repo: &(impl HubWriter + UserWriter),
+) -> ServiceResult<(usize, usize)> {
+    let before = now - config.retention();
+    // Hubs go first so the users deleted with them leave with the hub.
+    let hubs = repo.purge_deleted_hubs(before)?;
+    let users = repo.purge_deleted_users(before)?;
+    Ok((hubs, users))
+}
+
+/// Runs [`purge_expired`] every `purge_interval_seconds` for the lifetime of
+/// the server.
+pub async fn run_purge_job(config: RetentionConfig, repo: DieselRepository) {
+    let mut ticker = interval(StdDuration::from_secs(config.purge_interval_seconds.max(1)));
+    loop {
+        ticker.tick().await;
+
+        let config = config.clone();
+        let repo = repo.clone();
+        let result =
+            web::block(move || purge_expired(&config, Utc::now().naive_utc(), &repo)).await;
+        match result {
+            Ok(Ok((0, 0))) => {}
+            Ok(Ok((hubs, users))) => {
+                log::info!("Purged {hubs} deleted hubs and {users} deleted users");
+            }
+            Ok(Err(e)) => log::error!("Failed to purge deleted hubs and users: {e}"),
+            Err(e) => log::error!("Failed to run the purge job: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    use crate::repository::mock::MockRepository;
+
+    #[test]
+    fn purge_expired_uses_the_retention_window() {
+        let now = Utc::now().naive_utc();
+        let config = RetentionConfig {
+            deleted_retention_days: 7,
+            purge_interval_seconds: 60,
+        };
+
+        let mut repo = MockRepository::new();
+        repo.expect_purge_deleted_hubs()
+            .withf(move |before| *before == now - Duration::days(7))
+            .times(1)
+            .returning(|_| Ok(1));
+        repo.expect_purge_deleted_users()
+            .withf(move |before| *before == now - Duration::days(7))
+            .times(1)
+            .returning(|_| Ok(3));
+
+        assert_eq!(purge_expired(&config, now, &repo).unwrap(), (1, 3));
+    }
+}
@@ -0,0 +1,356 @@
+//! Service accounts: hub-scoped principals for integrations.
+//!
+//! Administrators create service accounts for their hub and hand the
+//! `client_id` and secret to the integration, which exchanges them for a
+//! short-lived access token with the OAuth client credentials grant. The
+//! token is only accepted as `Authorization: Bearer` by the user listing of
+//! the `/api` scope, where it resolves to claims carrying the account's hub
+//! and roles. Rotating the secret revokes every outstanding token.
+
+use chrono::{Duration, Utc};
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+
+use crate::domain::permission::Permission;
+use crate::domain::service_account::{
+    NewServiceAccount, NewServiceAccountToken, ServiceAccount, UpdateServiceAccount,
+};
+use crate::domain::types::{HubId, ServiceAccountId};
+use crate::dto::api::ServiceAccountCredentialsDto;
+use crate::dto::oauth::ClientCredentialsTokenDto;
+use crate::forms::main::ServiceAccountPayload;
+use crate::forms::oauth::TokenForm;
+use crate::repository::{PermissionReader, RoleReader, ServiceAccountReader, ServiceAccountWriter};
+use crate::services::admin::ensure_roles_in_hub;
+use crate::services::jwt::SessionClaims;
+use crate::services::oauth::{OAuthError, client_credentials};
+use crate::services::permission::ensure_permission;
+use crate::services::tokens::{generate_token, hash_token};
+
+/// Prefix of generated client ids, which keeps them apart from the numeric
+/// ids of users when used as `sub`.
+const CLIENT_ID_PREFIX: &str = "sa_";
+/// Prefix of issued access tokens, so they are easy to recognize in logs and
+/// by secret scanners.
+pub const TOKEN_PREFIX: &str = "sat_";
+/// Length of the random part of a generated `client_id`.
+const CLIENT_ID_LEN: usize = 24;
+/// Lifetime of access tokens.
+const ACCESS_TOKEN_TTL_SECONDS: i64 = 3600;
+
+/// Creates a service account in the current hub and returns its credentials
+/// once.
+pub fn create_service_account(
+    payload: ServiceAccountPayload,
+    current_user: &AuthenticatedUser,
+    repo: &(impl ServiceAccountWriter + RoleReader + PermissionReader),
+) -> ServiceResult<ServiceAccountCredentialsDto> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    ensure_roles_in_hub(&payload.roles, hub_id, repo)?;
+
+    let client_secret = generate_token();
+    let account = repo.create_service_account(&NewServiceAccount {
+        hub_id,
+        name: payload.name,
+        client_id: format!("{CLIENT_ID_PREFIX}{}", &generate_token()[..CLIENT_ID_LEN]),
+        secret_hash: hash_token(&client_secret),
+        roles: payload.roles,
+    })?;
+
+    Ok(ServiceAccountCredentialsDto {
+        message: "Сервисный аккаунт добавлен.".to_string(),
+        client_id: account.client_id,
+        client_secret,
+    })
+}
+
+/// Renames a service account of the current hub and replaces its roles.
+pub fn update_service_account(
+    id: i32,
+    payload: ServiceAccountPayload,
+    current_user: &AuthenticatedUser,
+    repo: &(impl ServiceAccountWriter + RoleReader + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let id = ServiceAccountId::new(id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    ensure_roles_in_hub(&payload.roles, hub_id, repo)?;
+
+    repo.update_service_account(
+        id,
+        hub_id,
+        &UpdateServiceAccount {
+            name: payload.name,
+            roles: payload.roles,
+        },
+    )?;
+    Ok(())
+}
+
+/// Replaces the secret of a service account of the current hub, revokes its
+/// access tokens, and returns the new credentials once.
+pub fn rotate_secret(
+    id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &(impl ServiceAccountWriter + PermissionReader),
+) -> ServiceResult<ServiceAccountCredentialsDto> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let id = ServiceAccountId::new(id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+
+    let client_secret = generate_token();
+    let account = repo.rotate_service_account_secret(
+        id,
+        hub_id,
+        &hash_token(&client_secret),
+        Utc::now().naive_utc(),
+    )?;
+
+    Ok(ServiceAccountCredentialsDto {
+        message: "Секрет обновлён.".to_string(),
+        client_id: account.client_id,
+        client_secret,
+    })
+}
+
+/// Removes a service account of the current hub together with its tokens.
+pub fn delete_service_account(
+    id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &(impl ServiceAccountWriter + PermissionReader),
+) -> ServiceResult<()> {
+    ensure_permission(current_user, Permission::UsersWrite, repo)?;
+    let id = ServiceAccountId::new(id)?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    match repo.delete_service_account(id, hub_id)? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Issues an access token for the client credentials grant.
+///
+/// `basic_credentials` come from the `Authorization` header and take
+/// precedence over the credentials in the form body.
+pub fn issue_token(
+    form: TokenForm,
+    basic_credentials: Option<(String, String)>,
+    repo: &(impl ServiceAccountReader + ServiceAccountWriter),
+) -> Result<ClientCredentialsTokenDto, OAuthError> {
+    if form.grant_type.as_deref() != Some("client_credentials") {
+        return Err(OAuthError::UnsupportedGrantType);
+    }
+    let (client_id, client_secret) = client_credentials(
+        (form.client_id.as_deref(), form.client_secret.as_deref()),
+        basic_credentials,
+    );
+    let account = repo
+        .get_service_account_by_client_id(client_id.as_deref().ok_or(OAuthError::InvalidClient)?)?
+        .ok_or(OAuthError::InvalidClient)?;
+    match client_secret {
+        Some(secret) if hash_token(&secret) == account.secret_hash => {}
+        _ => return Err(OAuthError::InvalidClient),
+    }
+
+    let access_token = format!("{TOKEN_PREFIX}{}", generate_token());
+    repo.create_service_account_token(&NewServiceAccountToken {
+        service_account_id: account.id,
+        hub_id: account.hub_id,
+        token_hash: hash_token(&access_token),
+        expires_at: Utc::now().naive_utc() + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS),
+    })?;
+
+    Ok(ClientCredentialsTokenDto {
+        access_token,
+        token_type: "Bearer".to_string(),
+        expires_in: ACCESS_TOKEN_TTL_SECONDS,
+    })
+}
+
+/// Resolves a Bearer token to the current claims of its service account.
+///
+/// The claims carry no `jti` and expire with the token. Returns
+/// [`ServiceError::Unauthorized`] when the token is unknown, revoked, or
+/// expired, or its account no longer exists.
+pub fn authenticate(
+    token: &str,
+    repo: &impl ServiceAccountReader,
+) -> ServiceResult<(SessionClaims, ServiceAccount)> {
+    let now = Utc::now().naive_utc();
+    let token = repo
+        .get_service_account_token(&hash_token(token))?
+        .filter(|token| token.is_active(now))
+        .ok_or(ServiceError::Unauthorized)?;
+    let account = repo
+        .get_service_account(token.service_account_id, token.hub_id)?
+        .ok_or(ServiceError::Unauthorized)?;
+
+    let mut user = AuthenticatedUser::from(account.clone());
+    user.exp = token.expires_at.and_utc().timestamp();
+    Ok((
+        SessionClaims {
+            user,
+            jti: None,
+            permissions: Vec::new(),
+        },
+        account,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::role::Role;
+    use crate::domain::service_account::ServiceAccountToken;
+    use crate::domain::types::RoleId;
+    use crate::repository::mock::MockRepository;
+    use chrono::NaiveDateTime;
+
+    fn make_admin() -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: "1".into(),
+            email: "admin@example.com".into(),
+            hub_id: 10,
+            name: "Admin".into(),
+            roles: vec!["admin".into()],
+            exp: 0,
+        }
+    }
+
+    fn make_account(secret_hash: &str) -> ServiceAccount {
+        let now = Utc::now().naive_utc();
+        ServiceAccount::try_new(
+            4,
+            10,
+            "CRM sync".into(),
+            "sa_client".into(),
+            secret_hash.to_string(),
+            now,
+            now,
+            vec![Role::try_new(3, "crm", now, now).unwrap()],
+        )
+        .unwrap()
+    }
+
+    fn make_token(revoked_at: Option<NaiveDateTime>) -> ServiceAccountToken {
+        let now = Utc::now().naive_utc();
+        ServiceAccountToken::try_new(
+            1,
+            4,
+            10,
+            hash_token("sat_token"),
+            now + Duration::hours(1),
+            revoked_at,
+            now,
+        )
+        .unwrap()
+    }
+
+    fn make_token_form(client_secret: &str) -> TokenForm {
+        TokenForm {
+            grant_type: Some("client_credentials".into()),
+            code: None,
+            redirect_uri: None,
+            code_verifier: None,
+            client_id: Some("sa_client".into()),
+            client_secret: Some(client_secret.into()),
+        }
+    }
+
+    #[test]
+    fn create_service_account_stores_only_the_secret_hash() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersWrite]));
+        repo.expect_list_roles()
+            .withf(|hub_id| hub_id.get() == 10)
+            .returning(|_| {
+                let now = Utc::now().naive_utc();
+                Ok(vec![Role::try_new(3, "crm", now, now).unwrap()])
+            });
+        repo.expect_create_service_account()
+            .withf(|new_account| {
+                new_account.hub_id.get() == 10
+                    && new_account.client_id.starts_with(CLIENT_ID_PREFIX)
+                    && new_account.roles == vec![RoleId::new(3).unwrap()]
+            })
+            .times(1)
+            .returning(|new_account| Ok(make_account(&new_account.secret_hash)));
+
+        let payload = ServiceAccountPayload {
+            name: "CRM sync".into(),
+            roles: vec![RoleId::new(3).unwrap()],
+        };
+        let credentials = create_service_account(payload, &make_admin(), &repo).unwrap();
+        assert_eq!(credentials.client_id, "sa_client");
+        assert!(!credentials.client_secret.is_empty());
+    }
+
+    #[test]
+    fn create_service_account_requires_users_write() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_permissions()
+            .returning(|_, _| Ok(vec![Permission::UsersRead]));
+        repo.expect_create_service_account().never();
+
+        let payload = ServiceAccountPayload {
+            name: "CRM sync".into(),
+            roles: vec![],
+        };
+        assert!(matches!(
+            create_service_account(payload, &make_admin(), &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn issue_token_checks_secret() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_service_account_by_client_id()
+            .returning(|_| Ok(Some(make_account(&hash_token("s3cret")))));
+        repo.expect_create_service_account_token()
+            .withf(|new_token| new_token.service_account_id.get() == 4)
+            .times(1)
+            .returning(|_| Ok(make_token(None)));
+
+        let issued = issue_token(make_token_form("s3cret"), None, &repo).unwrap();
+        assert!(issued.access_token.starts_with(TOKEN_PREFIX));
+        assert_eq!(issued.expires_in, ACCESS_TOKEN_TTL_SECONDS);
+
+        assert!(matches!(
+            issue_token(make_token_form("wrong"), None, &repo),
+            Err(OAuthError::InvalidClient)
+        ));
+    }
+
+    #[test]
+    fn authenticate_resolves_account_claims() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_service_account_token()
+            .withf(|token_hash| token_hash == hash_token("sat_token"))
+            .returning(|_| Ok(Some(make_token(None))));
+        repo.expect_get_service_account()
+            .returning(|_, _| Ok(Some(make_account("hash"))));
+
+        let (claims, account) = authenticate("sat_token", &repo).unwrap();
+        assert_eq!(claims.user.sub, "sa_client");
+        assert_eq!(claims.user.hub_id, 10);
+        assert_eq!(claims.user.roles, vec!["crm".to_string()]);
+        assert!(claims.jti.is_none());
+        assert_eq!(account.id.get(), 4);
+    }
+
+    #[test]
+    fn authenticate_rejects_revoked_tokens() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_service_account_token()
+            .returning(|_| Ok(Some(make_token(Some(Utc::now().naive_utc())))));
+        repo.expect_get_service_account().never();
+
+        assert!(matches!(
+            authenticate("sat_token", &repo),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+}
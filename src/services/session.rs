@@ -0,0 +1,338 @@
+//! Session registry checks, listing, and revocation.
+//!
+//! Every session JWT carries a `jti` recorded in the `sessions` table when
+//! the token is issued. A token is only accepted while its session exists
+//! and is neither revoked nor expired, so revoking the row ends the session
+//! before the token's `exp`.
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+
+use crate::domain::session::Session;
+use crate::domain::types::{SessionId, UserId};
+use crate::domain::user::UserWithRoles;
+use crate::dto::api::SessionListItemDto;
+use crate::repository::{SessionReader, SessionWriter};
+use crate::services::jwt::SessionClaims;
+
+/// Minimum time between two `last_seen_at` updates of a session.
+const LAST_SEEN_INTERVAL_SECONDS: i64 = 60;
+
+fn current_user_id(current_user: &AuthenticatedUser) -> ServiceResult<UserId> {
+    let user_id: i32 = current_user
+        .sub
+        .parse()
+        .map_err(|_| ServiceError::Internal)?;
+    Ok(UserId::new(user_id)?)
+}
+
+fn needs_touch(session: &Session, now: NaiveDateTime) -> bool {
+    session.last_seen_at.is_none_or(|last_seen_at| {
+        now - last_seen_at >= Duration::seconds(LAST_SEEN_INTERVAL_SECONDS)
+    })
+}
+
+/// Checks that the session behind verified token claims is still live and
+/// records activity on it.
+///
+/// Returns [`ServiceError::Unauthorized`] when the token has no `jti` or its
+/// session is unknown, belongs to another user, is revoked, or has expired.
+pub fn verify_session(
+    claims: &SessionClaims,
+    repo: &(impl SessionReader + SessionWriter),
+) -> ServiceResult<Session> {
+    let jti = claims.jti.as_deref().ok_or(ServiceError::Unauthorized)?;
+    let now = Utc::now().naive_utc();
+    let session = repo
+        .get_session_by_jti(jti)?
+        .filter(|session| session.user_id.get().to_string() == claims.user.sub)
+        .filter(|session| session.is_live(now))
+        .ok_or(ServiceError::Unauthorized)?;
+
+    if needs_touch(&session, now) {
+        repo.touch_session(session.id, now)?;
+    }
+    Ok(session)
+}
+
+/// Returns claims with the user's current email, name, roles, and
+/// `permissions` claim when they differ from the ones frozen into the token
+/// at login.
+///
+/// The session id and expiry are kept, so a refreshed token still belongs to
+/// the same session. Returns `None` when the claims are up to date.
+pub fn refresh_claims(
+    claims: &SessionClaims,
+    user_roles: UserWithRoles,
+    permissions: Vec<String>,
+) -> Option<SessionClaims> {
+    let mut live = AuthenticatedUser::from(user_roles);
+    live.exp = claims.user.exp;
+
+    let mut live_roles = live.roles.clone();
+    let mut token_roles = claims.user.roles.clone();
+    live_roles.sort();
+    token_roles.sort();
+    if live.email == claims.user.email
+        && live.name == claims.user.name
+        && live_roles == token_roles
+        && permissions == claims.permissions
+    {
+        return None;
+    }
+    Some(SessionClaims {
+        user: live,
+        jti: claims.jti.clone(),
+        permissions,
+    })
+}
+
+/// Lists the live sessions of the current user, marking the one identified
+/// by `current_jti`.
+pub fn list_sessions(
+    current_user: &AuthenticatedUser,
+    current_jti: Option<&str>,
+    repo: &impl SessionReader,
+) -> ServiceResult<Vec<SessionListItemDto>> {
+    let user_id = current_user_id(current_user)?;
+    let now = Utc::now().naive_utc();
+    Ok(repo
+        .list_user_sessions(user_id, now)?
+        .into_iter()
+        .map(|session| SessionListItemDto {
+            id: session.id.get(),
+            current: current_jti == Some(session.jti.as_str()),
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+        })
+        .collect())
+}
+
+/// Revokes one of the current user's sessions.
+pub fn revoke_session(
+    id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &impl SessionWriter,
+) -> ServiceResult<()> {
+    let user_id = current_user_id(current_user)?;
+    let id = SessionId::new(id)?;
+    match repo.revoke_session(user_id, id, Utc::now().naive_utc())? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+/// Revokes every session of the current user, including the one making the
+/// request, and returns how many were revoked.
+pub fn revoke_all_sessions(
+    current_user: &AuthenticatedUser,
+    repo: &impl SessionWriter,
+) -> ServiceResult<usize> {
+    let user_id = current_user_id(current_user)?;
+    Ok(repo.revoke_user_sessions(user_id, Utc::now().naive_utc())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::role::Role;
+    use crate::domain::types::{HubId, RoleId, RoleName, UserEmail, UserName};
+    use crate::domain::user::User;
+    use crate::repository::mock::MockRepository;
+
+    fn make_current_user(id: i32) -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: id.to_string(),
+            email: "user@example.com".into(),
+            hub_id: 1,
+            name: "User".into(),
+            roles: vec![],
+            exp: 0,
+        }
+    }
+
+    fn make_claims(user_id: i32, jti: Option<&str>) -> SessionClaims {
+        SessionClaims {
+            user: make_current_user(user_id),
+            jti: jti.map(str::to_string),
+            permissions: Vec::new(),
+        }
+    }
+
+    fn make_session(
+        user_id: i32,
+        jti: &str,
+        last_seen_at: Option<NaiveDateTime>,
+        revoked_at: Option<NaiveDateTime>,
+    ) -> Session {
+        let now = Utc::now().naive_utc();
+        Session::try_new(
+            4,
+            jti.to_string(),
+            user_id,
+            1,
+            "default".into(),
+            now - Duration::hours(1),
+            now + Duration::days(1),
+            Some("test-agent".into()),
+            Some("127.0.0.1".into()),
+            last_seen_at,
+            revoked_at,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn verify_session_rejects_tokens_without_jti() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_session_by_jti().never();
+
+        let res = verify_session(&make_claims(3, None), &repo);
+        assert!(matches!(res, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn verify_session_rejects_revoked_sessions() {
+        let mut repo = MockRepository::new();
+        let revoked = make_session(3, "abc", None, Some(Utc::now().naive_utc()));
+        repo.expect_get_session_by_jti()
+            .returning(move |_| Ok(Some(revoked.clone())));
+        repo.expect_touch_session().never();
+
+        let res = verify_session(&make_claims(3, Some("abc")), &repo);
+        assert!(matches!(res, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn verify_session_rejects_sessions_of_other_users() {
+        let mut repo = MockRepository::new();
+        let session = make_session(5, "abc", None, None);
+        repo.expect_get_session_by_jti()
+            .returning(move |_| Ok(Some(session.clone())));
+
+        let res = verify_session(&make_claims(3, Some("abc")), &repo);
+        assert!(matches!(res, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn verify_session_touches_stale_sessions_only() {
+        let mut repo = MockRepository::new();
+        let stale = make_session(3, "stale", None, None);
+        let fresh = make_session(3, "fresh", Some(Utc::now().naive_utc()), None);
+        repo.expect_get_session_by_jti()
+            .returning(move |jti| match jti {
+                "stale" => Ok(Some(stale.clone())),
+                _ => Ok(Some(fresh.clone())),
+            });
+        repo.expect_touch_session()
+            .withf(|id, _| id.get() == 4)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        assert!(verify_session(&make_claims(3, Some("stale")), &repo).is_ok());
+        assert!(verify_session(&make_claims(3, Some("fresh")), &repo).is_ok());
+    }
+
+    #[test]
+    fn list_sessions_marks_current_session() {
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_sessions()
+            .withf(|user_id, _| user_id.get() == 3)
+            .returning(|_, _| {
+                Ok(vec![
+                    make_session(3, "current", None, None),
+                    make_session(3, "other", None, None),
+                ])
+            });
+
+        let sessions = list_sessions(&make_current_user(3), Some("current"), &repo).unwrap();
+        assert_eq!(
+            sessions.iter().map(|s| s.current).collect::<Vec<_>>(),
+            vec![true, false]
+        );
+    }
+
+    #[test]
+    fn revoke_session_reports_missing_session() {
+        let mut repo = MockRepository::new();
+        repo.expect_revoke_session()
+            .withf(|user_id, id, _| user_id.get() == 3 && id.get() == 9)
+            .times(1)
+            .returning(|_, _, _| Ok(0));
+
+        let res = revoke_session(9, &make_current_user(3), &repo);
+        assert!(matches!(res, Err(ServiceError::NotFound)));
+    }
+
+    fn make_user_roles(name: &str, roles: &[&str]) -> UserWithRoles {
+        let now = Utc::now().naive_utc();
+        let user = User::new(
+            UserId::new(3).unwrap(),
+            UserEmail::new("user@example.com").unwrap(),
+            Some(UserName::new(name).unwrap()),
+            HubId::new(1).unwrap(),
+            "hash".into(),
+            now,
+            now,
+            vec![],
+        );
+        let roles = roles
+            .iter()
+            .enumerate()
+            .map(|(index, role)| {
+                Role::new(
+                    RoleId::new(index as i32 + 1).unwrap(),
+                    RoleName::new(*role).unwrap(),
+                    now,
+                    now,
+                )
+            })
+            .collect();
+        UserWithRoles::new(user, roles)
+    }
+
+    #[test]
+    fn refresh_claims_keeps_up_to_date_claims() {
+        let mut claims = make_claims(3, Some("abc"));
+        claims.user.roles = vec!["admin".into(), "crm".into()];
+
+        let refreshed = refresh_claims(
+            &claims,
+            make_user_roles("User", &["crm", "admin"]),
+            Vec::new(),
+        );
+        assert!(refreshed.is_none());
+    }
+
+    #[test]
+    fn refresh_claims_updates_changed_permissions() {
+        let mut claims = make_claims(3, Some("abc"));
+        claims.permissions = vec!["users.read".into()];
+
+        let refreshed = refresh_claims(
+            &claims,
+            make_user_roles("User", &[]),
+            vec!["users.read".into(), "users.write".into()],
+        )
+        .unwrap();
+        assert_eq!(refreshed.permissions, vec!["users.read", "users.write"]);
+    }
+
+    #[test]
+    fn refresh_claims_drops_removed_roles_and_keeps_session() {
+        let mut claims = make_claims(3, Some("abc"));
+        claims.user.roles = vec!["admin".into()];
+        claims.user.exp = 1_900_000_000;
+
+        let refreshed =
+            refresh_claims(&claims, make_user_roles("Renamed", &[]), Vec::new()).unwrap();
+        assert!(refreshed.user.roles.is_empty());
+        assert_eq!(refreshed.user.name, "Renamed");
+        assert_eq!(refreshed.user.exp, 1_900_000_000);
+        assert_eq!(refreshed.jti.as_deref(), Some("abc"));
+    }
+}
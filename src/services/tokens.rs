@@ -0,0 +1,47 @@
+//! Helpers for opaque, single-use tokens delivered to users by email.
+//!
+//! Raw tokens are only ever handed to the user; the database stores their
+//! SHA-256 hash so a leaked table cannot be replayed.
+
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+/// Number of random bytes backing a generated token.
+const TOKEN_BYTES: usize = 32;
+
+/// Generates a new random token encoded as lowercase hex.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    rand::rng().fill(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Hashes a raw token for storage and lookup.
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_tokens_are_unique_hex() {
+        let first = generate_token();
+        let second = generate_token();
+        assert_eq!(first.len(), TOKEN_BYTES * 2);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn hash_token_is_stable_and_differs_from_input() {
+        let token = "abc";
+        assert_eq!(hash_token(token), hash_token(token));
+        assert_ne!(hash_token(token), token);
+        assert_eq!(
+            hash_token(token),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! TOTP second factor enrollment and verification.
+//!
+//! Codes follow RFC 6238 with SHA-1, six digits and a 30 second step. One
+//! step of clock drift is tolerated in either direction, and every accepted
+//! step is recorded so a code cannot be replayed.
+
+use chrono::{NaiveDateTime, Utc};
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+use qrcode::QrCode;
+use qrcode::render::svg;
+use rand::Rng;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::SERVICE_ACCESS_ROLE;
+use crate::domain::types::{HubId, UserId};
+use crate::dto::auth::TotpEnrollmentDto;
+use crate::repository::{HubReader, TwoFactorReader, TwoFactorWriter};
+use crate::services::tokens::hash_token;
+
+/// Issuer shown in authenticator apps.
+const TOTP_ISSUER: &str = "Pushkind";
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECONDS: u64 = 30;
+/// Number of recovery codes issued when enrollment is confirmed.
+const RECOVERY_CODE_COUNT: usize = 10;
+/// Random bytes per recovery code half.
+const RECOVERY_CODE_HALF_BYTES: usize = 3;
+
+fn build_totp(secret: &str, account_name: &str) -> ServiceResult<TOTP> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|_| ServiceError::Internal)?;
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        0,
+        TOTP_STEP_SECONDS,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .map_err(|_| ServiceError::Internal)
+}
+
+/// Generates a new base32-encoded TOTP secret.
+pub fn generate_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+/// Returns the time step matched by `code` at unix time `now`, if any.
+pub fn match_totp_step(secret: &str, code: &str, now: u64) -> ServiceResult<Option<i64>> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != TOTP_DIGITS {
+        return Ok(None);
+    }
+
+    let totp = build_totp(secret, "")?;
+    let current = now / TOTP_STEP_SECONDS;
+    for step in [current.saturating_sub(1), current, current + 1] {
+        if totp.check(&code, step * TOTP_STEP_SECONDS) {
+            return Ok(i64::try_from(step).ok());
+        }
+    }
+    Ok(None)
+}
+
+/// Generates a fresh set of human-readable recovery codes.
+pub fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; RECOVERY_CODE_HALF_BYTES * 2];
+            rng.fill(&mut bytes);
+            let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            let (first, second) = hex.split_at(RECOVERY_CODE_HALF_BYTES * 2);
+            format!("{first}-{second}")
+        })
+        .collect()
+}
+
+/// Hashes a recovery code after normalizing case and separators.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hash_token(&normalized)
+}
+
+fn qr_svg(payload: &str) -> ServiceResult<String> {
+    let code = QrCode::new(payload.as_bytes()).map_err(|_| ServiceError::Internal)?;
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+fn unix_time(now: NaiveDateTime) -> u64 {
+    u64::try_from(now.and_utc().timestamp()).unwrap_or_default()
+}
+
+/// Starts TOTP enrollment by storing a new unconfirmed secret.
+///
+/// Returns [`ServiceError::Conflict`] when a confirmed second factor already
+/// exists; it has to be disabled or reset first.
+pub fn begin_enrollment(
+    user_id: i32,
+    email: &str,
+    repo: &(impl TwoFactorReader + TwoFactorWriter),
+) -> ServiceResult<TotpEnrollmentDto> {
+    let user_id = UserId::new(user_id)?;
+    if repo
+        .get_user_totp(user_id)?
+        .is_some_and(|totp| totp.is_enabled())
+    {
+        return Err(ServiceError::Conflict);
+    }
+
+    let secret = generate_secret();
+    repo.create_pending_totp(user_id, &secret)?;
+
+    let otpauth_uri = build_totp(&secret, email)?.get_url();
+    let qr_svg = qr_svg(&otpauth_uri)?;
+    Ok(TotpEnrollmentDto {
+        secret,
+        otpauth_uri,
+        qr_svg,
+    })
+}
+
+/// Confirms enrollment with a code from the authenticator app.
+///
+/// Returns the plain recovery codes, which are shown to the user only once.
+pub fn confirm_enrollment(
+    user_id: i32,
+    code: &str,
+    repo: &(impl TwoFactorReader + TwoFactorWriter),
+) -> ServiceResult<Vec<String>> {
+    let user_id = UserId::new(user_id)?;
+    let totp = repo
+        .get_user_totp(user_id)?
+        .filter(|totp| !totp.is_enabled())
+        .ok_or(ServiceError::NotFound)?;
+
+    let now = Utc::now().naive_utc();
+    let step =
+        match_totp_step(&totp.secret, code, unix_time(now))?.ok_or(ServiceError::Unauthorized)?;
+
+    let recovery_codes = generate_recovery_codes();
+    let hashes = recovery_codes
+        .iter()
+        .map(|code| hash_recovery_code(code))
+        .collect::<Vec<_>>();
+    repo.enable_totp(user_id, step, &hashes, now)?;
+    Ok(recovery_codes)
+}
+
+/// Checks a TOTP or recovery code against the user's confirmed second factor.
+///
+/// Accepted TOTP steps and recovery codes are consumed, so the same code is
+/// never accepted twice.
+pub fn verify_code(
+    user_id: UserId,
+    code: &str,
+    repo: &(impl TwoFactorReader + TwoFactorWriter),
+) -> ServiceResult<bool> {
+    let totp = match repo.get_user_totp(user_id)? {
+        Some(totp) if totp.is_enabled() => totp,
+        _ => return Ok(false),
+    };
+
+    let now = Utc::now().naive_utc();
+    if let Some(step) = match_totp_step(&totp.secret, code, unix_time(now))? {
+        return Ok(repo.record_totp_step(user_id, step)?);
+    }
+
+    Ok(repo.consume_recovery_code(user_id, &hash_recovery_code(code), now)?)
+}
+
+/// Returns `true` when the hub policy requires `is_admin` users to use 2FA.
+pub fn is_required(hub_id: HubId, is_admin: bool, repo: &impl HubReader) -> ServiceResult<bool> {
+    Ok(is_admin && repo.get_hub_policy(hub_id)?.require_admin_2fa)
+}
+
+/// Disables the current user's second factor after re-checking a code.
+///
+/// Returns [`ServiceError::Conflict`] for admins of hubs that enforce 2FA and
+/// [`ServiceError::Unauthorized`] when the code is wrong.
+pub fn disable(
+    code: &str,
+    current_user: &AuthenticatedUser,
+    repo: &(impl HubReader + TwoFactorReader + TwoFactorWriter),
+) -> ServiceResult<()> {
+    let user_id = UserId::new(
+        current_user
+            .sub
+            .parse()
+            .map_err(|_| ServiceError::Internal)?,
+    )?;
+    let hub_id = HubId::new(current_user.hub_id)?;
+    let is_admin = current_user
+        .roles
+        .iter()
+        .any(|role| role == SERVICE_ACCESS_ROLE);
+
+    if is_required(hub_id, is_admin, repo)? {
+        return Err(ServiceError::Conflict);
+    }
+    if !verify_code(user_id, code, repo)? {
+        return Err(ServiceError::Unauthorized);
+    }
+
+    repo.delete_user_totp(user_id)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::hub::HubPolicy;
+    use crate::domain::two_factor::UserTotp;
+    use crate::repository::mock::MockRepository;
+
+    fn current_code(secret: &str) -> String {
+        build_totp(secret, "")
+            .unwrap()
+            .generate(unix_time(Utc::now().naive_utc()))
+    }
+
+    fn enabled_totp(secret: &str) -> UserTotp {
+        let now = Utc::now().naive_utc();
+        UserTotp::try_new(1, secret.to_string(), Some(now), None, now).unwrap()
+    }
+
+    #[test]
+    fn match_totp_step_accepts_adjacent_steps_only() {
+        let secret = generate_secret();
+        let totp = build_totp(&secret, "").unwrap();
+        let now = 1_700_000_010;
+        let code = totp.generate(now);
+
+        assert_eq!(
+            match_totp_step(&secret, &code, now).unwrap(),
+            Some((now / TOTP_STEP_SECONDS) as i64)
+        );
+        assert!(
+            match_totp_step(&secret, &code, now + TOTP_STEP_SECONDS)
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(
+            match_totp_step(&secret, &code, now + 3 * TOTP_STEP_SECONDS).unwrap(),
+            None
+        );
+        assert_eq!(match_totp_step(&secret, "12", now).unwrap(), None);
+    }
+
+    #[test]
+    fn recovery_codes_are_unique_and_hash_ignores_formatting() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_ne!(codes[0], codes[1]);
+        assert_eq!(
+            hash_recovery_code(&codes[0]),
+            hash_recovery_code(&codes[0].replace('-', " ").to_uppercase())
+        );
+    }
+
+    #[test]
+    fn begin_enrollment_rejects_confirmed_factor() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_user_totp()
+            .returning(|_| Ok(Some(enabled_totp("JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP"))));
+        repo.expect_create_pending_totp().never();
+
+        let res = begin_enrollment(1, "a@b", &repo);
+        assert!(matches!(res, Err(ServiceError::Conflict)));
+    }
+
+    #[test]
+    fn begin_enrollment_returns_uri_and_qr() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_user_totp().returning(|_| Ok(None));
+        repo.expect_create_pending_totp()
+            .times(1)
+            .returning(|user_id, secret| {
+                Ok(UserTotp::new(
+                    user_id,
+                    secret.to_string(),
+                    None,
+                    None,
+                    Utc::now().naive_utc(),
+                ))
+            });
+
+        let dto = begin_enrollment(1, "a@b.c", &repo).unwrap();
+        assert!(dto.otpauth_uri.starts_with("otpauth://totp/"));
+        assert!(dto.otpauth_uri.contains(&dto.secret));
+        assert!(dto.qr_svg.contains("<svg"));
+    }
+
+    #[test]
+    fn confirm_enrollment_rejects_wrong_code() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_user_totp().returning(|_| {
+            let now = Utc::now().naive_utc();
+            Ok(Some(
+                UserTotp::try_new(
+                    1,
+                    "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP".to_string(),
+                    None,
+                    None,
+                    now,
+                )
+                .unwrap(),
+            ))
+        });
+        repo.expect_enable_totp().never();
+
+        let res = confirm_enrollment(1, "000000x", &repo);
+        assert!(matches!(res, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn confirm_enrollment_returns_recovery_codes() {
+        let secret = generate_secret();
+        let code = current_code(&secret);
+        let mut repo = MockRepository::new();
+        let stored_secret = secret.clone();
+        repo.expect_get_user_totp().returning(move |_| {
+            let now = Utc::now().naive_utc();
+            Ok(Some(
+                UserTotp::try_new(1, stored_secret.clone(), None, None, now).unwrap(),
+            ))
+        });
+        let enabled_secret = secret.clone();
+        repo.expect_enable_totp()
+            .withf(|_, _, hashes, _| hashes.len() == RECOVERY_CODE_COUNT)
+            .times(1)
+            .returning(move |_, _, _, _| Ok(enabled_totp(&enabled_secret)));
+
+        let codes = confirm_enrollment(1, &code, &repo).unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+    }
+
+    #[test]
+    fn verify_code_rejects_replayed_totp() {
+        let secret = generate_secret();
+        let code = current_code(&secret);
+        let mut repo = MockRepository::new();
+        let stored_secret = secret.clone();
+        repo.expect_get_user_totp()
+            .returning(move |_| Ok(Some(enabled_totp(&stored_secret))));
+        repo.expect_record_totp_step().returning(|_, _| Ok(false));
+        repo.expect_consume_recovery_code().never();
+
+        assert!(!verify_code(UserId::new(1).unwrap(), &code, &repo).unwrap());
+    }
+
+    #[test]
+    fn verify_code_falls_back_to_recovery_codes() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_user_totp()
+            .returning(|_| Ok(Some(enabled_totp("JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP"))));
+        repo.expect_consume_recovery_code()
+            .withf(|_, hash, _| hash == hash_recovery_code("abcdef-123456"))
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+
+        assert!(verify_code(UserId::new(1).unwrap(), "ABCDEF-123456", &repo).unwrap());
+    }
+
+    #[test]
+    fn disable_is_refused_when_hub_requires_admin_2fa() {
+        let mut repo = MockRepository::new();
+        repo.expect_get_hub_policy().returning(|_| {
+            Ok(HubPolicy {
+                require_admin_2fa: true,
+                ..Default::default()
+            })
+        });
+        repo.expect_delete_user_totp().never();
+
+        let current_user = AuthenticatedUser {
+            sub: "1".into(),
+            email: "admin@example.com".into(),
+            hub_id: 1,
+            name: "Admin".into(),
+            roles: vec!["admin".into()],
+            exp: 0,
+        };
+
+        let res = disable("123456", &current_user, &repo);
+        assert!(matches!(res, Err(ServiceError::Conflict)));
+    }
+}
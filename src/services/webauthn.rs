@@ -0,0 +1,364 @@
+//! WebAuthn passkey registration and sign-in.
+//!
+//! The relying party ID is `AppConfig.domain` and passkeys are accepted on any
+//! of its subdomains. Assertions require user verification on the
+//! authenticator, so a passkey sign-in is a complete login and is not
+//! followed by a TOTP prompt.
+
+use chrono::Utc;
+use pushkind_common::domain::auth::AuthenticatedUser;
+use pushkind_common::services::errors::{ServiceError, ServiceResult};
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, Passkey, PublicKeyCredential, RequestChallengeResponse, Url, Uuid,
+    Webauthn, WebauthnBuilder,
+};
+
+use crate::domain::audit::AuditEventType;
+use crate::domain::session::ClientInfo;
+use crate::domain::types::{HubId, UserCredentialId, UserId};
+use crate::domain::user_credential::{NewUserCredential, UserCredential};
+use crate::dto::api::PasskeyListItemDto;
+use crate::dto::auth::{PasskeyLoginStateDto, PasskeyRegistrationStateDto};
+use crate::forms::auth::{PasskeyLoginPayload, PasskeyRegistrationPayload};
+use crate::repository::{
+    AuditWriter, CredentialReader, CredentialWriter, HubReader, PasswordResetWriter,
+    PermissionReader, SessionWriter, UserReader,
+};
+use crate::services::auth::{
+    LoginError, LoginOutcome, ensure_email_verified, issue_jwt, password_change_step,
+};
+use crate::services::jwt::JwtKeys;
+
+/// Relying party name shown by authenticators.
+const RP_NAME: &str = "Pushkind";
+
+/// Builds the relying party for `domain`.
+///
+/// Browsers only allow WebAuthn over plain HTTP on `localhost`, every other
+/// domain is expected to be served over HTTPS.
+pub fn build_webauthn(domain: &str) -> ServiceResult<Webauthn> {
+    let scheme = if domain == "localhost" {
+        "http"
+    } else {
+        "https"
+    };
+    let origin = Url::parse(&format!("{scheme}://{domain}")).map_err(|_| ServiceError::Internal)?;
+    WebauthnBuilder::new(domain, &origin)
+        .and_then(|builder| {
+            builder
+                .rp_name(RP_NAME)
+                .allow_subdomains(true)
+                .allow_any_port(true)
+                .build()
+        })
+        .map_err(|_| ServiceError::Internal)
+}
+
+/// Encodes a raw credential id for storage and lookup.
+pub fn encode_credential_id(credential_id: &[u8]) -> String {
+    credential_id
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Stable WebAuthn user handle derived from the user id.
+fn user_handle(user_id: UserId) -> Uuid {
+    Uuid::from_u64_pair(0, user_id.get() as u64)
+}
+
+fn current_user_id(current_user: &AuthenticatedUser) -> ServiceResult<UserId> {
+    let user_id: i32 = current_user
+        .sub
+        .parse()
+        .map_err(|_| ServiceError::Internal)?;
+    Ok(UserId::new(user_id)?)
+}
+
+fn parse_passkey(credential: &UserCredential) -> ServiceResult<Passkey> {
+    serde_json::from_str(&credential.passkey).map_err(|e| {
+        log::error!("Failed to parse stored passkey {}: {e}", credential.id);
+        ServiceError::Internal
+    })
+}
+
+fn serialize_passkey(passkey: &Passkey) -> ServiceResult<String> {
+    serde_json::to_string(passkey).map_err(|_| ServiceError::Internal)
+}
+
+/// Starts registering a new passkey for the current user.
+///
+/// Passkeys the user already has are excluded so the same authenticator is
+/// not registered twice.
+pub fn start_registration(
+    webauthn: &Webauthn,
+    current_user: &AuthenticatedUser,
+    repo: &impl CredentialReader,
+) -> ServiceResult<(CreationChallengeResponse, PasskeyRegistrationStateDto)> {
+    let user_id = current_user_id(current_user)?;
+    let exclude_credentials = repo
+        .list_user_credentials(user_id)?
+        .iter()
+        .map(|credential| parse_passkey(credential).map(|passkey| passkey.cred_id().clone()))
+        .collect::<ServiceResult<Vec<_>>>()?;
+
+    let display_name = if current_user.name.is_empty() {
+        current_user.email.as_str()
+    } else {
+        current_user.name.as_str()
+    };
+    let (challenge, state) = webauthn
+        .start_passkey_registration(
+            user_handle(user_id),
+            &current_user.email,
+            display_name,
+            Some(exclude_credentials),
+        )
+        .map_err(|e| {
+            log::error!("Failed to start passkey registration: {e}");
+            ServiceError::Internal
+        })?;
+
+    Ok((
+        challenge,
+        PasskeyRegistrationStateDto {
+            user_id: user_id.get(),
+            state,
+        },
+    ))
+}
+
+/// Verifies the authenticator response and stores the new passkey.
+///
+/// Returns [`ServiceError::Unauthorized`] when the ceremony was started by a
+/// different user or the attestation does not verify.
+pub fn finish_registration(
+    webauthn: &Webauthn,
+    payload: PasskeyRegistrationPayload,
+    state: &PasskeyRegistrationStateDto,
+    current_user: &AuthenticatedUser,
+    repo: &impl CredentialWriter,
+) -> ServiceResult<PasskeyListItemDto> {
+    let user_id = current_user_id(current_user)?;
+    if state.user_id != user_id.get() {
+        return Err(ServiceError::Unauthorized);
+    }
+
+    let passkey = webauthn
+        .finish_passkey_registration(&payload.credential, &state.state)
+        .map_err(|e| {
+            log::warn!("Passkey registration rejected: {e}");
+            ServiceError::Unauthorized
+        })?;
+
+    let new_credential = NewUserCredential::new(
+        user_id,
+        HubId::new(current_user.hub_id)?,
+        encode_credential_id(passkey.cred_id().as_ref()),
+        payload.name,
+        serialize_passkey(&passkey)?,
+    );
+    let credential = repo.create_user_credential(&new_credential)?;
+    Ok(credential.into())
+}
+
+/// Starts a passkey sign-in for the account identified by email and hub.
+///
+/// Returns [`ServiceError::Unauthorized`] when the account does not exist or
+/// has no passkeys.
+pub fn start_login(
+    webauthn: &Webauthn,
+    payload: PasskeyLoginPayload,
+    repo: &(impl UserReader + CredentialReader),
+) -> ServiceResult<(RequestChallengeResponse, PasskeyLoginStateDto)> {
+    let user_roles = repo
+        .get_user_by_email(&payload.email, payload.hub_id)?
+        .ok_or(ServiceError::Unauthorized)?;
+    let passkeys = repo
+        .list_user_credentials(user_roles.user.id)?
+        .iter()
+        .map(parse_passkey)
+        .collect::<ServiceResult<Vec<_>>>()?;
+    if passkeys.is_empty() {
+        return Err(ServiceError::Unauthorized);
+    }
+
+    let (challenge, state) = webauthn
+        .start_passkey_authentication(&passkeys)
+        .map_err(|e| {
+            log::error!("Failed to start passkey authentication: {e}");
+            ServiceError::Internal
+        })?;
+
+    Ok((
+        challenge,
+        PasskeyLoginStateDto {
+            user_id: user_roles.user.id.get(),
+            hub_id: user_roles.user.hub_id.get(),
+            state,
+        },
+    ))
+}
+
+/// Verifies a passkey assertion and issues a session JWT.
+///
+/// The stored signature counter is updated on every successful assertion so
+/// cloned authenticators are detected. Like a password login, a successful
+/// sign-in invalidates outstanding recovery links, is refused with
+/// [`LoginError::EmailNotVerified`] when the hub requires a verified address,
+/// and sends users who have to change their password to that step.
+pub fn finish_login<R>(
+    webauthn: &Webauthn,
+    credential: &PublicKeyCredential,
+    state: &PasskeyLoginStateDto,
+    client: &ClientInfo,
+    keys: &JwtKeys,
+    repo: &R,
+) -> Result<LoginOutcome, LoginError>
+where
+    R: UserReader
+        + CredentialReader
+        + CredentialWriter
+        + PasswordResetWriter
+        + HubReader
+        + SessionWriter
+        + AuditWriter
+        + PermissionReader,
+{
+    let result = webauthn
+        .finish_passkey_authentication(credential, &state.state)
+        .map_err(|e| {
+            log::warn!("Passkey assertion rejected: {e}");
+            ServiceError::Unauthorized
+        })?;
+
+    let stored = repo
+        .get_credential_by_credential_id(&encode_credential_id(result.cred_id().as_ref()))?
+        .filter(|stored| stored.user_id.get() == state.user_id)
+        .ok_or(ServiceError::Unauthorized)?;
+    let mut passkey = parse_passkey(&stored)?;
+    passkey.update_credential(&result);
+    repo.update_credential_passkey(
+        stored.id,
+        &serialize_passkey(&passkey)?,
+        Utc::now().naive_utc(),
+    )?;
+
+    let user_roles = repo
+        .get_user_by_id(
+            stored.user_id,
+            HubId::new(state.hub_id).map_err(ServiceError::from)?,
+        )?
+        .filter(|user_roles| !user_roles.user.is_disabled())
+        .ok_or(ServiceError::Unauthorized)?;
+    ensure_email_verified(&user_roles, repo)?;
+    repo.invalidate_password_resets(user_roles.user.id)?;
+    if let Some(outcome) = password_change_step(&user_roles) {
+        return Ok(outcome);
+    }
+
+    let claims = AuthenticatedUser::from(user_roles);
+    Ok(LoginOutcome::Authenticated(issue_jwt(
+        &claims,
+        AuditEventType::LoginSucceeded,
+        client,
+        keys,
+        repo,
+    )?))
+}
+
+/// Lists the passkeys registered by the current user.
+pub fn list_passkeys(
+    current_user: &AuthenticatedUser,
+    repo: &impl CredentialReader,
+) -> ServiceResult<Vec<PasskeyListItemDto>> {
+    let user_id = current_user_id(current_user)?;
+    Ok(repo
+        .list_user_credentials(user_id)?
+        .into_iter()
+        .map(PasskeyListItemDto::from)
+        .collect())
+}
+
+/// Removes one of the current user's passkeys.
+pub fn delete_passkey(
+    id: i32,
+    current_user: &AuthenticatedUser,
+    repo: &impl CredentialWriter,
+) -> ServiceResult<()> {
+    let user_id = current_user_id(current_user)?;
+    let id = UserCredentialId::new(id)?;
+    match repo.delete_user_credential(user_id, id)? {
+        0 => Err(ServiceError::NotFound),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::types::UserEmail;
+    use crate::repository::mock::MockRepository;
+
+    fn make_current_user(id: i32) -> AuthenticatedUser {
+        AuthenticatedUser {
+            sub: id.to_string(),
+            email: "user@example.com".into(),
+            hub_id: 1,
+            name: "User".into(),
+            roles: vec![],
+            exp: 0,
+        }
+    }
+
+    #[test]
+    fn build_webauthn_accepts_plain_and_secure_domains() {
+        assert!(build_webauthn("localhost").is_ok());
+        assert!(build_webauthn("example.com").is_ok());
+    }
+
+    #[test]
+    fn encode_credential_id_is_lowercase_hex() {
+        assert_eq!(encode_credential_id(&[0x00, 0xab, 0x10]), "00ab10");
+    }
+
+    #[test]
+    fn start_registration_issues_challenge_for_current_user() {
+        let webauthn = build_webauthn("localhost").unwrap();
+        let mut repo = MockRepository::new();
+        repo.expect_list_user_credentials()
+            .returning(|_| Ok(vec![]));
+
+        let (challenge, state) =
+            start_registration(&webauthn, &make_current_user(7), &repo).unwrap();
+        assert_eq!(state.user_id, 7);
+        assert_eq!(challenge.public_key.rp.id, "localhost");
+    }
+
+    #[test]
+    fn start_login_rejects_account_without_passkeys() {
+        let webauthn = build_webauthn("localhost").unwrap();
+        let mut repo = MockRepository::new();
+        repo.expect_get_user_by_email().returning(|_, _| Ok(None));
+        repo.expect_list_user_credentials().never();
+
+        let payload = PasskeyLoginPayload {
+            email: UserEmail::new("missing@example.com").unwrap(),
+            hub_id: HubId::new(1).unwrap(),
+        };
+        let res = start_login(&webauthn, payload, &repo);
+        assert!(matches!(res, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn delete_passkey_reports_missing_credential() {
+        let mut repo = MockRepository::new();
+        repo.expect_delete_user_credential()
+            .withf(|user_id, id| user_id.get() == 3 && id.get() == 9)
+            .times(1)
+            .returning(|_, _| Ok(0));
+
+        let res = delete_passkey(9, &make_current_user(3), &repo);
+        assert!(matches!(res, Err(ServiceError::NotFound)));
+    }
+}
@@ -4,8 +4,11 @@ use actix_web::{
     App, HttpMessage, HttpRequest, HttpResponse, cookie::Key, http::StatusCode, post, test, web,
 };
 use pushkind_auth::{
-    middleware::RequireUserExists, repository::DieselRepository,
+    domain::{audit::AuditEventType, session::ClientInfo},
+    middleware::RequireUserExists,
+    repository::DieselRepository,
     routes::api::api_v1_admin_dashboard,
+    services::{auth::issue_jwt, jwt::JwtKeys},
 };
 use pushkind_common::{domain::auth::AuthenticatedUser, models::config::CommonServerConfig};
 
@@ -33,7 +36,16 @@ async fn api_scope_rejects_deleted_user_with_valid_session() {
         exp: 0,
     };
     user.set_expiration(1);
-    let token = user.to_jwt(secret).expect("test JWT should be created");
+    let keys = JwtKeys::from_secret(secret, "default");
+    let token = issue_jwt(
+        &user,
+        AuditEventType::LoginSucceeded,
+        &ClientInfo::default(),
+        &keys,
+        &repo,
+    )
+    .expect("test JWT should be created")
+    .token;
 
     let app = test::init_service(
         App::new()
@@ -47,6 +59,7 @@ async fn api_scope_rejects_deleted_user_with_valid_session() {
                 auth_service_url: "/auth/signin".to_string(),
                 secret: secret.to_string(),
             }))
+            .app_data(web::Data::new(keys))
             .app_data(web::Data::new(repo))
             .service(test_login)
             .service(
@@ -3,10 +3,24 @@ use serde_json::Value;
 
 mod common;
 
+use chrono::{Duration, Utc};
 use pushkind_auth::{
-    domain::types::{HubId, MenuId, RoleId, UserEmail, UserId, UserPassword},
-    repository::{DieselRepository, HubReader, MenuReader, RoleReader, UserReader},
+    domain::email_verification::NewEmailVerification,
+    domain::hub::NewHub,
+    domain::password_reset::NewPasswordReset,
+    domain::types::{
+        HubId, HubName, InvitationId, MenuId, RoleId, UserEmail, UserId, UserName, UserPassword,
+    },
+    domain::user::{NewUser, UpdateUser},
+    repository::{
+        DieselRepository, EmailVerificationWriter, HubReader, HubWriter, InvitationWriter,
+        MenuReader, PasswordResetWriter, RoleReader, TwoFactorWriter, UserReader, UserWriter,
+    },
+    services::{oauth::pkce_challenge, tokens::hash_token, two_factor::hash_recovery_code},
 };
+use webauthn_authenticator_rs::WebauthnAuthenticator;
+use webauthn_authenticator_rs::softpasskey::SoftPasskey;
+use webauthn_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse, Url};
 
 fn login_form_body(email: &str, password: &str, hub_id: i32) -> String {
     format!("email={email}&password={password}&hub_id={hub_id}")
@@ -48,6 +62,23 @@ async fn login_as(
     assert_eq!(login_payload["redirect_to"], "/");
 }
 
+async fn login_status(
+    client: &reqwest::Client,
+    address: &str,
+    email: &str,
+    password: &str,
+    hub_id: i32,
+) -> StatusCode {
+    client
+        .post(format!("{address}/auth/login"))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(login_form_body(email, password, hub_id))
+        .send()
+        .await
+        .expect("Failed to submit login form.")
+        .status()
+}
+
 #[actix_web::test]
 async fn test_health() {
     let app = common::spawn_app().await;
@@ -137,315 +168,2995 @@ async fn test_unauthenticated_json_routes_return_unauthorized() {
 }
 
 #[actix_web::test]
-async fn test_admin_user_full_management_story() {
-    let app = common::spawn_app().await;
+async fn test_eddsa_sessions_and_published_jwks() {
+    let (jwt, _key_file) = common::eddsa_jwt_config("test-key");
+    let app = common::spawn_app_with_jwt(jwt).await;
     let seeded = common::setup_hub_with_users(app.db_pool());
     let client = common::build_reqwest_client();
-    let repo = DieselRepository::new(app.db_pool());
+
+    let jwks_response = client
+        .get(format!("{}/.well-known/jwks.json", app.address()))
+        .send()
+        .await
+        .expect("Failed to request the JWKS.");
+    assert_eq!(jwks_response.status(), StatusCode::OK);
+    let jwks = response_json(jwks_response).await;
+    let keys = jwks["keys"].as_array().expect("JWKS should list keys");
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["kid"], "test-key");
+    assert_eq!(keys[0]["kty"], "OKP");
+    assert_eq!(keys[0]["use"], "sig");
+    assert!(keys[0].get("d").is_none());
 
     login_as(
         &client,
         app.address(),
-        common::ADMIN_EMAIL,
-        common::ADMIN_PASSWORD,
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
         seeded.hub_id,
     )
     .await;
 
-    let signin_response = client
-        .get(format!("{}/auth/signin", app.address()))
+    let iam_response = client
+        .get(format!("{}/api/v1/iam", app.address()))
         .send()
         .await
-        .expect("Failed to request sign-in page for authenticated admin.");
+        .expect("Failed to request the current user.");
+    assert_eq!(iam_response.status(), StatusCode::OK);
+}
 
-    assert_eq!(signin_response.status(), StatusCode::OK);
-    assert_eq!(
-        signin_response.url().as_str(),
-        format!("{}/", app.address())
-    );
-    let signin_html = signin_response
-        .text()
-        .await
-        .expect("Redirected admin sign-in response should be readable.");
-    assert!(signin_html.contains("main-admin.tsx"));
+#[actix_web::test]
+async fn test_admin_sessions_report_signing_key() {
+    let (jwt, _key_file) = common::eddsa_jwt_config("2026-10");
+    let app = common::spawn_app_with_jwt(jwt).await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
 
-    let index_response = client
-        .get(format!("{}/", app.address()))
+    let user_client = common::build_reqwest_client();
+    login_as(
+        &user_client,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let forbidden = user_client
+        .get(format!("{}/api/v1/admin/sessions", app.address()))
         .send()
         .await
-        .expect("Failed to request dashboard for authenticated admin.");
-
-    assert_eq!(index_response.status(), StatusCode::OK);
-    let index_html = index_response
-        .text()
-        .await
-        .expect("Admin dashboard response should be readable.");
-    assert!(index_html.contains("main-admin.tsx"));
+        .expect("Failed to request admin sessions.");
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
 
-    let admin_dashboard_response = client
-        .get(format!("{}/api/v1/admin/dashboard", app.address()))
+    let admin_client = common::build_reqwest_client();
+    login_as(
+        &admin_client,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let response = admin_client
+        .get(format!("{}/api/v1/admin/sessions", app.address()))
         .send()
         .await
-        .expect("Failed to request admin dashboard API.");
+        .expect("Failed to request admin sessions.");
+    assert_eq!(response.status(), StatusCode::OK);
 
-    assert_eq!(admin_dashboard_response.status(), StatusCode::OK);
+    let payload = response_json(response).await;
+    assert_eq!(payload["keys"][0]["key_id"], "2026-10");
+    assert_eq!(payload["keys"][0]["algorithm"], "EdDSA");
+    assert_eq!(payload["keys"][0]["active"], true);
+    assert_eq!(payload["keys"][0]["live_sessions"], 2);
+    let sessions = payload["sessions"]
+        .as_array()
+        .expect("sessions should be a list");
+    assert_eq!(sessions.len(), 2);
+    assert!(
+        sessions
+            .iter()
+            .all(|session| session["key_id"] == "2026-10")
+    );
+}
 
-    let add_role_response = client
-        .post(format!("{}/admin/role/add", app.address()))
+#[actix_web::test]
+async fn test_sessions_can_be_revoked_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+
+    let laptop = common::build_reqwest_client();
+    laptop
+        .post(format!("{}/auth/login", app.address()))
         .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .body(mutation_form_body(&[("name", "editor")]))
+        .header(header::USER_AGENT, "Laptop Browser")
+        .body(login_form_body(
+            common::USER_EMAIL,
+            common::USER_PASSWORD,
+            seeded.hub_id,
+        ))
         .send()
         .await
-        .expect("Failed to create role.");
-
-    assert_eq!(add_role_response.status(), StatusCode::CREATED);
-    let created_role = repo
-        .get_role_by_name("editor")
-        .expect("Role lookup should succeed.")
-        .expect("Role should exist after creation.");
+        .expect("Failed to submit login form.");
+    let phone = common::build_reqwest_client();
+    login_as(
+        &phone,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
 
-    let add_hub_response = client
-        .post(format!("{}/admin/hub/add", app.address()))
-        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .body(mutation_form_body(&[("name", "branch")]))
+    let sessions_response = laptop
+        .get(format!("{}/api/v1/sessions", app.address()))
         .send()
         .await
-        .expect("Failed to create hub.");
+        .expect("Failed to list sessions.");
+    assert_eq!(sessions_response.status(), StatusCode::OK);
+    let sessions = response_json(sessions_response).await;
+    let sessions = sessions.as_array().expect("sessions should be a list");
+    assert_eq!(sessions.len(), 2);
+    let current = sessions
+        .iter()
+        .find(|session| session["current"] == true)
+        .expect("the laptop session should be marked as current");
+    assert_eq!(current["user_agent"], "Laptop Browser");
+    let other_id = sessions
+        .iter()
+        .find(|session| session["current"] == false)
+        .and_then(|session| session["id"].as_i64())
+        .expect("the phone session should be listed");
 
-    assert_eq!(add_hub_response.status(), StatusCode::CREATED);
-    let created_hub = repo
-        .get_hub_by_name("branch")
-        .expect("Hub lookup should succeed.")
-        .expect("Hub should exist after creation.");
+    let revoke_response = laptop
+        .post(format!("{}/user/sessions/revoke/{other_id}", app.address()))
+        .send()
+        .await
+        .expect("Failed to revoke session.");
+    assert_eq!(revoke_response.status(), StatusCode::OK);
 
-    let add_menu_response = client
-        .post(format!("{}/admin/menu/add", app.address()))
-        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .body(mutation_form_body(&[
-            ("name", "docs"),
-            ("url", "https://docs.example.com"),
-        ]))
+    // The revoked session is signed out while public routes keep working.
+    let phone_iam = phone
+        .get(format!("{}/api/v1/iam", app.address()))
         .send()
         .await
-        .expect("Failed to create menu item.");
+        .expect("Failed to request shell data.");
+    assert_eq!(phone_iam.status(), StatusCode::UNAUTHORIZED);
+    let phone_hubs = phone
+        .get(format!("{}/api/v1/hubs", app.address()))
+        .send()
+        .await
+        .expect("Failed to list hubs.");
+    assert_eq!(phone_hubs.status(), StatusCode::OK);
 
-    assert_eq!(add_menu_response.status(), StatusCode::CREATED);
-    let created_menu = repo
-        .list_menu(HubId::new(seeded.hub_id).unwrap())
-        .expect("Menu lookup should succeed.")
-        .into_iter()
-        .find(|menu| menu.name.as_str() == "docs")
-        .expect("Menu item should exist after creation.");
+    let revoke_all_response = laptop
+        .post(format!("{}/user/sessions/revoke-all", app.address()))
+        .send()
+        .await
+        .expect("Failed to log out everywhere.");
+    assert_eq!(revoke_all_response.status(), StatusCode::OK);
+    let payload = response_json(revoke_all_response).await;
+    assert_eq!(payload["redirect_to"], "/auth/signin");
 
-    let modal_response = client
-        .post(format!(
-            "{}/admin/user/modal/{}",
-            app.address(),
-            seeded.user_id
-        ))
+    let laptop_iam = laptop
+        .get(format!("{}/api/v1/iam", app.address()))
         .send()
         .await
-        .expect("Failed to fetch admin user modal.");
+        .expect("Failed to request shell data.");
+    assert_eq!(laptop_iam.status(), StatusCode::UNAUTHORIZED);
+}
 
-    assert_eq!(modal_response.status(), StatusCode::OK);
-    let modal_payload = response_json(modal_response).await;
-    assert_eq!(modal_payload["user"]["id"], seeded.user_id);
-    assert_eq!(modal_payload["user"]["email"], common::USER_EMAIL);
-    assert!(
-        modal_payload["roles"]
-            .as_array()
-            .expect("Roles should be present in modal response.")
-            .iter()
-            .any(|role| role["name"] == "editor")
-    );
+#[actix_web::test]
+async fn test_personal_access_token_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let client = common::build_reqwest_client();
+    login_as(
+        &client,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
 
-    let update_user_response = client
-        .post(format!(
-            "{}/admin/user/update/{}",
-            app.address(),
-            seeded.user_id
-        ))
+    let response = client
+        .post(format!("{}/user/tokens/add", app.address()))
         .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
         .body(mutation_form_body(&[
-            ("name", "managed-user"),
-            ("password", "new-user-password"),
-            ("roles", &created_role.id.get().to_string()),
+            ("name", "CI"),
+            ("scopes", "users"),
+            ("expires_in_days", "30"),
         ]))
         .send()
         .await
-        .expect("Failed to update user as admin.");
-
-    assert_eq!(update_user_response.status(), StatusCode::OK);
-    let updated_user = repo
-        .get_user_by_id(
-            UserId::new(seeded.user_id).unwrap(),
-            HubId::new(seeded.hub_id).unwrap(),
-        )
-        .expect("Updated user lookup should succeed.")
-        .expect("Updated user should still exist.");
-    assert_eq!(
-        updated_user.user.name.as_ref().map(|name| name.as_str()),
-        Some("managed-user")
-    );
-    assert_eq!(updated_user.roles.len(), 1);
-    assert_eq!(updated_user.roles[0].name.as_str(), "editor");
-    let relogin = repo
-        .login(
-            &UserEmail::new(common::USER_EMAIL).unwrap(),
-            &UserPassword::new("new-user-password").unwrap(),
-            HubId::new(seeded.hub_id).unwrap(),
-        )
-        .expect("Updated user login lookup should succeed.");
-    assert!(relogin.is_some());
+        .expect("Failed to create a token.");
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = response_json(response).await;
+    let token = created["token"].as_str().unwrap().to_string();
+    assert!(token.starts_with("pat_"));
 
-    let delete_menu_response = client
-        .post(format!(
-            "{}/admin/menu/delete/{}",
-            app.address(),
-            created_menu.id.get()
-        ))
+    let tokens_response = client
+        .get(format!("{}/api/v1/tokens", app.address()))
         .send()
         .await
-        .expect("Failed to delete menu item.");
-
-    assert_eq!(delete_menu_response.status(), StatusCode::OK);
-    assert!(
-        repo.get_menu_by_id(
-            MenuId::new(created_menu.id.get()).unwrap(),
-            HubId::new(seeded.hub_id).unwrap(),
-        )
-        .expect("Deleted menu lookup should succeed.")
-        .is_none()
-    );
+        .expect("Failed to list tokens.");
+    assert_eq!(tokens_response.status(), StatusCode::OK);
+    let tokens = response_json(tokens_response).await;
+    let tokens = tokens.as_array().expect("tokens should be a list");
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["name"], "CI");
+    let token_id = tokens[0]["id"].as_i64().unwrap();
 
-    let delete_hub_response = client
-        .post(format!(
-            "{}/admin/hub/delete/{}",
-            app.address(),
-            created_hub.id.get()
-        ))
+    // The token works without cookies, but only within its scopes.
+    let script = reqwest::Client::new();
+    let users_response = script
+        .get(format!("{}/api/v1/users", app.address()))
+        .bearer_auth(&token)
         .send()
         .await
-        .expect("Failed to delete hub.");
-
-    assert_eq!(delete_hub_response.status(), StatusCode::OK);
-    assert!(
-        repo.get_hub_by_name("branch")
-            .expect("Deleted hub lookup should succeed.")
-            .is_none()
-    );
+        .expect("Failed to list users.");
+    assert_eq!(users_response.status(), StatusCode::OK);
+    let iam_response = script
+        .get(format!("{}/api/v1/iam", app.address()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(iam_response.status(), StatusCode::FORBIDDEN);
+    let sessions_response = script
+        .get(format!("{}/api/v1/sessions", app.address()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to list sessions.");
+    assert_eq!(sessions_response.status(), StatusCode::FORBIDDEN);
 
-    let delete_role_response = client
-        .post(format!(
-            "{}/admin/role/delete/{}",
-            app.address(),
-            created_role.id.get()
-        ))
+    let revoke_response = client
+        .post(format!("{}/user/tokens/revoke/{token_id}", app.address()))
         .send()
         .await
-        .expect("Failed to delete role.");
+        .expect("Failed to revoke the token.");
+    assert_eq!(revoke_response.status(), StatusCode::OK);
 
-    assert_eq!(delete_role_response.status(), StatusCode::OK);
-    assert!(
-        repo.get_role_by_id(RoleId::new(created_role.id.get()).unwrap())
-            .expect("Deleted role lookup should succeed.")
-            .is_none()
-    );
+    let users_response = script
+        .get(format!("{}/api/v1/users", app.address()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to list users.");
+    assert_eq!(users_response.status(), StatusCode::UNAUTHORIZED);
 }
 
 #[actix_web::test]
-async fn test_non_admin_user_self_service_story() {
-    let app = common::spawn_app().await;
+async fn test_personal_access_token_requests_never_issue_cookies() {
+    let jwt = pushkind_auth::models::config::JwtConfig {
+        permissions_claim: true,
+        ..Default::default()
+    };
+    let app = common::spawn_app_with_jwt(jwt).await;
     let seeded = common::setup_hub_with_users(app.db_pool());
     let client = common::build_reqwest_client();
-    let repo = DieselRepository::new(app.db_pool());
-
     login_as(
         &client,
         app.address(),
-        common::USER_EMAIL,
-        common::USER_PASSWORD,
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
         seeded.hub_id,
     )
     .await;
 
-    let signin_response = client
-        .get(format!("{}/auth/signin", app.address()))
+    let response = client
+        .post(format!("{}/user/tokens/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "CI"),
+            ("scopes", "users"),
+            ("expires_in_days", "30"),
+        ]))
         .send()
         .await
-        .expect("Failed to request sign-in page for authenticated user.");
+        .expect("Failed to create a token.");
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = response_json(response).await;
+    let token = created["token"].as_str().unwrap().to_string();
+
+    // The token's claims lack the permissions claim, yet no session cookie is
+    // minted from them.
+    let users_response = reqwest::Client::new()
+        .get(format!("{}/api/v1/users", app.address()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .expect("Failed to list users.");
+    assert_eq!(users_response.status(), StatusCode::OK);
+    assert!(users_response.headers().get(header::SET_COOKIE).is_none());
+}
+
+#[actix_web::test]
+async fn test_service_account_client_credentials_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let client = common::build_reqwest_client();
+    login_as(
+        &client,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let repo = DieselRepository::new(app.db_pool());
+    let admin_role = repo
+        .get_role_by_name("admin", HubId::new(seeded.hub_id).unwrap())
+        .unwrap()
+        .unwrap();
+
+    let response = client
+        .post(format!("{}/admin/service-account/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "crm-sync"),
+            ("roles", &admin_role.id.get().to_string()),
+        ]))
+        .send()
+        .await
+        .expect("Failed to create a service account.");
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let credentials = response_json(response).await;
+    let client_id = credentials["client_id"].as_str().unwrap().to_string();
+    let client_secret = credentials["client_secret"].as_str().unwrap().to_string();
+    assert!(client_id.starts_with("sa_"));
+
+    let accounts_response = client
+        .get(format!("{}/api/v1/users/service-accounts", app.address()))
+        .send()
+        .await
+        .expect("Failed to list service accounts.");
+    assert_eq!(accounts_response.status(), StatusCode::OK);
+    let accounts = response_json(accounts_response).await;
+    assert_eq!(accounts[0]["client_id"], client_id.as_str());
+    assert_eq!(accounts[0]["roles"], serde_json::json!(["admin"]));
+    let dashboard = response_json(
+        client
+            .get(format!("{}/api/v1/admin/dashboard", app.address()))
+            .send()
+            .await
+            .expect("Failed to request admin dashboard."),
+    )
+    .await;
+    let account_id = dashboard["service_accounts"][0]["id"].as_i64().unwrap();
+
+    // The integration exchanges its credentials for a short-lived token.
+    let integration = reqwest::Client::new();
+    let token_response = integration
+        .post(format!("{}/oauth/token", app.address()))
+        .basic_auth(&client_id, Some(&client_secret))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body("grant_type=client_credentials")
+        .send()
+        .await
+        .expect("Failed to request a token.");
+    assert_eq!(token_response.status(), StatusCode::OK);
+    let token = response_json(token_response).await;
+    let access_token = token["access_token"].as_str().unwrap().to_string();
+    assert_eq!(token["token_type"], "Bearer");
+    assert_eq!(token["expires_in"], 3600);
+
+    let wrong_secret = integration
+        .post(format!("{}/oauth/token", app.address()))
+        .basic_auth(&client_id, Some("wrong"))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body("grant_type=client_credentials")
+        .send()
+        .await
+        .expect("Failed to request a token.");
+    assert_eq!(wrong_secret.status(), StatusCode::UNAUTHORIZED);
+
+    // The token lists users of the hub but opens nothing else.
+    let users_response = integration
+        .get(format!("{}/api/v1/users", app.address()))
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .expect("Failed to list users.");
+    assert_eq!(users_response.status(), StatusCode::OK);
+    let dashboard_response = integration
+        .get(format!("{}/api/v1/admin/dashboard", app.address()))
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .expect("Failed to request admin dashboard.");
+    assert_eq!(dashboard_response.status(), StatusCode::FORBIDDEN);
+    let iam_response = integration
+        .get(format!("{}/api/v1/iam", app.address()))
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(iam_response.status(), StatusCode::FORBIDDEN);
+
+    // Rotating the secret revokes outstanding tokens.
+    let rotate_response = client
+        .post(format!(
+            "{}/admin/service-account/rotate/{account_id}",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to rotate the secret.");
+    assert_eq!(rotate_response.status(), StatusCode::OK);
+    let rotated = response_json(rotate_response).await;
+    assert_eq!(rotated["client_id"], client_id.as_str());
+    assert_ne!(rotated["client_secret"], client_secret.as_str());
+
+    let users_response = integration
+        .get(format!("{}/api/v1/users", app.address()))
+        .bearer_auth(&access_token)
+        .send()
+        .await
+        .expect("Failed to list users.");
+    assert_eq!(users_response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_removed_roles_take_effect_without_new_login() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let client = common::build_reqwest_client();
+    login_as(
+        &client,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let dashboard = client
+        .get(format!("{}/api/v1/admin/dashboard", app.address()))
+        .send()
+        .await
+        .expect("Failed to request admin dashboard.");
+    assert_eq!(dashboard.status(), StatusCode::OK);
+
+    let repo = DieselRepository::new(app.db_pool());
+    repo.update_user(
+        UserId::new(seeded.admin_user_id).unwrap(),
+        HubId::new(seeded.hub_id).unwrap(),
+        &UpdateUser::new(UserName::new("Former Admin").unwrap(), None, Some(vec![])),
+    )
+    .expect("Failed to remove admin roles.");
+
+    let dashboard = client
+        .get(format!("{}/api/v1/admin/dashboard", app.address()))
+        .send()
+        .await
+        .expect("Failed to request admin dashboard.");
+    assert_eq!(dashboard.status(), StatusCode::FORBIDDEN);
+
+    // The reissued cookie carries the live claims on later requests too.
+    let iam_response = client
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(iam_response.status(), StatusCode::OK);
+    let shell = response_json(iam_response).await;
+    assert_eq!(shell["current_user"]["name"], "Former Admin");
+    assert_eq!(shell["current_user"]["roles"], serde_json::json!([]));
+}
+
+#[actix_web::test]
+async fn test_openid_connect_authorization_code_story() {
+    let (jwt, _key_file) = common::eddsa_jwt_config("oidc-key");
+    let app = common::spawn_app_with_jwt(jwt).await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let client = reqwest::Client::builder()
+        .cookie_store(true)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("Failed to create no-redirect client.");
+    let redirect_uri = "https://portal.example.com/callback";
+
+    let discovery_response = client
+        .get(format!(
+            "{}/.well-known/openid-configuration",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to request the discovery document.");
+    assert_eq!(discovery_response.status(), StatusCode::OK);
+    let discovery = response_json(discovery_response).await;
+    assert_eq!(discovery["issuer"], app.address());
+    assert_eq!(
+        discovery["token_endpoint"],
+        format!("{}/oauth/token", app.address())
+    );
+    assert_eq!(
+        discovery["code_challenge_methods_supported"],
+        serde_json::json!(["S256"])
+    );
+
+    login_as(
+        &client,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let registration = client
+        .post(format!("{}/admin/oauth-client/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "Portal"),
+            ("redirect_uris", redirect_uri),
+            ("confidential", "true"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to register a client.");
+    assert_eq!(registration.status(), StatusCode::CREATED);
+    let credentials = response_json(registration).await;
+    let client_id = credentials["client_id"].as_str().unwrap().to_string();
+    let client_secret = credentials["client_secret"].as_str().unwrap().to_string();
+
+    let code_verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    let authorize_url = Url::parse_with_params(
+        &format!("{}/oauth/authorize", app.address()),
+        &[
+            ("response_type", "code"),
+            ("client_id", client_id.as_str()),
+            ("redirect_uri", redirect_uri),
+            ("scope", "openid email"),
+            ("state", "xyz"),
+            ("nonce", "n-0S6_WzA2Mj"),
+            ("code_challenge", pkce_challenge(code_verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .unwrap();
+
+    // Anonymous users sign in first and come back to the same request.
+    let anonymous = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("Failed to create no-redirect client.");
+    let response = anonymous
+        .get(authorize_url.clone())
+        .send()
+        .await
+        .expect("Failed to start authorization.");
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(location.starts_with("/auth/signin?next=%2Foauth%2Fauthorize"));
+
+    let response = client
+        .get(authorize_url)
+        .send()
+        .await
+        .expect("Failed to authorize.");
+    assert_eq!(response.status(), StatusCode::FOUND);
+    let location = Url::parse(response.headers()[header::LOCATION].to_str().unwrap()).unwrap();
+    assert!(location.as_str().starts_with(redirect_uri));
+    let params: std::collections::HashMap<_, _> = location.query_pairs().into_owned().collect();
+    assert_eq!(params["state"], "xyz");
+    let code = params["code"].clone();
+
+    let token_body = mutation_form_body(&[
+        ("grant_type", "authorization_code"),
+        ("code", &code),
+        ("redirect_uri", redirect_uri),
+        ("code_verifier", code_verifier),
+    ]);
+
+    let response = client
+        .post(format!("{}/oauth/token", app.address()))
+        .basic_auth(&client_id, Some("wrong-secret"))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(token_body.clone())
+        .send()
+        .await
+        .expect("Failed to exchange the code.");
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response_json(response).await["error"], "invalid_client");
+
+    let response = client
+        .post(format!("{}/oauth/token", app.address()))
+        .basic_auth(&client_id, Some(&client_secret))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(token_body.clone())
+        .send()
+        .await
+        .expect("Failed to exchange the code.");
+    assert_eq!(response.status(), StatusCode::OK);
+    let tokens = response_json(response).await;
+    assert_eq!(tokens["token_type"], "Bearer");
+    assert_eq!(tokens["scope"], "openid email");
+    assert!(tokens["id_token"].as_str().unwrap().split('.').count() == 3);
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    // Codes are single use.
+    let response = client
+        .post(format!("{}/oauth/token", app.address()))
+        .basic_auth(&client_id, Some(&client_secret))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(token_body)
+        .send()
+        .await
+        .expect("Failed to replay the code.");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_json(response).await["error"], "invalid_grant");
+
+    let response = anonymous
+        .get(format!("{}/oauth/userinfo", app.address()))
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .expect("Failed to request userinfo.");
+    assert_eq!(response.status(), StatusCode::OK);
+    let userinfo = response_json(response).await;
+    assert_eq!(userinfo["sub"], seeded.admin_user_id.to_string());
+    assert_eq!(userinfo["email"], common::ADMIN_EMAIL);
+    assert_eq!(userinfo["hub_id"], seeded.hub_id);
+
+    let response = anonymous
+        .get(format!("{}/oauth/userinfo", app.address()))
+        .bearer_auth("not-a-token")
+        .send()
+        .await
+        .expect("Failed to request userinfo.");
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_openid_connect_is_refused_with_hs256_sessions() {
+    let app = common::spawn_app().await;
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("Failed to create no-redirect client.");
+
+    let discovery_response = client
+        .get(format!(
+            "{}/.well-known/openid-configuration",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to request the discovery document.");
+    assert_eq!(discovery_response.status(), StatusCode::NOT_FOUND);
+
+    let authorize_response = client
+        .get(format!(
+            "{}/oauth/authorize?response_type=code&client_id=app",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to start authorization.");
+    assert_eq!(authorize_response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response_json(authorize_response).await["error"],
+        "unsupported_response_type"
+    );
+
+    let token_response = client
+        .post(format!("{}/oauth/token", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("grant_type", "authorization_code"),
+            ("code", "code"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to exchange a code.");
+    assert_eq!(token_response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response_json(token_response).await["error"],
+        "unsupported_grant_type"
+    );
+}
+
+#[actix_web::test]
+async fn test_repeated_failed_logins_are_throttled() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let client = common::build_reqwest_client();
+    let max_attempts =
+        pushkind_auth::models::config::LoginThrottleConfig::default().max_attempts_per_account;
+
+    for _ in 0..max_attempts {
+        let response = client
+            .post(format!("{}/auth/login", app.address()))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(login_form_body(common::USER_EMAIL, "wrong", seeded.hub_id))
+            .send()
+            .await
+            .expect("Failed to submit login form.");
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    let locked_response = client
+        .post(format!("{}/auth/login", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(login_form_body(
+            common::USER_EMAIL,
+            common::USER_PASSWORD,
+            seeded.hub_id,
+        ))
+        .send()
+        .await
+        .expect("Failed to submit login form.");
+    assert_eq!(locked_response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(locked_response.headers().contains_key(header::RETRY_AFTER));
+
+    // Other accounts from the same client are still allowed to sign in.
+    login_as(
+        &client,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+}
+
+#[actix_web::test]
+async fn test_login_with_second_factor_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let repo = DieselRepository::new(app.db_pool());
+    let client = common::build_reqwest_client();
+    let user_id = UserId::new(seeded.user_id).unwrap();
+
+    repo.create_pending_totp(user_id, "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP")
+        .expect("Failed to create TOTP secret.");
+    repo.enable_totp(
+        user_id,
+        0,
+        &[hash_recovery_code("abcdef-123456")],
+        Utc::now().naive_utc(),
+    )
+    .expect("Failed to enable TOTP.");
+
+    let login_response = client
+        .post(format!("{}/auth/login", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(login_form_body(
+            common::USER_EMAIL,
+            common::USER_PASSWORD,
+            seeded.hub_id,
+        ))
+        .send()
+        .await
+        .expect("Failed to submit login form.");
+    assert_eq!(login_response.status(), StatusCode::OK);
+    let login_payload = response_json(login_response).await;
+    assert!(
+        login_payload["redirect_to"]
+            .as_str()
+            .is_some_and(|url| url.starts_with("/auth/2fa?"))
+    );
+
+    // The password alone does not open a session.
+    let pending_response = client
+        .post(format!("{}/user/save", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "User")]))
+        .send()
+        .await
+        .expect("Failed to request user save JSON.");
+    assert_eq!(pending_response.status(), StatusCode::UNAUTHORIZED);
+
+    let wrong_code_response = client
+        .post(format!("{}/auth/2fa/verify", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("code", "000000")]))
+        .send()
+        .await
+        .expect("Failed to submit second factor.");
+    assert_eq!(wrong_code_response.status(), StatusCode::UNAUTHORIZED);
+
+    let verify_response = client
+        .post(format!("{}/auth/2fa/verify", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("code", "abcdef-123456")]))
+        .send()
+        .await
+        .expect("Failed to submit second factor.");
+    assert_eq!(verify_response.status(), StatusCode::OK);
+    let verify_payload = response_json(verify_response).await;
+    assert_eq!(verify_payload["redirect_to"], "/");
+
+    let save_response = client
+        .post(format!("{}/user/save", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "User")]))
+        .send()
+        .await
+        .expect("Failed to request user save JSON.");
+    assert_eq!(save_response.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_passkey_registration_and_login_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let origin = Url::parse(app.address()).unwrap();
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+    // Registration requires a signed-in user.
+    let client = common::build_reqwest_client();
+    let anonymous_response = client
+        .post(format!("{}/auth/webauthn/register/start", app.address()))
+        .send()
+        .await
+        .expect("Failed to start passkey registration.");
+    assert_eq!(anonymous_response.status(), StatusCode::UNAUTHORIZED);
+
+    login_as(
+        &client,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let start_response = client
+        .post(format!("{}/auth/webauthn/register/start", app.address()))
+        .send()
+        .await
+        .expect("Failed to start passkey registration.");
+    assert_eq!(start_response.status(), StatusCode::OK);
+    let challenge: CreationChallengeResponse = serde_json::from_str(
+        &start_response
+            .text()
+            .await
+            .expect("Response body should be readable."),
+    )
+    .expect("Registration challenge should be valid JSON.");
+    assert_eq!(challenge.public_key.rp.id, "localhost");
+
+    let credential = authenticator
+        .do_registration(origin.clone(), challenge)
+        .expect("Software authenticator failed to register.");
+    let finish_response = client
+        .post(format!("{}/auth/webauthn/register/finish", app.address()))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::json!({ "name": "Laptop", "credential": credential }).to_string())
+        .send()
+        .await
+        .expect("Failed to finish passkey registration.");
+    assert_eq!(finish_response.status(), StatusCode::CREATED);
+
+    let list_response = client
+        .get(format!("{}/api/v1/passkeys", app.address()))
+        .send()
+        .await
+        .expect("Failed to list passkeys.");
+    assert_eq!(list_response.status(), StatusCode::OK);
+    let passkeys = response_json(list_response).await;
+    assert_eq!(passkeys.as_array().map(Vec::len), Some(1));
+    assert_eq!(passkeys[0]["name"], "Laptop");
+    assert!(passkeys[0]["last_used_at"].is_null());
+    let passkey_id = passkeys[0]["id"].as_i64().unwrap();
+
+    // A fresh client signs in with the passkey instead of the password.
+    let client = common::build_reqwest_client();
+    let login_start_response = client
+        .post(format!("{}/auth/webauthn/login/start", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", common::USER_EMAIL),
+            ("hub_id", &seeded.hub_id.to_string()),
+        ]))
+        .send()
+        .await
+        .expect("Failed to start passkey login.");
+    assert_eq!(login_start_response.status(), StatusCode::OK);
+    let challenge: RequestChallengeResponse = serde_json::from_str(
+        &login_start_response
+            .text()
+            .await
+            .expect("Response body should be readable."),
+    )
+    .expect("Authentication challenge should be valid JSON.");
+
+    let assertion = authenticator
+        .do_authentication(origin, challenge)
+        .expect("Software authenticator failed to sign the challenge.");
+    let login_finish_response = client
+        .post(format!("{}/auth/webauthn/login/finish", app.address()))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&assertion).unwrap())
+        .send()
+        .await
+        .expect("Failed to finish passkey login.");
+    assert_eq!(login_finish_response.status(), StatusCode::OK);
+    let login_payload = response_json(login_finish_response).await;
+    assert_eq!(login_payload["redirect_to"], "/");
+
+    // The ceremony state is single use.
+    let replay_response = client
+        .post(format!("{}/auth/webauthn/login/finish", app.address()))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&assertion).unwrap())
+        .send()
+        .await
+        .expect("Failed to replay passkey login.");
+    assert_eq!(replay_response.status(), StatusCode::BAD_REQUEST);
+
+    let list_response = client
+        .get(format!("{}/api/v1/passkeys", app.address()))
+        .send()
+        .await
+        .expect("Failed to list passkeys.");
+    let passkeys = response_json(list_response).await;
+    assert!(!passkeys[0]["last_used_at"].is_null());
+
+    let delete_response = client
+        .post(format!(
+            "{}/user/passkeys/delete/{passkey_id}",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to delete passkey.");
+    assert_eq!(delete_response.status(), StatusCode::OK);
+
+    let login_start_response = client
+        .post(format!("{}/auth/webauthn/login/start", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", common::USER_EMAIL),
+            ("hub_id", &seeded.hub_id.to_string()),
+        ]))
+        .send()
+        .await
+        .expect("Failed to start passkey login.");
+    assert_eq!(login_start_response.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// Runs a passkey sign-in for the seeded user on a fresh client.
+async fn passkey_login(
+    address: &str,
+    authenticator: &mut WebauthnAuthenticator<SoftPasskey>,
+    hub_id: i32,
+) -> (reqwest::Client, reqwest::Response) {
+    let client = common::build_reqwest_client();
+    let start_response = client
+        .post(format!("{address}/auth/webauthn/login/start"))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", common::USER_EMAIL),
+            ("hub_id", &hub_id.to_string()),
+        ]))
+        .send()
+        .await
+        .expect("Failed to start passkey login.");
+    assert_eq!(start_response.status(), StatusCode::OK);
+    let challenge: RequestChallengeResponse = serde_json::from_str(
+        &start_response
+            .text()
+            .await
+            .expect("Response body should be readable."),
+    )
+    .expect("Authentication challenge should be valid JSON.");
+
+    let assertion = authenticator
+        .do_authentication(Url::parse(address).unwrap(), challenge)
+        .expect("Software authenticator failed to sign the challenge.");
+    let finish_response = client
+        .post(format!("{address}/auth/webauthn/login/finish"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&assertion).unwrap())
+        .send()
+        .await
+        .expect("Failed to finish passkey login.");
+    (client, finish_response)
+}
+
+#[actix_web::test]
+async fn test_passkey_login_respects_account_gates() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+    let client = common::build_reqwest_client();
+    login_as(
+        &client,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let start_response = client
+        .post(format!("{}/auth/webauthn/register/start", app.address()))
+        .send()
+        .await
+        .expect("Failed to start passkey registration.");
+    let challenge: CreationChallengeResponse = serde_json::from_str(
+        &start_response
+            .text()
+            .await
+            .expect("Response body should be readable."),
+    )
+    .expect("Registration challenge should be valid JSON.");
+    let credential = authenticator
+        .do_registration(Url::parse(app.address()).unwrap(), challenge)
+        .expect("Software authenticator failed to register.");
+    let finish_response = client
+        .post(format!("{}/auth/webauthn/register/finish", app.address()))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::json!({ "name": "Laptop", "credential": credential }).to_string())
+        .send()
+        .await
+        .expect("Failed to finish passkey registration.");
+    assert_eq!(finish_response.status(), StatusCode::CREATED);
+
+    // The hub starts requiring verified addresses; the user has none yet.
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let policy_response = admin
+        .post(format!("{}/admin/hub/policy", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body("require_email_verification=true")
+        .send()
+        .await
+        .expect("Failed to update the hub policy.");
+    assert_eq!(policy_response.status(), StatusCode::OK);
+
+    let (_, unverified_response) =
+        passkey_login(app.address(), &mut authenticator, seeded.hub_id).await;
+    assert_eq!(unverified_response.status(), StatusCode::FORBIDDEN);
+
+    // Once verified, a required password change still comes first.
+    let repo = DieselRepository::new(app.db_pool());
+    let user_id = UserId::new(seeded.user_id).unwrap();
+    let hub_id = HubId::new(seeded.hub_id).unwrap();
+    repo.mark_email_verified(user_id, hub_id, Utc::now().naive_utc())
+        .expect("Failed to verify the address.");
+    repo.update_user(
+        user_id,
+        hub_id,
+        &UpdateUser::new(UserName::new("User").unwrap(), None, None)
+            .with_must_change_password(true),
+    )
+    .expect("Failed to require a password change.");
+
+    let (user, pending_response) =
+        passkey_login(app.address(), &mut authenticator, seeded.hub_id).await;
+    assert_eq!(pending_response.status(), StatusCode::OK);
+    let pending_payload = response_json(pending_response).await;
+    assert!(
+        pending_payload["redirect_to"]
+            .as_str()
+            .is_some_and(|url| url.starts_with("/auth/password?"))
+    );
+    let iam_response = user
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(iam_response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_hub_registration_settings_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let repo = DieselRepository::new(app.db_pool());
+    let admin_role = repo
+        .get_role_by_name("admin", HubId::new(seeded.hub_id).unwrap())
+        .unwrap()
+        .unwrap();
+    let settings_url = format!("{}/admin/hub/{}/settings", app.address(), seeded.hub_id);
+    let hub_id = seeded.hub_id.to_string();
+    let register = |email: &'static str| {
+        let client = common::build_reqwest_client();
+        let body = mutation_form_body(&[
+            ("email", email),
+            ("password", "new-password"),
+            ("hub_id", &hub_id),
+        ]);
+        let url = format!("{}/auth/register", app.address());
+        async move {
+            client
+                .post(url)
+                .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(body)
+                .send()
+                .await
+                .expect("Failed to register.")
+        }
+    };
+
+    // Closed hubs refuse every registration.
+    let closed_response = admin
+        .post(&settings_url)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body("registration_mode=closed")
+        .send()
+        .await
+        .expect("Failed to update the registration settings.");
+    assert_eq!(closed_response.status(), StatusCode::OK);
+    assert_eq!(
+        register("closed@company.ru").await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    // Open hubs only accept the allowed domains and assign the default roles.
+    let role_id = admin_role.id.to_string();
+    let open_response = admin
+        .post(&settings_url)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("registration_mode", "open"),
+            ("allowed_email_domains", "%40company.ru"),
+            ("default_roles", &role_id),
+        ]))
+        .send()
+        .await
+        .expect("Failed to update the registration settings.");
+    assert_eq!(open_response.status(), StatusCode::OK);
+
+    let settings_response = admin
+        .get(&settings_url)
+        .send()
+        .await
+        .expect("Failed to load the registration settings.");
+    assert_eq!(settings_response.status(), StatusCode::OK);
+    let settings = response_json(settings_response).await;
+    assert_eq!(settings["registration_mode"], "open");
+    assert_eq!(settings["allowed_email_domains"][0], "company.ru");
+    assert_eq!(settings["default_roles"][0], admin_role.id.get());
+
+    let foreign_response = register("new@example.com").await;
+    assert_eq!(foreign_response.status(), StatusCode::BAD_REQUEST);
+    let foreign_payload = response_json(foreign_response).await;
+    assert_eq!(foreign_payload["field_errors"][0]["field"], "email");
+
+    assert_eq!(
+        register("new@company.ru").await.status(),
+        StatusCode::CREATED
+    );
+    let user = repo
+        .get_user_by_email(
+            &UserEmail::new("new@company.ru").unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.roles.len(), 1);
+    assert_eq!(user.roles[0].id, admin_role.id);
+
+    // Only admins may read the settings.
+    let member = common::build_reqwest_client();
+    login_as(
+        &member,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let forbidden_response = member
+        .get(&settings_url)
+        .send()
+        .await
+        .expect("Failed to request the registration settings.");
+    assert_eq!(forbidden_response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn test_invitation_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let repo = DieselRepository::new(app.db_pool());
+    let admin_role = repo
+        .get_role_by_name("admin", HubId::new(seeded.hub_id).unwrap())
+        .unwrap()
+        .unwrap();
+    let role_id = admin_role.id.to_string();
+    let invite = || {
+        admin
+            .post(format!("{}/admin/user/invite", app.address()))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(mutation_form_body(&[
+                ("email", "invitee%40example.com"),
+                ("name", "Invitee"),
+                ("roles", &role_id),
+            ]))
+            .send()
+    };
+    let list_invitations = || async {
+        let response = admin
+            .get(format!("{}/api/v1/admin/invitations", app.address()))
+            .send()
+            .await
+            .expect("Failed to list invitations.");
+        assert_eq!(response.status(), StatusCode::OK);
+        response_json(response).await
+    };
+
+    // Admins invite an address once; existing users cannot be invited.
+    let invite_response = invite().await.expect("Failed to invite a user.");
+    assert_eq!(invite_response.status(), StatusCode::CREATED);
+    let duplicate_response = invite().await.expect("Failed to invite a user.");
+    assert_eq!(duplicate_response.status(), StatusCode::CONFLICT);
+    let existing_response = admin
+        .post(format!("{}/admin/user/invite", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", &common::USER_EMAIL.replace('@', "%40")),
+            ("name", "User"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to invite a user.");
+    assert_eq!(existing_response.status(), StatusCode::CONFLICT);
+
+    let invitations = list_invitations().await;
+    assert_eq!(invitations.as_array().unwrap().len(), 1);
+    assert_eq!(invitations[0]["email"], "invitee@example.com");
+    assert_eq!(invitations[0]["roles"][0], admin_role.id.get());
+    let invitation_id = invitations[0]["id"].as_i64().unwrap() as i32;
+
+    let resend_response = admin
+        .post(format!(
+            "{}/admin/invitation/resend/{invitation_id}",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to resend the invitation.");
+    assert_eq!(resend_response.status(), StatusCode::OK);
+
+    // Cancelled invitations disappear and can be sent again.
+    let cancel_url = format!("{}/admin/invitation/cancel/{invitation_id}", app.address());
+    let cancel_response = admin
+        .post(&cancel_url)
+        .send()
+        .await
+        .expect("Failed to cancel the invitation.");
+    assert_eq!(cancel_response.status(), StatusCode::OK);
+    assert!(list_invitations().await.as_array().unwrap().is_empty());
+    let missing_response = admin
+        .post(&cancel_url)
+        .send()
+        .await
+        .expect("Failed to cancel the invitation.");
+    assert_eq!(missing_response.status(), StatusCode::NOT_FOUND);
+
+    let invite_response = invite().await.expect("Failed to invite a user.");
+    assert_eq!(invite_response.status(), StatusCode::CREATED);
+    let invitations = list_invitations().await;
+    let invitation_id = invitations[0]["id"].as_i64().unwrap() as i32;
+    repo.renew_invitation(
+        InvitationId::new(invitation_id).unwrap(),
+        HubId::new(seeded.hub_id).unwrap(),
+        &hash_token("invite-token"),
+        Utc::now().naive_utc() + Duration::hours(1),
+    )
+    .expect("Failed to renew the invitation.");
+
+    // The invitee sets a password and lands in the hub with the invited roles.
+    let invitee = common::build_reqwest_client();
+    let page_response = invitee
+        .get(format!("{}/auth/invite?token=invite-token", app.address()))
+        .send()
+        .await
+        .expect("Failed to request the invitation page.");
+    assert_eq!(page_response.status(), StatusCode::OK);
+
+    let accept = |client: &reqwest::Client| {
+        client
+            .post(format!("{}/auth/invite", app.address()))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(mutation_form_body(&[
+                ("token", "invite-token"),
+                ("password", "invitee-password"),
+                ("confirm_password", "invitee-password"),
+            ]))
+            .send()
+    };
+    let accept_response = accept(&invitee)
+        .await
+        .expect("Failed to accept the invitation.");
+    assert_eq!(accept_response.status(), StatusCode::OK);
+    let accept_payload = response_json(accept_response).await;
+    assert_eq!(accept_payload["redirect_to"], "/");
+
+    let user = repo
+        .get_user_by_email(
+            &UserEmail::new("invitee@example.com").unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.user.name, Some(UserName::new("Invitee").unwrap()));
+    assert!(user.user.is_email_verified());
+    assert_eq!(user.roles.len(), 1);
+    assert_eq!(user.roles[0].id, admin_role.id);
+    assert!(list_invitations().await.as_array().unwrap().is_empty());
+
+    let replay_response = accept(&common::build_reqwest_client())
+        .await
+        .expect("Failed to replay the invitation.");
+    assert_eq!(replay_response.status(), StatusCode::UNAUTHORIZED);
+
+    login_as(
+        &common::build_reqwest_client(),
+        app.address(),
+        "invitee@example.com",
+        "invitee-password",
+        seeded.hub_id,
+    )
+    .await;
+
+    // Members may not see the invitations.
+    let member = common::build_reqwest_client();
+    login_as(
+        &member,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let forbidden_response = member
+        .get(format!("{}/api/v1/admin/invitations", app.address()))
+        .send()
+        .await
+        .expect("Failed to list invitations.");
+    assert_eq!(forbidden_response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn test_email_verification_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let policy_response = admin
+        .post(format!("{}/admin/hub/policy", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body("require_email_verification=true")
+        .send()
+        .await
+        .expect("Failed to update the hub policy.");
+    assert_eq!(policy_response.status(), StatusCode::OK);
+
+    // A new user registers and cannot sign in before verifying the address.
+    let client = common::build_reqwest_client();
+    let hub_id = seeded.hub_id.to_string();
+    let register_response = client
+        .post(format!("{}/auth/register", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", "new@example.com"),
+            ("password", "new-password"),
+            ("hub_id", &hub_id),
+        ]))
+        .send()
+        .await
+        .expect("Failed to register.");
+    assert_eq!(register_response.status(), StatusCode::CREATED);
+
+    assert_eq!(
+        login_status(
+            &client,
+            app.address(),
+            "new@example.com",
+            "new-password",
+            seeded.hub_id
+        )
+        .await,
+        StatusCode::FORBIDDEN
+    );
+
+    let resend_response = client
+        .post(format!("{}/auth/verify/resend", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", "new@example.com"),
+            ("hub_id", &hub_id),
+        ]))
+        .send()
+        .await
+        .expect("Failed to resend the verification link.");
+    assert_eq!(resend_response.status(), StatusCode::OK);
+
+    // Following the emailed link verifies the address.
+    let repo = DieselRepository::new(app.db_pool());
+    let user = repo
+        .get_user_by_email(
+            &UserEmail::new("new@example.com").unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .unwrap()
+        .unwrap()
+        .user;
+    repo.create_email_verification(&NewEmailVerification::new(
+        user.id,
+        user.hub_id,
+        hash_token("verification-token"),
+        Utc::now().naive_utc() + Duration::hours(1),
+    ))
+    .unwrap();
+    let verify_response = client
+        .get(format!(
+            "{}/auth/verify?token=verification-token",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to follow the verification link.");
+    assert_eq!(verify_response.url().path(), "/auth/signin");
+    assert_eq!(
+        login_status(
+            &client,
+            app.address(),
+            "new@example.com",
+            "new-password",
+            seeded.hub_id
+        )
+        .await,
+        StatusCode::OK
+    );
+
+    let resend_again = client
+        .post(format!("{}/auth/verify/resend", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", "new@example.com"),
+            ("hub_id", &hub_id),
+        ]))
+        .send()
+        .await
+        .expect("Failed to resend the verification link.");
+    assert_eq!(resend_again.status(), StatusCode::CONFLICT);
+
+    // Admins can verify seeded users by hand.
+    assert_eq!(
+        login_status(
+            &common::build_reqwest_client(),
+            app.address(),
+            common::USER_EMAIL,
+            common::USER_PASSWORD,
+            seeded.hub_id
+        )
+        .await,
+        StatusCode::FORBIDDEN
+    );
+    let verify_user_response = admin
+        .post(format!(
+            "{}/admin/user/verify/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to verify the user.");
+    assert_eq!(verify_user_response.status(), StatusCode::OK);
+    login_as(
+        &common::build_reqwest_client(),
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+}
+
+#[actix_web::test]
+async fn test_password_reset_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let repo = DieselRepository::new(app.db_pool());
+    let client = common::build_reqwest_client();
+    let old_session = common::build_reqwest_client();
+    login_as(
+        &old_session,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    repo.create_password_reset(&NewPasswordReset::new(
+        UserId::new(seeded.user_id).unwrap(),
+        HubId::new(seeded.hub_id).unwrap(),
+        hash_token("reset-token"),
+        Utc::now().naive_utc() + Duration::hours(1),
+    ))
+    .expect("Failed to create password reset.");
+
+    let page_response = client
+        .get(format!("{}/auth/reset?token=reset-token", app.address()))
+        .send()
+        .await
+        .expect("Failed to request the reset page.");
+    assert_eq!(page_response.status(), StatusCode::OK);
+
+    let mismatch_response = client
+        .post(format!("{}/auth/reset", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("token", "reset-token"),
+            ("password", "new-password"),
+            ("confirm_password", "other"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to submit mismatched reset form.");
+    assert_eq!(mismatch_response.status(), StatusCode::BAD_REQUEST);
+
+    let reset_response = client
+        .post(format!("{}/auth/reset", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("token", "reset-token"),
+            ("password", "new-password"),
+            ("confirm_password", "new-password"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to submit reset form.");
+    assert_eq!(reset_response.status(), StatusCode::OK);
+    let reset_payload = response_json(reset_response).await;
+    assert_eq!(reset_payload["redirect_to"], "/");
+
+    // Sessions started with the old password end with the reset.
+    let old_session_iam = old_session
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(old_session_iam.status(), StatusCode::UNAUTHORIZED);
+
+    let replay_response = client
+        .post(format!("{}/auth/reset", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("token", "reset-token"),
+            ("password", "another-password"),
+            ("confirm_password", "another-password"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to replay reset form.");
+    assert_eq!(replay_response.status(), StatusCode::UNAUTHORIZED);
+
+    let fresh_client = common::build_reqwest_client();
+    let old_password_response = fresh_client
+        .post(format!("{}/auth/login", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(login_form_body(
+            common::USER_EMAIL,
+            common::USER_PASSWORD,
+            seeded.hub_id,
+        ))
+        .send()
+        .await
+        .expect("Failed to submit login form.");
+    assert_eq!(old_password_response.status(), StatusCode::UNAUTHORIZED);
+
+    login_as(
+        &fresh_client,
+        app.address(),
+        common::USER_EMAIL,
+        "new-password",
+        seeded.hub_id,
+    )
+    .await;
+}
+
+#[actix_web::test]
+async fn test_admin_user_full_management_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let client = common::build_reqwest_client();
+    let repo = DieselRepository::new(app.db_pool());
+
+    login_as(
+        &client,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let signin_response = client
+        .get(format!("{}/auth/signin", app.address()))
+        .send()
+        .await
+        .expect("Failed to request sign-in page for authenticated admin.");
+
+    assert_eq!(signin_response.status(), StatusCode::OK);
+    assert_eq!(
+        signin_response.url().as_str(),
+        format!("{}/", app.address())
+    );
+    let signin_html = signin_response
+        .text()
+        .await
+        .expect("Redirected admin sign-in response should be readable.");
+    assert!(signin_html.contains("main-admin.tsx"));
+
+    let index_response = client
+        .get(format!("{}/", app.address()))
+        .send()
+        .await
+        .expect("Failed to request dashboard for authenticated admin.");
+
+    assert_eq!(index_response.status(), StatusCode::OK);
+    let index_html = index_response
+        .text()
+        .await
+        .expect("Admin dashboard response should be readable.");
+    assert!(index_html.contains("main-admin.tsx"));
+
+    let admin_dashboard_response = client
+        .get(format!("{}/api/v1/admin/dashboard", app.address()))
+        .send()
+        .await
+        .expect("Failed to request admin dashboard API.");
+
+    assert_eq!(admin_dashboard_response.status(), StatusCode::OK);
+
+    let add_role_response = client
+        .post(format!("{}/admin/role/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "editor")]))
+        .send()
+        .await
+        .expect("Failed to create role.");
+
+    assert_eq!(add_role_response.status(), StatusCode::CREATED);
+    let created_role = repo
+        .get_role_by_name("editor", HubId::new(seeded.hub_id).unwrap())
+        .expect("Role lookup should succeed.")
+        .expect("Role should exist after creation.");
+
+    let add_hub_response = client
+        .post(format!("{}/admin/hub/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "branch")]))
+        .send()
+        .await
+        .expect("Failed to create hub.");
+
+    assert_eq!(add_hub_response.status(), StatusCode::CREATED);
+    let created_hub = repo
+        .get_hub_by_name("branch")
+        .expect("Hub lookup should succeed.")
+        .expect("Hub should exist after creation.");
+
+    let add_menu_response = client
+        .post(format!("{}/admin/menu/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "docs"),
+            ("url", "https://docs.example.com"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to create menu item.");
+
+    assert_eq!(add_menu_response.status(), StatusCode::CREATED);
+    let created_menu = repo
+        .list_menu(HubId::new(seeded.hub_id).unwrap())
+        .expect("Menu lookup should succeed.")
+        .into_iter()
+        .find(|menu| menu.name.as_str() == "docs")
+        .expect("Menu item should exist after creation.");
+
+    let modal_response = client
+        .post(format!(
+            "{}/admin/user/modal/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to fetch admin user modal.");
+
+    assert_eq!(modal_response.status(), StatusCode::OK);
+    let modal_payload = response_json(modal_response).await;
+    assert_eq!(modal_payload["user"]["id"], seeded.user_id);
+    assert_eq!(modal_payload["user"]["email"], common::USER_EMAIL);
+    assert!(
+        modal_payload["roles"]
+            .as_array()
+            .expect("Roles should be present in modal response.")
+            .iter()
+            .any(|role| role["name"] == "editor")
+    );
+
+    let update_user_response = client
+        .post(format!(
+            "{}/admin/user/update/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "managed-user"),
+            ("password", "new-user-password"),
+            ("roles", &created_role.id.get().to_string()),
+        ]))
+        .send()
+        .await
+        .expect("Failed to update user as admin.");
+
+    assert_eq!(update_user_response.status(), StatusCode::OK);
+    let updated_user = repo
+        .get_user_by_id(
+            UserId::new(seeded.user_id).unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .expect("Updated user lookup should succeed.")
+        .expect("Updated user should still exist.");
+    assert_eq!(
+        updated_user.user.name.as_ref().map(|name| name.as_str()),
+        Some("managed-user")
+    );
+    assert_eq!(updated_user.roles.len(), 1);
+    assert_eq!(updated_user.roles[0].name.as_str(), "editor");
+    let relogin = repo
+        .login(
+            &UserEmail::new(common::USER_EMAIL).unwrap(),
+            &UserPassword::new("new-user-password").unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .expect("Updated user login lookup should succeed.");
+    assert!(relogin.is_some());
+
+    let delete_menu_response = client
+        .post(format!(
+            "{}/admin/menu/delete/{}",
+            app.address(),
+            created_menu.id.get()
+        ))
+        .send()
+        .await
+        .expect("Failed to delete menu item.");
+
+    assert_eq!(delete_menu_response.status(), StatusCode::OK);
+    assert!(
+        repo.get_menu_by_id(
+            MenuId::new(created_menu.id.get()).unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .expect("Deleted menu lookup should succeed.")
+        .is_none()
+    );
+
+    let delete_hub_response = client
+        .post(format!(
+            "{}/admin/hub/delete/{}",
+            app.address(),
+            created_hub.id.get()
+        ))
+        .send()
+        .await
+        .expect("Failed to delete hub.");
+
+    assert_eq!(delete_hub_response.status(), StatusCode::OK);
+    assert!(
+        repo.get_hub_by_name("branch")
+            .expect("Deleted hub lookup should succeed.")
+            .is_none()
+    );
+
+    let delete_role_response = client
+        .post(format!(
+            "{}/admin/role/delete/{}",
+            app.address(),
+            created_role.id.get()
+        ))
+        .send()
+        .await
+        .expect("Failed to delete role.");
+
+    assert_eq!(delete_role_response.status(), StatusCode::OK);
+    assert!(
+        repo.get_role_by_id(RoleId::new(created_role.id.get()).unwrap())
+            .expect("Deleted role lookup should succeed.")
+            .is_none()
+    );
+}
+
+#[actix_web::test]
+async fn test_non_admin_user_self_service_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let client = common::build_reqwest_client();
+    let repo = DieselRepository::new(app.db_pool());
+
+    login_as(
+        &client,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let signin_response = client
+        .get(format!("{}/auth/signin", app.address()))
+        .send()
+        .await
+        .expect("Failed to request sign-in page for authenticated user.");
 
     assert_eq!(signin_response.status(), StatusCode::OK);
     assert_eq!(
-        signin_response.url().as_str(),
-        format!("{}/", app.address())
+        signin_response.url().as_str(),
+        format!("{}/", app.address())
+    );
+    let signin_html = signin_response
+        .text()
+        .await
+        .expect("Redirected regular user sign-in response should be readable.");
+    assert!(signin_html.contains("main-basic.tsx"));
+
+    let index_response = client
+        .get(format!("{}/", app.address()))
+        .send()
+        .await
+        .expect("Failed to request dashboard for authenticated user.");
+
+    assert_eq!(index_response.status(), StatusCode::OK);
+    let index_html = index_response
+        .text()
+        .await
+        .expect("Basic dashboard response should be readable.");
+    assert!(index_html.contains("main-basic.tsx"));
+
+    let admin_dashboard_response = client
+        .get(format!("{}/api/v1/admin/dashboard", app.address()))
+        .send()
+        .await
+        .expect("Failed to request admin dashboard API as regular user.");
+
+    assert_eq!(admin_dashboard_response.status(), StatusCode::FORBIDDEN);
+
+    let save_user_response = client
+        .post(format!("{}/user/save", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "self-edited-user"),
+            ("password", "updated-self-password"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to update personal profile.");
+
+    assert_eq!(save_user_response.status(), StatusCode::OK);
+    let updated_user = repo
+        .get_user_by_id(
+            UserId::new(seeded.user_id).unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .expect("Updated self user lookup should succeed.")
+        .expect("Updated self user should still exist.");
+    assert_eq!(
+        updated_user.user.name.as_ref().map(|name| name.as_str()),
+        Some("self-edited-user")
+    );
+    let relogin = repo
+        .login(
+            &UserEmail::new(common::USER_EMAIL).unwrap(),
+            &UserPassword::new("updated-self-password").unwrap(),
+            HubId::new(seeded.hub_id).unwrap(),
+        )
+        .expect("Self-updated user login lookup should succeed.");
+    assert!(relogin.is_some());
+
+    let forbidden_role_response = client
+        .post(format!("{}/admin/role/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "forbidden-role")]))
+        .send()
+        .await
+        .expect("Failed to exercise forbidden admin mutation.");
+
+    assert_eq!(forbidden_role_response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn test_user_suspension_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let user = common::build_reqwest_client();
+    login_as(
+        &user,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let self_suspend_response = admin
+        .post(format!(
+            "{}/admin/user/suspend/{}",
+            app.address(),
+            seeded.admin_user_id
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("reason", "")]))
+        .send()
+        .await
+        .expect("Failed to submit suspension.");
+    assert_eq!(self_suspend_response.status(), StatusCode::FORBIDDEN);
+
+    let suspend_response = admin
+        .post(format!(
+            "{}/admin/user/suspend/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("reason", "Spam")]))
+        .send()
+        .await
+        .expect("Failed to submit suspension.");
+    assert_eq!(suspend_response.status(), StatusCode::OK);
+
+    // The open session of the suspended user stops working and new logins
+    // are refused.
+    let user_iam = user
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(user_iam.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        login_status(
+            &user,
+            app.address(),
+            common::USER_EMAIL,
+            common::USER_PASSWORD,
+            seeded.hub_id,
+        )
+        .await,
+        StatusCode::UNAUTHORIZED
     );
-    let signin_html = signin_response
-        .text()
+
+    let listed_emails = |status: &'static str| {
+        let admin = admin.clone();
+        let url = format!("{}/api/v1/users{status}", app.address());
+        async move {
+            let response = admin.get(url).send().await.expect("Failed to list users.");
+            assert_eq!(response.status(), StatusCode::OK);
+            response_json(response)
+                .await
+                .as_array()
+                .expect("users should be a list")
+                .iter()
+                .map(|user| user["email"].as_str().unwrap_or_default().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    assert!(
+        !listed_emails("")
+            .await
+            .contains(&common::USER_EMAIL.to_string())
+    );
+    assert_eq!(
+        listed_emails("?status=suspended").await,
+        vec![common::USER_EMAIL.to_string()]
+    );
+    assert!(
+        listed_emails("?status=all")
+            .await
+            .contains(&common::USER_EMAIL.to_string())
+    );
+
+    let modal_response = admin
+        .post(format!(
+            "{}/admin/user/modal/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to load user modal.");
+    let modal = response_json(modal_response).await;
+    assert!(modal["user"]["disabled_at"].is_string());
+    assert_eq!(modal["user"]["disabled_reason"], "Spam");
+
+    let reactivate_response = admin
+        .post(format!(
+            "{}/admin/user/reactivate/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to reactivate user.");
+    assert_eq!(reactivate_response.status(), StatusCode::OK);
+    login_as(
+        &user,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let forbidden_response = user
+        .get(format!("{}/api/v1/users?status=suspended", app.address()))
+        .send()
+        .await
+        .expect("Failed to list users.");
+    assert_eq!(forbidden_response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn test_deleted_users_can_be_restored_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let user = common::build_reqwest_client();
+    login_as(
+        &user,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let delete_response = admin
+        .post(format!(
+            "{}/admin/user/delete/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to delete user.");
+    assert_eq!(delete_response.status(), StatusCode::OK);
+
+    // The deleted user is signed out and cannot sign in again.
+    let user_iam = user
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(user_iam.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        login_status(
+            &user,
+            app.address(),
+            common::USER_EMAIL,
+            common::USER_PASSWORD,
+            seeded.hub_id,
+        )
+        .await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let deleted_response = admin
+        .get(format!("{}/api/v1/admin/deleted", app.address()))
+        .send()
+        .await
+        .expect("Failed to list deleted entities.");
+    assert_eq!(deleted_response.status(), StatusCode::OK);
+    let deleted = response_json(deleted_response).await;
+    assert_eq!(deleted["users"][0]["id"], seeded.user_id);
+    assert_eq!(deleted["users"][0]["email"], common::USER_EMAIL);
+    assert!(deleted["users"][0]["purge_at"].is_string());
+
+    let restore_response = admin
+        .post(format!(
+            "{}/admin/user/restore/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to restore user.");
+    assert_eq!(restore_response.status(), StatusCode::OK);
+    login_as(
+        &user,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let restore_again_response = admin
+        .post(format!(
+            "{}/admin/user/restore/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to restore user.");
+    assert_eq!(restore_again_response.status(), StatusCode::NOT_FOUND);
+
+    let forbidden_response = user
+        .get(format!("{}/api/v1/admin/deleted", app.address()))
+        .send()
+        .await
+        .expect("Failed to list deleted entities.");
+    assert_eq!(forbidden_response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn test_admin_created_user_must_change_password_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let add_response = admin
+        .post(format!("{}/admin/user/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", "new@example.com"),
+            ("name", "New"),
+            ("password", "temporary"),
+            ("must_change_password", "true"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to add user.");
+    assert_eq!(add_response.status(), StatusCode::CREATED);
+
+    let duplicate_response = admin
+        .post(format!("{}/admin/user/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", "new@example.com"),
+            ("name", "New"),
+            ("password", "temporary"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to add user.");
+    assert_eq!(duplicate_response.status(), StatusCode::CONFLICT);
+
+    let user = common::build_reqwest_client();
+    let login_response = user
+        .post(format!("{}/auth/login", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(login_form_body(
+            "new@example.com",
+            "temporary",
+            seeded.hub_id,
+        ))
+        .send()
+        .await
+        .expect("Failed to submit login form.");
+    assert_eq!(login_response.status(), StatusCode::OK);
+    let login_payload = response_json(login_response).await;
+    assert!(
+        login_payload["redirect_to"]
+            .as_str()
+            .is_some_and(|url| url.starts_with("/auth/password?"))
+    );
+
+    // The temporary password alone does not open a session.
+    let pending_response = user
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(pending_response.status(), StatusCode::UNAUTHORIZED);
+
+    let same_password_response = user
+        .post(format!("{}/auth/password", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("password", "temporary"),
+            ("confirm_password", "temporary"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to change password.");
+    assert_eq!(same_password_response.status(), StatusCode::BAD_REQUEST);
+
+    let change_response = user
+        .post(format!("{}/auth/password", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("password", "chosen"),
+            ("confirm_password", "chosen"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to change password.");
+    assert_eq!(change_response.status(), StatusCode::OK);
+    let change_payload = response_json(change_response).await;
+    assert_eq!(change_payload["redirect_to"], "/");
+
+    let iam_response = user
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(iam_response.status(), StatusCode::OK);
+
+    // The requirement is gone once the password was changed.
+    let other_client = common::build_reqwest_client();
+    login_as(
+        &other_client,
+        app.address(),
+        "new@example.com",
+        "chosen",
+        seeded.hub_id,
+    )
+    .await;
+}
+
+#[actix_web::test]
+async fn test_admin_audit_log_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let user = common::build_reqwest_client();
+    assert_eq!(
+        login_status(
+            &user,
+            app.address(),
+            common::USER_EMAIL,
+            "wrong-password",
+            seeded.hub_id,
+        )
+        .await,
+        StatusCode::UNAUTHORIZED
+    );
+    login_as(
+        &user,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let suspend_response = admin
+        .post(format!(
+            "{}/admin/user/suspend/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("reason", "Spam")]))
+        .send()
+        .await
+        .expect("Failed to submit suspension.");
+    assert_eq!(suspend_response.status(), StatusCode::OK);
+
+    let audit_response = admin
+        .get(format!("{}/api/v1/admin/audit", app.address()))
+        .send()
+        .await
+        .expect("Failed to list audit events.");
+    assert_eq!(audit_response.status(), StatusCode::OK);
+    let audit = response_json(audit_response).await;
+    let event_types = audit["events"]
+        .as_array()
+        .expect("events should be a list")
+        .iter()
+        .map(|event| event["event_type"].as_str().unwrap_or_default())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        event_types,
+        vec![
+            "user_suspended",
+            "login_succeeded",
+            "login_succeeded",
+            "login_failed"
+        ]
+    );
+    assert_eq!(audit["total"], 4);
+    assert_eq!(audit["events"][0]["actor_id"], seeded.admin_user_id);
+    assert_eq!(audit["events"][0]["target_id"], seeded.user_id);
+
+    let failed_response = admin
+        .get(format!(
+            "{}/api/v1/admin/audit?event_type=login_failed",
+            app.address()
+        ))
+        .send()
+        .await
+        .expect("Failed to list audit events.");
+    let failed = response_json(failed_response).await;
+    assert_eq!(failed["total"], 1);
+    assert_eq!(failed["events"][0]["detail"], common::USER_EMAIL);
+
+    let reactivate_response = admin
+        .post(format!(
+            "{}/admin/user/reactivate/{}",
+            app.address(),
+            seeded.user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to reactivate user.");
+    assert_eq!(reactivate_response.status(), StatusCode::OK);
+    login_as(
+        &user,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let forbidden_response = user
+        .get(format!("{}/api/v1/admin/audit", app.address()))
+        .send()
+        .await
+        .expect("Failed to list audit events.");
+    assert_eq!(forbidden_response.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_web::test]
+async fn test_role_permissions_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let repo = DieselRepository::new(app.db_pool());
+    let admin_client = common::build_reqwest_client();
+    login_as(
+        &admin_client,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let iam_response = admin_client
+        .get(format!("{}/api/v1/iam", app.address()))
+        .send()
+        .await
+        .expect("Failed to request shell data.");
+    assert_eq!(iam_response.status(), StatusCode::OK);
+    let shell = response_json(iam_response).await;
+    assert_eq!(
+        shell["permissions"],
+        serde_json::json!([
+            "users.read",
+            "users.write",
+            "roles.manage",
+            "menu.manage",
+            "hubs.manage"
+        ])
+    );
+
+    let add_role_response = admin_client
+        .post(format!("{}/admin/role/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "menu-editor"),
+            ("permissions", "menu.manage"),
+            ("permissions", "users.read"),
+        ]))
+        .send()
         .await
-        .expect("Redirected regular user sign-in response should be readable.");
-    assert!(signin_html.contains("main-basic.tsx"));
+        .expect("Failed to create role.");
+    assert_eq!(add_role_response.status(), StatusCode::CREATED);
+    let role = repo
+        .get_role_by_name("menu-editor", HubId::new(seeded.hub_id).unwrap())
+        .expect("Role lookup should succeed.")
+        .expect("Role should exist after creation.");
 
-    let index_response = client
-        .get(format!("{}/", app.address()))
+    let user_client = common::build_reqwest_client();
+    login_as(
+        &user_client,
+        app.address(),
+        common::USER_EMAIL,
+        common::USER_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+    let shell = response_json(
+        user_client
+            .get(format!("{}/api/v1/iam", app.address()))
+            .send()
+            .await
+            .expect("Failed to request shell data."),
+    )
+    .await;
+    assert_eq!(shell["permissions"], serde_json::json!([]));
+
+    repo.update_user(
+        UserId::new(seeded.user_id).unwrap(),
+        HubId::new(seeded.hub_id).unwrap(),
+        &UpdateUser::new(UserName::new("Editor").unwrap(), None, Some(vec![role.id])),
+    )
+    .expect("Failed to assign the role.");
+
+    // The grant applies to the existing session without a new login.
+    let add_menu_response = user_client
+        .post(format!("{}/admin/menu/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "docs"),
+            ("url", "https://docs.example.com"),
+        ]))
         .send()
         .await
-        .expect("Failed to request dashboard for authenticated user.");
+        .expect("Failed to create menu item.");
+    assert_eq!(add_menu_response.status(), StatusCode::CREATED);
 
-    assert_eq!(index_response.status(), StatusCode::OK);
-    let index_html = index_response
-        .text()
+    let audit_response = user_client
+        .get(format!("{}/api/v1/admin/audit", app.address()))
+        .send()
         .await
-        .expect("Basic dashboard response should be readable.");
-    assert!(index_html.contains("main-basic.tsx"));
+        .expect("Failed to request audit log.");
+    assert_eq!(audit_response.status(), StatusCode::OK);
 
-    let admin_dashboard_response = client
-        .get(format!("{}/api/v1/admin/dashboard", app.address()))
+    let invite_response = user_client
+        .post(format!("{}/admin/user/invite", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("email", "invitee@example.com"),
+            ("name", "Invitee"),
+        ]))
         .send()
         .await
-        .expect("Failed to request admin dashboard API as regular user.");
+        .expect("Failed to exercise forbidden invitation.");
+    assert_eq!(invite_response.status(), StatusCode::FORBIDDEN);
 
-    assert_eq!(admin_dashboard_response.status(), StatusCode::FORBIDDEN);
+    let delete_response = user_client
+        .post(format!(
+            "{}/admin/user/delete/{}",
+            app.address(),
+            seeded.admin_user_id
+        ))
+        .send()
+        .await
+        .expect("Failed to exercise forbidden admin mutation.");
+    assert_eq!(delete_response.status(), StatusCode::FORBIDDEN);
+    assert!(
+        repo.get_user_by_id(
+            UserId::new(seeded.admin_user_id).unwrap(),
+            HubId::new(seeded.hub_id).unwrap()
+        )
+        .unwrap()
+        .is_some()
+    );
+}
 
-    let save_user_response = client
-        .post(format!("{}/user/save", app.address()))
+#[actix_web::test]
+async fn test_hub_roles_stay_in_their_hub_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let repo = DieselRepository::new(app.db_pool());
+    let hub_id = HubId::new(seeded.hub_id).unwrap();
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let add_role_response = admin
+        .post(format!("{}/admin/role/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "cashier")]))
+        .send()
+        .await
+        .expect("Failed to create role.");
+    assert_eq!(add_role_response.status(), StatusCode::CREATED);
+    let role = repo
+        .get_role_by_name("cashier", hub_id)
+        .expect("Role lookup should succeed.")
+        .expect("Role should exist after creation.");
+    assert_eq!(role.hub_id, Some(hub_id));
+
+    // A hub role may not take the name of a system role.
+    let shadow_response = admin
+        .post(format!("{}/admin/role/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "admin")]))
+        .send()
+        .await
+        .expect("Failed to exercise conflicting role name.");
+    assert_eq!(shadow_response.status(), StatusCode::CONFLICT);
+
+    // Another hub with its own admin sees only the system roles.
+    let branch = repo
+        .create_hub(&NewHub::new(HubName::new("branch").unwrap()))
+        .expect("Failed to create hub.");
+    let admin_role = repo.get_role_by_name("admin", branch.id).unwrap().unwrap();
+    repo.create_user(
+        &NewUser::new(
+            UserEmail::new("admin@branch").unwrap(),
+            Some(UserName::new("Branch Admin").unwrap()),
+            branch.id,
+            UserPassword::new("password").unwrap(),
+        )
+        .with_roles(vec![admin_role.id]),
+    )
+    .expect("Failed to create branch admin.");
+    let branch_admin = common::build_reqwest_client();
+    login_as(
+        &branch_admin,
+        app.address(),
+        "admin@branch",
+        "password",
+        branch.id.get(),
+    )
+    .await;
+
+    let dashboard = response_json(
+        branch_admin
+            .get(format!("{}/api/v1/admin/dashboard", app.address()))
+            .send()
+            .await
+            .expect("Failed to request admin dashboard."),
+    )
+    .await;
+    let names = dashboard["roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|role| role["name"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["admin".to_string()]);
+    assert_eq!(dashboard["roles"][0]["system"], true);
+
+    let delete_response = branch_admin
+        .post(format!(
+            "{}/admin/role/delete/{}",
+            app.address(),
+            role.id.get()
+        ))
+        .send()
+        .await
+        .expect("Failed to exercise cross-hub role deletion.");
+    assert!(!delete_response.status().is_success());
+    assert!(repo.get_role_by_id(role.id).unwrap().is_some());
+
+    // Nor can it assign the role to its users.
+    let invite_response = branch_admin
+        .post(format!("{}/admin/user/invite", app.address()))
         .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
         .body(mutation_form_body(&[
-            ("name", "self-edited-user"),
-            ("password", "updated-self-password"),
+            ("email", "cashier@branch"),
+            ("name", "Cashier"),
+            ("roles", &role.id.get().to_string()),
         ]))
         .send()
         .await
-        .expect("Failed to update personal profile.");
+        .expect("Failed to exercise cross-hub role assignment.");
+    assert_eq!(invite_response.status(), StatusCode::BAD_REQUEST);
+}
 
-    assert_eq!(save_user_response.status(), StatusCode::OK);
-    let updated_user = repo
-        .get_user_by_id(
-            UserId::new(seeded.user_id).unwrap(),
-            HubId::new(seeded.hub_id).unwrap(),
+#[actix_web::test]
+async fn test_role_editing_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let repo = DieselRepository::new(app.db_pool());
+    let hub_id = HubId::new(seeded.hub_id).unwrap();
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let add_role_response = admin
+        .post(format!("{}/admin/role/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "cashier"),
+            ("description", "Takes payments"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to create role.");
+    assert_eq!(add_role_response.status(), StatusCode::CREATED);
+    let role = repo
+        .get_role_by_name("cashier", hub_id)
+        .expect("Role lookup should succeed.")
+        .expect("Role should exist after creation.");
+    repo.create_user(
+        &NewUser::new(
+            UserEmail::new("cashier@hub").unwrap(),
+            Some(UserName::new("Cashier").unwrap()),
+            hub_id,
+            UserPassword::new("password").unwrap(),
         )
-        .expect("Updated self user lookup should succeed.")
-        .expect("Updated self user should still exist.");
+        .with_roles(vec![role.id]),
+    )
+    .expect("Failed to create cashier.");
+
+    // The dashboard shows how many users hold each role.
+    let dashboard = response_json(
+        admin
+            .get(format!("{}/api/v1/admin/dashboard", app.address()))
+            .send()
+            .await
+            .expect("Failed to request admin dashboard."),
+    )
+    .await;
+    let cashier = dashboard["roles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["id"] == role.id.get())
+        .expect("Dashboard should list the role.");
+    assert_eq!(cashier["description"], "Takes payments");
+    assert_eq!(cashier["user_count"], 1);
+
+    let members = response_json(
+        admin
+            .get(format!(
+                "{}/api/v1/admin/roles/{}/members",
+                app.address(),
+                role.id.get()
+            ))
+            .send()
+            .await
+            .expect("Failed to request role members."),
+    )
+    .await;
+    let emails = members
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|member| member["email"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(emails, vec!["cashier@hub".to_string()]);
+
+    let missing_response = admin
+        .get(format!("{}/api/v1/admin/roles/9999/members", app.address()))
+        .send()
+        .await
+        .expect("Failed to request members of a missing role.");
+    assert_eq!(missing_response.status(), StatusCode::NOT_FOUND);
+
+    let update_response = admin
+        .post(format!(
+            "{}/admin/role/update/{}",
+            app.address(),
+            role.id.get()
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[
+            ("name", "clerk"),
+            ("description", "Takes payments and refunds"),
+        ]))
+        .send()
+        .await
+        .expect("Failed to update role.");
+    assert_eq!(update_response.status(), StatusCode::OK);
+    let updated = repo.get_role_by_id(role.id).unwrap().unwrap();
+    assert_eq!(updated.name.as_str(), "clerk");
+    assert_eq!(updated.description, "Takes payments and refunds");
+
+    // System roles keep their names.
+    let admin_role = repo.get_role_by_name("admin", hub_id).unwrap().unwrap();
+    let rename_response = admin
+        .post(format!(
+            "{}/admin/role/update/{}",
+            app.address(),
+            admin_role.id.get()
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "owner")]))
+        .send()
+        .await
+        .expect("Failed to exercise system role rename.");
+    assert_eq!(rename_response.status(), StatusCode::FORBIDDEN);
+    assert!(repo.get_role_by_name("admin", hub_id).unwrap().is_some());
+}
+
+#[actix_web::test]
+async fn test_bulk_role_assignment_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let repo = DieselRepository::new(app.db_pool());
+    let hub_id = HubId::new(seeded.hub_id).unwrap();
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    let add_role_response = admin
+        .post(format!("{}/admin/role/add", app.address()))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[("name", "reviewer")]))
+        .send()
+        .await
+        .expect("Failed to create role.");
+    assert_eq!(add_role_response.status(), StatusCode::CREATED);
+    let role = repo.get_role_by_name("reviewer", hub_id).unwrap().unwrap();
+    let user_id = UserId::new(seeded.user_id).unwrap();
+    let roles_before = repo.get_roles(user_id).unwrap().len();
+
+    let assign = response_json(
+        admin
+            .post(format!(
+                "{}/admin/role/assign/{}",
+                app.address(),
+                role.id.get()
+            ))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(mutation_form_body(&[
+                ("user_ids", &seeded.user_id.to_string()),
+                ("user_ids", "9999"),
+            ]))
+            .send()
+            .await
+            .expect("Failed to assign role."),
+    )
+    .await;
+    assert_eq!(assign["changed"], 1);
+    assert_eq!(assign["not_found"], serde_json::json!([9999]));
+    // The new role is added next to the roles the user already had.
+    assert_eq!(repo.get_roles(user_id).unwrap().len(), roles_before + 1);
+
+    // Nothing is selected without ids or an explicit filter.
+    let empty_response = admin
+        .post(format!(
+            "{}/admin/role/assign/{}",
+            app.address(),
+            role.id.get()
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[]))
+        .send()
+        .await
+        .expect("Failed to exercise empty selection.");
+    assert_eq!(empty_response.status(), StatusCode::BAD_REQUEST);
+
+    let unassign = response_json(
+        admin
+            .post(format!(
+                "{}/admin/role/unassign/{}",
+                app.address(),
+                role.id.get()
+            ))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(mutation_form_body(&[
+                ("all_matching", "true"),
+                ("role", "reviewer"),
+            ]))
+            .send()
+            .await
+            .expect("Failed to remove role."),
+    )
+    .await;
+    assert_eq!(unassign["changed"], 1);
+    assert_eq!(repo.get_roles(user_id).unwrap().len(), roles_before);
+}
+
+#[actix_web::test]
+async fn test_role_inheritance_story() {
+    let app = common::spawn_app().await;
+    let seeded = common::setup_hub_with_users(app.db_pool());
+    let repo = DieselRepository::new(app.db_pool());
+    let hub_id = HubId::new(seeded.hub_id).unwrap();
+    let admin = common::build_reqwest_client();
+    login_as(
+        &admin,
+        app.address(),
+        common::ADMIN_EMAIL,
+        common::ADMIN_PASSWORD,
+        seeded.hub_id,
+    )
+    .await;
+
+    for name in ["viewer", "manager"] {
+        let response = admin
+            .post(format!("{}/admin/role/add", app.address()))
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(mutation_form_body(&[("name", name)]))
+            .send()
+            .await
+            .expect("Failed to create role.");
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let viewer = repo.get_role_by_name("viewer", hub_id).unwrap().unwrap();
+    let manager = repo.get_role_by_name("manager", hub_id).unwrap().unwrap();
+
+    let parents_response = admin
+        .post(format!(
+            "{}/admin/role/parents/{}",
+            app.address(),
+            manager.id.get()
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[(
+            "parents",
+            viewer.id.get().to_string().as_str(),
+        )]))
+        .send()
+        .await
+        .expect("Failed to set role parents.");
+    assert_eq!(parents_response.status(), StatusCode::OK);
+
+    // The reverse link would close a loop.
+    let cycle_response = admin
+        .post(format!(
+            "{}/admin/role/parents/{}",
+            app.address(),
+            viewer.id.get()
+        ))
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(mutation_form_body(&[(
+            "parents",
+            manager.id.get().to_string().as_str(),
+        )]))
+        .send()
+        .await
+        .expect("Failed to exercise role cycle.");
+    assert_eq!(cycle_response.status(), StatusCode::BAD_REQUEST);
+
+    let dashboard = response_json(
+        admin
+            .get(format!("{}/api/v1/admin/dashboard", app.address()))
+            .send()
+            .await
+            .expect("Failed to load dashboard."),
+    )
+    .await;
+    let manager_item = dashboard["roles"]
+        .as_array()
+        .expect("roles should be a list")
+        .iter()
+        .find(|role| role["name"] == "manager")
+        .expect("manager should be listed")
+        .clone();
     assert_eq!(
-        updated_user.user.name.as_ref().map(|name| name.as_str()),
-        Some("self-edited-user")
+        manager_item["parents"],
+        serde_json::json!([viewer.id.get()])
+    );
+    assert_eq!(
+        manager_item["inherited"],
+        serde_json::json!([viewer.id.get()])
     );
-    let relogin = repo
-        .login(
-            &UserEmail::new(common::USER_EMAIL).unwrap(),
-            &UserPassword::new("updated-self-password").unwrap(),
-            HubId::new(seeded.hub_id).unwrap(),
-        )
-        .expect("Self-updated user login lookup should succeed.");
-    assert!(relogin.is_some());
 
-    let forbidden_role_response = client
-        .post(format!("{}/admin/role/add", app.address()))
+    let assign_response = admin
+        .post(format!(
+            "{}/admin/role/assign/{}",
+            app.address(),
+            manager.id.get()
+        ))
         .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .body(mutation_form_body(&[("name", "forbidden-role")]))
+        .body(mutation_form_body(&[(
+            "user_ids",
+            seeded.user_id.to_string().as_str(),
+        )]))
         .send()
         .await
-        .expect("Failed to exercise forbidden admin mutation.");
+        .expect("Failed to assign role.");
+    assert_eq!(assign_response.status(), StatusCode::OK);
 
-    assert_eq!(forbidden_role_response.status(), StatusCode::FORBIDDEN);
+    // The modal keeps direct and inherited roles apart.
+    let modal = response_json(
+        admin
+            .post(format!(
+                "{}/admin/user/modal/{}",
+                app.address(),
+                seeded.user_id
+            ))
+            .send()
+            .await
+            .expect("Failed to fetch admin user modal."),
+    )
+    .await;
+    let direct = modal["user"]["roles"]
+        .as_array()
+        .expect("roles should be a list");
+    assert!(direct.contains(&serde_json::json!(manager.id.get())));
+    assert!(!direct.contains(&serde_json::json!(viewer.id.get())));
+    assert_eq!(
+        modal["user"]["inherited_roles"],
+        serde_json::json!([viewer.id.get()])
+    );
+
+    // Everywhere else the user simply holds both roles.
+    let users = response_json(
+        admin
+            .get(format!("{}/api/v1/users", app.address()))
+            .send()
+            .await
+            .expect("Failed to list users."),
+    )
+    .await;
+    let user = users
+        .as_array()
+        .expect("users should be a list")
+        .iter()
+        .find(|user| user["email"] == common::USER_EMAIL)
+        .expect("seeded user should be listed")
+        .clone();
+    let roles = user["roles"].as_array().expect("roles should be a list");
+    assert!(roles.contains(&serde_json::json!("manager")));
+    assert!(roles.contains(&serde_json::json!("viewer")));
 }
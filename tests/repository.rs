@@ -1,17 +1,47 @@
-use pushkind_auth::domain::hub::NewHub;
+use chrono::{Duration, Utc};
+use diesel::RunQueryDsl;
+use pushkind_auth::domain::audit::{AuditEventType, NewAuditEvent};
+use pushkind_auth::domain::email_verification::NewEmailVerification;
+use pushkind_auth::domain::hub::{HubPolicy, HubRegistrationSettings, NewHub, RegistrationMode};
+use pushkind_auth::domain::invitation::NewInvitation;
+use pushkind_auth::domain::login_throttle::LoginThrottlePolicy;
 use pushkind_auth::domain::menu::NewMenu;
-use pushkind_auth::domain::role::NewRole;
+use pushkind_auth::domain::oauth::{NewAccessToken, NewAuthorizationCode, NewOAuthClient};
+use pushkind_auth::domain::password_reset::NewPasswordReset;
+use pushkind_auth::domain::permission::Permission;
+use pushkind_auth::domain::personal_token::{NewPersonalAccessToken, TokenScope};
+use pushkind_auth::domain::role::{NewRole, Role, RoleChange, UpdateRole};
+use pushkind_auth::domain::service_account::{
+    NewServiceAccount, NewServiceAccountToken, UpdateServiceAccount,
+};
+use pushkind_auth::domain::session::{ClientInfo, NewSession};
 use pushkind_auth::domain::types::{
-    HubName, MenuName, MenuUrl, RoleName, UserEmail, UserName, UserPassword,
+    HubName, MenuName, MenuUrl, RoleName, UserEmail, UserId, UserName, UserPassword,
 };
 use pushkind_auth::domain::user::NewUser;
 use pushkind_auth::domain::user::UpdateUser;
+use pushkind_auth::domain::user_credential::NewUserCredential;
 use pushkind_auth::repository::DieselRepository;
-use pushkind_auth::repository::UserListQuery;
+use pushkind_auth::repository::EmailVerificationWriter;
+use pushkind_auth::repository::PasswordResetWriter;
+use pushkind_auth::repository::PermissionReader;
+use pushkind_auth::repository::{AuditEventQuery, AuditReader, AuditWriter};
+use pushkind_auth::repository::{CredentialReader, CredentialWriter};
 use pushkind_auth::repository::{HubReader, HubWriter};
+use pushkind_auth::repository::{InvitationReader, InvitationWriter};
+use pushkind_auth::repository::{LoginThrottleReader, LoginThrottleWriter};
 use pushkind_auth::repository::{MenuReader, MenuWriter};
+use pushkind_auth::repository::{
+    OAuthClientReader, OAuthClientWriter, OAuthTokenReader, OAuthTokenWriter,
+};
+use pushkind_auth::repository::{PersonalTokenReader, PersonalTokenWriter};
 use pushkind_auth::repository::{RoleReader, RoleWriter};
+use pushkind_auth::repository::{ServiceAccountReader, ServiceAccountWriter};
+use pushkind_auth::repository::{SessionReader, SessionWriter};
+use pushkind_auth::repository::{TwoFactorReader, TwoFactorWriter};
+use pushkind_auth::repository::{UserListQuery, UserSelection, UserStatusFilter};
 use pushkind_auth::repository::{UserReader, UserWriter};
+use pushkind_common::repository::errors::RepositoryError;
 
 mod common;
 
@@ -47,10 +77,11 @@ fn test_hub_repository_crud() {
     menu_repo.create_menu(&new_menu).unwrap();
     assert_eq!(menu_repo.list_menu(hub.id).unwrap().len(), 1);
 
-    repo.delete_hub(hub.id).unwrap();
+    repo.delete_hub(hub.id, Utc::now().naive_utc()).unwrap();
 
-    // menus should be removed when hub is deleted
+    // menus should be hidden when hub is deleted
     assert!(menu_repo.list_menu(hub.id).unwrap().is_empty());
+    assert!(repo.get_hub_by_id(hub.id).unwrap().is_none());
 }
 
 #[test]
@@ -125,13 +156,17 @@ fn test_user_repository_crud() {
 
     assert!(user_roles.iter().any(|r| r.name == role.name));
 
-    user_repo.delete_user(user.id).unwrap();
+    user_repo
+        .delete_user(user.id, Utc::now().naive_utc())
+        .unwrap();
+    assert!(user_repo.get_user_by_id(user.id, hub.id).unwrap().is_none());
 }
 
 #[test]
 fn test_role_repository_crud() {
     let test_db = common::TestDb::new();
     let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
 
     // Create
     let new_role = NewRole::new(RoleName::new("TestRole").unwrap());
@@ -143,14 +178,321 @@ fn test_role_repository_crud() {
     assert!(found.is_some());
 
     // Get by name
-    let found = repo.get_role_by_name("TestRole").unwrap();
+    let found = repo.get_role_by_name("TestRole", hub.id).unwrap();
     assert!(found.is_some());
 
     // List
-    let roles = repo.list_roles().unwrap();
+    let roles = repo.list_roles(hub.id).unwrap();
     assert_eq!(roles.len(), 2); // admin and TestRole
 
-    repo.delete_role(role.id).unwrap();
+    repo.delete_role(role.id, hub.id).unwrap();
+}
+
+#[test]
+fn test_hub_roles_are_scoped_to_their_hub() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("other").unwrap()))
+        .unwrap();
+
+    let staff = repo
+        .create_role(&NewRole::new(RoleName::new("staff").unwrap()).with_hub(hub.id))
+        .unwrap();
+    assert_eq!(staff.hub_id, Some(hub.id));
+    // Names are unique per hub, so another hub may reuse them.
+    assert!(
+        repo.create_role(&NewRole::new(RoleName::new("staff").unwrap()).with_hub(hub.id))
+            .is_err()
+    );
+    let other_staff = repo
+        .create_role(&NewRole::new(RoleName::new("staff").unwrap()).with_hub(other_hub.id))
+        .unwrap();
+
+    // Every hub sees the system roles followed by its own roles.
+    let ids = |roles: Vec<Role>| {
+        roles
+            .into_iter()
+            .map(|role| (role.id, role.hub_id))
+            .collect::<Vec<_>>()
+    };
+    let admin = repo.get_role_by_name("admin", hub.id).unwrap().unwrap();
+    assert_eq!(admin.hub_id, None);
+    assert_eq!(
+        ids(repo.list_roles(hub.id).unwrap()),
+        vec![(admin.id, None), (staff.id, Some(hub.id))]
+    );
+    assert_eq!(
+        ids(repo.list_roles(other_hub.id).unwrap()),
+        vec![(admin.id, None), (other_staff.id, Some(other_hub.id))]
+    );
+    assert_eq!(
+        repo.get_role_by_name("staff", other_hub.id)
+            .unwrap()
+            .map(|role| role.id),
+        Some(other_staff.id)
+    );
+
+    // A hub cannot delete the roles of another hub.
+    assert!(repo.delete_role(staff.id, other_hub.id).is_err());
+    repo.delete_role(staff.id, hub.id).unwrap();
+    assert!(repo.get_role_by_name("staff", hub.id).unwrap().is_none());
+    assert!(repo.get_role_by_id(other_staff.id).unwrap().is_some());
+
+    // Purging a hub removes its roles.
+    let now = Utc::now().naive_utc();
+    repo.delete_hub(other_hub.id, now).unwrap();
+    repo.purge_deleted_hubs(now + Duration::seconds(1)).unwrap();
+    assert!(repo.get_role_by_id(other_staff.id).unwrap().is_none());
+}
+
+#[test]
+fn test_role_update_and_member_counts() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo
+        .create_hub(&NewHub::new(HubName::new("roles").unwrap()))
+        .unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("other").unwrap()))
+        .unwrap();
+    let staff = repo
+        .create_role(
+            &NewRole::new(RoleName::new("staff").unwrap())
+                .with_hub(hub.id)
+                .with_description("Front desk"),
+        )
+        .unwrap();
+    assert_eq!(staff.description, "Front desk");
+
+    // Rename and describe; `updated_at` moves with the change.
+    let updated = repo
+        .update_role(
+            staff.id,
+            hub.id,
+            &UpdateRole::new(RoleName::new("support").unwrap(), "Answers customers"),
+        )
+        .unwrap();
+    assert_eq!(updated.name.as_str(), "support");
+    assert_eq!(updated.description, "Answers customers");
+    assert!(updated.updated_at >= staff.updated_at);
+    assert_eq!(
+        repo.get_role_by_id(staff.id).unwrap().unwrap().description,
+        "Answers customers"
+    );
+    // Another hub cannot change the role.
+    assert!(
+        repo.update_role(
+            staff.id,
+            other_hub.id,
+            &UpdateRole::new(RoleName::new("taken").unwrap(), "")
+        )
+        .is_err()
+    );
+
+    // Suspended users count, deleted ones do not.
+    let mut users = Vec::new();
+    for email in ["one@roles.test", "two@roles.test", "three@roles.test"] {
+        let user = repo
+            .create_user(
+                &NewUser::new(
+                    UserEmail::new(email).unwrap(),
+                    None,
+                    hub.id,
+                    UserPassword::new("pwd").unwrap(),
+                )
+                .with_roles(vec![staff.id]),
+            )
+            .unwrap();
+        users.push(user);
+    }
+    let now = Utc::now().naive_utc();
+    repo.suspend_user(users[1].id, hub.id, None, now).unwrap();
+    repo.delete_user(users[2].id, now).unwrap();
+    assert_eq!(
+        repo.count_role_members(hub.id).unwrap(),
+        vec![(staff.id, 2)]
+    );
+    assert!(repo.count_role_members(other_hub.id).unwrap().is_empty());
+}
+
+#[test]
+fn test_role_changes_for_many_users() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo
+        .create_hub(&NewHub::new(HubName::new("bulk").unwrap()))
+        .unwrap();
+    let viewer = repo
+        .create_role(&NewRole::new(RoleName::new("viewer").unwrap()).with_hub(hub.id))
+        .unwrap();
+    let editor = repo
+        .create_role(&NewRole::new(RoleName::new("editor").unwrap()).with_hub(hub.id))
+        .unwrap();
+    let mut users = Vec::new();
+    for (email, roles) in [
+        ("one@bulk.test", vec![viewer.id]),
+        ("two@bulk.test", vec![viewer.id, editor.id]),
+        ("three@bulk.test", vec![]),
+    ] {
+        let user = repo
+            .create_user(
+                &NewUser::new(
+                    UserEmail::new(email).unwrap(),
+                    None,
+                    hub.id,
+                    UserPassword::new("pwd").unwrap(),
+                )
+                .with_roles(roles),
+            )
+            .unwrap();
+        users.push(user);
+    }
+    let unknown = UserId::new(9999).unwrap();
+    let audit = NewAuditEvent::new(hub.id, AuditEventType::UserUpdated, &ClientInfo::default());
+
+    // Listed ids: holders stay as they are and unknown ids are reported.
+    let summary = repo
+        .change_role_for_users(
+            editor.id,
+            hub.id,
+            RoleChange::Add,
+            &UserSelection::Ids(vec![users[0].id, users[1].id, unknown]),
+            &audit,
+        )
+        .unwrap();
+    assert_eq!(summary.changed, vec![users[0].id]);
+    assert_eq!(summary.unchanged, 1);
+    assert_eq!(summary.not_found, vec![unknown]);
+    let roles = repo.get_roles(users[0].id).unwrap();
+    assert_eq!(roles.len(), 2);
+
+    // A filter selects every matching user; other roles are kept.
+    let summary = repo
+        .change_role_for_users(
+            editor.id,
+            hub.id,
+            RoleChange::Remove,
+            &UserSelection::Matching(UserListQuery::new(hub.id).role("viewer")),
+            &audit,
+        )
+        .unwrap();
+    assert_eq!(summary.changed, vec![users[0].id, users[1].id]);
+    assert_eq!(summary.unchanged, 0);
+    for user in &users[..2] {
+        let roles = repo.get_roles(user.id).unwrap();
+        assert_eq!(
+            roles.into_iter().map(|role| role.id).collect::<Vec<_>>(),
+            vec![viewer.id]
+        );
+    }
+    assert!(repo.get_roles(users[2].id).unwrap().is_empty());
+
+    // Every change was audited with the changed user as the target.
+    let (total, events) = repo
+        .list_audit_events(AuditEventQuery::new(hub.id))
+        .unwrap();
+    assert_eq!(total, 3);
+    let mut targets = events
+        .iter()
+        .filter_map(|event| event.target_id)
+        .collect::<Vec<_>>();
+    targets.sort();
+    assert_eq!(
+        targets,
+        vec![users[0].id.get(), users[0].id.get(), users[1].id.get()]
+    );
+}
+
+#[test]
+fn test_role_parents_grant_inherited_roles() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo
+        .create_hub(&NewHub::new(HubName::new("tree").unwrap()))
+        .unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("other").unwrap()))
+        .unwrap();
+    let viewer = repo
+        .create_role(
+            &NewRole::new(RoleName::new("viewer").unwrap())
+                .with_hub(hub.id)
+                .with_permissions(vec![Permission::UsersRead]),
+        )
+        .unwrap();
+    let editor = repo
+        .create_role(
+            &NewRole::new(RoleName::new("editor").unwrap())
+                .with_hub(hub.id)
+                .with_permissions(vec![Permission::MenuManage]),
+        )
+        .unwrap();
+    let manager = repo
+        .create_role(&NewRole::new(RoleName::new("manager").unwrap()).with_hub(hub.id))
+        .unwrap();
+    repo.set_role_parents(editor.id, hub.id, &[viewer.id])
+        .unwrap();
+    repo.set_role_parents(manager.id, hub.id, &[editor.id])
+        .unwrap();
+    let links = repo.list_role_parents().unwrap();
+    assert_eq!(links.len(), 2);
+    assert!(
+        links
+            .iter()
+            .any(|link| link.role_id == manager.id && link.parent_id == editor.id)
+    );
+
+    // Loops are refused and leave the links untouched; so are other hubs.
+    assert!(matches!(
+        repo.set_role_parents(viewer.id, hub.id, &[manager.id]),
+        Err(RepositoryError::ValidationError(_))
+    ));
+    assert!(matches!(
+        repo.set_role_parents(manager.id, other_hub.id, &[viewer.id]),
+        Err(RepositoryError::NotFound)
+    ));
+    assert_eq!(repo.list_role_parents().unwrap(), links);
+
+    let user = repo
+        .create_user(
+            &NewUser::new(
+                UserEmail::new("boss@tree.test").unwrap(),
+                None,
+                hub.id,
+                UserPassword::new("pwd").unwrap(),
+            )
+            .with_roles(vec![manager.id]),
+        )
+        .unwrap();
+    let user_roles = repo.get_user_by_id(user.id, hub.id).unwrap().unwrap();
+    assert_eq!(user_roles.user.roles, vec![manager.id]);
+    assert_eq!(
+        user_roles
+            .inherited_roles
+            .iter()
+            .map(|role| role.id)
+            .collect::<Vec<_>>(),
+        vec![viewer.id, editor.id]
+    );
+    let (_, listed) = repo.list_users(UserListQuery::new(hub.id)).unwrap();
+    assert_eq!(listed[0].inherited_roles.len(), 2);
+    assert_eq!(
+        repo.list_user_permissions(user.id, hub.id).unwrap(),
+        vec![Permission::UsersRead, Permission::MenuManage]
+    );
+
+    // Deleting a role in the middle cuts the chain.
+    repo.delete_role(editor.id, hub.id).unwrap();
+    assert!(repo.list_role_parents().unwrap().is_empty());
+    let user_roles = repo.get_user_by_id(user.id, hub.id).unwrap().unwrap();
+    assert!(user_roles.inherited_roles.is_empty());
+    assert!(
+        repo.list_user_permissions(user.id, hub.id)
+            .unwrap()
+            .is_empty()
+    );
 }
 
 #[test]
@@ -199,3 +541,1264 @@ fn test_email_lowercase_and_login_case_insensitive() {
     let res = user_repo.create_user(&dup_user);
     assert!(res.is_err());
 }
+
+#[test]
+fn test_email_verification_marks_the_user_verified_once() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("verify@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+    assert!(!user.is_email_verified());
+    let now = Utc::now().naive_utc();
+    let expires_at = now + Duration::hours(1);
+
+    repo.create_email_verification(&NewEmailVerification::new(
+        user.id,
+        hub.id,
+        "first".to_string(),
+        expires_at,
+    ))
+    .unwrap();
+    repo.create_email_verification(&NewEmailVerification::new(
+        user.id,
+        hub.id,
+        "second".to_string(),
+        expires_at,
+    ))
+    .unwrap();
+
+    // Sending a new link invalidates the previous one.
+    assert!(
+        repo.consume_email_verification("first", now)
+            .unwrap()
+            .is_none()
+    );
+
+    let consumed = repo
+        .consume_email_verification("second", now)
+        .unwrap()
+        .expect("latest token should be redeemable");
+    assert_eq!(consumed.user_id, user.id);
+    let stored = repo.get_user_by_id(user.id, hub.id).unwrap().unwrap();
+    assert_eq!(stored.user.email_verified_at, Some(now));
+
+    // The same token cannot be redeemed twice.
+    assert!(
+        repo.consume_email_verification("second", now)
+            .unwrap()
+            .is_none()
+    );
+
+    // Marking by hand keeps the original timestamp of verified users.
+    assert_eq!(
+        repo.mark_email_verified(user.id, hub.id, now + Duration::hours(1))
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn test_password_reset_tokens_are_single_use() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("reset@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+    let now = Utc::now().naive_utc();
+    let expires_at = now + Duration::hours(1);
+    let password = UserPassword::new("new").unwrap();
+
+    repo.create_password_reset(&NewPasswordReset::new(
+        user.id,
+        hub.id,
+        "first".to_string(),
+        expires_at,
+    ))
+    .unwrap();
+    repo.create_password_reset(&NewPasswordReset::new(
+        user.id,
+        hub.id,
+        "second".to_string(),
+        expires_at,
+    ))
+    .unwrap();
+
+    // Issuing a new token invalidates the previous one.
+    assert!(
+        repo.reset_password("first", &password, now)
+            .unwrap()
+            .is_none()
+    );
+
+    let consumed = repo
+        .reset_password("second", &password, now)
+        .unwrap()
+        .expect("latest token should be redeemable");
+    assert_eq!(consumed.user_id, user.id);
+    assert_eq!(consumed.hub_id, hub.id);
+
+    // Expired tokens are rejected.
+    repo.create_password_reset(&NewPasswordReset::new(
+        user.id,
+        hub.id,
+        "expired".to_string(),
+        now - Duration::minutes(1),
+    ))
+    .unwrap();
+    assert!(
+        repo.reset_password("expired", &password, now)
+            .unwrap()
+            .is_none()
+    );
+
+    // A successful login invalidates outstanding tokens.
+    repo.create_password_reset(&NewPasswordReset::new(
+        user.id,
+        hub.id,
+        "third".to_string(),
+        expires_at,
+    ))
+    .unwrap();
+    assert_eq!(repo.invalidate_password_resets(user.id).unwrap(), 1);
+    assert!(
+        repo.reset_password("third", &password, now)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_reset_password_spends_token_with_new_password() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let email = UserEmail::new("reset@example.com").unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            email.clone(),
+            None,
+            hub.id,
+            UserPassword::new("old").unwrap(),
+        ))
+        .unwrap();
+    let now = Utc::now().naive_utc();
+    repo.create_password_reset(&NewPasswordReset::new(
+        user.id,
+        hub.id,
+        "token".to_string(),
+        now + Duration::hours(1),
+    ))
+    .unwrap();
+    let password = UserPassword::new("new").unwrap();
+
+    // A failed password update keeps the token usable.
+    repo.delete_user(user.id, now).unwrap();
+    assert!(matches!(
+        repo.reset_password("token", &password, now),
+        Err(RepositoryError::NotFound)
+    ));
+    repo.restore_user(user.id, hub.id).unwrap();
+
+    let reset = repo
+        .reset_password("token", &password, now)
+        .unwrap()
+        .expect("token should still be redeemable");
+    assert_eq!(reset.user_id, user.id);
+    assert!(repo.login(&email, &password, hub.id).unwrap().is_some());
+    assert!(
+        repo.reset_password("token", &password, now)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_login_throttle_counters_persist_and_lock() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let policy = LoginThrottlePolicy {
+        max_attempts: 2,
+        window: Duration::minutes(15),
+        base_lockout: Duration::minutes(1),
+        max_lockout: Duration::minutes(10),
+    };
+    let key = "account:1:user@example.com";
+    let now = Utc::now().naive_utc();
+
+    assert!(repo.get_login_throttle(key).unwrap().is_none());
+
+    let first = repo.record_login_failure(key, now, &policy).unwrap();
+    assert_eq!(first.failed_attempts, 1);
+    assert_eq!(first.locked_for(now), None);
+
+    // A fresh repository over the same database sees the stored counters.
+    let reopened = DieselRepository::new(test_db.pool());
+    let stored = reopened.get_login_throttle(key).unwrap().unwrap();
+    assert_eq!(stored.failed_attempts, 1);
+
+    let locked = reopened.record_login_failure(key, now, &policy).unwrap();
+    assert_eq!(locked.lockout_count, 1);
+    assert_eq!(locked.locked_for(now), Some(Duration::minutes(1)));
+    assert_eq!(
+        repo.get_login_throttle(key).unwrap().unwrap().locked_until,
+        locked.locked_until
+    );
+
+    assert_eq!(repo.clear_login_throttle(key).unwrap(), 1);
+    assert!(repo.get_login_throttle(key).unwrap().is_none());
+}
+
+#[test]
+fn test_two_factor_enrollment_and_single_use_codes() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("totp@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+    let now = Utc::now().naive_utc();
+
+    assert!(repo.get_user_totp(user.id).unwrap().is_none());
+
+    // Restarting enrollment replaces an unconfirmed secret.
+    repo.create_pending_totp(user.id, "FIRST").unwrap();
+    let pending = repo.create_pending_totp(user.id, "SECOND").unwrap();
+    assert_eq!(pending.secret, "SECOND");
+    assert!(!pending.is_enabled());
+
+    let codes = vec!["hash-a".to_string(), "hash-b".to_string()];
+    let enabled = repo.enable_totp(user.id, 100, &codes, now).unwrap();
+    assert!(enabled.is_enabled());
+    assert_eq!(enabled.last_used_step, Some(100));
+
+    // A confirmed secret cannot be overwritten by a new enrollment.
+    assert!(repo.create_pending_totp(user.id, "THIRD").is_err());
+
+    // TOTP steps only move forward.
+    assert!(!repo.record_totp_step(user.id, 100).unwrap());
+    assert!(repo.record_totp_step(user.id, 101).unwrap());
+    assert!(!repo.record_totp_step(user.id, 99).unwrap());
+
+    // Recovery codes are single use.
+    assert!(repo.consume_recovery_code(user.id, "hash-a", now).unwrap());
+    assert!(!repo.consume_recovery_code(user.id, "hash-a", now).unwrap());
+    assert!(!repo.consume_recovery_code(user.id, "unknown", now).unwrap());
+
+    assert_eq!(repo.delete_user_totp(user.id).unwrap(), 1);
+    assert!(repo.get_user_totp(user.id).unwrap().is_none());
+    assert!(!repo.consume_recovery_code(user.id, "hash-b", now).unwrap());
+}
+
+#[test]
+fn test_hub_policy_defaults_and_upserts() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+
+    assert_eq!(repo.get_hub_policy(hub.id).unwrap(), HubPolicy::default());
+
+    let policy = HubPolicy {
+        require_admin_2fa: true,
+        require_email_verification: true,
+    };
+    assert_eq!(repo.update_hub_policy(hub.id, &policy).unwrap(), policy);
+    assert_eq!(repo.get_hub_policy(hub.id).unwrap(), policy);
+
+    let relaxed = HubPolicy::default();
+    repo.update_hub_policy(hub.id, &relaxed).unwrap();
+    assert_eq!(repo.get_hub_policy(hub.id).unwrap(), relaxed);
+}
+
+#[test]
+fn test_hub_registration_settings_assign_default_roles() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let role = repo
+        .create_role(&NewRole::new(RoleName::new("staff").unwrap()))
+        .unwrap();
+
+    assert_eq!(
+        repo.get_hub_registration_settings(hub.id).unwrap(),
+        HubRegistrationSettings::default()
+    );
+
+    let settings = HubRegistrationSettings {
+        registration_mode: RegistrationMode::InviteOnly,
+        allowed_email_domains: vec!["company.ru".to_string(), "example.com".to_string()],
+        default_roles: vec![role.id],
+    };
+    assert_eq!(
+        repo.update_hub_registration_settings(hub.id, &settings)
+            .unwrap(),
+        settings
+    );
+    assert_eq!(
+        repo.get_hub_registration_settings(hub.id).unwrap(),
+        settings
+    );
+
+    let user = repo
+        .create_user(
+            &NewUser::new(
+                UserEmail::new("staff@company.ru").unwrap(),
+                None,
+                hub.id,
+                UserPassword::new("pwd").unwrap(),
+            )
+            .with_roles(settings.default_roles.clone()),
+        )
+        .unwrap();
+    let roles = repo.get_roles(user.id).unwrap();
+    assert_eq!(roles.len(), 1);
+    assert_eq!(roles[0].id, role.id);
+
+    // Deleting a role removes it from the defaults.
+    repo.delete_role(role.id, hub.id).unwrap();
+    assert!(
+        repo.get_hub_registration_settings(hub.id)
+            .unwrap()
+            .default_roles
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_user_credentials_are_scoped_to_their_owner() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let owner = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("passkey@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+    let other = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("other@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+
+    let new_credential = NewUserCredential::new(
+        owner.id,
+        hub.id,
+        "0a0b".to_string(),
+        "Laptop".to_string(),
+        "{}".to_string(),
+    );
+    let credential = repo.create_user_credential(&new_credential).unwrap();
+    assert_eq!(credential.name, "Laptop");
+    assert!(credential.last_used_at.is_none());
+
+    // Credential ids are globally unique.
+    assert!(repo.create_user_credential(&new_credential).is_err());
+
+    let found = repo
+        .get_credential_by_credential_id("0a0b")
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, credential.id);
+    assert!(
+        repo.get_credential_by_credential_id("ffff")
+            .unwrap()
+            .is_none()
+    );
+
+    let now = Utc::now().naive_utc();
+    let updated = repo
+        .update_credential_passkey(credential.id, "{\"counter\":1}", now)
+        .unwrap();
+    assert_eq!(updated.passkey, "{\"counter\":1}");
+    assert!(updated.last_used_at.is_some());
+
+    assert_eq!(repo.list_user_credentials(owner.id).unwrap().len(), 1);
+    assert!(repo.list_user_credentials(other.id).unwrap().is_empty());
+
+    // Users can only remove their own credentials.
+    assert_eq!(
+        repo.delete_user_credential(other.id, credential.id)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        repo.delete_user_credential(owner.id, credential.id)
+            .unwrap(),
+        1
+    );
+    assert!(repo.list_user_credentials(owner.id).unwrap().is_empty());
+}
+
+#[test]
+fn test_live_sessions_are_listed_per_hub() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("other").unwrap()))
+        .unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("session@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+    let outsider = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("session@example.com").unwrap(),
+            None,
+            other_hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+
+    let now = Utc::now().naive_utc();
+    let live = repo
+        .create_session(&NewSession::new(
+            "live".to_string(),
+            user.id,
+            hub.id,
+            "2026-10".to_string(),
+            now + Duration::days(7),
+            ClientInfo::default(),
+        ))
+        .unwrap();
+    assert_eq!(live.key_id, "2026-10");
+    repo.create_session(&NewSession::new(
+        "expired".to_string(),
+        user.id,
+        hub.id,
+        "2026-04".to_string(),
+        now - Duration::minutes(1),
+        ClientInfo::default(),
+    ))
+    .unwrap();
+    repo.create_session(&NewSession::new(
+        "elsewhere".to_string(),
+        outsider.id,
+        other_hub.id,
+        "2026-10".to_string(),
+        now + Duration::days(7),
+        ClientInfo::default(),
+    ))
+    .unwrap();
+
+    // Session ids are globally unique.
+    assert!(
+        repo.create_session(&NewSession::new(
+            "live".to_string(),
+            user.id,
+            hub.id,
+            "2026-10".to_string(),
+            now,
+            ClientInfo::default(),
+        ))
+        .is_err()
+    );
+
+    let sessions = repo.list_live_sessions(hub.id, now).unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].jti, "live");
+}
+
+#[test]
+fn test_sessions_can_be_listed_and_revoked_per_user() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("revoke@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+
+    let now = Utc::now().naive_utc();
+    let client = ClientInfo {
+        user_agent: Some("Firefox".to_string()),
+        ip_address: Some("10.0.0.1".to_string()),
+    };
+    let first = repo
+        .create_session(&NewSession::new(
+            "first".to_string(),
+            user.id,
+            hub.id,
+            "default".to_string(),
+            now + Duration::days(7),
+            client.clone(),
+        ))
+        .unwrap();
+    assert_eq!(first.user_agent.as_deref(), Some("Firefox"));
+    assert_eq!(first.ip_address.as_deref(), Some("10.0.0.1"));
+    assert_eq!(first.last_seen_at, None);
+    repo.create_session(&NewSession::new(
+        "second".to_string(),
+        user.id,
+        hub.id,
+        "default".to_string(),
+        now + Duration::days(7),
+        client,
+    ))
+    .unwrap();
+
+    repo.touch_session(first.id, now).unwrap();
+    let touched = repo.get_session_by_jti("first").unwrap().unwrap();
+    assert!(touched.last_seen_at.is_some());
+    assert!(repo.get_session_by_jti("missing").unwrap().is_none());
+
+    // Sessions can only be revoked by their owner.
+    let other_user = UserId::new(user.id.get() + 100).unwrap();
+    assert_eq!(repo.revoke_session(other_user, first.id, now).unwrap(), 0);
+    assert_eq!(repo.revoke_session(user.id, first.id, now).unwrap(), 1);
+    assert_eq!(repo.revoke_session(user.id, first.id, now).unwrap(), 0);
+
+    let revoked = repo.get_session_by_jti("first").unwrap().unwrap();
+    assert_eq!(repo.revoke_session_by_jti("first", now).unwrap(), 0);
+    assert!(!revoked.is_live(now));
+    let sessions = repo.list_user_sessions(user.id, now).unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].jti, "second");
+
+    assert_eq!(repo.revoke_user_sessions(user.id, now).unwrap(), 1);
+    assert!(repo.list_user_sessions(user.id, now).unwrap().is_empty());
+    assert!(repo.list_live_sessions(hub.id, now).unwrap().is_empty());
+}
+
+#[test]
+fn test_oauth_clients_and_single_use_authorization_codes() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("oidc@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+
+    let client = repo
+        .create_oauth_client(&NewOAuthClient::new(
+            "client-id".to_string(),
+            Some("secret-hash".to_string()),
+            "Portal".to_string(),
+            vec![
+                "https://portal.example.com/callback".to_string(),
+                "http://localhost:3000/callback".to_string(),
+            ],
+        ))
+        .unwrap();
+    assert!(!client.is_public());
+    assert!(client.allows_redirect_uri("http://localhost:3000/callback"));
+
+    let loaded = repo.get_oauth_client("client-id").unwrap().unwrap();
+    assert_eq!(loaded, client);
+    assert!(repo.get_oauth_client("missing").unwrap().is_none());
+    assert_eq!(repo.list_oauth_clients().unwrap(), vec![client.clone()]);
+
+    let now = Utc::now().naive_utc();
+    let new_code = |code_hash: &str, expires_at| NewAuthorizationCode {
+        code_hash: code_hash.to_string(),
+        client_id: client.id,
+        user_id: user.id,
+        hub_id: hub.id,
+        redirect_uri: "https://portal.example.com/callback".to_string(),
+        scope: "openid".to_string(),
+        nonce: Some("nonce".to_string()),
+        code_challenge: "challenge".to_string(),
+        expires_at,
+    };
+    repo.create_authorization_code(&new_code("live", now + Duration::minutes(1)))
+        .unwrap();
+    repo.create_authorization_code(&new_code("expired", now - Duration::minutes(1)))
+        .unwrap();
+
+    let consumed = repo
+        .consume_authorization_code("live", now)
+        .unwrap()
+        .expect("fresh code should be redeemable");
+    assert_eq!(consumed.user_id, user.id);
+    assert_eq!(consumed.nonce.as_deref(), Some("nonce"));
+    assert!(
+        repo.consume_authorization_code("live", now)
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        repo.consume_authorization_code("expired", now)
+            .unwrap()
+            .is_none()
+    );
+
+    let token = repo
+        .create_access_token(&NewAccessToken {
+            token_hash: "token-hash".to_string(),
+            client_id: client.id,
+            user_id: user.id,
+            hub_id: hub.id,
+            scope: "openid".to_string(),
+            expires_at: now + Duration::hours(1),
+        })
+        .unwrap();
+    assert!(token.is_active(now));
+    assert_eq!(repo.get_access_token("token-hash").unwrap(), Some(token));
+
+    assert_eq!(repo.revoke_access_token("token-hash", now).unwrap(), 1);
+    assert_eq!(repo.revoke_access_token("token-hash", now).unwrap(), 0);
+    let revoked = repo.get_access_token("token-hash").unwrap().unwrap();
+    assert!(!revoked.is_active(now));
+
+    // Deleting a client removes everything issued to it.
+    assert_eq!(repo.delete_oauth_client(client.id).unwrap(), 1);
+    assert!(repo.get_oauth_client("client-id").unwrap().is_none());
+    assert!(repo.get_access_token("token-hash").unwrap().is_none());
+    assert_eq!(repo.delete_oauth_client(client.id).unwrap(), 0);
+}
+
+#[test]
+fn test_personal_access_tokens_are_scoped_to_their_owner() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("pat@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+
+    let now = Utc::now().naive_utc();
+    let token = repo
+        .create_personal_token(&NewPersonalAccessToken {
+            user_id: user.id,
+            hub_id: hub.id,
+            name: "CI".to_string(),
+            token_hash: "live-hash".to_string(),
+            scopes: vec![TokenScope::Profile, TokenScope::Users],
+            expires_at: now + Duration::days(30),
+        })
+        .unwrap();
+    assert_eq!(token.scopes, vec![TokenScope::Profile, TokenScope::Users]);
+    assert_eq!(token.last_used_at, None);
+    repo.create_personal_token(&NewPersonalAccessToken {
+        user_id: user.id,
+        hub_id: hub.id,
+        name: "Old".to_string(),
+        token_hash: "expired-hash".to_string(),
+        scopes: vec![TokenScope::Admin],
+        expires_at: now - Duration::days(1),
+    })
+    .unwrap();
+
+    let tokens = repo.list_personal_tokens(user.id, now).unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].name, "CI");
+
+    repo.touch_personal_token(token.id, now).unwrap();
+    let touched = repo
+        .get_personal_token_by_hash("live-hash")
+        .unwrap()
+        .unwrap();
+    assert!(touched.last_used_at.is_some());
+    assert!(
+        repo.get_personal_token_by_hash("missing")
+            .unwrap()
+            .is_none()
+    );
+
+    // Tokens can only be revoked by their owner.
+    let other_user = UserId::new(user.id.get() + 100).unwrap();
+    assert_eq!(
+        repo.revoke_personal_token(other_user, token.id, now)
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        repo.revoke_personal_token(user.id, token.id, now).unwrap(),
+        1
+    );
+    assert_eq!(
+        repo.revoke_personal_token(user.id, token.id, now).unwrap(),
+        0
+    );
+    assert!(repo.list_personal_tokens(user.id, now).unwrap().is_empty());
+
+    // Tokens are removed when their deleted owner is purged.
+    repo.delete_user(user.id, now).unwrap();
+    repo.purge_deleted_users(now + Duration::seconds(1))
+        .unwrap();
+    assert!(
+        repo.get_personal_token_by_hash("live-hash")
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_service_accounts_are_scoped_to_their_hub() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("Other").unwrap()))
+        .unwrap();
+    let crm = repo
+        .create_role(&NewRole::new(RoleName::new("crm").unwrap()))
+        .unwrap();
+    let mailer = repo
+        .create_role(&NewRole::new(RoleName::new("mailer").unwrap()))
+        .unwrap();
+
+    let account = repo
+        .create_service_account(&NewServiceAccount {
+            hub_id: hub.id,
+            name: "CRM sync".to_string(),
+            client_id: "sa_crm".to_string(),
+            secret_hash: "secret-hash".to_string(),
+            roles: vec![crm.id],
+        })
+        .unwrap();
+    assert_eq!(account.roles, vec![crm.clone()]);
+    assert!(
+        repo.create_service_account(&NewServiceAccount {
+            hub_id: hub.id,
+            name: "CRM sync".to_string(),
+            client_id: "sa_crm_2".to_string(),
+            secret_hash: "secret-hash".to_string(),
+            roles: vec![],
+        })
+        .is_err()
+    );
+
+    assert_eq!(
+        repo.list_service_accounts(hub.id).unwrap(),
+        vec![account.clone()]
+    );
+    assert!(repo.list_service_accounts(other_hub.id).unwrap().is_empty());
+    assert!(
+        repo.get_service_account(account.id, other_hub.id)
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        repo.get_service_account_by_client_id("sa_crm")
+            .unwrap()
+            .unwrap()
+            .id,
+        account.id
+    );
+
+    let updated = repo
+        .update_service_account(
+            account.id,
+            hub.id,
+            &UpdateServiceAccount {
+                name: "Mailer".to_string(),
+                roles: vec![mailer.id],
+            },
+        )
+        .unwrap();
+    assert_eq!(updated.name, "Mailer");
+    assert_eq!(updated.roles, vec![mailer.clone()]);
+    assert!(
+        repo.update_service_account(
+            account.id,
+            other_hub.id,
+            &UpdateServiceAccount {
+                name: "Stolen".to_string(),
+                roles: vec![],
+            },
+        )
+        .is_err()
+    );
+
+    // Rotating the secret revokes outstanding tokens.
+    let now = Utc::now().naive_utc();
+    repo.create_service_account_token(&NewServiceAccountToken {
+        service_account_id: account.id,
+        hub_id: hub.id,
+        token_hash: "token-hash".to_string(),
+        expires_at: now + Duration::hours(1),
+    })
+    .unwrap();
+    let rotated = repo
+        .rotate_service_account_secret(account.id, hub.id, "new-hash", now)
+        .unwrap();
+    assert_eq!(rotated.secret_hash, "new-hash");
+    let token = repo
+        .get_service_account_token("token-hash")
+        .unwrap()
+        .unwrap();
+    assert!(!token.is_active(now));
+
+    // Deleting the role drops it from the account.
+    repo.delete_role(mailer.id, hub.id).unwrap();
+    let account = repo
+        .get_service_account(account.id, hub.id)
+        .unwrap()
+        .unwrap();
+    assert!(account.roles.is_empty());
+
+    assert_eq!(
+        repo.delete_service_account(account.id, other_hub.id)
+            .unwrap(),
+        0
+    );
+    assert_eq!(repo.delete_service_account(account.id, hub.id).unwrap(), 1);
+    assert!(
+        repo.get_service_account_token("token-hash")
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn test_invitation_acceptance_creates_user_with_roles() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("other").unwrap()))
+        .unwrap();
+    let role = repo
+        .create_role(&NewRole::new(RoleName::new("staff").unwrap()))
+        .unwrap();
+    let now = Utc::now().naive_utc();
+
+    let new_invitation = NewInvitation {
+        hub_id: hub.id,
+        email: UserEmail::new("invitee@example.com").unwrap(),
+        name: UserName::new("Invitee").unwrap(),
+        token_hash: "first-hash".to_string(),
+        invited_by: None,
+        expires_at: now + Duration::hours(1),
+        roles: vec![role.id],
+    };
+    let invitation = repo.create_invitation(&new_invitation).unwrap();
+    assert_eq!(invitation.roles, vec![role.id]);
+    assert!(repo.create_invitation(&new_invitation).is_err());
+    assert!(
+        repo.get_invitation(invitation.id, other_hub.id)
+            .unwrap()
+            .is_none()
+    );
+
+    let renewed = repo
+        .renew_invitation(
+            invitation.id,
+            hub.id,
+            "second-hash",
+            now + Duration::hours(2),
+        )
+        .unwrap();
+    assert_eq!(renewed.token_hash, "second-hash");
+    let password = UserPassword::new("pwd").unwrap();
+    assert!(
+        repo.accept_invitation("first-hash", &password, now)
+            .unwrap()
+            .is_none()
+    );
+
+    let user = repo
+        .accept_invitation("second-hash", &password, now)
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.hub_id, hub.id);
+    assert!(user.is_email_verified());
+    let roles = repo.get_roles(user.id).unwrap();
+    assert_eq!(roles.len(), 1);
+    assert_eq!(roles[0].id, role.id);
+    assert!(
+        repo.login(
+            &UserEmail::new("invitee@example.com").unwrap(),
+            &password,
+            hub.id
+        )
+        .unwrap()
+        .is_some()
+    );
+
+    // Accepted invitations are no longer pending and cannot be reused.
+    assert!(repo.list_invitations(hub.id).unwrap().is_empty());
+    assert!(
+        repo.accept_invitation("second-hash", &password, now)
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(repo.delete_invitation(invitation.id, hub.id).unwrap(), 0);
+}
+
+#[test]
+fn test_suspended_users_are_listed_on_request_and_cannot_log_in() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let email = UserEmail::new("suspended@example.com").unwrap();
+    let password = UserPassword::new("secret").unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            email.clone(),
+            Some(UserName::new("Suspended").unwrap()),
+            hub.id,
+            password.clone(),
+        ))
+        .unwrap();
+    let now = Utc::now().naive_utc();
+
+    assert_eq!(
+        repo.suspend_user(user.id, hub.id, Some("Спам"), now)
+            .unwrap(),
+        1
+    );
+    // A second suspension keeps the original timestamp and reason.
+    assert_eq!(
+        repo.suspend_user(user.id, hub.id, None, now + Duration::hours(1))
+            .unwrap(),
+        0
+    );
+    let stored = repo.get_user_by_id(user.id, hub.id).unwrap().unwrap().user;
+    assert_eq!(stored.disabled_at, Some(now));
+    assert_eq!(stored.disabled_reason.as_deref(), Some("Спам"));
+    assert!(repo.login(&email, &password, hub.id).unwrap().is_none());
+
+    let listed_ids = |status| {
+        repo.list_users(UserListQuery::new(hub.id).status(status))
+            .unwrap()
+            .1
+            .into_iter()
+            .map(|user_roles| user_roles.user.id)
+            .collect::<Vec<_>>()
+    };
+    assert!(!listed_ids(UserStatusFilter::Active).contains(&user.id));
+    assert_eq!(listed_ids(UserStatusFilter::Suspended), vec![user.id]);
+    assert!(listed_ids(UserStatusFilter::All).contains(&user.id));
+
+    assert_eq!(repo.reactivate_user(user.id, hub.id).unwrap(), 1);
+    assert_eq!(repo.reactivate_user(user.id, hub.id).unwrap(), 0);
+    let stored = repo.get_user_by_id(user.id, hub.id).unwrap().unwrap().user;
+    assert!(!stored.is_disabled());
+    assert!(stored.disabled_reason.is_none());
+    assert!(repo.login(&email, &password, hub.id).unwrap().is_some());
+    assert!(listed_ids(UserStatusFilter::Active).contains(&user.id));
+}
+
+#[test]
+fn test_deleted_users_free_their_email_until_restored() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let email = UserEmail::new("deleted@example.com").unwrap();
+    let new_user = NewUser::new(
+        email.clone(),
+        None,
+        hub.id,
+        UserPassword::new("secret").unwrap(),
+    );
+    let user = repo.create_user(&new_user).unwrap();
+    let now = Utc::now().naive_utc();
+
+    repo.delete_user(user.id, now).unwrap();
+    assert!(repo.delete_user(user.id, now).is_err());
+    assert!(repo.get_user_by_email(&email, hub.id).unwrap().is_none());
+    let deleted = repo.list_deleted_users(hub.id).unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0].deleted_at, Some(now));
+
+    // The address can be reused, which blocks restoring the deleted user.
+    let replacement = repo.create_user(&new_user).unwrap();
+    assert!(repo.create_user(&new_user).is_err());
+    assert!(repo.restore_user(user.id, hub.id).is_err());
+
+    repo.delete_user(replacement.id, now).unwrap();
+    assert_eq!(repo.restore_user(user.id, hub.id).unwrap(), 1);
+    assert_eq!(repo.restore_user(user.id, hub.id).unwrap(), 0);
+    assert!(repo.get_user_by_id(user.id, hub.id).unwrap().is_some());
+
+    // Only users deleted before the cutoff are purged.
+    assert_eq!(repo.purge_deleted_users(now).unwrap(), 0);
+    assert_eq!(
+        repo.purge_deleted_users(now + Duration::seconds(1))
+            .unwrap(),
+        1
+    );
+    assert!(repo.list_deleted_users(hub.id).unwrap().is_empty());
+    assert_eq!(repo.restore_user(replacement.id, hub.id).unwrap(), 0);
+}
+
+#[test]
+fn test_deleted_hubs_are_restored_with_their_users() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let new_hub = NewHub::new(HubName::new("Branch").unwrap());
+    let hub = repo.create_hub(&new_hub).unwrap();
+    let new_user = |email: &str| {
+        NewUser::new(
+            UserEmail::new(email).unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("secret").unwrap(),
+        )
+    };
+    let member = repo.create_user(&new_user("member@example.com")).unwrap();
+    let removed = repo.create_user(&new_user("removed@example.com")).unwrap();
+    repo.create_menu(&NewMenu::new(
+        MenuName::new("Orders").unwrap(),
+        MenuUrl::new("https://orders.test.me/").unwrap(),
+        hub.id,
+    ))
+    .unwrap();
+
+    let now = Utc::now().naive_utc();
+    repo.delete_user(removed.id, now - Duration::hours(1))
+        .unwrap();
+    repo.delete_hub(hub.id, now).unwrap();
+    assert!(repo.get_hub_by_name("Branch").unwrap().is_none());
+    assert!(repo.get_user_by_id(member.id, hub.id).unwrap().is_none());
+    assert!(repo.list_menu(hub.id).unwrap().is_empty());
+    assert_eq!(repo.list_deleted_hubs().unwrap()[0].id, hub.id);
+
+    // The name can be reused, which blocks restoring the deleted hub.
+    let replacement = repo.create_hub(&new_hub).unwrap();
+    assert!(repo.restore_hub(hub.id).is_err());
+    repo.delete_hub(replacement.id, now).unwrap();
+
+    assert_eq!(repo.restore_hub(hub.id).unwrap(), 1);
+    assert!(repo.get_hub_by_id(hub.id).unwrap().is_some());
+    assert!(repo.get_user_by_id(member.id, hub.id).unwrap().is_some());
+    assert_eq!(repo.list_menu(hub.id).unwrap().len(), 1);
+    // Users deleted before the hub stay deleted.
+    assert!(repo.get_user_by_id(removed.id, hub.id).unwrap().is_none());
+
+    repo.delete_hub(hub.id, now).unwrap();
+    assert_eq!(
+        repo.purge_deleted_hubs(now + Duration::seconds(1)).unwrap(),
+        2
+    );
+    assert!(repo.list_deleted_hubs().unwrap().is_empty());
+    assert_eq!(repo.restore_hub(hub.id).unwrap(), 0);
+    assert_eq!(repo.restore_user(removed.id, hub.id).unwrap(), 0);
+}
+
+#[test]
+fn test_password_change_requirement_is_cleared_by_a_new_password() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let new_user = NewUser::new(
+        UserEmail::new("temporary@example.com").unwrap(),
+        Some(UserName::new("Temporary").unwrap()),
+        hub.id,
+        UserPassword::new("temporary").unwrap(),
+    )
+    .with_must_change_password(true);
+    let user = repo.create_user(&new_user).unwrap();
+    assert!(user.must_change_password);
+
+    // Profile updates without the flag leave it untouched.
+    let user = repo
+        .update_user(
+            user.id,
+            hub.id,
+            &UpdateUser::new(UserName::new("Renamed").unwrap(), None, None),
+        )
+        .unwrap();
+    assert!(user.must_change_password);
+
+    let user = repo
+        .update_password(user.id, hub.id, &UserPassword::new("chosen").unwrap())
+        .unwrap();
+    assert!(!user.must_change_password);
+
+    let user = repo
+        .update_user(
+            user.id,
+            hub.id,
+            &UpdateUser::new(UserName::new("Renamed").unwrap(), None, None)
+                .with_must_change_password(true),
+        )
+        .unwrap();
+    assert!(user.must_change_password);
+}
+
+#[test]
+fn test_audit_events_are_filtered_paginated_and_append_only() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("Other").unwrap()))
+        .unwrap();
+    let client = ClientInfo {
+        user_agent: Some("agent".into()),
+        ip_address: Some("10.0.0.1".into()),
+    };
+    let admin_id = UserId::new(1).unwrap();
+
+    repo.record_audit_event(
+        &NewAuditEvent::new(hub.id, AuditEventType::LoginFailed, &client)
+            .with_detail("unknown@example.com"),
+    )
+    .unwrap();
+    for target_id in [2, 3, 4] {
+        repo.record_audit_event(
+            &NewAuditEvent::new(hub.id, AuditEventType::UserDeleted, &client)
+                .with_actor(admin_id)
+                .with_target(target_id),
+        )
+        .unwrap();
+    }
+    repo.record_audit_event(&NewAuditEvent::new(
+        other_hub.id,
+        AuditEventType::LoginFailed,
+        &client,
+    ))
+    .unwrap();
+
+    let (total, events) = repo
+        .list_audit_events(AuditEventQuery::new(hub.id))
+        .unwrap();
+    assert_eq!(total, 4);
+    assert_eq!(events.len(), 4);
+    assert!(events.iter().all(|event| event.hub_id == hub.id));
+    assert_eq!(events[0].ip_address.as_deref(), Some("10.0.0.1"));
+    assert_eq!(events[0].user_agent.as_deref(), Some("agent"));
+
+    let (total, events) = repo
+        .list_audit_events(AuditEventQuery::new(hub.id).event_type(AuditEventType::LoginFailed))
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(events[0].actor_id, None);
+    assert_eq!(events[0].detail.as_deref(), Some("unknown@example.com"));
+
+    let (total, events) = repo
+        .list_audit_events(AuditEventQuery::new(hub.id).actor(admin_id).paginate(2, 2))
+        .unwrap();
+    assert_eq!(total, 3);
+    assert_eq!(events.len(), 1);
+
+    let (total, events) = repo
+        .list_audit_events(AuditEventQuery::new(hub.id).target(3))
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(events[0].event_type, AuditEventType::UserDeleted);
+
+    let (total, _) = repo
+        .list_audit_events(
+            AuditEventQuery::new(hub.id).since(Utc::now().naive_utc() + Duration::hours(1)),
+        )
+        .unwrap();
+    assert_eq!(total, 0);
+
+    // Stored events can be neither changed nor removed.
+    let mut conn = test_db.pool().get().unwrap();
+    assert!(
+        diesel::sql_query("UPDATE audit_events SET detail = 'changed'")
+            .execute(&mut conn)
+            .is_err()
+    );
+    assert!(
+        diesel::sql_query("DELETE FROM audit_events")
+            .execute(&mut conn)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_role_permissions_grant_users_their_permissions() {
+    let test_db = common::TestDb::new();
+    let repo = DieselRepository::new(test_db.pool());
+    let hub = repo.get_hub_by_name("default").unwrap().unwrap();
+    let other_hub = repo
+        .create_hub(&NewHub::new(HubName::new("other").unwrap()))
+        .unwrap();
+
+    // The base admin role is seeded with every permission.
+    let grants = repo.list_role_permissions().unwrap();
+    let admin_grants = grants
+        .iter()
+        .filter(|grant| grant.role_id.get() == 1)
+        .map(|grant| grant.permission)
+        .collect::<Vec<_>>();
+    assert_eq!(admin_grants, Permission::ALL.to_vec());
+
+    let role = repo
+        .create_role(
+            &NewRole::new(RoleName::new("editor").unwrap())
+                .with_permissions(vec![Permission::MenuManage, Permission::UsersRead]),
+        )
+        .unwrap();
+    let user = repo
+        .create_user(&NewUser::new(
+            UserEmail::new("editor@example.com").unwrap(),
+            None,
+            hub.id,
+            UserPassword::new("pwd").unwrap(),
+        ))
+        .unwrap();
+    assert!(
+        repo.list_user_permissions(user.id, hub.id)
+            .unwrap()
+            .is_empty()
+    );
+
+    repo.update_user(
+        user.id,
+        hub.id,
+        &UpdateUser::new(UserName::new("Editor").unwrap(), None, Some(vec![role.id])),
+    )
+    .unwrap();
+    assert_eq!(
+        repo.list_user_permissions(user.id, hub.id).unwrap(),
+        vec![Permission::UsersRead, Permission::MenuManage]
+    );
+    assert!(
+        repo.list_user_permissions(user.id, other_hub.id)
+            .unwrap()
+            .is_empty()
+    );
+
+    // Deleting the role removes its grants.
+    repo.delete_role(role.id, hub.id).unwrap();
+    assert!(
+        repo.list_user_permissions(user.id, hub.id)
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        repo.list_role_permissions()
+            .unwrap()
+            .iter()
+            .all(|grant| grant.role_id != role.id)
+    );
+}